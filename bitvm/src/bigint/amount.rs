@@ -0,0 +1,274 @@
+//! Satoshi amounts as `U64`, with the consensus checks on money ranges.
+//! Amounts are expected to be in the money range (see `verify_money_range`), so that sums of a
+//! few of them can't wrap around 2^64.
+
+use crate::bigint::{U128, U64};
+use crate::bn254::utils::Hint;
+use crate::pseudo::NMUL;
+use crate::treepp::*;
+use num_bigint::BigUint;
+
+/// Number of bytes of a serialized amount
+pub const AMOUNT_LENGTH: u32 = 8;
+/// Maximum amount of satoshis, as in Bitcoin Core's `MAX_MONEY`
+pub const MAX_MONEY: u64 = 21_000_000 * 100_000_000;
+
+pub fn push(amount: u64) -> Script {
+    U64::push_u64_le(&[amount])
+}
+
+/// Parse a serialized amount.
+/// Input: the 8 little endian bytes of the amount, first byte on top (as in a serialized
+/// transaction pushed with its first byte on top)
+/// Output: the amount as `U64`
+/// Fails if one of the bytes is out of range.
+pub fn from_le_bytes() -> Script {
+    script! {
+        for _ in 0..U64::N_LIMBS {
+            // the 16-bit limb of two bytes, least significant byte on top
+            OP_DUP 0 256 OP_WITHIN OP_VERIFY
+            OP_SWAP
+            OP_DUP 0 256 OP_WITHIN OP_VERIFY
+            { NMUL(256) }
+            OP_ADD
+            OP_TOALTSTACK
+        }
+        // most significant limb deepest
+        for _ in 0..U64::N_LIMBS {
+            OP_FROMALTSTACK
+        }
+    }
+}
+
+/// Fail unless the amount on top of the stack is at most `MAX_MONEY`, keeping it
+pub fn verify_money_range() -> Script {
+    script! {
+        { U64::copy(0) }
+        { push(MAX_MONEY) }
+        { U64::lessthanorequal(1, 0) }
+        OP_VERIFY
+    }
+}
+
+/// Sum of the top two amounts, failing if it exceeds `MAX_MONEY`
+pub fn add() -> Script {
+    script! {
+        { U64::add(1, 0) }
+        { verify_money_range() }
+    }
+}
+
+/// Sum of the top `n` amounts, failing if it exceeds `MAX_MONEY`
+pub fn sum(n: u32) -> Script {
+    assert!(n > 0, "nothing to sum");
+    script! {
+        for _ in 1..n {
+            { add() }
+        }
+    }
+}
+
+/// Difference of the top two amounts, the subtrahend on top.
+/// Fails if the subtrahend exceeds the minuend.
+pub fn sub() -> Script {
+    script! {
+        { U64::copy(1) }
+        { U64::copy(1) }
+        { U64::greaterthanorequal(1, 0) }
+        OP_VERIFY
+        { U64::sub(1, 0) }
+    }
+}
+
+/// Verify that a transaction pays at least `fee`, i.e. `sum(inputs) >= sum(outputs) + fee`.
+/// Input: `n_inputs` input amounts, `n_outputs` output amounts, fee on top
+pub fn verify_fee(n_inputs: u32, n_outputs: u32) -> Script {
+    script! {
+        { sum(n_outputs + 1) }
+        { U64::toaltstack() }
+        { sum(n_inputs) }
+        { U64::fromaltstack() }
+        { U64::greaterthanorequal(1, 0) }
+        OP_VERIFY
+    }
+}
+
+/// Product of the amount on top of the stack with a constant, e.g. a fee rate.
+/// Output: the product as `U128`
+pub fn mul_constant(multiplier: u64) -> Script {
+    script! {
+        { push(multiplier) }
+        { U64::mul_full::<128>(4) }
+    }
+}
+
+/// Verify that a part is at least `multiplier / precision` of a total, e.g. that a reward is
+/// at least `deposit * REWARD_MULTIPLIER / REWARD_PRECISION` without rounding.
+/// Input: total, part on top
+pub fn verify_ratio_at_least(multiplier: u64, precision: u64) -> Script {
+    script! {
+        { mul_constant(precision) }
+        { U128::toaltstack() }
+        { mul_constant(multiplier) }
+        { U128::fromaltstack() }
+        // part * precision >= total * multiplier
+        { U128::greaterthanorequal(0, 1) }
+        OP_VERIFY
+    }
+}
+
+/// `floor(amount * multiplier / precision)` of the amount on top of the stack, the quotient and
+/// the remainder of the division are hints. The ratio must be at most one.
+pub fn hinted_mul_ratio(amount: u64, multiplier: u64, precision: u64) -> (Script, Vec<Hint>) {
+    assert!(
+        multiplier <= precision,
+        "the result must fit in an amount"
+    );
+    let product = BigUint::from(amount) * multiplier;
+    let (divmod, hints) = U128::hinted_divmod::<256>(&product, &BigUint::from(precision));
+    (
+        script! {
+            { mul_constant(multiplier) }
+            { U128::push_u64_le(&[precision]) }
+            { divmod }
+            // drop the remainder
+            { U128::drop() }
+            { U128::resize::<64>() }
+        },
+        hints,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hash::blake3::push_bytes_hex;
+
+    // the bridge's reward ratio
+    const REWARD_MULTIPLIER: u64 = 20;
+    const REWARD_PRECISION: u64 = 1000;
+
+    fn check(script: Script, success: bool) {
+        assert_eq!(execute_script(script).success, success);
+    }
+
+    #[test]
+    fn test_from_le_bytes() {
+        for amount in [0, 1, 0x0102_0304_0506_0708, MAX_MONEY, u64::MAX] {
+            let script = script! {
+                { push_bytes_hex(&hex::encode(amount.to_le_bytes())) }
+                { from_le_bytes() }
+                { push(amount) }
+                { U64::equalverify(1, 0) }
+                OP_TRUE
+            };
+            run(script);
+        }
+
+        // a byte out of range
+        let script = script! {
+            for byte in [1, 2, 3, 4, 5, 6, 256, 7] {
+                { byte }
+            }
+            { from_le_bytes() }
+            { U64::drop() }
+            OP_TRUE
+        };
+        check(script, false);
+    }
+
+    #[test]
+    fn test_add_sub() {
+        let script = script! {
+            { push(50_000) }
+            { push(25_000) }
+            { add() }
+            { push(75_000) }
+            { U64::equalverify(1, 0) }
+            { push(50_000) }
+            { push(25_000) }
+            { sub() }
+            { push(25_000) }
+            { U64::equalverify(1, 0) }
+            OP_TRUE
+        };
+        run(script);
+
+        check(
+            script! {
+                { push(MAX_MONEY) }
+                { push(1) }
+                { add() }
+                { U64::drop() }
+                OP_TRUE
+            },
+            false,
+        );
+        check(
+            script! {
+                { push(25_000) }
+                { push(25_001) }
+                { sub() }
+                { U64::drop() }
+                OP_TRUE
+            },
+            false,
+        );
+    }
+
+    #[test]
+    fn test_verify_fee() {
+        for (fee, success) in [(1_000, true), (2_000, true), (2_001, false)] {
+            let script = script! {
+                { push(100_000) }
+                { push(50_000) }
+                { push(120_000) }
+                { push(28_000) }
+                { push(fee) }
+                { verify_fee(2, 2) }
+                OP_TRUE
+            };
+            check(script, success);
+        }
+    }
+
+    #[test]
+    fn test_ratio() {
+        let deposit = 100_000_123;
+        let reward = deposit * REWARD_MULTIPLIER / REWARD_PRECISION;
+
+        let (mul_ratio, hints) = hinted_mul_ratio(deposit, REWARD_MULTIPLIER, REWARD_PRECISION);
+        println!("amount::hinted_mul_ratio: {} bytes", mul_ratio.len());
+        let script = script! {
+            for hint in hints {
+                { hint.push() }
+            }
+            { push(deposit) }
+            { mul_ratio }
+            { push(reward) }
+            { U64::equalverify(1, 0) }
+            OP_TRUE
+        };
+        run(script);
+
+        // the rounded down reward is below the exact ratio
+        for (part, success) in [(reward + 1, true), (reward, false)] {
+            let script = script! {
+                { push(deposit) }
+                { push(part) }
+                { verify_ratio_at_least(REWARD_MULTIPLIER, REWARD_PRECISION) }
+                OP_TRUE
+            };
+            check(script, success);
+        }
+
+        let script = script! {
+            { push(MAX_MONEY) }
+            { mul_constant(1_000) }
+            { U128::push_u32_le(&(BigUint::from(MAX_MONEY) * 1_000u32).to_u32_digits()) }
+            { U128::equalverify(1, 0) }
+            OP_TRUE
+        };
+        run(script);
+    }
+}
@@ -0,0 +1,218 @@
+use crate::bigint::BigIntImpl;
+use crate::bn254::utils::Hint;
+use crate::treepp::*;
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+
+/// Window of the multiplication checking the quotient
+const DIVMOD_WINDOW: u32 = 4;
+
+impl<const N_BITS: u32, const LIMB_SIZE: u32> BigIntImpl<N_BITS, LIMB_SIZE> {
+    /// Euclidean division with the quotient and the remainder given as hints.
+    /// Input: a b
+    /// Output: q r, such that a = q * b + r and r < b
+    ///
+    /// The product is checked in `T_BITS` bits, which must be at least `2 * N_BITS`.
+    /// Fails if b is zero.
+    pub fn hinted_divmod<const T_BITS: u32>(a: &BigUint, b: &BigUint) -> (Script, Vec<Hint>) {
+        assert!(!b.is_zero(), "division by zero");
+        let (q, r) = (a / b, a % b);
+        (
+            script! {
+                for _ in 0..2 * Self::N_LIMBS {
+                    OP_DEPTH OP_1SUB OP_ROLL // hints
+                }
+                { Self::divmod_verify::<T_BITS>() }
+            },
+            vec![Self::hint(&q), Self::hint(&r)],
+        )
+    }
+
+    /// Reduction modulo a constant with the quotient as a hint.
+    /// Input: a
+    /// Output: a mod `modulus`
+    pub fn hinted_mod_reduce<const T_BITS: u32>(
+        a: &BigUint,
+        modulus: &BigUint,
+    ) -> (Script, Vec<Hint>) {
+        assert!(
+            modulus < &(BigUint::one() << N_BITS),
+            "modulus doesn't fit in {} bits",
+            N_BITS
+        );
+        let (divmod, hints) = Self::hinted_divmod::<T_BITS>(a, modulus);
+        (
+            script! {
+                { Self::push_u32_le(&modulus.to_u32_digits()) }
+                { divmod }
+                // drop the quotient
+                { Self::roll(1) }
+                { Self::drop() }
+            },
+            hints,
+        )
+    }
+
+    /// Verify a division, consuming the dividend and the divisor.
+    /// Input: a b q r
+    /// Output: q r
+    /// Fails unless a = q * b + r and r < b.
+    pub fn divmod_verify<const T_BITS: u32>() -> Script {
+        script! {
+            // r < b
+            { Self::copy(0) }
+            { Self::copy(3) }
+            { Self::lessthan(1, 0) }
+            OP_VERIFY
+
+            // q * b
+            { Self::copy(1) }
+            { Self::roll(3) }
+            { Self::mul_full::<T_BITS>(DIVMOD_WINDOW) }
+
+            // q * b + r
+            { BigIntImpl::<T_BITS, LIMB_SIZE>::toaltstack() }
+            { Self::copy(0) }
+            { Self::resize::<T_BITS>() }
+            { BigIntImpl::<T_BITS, LIMB_SIZE>::fromaltstack() }
+            { BigIntImpl::<T_BITS, LIMB_SIZE>::add(1, 0) }
+
+            // compare with a
+            { BigIntImpl::<T_BITS, LIMB_SIZE>::toaltstack() }
+            { Self::roll(2) }
+            { Self::resize::<T_BITS>() }
+            { BigIntImpl::<T_BITS, LIMB_SIZE>::fromaltstack() }
+            { BigIntImpl::<T_BITS, LIMB_SIZE>::equalverify(1, 0) }
+        }
+    }
+
+    fn hint(x: &BigUint) -> Hint {
+        assert!(
+            x < &(BigUint::one() << N_BITS),
+            "{} doesn't fit in {} bits",
+            x,
+            N_BITS
+        );
+        Hint::BigIntLimbs(Self::u32_le_to_limbs(&x.to_u32_digits()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bigint::{U254, U256, U64};
+    use crate::bn254::utils::Hint;
+    use crate::treepp::*;
+    use num_bigint::{BigUint, RandomBits};
+    use num_traits::{Num, One};
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_hinted_divmod() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+        for divisor_bits in [1, 30, 128, 254] {
+            let a: BigUint = prng.sample(RandomBits::new(254));
+            let mut b: BigUint = prng.sample(RandomBits::new(divisor_bits));
+            b += BigUint::one();
+            let (q, r) = (&a / &b, &a % &b);
+
+            let (divmod, hints) = U254::hinted_divmod::<508>(&a, &b);
+            println!("U254::hinted_divmod: {} bytes", divmod.len());
+
+            let script = script! {
+                for hint in hints {
+                    { hint.push() }
+                }
+                { U254::push_u32_le(&a.to_u32_digits()) }
+                { U254::push_u32_le(&b.to_u32_digits()) }
+                { divmod }
+                { U254::push_u32_le(&r.to_u32_digits()) }
+                { U254::equalverify(1, 0) }
+                { U254::push_u32_le(&q.to_u32_digits()) }
+                { U254::equalverify(1, 0) }
+                OP_TRUE
+            };
+            run(script);
+        }
+
+        for _ in 0..3 {
+            let a: BigUint = prng.sample(RandomBits::new(64));
+            let b: BigUint = prng.sample(RandomBits::new(20));
+            let b = b + BigUint::one();
+            let (q, r) = (&a / &b, &a % &b);
+
+            let (divmod, hints) = U64::hinted_divmod::<128>(&a, &b);
+            let script = script! {
+                for hint in hints {
+                    { hint.push() }
+                }
+                { U64::push_u32_le(&a.to_u32_digits()) }
+                { U64::push_u32_le(&b.to_u32_digits()) }
+                { divmod }
+                { U64::push_u32_le(&r.to_u32_digits()) }
+                { U64::equalverify(1, 0) }
+                { U64::push_u32_le(&q.to_u32_digits()) }
+                { U64::equalverify(1, 0) }
+                OP_TRUE
+            };
+            run(script);
+        }
+    }
+
+    #[test]
+    fn test_divmod_verify_rejects_wrong_hints() {
+        let a = BigUint::from(1_000_000_007u64);
+        let b = BigUint::from(1000u64);
+        let hint = |x: u64| Hint::BigIntLimbs(U64::u32_le_to_limbs(&BigUint::from(x).to_u32_digits()));
+
+        // the correct quotient and remainder, then a remainder that is too large, and a
+        // quotient that doesn't match the remainder
+        for (q, r, success) in [
+            (1_000_000, 7, true),
+            (999_999, 1007, false),
+            (1_000_001, 7, false),
+        ] {
+            let script = script! {
+                { hint(q).push() }
+                { hint(r).push() }
+                { U64::push_u32_le(&a.to_u32_digits()) }
+                { U64::push_u32_le(&b.to_u32_digits()) }
+                for _ in 0..2 * U64::N_LIMBS {
+                    OP_DEPTH OP_1SUB OP_ROLL
+                }
+                { U64::divmod_verify::<128>() }
+                { U64::drop() }
+                { U64::drop() }
+                OP_TRUE
+            };
+            assert_eq!(execute_script(script).success, success, "q = {}, r = {}", q, r);
+        }
+    }
+
+    #[test]
+    fn test_hinted_mod_reduce() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+        let modulus = BigUint::from_str_radix(
+            "30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47",
+            16,
+        )
+        .unwrap();
+        for _ in 0..3 {
+            let a: BigUint = prng.sample(RandomBits::new(256));
+            let (reduce, hints) = U256::hinted_mod_reduce::<512>(&a, &modulus);
+            println!("U256::hinted_mod_reduce: {} bytes", reduce.len());
+
+            let script = script! {
+                for hint in hints {
+                    { hint.push() }
+                }
+                { U256::push_u32_le(&a.to_u32_digits()) }
+                { reduce }
+                { U256::push_u32_le(&(&a % &modulus).to_u32_digits()) }
+                { U256::equalverify(1, 0) }
+                OP_TRUE
+            };
+            run(script);
+        }
+    }
+}
@@ -1,8 +1,11 @@
 pub mod add;
+pub mod amount;
 pub mod bits;
 pub mod cmp;
+pub mod div;
 pub mod inv;
 pub mod mul;
+pub mod signed;
 pub mod std;
 pub mod sub;
 
@@ -15,6 +18,19 @@ impl<const N_BITS: u32, const LIMB_SIZE: u32> BigIntImpl<N_BITS, LIMB_SIZE> {
     pub const HEAD_OFFSET: u32 = 1u32 << Self::HEAD;
 }
 
+/// Two's complement integers over the limbs of `BigIntImpl<N_BITS, LIMB_SIZE>`
+pub struct SignedBigIntImpl<const N_BITS: u32, const LIMB_SIZE: u32> {}
+
+impl<const N_BITS: u32, const LIMB_SIZE: u32> SignedBigIntImpl<N_BITS, LIMB_SIZE> {
+    pub const N_BITS: u32 = N_BITS;
+    pub const N_LIMBS: u32 = N_BITS.div_ceil(LIMB_SIZE);
+    pub const HEAD: u32 = N_BITS - (Self::N_LIMBS - 1) * LIMB_SIZE;
+    pub const HEAD_OFFSET: u32 = 1u32 << Self::HEAD;
+}
+
 pub type U254 = BigIntImpl<254, 29>;
 pub type U64 = BigIntImpl<64, 16>;
+pub type U128 = BigIntImpl<128, 16>;
 pub type U256 = BigIntImpl<256, 29>;
+pub type I64 = SignedBigIntImpl<64, 16>;
+pub type I256 = SignedBigIntImpl<256, 29>;
@@ -37,11 +37,98 @@ impl<const N_BITS: u32, const LIMB_SIZE: u32> BigIntImpl<N_BITS, LIMB_SIZE> {
             OP_ENDIF
         }
     }
+
+    /// Multiplication modulo 2^N_BITS with `window`-bit windows. The multiples `0..2^window` of
+    /// the first operand are precomputed on the stack, then each window of the second operand
+    /// costs `window` doublings and a single addition.
+    pub fn mul_windowed(window: u32) -> Script {
+        script! {
+            { Self::convert_to_be_bits_toaltstack() }
+            { Self::mul_bits_from_altstack(window, N_BITS) }
+        }
+    }
+
+    /// Full-width product of two BigInts, returned as a `BigIntImpl<T_BITS, LIMB_SIZE>`.
+    /// `T_BITS` must hold the `2 * N_BITS` bits of the product.
+    pub fn mul_full<const T_BITS: u32>(window: u32) -> Script {
+        assert!(
+            T_BITS >= 2 * N_BITS,
+            "the product needs {} bits",
+            2 * N_BITS
+        );
+        script! {
+            { Self::convert_to_be_bits_toaltstack() }
+            { Self::resize::<T_BITS>() }
+            { BigIntImpl::<T_BITS, LIMB_SIZE>::mul_bits_from_altstack(window, N_BITS) }
+        }
+    }
+
+    /// Multiply the BigInt on top of the stack by the `num_bits`-bit number whose bits are on
+    /// the altstack, most significant bit on top.
+    fn mul_bits_from_altstack(window: u32, num_bits: u32) -> Script {
+        assert!(
+            window > 0 && window <= 8,
+            "window must lie between 1 and 8 (inclusive)"
+        );
+        let table_size = 1 << window;
+        let n_windows = num_bits.div_ceil(window);
+        // the most significant window takes the remaining bits
+        let head_window = num_bits - (n_windows - 1) * window;
+
+        script! {
+            // table of multiples: 0 deepest, (2^window - 1) * a on top
+            { Self::push_zero() }
+            { Self::roll(1) }
+            for i in 2..table_size {
+                { Self::copy(0) }
+                { Self::copy(i - 1) }
+                { Self::add(0, 1) }
+            }
+
+            // copy the multiple of the first window
+            { Self::pick_multiple(table_size * Self::N_LIMBS, head_window) }
+
+            for _ in 1..n_windows {
+                for _ in 0..window {
+                    { Self::double(0) }
+                }
+                // the table is one BigInt deeper below the accumulator
+                { Self::pick_multiple((table_size + 1) * Self::N_LIMBS, window) }
+                { Self::add(0, 1) }
+            }
+
+            // drop the table
+            { Self::toaltstack() }
+            for _ in 0..table_size {
+                { Self::drop() }
+            }
+            { Self::fromaltstack() }
+        }
+    }
+
+    /// Copy the multiple selected by the next `bits` bits of the altstack. `base` is the pick
+    /// depth of the multiple 0, see the second branch of `copy`.
+    fn pick_multiple(base: u32, bits: u32) -> Script {
+        script! {
+            { base }
+            for i in (0..bits).rev() {
+                OP_FROMALTSTACK
+                OP_IF
+                    { (1 << i) * Self::N_LIMBS }
+                    OP_SUB
+                OP_ENDIF
+            }
+            for _ in 0..Self::N_LIMBS - 1 {
+                OP_DUP OP_PICK OP_SWAP
+            }
+            OP_1SUB OP_PICK
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::bigint::{U254, U64};
+    use crate::bigint::{BigIntImpl, U128, U254, U64};
     use crate::treepp::*;
     use core::ops::{Mul, Rem, Shl};
     use num_bigint::{BigUint, RandomBits};
@@ -84,4 +171,97 @@ mod test {
             run(script);
         }
     }
+
+    #[test]
+    fn test_mul_windowed() {
+        println!("U254.mul: {} bytes", U254::mul().len());
+        for window in 1..=6 {
+            println!(
+                "U254.mul_windowed({}): {} bytes",
+                window,
+                U254::mul_windowed(window).len()
+            );
+        }
+
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+        for window in [1, 3, 4, 5] {
+            let a: BigUint = prng.sample(RandomBits::new(254));
+            let b: BigUint = prng.sample(RandomBits::new(254));
+            let c: BigUint = (a.clone().mul(b.clone())).rem(BigUint::one().shl(254));
+
+            let script = script! {
+                { U254::push_u32_le(&a.to_u32_digits()) }
+                { U254::push_u32_le(&b.to_u32_digits()) }
+                { U254::mul_windowed(window) }
+                { U254::push_u32_le(&c.to_u32_digits()) }
+                { U254::equalverify(1, 0) }
+                OP_TRUE
+            };
+            run(script);
+
+            let a: BigUint = prng.sample(RandomBits::new(64));
+            let b: BigUint = prng.sample(RandomBits::new(64));
+            let c: BigUint = (a.clone().mul(b.clone())).rem(BigUint::one().shl(64));
+
+            let script = script! {
+                { U64::push_u32_le(&a.to_u32_digits()) }
+                { U64::push_u32_le(&b.to_u32_digits()) }
+                { U64::mul_windowed(window) }
+                { U64::push_u32_le(&c.to_u32_digits()) }
+                { U64::equalverify(1, 0) }
+                OP_TRUE
+            };
+            run(script);
+        }
+
+        // all ones, so that every multiple of the table is used
+        let a = BigUint::one().shl(254) - BigUint::one();
+        let c = (a.clone().mul(a.clone())).rem(BigUint::one().shl(254));
+        let script = script! {
+            { U254::push_u32_le(&a.to_u32_digits()) }
+            { U254::push_u32_le(&a.to_u32_digits()) }
+            { U254::mul_windowed(4) }
+            { U254::push_u32_le(&c.to_u32_digits()) }
+            { U254::equalverify(1, 0) }
+            OP_TRUE
+        };
+        run(script);
+    }
+
+    #[test]
+    fn test_mul_full() {
+        type U508 = BigIntImpl<508, 29>;
+        println!("U254.mul_full(4): {} bytes", U254::mul_full::<508>(4).len());
+
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..3 {
+            let a: BigUint = prng.sample(RandomBits::new(254));
+            let b: BigUint = prng.sample(RandomBits::new(254));
+            let c: BigUint = a.clone().mul(b.clone());
+
+            let script = script! {
+                { U254::push_u32_le(&a.to_u32_digits()) }
+                { U254::push_u32_le(&b.to_u32_digits()) }
+                { U254::mul_full::<508>(4) }
+                { U508::push_u32_le(&c.to_u32_digits()) }
+                { U508::equalverify(1, 0) }
+                OP_TRUE
+            };
+            run(script);
+
+            let a: BigUint = prng.sample(RandomBits::new(64));
+            let b: BigUint = prng.sample(RandomBits::new(64));
+            let c: BigUint = a.clone().mul(b.clone());
+
+            let script = script! {
+                { U64::push_u32_le(&a.to_u32_digits()) }
+                { U64::push_u32_le(&b.to_u32_digits()) }
+                { U64::mul_full::<128>(3) }
+                { U128::push_u32_le(&c.to_u32_digits()) }
+                { U128::equalverify(1, 0) }
+                OP_TRUE
+            };
+            run(script);
+        }
+    }
 }
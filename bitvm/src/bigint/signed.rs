@@ -0,0 +1,425 @@
+use crate::bigint::{BigIntImpl, SignedBigIntImpl};
+use crate::treepp::*;
+use num_bigint::BigInt;
+use num_traits::{One, Signed};
+
+/// Signed arithmetic is the unsigned arithmetic of `BigIntImpl` modulo 2^N_BITS, the most
+/// significant bit being the sign. The operations below fail instead of wrapping around.
+impl<const N_BITS: u32, const LIMB_SIZE: u32> SignedBigIntImpl<N_BITS, LIMB_SIZE> {
+    /// Push a number in [-2^(N_BITS - 1), 2^(N_BITS - 1))
+    pub fn push(v: &BigInt) -> Script {
+        let bound = BigInt::one() << (N_BITS - 1);
+        assert!(
+            v >= &-bound.clone() && v < &bound,
+            "{} doesn't fit in {} signed bits",
+            v,
+            N_BITS
+        );
+        let encoded = if v.is_negative() {
+            v + (BigInt::one() << N_BITS)
+        } else {
+            v.clone()
+        };
+        BigIntImpl::<N_BITS, LIMB_SIZE>::push_u32_le(&encoded.to_biguint().unwrap().to_u32_digits())
+    }
+
+    pub fn push_zero() -> Script {
+        BigIntImpl::<N_BITS, LIMB_SIZE>::push_zero()
+    }
+
+    pub fn copy(a: u32) -> Script {
+        BigIntImpl::<N_BITS, LIMB_SIZE>::copy(a)
+    }
+
+    pub fn roll(a: u32) -> Script {
+        BigIntImpl::<N_BITS, LIMB_SIZE>::roll(a)
+    }
+
+    pub fn drop() -> Script {
+        BigIntImpl::<N_BITS, LIMB_SIZE>::drop()
+    }
+
+    pub fn equal(a: u32, b: u32) -> Script {
+        BigIntImpl::<N_BITS, LIMB_SIZE>::equal(a, b)
+    }
+
+    pub fn equalverify(a: u32, b: u32) -> Script {
+        BigIntImpl::<N_BITS, LIMB_SIZE>::equalverify(a, b)
+    }
+
+    pub fn is_negative(depth: u32) -> Script {
+        BigIntImpl::<N_BITS, LIMB_SIZE>::is_negative(depth)
+    }
+
+    pub fn is_positive(depth: u32) -> Script {
+        BigIntImpl::<N_BITS, LIMB_SIZE>::is_positive(depth)
+    }
+
+    /// Compute the sum of two numbers, failing on overflow
+    pub fn add(a: u32, b: u32) -> Script {
+        script! {
+            { Self::is_negative(a) } OP_TOALTSTACK
+            { Self::is_negative(b) } OP_TOALTSTACK
+            { BigIntImpl::<N_BITS, LIMB_SIZE>::add(a, b) }
+            { Self::verify_no_overflow() }
+        }
+    }
+
+    /// Compute a - b, failing on overflow
+    pub fn sub(a: u32, b: u32) -> Script {
+        script! {
+            { Self::is_negative(a) } OP_TOALTSTACK
+            // a - b overflows like a + (-b)
+            { Self::is_negative(b) } OP_NOT OP_TOALTSTACK
+            { BigIntImpl::<N_BITS, LIMB_SIZE>::sub(a, b) }
+            { Self::verify_no_overflow() }
+        }
+    }
+
+    /// Negate the number at depth `a`, failing for -2^(N_BITS - 1)
+    pub fn neg(a: u32) -> Script {
+        script! {
+            { Self::push_zero() }
+            { Self::sub(0, a + 1) }
+        }
+    }
+
+    /// Absolute value of the number on top of the stack, failing for -2^(N_BITS - 1)
+    pub fn abs() -> Script {
+        script! {
+            { Self::is_negative(0) }
+            OP_IF
+                { Self::neg(0) }
+            OP_ENDIF
+        }
+    }
+
+    /// Check the sign of the result of an addition.
+    /// Input: result, with the signs of the operands on the altstack
+    /// An addition overflows iff both operands have the same sign and the result the other one.
+    fn verify_no_overflow() -> Script {
+        script! {
+            { Self::is_negative(0) }
+            OP_FROMALTSTACK OP_FROMALTSTACK
+            OP_2DUP OP_EQUAL
+            OP_IF
+                OP_NIP OP_EQUALVERIFY
+            OP_ELSE
+                OP_2DROP OP_DROP
+            OP_ENDIF
+        }
+    }
+
+    // return if a < b
+    pub fn lessthan(a: u32, b: u32) -> Script {
+        script! {
+            { Self::is_negative(a) } OP_TOALTSTACK
+            { Self::is_negative(b) } OP_TOALTSTACK
+            { BigIntImpl::<N_BITS, LIMB_SIZE>::lessthan(a, b) }
+            OP_FROMALTSTACK OP_FROMALTSTACK
+            // with different signs, a < b iff a is negative
+            OP_2DUP OP_EQUAL
+            OP_IF
+                OP_2DROP
+            OP_ELSE
+                OP_NIP OP_NIP
+            OP_ENDIF
+        }
+    }
+
+    // return if a <= b
+    pub fn lessthanorequal(a: u32, b: u32) -> Script { Self::greaterthanorequal(b, a) }
+
+    // return if a > b
+    pub fn greaterthan(a: u32, b: u32) -> Script {
+        script! {
+            { Self::lessthanorequal(a, b) }
+            OP_NOT
+        }
+    }
+
+    // return if a >= b
+    pub fn greaterthanorequal(a: u32, b: u32) -> Script {
+        script! {
+            { Self::lessthan(a, b) }
+            OP_NOT
+        }
+    }
+
+    /// Resize the number on top of the stack, extending the sign. Shrinking fails if the
+    /// number doesn't fit in `T_BITS`.
+    pub fn resize<const T_BITS: u32>() -> Script {
+        if T_BITS >= N_BITS {
+            Self::sign_extend::<T_BITS>()
+        } else {
+            Self::truncate::<T_BITS>()
+        }
+    }
+
+    fn sign_extend<const T_BITS: u32>() -> Script {
+        let n_limbs_self = Self::N_LIMBS;
+        let n_limbs_target = SignedBigIntImpl::<T_BITS, LIMB_SIZE>::N_LIMBS;
+        let head_offset_target = SignedBigIntImpl::<T_BITS, LIMB_SIZE>::HEAD_OFFSET;
+
+        // the head limb takes the sign bits up to the target head, or the whole limb
+        let head_fill = if n_limbs_target == n_limbs_self {
+            head_offset_target - Self::HEAD_OFFSET
+        } else {
+            (1 << LIMB_SIZE) - Self::HEAD_OFFSET
+        };
+
+        script! {
+            { Self::is_negative(0) }
+            OP_IF
+                { n_limbs_self - 1 } OP_ROLL
+                { head_fill } OP_ADD
+                for _ in 0..n_limbs_self - 1 {
+                    { n_limbs_self - 1 } OP_ROLL
+                }
+                if n_limbs_target > n_limbs_self {
+                    { head_offset_target - 1 }
+                    for _ in 1..n_limbs_target - n_limbs_self {
+                        { (1 << LIMB_SIZE) - 1 }
+                    }
+                    for _ in 0..n_limbs_self {
+                        { n_limbs_target - 1 } OP_ROLL
+                    }
+                }
+            OP_ELSE
+                { BigIntImpl::<N_BITS, LIMB_SIZE>::resize::<T_BITS>() }
+            OP_ENDIF
+        }
+    }
+
+    fn truncate<const T_BITS: u32>() -> Script {
+        let n_limbs_target = SignedBigIntImpl::<T_BITS, LIMB_SIZE>::N_LIMBS;
+        let head_target = SignedBigIntImpl::<T_BITS, LIMB_SIZE>::HEAD;
+        // bits of the limb that becomes the target head
+        let head_bits = if n_limbs_target < Self::N_LIMBS {
+            LIMB_SIZE
+        } else {
+            Self::HEAD
+        };
+
+        script! {
+            { Self::copy(0) }
+            { BigIntImpl::<N_BITS, LIMB_SIZE>::resize::<T_BITS>() }
+
+            // clear the bits above the target head
+            { n_limbs_target - 1 } OP_ROLL
+            for bit in (head_target..head_bits).rev() {
+                { 1 << bit }
+                OP_2DUP OP_GREATERTHANOREQUAL
+                OP_IF
+                    OP_SUB
+                OP_ELSE
+                    OP_DROP
+                OP_ENDIF
+            }
+            for _ in 0..n_limbs_target - 1 {
+                { n_limbs_target - 1 } OP_ROLL
+            }
+
+            // the number fits iff extending the truncated number gives it back
+            { SignedBigIntImpl::<T_BITS, LIMB_SIZE>::copy(0) }
+            { BigIntImpl::<T_BITS, LIMB_SIZE>::toaltstack() }
+            { SignedBigIntImpl::<T_BITS, LIMB_SIZE>::sign_extend::<N_BITS>() }
+            { Self::equalverify(1, 0) }
+            { BigIntImpl::<T_BITS, LIMB_SIZE>::fromaltstack() }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bigint::{SignedBigIntImpl, I256, I64};
+    use crate::treepp::*;
+    use num_bigint::{BigInt, RandomBits};
+    use num_traits::One;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    fn sample(prng: &mut ChaCha20Rng, bits: u64) -> BigInt {
+        // a magnitude of `bits` bits with a random sign
+        prng.sample(RandomBits::new(bits))
+    }
+
+    fn min(bits: u32) -> BigInt {
+        -(BigInt::one() << (bits - 1))
+    }
+
+    fn max(bits: u32) -> BigInt {
+        (BigInt::one() << (bits - 1)) - BigInt::one()
+    }
+
+    #[test]
+    fn test_add_sub() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..20 {
+            let a = sample(&mut prng, 62);
+            let b = sample(&mut prng, 62);
+
+            let script = script! {
+                { I64::push(&a) }
+                { I64::push(&b) }
+                { I64::add(1, 0) }
+                { I64::push(&(&a + &b)) }
+                { I64::equalverify(1, 0) }
+                { I64::push(&a) }
+                { I64::push(&b) }
+                { I64::sub(1, 0) }
+                { I64::push(&(&a - &b)) }
+                { I64::equalverify(1, 0) }
+                OP_TRUE
+            };
+            run(script);
+
+            let a = sample(&mut prng, 254);
+            let b = sample(&mut prng, 254);
+            let script = script! {
+                { I256::push(&b) }
+                { I256::push(&a) }
+                { I256::sub(0, 1) }
+                { I256::push(&(&a - &b)) }
+                { I256::equalverify(1, 0) }
+                OP_TRUE
+            };
+            run(script);
+        }
+    }
+
+    #[test]
+    fn test_overflow() {
+        let one = BigInt::one();
+        for (a, b, add_ok, sub_ok) in [
+            (max(64), one.clone(), false, true),
+            (min(64), one.clone(), true, false),
+            (min(64), -one.clone(), false, true),
+            (max(64), min(64), true, false),
+            (-one.clone(), max(64), true, true),
+        ] {
+            let script = script! {
+                { I64::push(&a) }
+                { I64::push(&b) }
+                { I64::add(1, 0) }
+                { I64::drop() }
+                OP_TRUE
+            };
+            assert_eq!(execute_script(script).success, add_ok, "{} + {}", a, b);
+
+            let script = script! {
+                { I64::push(&a) }
+                { I64::push(&b) }
+                { I64::sub(1, 0) }
+                { I64::drop() }
+                OP_TRUE
+            };
+            assert_eq!(execute_script(script).success, sub_ok, "{} - {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_neg_abs() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..10 {
+            let a = sample(&mut prng, 63);
+            let script = script! {
+                { I64::push(&a) }
+                { I64::neg(0) }
+                { I64::push(&-&a) }
+                { I64::equalverify(1, 0) }
+                { I64::push(&a) }
+                { I64::abs() }
+                { I64::push(&BigInt::from(a.magnitude().clone())) }
+                { I64::equalverify(1, 0) }
+                OP_TRUE
+            };
+            run(script);
+        }
+
+        let script = script! {
+            { I64::push(&min(64)) }
+            { I64::abs() }
+            { I64::drop() }
+            OP_TRUE
+        };
+        assert!(!execute_script(script).success);
+    }
+
+    #[test]
+    fn test_cmp() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+        let mut values = vec![min(64), max(64), BigInt::from(0), BigInt::from(-1)];
+        for _ in 0..4 {
+            values.push(sample(&mut prng, 63));
+        }
+        for a in values.iter() {
+            for b in values.iter() {
+                let script = script! {
+                    { I64::push(a) }
+                    { I64::push(b) }
+                    { I64::lessthan(1, 0) }
+                    { (a < b) as u32 }
+                    OP_EQUALVERIFY
+                    { I64::push(a) }
+                    { I64::push(b) }
+                    { I64::greaterthanorequal(1, 0) }
+                    { (a >= b) as u32 }
+                    OP_EQUAL
+                };
+                run(script);
+            }
+        }
+    }
+
+    #[test]
+    fn test_resize() {
+        type I60 = SignedBigIntImpl<60, 16>;
+        type I128 = SignedBigIntImpl<128, 16>;
+
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..10 {
+            let a = sample(&mut prng, 59);
+            let script = script! {
+                { I64::push(&a) }
+                { I64::resize::<128>() }
+                { I128::push(&a) }
+                { I128::equalverify(1, 0) }
+                { I60::push(&a) }
+                { I60::resize::<64>() }
+                { I64::push(&a) }
+                { I64::equalverify(1, 0) }
+                { I128::push(&a) }
+                { I128::resize::<64>() }
+                { I64::push(&a) }
+                { I64::equalverify(1, 0) }
+                { I64::push(&a) }
+                { I64::resize::<60>() }
+                { I60::push(&a) }
+                { I60::equalverify(1, 0) }
+                OP_TRUE
+            };
+            run(script);
+        }
+
+        // values that don't fit
+        for (a, bits) in [(max(64), 60), (min(64), 60), (max(128), 64), (min(64) - 1, 64)] {
+            let script = if bits == 60 {
+                script! {
+                    { I64::push(&a) }
+                    { I64::resize::<60>() }
+                    { I60::drop() }
+                    OP_TRUE
+                }
+            } else {
+                script! {
+                    { I128::push(&a) }
+                    { I128::resize::<64>() }
+                    { I64::drop() }
+                    OP_TRUE
+                }
+            };
+            assert!(!execute_script(script).success, "{} in {} bits", a, bits);
+        }
+    }
+}
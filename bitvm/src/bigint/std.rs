@@ -24,6 +24,19 @@ struct TransformStep {
 
 impl<const N_BITS: u32, const LIMB_SIZE: u32> BigIntImpl<N_BITS, LIMB_SIZE> {
     pub fn push_u32_le(v: &[u32]) -> Script {
+        let limbs = Self::u32_le_to_limbs(v);
+
+        script! {
+            for limb in &limbs {
+                { *limb }
+            }
+            { push_to_stack(0,Self::N_LIMBS as usize - limbs.len()) }
+        }
+    }
+
+    /// Split a little endian u32 number into limbs, most significant limb first, i.e. in the
+    /// order they are pushed
+    pub fn u32_le_to_limbs(v: &[u32]) -> Vec<u32> {
         let mut bits = vec![];
         for elem in v.iter() {
             for i in 0..32 {
@@ -48,13 +61,7 @@ impl<const N_BITS: u32, const LIMB_SIZE: u32> BigIntImpl<N_BITS, LIMB_SIZE> {
         }
 
         limbs.reverse();
-
-        script! {
-            for limb in &limbs {
-                { *limb }
-            }
-            { push_to_stack(0,Self::N_LIMBS as usize - limbs.len()) }
-        }
+        limbs
     }
 
     pub fn read_u32_le(mut witness: Vec<Vec<u8>>) -> Vec<u32> {
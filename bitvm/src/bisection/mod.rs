@@ -0,0 +1,21 @@
+//! Interactive n-ary bisection over a committed trace of state hashes.
+//!
+//! The operator claims a trace of `num_steps` steps from an agreed initial state. In each round it
+//! commits with Winternitz signatures the hashes of the states splitting the disputed range into
+//! `arity` sub-ranges, and the challenger commits to the first sub-range whose end state it
+//! disagrees with. The endpoints of every round are committed again, so that the consistency of two
+//! consecutive rounds is checked by a script of its own. After the last selection the operator
+//! reveals the states before and after the isolated step along with its index, which the
+//! challenger disproves with the step script of the computation for that index.
+//!
+//! `protocol` is the off-chain state machine of the game and `scripts` has the locking scripts and
+//! witnesses of each move, the output of each move being spendable by the other party after the
+//! timeout.
+
+pub mod protocol;
+pub mod scripts;
+
+/// Number of bytes of a state hash
+pub const TRACE_HASH_LENGTH: usize = 20;
+
+pub type TraceHash = [u8; TRACE_HASH_LENGTH];
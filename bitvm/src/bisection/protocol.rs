@@ -0,0 +1,329 @@
+use super::TraceHash;
+use serde::{Deserialize, Serialize};
+
+/// Largest arity, so that the Winternitz signatures of the hashes of a round fit the stack
+pub const MAX_ARITY: u32 = 8;
+
+/// Parameters of a bisection game over a trace of `num_steps` steps, i.e. `num_steps + 1` states
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BisectionConfig {
+    pub num_steps: u64,
+    /// Number of sub-ranges the disputed range is split into in each round
+    pub arity: u32,
+    /// Relative timelock in blocks after which the party that did not move loses
+    pub timeout: u32,
+}
+
+impl BisectionConfig {
+    pub fn new(num_steps: u64, arity: u32, timeout: u32) -> Self {
+        assert!(num_steps > 0, "the trace should have a step");
+        assert!(
+            (2..=MAX_ARITY).contains(&arity),
+            "the arity should be in [2, {}]",
+            MAX_ARITY
+        );
+        Self {
+            num_steps,
+            arity,
+            timeout,
+        }
+    }
+
+    /// Number of rounds followed by a selection of the challenger
+    pub fn rounds(&self) -> u32 {
+        let mut rounds = 0;
+        let mut width = 1_u64;
+        while width < self.num_steps {
+            width *= self.arity as u64;
+            rounds += 1;
+        }
+        rounds
+    }
+
+    /// Number of steps of the padded trace, whose last state is repeated after the end of the trace
+    /// The step script should accept a step from a final state to itself.
+    pub fn padded_steps(&self) -> u64 { (self.arity as u64).pow(self.rounds()) }
+
+    /// Number of hashes committed by the operator in the round
+    /// The round after the last selection reveals the states before and after the isolated step.
+    pub fn num_hashes(&self, round: u32) -> usize {
+        assert!(round <= self.rounds(), "the round is out of the game");
+        if round < self.rounds() {
+            self.arity as usize + 1
+        } else {
+            2
+        }
+    }
+
+    /// Indices of the states committed in the round whose range starts at `start`
+    pub fn points(&self, round: u32, start: u64) -> Vec<u64> {
+        let num_hashes = self.num_hashes(round);
+        let width = (self.arity as u64).pow(self.rounds() - round);
+        let step = width / (num_hashes as u64 - 1);
+        (0..num_hashes as u64).map(|i| start + i * step).collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Party {
+    Operator,
+    Challenger,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// The operator should commit the hashes of the round
+    Commit { round: u32 },
+    /// The challenger should select a sub-range of the hashes committed in the round
+    Select { round: u32 },
+    /// The endpoints committed in the round differ from the sub-range selected before, the
+    /// challenger can spend the equivocation script
+    Equivocation { round: u32 },
+    /// The step is isolated, the challenger can spend its disprove script
+    Disprove { step: u64 },
+    /// The party did not move before the timeout and the other party wins
+    TimedOut { party: Party },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BisectionError {
+    /// The move is not expected in the phase
+    UnexpectedMove(Phase),
+    /// The number of committed hashes differs from the one of the round
+    InvalidCommitment { expected: usize, actual: usize },
+    /// The first committed hash differs from the agreed initial state
+    InvalidInitialState,
+    /// The selected sub-range is not in the round
+    InvalidSelection(u32),
+}
+
+/// The state of a bisection game, updated with the moves of both parties as they are confirmed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Bisection {
+    pub config: BisectionConfig,
+    pub initial_state: TraceHash,
+    pub phase: Phase,
+    /// Index of the first state of the current range
+    pub start: u64,
+    /// The hashes committed by the operator in each round
+    pub commitments: Vec<Vec<TraceHash>>,
+    /// The sub-ranges selected by the challenger in each round
+    pub selections: Vec<u32>,
+}
+
+impl Bisection {
+    pub fn new(config: BisectionConfig, initial_state: TraceHash) -> Self {
+        Self {
+            config,
+            initial_state,
+            phase: Phase::Commit { round: 0 },
+            start: 0,
+            commitments: vec![],
+            selections: vec![],
+        }
+    }
+
+    /// Indices of the states to be committed in the current round
+    pub fn points(&self) -> Vec<u64> {
+        match self.phase {
+            Phase::Commit { round } | Phase::Select { round } => {
+                self.config.points(round, self.start)
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Applies the commitment of the operator
+    pub fn commit(&mut self, hashes: Vec<TraceHash>) -> Result<(), BisectionError> {
+        let Phase::Commit { round } = self.phase else {
+            return Err(BisectionError::UnexpectedMove(self.phase));
+        };
+        let expected = self.config.num_hashes(round);
+        if hashes.len() != expected {
+            return Err(BisectionError::InvalidCommitment {
+                expected,
+                actual: hashes.len(),
+            });
+        }
+        if round == 0 && hashes[0] != self.initial_state {
+            return Err(BisectionError::InvalidInitialState);
+        }
+
+        let consistent = match round {
+            0 => true,
+            _ => {
+                let previous = &self.commitments[round as usize - 1];
+                let selection = self.selections[round as usize - 1] as usize;
+                hashes[0] == previous[selection] && hashes[expected - 1] == previous[selection + 1]
+            }
+        };
+        self.commitments.push(hashes);
+        self.phase = if !consistent {
+            Phase::Equivocation { round }
+        } else if round == self.config.rounds() {
+            Phase::Disprove { step: self.start }
+        } else {
+            Phase::Select { round }
+        };
+        Ok(())
+    }
+
+    /// Applies the selection of the challenger
+    pub fn select(&mut self, selection: u32) -> Result<(), BisectionError> {
+        let Phase::Select { round } = self.phase else {
+            return Err(BisectionError::UnexpectedMove(self.phase));
+        };
+        if selection >= self.config.arity {
+            return Err(BisectionError::InvalidSelection(selection));
+        }
+        self.start = self.config.points(round, self.start)[selection as usize];
+        self.selections.push(selection);
+        self.phase = Phase::Commit { round: round + 1 };
+        Ok(())
+    }
+
+    /// Ends the game after the timeout of the party to move
+    pub fn timeout(&mut self) -> Result<(), BisectionError> {
+        let party = match self.phase {
+            Phase::Commit { .. } => Party::Operator,
+            Phase::Select { .. } => Party::Challenger,
+            _ => return Err(BisectionError::UnexpectedMove(self.phase)),
+        };
+        self.phase = Phase::TimedOut { party };
+        Ok(())
+    }
+
+    /// The state of the padded trace at the index
+    fn state(&self, trace: &[TraceHash], index: u64) -> TraceHash {
+        assert_eq!(
+            trace.len() as u64,
+            self.config.num_steps + 1,
+            "the trace should have a state before and after each step"
+        );
+        trace[index.min(self.config.num_steps) as usize]
+    }
+
+    /// The hashes the operator commits in the current round for its trace
+    pub fn hashes(&self, trace: &[TraceHash]) -> Vec<TraceHash> {
+        self.points()
+            .into_iter()
+            .map(|index| self.state(trace, index))
+            .collect()
+    }
+
+    /// The first sub-range of the current round whose committed end state differs from the trace
+    /// of the challenger, none if the challenger agrees with all the committed hashes
+    pub fn selection(&self, trace: &[TraceHash]) -> Option<u32> {
+        let Phase::Select { round } = self.phase else {
+            return None;
+        };
+        let committed = &self.commitments[round as usize];
+        let hashes = self.hashes(trace);
+        (0..self.config.arity).find(|&i| committed[i as usize + 1] != hashes[i as usize + 1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace(num_steps: u64, faulty_step: Option<u64>) -> Vec<TraceHash> {
+        (0..=num_steps)
+            .map(|i| {
+                let faulty = faulty_step.is_some_and(|step| i > step);
+                let state = [i.to_le_bytes(), (faulty as u64).to_le_bytes()].concat();
+                blake3::hash(&state).as_bytes()[..20].try_into().unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_rounds() {
+        let config = BisectionConfig::new(1, 2, 6);
+        assert_eq!(config.rounds(), 0);
+        assert_eq!(config.points(0, 0), vec![0, 1]);
+
+        let config = BisectionConfig::new(10, 3, 6);
+        assert_eq!(config.rounds(), 3);
+        assert_eq!(config.padded_steps(), 27);
+        assert_eq!(config.points(0, 0), vec![0, 9, 18, 27]);
+        assert_eq!(config.points(2, 21), vec![21, 22, 23, 24]);
+        assert_eq!(config.points(3, 22), vec![22, 23]);
+        assert_eq!(config.num_hashes(3), 2);
+    }
+
+    #[test]
+    fn test_bisection_isolates_faulty_step() {
+        for (num_steps, arity) in [(10, 3), (16, 2), (100, 8)] {
+            let honest = trace(num_steps, None);
+            for faulty_step in [0, num_steps / 2, num_steps - 1] {
+                let faulty = trace(num_steps, Some(faulty_step));
+                let config = BisectionConfig::new(num_steps, arity, 6);
+                let mut bisection = Bisection::new(config, honest[0]);
+                loop {
+                    match bisection.phase {
+                        Phase::Commit { .. } => {
+                            bisection.commit(bisection.hashes(&faulty)).unwrap()
+                        }
+                        Phase::Select { .. } => {
+                            let selection = bisection.selection(&honest).unwrap();
+                            bisection.select(selection).unwrap()
+                        }
+                        _ => break,
+                    }
+                }
+                assert_eq!(bisection.phase, Phase::Disprove { step: faulty_step });
+                assert_eq!(bisection.selections.len() as u32, config.rounds());
+                let revealed = bisection.commitments.last().unwrap();
+                assert_eq!(revealed[0], honest[faulty_step as usize]);
+                assert_ne!(revealed[1], honest[faulty_step as usize + 1]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bisection_errors() {
+        let honest = trace(10, None);
+        let config = BisectionConfig::new(10, 3, 6);
+        let mut bisection = Bisection::new(config, honest[0]);
+        assert_eq!(
+            bisection.select(0),
+            Err(BisectionError::UnexpectedMove(Phase::Commit { round: 0 }))
+        );
+        assert_eq!(
+            bisection.commit(vec![honest[0]]),
+            Err(BisectionError::InvalidCommitment {
+                expected: 4,
+                actual: 1
+            })
+        );
+        assert_eq!(
+            bisection.commit(vec![honest[1]; 4]),
+            Err(BisectionError::InvalidInitialState)
+        );
+
+        bisection.commit(bisection.hashes(&honest)).unwrap();
+        assert_eq!(bisection.selection(&honest), None);
+        assert_eq!(
+            bisection.select(3),
+            Err(BisectionError::InvalidSelection(3))
+        );
+        bisection.select(1).unwrap();
+
+        // the endpoints should be the ones of the selected sub-range
+        let mut hashes = bisection.hashes(&honest);
+        hashes[3] = honest[0];
+        bisection.commit(hashes).unwrap();
+        assert_eq!(bisection.phase, Phase::Equivocation { round: 1 });
+        assert!(bisection.timeout().is_err());
+
+        let mut bisection = Bisection::new(config, honest[0]);
+        bisection.timeout().unwrap();
+        assert_eq!(
+            bisection.phase,
+            Phase::TimedOut {
+                party: Party::Operator
+            }
+        );
+    }
+}
@@ -0,0 +1,606 @@
+use super::protocol::BisectionConfig;
+use super::{TraceHash, TRACE_HASH_LENGTH};
+use crate::chunker::common::{equalverify, not_equal};
+use crate::signatures::{
+    signing_winternitz::{
+        generate_winternitz_checksig_leave_hash, generate_winternitz_witness,
+        winternitz_message_checksig, WinternitzPublicKey, WinternitzSecret,
+        WinternitzSigningInputs, LOG_D,
+    },
+    utils::digits_to_number,
+};
+use crate::treepp::*;
+use bitcoin::{Witness, XOnlyPublicKey};
+use serde::{Deserialize, Serialize};
+
+/*
+    Each move spends the output of the previous one by one of the leaves below, the output of the
+    challenge being spent by the commitment of the first round:
+        commitment of the round:    operator_commit_script, timeout_script(challenger)
+        response to the round:      challenger_select_script (or disprove_script after the last
+                                    selection), equivocation_script for each selection of the
+                                    previous round, timeout_script(operator)
+    The Winternitz signatures revealed by the moves are reused by the witnesses of the
+    equivocation and disprove scripts. The commitment of the last round also signs the index of
+    the isolated step, so that each disprove leaf only applies to the step it was built for.
+*/
+
+/// Number of bytes of the selection message, a u32 in little endian
+pub const SELECTION_MESSAGE_LENGTH: usize = 4;
+/// Number of bytes of the index of the isolated step, a u64 in little endian
+pub const STEP_MESSAGE_LENGTH: usize = 8;
+
+/// The Winternitz public keys of the hashes committed by the operator in each round, of the index
+/// of the isolated step revealed with the last round and of the selections of the challenger
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct BisectionPublicKeys {
+    pub operator: Vec<Vec<WinternitzPublicKey>>,
+    pub step: WinternitzPublicKey,
+    pub challenger: Vec<WinternitzPublicKey>,
+}
+
+impl BisectionPublicKeys {
+    pub fn new(
+        operator_secrets: &[Vec<WinternitzSecret>],
+        step_secret: &WinternitzSecret,
+        challenger_secrets: &[WinternitzSecret],
+    ) -> Self {
+        Self {
+            operator: operator_secrets
+                .iter()
+                .map(|round| round.iter().map(WinternitzPublicKey::from).collect())
+                .collect(),
+            step: step_secret.into(),
+            challenger: challenger_secrets
+                .iter()
+                .map(WinternitzPublicKey::from)
+                .collect(),
+        }
+    }
+}
+
+/// One secret for each hash committed by the operator, for each round
+pub fn generate_operator_secrets(config: &BisectionConfig) -> Vec<Vec<WinternitzSecret>> {
+    (0..=config.rounds())
+        .map(|round| {
+            (0..config.num_hashes(round))
+                .map(|_| WinternitzSecret::new(TRACE_HASH_LENGTH))
+                .collect()
+        })
+        .collect()
+}
+
+/// The secret of the operator for the index of the isolated step
+pub fn generate_step_secret() -> WinternitzSecret { WinternitzSecret::new(STEP_MESSAGE_LENGTH) }
+
+/// One secret for the selection of the challenger, for each round
+pub fn generate_challenger_secrets(config: &BisectionConfig) -> Vec<WinternitzSecret> {
+    (0..config.rounds())
+        .map(|_| WinternitzSecret::new(SELECTION_MESSAGE_LENGTH))
+        .collect()
+}
+
+/// Witness of `verify_commitment` for the hashes, the signature of the first hash deepest
+pub fn commitment_witness(secrets: &[WinternitzSecret], hashes: &[TraceHash]) -> Witness {
+    assert_eq!(secrets.len(), hashes.len());
+    let mut witness = Witness::new();
+    for (secret, hash) in secrets.iter().zip(hashes) {
+        for element in generate_winternitz_witness(&WinternitzSigningInputs {
+            message: hash,
+            signing_key: secret,
+        })
+        .iter()
+        {
+            witness.push(element);
+        }
+    }
+    witness
+}
+
+/// The signatures of the hashes at the slots, taken from the witness of a commitment of n hashes
+fn commitment_slots(witness: &Witness, n: usize, slots: &[usize]) -> Vec<Vec<u8>> {
+    let elements = witness.to_vec();
+    assert_eq!(elements.len() % n, 0, "the witness should commit n hashes");
+    let size = elements.len() / n;
+    slots
+        .iter()
+        .flat_map(|slot| elements[slot * size..(slot + 1) * size].to_vec())
+        .collect()
+}
+
+pub fn selection_witness(secret: &WinternitzSecret, selection: u32) -> Witness {
+    generate_winternitz_witness(&WinternitzSigningInputs {
+        message: &selection.to_le_bytes(),
+        signing_key: secret,
+    })
+}
+
+/// Witness of the index of the isolated step, put on top of the commitment of the last round
+pub fn step_index_witness(secret: &WinternitzSecret, step: u64) -> Witness {
+    generate_winternitz_witness(&WinternitzSigningInputs {
+        message: &step.to_le_bytes(),
+        signing_key: secret,
+    })
+}
+
+/// Verifies the signatures of the hashes and leaves them, the first hash deepest
+pub fn verify_commitment(public_keys: &[&WinternitzPublicKey]) -> Script {
+    script! {
+        for public_key in public_keys.iter().rev() {
+            { generate_winternitz_checksig_leave_hash(public_key, TRACE_HASH_LENGTH) }
+            for _ in 0..TRACE_HASH_LENGTH {
+                OP_TOALTSTACK
+            }
+        }
+        for _ in 0..public_keys.len() * TRACE_HASH_LENGTH {
+            OP_FROMALTSTACK
+        }
+    }
+}
+
+/// Verifies the signature of the selection and leaves it
+pub fn verify_selection(public_key: &WinternitzPublicKey) -> Script {
+    script! {
+        { winternitz_message_checksig(public_key) }
+        { digits_to_number::<{ SELECTION_MESSAGE_LENGTH * 2 }, { LOG_D as usize }>() }
+    }
+}
+
+/// Verifies the signature of the index of the isolated step and leaves it, the first byte deepest
+fn verify_step_commitment(public_keys: &BisectionPublicKeys) -> Script {
+    generate_winternitz_checksig_leave_hash(&public_keys.step, STEP_MESSAGE_LENGTH)
+}
+
+/// Verifies that the signed index of the isolated step is `step`
+pub fn verify_step_index(public_keys: &BisectionPublicKeys, step: u64) -> Script {
+    script! {
+        { verify_step_commitment(public_keys) }
+        for byte in step.to_le_bytes() {
+            { byte }
+        }
+        { equalverify(STEP_MESSAGE_LENGTH) }
+    }
+}
+
+fn push_hash(hash: &TraceHash) -> Script {
+    script! {
+        for byte in hash {
+            { *byte }
+        }
+    }
+}
+
+fn drop_hashes(n: usize) -> Script {
+    script! {
+        for _ in 0..n * TRACE_HASH_LENGTH / 2 {
+            OP_2DROP
+        }
+    }
+}
+
+/// Verifies the commitment of the round, whose first hash is the initial state in the first round
+/// The commitment of the last round is topped by the signature of the index of the isolated step.
+pub fn verify_operator_commitment(
+    config: &BisectionConfig,
+    public_keys: &BisectionPublicKeys,
+    round: u32,
+    initial_state: &TraceHash,
+) -> Script {
+    let keys: Vec<_> = public_keys.operator[round as usize].iter().collect();
+    assert_eq!(keys.len(), config.num_hashes(round));
+    script! {
+        if round == config.rounds() {
+            { verify_step_commitment(public_keys) }
+            for _ in 0..STEP_MESSAGE_LENGTH / 2 {
+                OP_2DROP
+            }
+        }
+        { verify_commitment(&keys) }
+        if round == 0 {
+            { drop_hashes(keys.len() - 1) }
+            { push_hash(initial_state) }
+            { equalverify(TRACE_HASH_LENGTH) }
+        } else {
+            { drop_hashes(keys.len()) }
+        }
+    }
+}
+
+/// Verifies that the selection of the round is one of its sub-ranges
+pub fn verify_challenger_selection(
+    config: &BisectionConfig,
+    public_keys: &BisectionPublicKeys,
+    round: u32,
+) -> Script {
+    assert!(round < config.rounds(), "the last round has no selection");
+    script! {
+        { verify_selection(&public_keys.challenger[round as usize]) }
+        0
+        { config.arity }
+        OP_WITHIN
+        OP_VERIFY
+    }
+}
+
+/// Verifies that the endpoints committed in the round differ from the sub-range of the selection
+/// of the previous round
+pub fn verify_equivocation(
+    config: &BisectionConfig,
+    public_keys: &BisectionPublicKeys,
+    round: u32,
+    selection: u32,
+) -> Script {
+    assert!(round > 0, "the first round has no previous selection");
+    let previous = &public_keys.operator[round as usize - 1];
+    let current = &public_keys.operator[round as usize];
+    let last = config.num_hashes(round) - 1;
+    let selection = selection as usize;
+    script! {
+        { verify_selection(&public_keys.challenger[round as usize - 1]) }
+        { selection }
+        OP_EQUALVERIFY
+        { verify_commitment(&[&previous[selection], &previous[selection + 1]]) }
+        for _ in 0..2 * TRACE_HASH_LENGTH {
+            OP_TOALTSTACK
+        }
+        { verify_commitment(&[&current[0], &current[last]]) }
+        for _ in 0..2 * TRACE_HASH_LENGTH {
+            OP_FROMALTSTACK
+        }
+        { not_equal(2 * TRACE_HASH_LENGTH) }
+        OP_VERIFY
+    }
+}
+
+/// Verifies that the operator isolated `step`, then the states revealed around it and runs the step
+/// script on them
+/// The step script is given the states before and after the step, the first byte of each hash
+/// deepest, on top of its own witness, and should leave 1 if the step is invalid.
+pub fn verify_disprove(
+    config: &BisectionConfig,
+    public_keys: &BisectionPublicKeys,
+    step: u64,
+    step_script: Script,
+) -> Script {
+    let keys: Vec<_> = public_keys.operator[config.rounds() as usize]
+        .iter()
+        .collect();
+    script! {
+        { verify_step_index(public_keys, step) }
+        { verify_commitment(&keys) }
+        { step_script }
+        OP_VERIFY
+    }
+}
+
+pub fn operator_commit_script(
+    config: &BisectionConfig,
+    public_keys: &BisectionPublicKeys,
+    round: u32,
+    initial_state: &TraceHash,
+    operator: &XOnlyPublicKey,
+) -> Script {
+    script! {
+        { verify_operator_commitment(config, public_keys, round, initial_state) }
+        { *operator }
+        OP_CHECKSIG
+    }
+}
+
+pub fn challenger_select_script(
+    config: &BisectionConfig,
+    public_keys: &BisectionPublicKeys,
+    round: u32,
+    challenger: &XOnlyPublicKey,
+) -> Script {
+    script! {
+        { verify_challenger_selection(config, public_keys, round) }
+        { *challenger }
+        OP_CHECKSIG
+    }
+}
+
+pub fn equivocation_script(
+    config: &BisectionConfig,
+    public_keys: &BisectionPublicKeys,
+    round: u32,
+    selection: u32,
+    challenger: &XOnlyPublicKey,
+) -> Script {
+    script! {
+        { verify_equivocation(config, public_keys, round, selection) }
+        { *challenger }
+        OP_CHECKSIG
+    }
+}
+
+pub fn disprove_script(
+    config: &BisectionConfig,
+    public_keys: &BisectionPublicKeys,
+    step: u64,
+    step_script: Script,
+    challenger: &XOnlyPublicKey,
+) -> Script {
+    script! {
+        { verify_disprove(config, public_keys, step, step_script) }
+        { *challenger }
+        OP_CHECKSIG
+    }
+}
+
+/// The party to move loses the output after the timeout
+pub fn timeout_script(config: &BisectionConfig, winner: &XOnlyPublicKey) -> Script {
+    script! {
+        { config.timeout }
+        OP_CSV
+        OP_DROP
+        { *winner }
+        OP_CHECKSIG
+    }
+}
+
+/// Leaves of the output spent by the commitment of the round, the timeout being the last one
+pub fn commit_leaves(
+    config: &BisectionConfig,
+    public_keys: &BisectionPublicKeys,
+    round: u32,
+    initial_state: &TraceHash,
+    operator: &XOnlyPublicKey,
+    challenger: &XOnlyPublicKey,
+) -> Vec<Script> {
+    vec![
+        operator_commit_script(config, public_keys, round, initial_state, operator),
+        timeout_script(config, challenger),
+    ]
+}
+
+/// Leaves of the output of the commitment of the round, the timeout being the last one
+/// The step scripts are needed after the last round only, one disprove leaf for each of them, the
+/// script at index `i` disproving step `i`.
+pub fn response_leaves(
+    config: &BisectionConfig,
+    public_keys: &BisectionPublicKeys,
+    round: u32,
+    step_scripts: &[Script],
+    operator: &XOnlyPublicKey,
+    challenger: &XOnlyPublicKey,
+) -> Vec<Script> {
+    let mut leaves = vec![];
+    if round < config.rounds() {
+        leaves.push(challenger_select_script(
+            config,
+            public_keys,
+            round,
+            challenger,
+        ));
+    } else {
+        for (step, step_script) in step_scripts.iter().enumerate() {
+            leaves.push(disprove_script(
+                config,
+                public_keys,
+                step as u64,
+                step_script.clone(),
+                challenger,
+            ));
+        }
+    }
+    if round > 0 {
+        for selection in 0..config.arity {
+            leaves.push(equivocation_script(
+                config,
+                public_keys,
+                round,
+                selection,
+                challenger,
+            ));
+        }
+    }
+    leaves.push(timeout_script(config, operator));
+    leaves
+}
+
+/// Witness of `verify_equivocation` from the witnesses of the commitments of the round and of the
+/// previous round and of the selection of the previous round
+pub fn equivocation_witness(
+    config: &BisectionConfig,
+    round: u32,
+    selection: u32,
+    previous_commitment: &Witness,
+    commitment: &Witness,
+    selection_witness: &Witness,
+) -> Witness {
+    let n = config.num_hashes(round);
+    let previous_n = config.num_hashes(round - 1);
+    let selection = selection as usize;
+    let mut elements = commitment_slots(commitment, n, &[0, n - 1]);
+    elements.extend(commitment_slots(
+        previous_commitment,
+        previous_n,
+        &[selection, selection + 1],
+    ));
+    elements.extend(selection_witness.to_vec());
+    Witness::from_slice(&elements)
+}
+
+/// Witness of `verify_disprove` from the witness of the step script and the ones of the states
+/// revealed after the last selection and of the index of the isolated step
+pub fn disprove_witness(
+    step_witness: Vec<Vec<u8>>,
+    reveal_commitment: &Witness,
+    step_index: &Witness,
+) -> Witness {
+    let mut elements = step_witness;
+    elements.extend(reveal_commitment.to_vec());
+    elements.extend(step_index.to_vec());
+    Witness::from_slice(&elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::protocol::Bisection;
+    use super::*;
+    use crate::execute_script_with_inputs;
+    use std::str::FromStr;
+
+    fn state(i: u64) -> TraceHash {
+        blake3::hash(&i.to_le_bytes()).as_bytes()[..TRACE_HASH_LENGTH]
+            .try_into()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_operator_commitment() {
+        let config = BisectionConfig::new(9, 3, 6);
+        let secrets = generate_operator_secrets(&config);
+        let public_keys = BisectionPublicKeys::new(
+            &secrets,
+            &generate_step_secret(),
+            &generate_challenger_secrets(&config),
+        );
+        let hashes: Vec<_> = config.points(0, 0).into_iter().map(state).collect();
+
+        let witness = commitment_witness(&secrets[0], &hashes);
+        let script = script! {
+            { verify_commitment(&public_keys.operator[0].iter().collect::<Vec<_>>()) }
+            for hash in hashes.iter().rev() {
+                for byte in hash.iter().rev() {
+                    { *byte }
+                    OP_EQUALVERIFY
+                }
+            }
+            OP_TRUE
+        };
+        assert!(execute_script_with_inputs(script, witness.to_vec()).success);
+
+        for (initial_state, success) in [(state(0), true), (state(1), false)] {
+            let script = script! {
+                { verify_operator_commitment(&config, &public_keys, 0, &initial_state) }
+                OP_TRUE
+            };
+            let res = execute_script_with_inputs(script, witness.to_vec());
+            assert_eq!(res.success, success);
+        }
+
+        // the signatures of a round do not verify with the keys of another one
+        let script = script! {
+            { verify_operator_commitment(&config, &public_keys, 1, &state(0)) }
+            OP_TRUE
+        };
+        assert!(!execute_script_with_inputs(script, witness.to_vec()).success);
+    }
+
+    #[test]
+    fn test_challenger_selection() {
+        let config = BisectionConfig::new(9, 3, 6);
+        let secrets = generate_challenger_secrets(&config);
+        let public_keys = BisectionPublicKeys::new(
+            &generate_operator_secrets(&config),
+            &generate_step_secret(),
+            &secrets,
+        );
+        for (selection, success) in [(0, true), (2, true), (3, false)] {
+            let script = script! {
+                { verify_challenger_selection(&config, &public_keys, 1) }
+                OP_TRUE
+            };
+            let witness = selection_witness(&secrets[1], selection);
+            let res = execute_script_with_inputs(script, witness.to_vec());
+            assert_eq!(res.success, success);
+        }
+    }
+
+    #[test]
+    fn test_equivocation() {
+        let config = BisectionConfig::new(9, 3, 6);
+        let operator_secrets = generate_operator_secrets(&config);
+        let challenger_secrets = generate_challenger_secrets(&config);
+        let public_keys = BisectionPublicKeys::new(
+            &operator_secrets,
+            &generate_step_secret(),
+            &challenger_secrets,
+        );
+
+        let mut bisection = Bisection::new(config, state(0));
+        let hashes = bisection.hashes(&(0..=9).map(state).collect::<Vec<_>>());
+        let previous_commitment = commitment_witness(&operator_secrets[0], &hashes);
+        bisection.commit(hashes).unwrap();
+        let selection = 1;
+        let selection_witness = selection_witness(&challenger_secrets[0], selection);
+        bisection.select(selection).unwrap();
+
+        let consistent: Vec<_> = bisection.points().into_iter().map(state).collect();
+        let mut equivocating = consistent.clone();
+        equivocating[3] = state(100);
+        for (hashes, equivocation) in [(consistent, false), (equivocating, true)] {
+            let commitment = commitment_witness(&operator_secrets[1], &hashes);
+            for script_selection in [0, 1] {
+                let witness = equivocation_witness(
+                    &config,
+                    1,
+                    selection,
+                    &previous_commitment,
+                    &commitment,
+                    &selection_witness,
+                );
+                let script = script! {
+                    { verify_equivocation(&config, &public_keys, 1, script_selection) }
+                    OP_TRUE
+                };
+                let res = execute_script_with_inputs(script, witness.to_vec());
+                assert_eq!(res.success, equivocation && script_selection == selection);
+            }
+        }
+    }
+
+    #[test]
+    fn test_disprove() {
+        let config = BisectionConfig::new(4, 2, 6);
+        let operator_secrets = generate_operator_secrets(&config);
+        let step_secret = generate_step_secret();
+        let public_keys = BisectionPublicKeys::new(
+            &operator_secrets,
+            &step_secret,
+            &generate_challenger_secrets(&config),
+        );
+
+        // a step keeping the state, as the steps after the end of the trace
+        let step_script = not_equal(TRACE_HASH_LENGTH);
+        let step_index = step_index_witness(&step_secret, 2);
+        for (post_state, disproved) in [(state(2), false), (state(3), true)] {
+            let reveal = commitment_witness(&operator_secrets[2], &[state(2), post_state]);
+            let script = script! {
+                { verify_disprove(&config, &public_keys, 2, step_script.clone()) }
+                OP_TRUE
+            };
+            let witness = disprove_witness(vec![], &reveal, &step_index);
+            let res = execute_script_with_inputs(script, witness.to_vec());
+            assert_eq!(res.success, disproved);
+        }
+
+        // the commitment of a step only disproves with the leaf of that step
+        let reveal = commitment_witness(&operator_secrets[2], &[state(2), state(3)]);
+        let script = script! {
+            { verify_disprove(&config, &public_keys, 1, step_script.clone()) }
+            OP_TRUE
+        };
+        let witness = disprove_witness(vec![], &reveal, &step_index);
+        assert!(!execute_script_with_inputs(script, witness.to_vec()).success);
+
+        // the last commitment of the operator reveals the index of the step
+        let script = script! {
+            { verify_operator_commitment(&config, &public_keys, config.rounds(), &state(0)) }
+            OP_TRUE
+        };
+        let mut witness = reveal.to_vec();
+        witness.extend(step_index.to_vec());
+        assert!(execute_script_with_inputs(script.clone(), witness).success);
+        assert!(!execute_script_with_inputs(script, reveal.to_vec()).success);
+
+        let key = XOnlyPublicKey::from_str(
+            "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        )
+        .unwrap();
+        let leaves = response_leaves(&config, &public_keys, 2, &[step_script], &key, &key);
+        assert_eq!(leaves.len(), 1 + config.arity as usize + 1);
+    }
+}
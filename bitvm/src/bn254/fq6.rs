@@ -298,6 +298,77 @@ impl Fq6 {
         (script, hints)
     }
 
+    pub fn hinted_square(a: ark_bn254::Fq6) -> (Script, Vec<Hint>) {
+        // CH-SQR2 from https://eprint.iacr.org/2006/471.pdf
+        let mut hints = Vec::new();
+
+        let (hinted_script1, hint1) = Fq2::hinted_square(a.c0);
+        let (hinted_script2, hint2) = Fq2::hinted_mul(2, a.c0, 0, a.c1);
+        let (hinted_script3, hint3) = Fq2::hinted_mul(2, a.c1, 0, a.c2);
+        let (hinted_script4, hint4) = Fq2::hinted_square(a.c2);
+        let (hinted_script5, hint5) = Fq2::hinted_square(a.c0 - a.c1 + a.c2);
+
+        let script = script! {
+            // compute s_0 = a^2
+            { Fq2::copy(4) }
+            { hinted_script1 }
+
+            // compute s_1 = 2ab
+            { Fq2::copy(6) }
+            { Fq2::copy(6) }
+            { hinted_script2 }
+            { Fq2::double(0) }
+
+            // compute s_3 = 2bc
+            { Fq2::copy(6) }
+            { Fq2::copy(6) }
+            { hinted_script3 }
+            { Fq2::double(0) }
+
+            // compute s_4 = c^2
+            { Fq2::copy(6) }
+            { hinted_script4 }
+
+            // compute s_2 = (a - b + c)^2
+            { Fq2::roll(12) }
+            { Fq2::roll(12) }
+            { Fq2::sub(2, 0) }
+            { Fq2::roll(10) }
+            { Fq2::add(2, 0) }
+            { hinted_script5 }
+
+            // compute c_2 = s_1 + s_2 + s_3 - s_0 - s_4
+            { Fq2::copy(6) }
+            { Fq2::add(2, 0) }
+            { Fq2::copy(4) }
+            { Fq2::add(2, 0) }
+            { Fq2::copy(8) }
+            { Fq2::sub(2, 0) }
+            { Fq2::copy(2) }
+            { Fq2::sub(2, 0) }
+            { Fq2::toaltstack() }
+
+            // compute c_1 = s_1 + \beta s_4
+            { Fq6::mul_fq2_by_nonresidue() }
+            { Fq2::roll(4) }
+            { Fq2::add(2, 0) }
+            { Fq2::toaltstack() }
+
+            // compute c_0 = s_0 + \beta s_3
+            { Fq6::mul_fq2_by_nonresidue() }
+            { Fq2::add(2, 0) }
+            { Fq2::fromaltstack() }
+            { Fq2::fromaltstack() }
+        };
+        hints.extend(hint1);
+        hints.extend(hint2);
+        hints.extend(hint3);
+        hints.extend(hint4);
+        hints.extend(hint5);
+
+        (script, hints)
+    }
+
     // input:
     //    p.c0   (2 elements)
     //    p.c1   (2 elements)
@@ -554,6 +625,31 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_bn254_fq6_hinted_square() {
+        let mut prng: ChaCha20Rng = ChaCha20Rng::seed_from_u64(0);
+
+        for _ in 0..100 {
+            let a = ark_bn254::Fq6::rand(&mut prng);
+            let c = a.square();
+
+            let (hinted_square, hints) = Fq6::hinted_square(a);
+            println!("Fq6::hinted_square: {} bytes", hinted_square.len());
+
+            let script = script! {
+                for hint in hints {
+                    { hint.push() }
+                }
+                { Fq6::push(a) }
+                { hinted_square.clone() }
+                { Fq6::push(c) }
+                { Fq6::equalverify() }
+                OP_TRUE
+            };
+            run(script);
+        }
+    }
+
     #[test]
     fn test_bn254_fq6_hinted_mul_by_01() {
         let mut prng: ChaCha20Rng = ChaCha20Rng::seed_from_u64(0);
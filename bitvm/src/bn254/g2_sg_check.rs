@@ -78,7 +78,7 @@ fn hinted_check_double_and_add(t: ark_bn254::G2Affine, q: ark_bn254::G2Affine, b
     (script, hints)
 }
 
-fn hinted_check_double(t: ark_bn254::G2Affine) -> (Script, Vec<Hint>) {
+pub(crate) fn hinted_check_double(t: ark_bn254::G2Affine) -> (Script, Vec<Hint>) {
     let mut hints = vec![];
 
     let t_is_zero = t.is_zero() || (t == ark_bn254::G2Affine::new_unchecked(ark_bn254::Fq2::ZERO, ark_bn254::Fq2::ZERO)); // t is none or Some(0)
@@ -128,7 +128,7 @@ fn hinted_check_double(t: ark_bn254::G2Affine) -> (Script, Vec<Hint>) {
     (script, hints)
 }
 
-fn hinted_check_add(t: ark_bn254::G2Affine, q: ark_bn254::G2Affine) -> (Script, Vec<Hint>) {
+pub(crate) fn hinted_check_add(t: ark_bn254::G2Affine, q: ark_bn254::G2Affine) -> (Script, Vec<Hint>) {
     let mut hints = vec![];
 
     let t_is_zero = t.is_zero() || (t == ark_bn254::G2Affine::new_unchecked(ark_bn254::Fq2::ZERO, ark_bn254::Fq2::ZERO)); // t is none or Some(0)
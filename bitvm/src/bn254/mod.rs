@@ -8,6 +8,7 @@ pub mod g2;
 pub(crate) mod g2_sg_check;
 
 pub mod pairing;
+pub mod stack;
 
 pub mod ell_coeffs;
 
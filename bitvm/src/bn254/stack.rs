@@ -0,0 +1,433 @@
+use crate::bn254::fp254impl::Fp254Impl;
+use crate::bn254::fq::Fq;
+use crate::bn254::fq12::Fq12;
+use crate::bn254::fq2::Fq2;
+use crate::bn254::fq6::Fq6;
+use crate::bn254::g1::G1Affine;
+use crate::bn254::g2::G2Affine;
+use crate::bn254::g2_sg_check;
+use crate::bn254::utils::Hint;
+use crate::treepp::{script, Script};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{BitIteratorBE, PrimeField};
+use bitcoin_script_stack::stack::{StackTracker, StackVariable};
+
+/*
+    Field and curve arithmetic over named stack variables instead of depths.
+    Each operation moves its inputs to the top of the stack in order and wraps the script of the depth based API
+    (Fq::*, Fq2::*, ...) called for those positions, so the emitted scripts are the ones of the underlying functions
+    after the moves. The hints of the hinted operations are collected in the order they are consumed, and should be
+    pushed before the script as for the underlying functions.
+*/
+
+/// The value of a variable, kept to calculate the hints and the results of the operations
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bn254Value {
+    Fq(ark_bn254::Fq),
+    Fq2(ark_bn254::Fq2),
+    Fq6(ark_bn254::Fq6),
+    Fq12(ark_bn254::Fq12),
+    G1(ark_bn254::G1Affine),
+    G2(ark_bn254::G2Affine),
+}
+
+impl Bn254Value {
+    /// Number of stack elements of the value
+    pub fn n_elements(&self) -> u32 {
+        Fq::N_LIMBS
+            * match self {
+                Bn254Value::Fq(_) => 1,
+                Bn254Value::Fq2(_) | Bn254Value::G1(_) => 2,
+                Bn254Value::Fq6(_) => 6,
+                Bn254Value::Fq12(_) => 12,
+                Bn254Value::G2(_) => 4,
+            }
+    }
+
+    pub fn push(&self) -> Script {
+        match self {
+            Bn254Value::Fq(a) => Fq::push(*a),
+            Bn254Value::Fq2(a) => Fq2::push(*a),
+            Bn254Value::Fq6(a) => Fq6::push(*a),
+            Bn254Value::Fq12(a) => Fq12::push(*a),
+            Bn254Value::G1(a) => G1Affine::push(*a),
+            Bn254Value::G2(a) => G2Affine::push(*a),
+        }
+    }
+}
+
+/// A value of the curve on the stack
+#[derive(Clone, Copy)]
+pub struct Bn254Var {
+    pub var: StackVariable,
+    pub value: Bn254Value,
+}
+
+/// Stack of the field and curve values, addressed by their variables
+/// The operations consume their inputs, `copy` keeps a value for later use.
+pub struct Bn254Stack {
+    pub stack: StackTracker,
+    hints: Vec<Hint>,
+}
+
+impl Default for Bn254Stack {
+    fn default() -> Self { Self::new() }
+}
+
+impl Bn254Stack {
+    pub fn new() -> Self {
+        Self {
+            stack: StackTracker::new(),
+            hints: vec![],
+        }
+    }
+
+    /// Declares a value already on the stack, e.g. given in the witness, the first one declared being the deepest
+    pub fn define(&mut self, value: Bn254Value, name: &str) -> Bn254Var {
+        let var = self.stack.define(value.n_elements(), name);
+        Bn254Var { var, value }
+    }
+
+    pub fn push(&mut self, value: Bn254Value, name: &str) -> Bn254Var {
+        let var = self.stack.var(value.n_elements(), value.push(), name);
+        Bn254Var { var, value }
+    }
+
+    pub fn copy(&mut self, a: &Bn254Var) -> Bn254Var {
+        let var = self.stack.copy_var(a.var);
+        Bn254Var { var, value: a.value }
+    }
+
+    pub fn drop(&mut self, a: Bn254Var) { self.stack.drop(a.var); }
+
+    /// Moves the inputs to the top of the stack in order and runs the script of the depth based API on them
+    /// The last input is at depth 0 for the script, which should consume the inputs and leave a value of the type of
+    /// the result.
+    pub fn apply(&mut self, inputs: &[Bn254Var], script: Script, hints: Vec<Hint>, result: Bn254Value, name: &str) -> Bn254Var {
+        for input in inputs {
+            self.stack.move_var(input.var);
+        }
+        let var = self.stack.custom_ex(script, inputs.len() as u32, vec![(result.n_elements(), name.to_string())], 0)[0];
+        self.hints.extend(hints);
+        Bn254Var { var, value: result }
+    }
+
+    /// Moves the inputs to the top of the stack in order and runs the script on them, which should consume them and
+    /// leave nothing, e.g. a verification
+    pub fn apply_verify(&mut self, inputs: &[Bn254Var], script: Script, hints: Vec<Hint>, name: &str) {
+        for input in inputs {
+            self.stack.move_var(input.var);
+        }
+        self.stack.custom(script, inputs.len() as u32, false, 0, name);
+        self.hints.extend(hints);
+    }
+
+    /// a + b, for field elements or points
+    /// The points should be distinct and not opposite, `double` adds a point to itself.
+    pub fn add(&mut self, a: Bn254Var, b: Bn254Var) -> Bn254Var {
+        let ((script, hints), result) = match (a.value, b.value) {
+            (Bn254Value::Fq(x), Bn254Value::Fq(y)) => ((Fq::add(1, 0), vec![]), Bn254Value::Fq(x + y)),
+            (Bn254Value::Fq2(x), Bn254Value::Fq2(y)) => ((Fq2::add(2, 0), vec![]), Bn254Value::Fq2(x + y)),
+            (Bn254Value::Fq6(x), Bn254Value::Fq6(y)) => ((Fq6::add(6, 0), vec![]), Bn254Value::Fq6(x + y)),
+            (Bn254Value::Fq12(x), Bn254Value::Fq12(y)) => ((Fq12::add(12, 0), vec![]), Bn254Value::Fq12(x + y)),
+            (Bn254Value::G1(p), Bn254Value::G1(q)) => {
+                assert!(p.x != q.x || p.is_zero() || q.is_zero(), "the points should be distinct and not opposite");
+                (G1Affine::hinted_check_add(p, q), Bn254Value::G1((p + q).into_affine()))
+            }
+            (Bn254Value::G2(p), Bn254Value::G2(q)) => {
+                assert!(p.x != q.x || p.is_zero() || q.is_zero(), "the points should be distinct and not opposite");
+                (g2_sg_check::hinted_check_add(p, q), Bn254Value::G2((p + q).into_affine()))
+            }
+            _ => panic!("the addition is defined for values of the same type"),
+        };
+        self.apply(&[a, b], script, hints, result, "add")
+    }
+
+    /// a - b
+    pub fn sub(&mut self, a: Bn254Var, b: Bn254Var) -> Bn254Var {
+        let (script, result) = match (a.value, b.value) {
+            (Bn254Value::Fq(x), Bn254Value::Fq(y)) => (Fq::sub(1, 0), Bn254Value::Fq(x - y)),
+            (Bn254Value::Fq2(x), Bn254Value::Fq2(y)) => (Fq2::sub(2, 0), Bn254Value::Fq2(x - y)),
+            (Bn254Value::Fq6(x), Bn254Value::Fq6(y)) => (Fq6::sub(6, 0), Bn254Value::Fq6(x - y)),
+            (Bn254Value::Fq12(x), Bn254Value::Fq12(y)) => (Fq12::sub(12, 0), Bn254Value::Fq12(x - y)),
+            _ => panic!("the subtraction is defined for field elements of the same type"),
+        };
+        self.apply(&[a, b], script, vec![], result, "sub")
+    }
+
+    /// 2a, for field elements or points
+    pub fn double(&mut self, a: Bn254Var) -> Bn254Var {
+        let ((script, hints), result) = match a.value {
+            Bn254Value::Fq(x) => ((Fq::double(0), vec![]), Bn254Value::Fq(x + x)),
+            Bn254Value::Fq2(x) => ((Fq2::double(0), vec![]), Bn254Value::Fq2(x + x)),
+            Bn254Value::Fq6(x) => ((Fq6::double(0), vec![]), Bn254Value::Fq6(x + x)),
+            Bn254Value::Fq12(x) => ((Fq12::double(0), vec![]), Bn254Value::Fq12(x + x)),
+            Bn254Value::G1(p) => (G1Affine::hinted_check_double(p), Bn254Value::G1((p + p).into_affine())),
+            Bn254Value::G2(p) => (g2_sg_check::hinted_check_double(p), Bn254Value::G2((p + p).into_affine())),
+        };
+        self.apply(&[a], script, hints, result, "double")
+    }
+
+    /// k * p for the point p and the constant scalar k, by doubling and adding over the bits of k
+    pub fn scalar_mul(&mut self, p: Bn254Var, k: ark_bn254::Fr) -> Bn254Var {
+        let zero = match p.value {
+            Bn254Value::G1(_) => Bn254Value::G1(ark_bn254::G1Affine::zero()),
+            Bn254Value::G2(_) => Bn254Value::G2(ark_bn254::G2Affine::zero()),
+            _ => panic!("the scalar multiplication is defined for points"),
+        };
+        let mut bits = BitIteratorBE::without_leading_zeros(k.into_bigint());
+        if bits.next().is_none() {
+            self.drop(p);
+            return self.push(zero, "scalar_mul");
+        }
+        // the accumulator is a multiple of p smaller than the order of the group, so it is not p, -p or zero when
+        // adding p to it
+        let mut acc = self.copy(&p);
+        for bit in bits {
+            acc = self.double(acc);
+            if bit {
+                let p_copy = self.copy(&p);
+                acc = self.add(acc, p_copy);
+            }
+        }
+        self.drop(p);
+        acc
+    }
+
+    pub fn neg(&mut self, a: Bn254Var) -> Bn254Var {
+        let (script, result) = match a.value {
+            Bn254Value::Fq(x) => (Fq::neg(0), Bn254Value::Fq(-x)),
+            Bn254Value::Fq2(x) => (Fq2::neg(0), Bn254Value::Fq2(-x)),
+            Bn254Value::Fq6(x) => (Fq6::neg(0), Bn254Value::Fq6(-x)),
+            Bn254Value::Fq12(x) => (
+                script! {
+                    { Fq6::neg(6) }
+                    { Fq6::neg(6) }
+                },
+                Bn254Value::Fq12(-x),
+            ),
+            _ => panic!("the negation is defined for field elements"),
+        };
+        self.apply(&[a], script, vec![], result, "neg")
+    }
+
+    pub fn mul(&mut self, a: Bn254Var, b: Bn254Var) -> Bn254Var {
+        let ((script, hints), result) = match (a.value, b.value) {
+            (Bn254Value::Fq(x), Bn254Value::Fq(y)) => (Fq::hinted_mul(1, x, 0, y), Bn254Value::Fq(x * y)),
+            (Bn254Value::Fq2(x), Bn254Value::Fq2(y)) => (Fq2::hinted_mul(2, x, 0, y), Bn254Value::Fq2(x * y)),
+            (Bn254Value::Fq6(x), Bn254Value::Fq6(y)) => (Fq6::hinted_mul(6, x, 0, y), Bn254Value::Fq6(x * y)),
+            (Bn254Value::Fq12(x), Bn254Value::Fq12(y)) => (Fq12::hinted_mul(12, x, 0, y), Bn254Value::Fq12(x * y)),
+            _ => panic!("the multiplication is defined for field elements of the same type"),
+        };
+        self.apply(&[a, b], script, hints, result, "mul")
+    }
+
+    pub fn square(&mut self, a: Bn254Var) -> Bn254Var {
+        let ((script, hints), result) = match a.value {
+            Bn254Value::Fq(x) => (Fq::hinted_square(x), Bn254Value::Fq(x * x)),
+            Bn254Value::Fq2(x) => (Fq2::hinted_square(x), Bn254Value::Fq2(x * x)),
+            Bn254Value::Fq6(x) => (Fq6::hinted_square(x), Bn254Value::Fq6(x * x)),
+            Bn254Value::Fq12(x) => (Fq12::hinted_square(x), Bn254Value::Fq12(x * x)),
+            _ => panic!("the squaring is defined for field elements"),
+        };
+        self.apply(&[a], script, hints, result, "square")
+    }
+
+    /// Verifies that the values are equal
+    pub fn equalverify(&mut self, a: Bn254Var, b: Bn254Var) {
+        let script = match (a.value, b.value) {
+            (Bn254Value::Fq(_), Bn254Value::Fq(_)) => Fq::equalverify(1, 0),
+            (Bn254Value::Fq2(_), Bn254Value::Fq2(_)) => Fq2::equalverify(),
+            (Bn254Value::Fq6(_), Bn254Value::Fq6(_)) => Fq6::equalverify(),
+            (Bn254Value::Fq12(_), Bn254Value::Fq12(_)) => Fq12::equalverify(),
+            (Bn254Value::G1(_), Bn254Value::G1(_)) => G1Affine::equalverify(),
+            (Bn254Value::G2(_), Bn254Value::G2(_)) => script! {
+                { G2Affine::equal() }
+                OP_VERIFY
+            },
+            _ => panic!("the values should be of the same type"),
+        };
+        self.apply_verify(&[a, b], script, vec![], "equalverify");
+    }
+
+    /// Checks that the point is on the curve, leaving the result as a boolean
+    pub fn is_on_curve(&mut self, p: Bn254Var) -> StackVariable {
+        let (script, hints) = match p.value {
+            Bn254Value::G1(p) => G1Affine::hinted_is_on_curve(p.x, p.y),
+            Bn254Value::G2(p) => G2Affine::hinted_is_on_curve(p.x, p.y),
+            _ => panic!("the value should be a point"),
+        };
+        self.stack.move_var(p.var);
+        let var = self.stack.custom_ex(script, 1, vec![(1, "is_on_curve".to_string())], 0)[0];
+        self.hints.extend(hints);
+        var
+    }
+
+    /// The hints consumed by the script, in order
+    pub fn hints(&self) -> &[Hint] { &self.hints }
+
+    /// Pushes the hints, to be run before the script
+    pub fn push_hints(&self) -> Script {
+        script! {
+            for hint in self.hints.iter() {
+                { hint.push() }
+            }
+        }
+    }
+
+    pub fn get_script(&self) -> Script { self.stack.get_script() }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::treepp::*;
+    use ark_ec::AffineRepr;
+    use ark_ff::{AdditiveGroup, Field};
+    use ark_std::UniformRand;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_bn254_stack_fq2_arithmetic() {
+        let mut prng: ChaCha20Rng = ChaCha20Rng::seed_from_u64(0);
+
+        for _ in 0..10 {
+            let a = ark_bn254::Fq2::rand(&mut prng);
+            let b = ark_bn254::Fq2::rand(&mut prng);
+            let c = ark_bn254::Fq2::rand(&mut prng);
+            let expected = (a * b + c).square() - a.double();
+
+            let mut stack = Bn254Stack::new();
+            let a = stack.push(Bn254Value::Fq2(a), "a");
+            let b = stack.push(Bn254Value::Fq2(b), "b");
+            let c = stack.push(Bn254Value::Fq2(c), "c");
+            let a_copy = stack.copy(&a);
+            let ab = stack.mul(a_copy, b);
+            let abc = stack.add(ab, c);
+            let squared = stack.square(abc);
+            let doubled = stack.double(a);
+            let result = stack.sub(squared, doubled);
+            assert_eq!(result.value, Bn254Value::Fq2(expected));
+
+            let expected = stack.push(Bn254Value::Fq2(expected), "expected");
+            stack.equalverify(result, expected);
+            stack.stack.op_true();
+
+            let script = script! {
+                { stack.push_hints() }
+                { stack.get_script() }
+            };
+            run(script);
+        }
+    }
+
+    #[test]
+    fn test_bn254_stack_fq6_square() {
+        let mut prng: ChaCha20Rng = ChaCha20Rng::seed_from_u64(2);
+
+        let a = ark_bn254::Fq6::rand(&mut prng);
+        let mut stack = Bn254Stack::new();
+        let a_var = stack.push(Bn254Value::Fq6(a), "a");
+        let squared = stack.square(a_var);
+        let expected = stack.push(Bn254Value::Fq6(a.square()), "expected");
+        stack.equalverify(squared, expected);
+        stack.stack.op_true();
+
+        let script = script! {
+            { stack.push_hints() }
+            { stack.get_script() }
+        };
+        run(script);
+    }
+
+    /// Runs 2 (p + q) + k p on the stack
+    fn check_point_arithmetic(p: Bn254Value, q: Bn254Value, k: ark_bn254::Fr, expected: Bn254Value) {
+        let mut stack = Bn254Stack::new();
+        let p_var = stack.push(p, "p");
+        let q_var = stack.push(q, "q");
+        let p_copy = stack.copy(&p_var);
+        let sum = stack.add(p_copy, q_var);
+        let doubled = stack.double(sum);
+        let multiple = stack.scalar_mul(p_var, k);
+        let result = stack.add(doubled, multiple);
+        assert_eq!(result.value, expected);
+
+        let expected = stack.push(expected, "expected");
+        stack.equalverify(result, expected);
+        stack.stack.op_true();
+
+        let script = script! {
+            { stack.push_hints() }
+            { stack.get_script() }
+        };
+        run(script);
+    }
+
+    #[test]
+    fn test_bn254_stack_g1_arithmetic() {
+        let mut prng: ChaCha20Rng = ChaCha20Rng::seed_from_u64(3);
+
+        let p = ark_bn254::G1Affine::rand(&mut prng);
+        let q = ark_bn254::G1Affine::rand(&mut prng);
+        for k in [ark_bn254::Fr::ZERO, ark_bn254::Fr::from(prng.gen::<u16>())] {
+            let expected = ((p + q).double() + p * k).into_affine();
+            check_point_arithmetic(Bn254Value::G1(p), Bn254Value::G1(q), k, Bn254Value::G1(expected));
+        }
+    }
+
+    #[test]
+    fn test_bn254_stack_g2_arithmetic() {
+        let mut prng: ChaCha20Rng = ChaCha20Rng::seed_from_u64(4);
+
+        let p = ark_bn254::G2Affine::rand(&mut prng);
+        let q = ark_bn254::G2Affine::rand(&mut prng);
+        for k in [ark_bn254::Fr::ZERO, ark_bn254::Fr::from(prng.gen::<u8>())] {
+            let expected = ((p + q).double() + p * k).into_affine();
+            check_point_arithmetic(Bn254Value::G2(p), Bn254Value::G2(q), k, Bn254Value::G2(expected));
+        }
+    }
+
+    #[test]
+    fn test_bn254_stack_fq12_and_points() {
+        let mut prng: ChaCha20Rng = ChaCha20Rng::seed_from_u64(1);
+
+        let a = ark_bn254::Fq12::rand(&mut prng);
+        let b = ark_bn254::Fq12::rand(&mut prng);
+        let p = ark_bn254::G1Affine::generator();
+        let q = ark_bn254::G2Affine::generator();
+
+        let mut stack = Bn254Stack::new();
+        // the inputs are given before the script, as in a witness
+        let a_var = stack.define(Bn254Value::Fq12(a), "a");
+        let b_var = stack.define(Bn254Value::Fq12(b), "b");
+        let p_var = stack.define(Bn254Value::G1(p), "p");
+        let q_var = stack.define(Bn254Value::G2(q), "q");
+
+        stack.is_on_curve(q_var);
+        stack.stack.to_altstack();
+        let p_copy = stack.copy(&p_var);
+        stack.is_on_curve(p_copy);
+        stack.stack.to_altstack();
+        let p_expected = stack.push(Bn254Value::G1(p), "p_expected");
+        stack.equalverify(p_var, p_expected);
+
+        let product = stack.mul(a_var, b_var);
+        let negated = stack.neg(product);
+        let expected = stack.push(Bn254Value::Fq12(-(a * b)), "expected");
+        stack.equalverify(negated, expected);
+        stack.stack.from_altstack();
+        stack.stack.from_altstack();
+        stack.stack.custom(script! { OP_BOOLAND }, 2, true, 0, "on_curve");
+
+        let script = script! {
+            { stack.push_hints() }
+            { Fq12::push(a) }
+            { Fq12::push(b) }
+            { G1Affine::push(p) }
+            { G2Affine::push(q) }
+            { stack.get_script() }
+        };
+        run(script);
+    }
+}
@@ -12,6 +12,8 @@ pub enum Hint {
     Fr(ark_bn254::Fr),
     BigIntegerTmulLC1(num_bigint::BigInt),
     BigIntegerTmulLC2(num_bigint::BigInt),
+    /// Limbs of a `BigIntImpl`, as returned by `BigIntImpl::u32_le_to_limbs`
+    BigIntLimbs(Vec<u32>),
 }
 
 impl Hint {
@@ -36,6 +38,11 @@ impl Hint {
             Hint::BigIntegerTmulLC2(a) => script! {
                 { T2::push_u32_le(&bigint_to_u32_limbs(a.clone(), T2::N_BITS)) }
             },
+            Hint::BigIntLimbs(limbs) => script! {
+                for limb in limbs {
+                    { *limb }
+                }
+            },
         }
     }
 }
@@ -0,0 +1,331 @@
+use crate::bigint::U256;
+use crate::hash::sha256::{sha256, sha256_80bytes};
+use crate::treepp::{script, Script};
+
+/// Length of a serialized block header in bytes
+pub const HEADER_LENGTH: u32 = 80;
+/// Offset of the previous block hash in the serialized header
+const PREV_BLOCKHASH_OFFSET: u32 = 4;
+/// Offset of the compact target (`bits`) in the serialized header
+const BITS_OFFSET: u32 = 72;
+
+/// Double sha256 of a serialized block header.
+/// Input: the 80 header bytes, first byte on top
+/// Output: the 32 bytes of the block hash in internal byte order (the reverse of the displayed
+/// hash), first byte on top
+pub fn block_hash() -> Script {
+    script! {
+        { sha256_80bytes() }
+        { sha256(32) }
+    }
+}
+
+/// Expand a compact target into a U256.
+/// Input: `[exponent, mantissa_2, mantissa_1, mantissa_0]`, i.e. the `bits` bytes of a header
+/// from the most significant one, `mantissa_0` on top
+/// Output: the target as U256
+/// Fails for negative targets and targets that don't fit in 256 bits.
+pub fn bits_to_target() -> Script {
+    script! {
+        // the sign bit of the mantissa must be unset
+        2 OP_PICK
+        0x80
+        OP_LESSTHAN
+        OP_VERIFY
+        // the mantissa must not be shifted beyond 256 bits
+        3 OP_PICK
+        33
+        OP_LESSTHAN
+        OP_VERIFY
+
+        // target byte k, starting from the least significant one, is the mantissa byte at
+        // depth exponent - k: 3 for mantissa_0, 2 for mantissa_1 and 1 for mantissa_2
+        for k in 0..32 {
+            3 OP_PICK
+            { k }
+            OP_SUB
+            OP_DUP
+            3
+            OP_EQUAL
+            OP_IF
+                OP_DROP
+                OP_DUP
+            OP_ELSE
+                OP_DUP
+                2
+                OP_EQUAL
+                OP_IF
+                    OP_DROP
+                    OP_OVER
+                OP_ELSE
+                    1
+                    OP_EQUAL
+                    OP_IF
+                        2 OP_PICK
+                    OP_ELSE
+                        0
+                    OP_ENDIF
+                OP_ENDIF
+            OP_ENDIF
+            OP_TOALTSTACK
+        }
+        OP_2DROP
+        OP_2DROP
+
+        for _ in 0..32 {
+            OP_FROMALTSTACK
+        }
+        { U256::transform_limbsize(8, 29) }
+    }
+}
+
+/// Verify the proof of work of a block header, i.e. that its hash doesn't exceed the target
+/// encoded in its `bits` field.
+/// Input: the 80 header bytes, first byte on top
+/// Output: the block hash as in `block_hash`
+/// The header bytes are assumed to be in the byte range, as they are hashed.
+pub fn verify_header_pow() -> Script {
+    script! {
+        // copy the bits field, exponent first
+        for _ in 0..4 {
+            { BITS_OFFSET + 3 }
+            OP_PICK
+        }
+        for _ in 0..4 {
+            OP_TOALTSTACK
+        }
+
+        { block_hash() }
+
+        // the hash is a little endian number: copy it with its last byte deepest
+        for _ in 0..32 {
+            31
+            OP_PICK
+        }
+        { U256::transform_limbsize(8, 29) }
+
+        for _ in 0..4 {
+            OP_FROMALTSTACK
+        }
+        { bits_to_target() }
+
+        { U256::lessthanorequal(1, 0) }
+        OP_VERIFY
+    }
+}
+
+/// Verify that a block header extends the given block hash and has a valid proof of work.
+/// Input: `[prev_hash, header]`, the 32 bytes of the previous block hash (as output by
+/// `block_hash`) below the 80 header bytes, first byte on top
+/// Output: the block hash of the header
+pub fn verify_header_link() -> Script {
+    script! {
+        // compare the prev_blockhash field with the given hash and consume it
+        for i in 0..32 {
+            { PREV_BLOCKHASH_OFFSET + i }
+            OP_PICK
+            { HEADER_LENGTH + 1 }
+            OP_ROLL
+            OP_EQUALVERIFY
+        }
+        { verify_header_pow() }
+    }
+}
+
+/// Verify a chain of `n` consecutive block headers, each one with a valid proof of work.
+/// Input: `n` headers of 80 bytes each, the first block of the chain on top
+/// Output: the block hash of the last header
+/// The pending headers stay on the stack while the previous one is hashed, so the chain quickly
+/// exceeds the stack limit. In a tapscript the chain has to be split with
+/// `verify_header_link`, committing to the intermediate block hashes.
+pub fn verify_header_chain(n: u32) -> Script {
+    assert!(n > 0, "the chain must contain at least one header");
+    script! {
+        { verify_header_pow() }
+        for _ in 1..n {
+            // move the next header above the hash of the previous one
+            for _ in 0..HEADER_LENGTH {
+                { HEADER_LENGTH + 32 - 1 }
+                OP_ROLL
+            }
+            { verify_header_link() }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::blake3::push_bytes_hex;
+    use crate::{execute_script, execute_script_without_stack_limit};
+    use bitcoin::block::Header;
+    use bitcoin::consensus::deserialize;
+    use bitcoin::hashes::Hash;
+
+    const GENESIS: &str = "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c";
+    const BLOCK_1: &str = "010000006fe28c0ab6f1b372c1a6a246ae63f74f931e8365e15a089c68d6190000000000982051fd1e4ba744bbbe680e1fee14677ba1a3c3540bf7b1cdb606e857233e0e61bc6649ffff001d01e36299";
+    const BLOCK_2: &str = "010000004860eb18bf1b1620e37e9490fc8a427514416fd75159ab86688e9a8300000000d5fdcc541e25de1c7a5addedf24858b8bb665c9f36ef744ee42c316022c90f9bb0bc6649ffff001d08d2bd61";
+    // the header returned by `find_superblock` in the bridge
+    const SUPERBLOCK: &str = "00200d205da1f432acbd7894510d904daa31ba391ef2075b9f7c0200000000000000000019ed348650ca2aaceacfd5daf2068b54f394057be07cba562741204fd5b06400794a1267cd0e03175c340e40";
+
+    fn hash_hex(header_hex: &str) -> String {
+        let header: Header = deserialize(&hex::decode(header_hex).unwrap()).unwrap();
+        hex::encode(header.block_hash().to_byte_array())
+    }
+
+    fn check_hash(expected_hex: &str) -> Script {
+        script! {
+            { push_bytes_hex(expected_hex) }
+            for i in 0..32 {
+                { 32 - i }
+                OP_ROLL
+                OP_EQUALVERIFY
+            }
+        }
+    }
+
+    #[test]
+    fn test_block_hash() {
+        println!("block_hash: {} bytes", block_hash().len());
+        for header in [GENESIS, BLOCK_1, SUPERBLOCK] {
+            let script = script! {
+                { push_bytes_hex(header) }
+                { block_hash() }
+                { check_hash(&hash_hex(header)) }
+                OP_TRUE
+            };
+            assert!(execute_script(script).success);
+        }
+    }
+
+    #[test]
+    fn test_bits_to_target() {
+        println!("bits_to_target: {} bytes", bits_to_target().len());
+        for (bits, target) in [
+            (
+                [0x1d, 0x00, 0xff, 0xff],
+                "ffff0000000000000000000000000000000000000000000000000000",
+            ),
+            (
+                [0x17, 0x03, 0x0e, 0xcd],
+                "30ecd0000000000000000000000000000000000000000",
+            ),
+            ([0x03, 0x12, 0x34, 0x56], "123456"),
+            ([0x02, 0x12, 0x34, 0x56], "1234"),
+            ([0x01, 0x12, 0x34, 0x56], "12"),
+            (
+                [0x20, 0x7f, 0xff, 0xff],
+                "7fffff0000000000000000000000000000000000000000000000000000000000",
+            ),
+        ] {
+            let script = script! {
+                for byte in bits {
+                    { byte }
+                }
+                { bits_to_target() }
+                { U256::push_hex(target) }
+                { U256::equalverify(1, 0) }
+                OP_TRUE
+            };
+            assert!(execute_script(script).success, "bits {:02x?}", bits);
+        }
+    }
+
+    #[test]
+    fn test_bits_to_target_rejects_invalid() {
+        for bits in [[0x1d, 0x80, 0x00, 0x01], [0x21, 0x00, 0xff, 0xff]] {
+            let script = script! {
+                for byte in bits {
+                    { byte }
+                }
+                { bits_to_target() }
+                { U256::drop() }
+                OP_TRUE
+            };
+            assert!(!execute_script(script).success, "bits {:02x?}", bits);
+        }
+    }
+
+    #[test]
+    fn test_verify_header_pow() {
+        println!("verify_header_pow: {} bytes", verify_header_pow().len());
+        for header in [GENESIS, BLOCK_1, BLOCK_2, SUPERBLOCK] {
+            let script = script! {
+                { push_bytes_hex(header) }
+                { verify_header_pow() }
+                { check_hash(&hash_hex(header)) }
+                OP_TRUE
+            };
+            assert!(execute_script(script).success);
+        }
+    }
+
+    #[test]
+    fn test_verify_header_pow_wrong_nonce() {
+        let mut header = hex::decode(GENESIS).unwrap();
+        header[79] ^= 1;
+        let script = script! {
+            { push_bytes_hex(&hex::encode(&header)) }
+            { verify_header_pow() }
+            for _ in 0..16 {
+                OP_2DROP
+            }
+            OP_TRUE
+        };
+        assert!(!execute_script(script).success);
+    }
+
+    #[test]
+    fn test_verify_header_link() {
+        println!("verify_header_link: {} bytes", verify_header_link().len());
+        let script = script! {
+            { push_bytes_hex(&hash_hex(BLOCK_1)) }
+            { push_bytes_hex(BLOCK_2) }
+            { verify_header_link() }
+            { check_hash(&hash_hex(BLOCK_2)) }
+            OP_TRUE
+        };
+        assert!(execute_script(script).success);
+
+        // block 2 doesn't extend the genesis block
+        let script = script! {
+            { push_bytes_hex(&hash_hex(GENESIS)) }
+            { push_bytes_hex(BLOCK_2) }
+            { verify_header_link() }
+            for _ in 0..16 {
+                OP_2DROP
+            }
+            OP_TRUE
+        };
+        assert!(!execute_script(script).success);
+    }
+
+    #[test]
+    fn test_verify_header_chain() {
+        println!(
+            "verify_header_chain(3): {} bytes",
+            verify_header_chain(3).len()
+        );
+        let script = script! {
+            { push_bytes_hex(BLOCK_2) }
+            { push_bytes_hex(BLOCK_1) }
+            { push_bytes_hex(GENESIS) }
+            { verify_header_chain(3) }
+            { check_hash(&hash_hex(BLOCK_2)) }
+            OP_TRUE
+        };
+        assert!(execute_script_without_stack_limit(script).success);
+
+        // the headers are in the wrong order
+        let script = script! {
+            { push_bytes_hex(GENESIS) }
+            { push_bytes_hex(BLOCK_1) }
+            { verify_header_chain(2) }
+            for _ in 0..16 {
+                OP_2DROP
+            }
+            OP_TRUE
+        };
+        assert!(!execute_script_without_stack_limit(script).success);
+    }
+}
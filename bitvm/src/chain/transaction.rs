@@ -0,0 +1,490 @@
+use crate::bigint::amount;
+use crate::hash::sha256::sha256;
+use crate::hash::sha256_unified::{
+    bytes_to_nibbles, nibbles_to_bytes, sha256_stream_finalize, sha256_stream_update,
+    try_cheapest_sha256_backend,
+};
+use crate::treepp::{script, Script};
+use bitcoin::consensus::Encodable;
+use bitcoin::{Transaction, TxOut, VarInt};
+
+/// Length of a transaction version or lock time
+const U32_LENGTH: usize = 4;
+/// Length of an outpoint: txid and output index
+pub const OUTPOINT_LENGTH: usize = 36;
+/// Length of an input without its scriptSig: outpoint and sequence
+const INPUT_FIXED_LENGTH: usize = OUTPOINT_LENGTH + U32_LENGTH;
+/// Length of a sha256 block
+const SHA256_BLOCK_LENGTH: usize = 64;
+/// Number of nibbles of a sha256 midstate
+const SHA256_MIDSTATE_NIBBLES: usize = 64;
+
+/// Number of bytes of the compact size encoding of `value`
+pub fn compact_size_len(value: usize) -> usize {
+    VarInt(value as u64).size()
+}
+
+fn compact_size_bytes(value: usize) -> Vec<u8> {
+    let mut bytes = vec![];
+    VarInt(value as u64).consensus_encode(&mut bytes).unwrap();
+    bytes
+}
+
+/// Fail unless the compact size whose first byte is at `depth` encodes `value`
+pub fn verify_compact_size(depth: usize, value: usize) -> Script {
+    script! {
+        for (i, byte) in compact_size_bytes(value).into_iter().enumerate() {
+            { depth + i }
+            OP_PICK
+            { byte as u32 }
+            OP_EQUALVERIFY
+        }
+    }
+}
+
+/// Decode a compact size of `n_bytes` bytes.
+/// Input: the bytes of the compact size, first byte on top
+/// Output: the decoded number
+/// Only one and three-byte encodings fit in a script number. Non-canonical encodings fail.
+pub fn decode_compact_size(n_bytes: usize) -> Script {
+    match n_bytes {
+        1 => script! {
+            OP_DUP 0 0xfd OP_WITHIN OP_VERIFY
+        },
+        3 => script! {
+            0xfd OP_EQUALVERIFY
+            OP_DUP 0 256 OP_WITHIN OP_VERIFY
+            OP_SWAP
+            OP_DUP 0 256 OP_WITHIN OP_VERIFY
+            for _ in 0..8 {
+                OP_DUP OP_ADD
+            }
+            OP_ADD
+            // canonical encodings use a single byte below 0xfd
+            OP_DUP 0xfd OP_GREATERTHANOREQUAL OP_VERIFY
+        },
+        _ => panic!("compact size of {} bytes doesn't fit in a script number", n_bytes),
+    }
+}
+
+/// sha256 of a message too large to be hashed at once, one block at a time so that only the
+/// nibbles of the current block are on the stack next to the remaining bytes.
+/// Input and output are encoded as in `sha256::sha256`.
+fn sha256_streamed(num_bytes: usize) -> Script {
+    // keep at least one byte for the last block, which holds the padding
+    let num_blocks = (num_bytes - 1) / SHA256_BLOCK_LENGTH;
+    let tail_bytes = num_bytes - num_blocks * SHA256_BLOCK_LENGTH;
+    script! {
+        for block in 0..num_blocks {
+            if block > 0 {
+                for _ in 0..SHA256_MIDSTATE_NIBBLES {
+                    OP_TOALTSTACK
+                }
+            }
+            { bytes_to_nibbles(SHA256_BLOCK_LENGTH) }
+            if block > 0 {
+                for _ in 0..SHA256_MIDSTATE_NIBBLES {
+                    OP_FROMALTSTACK
+                }
+            }
+            { sha256_stream_update(1, block > 0) }
+        }
+        for _ in 0..SHA256_MIDSTATE_NIBBLES {
+            OP_TOALTSTACK
+        }
+        { bytes_to_nibbles(tail_bytes) }
+        for _ in 0..SHA256_MIDSTATE_NIBBLES {
+            OP_FROMALTSTACK
+        }
+        { sha256_stream_finalize(tail_bytes as u32, num_bytes as u32) }
+        { nibbles_to_bytes(32) }
+    }
+}
+
+/// Copy `len` bytes starting at `offset` of a byte string whose first byte is on top, keeping
+/// the first copied byte on top
+pub fn copy_bytes(offset: usize, len: usize) -> Script {
+    script! {
+        for _ in 0..len {
+            { offset + len - 1 }
+            OP_PICK
+        }
+    }
+}
+
+/// Shape of a transaction serialized without witness: the numbers of inputs and outputs and
+/// the lengths of their scripts.
+/// The gadgets are generated for a given shape, e.g. the one of a transaction template of a
+/// graph, while the content of the transaction is given in the witness. `verify_structure`
+/// checks that the transaction has this shape, so that the fields are read at the right
+/// offsets.
+///
+/// The transaction is expected on the stack as its serialized bytes, first byte on top.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxLayout {
+    pub script_sig_lengths: Vec<usize>,
+    pub script_pubkey_lengths: Vec<usize>,
+}
+
+impl TxLayout {
+    pub fn new(script_sig_lengths: Vec<usize>, script_pubkey_lengths: Vec<usize>) -> Self {
+        assert!(!script_sig_lengths.is_empty(), "a transaction has inputs");
+        Self {
+            script_sig_lengths,
+            script_pubkey_lengths,
+        }
+    }
+
+    pub fn from_transaction(tx: &Transaction) -> Self {
+        Self::new(
+            tx.input.iter().map(|input| input.script_sig.len()).collect(),
+            tx.output.iter().map(|output| output.script_pubkey.len()).collect(),
+        )
+    }
+
+    /// Offset of the outpoint of input `i`
+    pub fn input_offset(&self, i: usize) -> usize {
+        U32_LENGTH
+            + compact_size_len(self.script_sig_lengths.len())
+            + self.script_sig_lengths[..i]
+                .iter()
+                .map(|len| INPUT_FIXED_LENGTH + compact_size_len(*len) + len)
+                .sum::<usize>()
+    }
+
+    fn outputs_offset(&self) -> usize {
+        self.input_offset(self.script_sig_lengths.len())
+    }
+
+    /// Offset of the value of output `i`
+    pub fn output_offset(&self, i: usize) -> usize {
+        self.outputs_offset()
+            + compact_size_len(self.script_pubkey_lengths.len())
+            + self.script_pubkey_lengths[..i]
+                .iter()
+                .map(|len| amount::AMOUNT_LENGTH as usize + compact_size_len(*len) + len)
+                .sum::<usize>()
+    }
+
+    /// Offset of the scriptPubKey of output `i`, after its length
+    pub fn output_script_pubkey_offset(&self, i: usize) -> usize {
+        self.output_offset(i)
+            + amount::AMOUNT_LENGTH as usize
+            + compact_size_len(self.script_pubkey_lengths[i])
+    }
+
+    /// Length of the serialized transaction
+    pub fn size(&self) -> usize {
+        self.output_offset(self.script_pubkey_lengths.len()) + U32_LENGTH
+    }
+
+    /// Verify the compact sizes of the transaction, keeping it on the stack
+    pub fn verify_structure(&self) -> Script {
+        let mut compact_sizes = vec![(U32_LENGTH, self.script_sig_lengths.len())];
+        for (i, len) in self.script_sig_lengths.iter().enumerate() {
+            compact_sizes.push((self.input_offset(i) + OUTPOINT_LENGTH, *len));
+        }
+        compact_sizes.push((self.outputs_offset(), self.script_pubkey_lengths.len()));
+        for (i, len) in self.script_pubkey_lengths.iter().enumerate() {
+            compact_sizes.push((self.output_offset(i) + amount::AMOUNT_LENGTH as usize, *len));
+        }
+        script! {
+            for (depth, value) in compact_sizes {
+                { verify_compact_size(depth, value) }
+            }
+        }
+    }
+
+    /// Compute the txid, consuming the transaction.
+    /// Output: the 32 bytes of the txid in internal byte order (the reverse of the displayed
+    /// txid), first byte on top
+    pub fn txid(&self) -> Script {
+        let size = self.size();
+        let first_hash = match try_cheapest_sha256_backend(size) {
+            Some(backend) => backend.script(size),
+            None => sha256_streamed(size),
+        };
+        script! {
+            { first_hash }
+            { sha256(32) }
+        }
+    }
+
+    /// Copy the outpoint of input `i`: the 32 bytes of the txid and the 4 bytes of the output
+    /// index, first byte on top
+    pub fn copy_input_outpoint(&self, i: usize) -> Script {
+        copy_bytes(self.input_offset(i), OUTPOINT_LENGTH)
+    }
+
+    /// Copy the value of output `i` as a `U64`
+    pub fn copy_output_value(&self, i: usize) -> Script {
+        script! {
+            { copy_bytes(self.output_offset(i), amount::AMOUNT_LENGTH as usize) }
+            { amount::from_le_bytes() }
+        }
+    }
+
+    /// Copy the scriptPubKey of output `i`, first byte on top
+    pub fn copy_output_script_pubkey(&self, i: usize) -> Script {
+        copy_bytes(
+            self.output_script_pubkey_offset(i),
+            self.script_pubkey_lengths[i],
+        )
+    }
+
+    /// Fail unless output `i` is `expected`, i.e. pays its value to its scriptPubKey, keeping
+    /// the transaction on the stack
+    pub fn verify_output(&self, i: usize, expected: &TxOut) -> Script {
+        assert_eq!(
+            expected.script_pubkey.len(),
+            self.script_pubkey_lengths[i],
+            "the scriptPubKey doesn't match the layout"
+        );
+        let mut bytes = vec![];
+        expected.consensus_encode(&mut bytes).unwrap();
+        script! {
+            for (j, byte) in bytes.into_iter().enumerate() {
+                { self.output_offset(i) + j }
+                OP_PICK
+                { byte as u32 }
+                OP_EQUALVERIFY
+            }
+        }
+    }
+
+    /// Fail unless the `len` bytes at `offset` match the `len` bytes below the transaction,
+    /// e.g. a committed value, keeping both on the stack
+    pub fn verify_field(&self, offset: usize, len: usize) -> Script {
+        script! {
+            for i in 0..len {
+                { offset + i }
+                OP_PICK
+                { self.size() + i + 1 }
+                OP_PICK
+                OP_EQUALVERIFY
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bigint::U64;
+    use crate::execute_script;
+    use crate::hash::blake3::push_bytes_hex;
+    use bitcoin::consensus::serialize;
+    use bitcoin::hashes::Hash;
+    use bitcoin::{absolute, transaction, Amount, OutPoint, ScriptBuf, Sequence, TxIn, Txid};
+    use bitcoin::{WPubkeyHash, Witness};
+
+    fn peg_out_tx() -> Transaction {
+        Transaction {
+            version: transaction::Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint {
+                    txid: Txid::from_byte_array([0x11; 32]),
+                    vout: 3,
+                },
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::from_slice(&[[0x22; 64]]),
+            }],
+            output: vec![
+                TxOut {
+                    value: Amount::from_sat(1_234_567),
+                    script_pubkey: ScriptBuf::new_p2wpkh(&WPubkeyHash::from_byte_array(
+                        [0x33; 20],
+                    )),
+                },
+                TxOut {
+                    value: Amount::from_sat(330),
+                    script_pubkey: ScriptBuf::new_op_return([0x44; 32]),
+                },
+            ],
+        }
+    }
+
+    /// Serialization without witness, as hashed by the txid
+    fn stripped_hex(tx: &Transaction) -> String {
+        let mut tx = tx.clone();
+        tx.input.iter_mut().for_each(|input| input.witness.clear());
+        hex::encode(serialize(&tx))
+    }
+
+    fn drop_tx(layout: &TxLayout) -> Script {
+        script! {
+            for _ in 0..layout.size() {
+                OP_DROP
+            }
+        }
+    }
+
+    #[test]
+    fn test_layout() {
+        let tx = peg_out_tx();
+        let layout = TxLayout::from_transaction(&tx);
+        assert_eq!(layout.size() * 2, stripped_hex(&tx).len());
+        assert_eq!(layout.input_offset(0), 5);
+        assert_eq!(layout.output_offset(0), 47);
+        assert_eq!(layout.output_script_pubkey_offset(1), 47 + 31 + 9);
+    }
+
+    #[test]
+    fn test_decode_compact_size() {
+        for (value, success) in [(0, true), (0xfc, true), (0xfd, true), (0x1234, true)] {
+            let bytes = compact_size_bytes(value);
+            let script = script! {
+                { push_bytes_hex(&hex::encode(&bytes)) }
+                { decode_compact_size(bytes.len()) }
+                { value }
+                OP_EQUAL
+            };
+            assert_eq!(execute_script(script).success, success);
+        }
+
+        // 3 bytes for a value below 0xfd
+        let script = script! {
+            { push_bytes_hex("fd1000") }
+            { decode_compact_size(3) }
+            OP_DROP
+            OP_TRUE
+        };
+        assert!(!execute_script(script).success);
+    }
+
+    #[test]
+    fn test_txid() {
+        let tx = peg_out_tx();
+        let layout = TxLayout::from_transaction(&tx);
+        println!("TxLayout::txid: {} bytes", layout.txid().len());
+
+        let script = script! {
+            { push_bytes_hex(&stripped_hex(&tx)) }
+            { layout.verify_structure() }
+            { layout.txid() }
+            { push_bytes_hex(&hex::encode(tx.compute_txid().to_byte_array())) }
+            for i in 0..32 {
+                { 32 - i }
+                OP_ROLL
+                OP_EQUALVERIFY
+            }
+            OP_TRUE
+        };
+        assert!(execute_script(script).success);
+    }
+
+    #[test]
+    fn test_txid_streamed() {
+        let mut tx = peg_out_tx();
+        for i in 0..6 {
+            tx.output.push(TxOut {
+                value: Amount::from_sat(330),
+                script_pubkey: ScriptBuf::new_op_return([0x55 + i; 32]),
+            });
+        }
+        let layout = TxLayout::from_transaction(&tx);
+        assert!(layout.size() > 333);
+        assert!(try_cheapest_sha256_backend(layout.size()).is_none());
+
+        let script = script! {
+            { push_bytes_hex(&stripped_hex(&tx)) }
+            { layout.verify_structure() }
+            { layout.txid() }
+            { push_bytes_hex(&hex::encode(tx.compute_txid().to_byte_array())) }
+            for i in 0..32 {
+                { 32 - i }
+                OP_ROLL
+                OP_EQUALVERIFY
+            }
+            OP_TRUE
+        };
+        let res = execute_script(script);
+        assert!(res.success);
+        assert!(res.stats.max_nb_stack_items <= 1000);
+    }
+
+    #[test]
+    fn test_verify_structure_rejects_other_layout() {
+        let tx = peg_out_tx();
+        let mut other = tx.clone();
+        other.output[1].script_pubkey = ScriptBuf::new_op_return([0x44; 31]);
+        other.output.push(TxOut {
+            value: Amount::from_sat(1),
+            script_pubkey: ScriptBuf::new_op_return([0x44]),
+        });
+        let layout = TxLayout::from_transaction(&tx);
+        assert_ne!(layout, TxLayout::from_transaction(&other));
+
+        let script = script! {
+            { push_bytes_hex(&stripped_hex(&other)) }
+            { layout.verify_structure() }
+            OP_TRUE
+        };
+        assert!(!execute_script(script).success);
+    }
+
+    #[test]
+    fn test_fields() {
+        let tx = peg_out_tx();
+        let layout = TxLayout::from_transaction(&tx);
+
+        let script = script! {
+            { push_bytes_hex(&stripped_hex(&tx)) }
+            { layout.verify_output(0, &tx.output[0]) }
+            { layout.copy_output_value(0) }
+            { amount::push(1_234_567) }
+            { U64::equalverify(1, 0) }
+            { layout.copy_output_script_pubkey(0) }
+            { push_bytes_hex(&hex::encode(tx.output[0].script_pubkey.as_bytes())) }
+            for i in 0..22 {
+                { 22 - i }
+                OP_ROLL
+                OP_EQUALVERIFY
+            }
+            { layout.copy_input_outpoint(0) }
+            { push_bytes_hex(&hex::encode(serialize(&tx.input[0].previous_output))) }
+            for i in 0..36 {
+                { 36 - i }
+                OP_ROLL
+                OP_EQUALVERIFY
+            }
+            { drop_tx(&layout) }
+            OP_TRUE
+        };
+        assert!(execute_script(script).success);
+
+        // a different amount to the same address
+        let mut wrong = tx.output[0].clone();
+        wrong.value = Amount::from_sat(1_234_568);
+        let script = script! {
+            { push_bytes_hex(&stripped_hex(&tx)) }
+            { layout.verify_output(0, &wrong) }
+            { drop_tx(&layout) }
+            OP_TRUE
+        };
+        assert!(!execute_script(script).success);
+    }
+
+    #[test]
+    fn test_verify_field() {
+        let tx = peg_out_tx();
+        let layout = TxLayout::from_transaction(&tx);
+        let value_hex = hex::encode(1_234_567u64.to_le_bytes());
+
+        for (committed, success) in [(value_hex.as_str(), true), ("0000000000000001", false)] {
+            let script = script! {
+                { push_bytes_hex(committed) }
+                { push_bytes_hex(&stripped_hex(&tx)) }
+                { layout.verify_field(layout.output_offset(0), 8) }
+                { drop_tx(&layout) }
+                for _ in 0..4 {
+                    OP_2DROP
+                }
+                OP_TRUE
+            };
+            assert_eq!(execute_script(script).success, success);
+        }
+    }
+}
@@ -6,26 +6,55 @@ use super::{
     common::{self, *},
     disprove_execution::RawProof,
     elements::ElementTrait,
+    segment::Segment,
 };
 use crate::{
     signatures::signing_winternitz::{
+        check_batch_size, generate_winternitz_batch_checksig_leave_hash,
+        generate_winternitz_batch_checksig_leave_hashes, generate_winternitz_batch_witness,
         generate_winternitz_checksig_leave_hash, generate_winternitz_checksig_leave_variable,
-        generate_winternitz_witness, WinternitzPublicKey, WinternitzSecret,
+        generate_winternitz_witness, split_batch_messages, WinternitzBatchError,
+        WinternitzBatchSigningInputs, WinternitzPublicKey, WinternitzSecret,
         WinternitzSigningInputs,
     },
     execute_script_with_inputs,
     treepp::*,
 };
-use std::{collections::BTreeMap, rc::Rc};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    rc::Rc,
+};
 
 /// Implement `BCAssinger` to adapt with bridge.
 #[allow(clippy::borrowed_box)]
 pub trait BCAssigner: Default {
-    /// check hash
-    fn create_hash(&mut self, id: &str);
+    /// check hash, committed with `digest_length` bytes
+    fn create_hash(&mut self, id: &str, digest_length: usize);
+    /// digest length of each element type
+    fn digest_config(&self) -> DigestConfig {
+        DigestConfig::default()
+    }
     /// return a element of
     fn locking_script<T: ElementTrait + ?Sized>(&self, element: &Box<T>) -> Script;
     fn get_witness<T: ElementTrait + ?Sized>(&self, element: &Box<T>) -> RawWitness;
+    /// unlock the results of a segment, the last result first, and move them to the altstack
+    fn results_locking_script(&self, results: &[Rc<Box<dyn ElementTrait>>]) -> Script {
+        script! {
+            for result in results.iter().rev() {
+                {self.locking_script(result)}
+                for _ in 0..result.digest_length() {
+                    OP_TOALTSTACK
+                }
+            }
+        }
+    }
+    /// witness of the results of a segment, the first result first
+    fn results_witness(&self, results: &[Rc<Box<dyn ElementTrait>>]) -> RawWitness {
+        results
+            .iter()
+            .flat_map(|result| self.get_witness(result))
+            .collect()
+    }
     /// output sciprt for all elements, used by assert transaction
     fn all_intermediate_scripts(&self) -> Vec<Vec<Script>>;
     /// output witness for all elements, used by assert transaction
@@ -44,16 +73,31 @@ pub trait BCAssigner: Default {
 #[derive(Default)]
 pub struct DummyAssigner {
     bc_map: BTreeMap<String, String>,
+    digest_config: DigestConfig,
+}
+
+impl DummyAssigner {
+    pub fn with_digest_config(digest_config: DigestConfig) -> Self {
+        digest_config.validate();
+        Self {
+            bc_map: BTreeMap::new(),
+            digest_config,
+        }
+    }
 }
 
 impl BCAssigner for DummyAssigner {
-    fn create_hash(&mut self, id: &str) {
+    fn create_hash(&mut self, id: &str, _digest_length: usize) {
         if self.bc_map.contains_key(id) {
             panic!("variable name is repeated, check {}", id);
         }
         self.bc_map.insert(id.to_string(), id.to_string());
     }
 
+    fn digest_config(&self) -> DigestConfig {
+        self.digest_config
+    }
+
     fn locking_script<T: ElementTrait + ?Sized>(&self, _: &Box<T>) -> Script {
         script! {}
     }
@@ -127,6 +171,52 @@ pub struct BridgeAssigner {
     commits_publickeys: BTreeMap<String, WinternitzPublicKey>,
     is_operator: bool,
     recoverd_witness_store: BTreeMap<String, RawWitness>,
+    digest_config: DigestConfig,
+    batches: Vec<Vec<(String, usize)>>,
+}
+
+#[derive(Debug)]
+pub enum BatchError {
+    /// The variables can not be committed under the key of the batch
+    Winternitz(WinternitzBatchError),
+    /// The variable has no commitment key
+    UnknownVariable(String),
+    /// The element of the variable is not given
+    MissingElement(String),
+    /// Only the operator holds the secrets to sign a batch
+    NotOperator,
+    /// A batch commits at least one variable
+    EmptyBatch,
+}
+
+/// Group the results of each segment, in order, so that each group fits under a single batched
+/// Winternitz key. A disprove leaf unlocks the results of its segment with a single checksig,
+/// and a batched parameter only brings the results of the segment that computed it.
+/// A result alone in its group is committed on its own, as are the variables of the proof.
+pub fn intermediate_variable_batches(segments: &[Segment]) -> Vec<Vec<(String, usize)>> {
+    let mut batches: Vec<Vec<(String, usize)>> = vec![];
+    let mut batched: BTreeSet<String> = BTreeSet::new();
+    for segment in segments {
+        let mut batch: Vec<(String, usize)> = vec![];
+        let mut batch_size = 0;
+        for result in segment.result_list.iter() {
+            let name = result.id();
+            if common::PROOF_NAMES.contains(&name) || batched.contains(name) {
+                continue;
+            }
+            let size = variable_name_to_size(name, result.digest_length());
+            if check_batch_size(batch_size + size).is_err() {
+                batches.push(std::mem::take(&mut batch));
+                batch_size = 0;
+            }
+            batch.push((name.to_string(), size));
+            batch_size += size;
+            batched.insert(name.to_string());
+        }
+        batches.push(batch);
+    }
+    batches.retain(|batch| batch.len() > 1);
+    batches
 }
 
 impl BridgeAssigner {
@@ -140,6 +230,8 @@ impl BridgeAssigner {
             commits_secrets,
             is_operator: true,
             recoverd_witness_store: BTreeMap::new(),
+            digest_config: DigestConfig::default(),
+            batches: vec![],
         }
     }
 
@@ -154,24 +246,162 @@ impl BridgeAssigner {
             commits_publickeys,
             is_operator: false,
             recoverd_witness_store: BTreeMap::new(),
+            digest_config: DigestConfig::default(),
+            batches: vec![],
         }
     }
 
+    /// Commit the intermediate values with the digest lengths of `digest_config`.
+    /// The commitment secrets and public keys must be generated with the same configuration,
+    /// i.e. from the sizes returned by `all_intermediate_variables`.
+    pub fn with_digest_config(mut self, digest_config: DigestConfig) -> Self {
+        digest_config.validate();
+        self.digest_config = digest_config;
+        self
+    }
+
+    /// Commit each group of `batches` under a single Winternitz key with a shared checksum.
+    /// The key of a group is stored under the name of every variable of the group.
+    /// The witnesses to recover are then ordered as the variables committed on their own,
+    /// in name order, followed by the groups in the order of `batches`.
+    pub fn with_batches(mut self, batches: Vec<Vec<(String, usize)>>) -> Result<Self, BatchError> {
+        for batch in batches.iter() {
+            if batch.is_empty() {
+                return Err(BatchError::EmptyBatch);
+            }
+            let total_size = batch.iter().map(|(_, size)| size).sum();
+            check_batch_size(total_size).map_err(BatchError::Winternitz)?;
+            for (var_name, _) in batch.iter() {
+                let public_key = self
+                    .commits_publickeys
+                    .get(var_name)
+                    .ok_or_else(|| BatchError::UnknownVariable(var_name.clone()))?;
+                // the public key has two digits per committed byte
+                let key_size = public_key.parameters.message_digit_count() as usize / 2;
+                if key_size != total_size {
+                    return Err(BatchError::Winternitz(
+                        WinternitzBatchError::KeySizeMismatch {
+                            expected: key_size,
+                            actual: total_size,
+                        },
+                    ));
+                }
+            }
+        }
+        self.batches = batches;
+        Ok(self)
+    }
+
+    fn batch_of(&self, var_name: &str) -> Option<(&Vec<(String, usize)>, usize)> {
+        self.batches.iter().find_map(|batch| {
+            batch
+                .iter()
+                .position(|(name, _)| name == var_name)
+                .map(|message_index| (batch, message_index))
+        })
+    }
+
+    fn public_key(&self, var_name: &str) -> &WinternitzPublicKey {
+        self.commits_publickeys
+            .get(var_name)
+            .unwrap_or_else(|| panic!("{}/{} variables", var_name, self.commits_publickeys.len()))
+    }
+
+    /// Split the results of a segment into the batches they are committed in, in order.
+    /// A result which does not start a batch given in full is committed on its own.
+    fn result_groups<'a>(
+        &self,
+        results: &'a [Rc<Box<dyn ElementTrait>>],
+    ) -> Vec<(
+        Option<&Vec<(String, usize)>>,
+        &'a [Rc<Box<dyn ElementTrait>>],
+    )> {
+        let mut groups = vec![];
+        let mut index = 0;
+        while index < results.len() {
+            let batch = self
+                .batch_of(results[index].id())
+                .and_then(|(batch, position)| {
+                    let group = results.get(index..index + batch.len())?;
+                    let in_order = group
+                        .iter()
+                        .zip(batch.iter())
+                        .all(|(result, (name, _))| result.id() == name);
+                    (position == 0 && in_order).then_some(batch)
+                });
+            let group_length = batch.map_or(1, |batch| batch.len());
+            groups.push((batch, &results[index..index + group_length]));
+            index += group_length;
+        }
+        groups
+    }
+
+    /// Sign all the variables of a batch at once, the operator commits this witness for the batch.
+    pub fn get_batch_witness(
+        &self,
+        batch: &[String],
+        elements: &BTreeMap<String, Rc<Box<dyn ElementTrait>>>,
+    ) -> Result<RawWitness, BatchError> {
+        if !self.is_operator {
+            return Err(BatchError::NotOperator);
+        }
+        let var_name = batch.first().ok_or(BatchError::EmptyBatch)?;
+        let secret_key = self
+            .commits_secrets
+            .get(var_name)
+            .ok_or_else(|| BatchError::UnknownVariable(var_name.clone()))?;
+        let messages = batch
+            .iter()
+            .map(|name| {
+                elements
+                    .get(name)
+                    .and_then(|element| element.to_hash())
+                    .ok_or_else(|| BatchError::MissingElement(name.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let witness = generate_winternitz_batch_witness(&WinternitzBatchSigningInputs {
+            messages: &messages
+                .iter()
+                .map(|message| message.as_slice())
+                .collect::<Vec<_>>(),
+            signing_key: secret_key,
+        })
+        .map_err(BatchError::Winternitz)?;
+        Ok(witness.to_vec())
+    }
+
     pub fn all_intermediate_variables(&mut self) -> BTreeMap<String, usize> {
         let proof = RawProof::default();
         let _ = groth16_verify_to_segments(self, &proof.public, &proof.proof, &proof.vk);
         self.bc_map.clone()
     }
+
+    /// All intermediate variables, and the batches of `intermediate_variable_batches` to commit
+    /// them in. The chunker runs only once for both.
+    pub fn all_intermediate_variables_with_batches(
+        &mut self,
+    ) -> (BTreeMap<String, usize>, Vec<Vec<(String, usize)>>) {
+        let proof = RawProof::default();
+        let segments = groth16_verify_to_segments(self, &proof.public, &proof.proof, &proof.vk);
+        (
+            self.bc_map.clone(),
+            intermediate_variable_batches(&segments),
+        )
+    }
 }
 
 impl BCAssigner for BridgeAssigner {
-    fn create_hash(&mut self, id: &str) {
+    fn create_hash(&mut self, id: &str, digest_length: usize) {
         if self.bc_map.contains_key(id) {
             panic!("variable name is repeated, check {}", id);
         }
 
         self.bc_map
-            .insert(id.to_string(), variable_name_to_size(id));
+            .insert(id.to_string(), variable_name_to_size(id, digest_length));
+    }
+
+    fn digest_config(&self) -> DigestConfig {
+        self.digest_config
     }
 
     fn locking_script<T: ElementTrait + ?Sized>(&self, element: &Box<T>) -> Script {
@@ -181,18 +411,75 @@ impl BCAssigner for BridgeAssigner {
                 self.commits_publickeys.get(var_name).unwrap_or_else(|| {
                     panic!("{}/{} variables", var_name, self.commits_publickeys.len())
                 }),
-                variable_name_to_size(var_name),
+                variable_name_to_size(var_name, element.digest_length()),
+            )
+        } else if let Some((batch, message_index)) = self.batch_of(var_name) {
+            let message_sizes: Vec<usize> = batch.iter().map(|(_, size)| *size).collect();
+            generate_winternitz_batch_checksig_leave_hash(
+                self.public_key(var_name),
+                &message_sizes,
+                message_index,
             )
+            .unwrap_or_else(|err| panic!("invalid batch of {}: {:?}", var_name, err))
         } else {
             generate_winternitz_checksig_leave_hash(
                 self.commits_publickeys.get(var_name).unwrap_or_else(
                     || panic! {"{}/{} variables", var_name, self.commits_publickeys.len()},
                 ),
-                variable_name_to_size(var_name),
+                variable_name_to_size(var_name, element.digest_length()),
             )
         }
     }
 
+    fn results_locking_script(&self, results: &[Rc<Box<dyn ElementTrait>>]) -> Script {
+        let mut script = script! {};
+        for (batch, group) in self.result_groups(results).into_iter().rev() {
+            let group_script = match batch {
+                // a batch is unlocked once for all of its results, its messages are left in order
+                Some(batch) => {
+                    let message_sizes: Vec<usize> = batch.iter().map(|(_, size)| *size).collect();
+                    let checksig = generate_winternitz_batch_checksig_leave_hashes(
+                        self.public_key(&batch[0].0),
+                        &message_sizes,
+                    )
+                    .unwrap_or_else(|err| panic!("invalid batch of {}: {:?}", batch[0].0, err));
+                    let digest_length: usize =
+                        group.iter().map(|result| result.digest_length()).sum();
+                    script! {
+                        {checksig}
+                        for _ in 0..digest_length {
+                            OP_TOALTSTACK
+                        }
+                    }
+                }
+                None => script! {
+                    for result in group.iter().rev() {
+                        {self.locking_script(result)}
+                        for _ in 0..result.digest_length() {
+                            OP_TOALTSTACK
+                        }
+                    }
+                },
+            };
+            script = script.push_script(group_script.compile());
+        }
+        script
+    }
+
+    fn results_witness(&self, results: &[Rc<Box<dyn ElementTrait>>]) -> RawWitness {
+        self.result_groups(results)
+            .into_iter()
+            .flat_map(|(batch, group)| match batch {
+                // all the results of a batch share its witness
+                Some(_) => self.get_witness(&group[0]),
+                None => group
+                    .iter()
+                    .flat_map(|result| self.get_witness(result))
+                    .collect(),
+            })
+            .collect()
+    }
+
     fn get_witness<T: ElementTrait + ?Sized>(&self, element: &Box<T>) -> RawWitness {
         // for recover case
         if !self.is_operator {
@@ -204,6 +491,11 @@ impl BCAssigner for BridgeAssigner {
         }
 
         assert!(self.commits_secrets.contains_key(element.id()));
+        assert!(
+            self.batch_of(element.id()).is_none(),
+            "{} is committed in a batch, see get_batch_witness",
+            element.id()
+        );
         let secret_key = self.commits_secrets.get(element.id()).unwrap();
 
 
@@ -245,43 +537,59 @@ impl BCAssigner for BridgeAssigner {
             w.extend(x);
             w
         });
-        assert_eq!(flat_witnesses.len(), self.commits_publickeys.len());
+        let singles: Vec<String> = self
+            .commits_publickeys
+            .keys()
+            .filter(|var_name| self.batch_of(var_name).is_none())
+            .cloned()
+            .collect();
+        assert_eq!(flat_witnesses.len(), singles.len() + self.batches.len());
+        let (single_witnesses, batch_witnesses) = flat_witnesses.split_at(singles.len());
 
         self.recoverd_witness_store = BTreeMap::from_iter(
-            self.commits_publickeys
-                .clone()
-                .into_keys()
-                .collect_vec()
-                .into_iter()
-                .zip(flat_witnesses.clone()),
+            singles
+                .iter()
+                .cloned()
+                .zip(single_witnesses.iter().cloned()),
         );
 
         let mut raw_proof_recover = RawProofRecover::default();
-        for ((var_name, _pk), witness) in self.commits_publickeys.iter().zip(flat_witnesses) {
+        for (var_name, witness) in singles.iter().zip(single_witnesses.iter().cloned()) {
+            // the public key has two digits per committed byte
+            let size = self.public_key(var_name).parameters.message_digit_count() as usize / 2;
             // skip when the param is in proof
-            if common::PROOF_NAMES.contains(&&*var_name.clone()) {
-                let script = generate_winternitz_checksig_leave_variable(
-                    self.commits_publickeys.get(var_name).unwrap_or_else(|| {
-                        panic!("{}/{} variables", var_name, self.commits_publickeys.len())
-                    }),
-                    variable_name_to_size(var_name),
-                );
+            if common::PROOF_NAMES.contains(&var_name.as_str()) {
+                let script =
+                    generate_winternitz_checksig_leave_variable(self.public_key(var_name), size);
                 let witness_left =
                     extract_witness_from_stack(execute_script_with_inputs(script, witness));
-                raw_proof_recover.add_witness(&var_name.clone(), witness_left);
+                raw_proof_recover.add_witness(var_name, witness_left);
                 continue;
             }
-            let script = generate_winternitz_checksig_leave_hash(
-                self.commits_publickeys.get(var_name).unwrap_or_else(
-                    || panic! {"{}/{} variables", var_name, self.commits_publickeys.len()},
-                ),
-                variable_name_to_size(var_name),
-            );
+            let script = generate_winternitz_checksig_leave_hash(self.public_key(var_name), size);
             let witness_left =
                 extract_witness_from_stack(execute_script_with_inputs(script, witness));
             btree_map.insert(var_name.to_owned(), witness_to_array(witness_left));
         }
 
+        for (batch, witness) in self.batches.iter().zip(batch_witnesses.iter().cloned()) {
+            let message_sizes: Vec<usize> = batch.iter().map(|(_, size)| *size).collect();
+            let script = generate_winternitz_batch_checksig_leave_hashes(
+                self.public_key(&batch[0].0),
+                &message_sizes,
+            )
+            .unwrap_or_else(|err| panic!("invalid batch: {:?}", err));
+            let witness_left =
+                extract_witness_from_stack(execute_script_with_inputs(script, witness.clone()));
+            let messages = split_batch_messages(&witness_left, &message_sizes)
+                .unwrap_or_else(|err| panic!("invalid batch witness: {:?}", err));
+            for ((var_name, _), message) in batch.iter().zip(messages) {
+                btree_map.insert(var_name.to_owned(), message);
+                self.recoverd_witness_store
+                    .insert(var_name.to_owned(), witness.clone());
+            }
+        }
+
         // rebuild the raw proof
         let raw_proof = raw_proof_recover.to_raw_proof(vk).unwrap();
 
@@ -291,7 +599,8 @@ impl BCAssigner for BridgeAssigner {
 
 #[cfg(test)]
 mod tests {
-    use super::DummyAssigner;
+    use super::{intermediate_variable_batches, BCAssigner as _, DummyAssigner};
+    use crate::signatures::signing_winternitz::check_batch_size;
     use crate::chunker::common::witness_size;
     use crate::execute_script_with_inputs;
     use crate::treepp::script;
@@ -302,10 +611,12 @@ mod tests {
         },
         chunker::{
             assigner::BridgeAssigner,
+            chunk_groth16_verifier::groth16_verify_to_segments,
             disprove_execution::RawProof,
             elements::{ElementTrait as _, G2PointType},
         },
     };
+    use std::collections::BTreeMap;
 
     #[test]
     fn test_variable_names() {
@@ -313,6 +624,98 @@ mod tests {
         println!("variable_name: {}", variable_names.len());
     }
 
+    #[test]
+    fn test_intermediate_variable_batches() {
+        let mut assigner = BridgeAssigner::default();
+        let proof = RawProof::default();
+        let segments =
+            groth16_verify_to_segments(&mut assigner, &proof.public, &proof.proof, &proof.vk);
+        let batches = intermediate_variable_batches(&segments);
+
+        let batched: Vec<&String> = batches.iter().flatten().map(|(name, _)| name).collect();
+        for name in batched.iter() {
+            assert!(!crate::chunker::common::PROOF_NAMES.contains(&name.as_str()));
+            assert_eq!(batched.iter().filter(|other| other == &name).count(), 1);
+        }
+        for batch in batches.iter() {
+            assert!(batch.len() > 1);
+            let total_size = batch.iter().map(|(_, size)| size).sum();
+            assert!(check_batch_size(total_size).is_ok());
+            // a batch only holds results of the same segment
+            assert!(segments.iter().any(|segment| batch
+                .iter()
+                .all(|(name, _)| segment.result_list.iter().any(|result| result.id() == name))));
+        }
+    }
+
+    #[test]
+    fn test_batched_disprove_leaves_stack_size() {
+        let (variables, batches) =
+            BridgeAssigner::default().all_intermediate_variables_with_batches();
+
+        let mut secrets = BTreeMap::new();
+        for (name, size) in variables.iter() {
+            if batches.iter().flatten().all(|(batched, _)| batched != name) {
+                secrets.insert(name.clone(), WinternitzSecret::new(*size));
+            }
+        }
+        for batch in batches.iter() {
+            let message_sizes: Vec<usize> = batch.iter().map(|(_, size)| *size).collect();
+            let secret = WinternitzSecret::new_batch(&message_sizes).unwrap();
+            for (name, _) in batch.iter() {
+                secrets.insert(name.clone(), secret.clone());
+            }
+        }
+        let public_keys: BTreeMap<String, WinternitzPublicKey> = secrets
+            .iter()
+            .map(|(name, secret)| (name.clone(), secret.into()))
+            .collect();
+
+        // the operator commits the intermediate values of the proof
+        let mut operator = BridgeAssigner::new_operator(secrets)
+            .with_batches(batches.clone())
+            .unwrap();
+        let proof = RawProof::default();
+        let segments =
+            groth16_verify_to_segments(&mut operator, &proof.public, &proof.proof, &proof.vk);
+        let mut elements = BTreeMap::new();
+        for segment in segments.iter() {
+            for element in segment
+                .parameter_list
+                .iter()
+                .chain(segment.result_list.iter())
+            {
+                elements.insert(element.id().to_owned(), element.clone());
+            }
+        }
+        let mut witnesses = vec![];
+        for name in variables.keys() {
+            if batches.iter().flatten().all(|(batched, _)| batched != name) {
+                witnesses.push(operator.get_witness(elements.get(name).unwrap()));
+            }
+        }
+        for batch in batches.iter() {
+            let names: Vec<String> = batch.iter().map(|(name, _)| name.clone()).collect();
+            witnesses.push(operator.get_batch_witness(&names, &elements).unwrap());
+        }
+
+        // the watcher recovers them and runs every disprove leaf
+        let mut watcher = BridgeAssigner::new_watcher(public_keys)
+            .with_batches(batches)
+            .unwrap();
+        watcher.recover_from_witnesses(vec![witnesses], proof.vk.clone());
+        for segment in segments.iter() {
+            let res =
+                execute_script_with_inputs(segment.script(&watcher), segment.witness(&watcher));
+            assert!(
+                res.stats.max_nb_stack_items < 1000,
+                "{}: {} stack items",
+                segment.name,
+                res.stats.max_nb_stack_items
+            );
+        }
+    }
+
     #[test]
     fn test_commitment_size() {
         let mut dummy_assigner = DummyAssigner::default();
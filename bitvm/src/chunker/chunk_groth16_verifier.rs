@@ -161,12 +161,12 @@ mod test {
     }
 
     impl BCAssigner for StatisticAssinger {
-        fn create_hash(&mut self, id: &str) {
+        fn create_hash(&mut self, id: &str, digest_length: usize) {
             if self.commitments.contains_key(id) {
                 panic!("varible name is repeated, check {}", id);
             }
             self.commitments.insert(id.to_owned(), 1);
-            self.dummy_assigner.create_hash(id);
+            self.dummy_assigner.create_hash(id, digest_length);
         }
 
         fn locking_script<T: ElementTrait + ?Sized>(&self, element: &Box<T>) -> Script {
@@ -18,14 +18,117 @@ use regex::Regex;
 pub type RawWitness = Vec<Vec<u8>>;
 
 /// Should use u32 version's blake3 hash for fq element
-pub use crate::hash::blake3_u32::blake3_var_length;
+pub use crate::hash::blake3_u32::{blake3_var_length, blake3_var_length_truncated};
 
 use super::disprove_execution::RawProof;
 
-/// The depth of a blake3 hash, depending on the definition of `N_DIGEST_U32_LIMBS`
+/// The depth of a blake3 hash, depending on the definition of `N_DIGEST_U32_LIMBS`.
+/// This is the default digest length of the intermediate values.
 pub const BLAKE3_HASH_LENGTH: usize =
     crate::hash::blake3_u32::N_DIGEST_U32_LIMBS as usize * 4;
-pub type BLAKE3HASH = [u8; BLAKE3_HASH_LENGTH];
+/// The depth of an untruncated blake3 hash
+pub const MAX_DIGEST_LENGTH: usize = 32;
+/// A blake3 hash truncated to the digest length of its element
+pub type BLAKE3HASH = Vec<u8>;
+
+/// Digest length in bytes of the commitment to each element type.
+/// Every committed byte costs a Winternitz signature of two digits, so shorter digests make the
+/// assert transactions cheaper at the cost of collision resistance, see `DigestSecurity`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DigestConfig {
+    pub fq: usize,
+    pub fr: usize,
+    pub fq2: usize,
+    pub fq6: usize,
+    pub fq12: usize,
+    pub g1_point: usize,
+    pub g2_point: usize,
+}
+
+impl Default for DigestConfig {
+    fn default() -> Self {
+        Self::uniform(BLAKE3_HASH_LENGTH)
+    }
+}
+
+impl DigestConfig {
+    /// Use the same digest length for all element types
+    pub fn uniform(digest_length: usize) -> Self {
+        assert_digest_length(digest_length);
+        Self {
+            fq: digest_length,
+            fr: digest_length,
+            fq2: digest_length,
+            fq6: digest_length,
+            fq12: digest_length,
+            g1_point: digest_length,
+            g2_point: digest_length,
+        }
+    }
+
+    fn lengths(&self) -> [usize; 7] {
+        [
+            self.fq,
+            self.fr,
+            self.fq2,
+            self.fq6,
+            self.fq12,
+            self.g1_point,
+            self.g2_point,
+        ]
+    }
+
+    /// Panics if one of the digest lengths can't be produced by `blake3_var_length_truncated`
+    pub fn validate(&self) {
+        self.lengths().into_iter().for_each(assert_digest_length);
+    }
+
+    /// Security of the weakest commitment of the configuration
+    pub fn security(&self) -> DigestSecurity {
+        DigestSecurity::of(self.lengths().into_iter().min().unwrap())
+    }
+}
+
+/// Panics if `digest_length` is not a whole number of u32 limbs of a blake3 digest
+pub fn assert_digest_length(digest_length: usize) {
+    assert!(
+        digest_length > 0 && digest_length <= MAX_DIGEST_LENGTH && digest_length % 4 == 0,
+        "digest length {} must be a multiple of 4 bytes, up to {}",
+        digest_length,
+        MAX_DIGEST_LENGTH
+    );
+}
+
+/// Generic security of a blake3 digest truncated to `digest_length` bytes, in bits.
+///
+/// A disprove only works if the hash of an intermediate value binds the operator to it. An
+/// operator committing to a wrong value has to make it hash like a value the challenger can't
+/// dispute, which is a second preimage attack against the honest value. An operator preparing
+/// the wrong values offline, before committing, can instead search for any two values with the
+/// same digest, which is bounded by the birthday attack. Deployments should size the digests
+/// for the collision bound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DigestSecurity {
+    pub digest_length: usize,
+    pub collision_bits: usize,
+    pub second_preimage_bits: usize,
+}
+
+impl DigestSecurity {
+    pub fn of(digest_length: usize) -> Self {
+        Self {
+            digest_length,
+            collision_bits: digest_length * 8 / 2,
+            second_preimage_bits: digest_length * 8,
+        }
+    }
+}
+
+/// Hash the top `witness_size` u32 of the stack, leaving a digest of `digest_length` bytes
+pub fn hash_script(witness_size: usize, digest_length: usize) -> Script {
+    assert_digest_length(digest_length);
+    blake3_var_length_truncated(witness_size, digest_length as u32 / 4)
+}
 
 /// Commit the original proof, listing all the variable name of original proof.
 /// [proof.a, proof.b, proof.c, public_input0, public_input1, public_input2, public_input3]
@@ -42,11 +145,11 @@ pub const PROOF_NAMES: [&str; 10] = [
     "scalar_7",
 ];
 
-// count as bytes
-pub fn variable_name_to_size(id: &str) -> usize {
+// count as bytes, `digest_length` is the commitment size of a non-proof variable
+pub fn variable_name_to_size(id: &str, digest_length: usize) -> usize {
     // use hash for non-proof
     if !PROOF_NAMES.contains(&id) {
-        BLAKE3_HASH_LENGTH
+        digest_length
     // proof.a -> G1 point (Fq, Fq)
     } else if id == PROOF_NAMES[0] {
         Fq::N_LIMBS as usize * 4 * 2
@@ -175,8 +278,8 @@ pub fn not_equal(n: usize) -> Script {
 
 /// From witness to hash
 pub fn witness_to_array(witness: RawWitness) -> BLAKE3HASH {
-    assert_eq!(witness.len(), BLAKE3_HASH_LENGTH);
-    let mut res: BLAKE3HASH = [0; BLAKE3_HASH_LENGTH];
+    assert_digest_length(witness.len());
+    let mut res: BLAKE3HASH = vec![0; witness.len()];
     for (idx, byte) in witness.iter().enumerate() {
         if byte.is_empty() {
             res[idx] = 0;
@@ -106,7 +106,7 @@ pub fn disprove_exec<A: BCAssigner>(
                 // replace the result to hash_map
                 *result = Rc::new(Box::new(dummy_element(
                     result.id(),
-                    *hash_map.get(result.id()).unwrap(),
+                    hash_map.get(result.id()).unwrap().clone(),
                 )));
             }
         }
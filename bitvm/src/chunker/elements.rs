@@ -15,6 +15,7 @@ use std::fmt::Debug;
 pub struct FqElement {
     pub identity: String,
     pub size: usize,
+    pub digest_length: usize,
     pub witness_data: Option<RawWitness>,
     pub data: Option<DataType>,
 }
@@ -51,6 +52,8 @@ pub trait ElementTrait: Debug {
     fn to_hash(&self) -> Option<BLAKE3HASH>;
     /// Hash witness by blake3, return witness of Hash
     fn to_hash_witness(&self) -> Option<RawWitness>;
+    /// Length of the hash in bytes
+    fn digest_length(&self) -> usize;
     /// Size of element by Fq
     fn size(&self) -> usize;
     /// Witness size of element by u32
@@ -60,18 +63,21 @@ pub trait ElementTrait: Debug {
 }
 
 macro_rules! impl_element_trait {
-    ($element_type:ident, $data_type:ident, $size:expr, $push_method:expr) => {
+    ($element_type:ident, $data_type:ident, $size:expr, $push_method:expr, $digest_field:ident) => {
         #[derive(Clone, Debug)]
         pub struct $element_type(FqElement);
 
         impl $element_type {
             /// Create a new element by using bitcommitment assigner
+            /// The digest length is taken from the `DigestConfig` of the assigner
             pub fn new<F: BCAssigner>(assigner: &mut F, id: &str) -> Self {
-                assigner.create_hash(id);
+                let digest_length = assigner.digest_config().$digest_field;
+                assigner.create_hash(id, digest_length);
                 Self {
                     0: FqElement {
                         identity: id.to_owned(),
                         size: $size,
+                        digest_length,
                         witness_data: None,
                         data: None,
                     },
@@ -83,6 +89,7 @@ macro_rules! impl_element_trait {
                     0: FqElement {
                         identity: id.to_owned(),
                         size: $size,
+                        digest_length: BLAKE3_HASH_LENGTH,
                         witness_data: None,
                         data: None,
                     },
@@ -122,7 +129,7 @@ macro_rules! impl_element_trait {
                     Some(witness) => {
                         let res = execute_script_with_inputs(
                             script! {
-                                {blake3_var_length(self.0.witness_size())}
+                                {hash_script(self.0.witness_size(), self.0.digest_length)}
                             },
                             witness,
                         );
@@ -138,7 +145,7 @@ macro_rules! impl_element_trait {
                     Some(witness) => {
                         let res = execute_script_with_inputs(
                             script! {
-                                {blake3_var_length(self.0.witness_size())}
+                                {hash_script(self.0.witness_size(), self.0.digest_length)}
                             },
                             witness,
                         );
@@ -148,6 +155,10 @@ macro_rules! impl_element_trait {
                 }
             }
 
+            fn digest_length(&self) -> usize {
+                self.0.digest_length
+            }
+
             fn size(&self) -> usize {
                 self.0.size
             }
@@ -164,19 +175,19 @@ macro_rules! impl_element_trait {
 }
 
 // (Fq)
-impl_element_trait!(FqType, FqData, 1, Fq::push);
+impl_element_trait!(FqType, FqData, 1, Fq::push, fq);
 // (Fr)
-impl_element_trait!(FrType, FrData, 1, Fr::push);
+impl_element_trait!(FrType, FrData, 1, Fr::push, fr);
 // (Fq2)
-impl_element_trait!(Fq2Type, Fq2Data, 2, Fq2::push);
+impl_element_trait!(Fq2Type, Fq2Data, 2, Fq2::push, fq2);
 // (Fq6)
-impl_element_trait!(Fq6Type, Fq6Data, 6, Fq6::push);
+impl_element_trait!(Fq6Type, Fq6Data, 6, Fq6::push, fq6);
 // (Fq12)
-impl_element_trait!(Fq12Type, Fq12Data, 12, Fq12::push);
+impl_element_trait!(Fq12Type, Fq12Data, 12, Fq12::push, fq12);
 // (x: Fq, y: Fq)
-impl_element_trait!(G1PointType, G1PointData, 2, G1Affine::push);
+impl_element_trait!(G1PointType, G1PointData, 2, G1Affine::push, g1_point);
 // (x: Fq, y: Fq2)
-impl_element_trait!(G2PointType, G2PointData, 4, G2Affine::push);
+impl_element_trait!(G2PointType, G2PointData, 4, G2Affine::push, g2_point);
 
 #[derive(Debug, Clone)]
 pub struct DummyElement {
@@ -196,11 +207,15 @@ impl ElementTrait for DummyElement {
     }
 
     fn to_hash(&self) -> Option<BLAKE3HASH> {
-        Some(self.hash)
+        Some(self.hash.clone())
     }
 
     fn to_hash_witness(&self) -> Option<RawWitness> {
-        Some(array_to_witness(self.hash))
+        Some(array_to_witness(self.hash.clone()))
+    }
+
+    fn digest_length(&self) -> usize {
+        self.hash.len()
     }
 
     fn size(&self) -> usize {
@@ -79,12 +79,7 @@ impl Segment {
         let mut script = script! {
 
             // 1. unlock all bitcommitment
-            for result in self.result_list.iter().rev() {
-                {assigner.locking_script(result)}
-                for _ in 0..BLAKE3_HASH_LENGTH {
-                    OP_TOALTSTACK
-                }
-            }
+            {assigner.results_locking_script(&self.result_list)}
             for parameter in self.parameter_list.iter() {
                 {assigner.locking_script(parameter)} // verify bit commitment
                 // move all original data when verifying the proof
@@ -94,7 +89,7 @@ impl Segment {
                     }
                 }
                 else {
-                    for _ in 0..BLAKE3_HASH_LENGTH {
+                    for _ in 0..parameter.digest_length() {
                         OP_TOALTSTACK
                     }
                 }
@@ -103,6 +98,7 @@ impl Segment {
 
         for parameter in self.parameter_list.iter().rev() {
             let parameter_length = parameter.as_ref().witness_size();
+            let digest_length = parameter.digest_length();
 
             // skip hash when verifying the proof
             if common::PROOF_NAMES.contains(&parameter.id()) {
@@ -125,11 +121,11 @@ impl Segment {
                         for _ in 0..parameter_length {
                             {base + parameter_length - 1} OP_PICK
                         }
-                        {blake3_var_length(parameter_length)}
-                        for _ in 0..BLAKE3_HASH_LENGTH {
+                        {hash_script(parameter_length, digest_length)}
+                        for _ in 0..digest_length {
                             OP_FROMALTSTACK
                         }
-                        {equalverify(BLAKE3_HASH_LENGTH)}
+                        {equalverify(digest_length)}
                     }
                     .compile(),
                 );
@@ -138,6 +134,12 @@ impl Segment {
             base += parameter_length;
         }
 
+        let results_digest_length: usize = self
+            .result_list
+            .iter()
+            .map(|result| result.digest_length())
+            .sum();
+
         script = script.push_script(
             script! {
 
@@ -146,13 +148,13 @@ impl Segment {
 
                 // 4. result of blake3
                 for result in self.result_list.iter().rev() {
-                    {blake3_var_length(result.as_ref().witness_size())}
-                    for _ in 0..BLAKE3_HASH_LENGTH {
+                    {hash_script(result.as_ref().witness_size(), result.digest_length())}
+                    for _ in 0..result.digest_length() {
                         OP_TOALTSTACK
                     }
                 }
 
-                for _ in 0..BLAKE3_HASH_LENGTH * self.result_list.len() * 2 {
+                for _ in 0..results_digest_length * 2 {
                     OP_FROMALTSTACK
                 }
             }
@@ -163,7 +165,7 @@ impl Segment {
             script = script.push_script(
                 script! {
                 // 5. compare the result with assigned value
-                {common::not_equal(BLAKE3_HASH_LENGTH * self.result_list.len())}
+                {common::not_equal(results_digest_length)}
                 }
                 .compile(),
             );
@@ -193,9 +195,7 @@ impl Segment {
             witness.append(&mut assigner.get_witness(parameter));
         }
 
-        for result in self.result_list.iter() {
-            witness.append(&mut assigner.get_witness(result))
-        }
+        witness.append(&mut assigner.results_witness(&self.result_list));
 
         witness
     }
@@ -211,6 +211,7 @@ mod tests {
     use crate::chunker::elements::DataType::G1PointData;
     use crate::chunker::elements::DataType::G2PointData;
     use crate::chunker::elements::{ElementTrait, G1PointType, G2PointType};
+    use crate::chunker::common::DigestConfig;
     use crate::chunker::{assigner::DummyAssigner, elements::DataType::Fq6Data, elements::Fq6Type};
     use crate::{execute_script_with_inputs, treepp::*};
 
@@ -304,4 +305,39 @@ mod tests {
         println!("rse.remaining: {}", res.remaining_script);
         println!("res: {:1000}", res);
     }
+
+    #[test]
+    fn test_segment_digest_lengths() {
+        for digest_config in [
+            DigestConfig::uniform(16),
+            DigestConfig::uniform(32),
+            DigestConfig {
+                fq6: 24,
+                ..Default::default()
+            },
+        ] {
+            let mut assigner = DummyAssigner::with_digest_config(digest_config);
+
+            let mut a0 = Fq6Type::new(&mut assigner, "a0");
+            a0.fill_with_data(Fq6Data(ark_bn254::Fq6::from(1)));
+            assert_eq!(a0.to_hash().unwrap().len(), digest_config.fq6);
+
+            let mut a1 = Fq6Type::new(&mut assigner, "a1");
+            a1.fill_with_data(Fq6Data(ark_bn254::Fq6::from(2)));
+
+            // the correct result leaves OP_FALSE, a wrong one OP_TRUE
+            for (result, disproved) in [(&a0, false), (&a1, true)] {
+                let segment = Segment::new(script! {})
+                    .add_parameter(&a0)
+                    .add_result(result);
+
+                let res = execute_script_with_inputs(
+                    segment.script(&assigner),
+                    segment.witness(&assigner),
+                );
+                assert_eq!(res.final_stack.len(), 1);
+                assert_eq!(res.success, disproved, "{:?}", digest_config);
+            }
+        }
+    }
 }
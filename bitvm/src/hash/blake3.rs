@@ -1,6 +1,9 @@
 #![allow(non_snake_case)]
 use std::collections::HashMap;
 
+use crate::hash::blake3_mode::{
+    blake3_compressions, num_message_blocks, u32_arrange, Blake3Compression, Blake3Mode,
+};
 use crate::pseudo::push_to_stack;
 use crate::treepp::{script, Script};
 use crate::u32::u32_std::{u32_equalverify, u32_roll};
@@ -265,42 +268,48 @@ pub fn blake3() -> Script {
 }
 
 pub fn blake3_var_length(num_bytes: usize) -> Script {
+    blake3_var_length_with_mode(num_bytes, &Blake3Mode::Hash)
+}
+
+/// Push the whole state of a compression starting from `chaining_value`
+fn full_state(chaining_value: &[u32; 8], counter: u32, block_len: u32, flags: u32) -> Vec<Script> {
+    let mut state = [
+        chaining_value[0],
+        chaining_value[1],
+        chaining_value[2],
+        chaining_value[3],
+        chaining_value[4],
+        chaining_value[5],
+        chaining_value[6],
+        chaining_value[7],
+        IV[0],
+        IV[1],
+        IV[2],
+        IV[3],
+        counter,
+        0,
+        block_len,
+        flags,
+    ];
+    state.reverse();
+    state.iter().map(|x| u32_push(*x)).collect::<Vec<_>>()
+}
+
+/// Blake3 of a message of `num_bytes` bytes in the given mode, returning a 32-byte digest.
+/// Messages longer than a chunk (1024 bytes) are hashed as a tree, the chaining values waiting
+/// to be merged are kept on the altstack. The message bytes stay on the stack, so messages of
+/// more than a few hundred bytes exceed the stack limit.
+pub fn blake3_var_length_with_mode(num_bytes: usize, mode: &Blake3Mode) -> Script {
     //assert!(num_bytes <= 512,
     //"This blake3 implementation does not support input larger than 512 bytes due to stack limit. \
     //Please modify the hashing routine to avoid calling blake3 in this way.");
 
     // Compute how many padding elements are needed
-    let num_blocks = num_bytes.div_ceil(64);
+    let num_blocks = num_message_blocks(num_bytes as u32) as usize;
     let num_padding_bytes = num_blocks * 64 - num_bytes;
 
-    // Calculate the initial state
-    let first_block_flag = if num_bytes <= 64 {
-        0b00001011
-    } else {
-        0b00000001
-    };
-    let init_state = {
-        let mut state = [
-            IV[0],
-            IV[1],
-            IV[2],
-            IV[3],
-            IV[4],
-            IV[5],
-            IV[6],
-            IV[7],
-            IV[0],
-            IV[1],
-            IV[2],
-            IV[3],
-            0,
-            0,
-            core::cmp::min(num_bytes as u32, 64),
-            first_block_flag,
-        ];
-        state.reverse();
-        state.iter().map(|x| u32_push(*x)).collect::<Vec<_>>()
-    };
+    let key = mode.key_words();
+    let mode_flags = mode.flags();
 
     let mut env = ptr_init();
 
@@ -326,6 +335,56 @@ pub fn blake3_var_length(num_bytes: usize) -> Script {
         }
     };
 
+    let compressions = blake3_compressions(num_bytes as u32)
+        .into_iter()
+        .map(|compression| {
+            let (counter, block_len, flags) = compression.state_words(mode_flags);
+            let message = match compression {
+                // The message blocks are already below the lookup table
+                Blake3Compression::Block { .. } => script! {},
+                // The message of a parent node is the concatenation of the chaining values of
+                // its children, it has to go below the lookup table
+                Blake3Compression::Parent { .. } => script! {
+                    u8_drop_xor_table
+                    for _ in 0..16 {
+                        {u32_fromaltstack()}
+                    }
+                    // right child words 0..7, left child words 0..7 (top)
+                    { u32_arrange(
+                        &(8..16).chain(0..8).collect::<Vec<_>>(),
+                        &(0..16).rev().collect::<Vec<_>>(),
+                    ) }
+                    u8_push_xor_table
+                },
+            };
+            let state = if compression.is_chained() {
+                let state_add = {
+                    let mut state = [IV[0], IV[1], IV[2], IV[3], counter, 0, block_len, flags];
+                    state.reverse();
+                    state.iter().map(|x| u32_push(*x)).collect::<Vec<_>>()
+                };
+                script! {
+                    { state_add }
+                    for _ in 0..8 {
+                        {u32_fromaltstack()}
+                    }
+                    for i in 1..8 {
+                        {u32_roll(i)}
+                    }
+                }
+            } else {
+                script! {
+                    { full_state(&key, counter, block_len, flags) }
+                }
+            };
+            script! {
+                { message }
+                { state }
+                { compression_script.clone() }
+            }
+        })
+        .collect::<Vec<_>>();
+
     script! {
         // Add the padding
         { push_to_stack(0, num_padding_bytes) }
@@ -340,57 +399,8 @@ pub fn blake3_var_length(num_bytes: usize) -> Script {
         // Initialize the lookup table
         u8_push_xor_table
 
-        // Push the initial Blake3 state onto the stack
-        { init_state }
-
-        // Call compression function initially
-        { compression_script.clone() }
-
-        // Variable script for the rest of the blocks
-        // TODO: This is very ugly and can likely be improved by creating an iterator of num_bytes
-        // beforehand and getting the next value (num_bytes - 64) from it.
-        // By doing so we can get rid of the closure.
-        { (| num_bytes | {
-            let mut sub_script = script! {};
-            let mut num_bytes = num_bytes;
-            for i in 1..num_blocks {
-                num_bytes -= 64;
-
-                let block_flag = if i == num_blocks - 1 { 0b00001010 } else { 0 };
-
-                let state_add = {
-                    let mut state = [
-                        IV[0],
-                        IV[1],
-                        IV[2],
-                        IV[3],
-                        0,
-                        0,
-                        core::cmp::min(num_bytes as u32, 64),
-                        block_flag,
-                    ];
-                    state.reverse();
-                    state.iter().map(|x| u32_push(*x)).collect::<Vec<_>>()
-                };
-
-                sub_script = script! {
-                    { sub_script }
-                    { script! {
-                            { state_add }
-                            for _ in 0..8 {
-                                {u32_fromaltstack()}
-                            }
-                            for i in 1..8 {
-                                {u32_roll(i)}
-                            }
-                            {compression_script.clone()}
-                        }
-                    }
-                }
-            }
-            sub_script
-            })(num_bytes)
-        }
+        // Compress the blocks and merge the chunks
+        { compressions }
 
         u8_drop_xor_table
         for _ in 0..8 {
@@ -501,7 +511,8 @@ mod tests {
     use crate::hash::blake3::*;
     use crate::run;
 
-    use crate::treepp::{execute_script, script};
+    use crate::hash::blake3_mode::tests::{read_test_vectors, test_vector_input};
+    use crate::treepp::{execute_script, execute_script_without_stack_limit, script};
 
     #[test]
     fn test_permute() {
@@ -663,4 +674,54 @@ mod tests {
         println!("Blake3_160_var_length_{} size: {:?}, stack: {} \n", num_bytes, script.len(), res.stats.max_nb_stack_items);
         assert!(res.success);
     }
+
+    #[test]
+    fn test_blake3_var_length_modes() {
+        let test_vectors = read_test_vectors();
+        for case in test_vectors.cases.iter() {
+            if ![0, 1, 7, 63, 64, 65, 128, 129].contains(&case.input_len) {
+                continue;
+            }
+            let input = test_vector_input(case.input_len);
+            for (mode, expected) in test_vectors.modes(case) {
+                let script = script! {
+                    { push_bytes_hex(&hex::encode(&input)) }
+                    { blake3_var_length_with_mode(case.input_len, &mode) }
+                    { push_bytes_hex(expected) }
+                    { blake3_hash_equalverify() }
+                    OP_TRUE
+                };
+                let res = execute_script(script);
+                assert!(res.success, "{:?} failed for {} bytes", mode, case.input_len);
+            }
+        }
+    }
+
+    #[test]
+    fn test_blake3_var_length_multiple_chunks() {
+        // the message doesn't fit on the stack, this only checks the tree hashing
+        let test_vectors = read_test_vectors();
+        for case in test_vectors.cases.iter() {
+            if ![1025, 2049].contains(&case.input_len) {
+                continue;
+            }
+            let input = test_vector_input(case.input_len);
+            for (mode, expected) in test_vectors.modes(case) {
+                let script = script! {
+                    { push_bytes_hex(&hex::encode(&input)) }
+                    { blake3_var_length_with_mode(case.input_len, &mode) }
+                    { push_bytes_hex(expected) }
+                    { blake3_hash_equalverify() }
+                    OP_TRUE
+                };
+                println!(
+                    "blake3_var_length_with_mode({}): {} bytes",
+                    case.input_len,
+                    script.len()
+                );
+                let res = execute_script_without_stack_limit(script);
+                assert!(res.success, "{:?} failed for {} bytes", mode, case.input_len);
+            }
+        }
+    }
 }
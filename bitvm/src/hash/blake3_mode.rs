@@ -0,0 +1,370 @@
+use crate::treepp::{script, Script};
+use crate::u32::u32_std::u32_roll;
+
+/// Size of a message block in bytes
+pub const BLOCK_LEN: u32 = 64;
+/// Size of a chunk, the leaves of the BLAKE3 tree, in bytes
+pub const CHUNK_LEN: u32 = 1024;
+
+// Domain separation flags
+pub const CHUNK_START: u32 = 1 << 0;
+pub const CHUNK_END: u32 = 1 << 1;
+pub const PARENT: u32 = 1 << 2;
+pub const ROOT: u32 = 1 << 3;
+pub const KEYED_HASH: u32 = 1 << 4;
+pub const DERIVE_KEY_CONTEXT: u32 = 1 << 5;
+pub const DERIVE_KEY_MATERIAL: u32 = 1 << 6;
+
+const IV: [u32; 8] = [
+    0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A, 0x510E527F, 0x9B05688C, 0x1F83D9AB, 0x5BE0CD19,
+];
+
+const MSG_PERMUTATION: [usize; 16] = [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8];
+
+/// The hashing modes of BLAKE3. They only differ in the key used as chaining value at the start
+/// of every chunk and parent node, and in the flags added to every compression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Blake3Mode {
+    /// The default mode, as `blake3::hash`
+    Hash,
+    /// Keyed hashing with a 32-byte key, as `blake3::keyed_hash`
+    KeyedHash([u8; 32]),
+    /// Key derivation from a context string, as `blake3::derive_key`. The context string is
+    /// fixed when building the script, so its context key is computed off-chain.
+    DeriveKey(String),
+}
+
+impl Blake3Mode {
+    /// The key words replacing the IV as initial chaining value
+    pub fn key_words(&self) -> [u32; 8] {
+        match self {
+            Blake3Mode::Hash => IV,
+            Blake3Mode::KeyedHash(key) => words_from_le_bytes(key),
+            Blake3Mode::DeriveKey(context) => {
+                hash_words(context.as_bytes(), &IV, DERIVE_KEY_CONTEXT)
+            }
+        }
+    }
+
+    /// The flags added to every compression
+    pub fn flags(&self) -> u32 {
+        match self {
+            Blake3Mode::Hash => 0,
+            Blake3Mode::KeyedHash(_) => KEYED_HASH,
+            Blake3Mode::DeriveKey(_) => DERIVE_KEY_MATERIAL,
+        }
+    }
+
+    /// Off-chain digest of `input` in this mode
+    pub fn hash(&self, input: &[u8]) -> [u8; 32] {
+        match self {
+            Blake3Mode::Hash => *blake3::hash(input).as_bytes(),
+            Blake3Mode::KeyedHash(key) => *blake3::keyed_hash(key, input).as_bytes(),
+            Blake3Mode::DeriveKey(context) => blake3::derive_key(context, input),
+        }
+    }
+}
+
+/// A compression of the BLAKE3 tree, as performed by the script implementations. Every
+/// compression leaves its chaining value on the altstack, so that the altstack works as the
+/// chaining value stack of the reference implementation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Blake3Compression {
+    /// Compress a message block of a chunk, the chunk index is the counter. The first block of a
+    /// chunk starts from the key, the other ones from the chaining value on top of the altstack.
+    Block {
+        chunk: u32,
+        block: u32,
+        block_len: u32,
+        flags: u32,
+    },
+    /// Compress the two chaining values on top of the altstack, the right child on top, into
+    /// their parent node
+    Parent { flags: u32 },
+}
+
+impl Blake3Compression {
+    /// Index of the message block in the whole message, if this compresses a message block
+    pub fn message_block(&self) -> Option<u32> {
+        match *self {
+            Blake3Compression::Block { chunk, block, .. } => {
+                Some(chunk * (CHUNK_LEN / BLOCK_LEN) + block)
+            }
+            Blake3Compression::Parent { .. } => None,
+        }
+    }
+
+    /// Whether the compression starts from the chaining value of the previous block
+    pub fn is_chained(&self) -> bool {
+        matches!(*self, Blake3Compression::Block { block, .. } if block > 0)
+    }
+
+    /// The counter, block length and flags words of the compression state
+    pub fn state_words(&self, mode_flags: u32) -> (u32, u32, u32) {
+        match *self {
+            Blake3Compression::Block {
+                chunk,
+                block_len,
+                flags,
+                ..
+            } => (chunk, block_len, flags | mode_flags),
+            Blake3Compression::Parent { flags } => (0, BLOCK_LEN, flags | mode_flags),
+        }
+    }
+}
+
+/// Number of 64-byte blocks to hash a message of `msg_len` bytes, the empty message being
+/// hashed as one block of zeros
+pub fn num_message_blocks(msg_len: u32) -> u32 { msg_len.div_ceil(BLOCK_LEN).max(1) }
+
+/// The sequence of compressions to hash a message of `msg_len` bytes. Chunk chaining values are
+/// merged as soon as a subtree is complete, the remaining ones are merged into the root at the
+/// end.
+pub fn blake3_compressions(msg_len: u32) -> Vec<Blake3Compression> {
+    let num_chunks = msg_len.div_ceil(CHUNK_LEN).max(1);
+    let mut compressions = vec![];
+    for chunk in 0..num_chunks {
+        let chunk_len = (msg_len - chunk * CHUNK_LEN).min(CHUNK_LEN);
+        let num_blocks = num_message_blocks(chunk_len);
+        for block in 0..num_blocks {
+            let mut flags = 0;
+            if block == 0 {
+                flags |= CHUNK_START;
+            }
+            if block == num_blocks - 1 {
+                flags |= CHUNK_END;
+                if num_chunks == 1 {
+                    flags |= ROOT;
+                }
+            }
+            compressions.push(Blake3Compression::Block {
+                chunk,
+                block,
+                block_len: (chunk_len - block * BLOCK_LEN).min(BLOCK_LEN),
+                flags,
+            });
+        }
+        // merge the completed subtrees, but keep the last chunk for the final merges
+        if chunk < num_chunks - 1 {
+            let mut total_chunks = chunk + 1;
+            while total_chunks & 1 == 0 {
+                compressions.push(Blake3Compression::Parent { flags: PARENT });
+                total_chunks >>= 1;
+            }
+        }
+    }
+    let pending = (num_chunks - 1).count_ones();
+    for i in 0..pending {
+        let flags = if i == pending - 1 { PARENT | ROOT } else { PARENT };
+        compressions.push(Blake3Compression::Parent { flags });
+    }
+    compressions
+}
+
+/// Rearrange the top u32 values of the stack. `current` lists their labels from the deepest
+/// one, `target` the wanted order.
+pub fn u32_arrange(current: &[u32], target: &[u32]) -> Script {
+    assert_eq!(current.len(), target.len());
+    let mut current = current.to_vec();
+    let mut rolls = vec![];
+    for label in target {
+        let index = current.iter().position(|x| x == label).unwrap();
+        let depth = (current.len() - 1 - index) as u32;
+        if depth > 0 {
+            rolls.push(depth);
+        }
+        let label = current.remove(index);
+        current.push(label);
+    }
+    script! {
+        for depth in rolls {
+            { u32_roll(depth) }
+        }
+    }
+}
+
+fn words_from_le_bytes(bytes: &[u8]) -> [u32; 8] {
+    let mut words = [0; 8];
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks(4)) {
+        *word = u32::from_le_bytes(chunk.try_into().unwrap());
+    }
+    words
+}
+
+fn g(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize, mx: u32, my: u32) {
+    state[a] = state[a].wrapping_add(state[b]).wrapping_add(mx);
+    state[d] = (state[d] ^ state[a]).rotate_right(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_right(12);
+    state[a] = state[a].wrapping_add(state[b]).wrapping_add(my);
+    state[d] = (state[d] ^ state[a]).rotate_right(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_right(7);
+}
+
+/// Off-chain compression function, returning the chaining value
+fn compress_words(
+    chaining_value: &[u32; 8],
+    block: &[u32; 16],
+    counter: u32,
+    block_len: u32,
+    flags: u32,
+) -> [u32; 8] {
+    let mut state = [0; 16];
+    state[..8].copy_from_slice(chaining_value);
+    state[8..12].copy_from_slice(&IV[..4]);
+    state[12] = counter;
+    state[14] = block_len;
+    state[15] = flags;
+
+    let mut m = *block;
+    for round in 0..7 {
+        g(&mut state, 0, 4, 8, 12, m[0], m[1]);
+        g(&mut state, 1, 5, 9, 13, m[2], m[3]);
+        g(&mut state, 2, 6, 10, 14, m[4], m[5]);
+        g(&mut state, 3, 7, 11, 15, m[6], m[7]);
+        g(&mut state, 0, 5, 10, 15, m[8], m[9]);
+        g(&mut state, 1, 6, 11, 12, m[10], m[11]);
+        g(&mut state, 2, 7, 8, 13, m[12], m[13]);
+        g(&mut state, 3, 4, 9, 14, m[14], m[15]);
+        if round < 6 {
+            m = MSG_PERMUTATION.map(|i| m[i]);
+        }
+    }
+
+    let mut output = [0; 8];
+    for i in 0..8 {
+        output[i] = state[i] ^ state[i + 8];
+    }
+    output
+}
+
+/// Off-chain hash following `blake3_compressions`, as the scripts do
+fn hash_words(input: &[u8], key: &[u32; 8], mode_flags: u32) -> [u32; 8] {
+    let mut chaining_values: Vec<[u32; 8]> = vec![];
+    for compression in blake3_compressions(input.len() as u32) {
+        let (counter, block_len, flags) = compression.state_words(mode_flags);
+        let (chaining_value, block) = match compression.message_block() {
+            Some(index) => {
+                let start = (index * BLOCK_LEN) as usize;
+                let mut bytes = [0u8; BLOCK_LEN as usize];
+                bytes[..block_len as usize]
+                    .copy_from_slice(&input[start..start + block_len as usize]);
+                let mut block = [0; 16];
+                for (word, chunk) in block.iter_mut().zip(bytes.chunks(4)) {
+                    *word = u32::from_le_bytes(chunk.try_into().unwrap());
+                }
+                let chaining_value = if compression.is_chained() {
+                    chaining_values.pop().unwrap()
+                } else {
+                    *key
+                };
+                (chaining_value, block)
+            }
+            None => {
+                let right = chaining_values.pop().unwrap();
+                let left = chaining_values.pop().unwrap();
+                let mut block = [0; 16];
+                block[..8].copy_from_slice(&left);
+                block[8..].copy_from_slice(&right);
+                (*key, block)
+            }
+        };
+        chaining_values.push(compress_words(
+            &chaining_value,
+            &block,
+            counter,
+            block_len,
+            flags,
+        ));
+    }
+    assert_eq!(chaining_values.len(), 1);
+    chaining_values[0]
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::fs::File;
+    use std::io::BufReader;
+
+    #[derive(Debug, Deserialize)]
+    pub(crate) struct TestVectors {
+        pub(crate) key: String,
+        pub(crate) context_string: String,
+        pub(crate) cases: Vec<TestCase>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub(crate) struct TestCase {
+        pub(crate) input_len: usize,
+        pub(crate) hash: String,
+        pub(crate) keyed_hash: String,
+        pub(crate) derive_key: String,
+    }
+
+    impl TestVectors {
+        /// The three modes with the expected 32-byte digest of each one
+        pub(crate) fn modes<'a>(&self, case: &'a TestCase) -> [(Blake3Mode, &'a str); 3] {
+            [
+                (Blake3Mode::Hash, &case.hash[..64]),
+                (
+                    Blake3Mode::KeyedHash(self.key.as_bytes().try_into().unwrap()),
+                    &case.keyed_hash[..64],
+                ),
+                (
+                    Blake3Mode::DeriveKey(self.context_string.clone()),
+                    &case.derive_key[..64],
+                ),
+            ]
+        }
+    }
+
+    /// The official test vectors given at https://github.com/BLAKE3-team/BLAKE3/blob/master/test_vectors/test_vectors.json
+    pub(crate) fn read_test_vectors() -> TestVectors {
+        let file = File::open("src/hash/blake3_official_test_vectors.json").unwrap();
+        serde_json::from_reader(BufReader::new(file)).unwrap()
+    }
+
+    /// The input of the test vectors, a repeating sequence of 251 bytes
+    pub(crate) fn test_vector_input(len: usize) -> Vec<u8> { (0..251u8).cycle().take(len).collect() }
+
+    #[test]
+    fn test_compressions() {
+        assert_eq!(
+            blake3_compressions(0),
+            vec![Blake3Compression::Block {
+                chunk: 0,
+                block: 0,
+                block_len: 0,
+                flags: CHUNK_START | CHUNK_END | ROOT
+            }]
+        );
+
+        let compressions = blake3_compressions(3 * CHUNK_LEN + 1);
+        assert_eq!(compressions.len(), 3 * 16 + 1 + 3);
+        // the first two chunks are merged once the second one is complete
+        assert_eq!(compressions[32], Blake3Compression::Parent { flags: PARENT });
+        assert_eq!(
+            compressions[compressions.len() - 1],
+            Blake3Compression::Parent {
+                flags: PARENT | ROOT
+            }
+        );
+    }
+
+    #[test]
+    fn test_reference_against_test_vectors() {
+        let test_vectors = read_test_vectors();
+        for case in test_vectors.cases.iter() {
+            let input = test_vector_input(case.input_len);
+            for (mode, expected) in test_vectors.modes(case) {
+                let words = hash_words(&input, &mode.key_words(), mode.flags());
+                let digest: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+                assert_eq!(hex::encode(digest), expected, "{:?}", mode);
+                assert_eq!(hex::encode(mode.hash(&input)), expected, "{:?}", mode);
+            }
+        }
+    }
+}
@@ -2,6 +2,9 @@
 use std::collections::HashMap;
 
 
+use crate::hash::blake3_mode::{
+    blake3_compressions, num_message_blocks, u32_arrange, Blake3Compression, Blake3Mode,
+};
 use crate::pseudo::push_to_stack;
 use crate::treepp::{script, Script};
 use crate::u32::u32_std::{u32_equalverify, u32_roll, u32_uncompress};
@@ -242,52 +245,74 @@ pub fn blake3() -> Script {
 }
 
 pub fn blake3_var_length(num_u32: usize) -> Script {
+    blake3_var_length_with_mode(num_u32, &Blake3Mode::Hash)
+}
+
+/// Blake3 of `num_u32` compressed u32, truncated to the first `n_digest_u32_limbs` words of the
+/// digest (at most 8, i.e. the full 32-byte output).
+/// The message is expected in the same word order as for `blake3_var_length`.
+pub fn blake3_var_length_truncated(num_u32: usize, n_digest_u32_limbs: u32) -> Script {
+    blake3_var_length_with_mode_truncated(num_u32, &Blake3Mode::Hash, n_digest_u32_limbs)
+}
+
+/// Push the whole state of a compression starting from `chaining_value`
+fn full_state(chaining_value: &[u32; 8], counter: u32, block_len: u32, flags: u32) -> Vec<Script> {
+    let mut state = [
+        chaining_value[0],
+        chaining_value[1],
+        chaining_value[2],
+        chaining_value[3],
+        chaining_value[4],
+        chaining_value[5],
+        chaining_value[6],
+        chaining_value[7],
+        IV[0],
+        IV[1],
+        IV[2],
+        IV[3],
+        counter,
+        0,
+        block_len,
+        flags,
+    ];
+    state.reverse();
+    state.iter().map(|x| u32_push(*x)).collect::<Vec<_>>()
+}
+
+/// Blake3 of `num_u32` compressed u32 in the given mode, returning a N_DIGEST_U32_LIMBS*4-byte
+/// digest. Messages longer than a chunk (256 u32) are hashed as a tree, the chaining values
+/// waiting to be merged are kept on the altstack.
+pub fn blake3_var_length_with_mode(num_u32: usize, mode: &Blake3Mode) -> Script {
+    blake3_var_length_with_mode_truncated(num_u32, mode, N_DIGEST_U32_LIMBS)
+}
+
+/// Same as `blake3_var_length_with_mode`, keeping the first `n_digest_u32_limbs` words of the
+/// digest. The message word order doesn't depend on `n_digest_u32_limbs`.
+pub fn blake3_var_length_with_mode_truncated(
+    num_u32: usize,
+    mode: &Blake3Mode,
+    n_digest_u32_limbs: u32,
+) -> Script {
+    assert!(
+        (1..=8).contains(&n_digest_u32_limbs),
+        "the digest has between 1 and 8 u32 limbs"
+    );
     assert!(num_u32 <= 512,
             "This blake3 implementation does not support input larger than 512 bytes due to stack limit. \
             Please modify the hashing routine to avoid calling blake3 in this way.");
 
     // Compute how many padding elements are needed
     let num_bytes = num_u32 * 4;
-    let num_blocks = num_bytes.div_ceil(64);
-    let _num_padding_bytes = num_blocks * 64 - num_bytes;
+    let num_blocks = num_message_blocks(num_bytes as u32) as usize;
     let num_padding_u32 = num_blocks * 16 - num_u32;
 
-    // Calculate the initial state
-    let first_block_flag = if num_bytes <= 64 {
-        0b00001011
-    } else {
-        0b00000001
-    };
-    let init_state = {
-        let mut state = [
-            IV[0],
-            IV[1],
-            IV[2],
-            IV[3],
-            IV[4],
-            IV[5],
-            IV[6],
-            IV[7],
-            IV[0],
-            IV[1],
-            IV[2],
-            IV[3],
-            0,
-            0,
-            core::cmp::min(num_bytes as u32, 64),
-            first_block_flag,
-        ];
-        state.reverse();
-        state.iter().map(|x| u32_push(*x)).collect::<Vec<_>>()
-    };
+    let key = mode.key_words();
+    let mode_flags = mode.flags();
 
     let mut env = ptr_init(Some(N_DIGEST_U32_LIMBS));
 
     // store the compression script for reuse
     let compression_script = script! {
-
-
-
         {compress(&mut env, 16, None)}
 
         { 321 }
@@ -310,13 +335,72 @@ pub fn blake3_var_length(num_u32: usize) -> Script {
         u8_drop_xor_table
     };
 
-    let script = script! {
+    let compressions = blake3_compressions(num_bytes as u32)
+        .into_iter()
+        .map(|compression| {
+            let (counter, block_len, flags) = compression.state_words(mode_flags);
+            let state = if compression.is_chained() {
+                let state_add = {
+                    let mut state = [IV[0], IV[1], IV[2], IV[3], counter, 0, block_len, flags];
+                    state.reverse();
+                    state.iter().map(|x| u32_push(*x)).collect::<Vec<_>>()
+                };
+                script! {
+                    { state_add }
+                    for _ in 0..8 {
+                        {u32_fromaltstack()}
+                    }
+                    for i in 1..8 {
+                        {u32_roll(i)}
+                    }
+                }
+            } else {
+                script! {
+                    { full_state(&key, counter, block_len, flags) }
+                }
+            };
+            let message = match compression {
+                Blake3Compression::Block { .. } => script! {
+                    for _ in 0..15 {
+                        OP_TOALTSTACK
+                    }
+                    { u32_uncompress() }
+
+                    for _ in 0..15 {
+                        OP_FROMALTSTACK
+                        { u32_uncompress() }
+                    }
+                },
+                // The message of a parent node is the concatenation of the chaining values of
+                // its children, in the same word order as the message blocks
+                Blake3Compression::Parent { .. } => script! {
+                    for _ in 0..16 {
+                        {u32_fromaltstack()}
+                    }
+                    // right child words 0..7, left child words 0..7 (top)
+                    { u32_arrange(
+                        &(8..16).chain(0..8).collect::<Vec<_>>(),
+                        &(0..16)
+                            .rev()
+                            .map(|position| (position + 2 * N_DIGEST_U32_LIMBS) % 16)
+                            .collect::<Vec<_>>(),
+                    ) }
+                },
+            };
+            script! {
+                { message }
+                u8_push_xor_table
+                { state }
+                { compression_script.clone() }
+            }
+        })
+        .collect::<Vec<_>>();
 
+    script! {
         // Add the padding
         for _ in 0..num_padding_u32 {
             {0}
         }
-        //{ push_to_stack(0, num_padding_bytes) }
 
         // If padded, move all the bytes down
         if num_padding_u32 != 0 {
@@ -325,96 +409,18 @@ pub fn blake3_var_length(num_u32: usize) -> Script {
             }
         }
 
-        // the 1st block
-        for _ in 0..15{
-            OP_TOALTSTACK
-        }
-        { u32_uncompress() }
-
-        for _ in 0..15 {
-            OP_FROMALTSTACK
-            { u32_uncompress() }
-        }
+        // Compress the blocks and merge the chunks
+        { compressions }
 
-        // Initialize the lookup table
-        u8_push_xor_table
-
-        // Push the initial Blake3 state onto the stack
-        { init_state }
-
-        // Call compression function initially
-        { compression_script.clone() }
-
-        // Variable script for the rest of the blocks
-        // TODO: This is very ugly and can likely be improved by creating an iterator of num_bytes
-        // beforehand and getting the next value (num_bytes - 64) from it.
-        // By doing so we can get rid of the closure.
-        { (| num_bytes | {
-            let mut sub_script = script! {};
-            let mut num_bytes = num_bytes;
-            for i in 1..num_blocks {
-                num_bytes -= 64;
-
-                let block_flag = if i == num_blocks - 1 { 0b00001010 } else { 0 };
-
-                let state_add = {
-                    let mut state = [
-                        IV[0],
-                        IV[1],
-                        IV[2],
-                        IV[3],
-                        0,
-                        0,
-                        core::cmp::min(num_bytes as u32, 64),
-                        block_flag,
-                    ];
-                    state.reverse();
-                    state.iter().map(|x| u32_push(*x)).collect::<Vec<_>>()
-                };
-
-                sub_script = script! {
-                    { sub_script }
-                    { script! {
-
-                            // the rest of blocks
-                            for _ in 0..15{
-                                OP_TOALTSTACK
-                            }
-                            { u32_uncompress() }
-
-                            for _ in 0..15 {
-                                OP_FROMALTSTACK
-                                { u32_uncompress() }
-                            }
-                            u8_push_xor_table
-                            { state_add }
-                            for _ in 0..8 {
-                                {u32_fromaltstack()}
-                            }
-                            for i in 1..8 {
-                                {u32_roll(i)}
-                            }
-                            {compression_script.clone()}
-                        }
-                    }
-                }
-            }
-            sub_script
-            })(num_bytes)
-        }
-
-        //u8_drop_xor_table
         for _ in 0..8 {
             u32_fromaltstack
         }
 
-        // Reduce the digest's length to 20 bytes
-        for _ in N_DIGEST_U32_LIMBS..8 {
+        // Truncate the digest
+        for _ in n_digest_u32_limbs..8 {
             u32_drop
         }
-    };
-    
-    script
+    }
 }
 
 pub fn blake3_var_length_copy(num_u32: usize) -> Script {
@@ -625,8 +631,9 @@ pub fn blake3_hash_equalverify() -> Script {
 #[cfg(test)]
 mod tests {
     use crate::hash::blake3_u32::*;
-    
-    use crate::treepp::{execute_script, script};
+    use crate::hash::blake3_mode::tests::{read_test_vectors, test_vector_input};
+
+    use crate::treepp::{execute_script, execute_script_without_stack_limit, script};
     use crate::u32::u32_std::{u32_equalverify, u32_push, u32_uncompress};
 
     #[test]
@@ -804,7 +811,76 @@ mod tests {
 
     }
 
+    /// Push the message as compressed u32, in the word order of `blake3_var_length`
+    fn push_message_compressed(message: &[u8]) -> Script {
+        assert!(message.len() % 64 == 0, "only whole blocks are supported");
+        let mut values = vec![];
+        for block in message.chunks(64).rev() {
+            for position in (0..16).rev() {
+                let word = (position + 2 * N_DIGEST_U32_LIMBS as usize) % 16;
+                let value = u32::from_le_bytes(block[4 * word..4 * word + 4].try_into().unwrap());
+                // 0x80000000 is the only value compressed to 5 bytes
+                values.push(if value == 0x8000_0000 {
+                    0x8000_0000_i64
+                } else {
+                    value as i32 as i64
+                });
+            }
+        }
+        script! {
+            for value in values {
+                { value }
+            }
+        }
+    }
 
+    #[test]
+    fn test_blake3_var_length_modes() {
+        let test_vectors = read_test_vectors();
+        for case in test_vectors.cases.iter() {
+            if ![64, 128, 1024, 2048].contains(&case.input_len) {
+                continue;
+            }
+            let input = test_vector_input(case.input_len);
+            for (mode, expected) in test_vectors.modes(case) {
+                let script = script! {
+                    { push_message_compressed(&input) }
+                    { blake3_var_length_with_mode(case.input_len / 4, &mode) }
+                    { push_bytes_hex(&expected[..8 * N_DIGEST_U32_LIMBS as usize]) }
+                    blake3_hash_equalverify
+                    OP_TRUE
+                };
+                // two chunks don't fit within the stack limit next to the lookup table
+                let res = if case.input_len > 1024 {
+                    execute_script_without_stack_limit(script)
+                } else {
+                    execute_script(script)
+                };
+                assert!(res.success, "{:?} failed for {} bytes", mode, case.input_len);
+            }
+        }
+    }
 
-
+    #[test]
+    fn test_blake3_var_length_truncated() {
+        let input = test_vector_input(128);
+        let expected = blake3::hash(&input).to_hex();
+        for n_limbs in [4, 5, 8] {
+            let n_bytes = 4 * n_limbs as usize;
+            let script = script! {
+                { push_message_compressed(&input) }
+                { blake3_var_length_truncated(32, n_limbs) }
+                { push_bytes_hex(&expected[..2 * n_bytes]) }
+                for i in (1..=n_bytes).rev() {
+                    { i }
+                    OP_ROLL
+                    OP_EQUALVERIFY
+                }
+                OP_TRUE
+            };
+            let res = execute_script(script);
+            assert!(res.success, "truncation to {} limbs failed", n_limbs);
+            assert_eq!(res.final_stack.len(), 1);
+        }
+    }
 }
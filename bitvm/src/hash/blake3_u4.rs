@@ -5,6 +5,7 @@ use bitcoin_script_stack::stack::{StackTracker, StackVariable};
 pub use bitcoin_script::script;
 pub use bitcoin_script::builder::StructuredScript as Script;
 
+use crate::hash::blake3_mode::{blake3_compressions, num_message_blocks, Blake3Mode};
 use crate::u4::{u4_add_stack::*, u4_logic_stack::*, u4_shift_stack::*, u4_std::u4_repeat_number};
 
 const IV: [u32; 8] = [
@@ -459,6 +460,7 @@ pub fn permutate(message_var_map: &HashMap<u8, StackVariable>) -> HashMap<u8, St
 pub fn init_state(
     stack: &mut StackTracker,
     chaining: bool,
+    key: &[u32; 8],
     counter: u32,
     block_len: u32,
     flags: u32,
@@ -470,15 +472,15 @@ pub fn init_state(
             state.push(stack.from_altstack_joined(8, &format!("prev-hash[{}]", i)));
         }
     } else {
-        for u32 in IV {
-            state.push(stack.number_u32(u32));
+        for u32 in key {
+            state.push(stack.number_u32(*u32));
         }
     }
     for u32 in &IV[0..4] {
         state.push(stack.number_u32(*u32));
     }
-    state.push(stack.number_u32(0));
     state.push(stack.number_u32(counter));
+    state.push(stack.number_u32(0));
     state.push(stack.number_u32(block_len));
     state.push(stack.number_u32(flags));
 
@@ -494,6 +496,7 @@ pub fn init_state(
 pub fn compress(
     stack: &mut StackTracker,
     chaining: bool,
+    key: &[u32; 8],
     counter: u32,
     block_len: u32,
     flags: u32,
@@ -505,7 +508,7 @@ pub fn compress(
     //chaining value needs to be copied for multiple blocks
     //every time that is provided
 
-    let mut state = init_state(stack, chaining, counter, block_len, flags);
+    let mut state = init_state(stack, chaining, key, counter, block_len, flags);
 
     for i in 0..7 {
         //round 6 could consume the message
@@ -552,17 +555,34 @@ pub fn get_flags_for_block(i: u32, num_blocks: u32) -> u32 {
     0
 }
 
+/// Pop the chaining values of the two children of a parent node from the altstack, the right
+/// one on top, as the message of the parent node
+pub fn parent_message(stack: &mut StackTracker) -> HashMap<u8, StackVariable> {
+    let mut message = HashMap::new();
+    for m in (8..16).chain(0..8) {
+        message.insert(m, stack.from_altstack_joined(8, &format!("child_cv_{}", m)));
+    }
+    message
+}
+
 // final rounds: 8 => 32 bytes hash
 // final rounds: 5 => 20 bytes hash (blake_160)
-pub fn blake3(stack: &mut StackTracker, mut msg_len: u32, final_rounds: u8) {
+pub fn blake3(stack: &mut StackTracker, msg_len: u32, final_rounds: u8) {
     assert!(
         msg_len <= 288,
         "This blake3 implementation supports up to 288 bytes"
     );
+    blake3_with_mode(stack, msg_len, final_rounds, &Blake3Mode::Hash);
+}
 
+/// Blake3 of the `msg_len` bytes on the stack in the given mode.
+/// Messages longer than a chunk (1024 bytes) are hashed as a tree, the chaining values waiting
+/// to be merged are kept on the altstack. The message nibbles stay on the stack, so messages
+/// beyond 288 bytes exceed the stack limit.
+pub fn blake3_with_mode(stack: &mut StackTracker, msg_len: u32, final_rounds: u8, mode: &Blake3Mode) {
     let use_full_tables = msg_len <= 232;
 
-    let num_blocks = msg_len.div_ceil(64);
+    let num_blocks = num_message_blocks(msg_len);
     let mut num_padding_bytes = num_blocks * 64 - msg_len;
 
     //to handle the message the padding needs to be multiple of 4
@@ -597,41 +617,50 @@ pub fn blake3(stack: &mut StackTracker, mut msg_len: u32, final_rounds: u8) {
         stack.from_altstack();
     }
 
+    let key = mode.key_words();
+    let compressions = blake3_compressions(msg_len);
 
-    //process every block
-    for i in 0..num_blocks {
-        let last_round = i == num_blocks - 1;
+    //process every block and merge the chunks
+    for (i, compression) in compressions.iter().enumerate() {
+        let last_round = i == compressions.len() - 1;
         let intermediate_rounds = if last_round { final_rounds } else { 8 };
 
-        let flags = get_flags_for_block(i, num_blocks);
-
-        // add the padding on the last round
-        if last_round && num_padding_bytes > 0 {
-            stack.custom(
-                u4_repeat_number(0, (num_padding_bytes) * 2),
-                0,
-                false,
-                0,
-                "padding",
-            );
-            for i in 0..(num_padding_bytes / 4) {
-                let m = stack.define(8, &format!("padd_{}", i));
-                original_message.push(m);
+        let (counter, block_len, flags) = compression.state_words(mode.flags());
+
+        let message = match compression.message_block() {
+            Some(block) => {
+                // add the padding on the last block
+                if block == num_blocks - 1 && num_padding_bytes > 0 {
+                    stack.custom(
+                        u4_repeat_number(0, (num_padding_bytes) * 2),
+                        0,
+                        false,
+                        0,
+                        "padding",
+                    );
+                    for i in 0..(num_padding_bytes / 4) {
+                        let m = stack.define(8, &format!("padd_{}", i));
+                        original_message.push(m);
+                    }
+                }
+
+                // create the current block message map
+                let mut message = HashMap::new();
+                for m in 0..16 {
+                    message.insert(m as u8, original_message[m + (16 * block) as usize]);
+                }
+                message
             }
-        }
-
-        // create the current block message map
-        let mut message = HashMap::new();
-        for m in 0..16 {
-            message.insert(m as u8, original_message[m + (16 * i) as usize]);
-        }
+            None => parent_message(stack),
+        };
 
         // compress the block
         compress(
             stack,
-            i > 0,
-            0,
-            msg_len.min(64),
+            compression.is_chained(),
+            &key,
+            counter,
+            block_len,
             flags,
             message,
             &tables,
@@ -639,10 +668,6 @@ pub fn blake3(stack: &mut StackTracker, mut msg_len: u32, final_rounds: u8) {
             last_round,
         );
 
-        if msg_len > 64 {
-            msg_len -= 64;
-        }
-
         //drop the rest of the state
         for _ in 0..16 - intermediate_rounds {
             stack.drop(stack.get_var_from_stack(0));
@@ -665,6 +690,8 @@ mod tests {
     use bitcoin_script_stack::{debugger::debug_script, script_util::verify_n, stack::StackTracker, optimizer::optimize};
 
     use super::*;
+    use crate::execute_script_without_stack_limit;
+    use crate::hash::blake3_mode::tests::{read_test_vectors, test_vector_input};
     use crate::u4::u4_std::u4_hex_to_nibbles;
 
     fn verify_blake3_hash(result: &str) -> Script {
@@ -984,4 +1011,49 @@ mod tests {
         let end = stack.get_script().len();
         println!("Round size: {}", end - start);
     }
+
+    /// The message as nibbles of its little endian words
+    fn message_words_hex(message: &[u8]) -> String {
+        assert!(message.len() % 4 == 0, "only whole words are supported");
+        message
+            .chunks(4)
+            .map(|word| format!("{:08x}", u32::from_le_bytes(word.try_into().unwrap())))
+            .collect()
+    }
+
+    #[test]
+    fn test_blake3_modes() {
+        let test_vectors = read_test_vectors();
+        for case in test_vectors.cases.iter() {
+            if ![0, 4, 8, 64, 128, 2048].contains(&case.input_len) {
+                continue;
+            }
+            let input = test_vector_input(case.input_len);
+            for (mode, expected) in test_vectors.modes(case) {
+                let mut stack = StackTracker::new();
+                stack.custom(
+                    script! { { u4_hex_to_nibbles(&message_words_hex(&input)) } },
+                    0,
+                    false,
+                    0,
+                    "msg",
+                );
+
+                blake3_with_mode(&mut stack, case.input_len as u32, 8, &mode);
+
+                stack.custom(
+                    script! { {verify_blake3_hash(expected)}},
+                    1,
+                    false,
+                    0,
+                    "verify",
+                );
+                stack.op_true();
+
+                // the nibbles of more than a chunk exceed the stack limit
+                let res = execute_script_without_stack_limit(stack.get_script());
+                assert!(res.success, "{:?} failed for {} bytes", mode, case.input_len);
+            }
+        }
+    }
 }
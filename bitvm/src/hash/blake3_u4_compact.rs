@@ -5,10 +5,10 @@ use bitcoin_script_stack::stack::StackTracker;
 pub use bitcoin_script::builder::StructuredScript as Script;
 pub use bitcoin_script::script;
 
-use hex::FromHex;
 use crate::bigint::U256;
 
-use crate::hash::blake3_u4::{compress, get_flags_for_block, TablesVars};
+use crate::hash::blake3_mode::{blake3_compressions, num_message_blocks, Blake3Mode};
+use crate::hash::blake3_u4::{compress, parent_message, TablesVars};
 
 // This implementation assumes you have the input is in compact form on the stack.
 // The message must be packed into U256 (which uses 9 limbs 29 bits each) such that it expands a multiple of 128 nibbles
@@ -57,23 +57,35 @@ use crate::hash::blake3_u4::{compress, get_flags_for_block, TablesVars};
 /// - Final result is left on the main stack as a BLAKE3 hash value.
 ///
 /// ## Panics:
-/// - If `msg_len` is greater than 1024 bytes, the function panics with an assertion error.
 /// - If the input is not a multiple of 18 limbs, or doesn't unpack to a multiple of 128 nibbles.
 /// - If the stack contains elements other than the message.
 
 pub fn blake3_u4_compact(
     stack: &mut StackTracker,
-    mut msg_len: u32,
+    msg_len: u32,
     define_var: bool,
     use_full_tables: bool,
 ) {
+    blake3_u4_compact_with_mode(stack, msg_len, define_var, use_full_tables, &Blake3Mode::Hash);
+}
+
+/// Compact BLAKE3 hash in the given mode, see `blake3_u4_compact` for the input format.
+///
+/// Messages longer than a chunk (1024 bytes) are hashed as a tree, the chaining values waiting
+/// to be merged are kept on the altstack. The compact message takes 18 stack elements per
+/// block, so about a thousand bytes fit within the stack limit next to the tables.
+pub fn blake3_u4_compact_with_mode(
+    stack: &mut StackTracker,
+    msg_len: u32,
+    define_var: bool,
+    use_full_tables: bool,
+    mode: &Blake3Mode,
+) {
 
     // this assumes that the stack is empty
     if msg_len == 0{
-        // af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262
-        //hardcoded hash of empty msg
-        let empty_msg_hash = "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262";
-        let empty_msg_hash_bytearray =  <[u8;32]>::from_hex(empty_msg_hash).unwrap();
+        //hash of the empty msg, computed off-chain
+        let empty_msg_hash_bytearray = mode.hash(&[]);
 
         stack.custom(script!(
             // push the hash value
@@ -92,15 +104,8 @@ pub fn blake3_u4_compact(
         return;
     }
 
-
-    // We require message take atmost a chunk. i.e, 1024 bytes.
-    assert!(
-        msg_len <= 1024,
-        "msg length must be less than or equal to 1024 bytes"
-    );
-
     //number of msg blocks
-    let num_blocks = f64::ceil(msg_len as f64 / 64 as f64) as u32;
+    let num_blocks = num_message_blocks(msg_len);
 
     // If the compact form of message is on stack but not associated with variable, convert it to StackVariable
     if define_var {
@@ -125,108 +130,116 @@ pub fn blake3_u4_compact(
         stack.from_altstack();
     }
 
-    //process each msg_block
-    for i in 0..num_blocks {
-        // unpack the compact form of message
-        stack.custom(
-            script!(
-                {U256::transform_limbsize(29, 4)}
-                for _ in 0..64{
-                    OP_TOALTSTACK
-                }
-            ),
-            1,
-            false,
-            0,
-            &format!("unpack msg{}p1", i),
-        );
-
-        stack.custom(
-            script!(
-                {U256::transform_limbsize(29,4)}
-                for _ in 0..64{
-                    OP_FROMALTSTACK
-                }
-            ),
-            1,
-            false,
-            0,
-            &format!("unpack msg{}p0", i),
-        );
-
-        // handle padding if it is the last block
-        if i == (num_blocks - 1) && msg_len != 64 {
-            // due to LE representation, msg portion can be on top of padding.
-            let j = msg_len % 4;
-            let pad_bytes = 64 + j - msg_len - 4;
-
-            stack.custom(
-                script!(
-                    //Drop whatever padding has been added for packing to limbs and pad with zeros
-                    for _ in 0..(pad_bytes*2){
-                        OP_DROP
-                    }
-
-                    for _ in 0..(j*2){
-                        OP_TOALTSTACK
-                    }
-
-                    for _ in 0..(4-j) * 2{
-                        OP_DROP
-                    }
-
-                    for _ in 0..(4-j) * 2{
-                        OP_0
-                    }
+    let key = mode.key_words();
+    let compressions = blake3_compressions(msg_len);
+
+    //process each msg_block and merge the chunks
+    for (c, compression) in compressions.iter().enumerate() {
+        let (counter, block_len, flags) = compression.state_words(mode.flags());
+
+        let message = match compression.message_block() {
+            Some(i) => {
+                // unpack the compact form of message
+                stack.custom(
+                    script!(
+                        {U256::transform_limbsize(29, 4)}
+                        for _ in 0..64{
+                            OP_TOALTSTACK
+                        }
+                    ),
+                    1,
+                    false,
+                    0,
+                    &format!("unpack msg{}p1", i),
+                );
 
-                    for _ in 0..(j*2){
-                        OP_FROMALTSTACK
-                    }
+                stack.custom(
+                    script!(
+                        {U256::transform_limbsize(29,4)}
+                        for _ in 0..64{
+                            OP_FROMALTSTACK
+                        }
+                    ),
+                    1,
+                    false,
+                    0,
+                    &format!("unpack msg{}p0", i),
+                );
 
-                    for _ in 0..(pad_bytes*2){
-                        OP_0
-                    }
-                ),
-                0,
-                false,
-                0,
-                "padding",
-            );
-        }
+                // handle padding if it is the last block
+                if i == (num_blocks - 1) && block_len != 64 {
+                    // due to LE representation, msg portion can be on top of padding.
+                    let j = block_len % 4;
+                    let pad_bytes = 64 + j - block_len - 4;
+
+                    stack.custom(
+                        script!(
+                            //Drop whatever padding has been added for packing to limbs and pad with zeros
+                            for _ in 0..(pad_bytes*2){
+                                OP_DROP
+                            }
+
+                            for _ in 0..(j*2){
+                                OP_TOALTSTACK
+                            }
+
+                            for _ in 0..(4-j) * 2{
+                                OP_DROP
+                            }
+
+                            for _ in 0..(4-j) * 2{
+                                OP_0
+                            }
+
+                            for _ in 0..(j*2){
+                                OP_FROMALTSTACK
+                            }
+
+                            for _ in 0..(pad_bytes*2){
+                                OP_0
+                            }
+                        ),
+                        0,
+                        false,
+                        0,
+                        "padding",
+                    );
+                }
 
-        //make a hashmap of msgs
-        let mut original_message = Vec::new();
-        for i in 0..16 {
-            let m = stack.define(8, &format!("msg_{}", i));
-            original_message.push(m);
-        }
+                //make a hashmap of msgs
+                let mut original_message = Vec::new();
+                for i in 0..16 {
+                    let m = stack.define(8, &format!("msg_{}", i));
+                    original_message.push(m);
+                }
 
-        // create the current block message map
-        let mut message = HashMap::new();
-        for m in 0..16 {
-            message.insert(m as u8, original_message[m as usize]);
-        }
+                // create the current block message map
+                let mut message = HashMap::new();
+                for m in 0..16 {
+                    message.insert(m as u8, original_message[m as usize]);
+                }
+                message
+            }
+            None => parent_message(stack),
+        };
 
         compress(
             stack,
-            i != 0,
-            0,
-            msg_len.min(64),
-            get_flags_for_block(i, num_blocks),
+            compression.is_chained(),
+            &key,
+            counter,
+            block_len,
+            flags,
             message,
             &tables,
             8,
-            i == num_blocks - 1,
+            c == compressions.len() - 1,
         );
 
         //delete the intermediate states
         for _ in 0..8 {
             stack.drop(stack.get_var_from_stack(0));
         }
-
-        if msg_len > 64 {
-            msg_len -= 64;
-        }
     }
     // drop tables
     tables.drop(stack);
@@ -310,7 +323,8 @@ mod tests {
     use rand::{Rng, SeedableRng};
     use rand_chacha::ChaCha20Rng;
     use super::*;
-    use crate::{execute_script, u4::u4_std::u4_hex_to_nibbles};
+    use crate::hash::blake3_mode::tests::{read_test_vectors, test_vector_input};
+    use crate::{execute_script, execute_script_without_stack_limit, u4::u4_std::u4_hex_to_nibbles};
 
     fn add_padding(input: String) -> String {
         let len_bytes = input.len() / 2;
@@ -607,33 +621,92 @@ mod tests {
         test_blake3_compact_givenbyteslice(&[], false);
     }
     
-    // should panic when msg len is larger than 1024 (using hexstring and full table)
-    #[test]
-    #[should_panic(expected = "msg length must be less than or equal to 1024 bytes")]
-    fn test_blake3_compact_large_length_hexstring_fulltable() {
-        test_blake3_compact_giveninputhex(String::from("0".repeat(1025 * 2)), 1025, true);
-    }
+    // hash the byte slice in the given mode, the message being pushed as in test_blake3_compact_givenbyteslice
+    fn blake3_compact_with_mode_script(input_bytes: &[u8], mode: &Blake3Mode, use_full_tables: bool) -> Script {
+        let mut stack = StackTracker::new();
 
-    // should panic when msg len is larger than 1024 (using bytearray and full table)
-    #[test]
-    #[should_panic(expected = "msg length must be less than or equal to 1024 bytes")]
-    fn test_blake3_compact_large_length_bytearray_fulltable() {
-        test_blake3_compact_givenbyteslice(&[0u8; 1025], true);
-    }    
+        let mut padded_msg = input_bytes.to_vec();
+        padded_msg.resize(input_bytes.len().div_ceil(64) * 64, 0);
+        for chunk in padded_msg.chunks_mut(4) {
+            chunk.reverse();
+        }
+        stack.custom(script!(
+            for chunk in padded_msg.chunks(64).rev(){
+                for (i,byte) in chunk.iter().enumerate(){
+                    {*byte}
+                    if i == 31 || i == 63{
+                        {U256::transform_limbsize(8,29)}
+                    }
+                }
+            }
+        ),
+        0,
+        false,
+        0,
+        "push_msgs"
+        );
+
+        blake3_u4_compact_with_mode(&mut stack, input_bytes.len() as u32, true, use_full_tables, mode);
+
+        stack.custom(script!(
+            for (i, byte) in mode.hash(input_bytes).iter().enumerate(){
+                {*byte}
+                if i % 32 == 31{
+                    {U256::transform_limbsize(8,4)}
+                }
+            }
+
+            for i in (2..65).rev(){
+                {i}
+                OP_ROLL
+                OP_EQUALVERIFY
+            }
+            OP_EQUAL
+        ),
+        0,
+        false,
+        0,
+        "verify");
 
+        stack.get_script()
+    }
 
-    // should panic when msg len is larger than 1024 (using hexstring and half table)
+    // test the keyed_hash and derive_key modes against the official test vectors
     #[test]
-    #[should_panic(expected = "msg length must be less than or equal to 1024 bytes")]
-    fn test_blake3_compact_large_length_hexstring_halftable() {
-        test_blake3_compact_giveninputhex(String::from("0".repeat(1025 * 2)), 1025, false);
+    fn test_blake3_compact_modes_official_testvectors() {
+        let test_vectors = read_test_vectors();
+        for case in test_vectors.cases.iter() {
+            if ![0, 1, 63, 64, 65, 1023, 1024].contains(&case.input_len) {
+                continue;
+            }
+            let input = test_vector_input(case.input_len);
+            for (mode, expected) in test_vectors.modes(case) {
+                assert_eq!(hex::encode(mode.hash(&input)), expected);
+                let script = blake3_compact_with_mode_script(&input, &mode, false);
+                assert!(execute_script(script).success, "{:?} failed for {} bytes", mode, case.input_len);
+            }
+        }
     }
 
-    // should panic when msg len is larger than 1024 (using bytearray and half table)
+    // messages longer than a chunk are hashed as a tree
     #[test]
-    #[should_panic(expected = "msg length must be less than or equal to 1024 bytes")]
-    fn test_blake3_compact_large_length_bytearray_halftable() {
-        test_blake3_compact_givenbyteslice(&[0u8; 1025], false);
+    fn test_blake3_compact_multiple_chunks() {
+        let test_vectors = read_test_vectors();
+        for case in test_vectors.cases.iter() {
+            if ![1025, 2048, 2049].contains(&case.input_len) {
+                continue;
+            }
+            let input = test_vector_input(case.input_len);
+            for (mode, expected) in test_vectors.modes(case) {
+                assert_eq!(hex::encode(mode.hash(&input)), expected);
+                for use_full_tables in [true, false] {
+                    let script = blake3_compact_with_mode_script(&input, &mode, use_full_tables);
+                    let res = execute_script_without_stack_limit(script);
+                    println!("Blake3 Max Stack Use for {} bytes : {}", case.input_len, res.stats.max_nb_stack_items);
+                    assert!(res.success, "{:?} failed for {} bytes", mode, case.input_len);
+                }
+            }
+        }
     }
 
     // test on single byte 
@@ -1,7 +1,9 @@
 pub mod blake3;
+pub mod blake3_mode;
 pub mod blake3_u32;
 pub mod blake3_u4;
 pub mod blake3_u4_compact;
 pub mod sha256;
 pub mod sha256_u4;
 pub mod sha256_u4_stack;
+pub mod sha256_unified;
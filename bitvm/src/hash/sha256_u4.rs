@@ -1,4 +1,5 @@
 use crate::treepp::{script, Script};
+use crate::u4::u4_tables::{U4Table, U4Tables};
 use crate::u4::{u4_add::*, u4_logic::*, u4_rot::*, u4_std::*};
 use std::vec;
 
@@ -254,6 +255,16 @@ pub fn schedule_iteration(
 
 fn get_full_w_pos(top_table: u32, i: u32) -> u32 { top_table - (i + 1) * 8 }
 
+/// Replaces the half table below the half lookup, which are at the top of the tables
+fn swap_half_table(from: U4Table, to: U4Table) -> Script {
+    script! {
+        { U4Table::HalfLookup.drop() }
+        { from.drop() }
+        { to.push() }
+        { U4Table::HalfLookup.push() }
+    }
+}
+
 pub fn sha256(num_bytes: u32) -> Script {
     // up to 55 is one block and always supports add table
     // probably up to 68 bytes I can afford to load the add tables for the first chunk (but have I would have to unload it)
@@ -276,37 +287,42 @@ pub fn sha256(num_bytes: u32) -> Script {
     println!("{:?}", bytes_per_chunk);
     println!("{:?}", padding_scripts);
 
-    let add_size = 130;
     let sched_size = 128;
-    let rrot_size = 16 * 5;
-    let half_logic_size = 136 + 16;
-    let mut tables_size = rrot_size + half_logic_size;
     let use_add_table = chunks == 1;
-    if use_add_table {
-        tables_size += add_size;
-    }
+    let tables = |half_table: U4Table| {
+        if use_add_table {
+            U4Tables::new(&[U4Table::Add, U4Table::Shift, half_table])
+        } else {
+            U4Tables::new(&[U4Table::Shift, half_table])
+        }
+    };
+    // the schedule loop uses the half XOR table and the main loop the half AND table, swapped at the top of the tables
+    let sched_tables = tables(U4Table::HalfXor);
+    let main_tables = tables(U4Table::HalfAnd);
+    let tables_size = sched_tables.size();
+    let add_offset = |tables: &U4Tables, depth: u32| {
+        if use_add_table {
+            tables.offset(U4Table::Add, depth)
+        } else {
+            0
+        }
+    };
 
-    let sched_loop_offset_and = sched_size;
-    let sched_loop_offset_rrot = sched_loop_offset_and + half_logic_size;
-    let sched_loop_offset_add = sched_loop_offset_rrot + rrot_size;
+    let sched_loop_offset_and = sched_tables.offset(U4Table::HalfLookup, sched_size);
+    let sched_loop_offset_rrot = sched_tables.offset(U4Table::Shift, sched_size);
+    let sched_loop_offset_add = add_offset(&sched_tables, sched_size);
 
     let full_sched_size = 512;
     let temp_vars_size = 8 * 8;
 
     let vars_top = temp_vars_size + full_sched_size;
-    let main_loop_offset_and = vars_top;
-    let main_loop_offset_rrot = main_loop_offset_and + half_logic_size;
-    let main_loop_offset_add = main_loop_offset_rrot + rrot_size;
+    let main_loop_offset_and = main_tables.offset(U4Table::HalfLookup, vars_top);
+    let main_loop_offset_rrot = main_tables.offset(U4Table::Shift, vars_top);
+    let main_loop_offset_add = add_offset(&main_tables, vars_top);
 
     script! {
 
-        if use_add_table {
-            { u4_push_add_tables() }
-        }
-        { u4_push_rrot_tables() }     // rshiftn 16*6= 96
-        { u4_push_half_xor_table() }  // 136
-        { u4_push_half_lookup() }     // 16
-                                      // total :  136 + 16 + 96 = 248
+        { sched_tables.push() }
 
         for c in 0..chunks {
 
@@ -314,10 +330,7 @@ pub fn sha256(num_bytes: u32) -> Script {
                 //change and with xor
                 //TODO: if lookup table is pushed first and substracted
                 // then we could avoid changing it  ~(32 * chunk)
-                { u4_drop_half_lookup() }
-                { u4_drop_half_table() }
-                { u4_push_half_xor_table() }
-                { u4_push_half_lookup() }
+                { swap_half_table(U4Table::HalfAnd, U4Table::HalfXor) }
             }
 
             for _ in 0..bytes_per_chunk[c as usize]*2 {
@@ -335,10 +348,7 @@ pub fn sha256(num_bytes: u32) -> Script {
 
             //change xor with and table
             { u4_toaltstack(full_sched_size) }
-            { u4_drop_half_lookup() }
-            { u4_drop_half_table() }
-            { u4_push_half_and_table() }
-            { u4_push_half_lookup() }
+            { swap_half_table(U4Table::HalfXor, U4Table::HalfAnd) }
             { u4_fromaltstack(full_sched_size) }
 
             if c == 0 {
@@ -451,12 +461,7 @@ pub fn sha256(num_bytes: u32) -> Script {
 
         }
 
-        { u4_drop_half_lookup() }
-        { u4_drop_half_table() }
-        { u4_drop_rrot_tables() }
-        if use_add_table {
-            { u4_drop_add_tables() }
-        }
+        { main_tables.drop() }
 
         { u4_fromaltstack( 64 )}
 
@@ -24,14 +24,15 @@ const INITSTATE: [u32; 8] = [
     0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
 ];
 
-fn scheduling_64_padding() -> [u32; 64] {
+fn scheduling_64_padding(bit_len: u32) -> [u32; 64] {
     const PADDING_64_BYTES: [u32; 16] = [
         0x80000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
         0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
-        0x00000000, 0x00000200,
+        0x00000000, 0x00000000,
     ];
     let mut result = [0; 64];
     result[..16].clone_from_slice(PADDING_64_BYTES.as_ref());
+    result[15] = bit_len;
 
     for i in 16..64 {
         let s0 = result[i - 15].rotate_right(7)
@@ -47,6 +48,54 @@ fn scheduling_64_padding() -> [u32; 64] {
     result
 }
 
+/// Off-chain SHA-256 compression of `prefix`, used to produce the midstate witness for
+/// `sha256_stack_update` and `sha256_stack_finalize`
+pub fn sha256_midstate(prefix: &[u8]) -> [u32; 8] {
+    assert_eq!(prefix.len() % 64, 0, "midstate is only defined for full blocks");
+    let mut state = INITSTATE;
+    for block in prefix.chunks(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes(word.try_into().unwrap());
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+        let mut v = state;
+        for i in 0..64 {
+            let s1 = v[4].rotate_right(6) ^ v[4].rotate_right(11) ^ v[4].rotate_right(25);
+            let ch = (v[4] & v[5]) ^ (!v[4] & v[6]);
+            let temp1 = v[7]
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(K[i])
+                .wrapping_add(w[i]);
+            let s0 = v[0].rotate_right(2) ^ v[0].rotate_right(13) ^ v[0].rotate_right(22);
+            let maj = (v[0] & v[1]) ^ (v[0] & v[2]) ^ (v[1] & v[2]);
+            let temp2 = s0.wrapping_add(maj);
+            v = [
+                temp1.wrapping_add(temp2),
+                v[0],
+                v[1],
+                v[2],
+                v[3].wrapping_add(temp1),
+                v[4],
+                v[5],
+                v[6],
+            ];
+        }
+        for (s, v) in state.iter_mut().zip(v) {
+            *s = s.wrapping_add(v);
+        }
+    }
+    state
+}
+
 pub fn u4_number_to_nibble(n: u32) -> Script {
     //constant number used during "compile" time
     script! {
@@ -57,6 +106,12 @@ pub fn u4_number_to_nibble(n: u32) -> Script {
 }
 
 pub fn double_padding(num_bytes: u32) -> (Vec<Script>, u32) {
+    double_padding_with_length(num_bytes, num_bytes)
+}
+
+/// Same as `double_padding` but encodes `total_bytes` as the message length, used when the
+/// `num_bytes` on the stack are only the tail of a longer streamed message.
+pub fn double_padding_with_length(num_bytes: u32, total_bytes: u32) -> (Vec<Script>, u32) {
     //55 bytes fits in one block
     //56 to 64 requires two block padding
 
@@ -81,7 +136,7 @@ pub fn double_padding(num_bytes: u32) -> (Vec<Script>, u32) {
             for _ in 0..59 {
                 OP_2DUP
             }
-            { u4_number_to_nibble( num_bytes * 8 ) }
+            { u4_number_to_nibble( total_bytes * 8 ) }
         };
 
         chunks += 1;
@@ -95,7 +150,7 @@ pub fn double_padding(num_bytes: u32) -> (Vec<Script>, u32) {
 
         (results, chunks)
     } else {
-        let (script1, _) = padding(num_bytes);
+        let (script1, _) = padding_with_length(num_bytes, total_bytes);
         let mut results = Vec::new();
         for _ in 0..(chunks - 1) {
             results.push(script! {});
@@ -105,7 +160,9 @@ pub fn double_padding(num_bytes: u32) -> (Vec<Script>, u32) {
     }
 }
 
-pub fn padding(num_bytes: u32) -> (Script, u32) {
+pub fn padding(num_bytes: u32) -> (Script, u32) { padding_with_length(num_bytes, num_bytes) }
+
+pub fn padding_with_length(num_bytes: u32, total_bytes: u32) -> (Script, u32) {
     let l = (num_bytes * 8) as i32;
     let mut k = 512 - l - 8 - 32; // heres is usually minus 8, but as
                                   // there will be never that many bytes to process
@@ -136,7 +193,7 @@ pub fn padding(num_bytes: u32) -> (Script, u32) {
               0
               0
           }
-          { u4_number_to_nibble( l as u32 ) }
+          { u4_number_to_nibble( total_bytes * 8 ) }
         },
         chunks,
     )
@@ -298,11 +355,80 @@ pub fn sha256_stack(
     num_bytes: u32,
     use_add_table: bool,
     use_full_xor: bool,
+) -> Script {
+    sha256_stack_internal(
+        stack,
+        num_bytes,
+        num_bytes,
+        false,
+        true,
+        use_add_table,
+        use_full_xor,
+    )
+}
+
+/// Compress `num_blocks` full 64-byte blocks without padding and leave the midstate.
+/// Input: `[message nibbles]` or `[message nibbles, midstate nibbles]` if `from_midstate`
+/// Output: the 64 nibbles of the midstate, in the same order as a digest
+pub fn sha256_stack_update(
+    stack: &mut StackTracker,
+    num_blocks: u32,
+    from_midstate: bool,
+    use_add_table: bool,
+    use_full_xor: bool,
+) -> Script {
+    assert!(num_blocks > 0, "at least one block is required");
+    sha256_stack_internal(
+        stack,
+        num_blocks * 64,
+        num_blocks * 64,
+        from_midstate,
+        false,
+        use_add_table,
+        use_full_xor,
+    )
+}
+
+/// Hash the last `num_bytes` of a streamed message of `total_bytes` and leave the digest.
+/// Input: `[message nibbles, midstate nibbles]`, where the midstate covers the first
+/// `total_bytes - num_bytes` bytes, which must be a multiple of 64
+pub fn sha256_stack_finalize(
+    stack: &mut StackTracker,
+    num_bytes: u32,
+    total_bytes: u32,
+    use_add_table: bool,
+    use_full_xor: bool,
+) -> Script {
+    assert!(total_bytes >= num_bytes && (total_bytes - num_bytes) % 64 == 0);
+    sha256_stack_internal(
+        stack,
+        num_bytes,
+        total_bytes,
+        total_bytes != num_bytes,
+        true,
+        use_add_table,
+        use_full_xor,
+    )
+}
+
+fn sha256_stack_internal(
+    stack: &mut StackTracker,
+    num_bytes: u32,
+    total_bytes: u32,
+    from_midstate: bool,
+    with_padding: bool,
+    use_add_table: bool,
+    use_full_xor: bool,
 ) -> Script {
     // up to 55 is one block and always supports add table
     // probably up to 68 bytes I can afford to load the add tables for the first chunk (but have I would have to unload it)
 
-    let (mut padding_scripts, chunks) = double_padding(num_bytes);
+    let (mut padding_scripts, chunks) = if with_padding {
+        double_padding_with_length(num_bytes, total_bytes)
+    } else {
+        assert_eq!(num_bytes % 64, 0, "only full blocks can be hashed without padding");
+        (vec![], num_bytes / 64)
+    };
     let mut bytes_per_chunk: Vec<u32> = Vec::new();
     let mut bytes_remaining = num_bytes;
     while bytes_remaining > 0 {
@@ -320,12 +446,25 @@ pub fn sha256_stack(
     //println!("{:?}", bytes_per_chunk);
     //println!("{:?}", padding_scripts);
 
-    let scheduling_64 = scheduling_64_padding();
+    let scheduling_64 = scheduling_64_padding(total_bytes * 8);
 
     let mut message = (0..num_bytes * 2)
         .map(|i| stack.define(1, &format!("message[{}]", i)))
         .collect::<Vec<StackVariable>>();
 
+    let mut varmap: HashMap<char, [StackVariable; 8]> = HashMap::new();
+    let mut initstate: HashMap<char, [StackVariable; 8]> = HashMap::new();
+
+    if from_midstate {
+        for c in INITSTATE_MAPPING.iter() {
+            let mut vars = [StackVariable::null(); 8];
+            for (nib, var) in vars.iter_mut().enumerate() {
+                *var = stack.define(1, &format!("midstate_{}_{}", c, nib));
+            }
+            initstate.insert(*c, vars);
+        }
+    }
+
     let (modulo, quotient) = match use_add_table {
         true => (
             u4_push_modulo_table_stack(stack),
@@ -350,9 +489,6 @@ pub fn sha256_stack(
         )
     };
 
-    let mut varmap: HashMap<char, [StackVariable; 8]> = HashMap::new();
-    let mut initstate: HashMap<char, [StackVariable; 8]> = HashMap::new();
-
     stack.set_breakpoint("load tables");
     for c in 0..chunks {
         //move the message to the top of the stack
@@ -363,11 +499,13 @@ pub fn sha256_stack(
         message.drain(0..moved_message.len());
 
         stack.set_breakpoint("moved message");
-        let is_64bytes_padding = num_bytes == 64 && c == 1;
+        let is_64bytes_padding = with_padding && num_bytes == 64 && c == 1;
 
         //complete message with padding
         if !is_64bytes_padding {
-            stack.custom(padding_scripts.remove(0), 0, false, 0, "padding");
+            if with_padding {
+                stack.custom(padding_scripts.remove(0), 0, false, 0, "padding");
+            }
             let len = moved_message.len();
             if len < 128 {
                 for i in 0..(128 - len) {
@@ -448,7 +586,7 @@ pub fn sha256_stack(
 
                     stack.set_breakpoint(&format!("schedule[{}]", i));
                 }
-            } else if c == 0 {
+            } else if c == 0 && !from_midstate {
                 for i in 0..INITSTATE.len() {
                     varmap.insert(INITSTATE_MAPPING[i], [StackVariable::null(); 8]);
                     for nib in 0..8 {
@@ -644,7 +782,7 @@ pub fn sha256_stack(
             }
         }
 
-        if c == 0 {
+        if c == 0 && !from_midstate {
             //first chunk adds with init state
             for i in (0..INITSTATE_MAPPING.len()).rev() {
                 initstate.insert(INITSTATE_MAPPING[i], [StackVariable::null(); 8]);
@@ -0,0 +1,372 @@
+use crate::hash::sha256::sha256;
+use crate::hash::sha256_u4_stack::{sha256_stack, sha256_stack_finalize, sha256_stack_update};
+use crate::treepp::{script, Script};
+use bitcoin_script_stack::stack::StackTracker;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+/// Largest message the u32 backend hashes within the stack limit: the padded message, the
+/// expanded schedule, the K constants and the xor table all stay on the stack, so at most
+/// two blocks fit
+const U32_MAX_BYTES: usize = 119;
+/// Consensus limit on the number of stack items (main stack and altstack)
+const MAX_STACK_ITEMS: u32 = 1000;
+
+/// The backend picked by `try_cheapest_sha256_backend` for each message length, as every
+/// candidate script has to be built to compare them
+static CHEAPEST_BACKENDS: LazyLock<Mutex<HashMap<usize, Option<Sha256Backend>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// The script implementations of SHA-256 that can be selected by `sha256_var_length`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sha256Backend {
+    /// `sha256::sha256` on u8 limbs
+    U32,
+    /// `sha256_u4_stack::sha256_stack` on nibbles, wrapped with byte/nibble conversion
+    U4Stack {
+        use_add_table: bool,
+        use_full_xor: bool,
+    },
+}
+
+impl Sha256Backend {
+    /// All backends able to hash a message of `num_bytes` within the stack limit
+    pub fn candidates(num_bytes: usize) -> Vec<Self> {
+        Self::measured_candidates(num_bytes)
+            .into_iter()
+            .map(|(backend, _)| backend)
+            .collect()
+    }
+
+    /// The candidates along with the length of their script, building each script only once
+    fn measured_candidates(num_bytes: usize) -> Vec<(Self, usize)> {
+        let mut candidates = vec![];
+        if num_bytes <= U32_MAX_BYTES {
+            candidates.push((Sha256Backend::U32, sha256(num_bytes).len()));
+        }
+        let conversion_len = bytes_to_nibbles(num_bytes).len() + nibbles_to_bytes(32).len();
+        for use_add_table in [true, false] {
+            for use_full_xor in [true, false] {
+                let backend = Sha256Backend::U4Stack {
+                    use_add_table,
+                    use_full_xor,
+                };
+                let (hash_script, max_stack) = backend.u4_stack_script(num_bytes);
+                // the conversion from bytes keeps up to 3 * num_bytes items on the stacks
+                if max_stack.max(3 * num_bytes as u32) <= MAX_STACK_ITEMS {
+                    candidates.push((backend, hash_script.len() + conversion_len));
+                }
+            }
+        }
+        candidates
+    }
+
+    /// Hash the top `num_bytes` bytes of the stack.
+    /// Input: message bytes, first byte on top (as in `sha256::sha256`)
+    /// Output: 32 digest bytes, first byte on top
+    pub fn script(&self, num_bytes: usize) -> Script {
+        match *self {
+            Sha256Backend::U32 => sha256(num_bytes),
+            Sha256Backend::U4Stack { .. } => {
+                let (hash_script, _) = self.u4_stack_script(num_bytes);
+                script! {
+                    { bytes_to_nibbles(num_bytes) }
+                    { hash_script }
+                    { nibbles_to_bytes(32) }
+                }
+            }
+        }
+    }
+
+    /// Returns the u4 hash script and its maximum stack size
+    fn u4_stack_script(&self, num_bytes: usize) -> (Script, u32) {
+        match *self {
+            Sha256Backend::U32 => unreachable!("not a stack tracker backend"),
+            Sha256Backend::U4Stack {
+                use_add_table,
+                use_full_xor,
+            } => {
+                let mut stack = StackTracker::new();
+                let script =
+                    sha256_stack(&mut stack, num_bytes as u32, use_add_table, use_full_xor);
+                (script, stack.get_max_stack_size())
+            }
+        }
+    }
+}
+
+/// Picks the backend with the smallest script for a message of `num_bytes` that stays within
+/// the stack limit, if any.
+pub fn try_cheapest_sha256_backend(num_bytes: usize) -> Option<Sha256Backend> {
+    if let Some(backend) = CHEAPEST_BACKENDS.lock().unwrap().get(&num_bytes) {
+        return *backend;
+    }
+    let backend = Sha256Backend::measured_candidates(num_bytes)
+        .into_iter()
+        .min_by_key(|(_, len)| *len)
+        .map(|(backend, _)| backend);
+    CHEAPEST_BACKENDS.lock().unwrap().insert(num_bytes, backend);
+    backend
+}
+
+/// Picks the backend with the smallest script for a message of `num_bytes` that stays within
+/// the stack limit.
+pub fn cheapest_sha256_backend(num_bytes: usize) -> Sha256Backend {
+    try_cheapest_sha256_backend(num_bytes).unwrap_or_else(|| {
+        panic!(
+            "no sha256 backend can hash {} bytes in one script",
+            num_bytes
+        )
+    })
+}
+
+/// sha256 of a message of any length, using the cheapest backend.
+/// Input and output are encoded as in `sha256::sha256`.
+/// Messages that don't fit on the stack have to be streamed with `sha256_stream_update` and
+/// `sha256_stream_finalize`.
+pub fn sha256_var_length(num_bytes: usize) -> Script {
+    cheapest_sha256_backend(num_bytes).script(num_bytes)
+}
+
+/// Compress `num_blocks` 64-byte blocks of a longer message, so that it can be split across
+/// several scripts.
+/// Input: message nibbles (first nibble deepest) and, if `from_midstate`, the 64 midstate nibbles
+/// Output: the 64 midstate nibbles, see `sha256_u4_stack::sha256_midstate`
+pub fn sha256_stream_update(num_blocks: u32, from_midstate: bool) -> Script {
+    let mut stack = StackTracker::new();
+    sha256_stack_update(&mut stack, num_blocks, from_midstate, false, true)
+}
+
+/// Hash the last `num_bytes` of a streamed message of `total_bytes`.
+/// Input: message nibbles and the 64 midstate nibbles of the first `total_bytes - num_bytes`
+/// Output: the 64 digest nibbles, first nibble deepest
+pub fn sha256_stream_finalize(num_bytes: u32, total_bytes: u32) -> Script {
+    let mut stack = StackTracker::new();
+    sha256_stack_finalize(&mut stack, num_bytes, total_bytes, false, true)
+}
+
+/// Split the top `num_bytes` bytes (first byte on top) into nibbles (first nibble deepest)
+pub fn bytes_to_nibbles(num_bytes: usize) -> Script {
+    script! {
+        for i in 1..num_bytes {
+            {i} OP_ROLL
+        }
+        for _ in 0..num_bytes {
+            0 OP_SWAP
+            for bit in (4..8).rev() {
+                {1 << bit}
+                OP_2DUP
+                OP_GREATERTHANOREQUAL
+                OP_IF
+                    OP_SUB
+                    OP_SWAP
+                    {1 << (bit - 4)}
+                    OP_ADD
+                    OP_SWAP
+                OP_ELSE
+                    OP_DROP
+                OP_ENDIF
+            }
+            OP_TOALTSTACK
+            OP_TOALTSTACK
+        }
+        for _ in 0..2 * num_bytes {
+            OP_FROMALTSTACK
+        }
+    }
+}
+
+/// Join the top `2 * num_bytes` nibbles (first nibble deepest) into bytes (first byte on top)
+pub fn nibbles_to_bytes(num_bytes: usize) -> Script {
+    script! {
+        for _ in 0..num_bytes {
+            OP_SWAP
+            for _ in 0..4 {
+                OP_DUP OP_ADD
+            }
+            OP_ADD
+            OP_TOALTSTACK
+        }
+        for _ in 0..num_bytes {
+            OP_FROMALTSTACK
+        }
+        for i in 1..num_bytes {
+            {i} OP_ROLL
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execute_script;
+    use crate::hash::blake3::push_bytes_hex;
+    use crate::hash::sha256_u4_stack::sha256_midstate;
+    use crate::u4::u4_std::u4_hex_to_nibbles;
+    use sha2::{Digest, Sha256};
+
+    use serde::Deserialize;
+    use std::fs::File;
+    use std::io::BufReader;
+
+    #[derive(Debug, Deserialize)]
+    struct TestVectors {
+        cases: Vec<TestCase>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TestCase {
+        len: usize,
+        msg: String,
+        md: String,
+    }
+
+    fn read_test_vectors() -> TestVectors {
+        // Examples of FIPS 180-4 and the byte-oriented vectors of the NIST CAVP SHA256ShortMsg
+        let file = File::open("src/hash/sha256_nist_test_vectors.json").unwrap();
+        serde_json::from_reader(BufReader::new(file)).unwrap()
+    }
+
+    fn check_digest_bytes(expected_hex: &str) -> Script {
+        script! {
+            {push_bytes_hex(expected_hex)}
+            for _ in 0..32 {
+                OP_TOALTSTACK
+            }
+            for i in 1..32 {
+                {i}
+                OP_ROLL
+            }
+            for _ in 0..32 {
+                OP_FROMALTSTACK
+                OP_EQUALVERIFY
+            }
+        }
+    }
+
+    fn check_nibbles(expected_hex: &str) -> Script {
+        script! {
+            {u4_hex_to_nibbles(expected_hex)}
+            for i in (2..65).rev() {
+                {i}
+                OP_ROLL
+                OP_EQUALVERIFY
+            }
+            OP_EQUALVERIFY
+        }
+    }
+
+    fn midstate_hex(state: [u32; 8]) -> String {
+        state.iter().map(|word| format!("{:08x}", word)).collect()
+    }
+
+    #[test]
+    fn test_nist_vectors() {
+        for case in read_test_vectors().cases {
+            let num_bytes = case.len / 8;
+            let msg = if num_bytes == 0 {
+                ""
+            } else {
+                case.msg.as_str()
+            };
+            for backend in Sha256Backend::candidates(num_bytes) {
+                let script = script! {
+                    {push_bytes_hex(msg)}
+                    {backend.script(num_bytes)}
+                    {check_digest_bytes(&case.md)}
+                    OP_TRUE
+                };
+                let res = execute_script(script);
+                assert!(res.success, "{:?} failed for {} bytes", backend, num_bytes);
+            }
+        }
+    }
+
+    #[test]
+    fn test_cheapest_backend() {
+        for num_bytes in [0, 32, 55, 64, 80, 200] {
+            let backend = cheapest_sha256_backend(num_bytes);
+            let len = sha256_var_length(num_bytes).len();
+            println!("sha256({}): {:?} with {} bytes", num_bytes, backend, len);
+
+            let data: Vec<u8> = (0..num_bytes).map(|i| i as u8).collect();
+            let script = script! {
+                {push_bytes_hex(&hex::encode(&data))}
+                {sha256_var_length(num_bytes)}
+                {check_digest_bytes(&hex::encode(Sha256::digest(&data)))}
+                OP_TRUE
+            };
+            assert!(execute_script(script).success);
+        }
+    }
+
+    #[test]
+    fn test_byte_nibble_conversion() {
+        let hex_in = "00ff1080f7a5";
+        let script = script! {
+            {push_bytes_hex(hex_in)}
+            {bytes_to_nibbles(6)}
+            {nibbles_to_bytes(6)}
+            {push_bytes_hex(hex_in)}
+            for i in (2..7).rev() {
+                {i}
+                OP_ROLL
+                OP_EQUALVERIFY
+            }
+            OP_EQUAL
+        };
+        assert!(execute_script(script).success);
+    }
+
+    #[test]
+    fn test_streaming() {
+        let data: Vec<u8> = (0..150u32).map(|i| (i * 7 + 3) as u8).collect();
+        let (first, rest) = data.split_at(64);
+        let (second, tail) = rest.split_at(64);
+
+        // first block from the initial state
+        let midstate_1 = sha256_midstate(first);
+        let script = script! {
+            {u4_hex_to_nibbles(&hex::encode(first))}
+            {sha256_stream_update(1, false)}
+            {check_nibbles(&midstate_hex(midstate_1))}
+            OP_TRUE
+        };
+        assert!(execute_script(script).success);
+
+        // second block from the committed midstate
+        let midstate_2 = sha256_midstate(&data[..128]);
+        let script = script! {
+            {u4_hex_to_nibbles(&hex::encode(second))}
+            {u4_hex_to_nibbles(&midstate_hex(midstate_1))}
+            {sha256_stream_update(1, true)}
+            {check_nibbles(&midstate_hex(midstate_2))}
+            OP_TRUE
+        };
+        assert!(execute_script(script).success);
+
+        // the remaining bytes and the padding
+        let script = script! {
+            {u4_hex_to_nibbles(&hex::encode(tail))}
+            {u4_hex_to_nibbles(&midstate_hex(midstate_2))}
+            {sha256_stream_finalize(tail.len() as u32, data.len() as u32)}
+            {check_nibbles(&hex::encode(Sha256::digest(&data)))}
+            OP_TRUE
+        };
+        assert!(execute_script(script).success);
+    }
+
+    #[test]
+    fn test_streaming_full_block_tail() {
+        let data: Vec<u8> = (0..128u32).map(|i| (i * 13) as u8).collect();
+        let midstate = sha256_midstate(&data[..64]);
+        let script = script! {
+            {u4_hex_to_nibbles(&hex::encode(&data[64..]))}
+            {u4_hex_to_nibbles(&midstate_hex(midstate))}
+            {sha256_stream_finalize(64, 128)}
+            {check_nibbles(&hex::encode(Sha256::digest(&data)))}
+            OP_TRUE
+        };
+        assert!(execute_script(script).success);
+    }
+}
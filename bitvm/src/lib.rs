@@ -18,11 +18,14 @@ use bitcoin::{
 use bitcoin_scriptexec::{Exec, ExecCtx, ExecError, ExecStats, Options, Stack, TxTemplate};
 
 pub mod bigint;
+pub mod bisection;
 pub mod bn254;
+pub mod chain;
 pub mod chunker;
 pub mod groth16;
 pub mod hash;
 pub mod pseudo;
+pub mod riscv;
 pub mod signatures;
 pub mod u32;
 pub mod u4;
@@ -176,7 +179,7 @@ pub fn dry_run_taproot_input(
         LeafVersion::TapScript,
     );
 
-    let mut exec = Exec::new(
+    let exec = Exec::new(
         ExecCtx::Tapscript,
         Options::default(),
         TxTemplate {
@@ -190,22 +193,50 @@ pub fn dry_run_taproot_input(
     )
     .expect("error creating exec");
 
+    run_input(exec)
+}
+
+/// Dry-runs a specific P2WSH input, the witness script being the last element of its witness
+pub fn dry_run_p2wsh_input(
+    tx: &Transaction,
+    input_index: usize,
+    prevouts: &[TxOut],
+) -> ExecuteInfo {
+    let mut stack = tx.input[input_index].witness.to_vec();
+    let script = ScriptBuf::from_bytes(stack.pop().unwrap());
+
+    let exec = Exec::new(
+        ExecCtx::SegwitV0,
+        Options::default(),
+        TxTemplate {
+            tx: tx.clone(),
+            prevouts: prevouts.into(),
+            input_idx: input_index,
+            taproot_annex_scriptleaf: None,
+        },
+        script,
+        stack,
+    )
+    .expect("error creating exec");
+
+    run_input(exec)
+}
+
+fn run_input(mut exec: Exec) -> ExecuteInfo {
     loop {
         if exec.exec_next().is_err() {
             break;
         }
     }
     let res = exec.result().unwrap();
-    let info = ExecuteInfo {
+    ExecuteInfo {
         success: res.success,
         error: res.error.clone(),
         last_opcode: res.opcode,
         final_stack: FmtStack(exec.stack().clone()),
         remaining_script: exec.remaining_script().to_asm_string(),
         stats: exec.stats().clone(),
-    };
-
-    info
+    }
 }
 
 /// Dry-runs all taproot input scripts. Return Ok(()) if all scripts execute successfully,
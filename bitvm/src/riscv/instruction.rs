@@ -0,0 +1,676 @@
+/// Register-register and register-immediate operations of RV32IM
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AluOp {
+    Add,
+    Sub,
+    Sll,
+    Slt,
+    Sltu,
+    Xor,
+    Srl,
+    Sra,
+    Or,
+    And,
+    Mul,
+    Mulh,
+    Mulhsu,
+    Mulhu,
+    Div,
+    Divu,
+    Rem,
+    Remu,
+}
+
+impl AluOp {
+    /// Result of the operation as specified by RV32IM
+    pub fn apply(&self, a: u32, b: u32) -> u32 {
+        match self {
+            AluOp::Add => a.wrapping_add(b),
+            AluOp::Sub => a.wrapping_sub(b),
+            AluOp::Sll => a << (b & 31),
+            AluOp::Slt => ((a as i32) < (b as i32)) as u32,
+            AluOp::Sltu => (a < b) as u32,
+            AluOp::Xor => a ^ b,
+            AluOp::Srl => a >> (b & 31),
+            AluOp::Sra => ((a as i32) >> (b & 31)) as u32,
+            AluOp::Or => a | b,
+            AluOp::And => a & b,
+            AluOp::Mul => a.wrapping_mul(b),
+            AluOp::Mulh => ((a as i32 as i64 * b as i32 as i64) >> 32) as u32,
+            AluOp::Mulhsu => ((a as i32 as i64 * b as i64) >> 32) as u32,
+            AluOp::Mulhu => ((a as u64 * b as u64) >> 32) as u32,
+            AluOp::Div => match b {
+                0 => u32::MAX,
+                _ => (a as i32).wrapping_div(b as i32) as u32,
+            },
+            AluOp::Divu => a.checked_div(b).unwrap_or(u32::MAX),
+            AluOp::Rem => match b {
+                0 => a,
+                _ => (a as i32).wrapping_rem(b as i32) as u32,
+            },
+            AluOp::Remu => a.checked_rem(b).unwrap_or(a),
+        }
+    }
+
+    /// Whether the operation is verified against a hinted quotient and remainder
+    pub fn is_division(&self) -> bool {
+        matches!(self, AluOp::Div | AluOp::Divu | AluOp::Rem | AluOp::Remu)
+    }
+
+    /// Whether the operation treats its operands as signed numbers when dividing
+    pub fn is_signed_division(&self) -> bool {
+        matches!(self, AluOp::Div | AluOp::Rem)
+    }
+
+    /// The quotient and remainder hints of a division, which are calculated on the absolute
+    /// values for the signed operations. Division by zero has no hints to verify.
+    pub fn division_hints(&self, a: u32, b: u32) -> (u32, u32) {
+        assert!(self.is_division(), "{:?} is not a division", self);
+        let (a, b) = if self.is_signed_division() {
+            ((a as i32).unsigned_abs(), (b as i32).unsigned_abs())
+        } else {
+            (a, b)
+        };
+        match b {
+            0 => (0, 0),
+            _ => (a / b, a % b),
+        }
+    }
+
+    fn funct3_funct7(&self) -> (u32, u32) {
+        match self {
+            AluOp::Add => (0, 0x00),
+            AluOp::Sub => (0, 0x20),
+            AluOp::Sll => (1, 0x00),
+            AluOp::Slt => (2, 0x00),
+            AluOp::Sltu => (3, 0x00),
+            AluOp::Xor => (4, 0x00),
+            AluOp::Srl => (5, 0x00),
+            AluOp::Sra => (5, 0x20),
+            AluOp::Or => (6, 0x00),
+            AluOp::And => (7, 0x00),
+            AluOp::Mul => (0, 0x01),
+            AluOp::Mulh => (1, 0x01),
+            AluOp::Mulhsu => (2, 0x01),
+            AluOp::Mulhu => (3, 0x01),
+            AluOp::Div => (4, 0x01),
+            AluOp::Divu => (5, 0x01),
+            AluOp::Rem => (6, 0x01),
+            AluOp::Remu => (7, 0x01),
+        }
+    }
+
+    fn from_funct3_funct7(funct3: u32, funct7: u32) -> Option<Self> {
+        [
+            AluOp::Add,
+            AluOp::Sub,
+            AluOp::Sll,
+            AluOp::Slt,
+            AluOp::Sltu,
+            AluOp::Xor,
+            AluOp::Srl,
+            AluOp::Sra,
+            AluOp::Or,
+            AluOp::And,
+            AluOp::Mul,
+            AluOp::Mulh,
+            AluOp::Mulhsu,
+            AluOp::Mulhu,
+            AluOp::Div,
+            AluOp::Divu,
+            AluOp::Rem,
+            AluOp::Remu,
+        ]
+        .into_iter()
+        .find(|op| op.funct3_funct7() == (funct3, funct7))
+    }
+}
+
+/// Conditions of the branch instructions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BranchOp {
+    Eq,
+    Ne,
+    Lt,
+    Ge,
+    Ltu,
+    Geu,
+}
+
+impl BranchOp {
+    /// Whether the branch is taken
+    pub fn taken(&self, a: u32, b: u32) -> bool {
+        match self {
+            BranchOp::Eq => a == b,
+            BranchOp::Ne => a != b,
+            BranchOp::Lt => (a as i32) < (b as i32),
+            BranchOp::Ge => (a as i32) >= (b as i32),
+            BranchOp::Ltu => a < b,
+            BranchOp::Geu => a >= b,
+        }
+    }
+
+    fn funct3(&self) -> u32 {
+        match self {
+            BranchOp::Eq => 0,
+            BranchOp::Ne => 1,
+            BranchOp::Lt => 4,
+            BranchOp::Ge => 5,
+            BranchOp::Ltu => 6,
+            BranchOp::Geu => 7,
+        }
+    }
+}
+
+/// Widths of the load instructions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoadOp {
+    Lb,
+    Lh,
+    Lw,
+    Lbu,
+    Lhu,
+}
+
+impl LoadOp {
+    /// Number of bytes read
+    pub fn width(&self) -> u32 {
+        match self {
+            LoadOp::Lb | LoadOp::Lbu => 1,
+            LoadOp::Lh | LoadOp::Lhu => 2,
+            LoadOp::Lw => 4,
+        }
+    }
+
+    /// Whether the loaded value is sign extended
+    pub fn is_signed(&self) -> bool {
+        matches!(self, LoadOp::Lb | LoadOp::Lh)
+    }
+
+    /// The value loaded from the byte `offset` of the aligned memory word
+    pub fn extract(&self, word: u32, offset: u32) -> u32 {
+        let bits = self.width() * 8;
+        let value = (word >> (offset * 8)) as u64 & ((1u64 << bits) - 1);
+        if self.is_signed() && value >> (bits - 1) == 1 {
+            (value as u32) | (u32::MAX << (bits - 1))
+        } else {
+            value as u32
+        }
+    }
+
+    fn funct3(&self) -> u32 {
+        match self {
+            LoadOp::Lb => 0,
+            LoadOp::Lh => 1,
+            LoadOp::Lw => 2,
+            LoadOp::Lbu => 4,
+            LoadOp::Lhu => 5,
+        }
+    }
+}
+
+/// Widths of the store instructions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StoreOp {
+    Sb,
+    Sh,
+    Sw,
+}
+
+impl StoreOp {
+    /// Number of bytes written
+    pub fn width(&self) -> u32 {
+        match self {
+            StoreOp::Sb => 1,
+            StoreOp::Sh => 2,
+            StoreOp::Sw => 4,
+        }
+    }
+
+    /// The aligned memory word after writing the low bytes of `value` at the byte `offset`
+    pub fn merge(&self, word: u32, value: u32, offset: u32) -> u32 {
+        let mask = (((1u64 << (self.width() * 8)) - 1) as u32) << (offset * 8);
+        (word & !mask) | ((value << (offset * 8)) & mask)
+    }
+
+    fn funct3(&self) -> u32 {
+        match self {
+            StoreOp::Sb => 0,
+            StoreOp::Sh => 1,
+            StoreOp::Sw => 2,
+        }
+    }
+}
+
+/// A decoded RV32IM instruction
+/// The immediates are sign extended, `OpImm` shifts keep the shift amount in `imm`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    Lui {
+        rd: u8,
+        imm: u32,
+    },
+    Auipc {
+        rd: u8,
+        imm: u32,
+    },
+    Jal {
+        rd: u8,
+        imm: i32,
+    },
+    Jalr {
+        rd: u8,
+        rs1: u8,
+        imm: i32,
+    },
+    Branch {
+        op: BranchOp,
+        rs1: u8,
+        rs2: u8,
+        imm: i32,
+    },
+    Load {
+        op: LoadOp,
+        rd: u8,
+        rs1: u8,
+        imm: i32,
+    },
+    Store {
+        op: StoreOp,
+        rs1: u8,
+        rs2: u8,
+        imm: i32,
+    },
+    OpImm {
+        op: AluOp,
+        rd: u8,
+        rs1: u8,
+        imm: i32,
+    },
+    Op {
+        op: AluOp,
+        rd: u8,
+        rs1: u8,
+        rs2: u8,
+    },
+    Fence,
+    Ecall,
+    Ebreak,
+}
+
+fn bits(word: u32, high: u32, low: u32) -> u32 {
+    (word >> low) & ((1 << (high - low + 1)) - 1)
+}
+
+fn sign_extend(value: u32, width: u32) -> i32 {
+    ((value << (32 - width)) as i32) >> (32 - width)
+}
+
+impl Instruction {
+    /// Decodes the instruction, returns None for encodings outside of RV32IM
+    pub fn decode(word: u32) -> Option<Self> {
+        let rd = bits(word, 11, 7) as u8;
+        let rs1 = bits(word, 19, 15) as u8;
+        let rs2 = bits(word, 24, 20) as u8;
+        let funct3 = bits(word, 14, 12);
+        let funct7 = bits(word, 31, 25);
+        let imm_i = sign_extend(bits(word, 31, 20), 12);
+        let imm_s = sign_extend((funct7 << 5) | rd as u32, 12);
+        let imm_b = sign_extend(
+            (bits(word, 31, 31) << 12)
+                | (bits(word, 7, 7) << 11)
+                | (bits(word, 30, 25) << 5)
+                | (bits(word, 11, 8) << 1),
+            13,
+        );
+        let imm_j = sign_extend(
+            (bits(word, 31, 31) << 20)
+                | (bits(word, 19, 12) << 12)
+                | (bits(word, 20, 20) << 11)
+                | (bits(word, 30, 21) << 1),
+            21,
+        );
+
+        let instruction = match bits(word, 6, 0) {
+            0x37 => Instruction::Lui {
+                rd,
+                imm: word & 0xfffff000,
+            },
+            0x17 => Instruction::Auipc {
+                rd,
+                imm: word & 0xfffff000,
+            },
+            0x6f => Instruction::Jal { rd, imm: imm_j },
+            0x67 if funct3 == 0 => Instruction::Jalr {
+                rd,
+                rs1,
+                imm: imm_i,
+            },
+            0x63 => {
+                let op = [
+                    BranchOp::Eq,
+                    BranchOp::Ne,
+                    BranchOp::Lt,
+                    BranchOp::Ge,
+                    BranchOp::Ltu,
+                    BranchOp::Geu,
+                ]
+                .into_iter()
+                .find(|op| op.funct3() == funct3)?;
+                Instruction::Branch {
+                    op,
+                    rs1,
+                    rs2,
+                    imm: imm_b,
+                }
+            }
+            0x03 => {
+                let op = [LoadOp::Lb, LoadOp::Lh, LoadOp::Lw, LoadOp::Lbu, LoadOp::Lhu]
+                    .into_iter()
+                    .find(|op| op.funct3() == funct3)?;
+                Instruction::Load {
+                    op,
+                    rd,
+                    rs1,
+                    imm: imm_i,
+                }
+            }
+            0x23 => {
+                let op = [StoreOp::Sb, StoreOp::Sh, StoreOp::Sw]
+                    .into_iter()
+                    .find(|op| op.funct3() == funct3)?;
+                Instruction::Store {
+                    op,
+                    rs1,
+                    rs2,
+                    imm: imm_s,
+                }
+            }
+            0x13 => {
+                let op = match funct3 {
+                    1 | 5 => AluOp::from_funct3_funct7(funct3, funct7)?,
+                    _ => AluOp::from_funct3_funct7(funct3, 0)?,
+                };
+                let imm = match op {
+                    AluOp::Sll | AluOp::Srl | AluOp::Sra => rs2 as i32,
+                    _ => imm_i,
+                };
+                Instruction::OpImm { op, rd, rs1, imm }
+            }
+            0x33 => Instruction::Op {
+                op: AluOp::from_funct3_funct7(funct3, funct7)?,
+                rd,
+                rs1,
+                rs2,
+            },
+            0x0f => Instruction::Fence,
+            0x73 if word == 0x00000073 => Instruction::Ecall,
+            0x73 if word == 0x00100073 => Instruction::Ebreak,
+            _ => return None,
+        };
+        match instruction {
+            // the M extension has no immediate variants
+            Instruction::OpImm { op, .. } if op.funct3_funct7().1 == 0x01 => None,
+            _ => Some(instruction),
+        }
+    }
+
+    /// Encodes the instruction
+    pub fn encode(&self) -> u32 {
+        let r = |funct7: u32, rs2: u8, rs1: u8, funct3: u32, rd: u8, opcode: u32| {
+            (funct7 << 25)
+                | ((rs2 as u32) << 20)
+                | ((rs1 as u32) << 15)
+                | (funct3 << 12)
+                | ((rd as u32) << 7)
+                | opcode
+        };
+        let i = |imm: i32, rs1: u8, funct3: u32, rd: u8, opcode: u32| {
+            ((imm as u32 & 0xfff) << 20)
+                | ((rs1 as u32) << 15)
+                | (funct3 << 12)
+                | ((rd as u32) << 7)
+                | opcode
+        };
+        match *self {
+            Instruction::Lui { rd, imm } => (imm & 0xfffff000) | ((rd as u32) << 7) | 0x37,
+            Instruction::Auipc { rd, imm } => (imm & 0xfffff000) | ((rd as u32) << 7) | 0x17,
+            Instruction::Jal { rd, imm } => {
+                let imm = imm as u32;
+                (bits(imm, 20, 20) << 31)
+                    | (bits(imm, 10, 1) << 21)
+                    | (bits(imm, 11, 11) << 20)
+                    | (bits(imm, 19, 12) << 12)
+                    | ((rd as u32) << 7)
+                    | 0x6f
+            }
+            Instruction::Jalr { rd, rs1, imm } => i(imm, rs1, 0, rd, 0x67),
+            Instruction::Branch { op, rs1, rs2, imm } => {
+                let imm = imm as u32;
+                r(
+                    (bits(imm, 12, 12) << 6) | bits(imm, 10, 5),
+                    rs2,
+                    rs1,
+                    op.funct3(),
+                    ((bits(imm, 4, 1) << 1) | bits(imm, 11, 11)) as u8,
+                    0x63,
+                )
+            }
+            Instruction::Load { op, rd, rs1, imm } => i(imm, rs1, op.funct3(), rd, 0x03),
+            Instruction::Store { op, rs1, rs2, imm } => {
+                let imm = imm as u32;
+                r(
+                    bits(imm, 11, 5),
+                    rs2,
+                    rs1,
+                    op.funct3(),
+                    bits(imm, 4, 0) as u8,
+                    0x23,
+                )
+            }
+            Instruction::OpImm { op, rd, rs1, imm } => {
+                let (funct3, funct7) = op.funct3_funct7();
+                match op {
+                    AluOp::Sll | AluOp::Srl | AluOp::Sra => {
+                        r(funct7, imm as u8 & 31, rs1, funct3, rd, 0x13)
+                    }
+                    _ => i(imm, rs1, funct3, rd, 0x13),
+                }
+            }
+            Instruction::Op { op, rd, rs1, rs2 } => {
+                let (funct3, funct7) = op.funct3_funct7();
+                r(funct7, rs2, rs1, funct3, rd, 0x33)
+            }
+            Instruction::Fence => 0x0000000f,
+            Instruction::Ecall => 0x00000073,
+            Instruction::Ebreak => 0x00100073,
+        }
+    }
+
+    /// The first source register if it is read, x0 reads as zero and is never read
+    pub fn rs1(&self) -> Option<u8> {
+        match *self {
+            Instruction::Jalr { rs1, .. }
+            | Instruction::Branch { rs1, .. }
+            | Instruction::Load { rs1, .. }
+            | Instruction::Store { rs1, .. }
+            | Instruction::OpImm { rs1, .. }
+            | Instruction::Op { rs1, .. } => Some(rs1).filter(|&r| r != 0),
+            _ => None,
+        }
+    }
+
+    /// The second source register if it is read
+    pub fn rs2(&self) -> Option<u8> {
+        match *self {
+            Instruction::Branch { rs2, .. }
+            | Instruction::Store { rs2, .. }
+            | Instruction::Op { rs2, .. } => Some(rs2).filter(|&r| r != 0),
+            _ => None,
+        }
+    }
+
+    /// The destination register if it is written, writes to x0 are discarded
+    pub fn rd(&self) -> Option<u8> {
+        match *self {
+            Instruction::Lui { rd, .. }
+            | Instruction::Auipc { rd, .. }
+            | Instruction::Jal { rd, .. }
+            | Instruction::Jalr { rd, .. }
+            | Instruction::Load { rd, .. }
+            | Instruction::OpImm { rd, .. }
+            | Instruction::Op { rd, .. } => Some(rd).filter(|&r| r != 0),
+            _ => None,
+        }
+    }
+
+    /// Whether the instruction accesses the data memory
+    pub fn accesses_memory(&self) -> bool {
+        matches!(self, Instruction::Load { .. } | Instruction::Store { .. })
+    }
+
+    /// Whether the instruction halts the machine
+    /// The environment calls are not supported, the machine halts on them with its state unchanged
+    pub fn is_halt(&self) -> bool {
+        matches!(self, Instruction::Ecall | Instruction::Ebreak)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode() {
+        let instructions = [
+            Instruction::Lui {
+                rd: 5,
+                imm: 0x12345000,
+            },
+            Instruction::Auipc {
+                rd: 1,
+                imm: 0xfffff000,
+            },
+            Instruction::Jal { rd: 1, imm: -2048 },
+            Instruction::Jal {
+                rd: 0,
+                imm: 0xffffe,
+            },
+            Instruction::Jalr {
+                rd: 0,
+                rs1: 1,
+                imm: -4,
+            },
+            Instruction::Branch {
+                op: BranchOp::Geu,
+                rs1: 3,
+                rs2: 4,
+                imm: -4096,
+            },
+            Instruction::Branch {
+                op: BranchOp::Ne,
+                rs1: 31,
+                rs2: 0,
+                imm: 4094,
+            },
+            Instruction::Load {
+                op: LoadOp::Lhu,
+                rd: 7,
+                rs1: 2,
+                imm: -1,
+            },
+            Instruction::Store {
+                op: StoreOp::Sb,
+                rs1: 2,
+                rs2: 9,
+                imm: 2047,
+            },
+            Instruction::OpImm {
+                op: AluOp::Sra,
+                rd: 8,
+                rs1: 8,
+                imm: 31,
+            },
+            Instruction::OpImm {
+                op: AluOp::Sltu,
+                rd: 8,
+                rs1: 9,
+                imm: -2048,
+            },
+            Instruction::Op {
+                op: AluOp::Mulhsu,
+                rd: 10,
+                rs1: 11,
+                rs2: 12,
+            },
+            Instruction::Op {
+                op: AluOp::Sub,
+                rd: 10,
+                rs1: 11,
+                rs2: 12,
+            },
+            Instruction::Fence,
+            Instruction::Ecall,
+            Instruction::Ebreak,
+        ];
+        for instruction in instructions {
+            assert_eq!(Instruction::decode(instruction.encode()), Some(instruction));
+        }
+        // addi a0, a0, -1
+        assert_eq!(
+            Instruction::decode(0xfff50513),
+            Some(Instruction::OpImm {
+                op: AluOp::Add,
+                rd: 10,
+                rs1: 10,
+                imm: -1
+            })
+        );
+        // mul a0, a1, a2
+        assert_eq!(
+            Instruction::decode(0x02c58533),
+            Some(Instruction::Op {
+                op: AluOp::Mul,
+                rd: 10,
+                rs1: 11,
+                rs2: 12
+            })
+        );
+        // the M extension has no immediate variants
+        assert_eq!(Instruction::decode(0x02c5d513), None);
+        assert_eq!(Instruction::decode(0), None);
+    }
+
+    #[test]
+    fn test_alu_ops() {
+        let min = i32::MIN as u32;
+        assert_eq!(AluOp::Sra.apply(0x80000000, 4), 0xf8000000);
+        assert_eq!(AluOp::Sll.apply(1, 33), 2);
+        assert_eq!(AluOp::Mulh.apply(-2i32 as u32, 3), u32::MAX);
+        assert_eq!(AluOp::Mulhsu.apply(-1i32 as u32, u32::MAX), u32::MAX);
+        assert_eq!(AluOp::Mulhu.apply(u32::MAX, u32::MAX), 0xfffffffe);
+        assert_eq!(AluOp::Div.apply(-7i32 as u32, 2), -3i32 as u32);
+        assert_eq!(AluOp::Rem.apply(-7i32 as u32, 2), -1i32 as u32);
+        assert_eq!(AluOp::Div.apply(min, u32::MAX), min);
+        assert_eq!(AluOp::Rem.apply(min, u32::MAX), 0);
+        assert_eq!(AluOp::Divu.apply(5, 0), u32::MAX);
+        assert_eq!(AluOp::Rem.apply(5, 0), 5);
+        assert_eq!(
+            AluOp::Div.division_hints(min, 3),
+            (0x80000000 / 3, 0x80000000 % 3)
+        );
+    }
+
+    #[test]
+    fn test_memory_ops() {
+        let word = 0x8070f0ff;
+        assert_eq!(LoadOp::Lb.extract(word, 0), 0xffffffff);
+        assert_eq!(LoadOp::Lbu.extract(word, 1), 0xf0);
+        assert_eq!(LoadOp::Lh.extract(word, 2), 0xffff8070);
+        assert_eq!(LoadOp::Lhu.extract(word, 0), 0xf0ff);
+        assert_eq!(LoadOp::Lw.extract(word, 0), word);
+        assert_eq!(StoreOp::Sb.merge(word, 0x12, 3), 0x1270f0ff);
+        assert_eq!(StoreOp::Sh.merge(word, 0xabcd1234, 0), 0x80701234);
+        assert_eq!(StoreOp::Sw.merge(word, 7, 0), 7);
+    }
+}
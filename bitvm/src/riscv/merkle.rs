@@ -0,0 +1,396 @@
+use crate::hash::blake3_u4::blake3;
+use crate::treepp::*;
+use bitcoin_script_stack::stack::StackTracker;
+
+/*
+    Commitments of the machine state, truncated blake3 digests that are hashed in script by blake3_u4.
+    Words and digests are given in the witness as limbs of up to 7 nibbles (first limb deepest) to keep the stack
+    small next to the hash tables, and are unpacked into nibbles (most significant nibble deepest) before the calculations.
+*/
+
+/// Number of bytes of a digest
+pub const DIGEST_LENGTH: usize = 20;
+/// Number of nibbles of an unpacked digest
+pub const DIGEST_NIBBLES: u32 = DIGEST_LENGTH as u32 * 2;
+/// Number of nibbles of a limb of a packed value, so that the limbs are positive script numbers
+const LIMB_NIBBLES: u32 = 7;
+/// Number of limbs of a packed word
+pub const WORD_LIMBS: u32 = 8_u32.div_ceil(LIMB_NIBBLES);
+/// Number of limbs of a packed digest
+pub const DIGEST_LIMBS: u32 = DIGEST_NIBBLES.div_ceil(LIMB_NIBBLES);
+
+pub type Digest = [u8; DIGEST_LENGTH];
+
+/// The digest calculated by `hash_script` for the message bytes
+/// blake3_u4 reads the message as u32 words, which swaps the bytes of each group of four
+pub fn hash(bytes: &[u8]) -> Digest {
+    assert!(bytes.len() % 4 == 0, "the message should consist of words");
+    let message: Vec<u8> = bytes
+        .chunks(4)
+        .flat_map(|word| word.iter().rev().copied())
+        .collect();
+    blake3::hash(&message).as_bytes()[..DIGEST_LENGTH]
+        .try_into()
+        .unwrap()
+}
+
+/// The leaf of a word, the word itself padded with zeros so that leaves need no hashing
+pub fn leaf_digest(word: u32) -> Digest {
+    let mut leaf = [0; DIGEST_LENGTH];
+    leaf[..4].copy_from_slice(&word.to_be_bytes());
+    leaf
+}
+
+/// The digest of an inner node
+pub fn node_digest(left: &Digest, right: &Digest) -> Digest {
+    hash(&[*left, *right].concat())
+}
+
+/// Merkle tree over 2^depth words
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleTree {
+    words: Vec<u32>,
+    // the layers of the digests, from the leaves to the root
+    layers: Vec<Vec<Digest>>,
+}
+
+impl MerkleTree {
+    /// The tree of 2^depth zero words
+    pub fn new(depth: u32) -> Self {
+        Self::from_words(&vec![0; 1 << depth])
+    }
+
+    /// The tree of the words, whose number should be a power of two
+    pub fn from_words(words: &[u32]) -> Self {
+        assert!(
+            words.len().is_power_of_two(),
+            "the number of words should be a power of two"
+        );
+        let mut layers = vec![words
+            .iter()
+            .map(|&word| leaf_digest(word))
+            .collect::<Vec<_>>()];
+        while layers.last().unwrap().len() > 1 {
+            let layer = layers
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| node_digest(&pair[0], &pair[1]))
+                .collect();
+            layers.push(layer);
+        }
+        Self {
+            words: words.to_vec(),
+            layers,
+        }
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.layers.len() as u32 - 1
+    }
+
+    pub fn words(&self) -> &[u32] {
+        &self.words
+    }
+
+    pub fn get(&self, index: u32) -> u32 {
+        self.words[index as usize]
+    }
+
+    /// Updates the word and the digests on its path
+    pub fn set(&mut self, index: u32, word: u32) {
+        let mut index = index as usize;
+        self.words[index] = word;
+        self.layers[0][index] = leaf_digest(word);
+        for level in 1..self.layers.len() {
+            index /= 2;
+            let digest = node_digest(
+                &self.layers[level - 1][2 * index],
+                &self.layers[level - 1][2 * index + 1],
+            );
+            self.layers[level][index] = digest;
+        }
+    }
+
+    pub fn root(&self) -> Digest {
+        self.layers.last().unwrap()[0]
+    }
+
+    /// The siblings on the path of the word, from the leaf to the root
+    pub fn proof(&self, index: u32) -> Vec<Digest> {
+        let index = index as usize;
+        (0..self.depth() as usize)
+            .map(|level| self.layers[level][(index >> level) ^ 1])
+            .collect()
+    }
+}
+
+/// Number of nibbles of each limb of a packed value of n nibbles
+fn limb_sizes(n: u32) -> Vec<u32> {
+    (0..n)
+        .step_by(LIMB_NIBBLES as usize)
+        .map(|start| LIMB_NIBBLES.min(n - start))
+        .collect()
+}
+
+/// Pushes the nibbles packed into limbs
+pub fn push_packed(nibbles: &[u8]) -> Script {
+    script! {
+        for limb in nibbles.chunks(LIMB_NIBBLES as usize) {
+            { limb.iter().fold(0, |value, &nibble| (value << 4) | nibble as u32) }
+        }
+    }
+}
+
+fn bytes_to_nibbles(bytes: &[u8]) -> Vec<u8> {
+    bytes
+        .iter()
+        .flat_map(|byte| [byte >> 4, byte & 15])
+        .collect()
+}
+
+/// Pushes the packed word
+pub fn push_word(word: u32) -> Script {
+    push_packed(&bytes_to_nibbles(&word.to_be_bytes()))
+}
+
+/// Pushes the packed digest
+pub fn push_digest(digest: &Digest) -> Script {
+    push_packed(&bytes_to_nibbles(digest))
+}
+
+/// Pushes the siblings of a proof whose path is known by the script, the sibling of the root deepest
+pub fn push_static_proof(proof: &[Digest]) -> Script {
+    script! {
+        for sibling in proof.iter().rev() {
+            { push_digest(sibling) }
+        }
+    }
+}
+
+/// Pushes the siblings of a proof together with the direction bits of the index, the bit being on top of each sibling
+pub fn push_dynamic_proof(proof: &[Digest], index: u32) -> Script {
+    script! {
+        for (level, sibling) in proof.iter().enumerate().rev() {
+            { push_digest(sibling) }
+            { (index >> level) & 1 }
+        }
+    }
+}
+
+/// Unpacks the limbs of a packed value of n nibbles
+/// Fails if a limb is out of its range, so that the witness has a unique representation
+pub fn unpack(n: u32) -> Script {
+    let sizes = limb_sizes(n);
+    script! {
+        for _ in 0..sizes.len() {
+            OP_TOALTSTACK
+        }
+        for size in sizes {
+            OP_FROMALTSTACK
+            OP_DUP
+            0
+            { 1 << (4 * size) }
+            OP_WITHIN
+            OP_VERIFY
+            for shift in (1..size).rev().map(|nibble| 4 * nibble) {
+                OP_0
+                OP_SWAP
+                for bit in (shift..shift + 4).rev() {
+                    OP_DUP
+                    { 1 << bit }
+                    OP_GREATERTHANOREQUAL
+                    OP_IF
+                        { 1 << bit }
+                        OP_SUB
+                        OP_SWAP
+                        { 1 << (bit - shift) }
+                        OP_ADD
+                        OP_SWAP
+                    OP_ENDIF
+                }
+            }
+        }
+    }
+}
+
+/// Packs the top n nibbles into limbs
+pub fn pack(n: u32) -> Script {
+    let sizes = limb_sizes(n);
+    script! {
+        for &size in sizes.iter().rev() {
+            for _ in 1..size {
+                OP_TOALTSTACK
+            }
+            for _ in 1..size {
+                for _ in 0..4 {
+                    OP_DUP
+                    OP_ADD
+                }
+                OP_FROMALTSTACK
+                OP_ADD
+            }
+            OP_TOALTSTACK
+        }
+        for _ in 0..sizes.len() {
+            OP_FROMALTSTACK
+        }
+    }
+}
+
+/// Swaps the top two blocks of n elements
+pub fn swap_blocks(n: u32) -> Script {
+    script! {
+        for _ in 0..n {
+            { 2 * n - 1 }
+            OP_ROLL
+        }
+    }
+}
+
+/// Replaces the message nibbles of num_bytes bytes with the digest nibbles, as `hash`
+pub fn hash_script(num_bytes: u32) -> Script {
+    let mut stack = StackTracker::new();
+    blake3(&mut stack, num_bytes, (DIGEST_LENGTH / 4) as u8);
+    stack.get_script()
+}
+
+/// Replaces the nibbles of the word with the nibbles of its leaf
+pub fn leaf_script() -> Script {
+    script! {
+        for _ in 0..DIGEST_NIBBLES - 8 {
+            OP_0
+        }
+    }
+}
+
+/// Calculates the root from the leaf nibbles on top of the proof
+/// If the index is known by the script the proof is pushed by `push_static_proof`, otherwise by
+/// `push_dynamic_proof`, and the index given by the direction bits is kept on top of the root.
+pub fn merkle_root(depth: u32, index: Option<u32>) -> Script {
+    let entry_size = DIGEST_LIMBS + index.is_none() as u32;
+    script! {
+        if index.is_none() {
+            OP_0
+            OP_TOALTSTACK
+        }
+        for level in 0..depth {
+            for _ in 0..entry_size {
+                { DIGEST_NIBBLES + entry_size - 1 }
+                OP_ROLL
+            }
+            if let Some(index) = index {
+                { unpack(DIGEST_NIBBLES) }
+                if (index >> level) & 1 == 1 {
+                    { swap_blocks(DIGEST_NIBBLES) }
+                }
+            } else {
+                OP_DUP
+                0
+                2
+                OP_WITHIN
+                OP_VERIFY
+                OP_DUP
+                OP_IF
+                    OP_FROMALTSTACK
+                    { 1 << level }
+                    OP_ADD
+                    OP_TOALTSTACK
+                OP_ENDIF
+                OP_TOALTSTACK
+                { unpack(DIGEST_NIBBLES) }
+                OP_FROMALTSTACK
+                OP_IF
+                    { swap_blocks(DIGEST_NIBBLES) }
+                OP_ENDIF
+            }
+            { hash_script(2 * DIGEST_LENGTH as u32) }
+        }
+        if index.is_none() {
+            OP_FROMALTSTACK
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::u4::u4_std::{u4_hex_to_nibbles, u4_number_to_nibble, verify_n};
+
+    #[test]
+    fn test_hash() {
+        let left = leaf_digest(0x01234567);
+        let right = leaf_digest(0x89abcdef);
+        let script = script! {
+            { u4_hex_to_nibbles(&hex::encode(left)) }
+            { u4_hex_to_nibbles(&hex::encode(right)) }
+            { hash_script(2 * DIGEST_LENGTH as u32) }
+            { u4_hex_to_nibbles(&hex::encode(node_digest(&left, &right))) }
+            { verify_n(DIGEST_NIBBLES) }
+            OP_TRUE
+        };
+        run(script);
+    }
+
+    #[test]
+    fn test_pack_unpack() {
+        let digest = hash(&[7; 8]);
+        let script = script! {
+            { push_digest(&digest) }
+            { unpack(DIGEST_NIBBLES) }
+            { u4_hex_to_nibbles(&hex::encode(digest)) }
+            { verify_n(DIGEST_NIBBLES) }
+            { u4_number_to_nibble(0xfedc0ba9) }
+            { pack(8) }
+            { push_word(0xfedc0ba9) }
+            { verify_n(WORD_LIMBS) }
+            OP_TRUE
+        };
+        run(script);
+
+        let script = script! {
+            { 1 << 28 }
+            0
+            { unpack(8) }
+            OP_TRUE
+        };
+        assert!(!execute_script(script).success);
+    }
+
+    #[test]
+    fn test_merkle_root() {
+        let words: Vec<u32> = (0..8).map(|i| i * 0x1111_1111).collect();
+        let tree = MerkleTree::from_words(&words);
+        assert_eq!(tree.proof(5).len(), 3);
+        for index in [0, 5, 6] {
+            for dynamic in [false, true] {
+                let script = script! {
+                    if dynamic {
+                        { push_dynamic_proof(&tree.proof(index), index) }
+                    } else {
+                        { push_static_proof(&tree.proof(index)) }
+                    }
+                    { u4_number_to_nibble(words[index as usize]) }
+                    { leaf_script() }
+                    if dynamic {
+                        { merkle_root(tree.depth(), None) }
+                        { index }
+                        OP_EQUALVERIFY
+                    } else {
+                        { merkle_root(tree.depth(), Some(index)) }
+                    }
+                    { u4_hex_to_nibbles(&hex::encode(tree.root())) }
+                    { verify_n(DIGEST_NIBBLES) }
+                    OP_TRUE
+                };
+                run(script);
+            }
+        }
+
+        let mut updated = tree.clone();
+        updated.set(5, 1);
+        assert_ne!(updated.root(), tree.root());
+        updated.set(5, words[5]);
+        assert_eq!(updated, tree);
+    }
+}
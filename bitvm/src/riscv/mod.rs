@@ -0,0 +1,16 @@
+//! Verification of RV32IM programs by disproving a single instruction step.
+//!
+//! The state of the machine is committed by the hash of the pc and the Merkle roots of the registers and the data
+//! memory, see `trace::state_hash`. The program is separate from the data memory, so that each of its instructions
+//! has its own disprove script in `step`, checking a step from a committed pre state hash to a committed post state
+//! hash. The tracer in `trace` executes a program, records the state hashes and produces the witness of the disprove
+//! script of a faulty step.
+//!
+//! The memory has 2^depth words, each proof level costing 7 stack items next to the hash tables, so the depth is
+//! limited by the stack. Misaligned or out of range accesses and jumps outside of the program stop the execution
+//! instead of trapping, and the environment calls halt the machine.
+
+pub mod instruction;
+pub mod merkle;
+pub mod step;
+pub mod trace;
@@ -0,0 +1,1288 @@
+use super::instruction::{AluOp, BranchOp, Instruction, LoadOp, StoreOp};
+use super::merkle::{
+    hash_script, leaf_script, merkle_root, pack, push_digest, push_dynamic_proof,
+    push_static_proof, push_word, unpack, Digest, DIGEST_LENGTH, DIGEST_LIMBS, DIGEST_NIBBLES,
+    WORD_LIMBS,
+};
+use super::trace::REGISTERS_DEPTH;
+use crate::treepp::*;
+use crate::u4::u4_alu::U4Alu;
+use crate::u4::u4_std::{
+    u4_copy_u32_from, u4_drop, u4_fromaltstack, u4_move_u32_from, u4_toaltstack, verify_n,
+};
+use crate::u4::u4_tables::U4Table;
+
+/*
+    Disprove script of a single instruction step.
+    There is one script per instruction of the program, so the pc, the instruction and the registers it reads and writes are
+    known when generating the script, only the memory address is calculated from the registers.
+
+    Input: the witness pushed by `StepWitness::push`, then the committed pre state and post state hashes (post on the top)
+    Output: OP_TRUE if the pre state hash is the one of the witness and the post state hash differs from the result of the
+    instruction, i.e. if the committed post state is wrong. The script fails if the witness is inconsistent.
+*/
+
+/// Number of bytes of the message of a state hash: pc, registers root and memory root
+pub const STATE_LENGTH: u32 = 4 + 2 * DIGEST_LENGTH as u32;
+
+/// A word of a Merkle tree with the siblings of its path
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Opening {
+    pub word: u32,
+    pub proof: Vec<Digest>,
+}
+
+/// The values a step reads from the pre state, each of them proven against the committed roots
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StepWitness {
+    pub registers_root: Digest,
+    pub memory_root: Digest,
+    /// Quotient and remainder of a division, on the absolute values for the signed divisions
+    pub division: Option<(u32, u32)>,
+    /// Index and old value of the accessed memory word
+    pub memory: Option<(u32, Opening)>,
+    /// Old value of the destination register
+    pub rd: Option<Opening>,
+    pub rs2: Option<Opening>,
+    pub rs1: Option<Opening>,
+}
+
+impl StepWitness {
+    /// Pushes the witness in the order expected by `disprove_script`
+    pub fn push(&self) -> Script {
+        let mut scripts = vec![
+            push_digest(&self.registers_root),
+            push_digest(&self.memory_root),
+        ];
+        if let Some((quotient, remainder)) = self.division {
+            scripts.push(push_word(quotient));
+            scripts.push(push_word(remainder));
+        }
+        if let Some((index, opening)) = &self.memory {
+            scripts.push(push_dynamic_proof(&opening.proof, *index));
+            scripts.push(push_word(opening.word));
+        }
+        for opening in [&self.rd, &self.rs2, &self.rs1].into_iter().flatten() {
+            scripts.push(push_static_proof(&opening.proof));
+            scripts.push(push_word(opening.word));
+        }
+        script! {
+            { scripts }
+        }
+    }
+}
+
+/// Whether the step has the quotient and remainder hints
+pub fn has_division_hints(instruction: &Instruction) -> bool {
+    matches!(instruction, Instruction::Op { op, .. } if op.is_division())
+        && instruction.rd().is_some()
+}
+
+/// The next pc of the instructions that don't calculate it
+fn static_next_pc(pc: u32, instruction: &Instruction) -> u32 {
+    match *instruction {
+        Instruction::Jal { imm, .. } => pc.wrapping_add(imm as u32),
+        Instruction::Ecall | Instruction::Ebreak => pc,
+        _ => pc.wrapping_add(4),
+    }
+}
+
+/// Blocks of the main stack, from the bottom to the top, locating the values the step script works on
+#[derive(Default)]
+struct Layout {
+    blocks: Vec<(&'static str, u32)>,
+}
+
+impl Layout {
+    fn push(&mut self, name: &'static str, size: u32) {
+        self.blocks.push((name, size));
+    }
+
+    /// Removes the top block, which should be the named one
+    fn pop(&mut self, name: &'static str) {
+        let (top, _) = self.blocks.pop().expect("the stack is empty");
+        assert_eq!(top, name, "{} is not on the top of the stack", name);
+    }
+
+    fn contains(&self, name: &'static str) -> bool {
+        self.blocks.iter().any(|(block, _)| *block == name)
+    }
+
+    /// Position, depth of the deepest element and size of the block
+    fn locate(&self, name: &'static str) -> (usize, u32, u32) {
+        let position = self
+            .blocks
+            .iter()
+            .rposition(|(block, _)| *block == name)
+            .unwrap_or_else(|| panic!("{} is not on the stack", name));
+        let size = self.blocks[position].1;
+        let above: u32 = self.blocks[position + 1..]
+            .iter()
+            .map(|(_, size)| size)
+            .sum();
+        (position, above + size - 1, size)
+    }
+
+    /// Moves the block to the top
+    fn roll(&mut self, name: &'static str) -> Script {
+        let (position, depth, size) = self.locate(name);
+        self.blocks.remove(position);
+        self.push(name, size);
+        script! {
+            for _ in 0..size {
+                { depth }
+                OP_ROLL
+            }
+        }
+    }
+
+    /// Copies the block to the top under another name
+    fn pick(&mut self, name: &'static str, copy: &'static str) -> Script {
+        let (_, depth, size) = self.locate(name);
+        self.push(copy, size);
+        script! {
+            for _ in 0..size {
+                { depth }
+                OP_PICK
+            }
+        }
+    }
+}
+
+/// An operand of a calculation
+#[derive(Clone, Copy)]
+enum Operand {
+    /// A packed word of the stack
+    Block(&'static str),
+    Constant(u32),
+}
+
+struct StepScript {
+    instruction: Instruction,
+    memory_depth: u32,
+    layout: Layout,
+    scripts: Vec<Script>,
+}
+
+impl StepScript {
+    fn new(instruction: Instruction, memory_depth: u32) -> Self {
+        let mut layout = Layout::default();
+        layout.push("registers_root", DIGEST_LIMBS);
+        layout.push("memory_root", DIGEST_LIMBS);
+        if has_division_hints(&instruction) {
+            layout.push("quotient", WORD_LIMBS);
+            layout.push("remainder", WORD_LIMBS);
+        }
+        if instruction.accesses_memory() {
+            layout.push("memory_proof", memory_depth * (DIGEST_LIMBS + 1));
+            layout.push("memory_word", WORD_LIMBS);
+        }
+        if instruction.rd().is_some() {
+            layout.push("rd_proof", REGISTERS_DEPTH * DIGEST_LIMBS);
+            layout.push("rd_word", WORD_LIMBS);
+        }
+        if instruction.rs2().is_some() {
+            layout.push("rs2_proof", REGISTERS_DEPTH * DIGEST_LIMBS);
+            layout.push("rs2", WORD_LIMBS);
+        }
+        if instruction.rs1().is_some() {
+            layout.push("rs1_proof", REGISTERS_DEPTH * DIGEST_LIMBS);
+            layout.push("rs1", WORD_LIMBS);
+        }
+        layout.push("pre", DIGEST_LIMBS);
+        layout.push("post", DIGEST_LIMBS);
+        Self {
+            instruction,
+            memory_depth,
+            layout,
+            scripts: vec![],
+        }
+    }
+
+    fn roll(&mut self, name: &'static str) {
+        let script = self.layout.roll(name);
+        self.scripts.push(script);
+    }
+
+    fn pick(&mut self, name: &'static str, copy: &'static str) {
+        let script = self.layout.pick(name, copy);
+        self.scripts.push(script);
+    }
+
+    /// Appends the script, which replaces the consumed blocks on the top (the last one on the top) with the produced ones
+    fn replace(
+        &mut self,
+        script: Script,
+        consumed: &[&'static str],
+        produced: &[(&'static str, u32)],
+    ) {
+        for &name in consumed.iter().rev() {
+            self.layout.pop(name);
+        }
+        for &(name, size) in produced {
+            self.layout.push(name, size);
+        }
+        self.scripts.push(script);
+    }
+
+    fn drop(&mut self, name: &'static str) {
+        self.roll(name);
+        let (_, _, size) = self.layout.locate(name);
+        self.replace(u4_drop(size), &[name], &[]);
+    }
+
+    /// Pushes the leaf nibbles of the packed word
+    fn leaf_of(&mut self, name: &'static str, copy: bool) {
+        if copy {
+            self.pick(name, "leaf");
+        } else {
+            self.roll(name);
+        }
+        let consumed = if copy { "leaf" } else { name };
+        self.replace(
+            script! { { unpack(8) } { leaf_script() } },
+            &[consumed],
+            &[("leaf", DIGEST_NIBBLES)],
+        );
+    }
+
+    /// Verifies that the root nibbles on the top are the packed root
+    fn verify_root(&mut self, root: &'static str) {
+        self.replace(pack(DIGEST_NIBBLES), &["root"], &[("root", DIGEST_LIMBS)]);
+        self.pick(root, "expected_root");
+        self.replace(verify_n(DIGEST_LIMBS), &["root", "expected_root"], &[]);
+    }
+
+    /// Verifies the value of the register, x0 being zero
+    fn read_register(&mut self, name: &'static str, proof: &'static str, register: Option<u8>) {
+        match register {
+            Some(register) => {
+                self.roll(proof);
+                self.leaf_of(name, true);
+                self.replace(
+                    merkle_root(REGISTERS_DEPTH, Some(register as u32)),
+                    &[proof, "leaf"],
+                    &[("root", DIGEST_NIBBLES)],
+                );
+                self.verify_root("registers_root");
+            }
+            None => self.replace(push_word(0), &[], &[(name, WORD_LIMBS)]),
+        }
+    }
+
+    /// Runs the calculation on the unpacked operands over the selected tables, keeping its result
+    fn calculate(
+        &mut self,
+        alu: &U4Alu,
+        operands: &[Operand],
+        calculation: Script,
+        result: (&'static str, u32),
+    ) {
+        let tables = alu.tables_size() > 0;
+        if tables {
+            self.replace(alu.push_tables(), &[], &[("tables", alu.tables_size())]);
+        }
+        let mut consumed = vec![];
+        for &operand in operands {
+            match operand {
+                Operand::Block(name) => {
+                    self.roll(name);
+                    self.replace(unpack(8), &[name], &[(name, 8)]);
+                    consumed.push(name);
+                }
+                Operand::Constant(value) => {
+                    self.replace(U4Alu::push(value), &[], &[("constant", 8)]);
+                    consumed.push("constant");
+                }
+            }
+        }
+        self.replace(calculation, &consumed, &[result]);
+        if tables {
+            self.replace(
+                script! {
+                    { u4_toaltstack(result.1) }
+                    { alu.drop_tables() }
+                    { u4_fromaltstack(result.1) }
+                },
+                &["tables", result.0],
+                &[result],
+            );
+        }
+    }
+
+    /// Verifies the accessed memory word against the memory root and splits the address into the word index and the byte
+    /// offset, keeping a copy of the proof for a store
+    fn access_memory(&mut self, store: bool) {
+        self.roll("address");
+        self.replace(
+            script! {
+                { unpack(8) }
+                { address_split(self.memory_depth) }
+            },
+            &["address"],
+            &[("index", 1), ("offset", 1)],
+        );
+        let proof = if store {
+            self.pick("memory_proof", "proof_copy");
+            "proof_copy"
+        } else {
+            self.roll("memory_proof");
+            "memory_proof"
+        };
+        self.leaf_of("memory_word", true);
+        self.replace(
+            merkle_root(self.memory_depth, None),
+            &[proof, "leaf"],
+            &[("root", DIGEST_NIBBLES), ("proof_index", 1)],
+        );
+        self.roll("index");
+        self.replace(script! { OP_EQUALVERIFY }, &["proof_index", "index"], &[]);
+        self.verify_root("memory_root");
+    }
+
+    fn load(&mut self, op: LoadOp) {
+        if self.instruction.rd().is_none() {
+            self.drop("memory_word");
+            self.drop("offset");
+            return;
+        }
+        self.roll("memory_word");
+        self.replace(unpack(8), &["memory_word"], &[("memory_word", 8)]);
+        self.roll("offset");
+        let cases = (0..4)
+            .map(|offset| match offset % op.width() {
+                0 => extract(2 * offset, 2 * op.width(), op.is_signed()),
+                _ => script! { OP_RETURN },
+            })
+            .collect();
+        self.replace(
+            script! {
+                { switch(cases) }
+                { pack(8) }
+            },
+            &["memory_word", "offset"],
+            &[("rd_value", WORD_LIMBS)],
+        );
+    }
+
+    fn store(&mut self, op: StoreOp) {
+        self.roll("memory_word");
+        self.replace(unpack(8), &["memory_word"], &[("memory_word", 8)]);
+        self.roll("rs2");
+        self.replace(unpack(8), &["rs2"], &[("rs2", 8)]);
+        self.roll("offset");
+        let cases = (0..4)
+            .map(|offset| match offset % op.width() {
+                0 => merge(2 * offset, 2 * op.width()),
+                _ => script! { OP_RETURN },
+            })
+            .collect();
+        self.replace(
+            script! {
+                { switch(cases) }
+                { leaf_script() }
+            },
+            &["memory_word", "rs2", "offset"],
+            &[("leaf", DIGEST_NIBBLES)],
+        );
+        // the direction bits are the ones of the verified copy
+        self.roll("memory_proof");
+        self.roll("leaf");
+        self.replace(
+            script! {
+                { merkle_root(self.memory_depth, None) }
+                OP_DROP
+                { pack(DIGEST_NIBBLES) }
+            },
+            &["memory_proof", "leaf"],
+            &[("memory_root_new", DIGEST_LIMBS)],
+        );
+    }
+
+    fn write_register(&mut self, rd: u8) {
+        self.pick("rd_proof", "proof_copy");
+        self.leaf_of("rd_word", false);
+        self.replace(
+            merkle_root(REGISTERS_DEPTH, Some(rd as u32)),
+            &["proof_copy", "leaf"],
+            &[("root", DIGEST_NIBBLES)],
+        );
+        self.verify_root("registers_root");
+        self.roll("rd_proof");
+        self.leaf_of("rd_value", false);
+        self.replace(
+            script! {
+                { merkle_root(REGISTERS_DEPTH, Some(rd as u32)) }
+                { pack(DIGEST_NIBBLES) }
+            },
+            &["rd_proof", "leaf"],
+            &[("registers_root_new", DIGEST_LIMBS)],
+        );
+    }
+
+    fn alu_operation(&mut self, op: AluOp, second: Operand) {
+        let alu = alu_tables(op);
+        let (operands, calculation) = match second {
+            Operand::Constant(shift) if matches!(op, AluOp::Sll | AluOp::Srl | AluOp::Sra) => {
+                (vec![Operand::Block("rs1")], shift_constant(&alu, op, shift))
+            }
+            _ if op.is_division() => (
+                vec![
+                    Operand::Block("rs1"),
+                    second,
+                    Operand::Block("quotient"),
+                    Operand::Block("remainder"),
+                ],
+                division(&alu, op),
+            ),
+            _ => (
+                vec![Operand::Block("rs1"), second],
+                binary_operation(&alu, op),
+            ),
+        };
+        self.calculate(
+            &alu,
+            &operands,
+            script! {
+                { calculation }
+                { pack(8) }
+            },
+            ("rd_value", WORD_LIMBS),
+        );
+    }
+
+    /// Calculates the result of the instruction
+    fn execute(&mut self, pc: u32) {
+        let add = U4Alu::new(&[U4Table::Add]);
+        let writes = self.instruction.rd().is_some();
+        match self.instruction {
+            Instruction::Lui { imm, .. } if writes => {
+                self.replace(push_word(imm), &[], &[("rd_value", WORD_LIMBS)]);
+            }
+            Instruction::Auipc { imm, .. } if writes => {
+                self.replace(
+                    push_word(pc.wrapping_add(imm)),
+                    &[],
+                    &[("rd_value", WORD_LIMBS)],
+                );
+            }
+            Instruction::Jal { .. } if writes => {
+                self.replace(
+                    push_word(pc.wrapping_add(4)),
+                    &[],
+                    &[("rd_value", WORD_LIMBS)],
+                );
+            }
+            Instruction::Jalr { imm, .. } => {
+                self.calculate(
+                    &add,
+                    &[Operand::Block("rs1"), Operand::Constant(imm as u32)],
+                    script! {
+                        { add.add(0) }
+                        // clear the lowest bit
+                        OP_DUP
+                        { nibble_low_bit() }
+                        OP_SUB
+                        { pack(8) }
+                    },
+                    ("next_pc", WORD_LIMBS),
+                );
+                if writes {
+                    self.replace(
+                        push_word(pc.wrapping_add(4)),
+                        &[],
+                        &[("rd_value", WORD_LIMBS)],
+                    );
+                }
+            }
+            Instruction::Branch { op, imm, .. } => {
+                self.calculate(
+                    &U4Alu::default(),
+                    &[Operand::Block("rs1"), Operand::Block("rs2")],
+                    script! {
+                        { branch_condition(op) }
+                        OP_IF
+                            { push_word(pc.wrapping_add(imm as u32)) }
+                        OP_ELSE
+                            { push_word(pc.wrapping_add(4)) }
+                        OP_ENDIF
+                    },
+                    ("next_pc", WORD_LIMBS),
+                );
+            }
+            Instruction::Load { op, imm, .. } => {
+                self.address(&add, imm);
+                self.access_memory(false);
+                self.load(op);
+            }
+            Instruction::Store { op, imm, .. } => {
+                self.address(&add, imm);
+                self.access_memory(true);
+                self.store(op);
+            }
+            Instruction::OpImm { op, imm, .. } if writes => {
+                self.alu_operation(op, Operand::Constant(imm as u32))
+            }
+            Instruction::Op { op, .. } if writes => self.alu_operation(op, Operand::Block("rs2")),
+            _ => {}
+        }
+    }
+
+    fn address(&mut self, add: &U4Alu, imm: i32) {
+        self.calculate(
+            add,
+            &[Operand::Block("rs1"), Operand::Constant(imm as u32)],
+            script! {
+                { add.add(0) }
+                { pack(8) }
+            },
+            ("address", WORD_LIMBS),
+        );
+    }
+
+    /// Hashes the state of the nibbles of the pc and the packed roots
+    fn state_hash(
+        &mut self,
+        registers_root: &'static str,
+        memory_root: &'static str,
+        result: &'static str,
+    ) {
+        self.roll(registers_root);
+        self.replace(
+            unpack(DIGEST_NIBBLES),
+            &[registers_root],
+            &[(registers_root, DIGEST_NIBBLES)],
+        );
+        self.roll(memory_root);
+        self.replace(
+            unpack(DIGEST_NIBBLES),
+            &[memory_root],
+            &[(memory_root, DIGEST_NIBBLES)],
+        );
+        self.replace(
+            script! {
+                { hash_script(STATE_LENGTH) }
+                { pack(DIGEST_NIBBLES) }
+            },
+            &["pc", registers_root, memory_root],
+            &[(result, DIGEST_LIMBS)],
+        );
+    }
+
+    /// Compares the post state with the committed one and verifies the committed pre state
+    fn commit(&mut self, pc: u32) {
+        for name in ["rs1", "rs2"] {
+            if self.layout.contains(name) {
+                self.drop(name);
+            }
+        }
+
+        if self.layout.contains("next_pc") {
+            self.roll("next_pc");
+            self.replace(unpack(8), &["next_pc"], &[("pc", 8)]);
+        } else {
+            let next_pc = static_next_pc(pc, &self.instruction);
+            self.replace(U4Alu::push(next_pc), &[], &[("pc", 8)]);
+        }
+        if !self.layout.contains("registers_root_new") {
+            self.pick("registers_root", "registers_root_new");
+        }
+        if !self.layout.contains("memory_root_new") {
+            self.pick("memory_root", "memory_root_new");
+        }
+        self.state_hash("registers_root_new", "memory_root_new", "post_state");
+        self.roll("post");
+        self.replace(
+            script! {
+                for i in 0..DIGEST_LIMBS {
+                    { DIGEST_LIMBS - i }
+                    OP_ROLL
+                    OP_EQUAL
+                    OP_TOALTSTACK
+                }
+                OP_FROMALTSTACK
+                for _ in 1..DIGEST_LIMBS {
+                    OP_FROMALTSTACK
+                    OP_BOOLAND
+                }
+                OP_NOT
+            },
+            &["post_state", "post"],
+            &[("disproved", 1)],
+        );
+
+        self.replace(U4Alu::push(pc), &[], &[("pc", 8)]);
+        self.state_hash("registers_root", "memory_root", "pre_state");
+        self.roll("pre");
+        self.replace(verify_n(DIGEST_LIMBS), &["pre_state", "pre"], &[]);
+        assert_eq!(self.layout.blocks, vec![("disproved", 1)]);
+    }
+}
+
+/// The disprove script of the step executing the instruction at pc, over a memory of 2^memory_depth words
+pub fn disprove_script(pc: u32, instruction: Instruction, memory_depth: u32) -> Script {
+    let mut step = StepScript::new(instruction, memory_depth);
+    let reads_rs1 = matches!(
+        instruction,
+        Instruction::Jalr { .. }
+            | Instruction::Branch { .. }
+            | Instruction::Load { .. }
+            | Instruction::Store { .. }
+            | Instruction::OpImm { .. }
+            | Instruction::Op { .. }
+    );
+    let reads_rs2 = matches!(
+        instruction,
+        Instruction::Branch { .. } | Instruction::Store { .. } | Instruction::Op { .. }
+    );
+    if reads_rs1 {
+        step.read_register("rs1", "rs1_proof", instruction.rs1());
+    }
+    if reads_rs2 {
+        step.read_register("rs2", "rs2_proof", instruction.rs2());
+    }
+    step.execute(pc);
+    if let Some(rd) = instruction.rd() {
+        step.write_register(rd);
+    }
+    step.commit(pc);
+    script! {
+        { step.scripts }
+    }
+}
+
+/// Tables of the ALU needed by the operation
+fn alu_tables(op: AluOp) -> U4Alu {
+    match op {
+        AluOp::Add | AluOp::Sub => U4Alu::new(&[U4Table::Add]),
+        AluOp::Xor | AluOp::Or | AluOp::And => U4Alu::new(&[U4Table::HalfAnd]),
+        AluOp::Sll | AluOp::Srl | AluOp::Sra => U4Alu::new(&[U4Table::Shift]),
+        AluOp::Slt | AluOp::Sltu => U4Alu::default(),
+        AluOp::Mul | AluOp::Mulhu => U4Alu::new(&[U4Table::Mul]),
+        AluOp::Mulh | AluOp::Mulhsu | AluOp::Div | AluOp::Divu | AluOp::Rem | AluOp::Remu => {
+            U4Alu::new(&[U4Table::Add, U4Table::Mul])
+        }
+    }
+}
+
+/// Replaces the nibble with its lowest bit
+fn nibble_low_bit() -> Script {
+    script! {
+        for bit in [8, 4, 2] {
+            OP_DUP
+            { bit }
+            OP_GREATERTHANOREQUAL
+            OP_IF
+                { bit }
+                OP_SUB
+            OP_ENDIF
+        }
+    }
+}
+
+/// Replaces the flag with the u32 number
+fn bool_to_word() -> Script {
+    script! {
+        OP_TOALTSTACK
+        for _ in 0..7 {
+            OP_0
+        }
+        OP_FROMALTSTACK
+    }
+}
+
+fn branch_condition(op: BranchOp) -> Script {
+    match op {
+        BranchOp::Eq => U4Alu::equal(),
+        BranchOp::Ne => script! { { U4Alu::equal() } OP_NOT },
+        BranchOp::Lt => U4Alu::lessthan_signed(),
+        BranchOp::Ge => script! { { U4Alu::lessthan_signed() } OP_NOT },
+        BranchOp::Ltu => U4Alu::lessthan(),
+        BranchOp::Geu => script! { { U4Alu::lessthan() } OP_NOT },
+    }
+}
+
+/// Binary operations of the tables on the top, except for the divisions
+fn binary_operation(alu: &U4Alu, op: AluOp) -> Script {
+    match op {
+        AluOp::Add => alu.add(0),
+        AluOp::Sub => alu.sub(0),
+        AluOp::Xor => alu.xor(0),
+        AluOp::Or => alu.or(0),
+        AluOp::And => alu.and(0),
+        AluOp::Slt => script! { { U4Alu::lessthan_signed() } { bool_to_word() } },
+        AluOp::Sltu => script! { { U4Alu::lessthan() } { bool_to_word() } },
+        AluOp::Mul => alu.mul(0),
+        AluOp::Mulhu => alu.mulhi(0),
+        AluOp::Mulh => signed_high_multiplication(alu, true),
+        AluOp::Mulhsu => signed_high_multiplication(alu, false),
+        AluOp::Sll | AluOp::Srl | AluOp::Sra => shift_dynamic(alu, op),
+        AluOp::Div | AluOp::Divu | AluOp::Rem | AluOp::Remu => {
+            unreachable!("the divisions need hints")
+        }
+    }
+}
+
+/// The high bits of the product of a signed a and a signed (or unsigned) b, calculated from the unsigned product by
+/// subtracting b if a is negative and a if b is negative
+fn signed_high_multiplication(alu: &U4Alu, signed_b: bool) -> Script {
+    script! {
+        { u4_copy_u32_from(8) }
+        { u4_copy_u32_from(8) }
+        { alu.mulhi(16) }
+        { 23 }
+        OP_PICK
+        8
+        OP_GREATERTHANOREQUAL
+        OP_IF
+            { u4_copy_u32_from(8) }
+            { alu.sub(16) }
+        OP_ENDIF
+        if signed_b {
+            { 15 }
+            OP_PICK
+            8
+            OP_GREATERTHANOREQUAL
+            OP_IF
+                { u4_copy_u32_from(16) }
+                { alu.sub(16) }
+            OP_ENDIF
+        }
+        { u4_toaltstack(8) }
+        { u4_drop(16) }
+        { u4_fromaltstack(8) }
+    }
+}
+
+fn shift_constant(alu: &U4Alu, op: AluOp, n: u32) -> Script {
+    match op {
+        _ if n == 0 => script! {},
+        AluOp::Sll => alu.lshift(n, 0),
+        AluOp::Srl => alu.rshift(n, 0),
+        // the arithmetic shift of a negative number is the logical shift of its complement, complemented
+        AluOp::Sra => script! {
+            7
+            OP_PICK
+            8
+            OP_GREATERTHANOREQUAL
+            OP_DUP
+            OP_TOALTSTACK
+            OP_IF
+                { U4Alu::not() }
+            OP_ENDIF
+            { alu.rshift(n, 0) }
+            OP_FROMALTSTACK
+            OP_IF
+                { U4Alu::not() }
+            OP_ENDIF
+        },
+        _ => unreachable!("{:?} is not a shift", op),
+    }
+}
+
+/// Shift by the lowest 5 bits of b, as the composition of the shifts by the powers of two
+fn shift_dynamic(alu: &U4Alu, op: AluOp) -> Script {
+    script! {
+        for _ in 0..6 {
+            2
+            OP_ROLL
+            OP_DROP
+        }
+        OP_SWAP
+        { nibble_low_bit() }
+        OP_TOALTSTACK
+        for bit in (0..4).rev() {
+            OP_DUP
+            { 1 << bit }
+            OP_GREATERTHANOREQUAL
+            OP_DUP
+            OP_TOALTSTACK
+            OP_IF
+                { 1 << bit }
+                OP_SUB
+            OP_ENDIF
+        }
+        OP_DROP
+        for bit in 0..5 {
+            OP_FROMALTSTACK
+            OP_IF
+                { shift_constant(alu, op, 1 << bit) }
+            OP_ENDIF
+        }
+    }
+}
+
+/// Replaces x with -x
+fn negate(alu: &U4Alu, offset: u32) -> Script {
+    script! {
+        { U4Alu::push(0) }
+        { u4_move_u32_from(8) }
+        { alu.sub(offset) }
+    }
+}
+
+/// Replaces x with |x|, keeping the sign at the altstack
+fn absolute_value(alu: &U4Alu, offset: u32) -> Script {
+    script! {
+        7
+        OP_PICK
+        8
+        OP_GREATERTHANOREQUAL
+        OP_DUP
+        OP_TOALTSTACK
+        OP_IF
+            { negate(alu, offset) }
+        OP_ENDIF
+    }
+}
+
+/// Verifies the hinted quotient and remainder of a / b for b != 0, i.e. a = q * b + r without overflow and r < b
+/// Replaces a, b, q and r with q and r
+fn unsigned_division_verification(alu: &U4Alu) -> Script {
+    script! {
+        // r < b
+        { u4_copy_u32_from(0) }
+        { u4_copy_u32_from(24) }
+        { U4Alu::lessthan() }
+        OP_VERIFY
+        // q * b < 2^32
+        { u4_copy_u32_from(8) }
+        { u4_copy_u32_from(24) }
+        { alu.mulhi(32) }
+        { U4Alu::push(0) }
+        { verify_n(8) }
+        // s = q * b + r without overflow
+        { u4_copy_u32_from(8) }
+        { u4_copy_u32_from(24) }
+        { alu.mul(32) }
+        { u4_copy_u32_from(8) }
+        { alu.add(32) }
+        { u4_copy_u32_from(0) }
+        { u4_copy_u32_from(16) }
+        { U4Alu::greaterthanorequal() }
+        OP_VERIFY
+        // s = a
+        { u4_move_u32_from(32) }
+        { verify_n(8) }
+        { u4_move_u32_from(16) }
+        { U4Alu::drop() }
+    }
+}
+
+/// Verifies the hints on the absolute values, and applies the signs: the quotient is negative if the signs differ and
+/// the remainder has the sign of a
+fn signed_division_verification(alu: &U4Alu) -> Script {
+    script! {
+        { u4_move_u32_from(24) }
+        { absolute_value(alu, 24) }
+        { u4_move_u32_from(24) }
+        { absolute_value(alu, 24) }
+        { u4_move_u32_from(24) }
+        { u4_move_u32_from(24) }
+        { unsigned_division_verification(alu) }
+        OP_FROMALTSTACK
+        OP_FROMALTSTACK
+        OP_DUP
+        OP_TOALTSTACK
+        OP_NUMNOTEQUAL
+        OP_TOALTSTACK
+        { u4_move_u32_from(8) }
+        OP_FROMALTSTACK
+        OP_IF
+            { negate(alu, 8) }
+        OP_ENDIF
+        { u4_move_u32_from(8) }
+        OP_FROMALTSTACK
+        OP_IF
+            { negate(alu, 8) }
+        OP_ENDIF
+    }
+}
+
+/// Replaces a, b and the hinted quotient and remainder with the result of the division
+/// Division by zero gives the quotient -1 and the remainder a, without using the hints
+fn division(alu: &U4Alu, op: AluOp) -> Script {
+    let verification = if op.is_signed_division() {
+        signed_division_verification(alu)
+    } else {
+        unsigned_division_verification(alu)
+    };
+    let quotient = matches!(op, AluOp::Div | AluOp::Divu);
+    script! {
+        { u4_copy_u32_from(16) }
+        { U4Alu::push(0) }
+        { U4Alu::equal() }
+        OP_IF
+            { u4_drop(16) }
+            { U4Alu::drop() }
+            { U4Alu::push(u32::MAX) }
+            { u4_move_u32_from(8) }
+        OP_ELSE
+            { verification }
+        OP_ENDIF
+        if quotient {
+            { U4Alu::drop() }
+        } else {
+            { u4_move_u32_from(8) }
+            { U4Alu::drop() }
+        }
+    }
+}
+
+/// Replaces the address nibbles with the word index and the byte offset (on the top)
+/// Fails if the address is outside of the memory of 2^depth words
+fn address_split(depth: u32) -> Script {
+    script! {
+        OP_TOALTSTACK
+        // address >> 4
+        OP_0
+        for i in (1..8).rev() {
+            for _ in 0..4 {
+                OP_DUP
+                OP_ADD
+            }
+            { i }
+            OP_ROLL
+            OP_ADD
+        }
+        OP_DUP
+        OP_ADD
+        OP_DUP
+        OP_ADD
+        OP_FROMALTSTACK
+        for bit in [3, 2] {
+            OP_DUP
+            { 1 << bit }
+            OP_GREATERTHANOREQUAL
+            OP_IF
+                { 1 << bit }
+                OP_SUB
+                OP_SWAP
+                { 1 << (bit - 2) }
+                OP_ADD
+                OP_SWAP
+            OP_ENDIF
+        }
+        OP_OVER
+        0
+        { 1 << depth }
+        OP_WITHIN
+        OP_VERIFY
+    }
+}
+
+/// Runs the case selected by the number on the top of the stack
+fn switch(mut cases: Vec<Script>) -> Script {
+    let last = cases.pop().expect("there are no cases");
+    let value = cases.len() as u32;
+    if cases.is_empty() {
+        script! {
+            OP_DROP
+            { last }
+        }
+    } else {
+        script! {
+            OP_DUP
+            { value }
+            OP_EQUAL
+            OP_IF
+                OP_DROP
+                { last }
+            OP_ELSE
+                { switch(cases) }
+            OP_ENDIF
+        }
+    }
+}
+
+/// Replaces the word with its n nibbles starting from the nibble first, zero or sign extended
+fn extract(first: u32, n: u32, signed: bool) -> Script {
+    script! {
+        for _ in 0..n {
+            { first + n - 1 }
+            OP_PICK
+        }
+        { u4_toaltstack(n) }
+        { U4Alu::drop() }
+        if signed {
+            OP_FROMALTSTACK
+            OP_DUP
+            8
+            OP_GREATERTHANOREQUAL
+            OP_IF
+                15
+            OP_ELSE
+                0
+            OP_ENDIF
+            OP_SWAP
+            OP_TOALTSTACK
+            for _ in 1..8 - n {
+                OP_DUP
+            }
+        } else {
+            for _ in 0..8 - n {
+                OP_0
+            }
+        }
+        { u4_fromaltstack(n) }
+    }
+}
+
+/// Replaces the word and the value with the word whose n nibbles starting from the nibble first are the low nibbles
+/// of the value
+fn merge(first: u32, n: u32) -> Script {
+    script! {
+        for i in (0..8).rev() {
+            if (first..first + n).contains(&i) {
+                { 7 - first }
+                OP_PICK
+            } else {
+                15
+                OP_PICK
+            }
+        }
+        { u4_toaltstack(8) }
+        { u4_drop(16) }
+        { u4_fromaltstack(8) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::riscv::trace::{Program, State, Trace};
+
+    const MEMORY_DEPTH: u32 = 4;
+
+    fn op(op: AluOp, rd: u8, rs1: u8, rs2: u8) -> Instruction {
+        Instruction::Op { op, rd, rs1, rs2 }
+    }
+
+    fn op_imm(op: AluOp, rd: u8, rs1: u8, imm: i32) -> Instruction {
+        Instruction::OpImm { op, rd, rs1, imm }
+    }
+
+    fn test_program() -> Program {
+        Program::new(
+            0x1000,
+            vec![
+                op_imm(AluOp::Add, 1, 0, 40),
+                Instruction::Lui {
+                    rd: 2,
+                    imm: 0x80000000,
+                },
+                op_imm(AluOp::Add, 2, 2, -7),
+                Instruction::Store {
+                    op: StoreOp::Sw,
+                    rs1: 1,
+                    rs2: 2,
+                    imm: -32,
+                },
+                Instruction::Load {
+                    op: LoadOp::Lb,
+                    rd: 3,
+                    rs1: 0,
+                    imm: 8,
+                },
+                Instruction::Load {
+                    op: LoadOp::Lhu,
+                    rd: 4,
+                    rs1: 1,
+                    imm: -30,
+                },
+                Instruction::Store {
+                    op: StoreOp::Sb,
+                    rs1: 0,
+                    rs2: 3,
+                    imm: 13,
+                },
+                Instruction::Store {
+                    op: StoreOp::Sh,
+                    rs1: 1,
+                    rs2: 4,
+                    imm: -26,
+                },
+                Instruction::Load {
+                    op: LoadOp::Lw,
+                    rd: 5,
+                    rs1: 0,
+                    imm: 12,
+                },
+                Instruction::Load {
+                    op: LoadOp::Lh,
+                    rd: 6,
+                    rs1: 0,
+                    imm: 14,
+                },
+                op(AluOp::Mul, 7, 3, 4),
+                op(AluOp::Mulh, 8, 3, 2),
+                op(AluOp::Mulhsu, 9, 3, 4),
+                op(AluOp::Mulhu, 10, 3, 3),
+                op(AluOp::Div, 11, 2, 3),
+                op(AluOp::Rem, 12, 3, 1),
+                op(AluOp::Divu, 13, 1, 0),
+                op(AluOp::Remu, 14, 2, 4),
+                op(AluOp::Sll, 15, 4, 1),
+                op_imm(AluOp::Sra, 16, 3, 3),
+                op(AluOp::Sra, 17, 2, 3),
+                op_imm(AluOp::Srl, 18, 3, 5),
+                op(AluOp::Slt, 19, 3, 1),
+                op_imm(AluOp::Sltu, 20, 3, 1),
+                op(AluOp::Xor, 21, 3, 4),
+                op_imm(AluOp::Or, 22, 1, -256),
+                op(AluOp::And, 23, 2, 5),
+                op(AluOp::Sub, 24, 1, 3),
+                op(AluOp::Add, 0, 1, 2),
+                Instruction::Auipc {
+                    rd: 25,
+                    imm: 0x1000,
+                },
+                Instruction::Branch {
+                    op: BranchOp::Lt,
+                    rs1: 3,
+                    rs2: 0,
+                    imm: 8,
+                },
+                Instruction::Fence,
+                Instruction::Branch {
+                    op: BranchOp::Geu,
+                    rs1: 1,
+                    rs2: 3,
+                    imm: 64,
+                },
+                Instruction::Jal { rd: 26, imm: 8 },
+                Instruction::Fence,
+                Instruction::Jalr {
+                    rd: 0,
+                    rs1: 26,
+                    imm: 9,
+                },
+                Instruction::Fence,
+                Instruction::Ebreak,
+            ],
+        )
+    }
+
+    #[test]
+    fn test_trace() {
+        let program = test_program();
+        let trace = Trace::execute(&program, State::new(program.base, MEMORY_DEPTH), 100).unwrap();
+        let state = &trace.final_state;
+        let x = |register: u8| state.register(register);
+        assert_eq!(x(3), -7i32 as u32);
+        assert_eq!(x(4), 0x7fff);
+        assert_eq!(x(5), 0x7fff_f900);
+        assert_eq!(x(6), 0x7fff);
+        assert_eq!(x(11), (0x7ffffff9u32 as i32 / -7) as u32);
+        assert_eq!(x(12), -7i32 as u32);
+        assert_eq!(x(13), u32::MAX);
+        assert_eq!(x(17), 0x3f);
+        assert_eq!(x(19), 1);
+        assert_eq!(x(20), 0);
+        assert_eq!(x(25), 0x1000 + 29 * 4 + 0x1000);
+        assert_eq!(x(26), 0x1000 + 34 * 4);
+        assert_eq!(state.memory.get(2), 0x7ffffff9);
+        assert_eq!(state.memory.get(3), 0x7fff_f900);
+        // the taken branch skips the fence, the jumps skip the others
+        assert_eq!(trace.steps.len(), 35);
+        assert_eq!(state.pc, 0x1000 + 37 * 4);
+        assert_eq!(trace.states.len(), trace.steps.len() + 1);
+        assert_eq!(trace.states.last(), Some(&state.hash()));
+
+        // misaligned accesses are not executed
+        let program = Program::new(
+            0,
+            vec![Instruction::Load {
+                op: LoadOp::Lw,
+                rd: 1,
+                rs1: 0,
+                imm: 2,
+            }],
+        );
+        assert_eq!(
+            Trace::execute(&program, State::new(0, MEMORY_DEPTH), 1),
+            Err(crate::riscv::trace::ExecutionError::InvalidAccess { pc: 0, address: 2 })
+        );
+    }
+
+    #[test]
+    fn test_disprove_steps() {
+        let program = test_program();
+        let trace = Trace::execute(&program, State::new(program.base, MEMORY_DEPTH), 100).unwrap();
+        let mut max_stack = 0;
+        for (i, step) in trace.steps.iter().enumerate() {
+            let script = disprove_script(step.pc, step.instruction, MEMORY_DEPTH);
+            println!("{:?}: {} bytes", step.instruction, script.len());
+
+            // the honest post state can't be disproved
+            let res = execute_script(script! {
+                { trace.disprove_witness(i, &trace.states[i + 1]) }
+                { script.clone() }
+                OP_NOT
+            });
+            assert!(res.success, "step {} {:?} fails", i, step.instruction);
+            max_stack = max_stack.max(res.stats.max_nb_stack_items);
+
+            // any other post state is disproved
+            let wrong_state = trace.states[(i + 2) % trace.states.len()];
+            let res = execute_script(script! {
+                { trace.disprove_witness(i, &wrong_state) }
+                { script }
+            });
+            assert!(
+                res.success,
+                "step {} {:?} is not disproved",
+                i, step.instruction
+            );
+        }
+        println!("max stack items: {}", max_stack);
+    }
+
+    #[test]
+    fn test_inconsistent_witness() {
+        let program = test_program();
+        let trace = Trace::execute(&program, State::new(program.base, MEMORY_DEPTH), 100).unwrap();
+        let wrong_state = trace.states[0];
+        for i in [4, 7, 14] {
+            let step = &trace.steps[i];
+            let script = disprove_script(step.pc, step.instruction, MEMORY_DEPTH);
+
+            // a wrong value of a register or of the memory
+            let mut witness = step.witness.clone();
+            if let Some((_, opening)) = witness.memory.as_mut() {
+                opening.word ^= 1;
+            } else {
+                witness.rs1.as_mut().unwrap().word ^= 1;
+            }
+            let res = execute_script(script! {
+                { witness.push() }
+                { push_digest(&trace.states[i]) }
+                { push_digest(&wrong_state) }
+                { script.clone() }
+            });
+            assert!(!res.success);
+
+            // a pre state other than the committed one
+            let res = execute_script(script! {
+                { step.witness.push() }
+                { push_digest(&trace.states[i + 1]) }
+                { push_digest(&wrong_state) }
+                { script }
+            });
+            assert!(!res.success);
+        }
+
+        // wrong division hints
+        let step = &trace.steps[14];
+        let mut witness = step.witness.clone();
+        let (quotient, remainder) = witness.division.unwrap();
+        witness.division = Some((quotient - 1, remainder + 7));
+        let res = execute_script(script! {
+            { witness.push() }
+            { push_digest(&trace.states[14]) }
+            { push_digest(&wrong_state) }
+            { disprove_script(step.pc, step.instruction, MEMORY_DEPTH) }
+        });
+        assert!(!res.success);
+    }
+}
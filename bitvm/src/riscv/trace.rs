@@ -0,0 +1,257 @@
+use super::instruction::Instruction;
+use super::merkle::{hash, push_digest, Digest, MerkleTree};
+use super::step::{disprove_script, has_division_hints, Opening, StepWitness};
+use crate::treepp::*;
+
+/// Depth of the Merkle tree of the 32 registers
+pub const REGISTERS_DEPTH: u32 = 5;
+
+/// The state hash committing to the pc and the roots of the registers and the memory
+pub fn state_hash(pc: u32, registers_root: &Digest, memory_root: &Digest) -> Digest {
+    hash(&[pc.to_be_bytes().as_slice(), registers_root, memory_root].concat())
+}
+
+/// A program in its own address space, the instruction at `base + 4 * i` being `code[i]`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Program {
+    pub base: u32,
+    pub code: Vec<Instruction>,
+}
+
+impl Program {
+    pub fn new(base: u32, code: Vec<Instruction>) -> Self {
+        Self { base, code }
+    }
+
+    /// The instruction at pc, if any
+    pub fn fetch(&self, pc: u32) -> Option<Instruction> {
+        let offset = pc.wrapping_sub(self.base);
+        if offset % 4 != 0 {
+            return None;
+        }
+        self.code.get((offset / 4) as usize).copied()
+    }
+
+    /// The disprove scripts of the steps of the instructions, in the order of the code
+    pub fn disprove_scripts(&self, memory_depth: u32) -> Vec<Script> {
+        self.code
+            .iter()
+            .enumerate()
+            .map(|(i, &instruction)| {
+                disprove_script(self.base + 4 * i as u32, instruction, memory_depth)
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExecutionError {
+    /// The pc is outside of the program
+    InvalidPc(u32),
+    /// The access is not aligned to its width or outside of the memory
+    InvalidAccess { pc: u32, address: u32 },
+}
+
+/// The state of the machine: the pc, the registers and the data memory, which is separate from the program
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct State {
+    pub pc: u32,
+    pub registers: MerkleTree,
+    pub memory: MerkleTree,
+}
+
+impl State {
+    /// The state with zero registers and zero memory of 2^memory_depth words
+    pub fn new(pc: u32, memory_depth: u32) -> Self {
+        Self {
+            pc,
+            registers: MerkleTree::new(REGISTERS_DEPTH),
+            memory: MerkleTree::new(memory_depth),
+        }
+    }
+
+    pub fn hash(&self) -> Digest {
+        state_hash(self.pc, &self.registers.root(), &self.memory.root())
+    }
+
+    pub fn register(&self, register: u8) -> u32 {
+        self.registers.get(register as u32)
+    }
+
+    pub fn set_register(&mut self, register: u8, value: u32) {
+        if register != 0 {
+            self.registers.set(register as u32, value);
+        }
+    }
+
+    fn read_register(&self, register: Option<u8>) -> (u32, Option<Opening>) {
+        match register {
+            Some(register) => {
+                let opening = Opening {
+                    word: self.register(register),
+                    proof: self.registers.proof(register as u32),
+                };
+                (opening.word, Some(opening))
+            }
+            None => (0, None),
+        }
+    }
+
+    /// Index and byte offset of the word at the address, which should be aligned to the width
+    fn locate(&self, address: u32, width: u32) -> Result<(u32, u32), ExecutionError> {
+        let index = address >> 2;
+        if address % width != 0 || index >= 1 << self.memory.depth() {
+            return Err(ExecutionError::InvalidAccess {
+                pc: self.pc,
+                address,
+            });
+        }
+        Ok((index, address & 3))
+    }
+
+    /// Executes the instruction, returns the witness of the step for its disprove script
+    pub fn step(&mut self, instruction: &Instruction) -> Result<StepWitness, ExecutionError> {
+        let pc = self.pc;
+        let (a, rs1) = self.read_register(instruction.rs1());
+        let (b, rs2) = self.read_register(instruction.rs2());
+        let mut witness = StepWitness {
+            registers_root: self.registers.root(),
+            memory_root: self.memory.root(),
+            division: None,
+            memory: None,
+            rd: None,
+            rs2,
+            rs1,
+        };
+
+        let mut next_pc = pc.wrapping_add(4);
+        let mut result = None;
+        match *instruction {
+            Instruction::Lui { imm, .. } => result = Some(imm),
+            Instruction::Auipc { imm, .. } => result = Some(pc.wrapping_add(imm)),
+            Instruction::Jal { imm, .. } => {
+                result = Some(next_pc);
+                next_pc = pc.wrapping_add(imm as u32);
+            }
+            Instruction::Jalr { imm, .. } => {
+                result = Some(next_pc);
+                next_pc = a.wrapping_add(imm as u32) & !1;
+            }
+            Instruction::Branch { op, imm, .. } => {
+                if op.taken(a, b) {
+                    next_pc = pc.wrapping_add(imm as u32);
+                }
+            }
+            Instruction::Load { op, imm, .. } => {
+                let (index, offset) = self.locate(a.wrapping_add(imm as u32), op.width())?;
+                let word = self.memory.get(index);
+                witness.memory = Some((
+                    index,
+                    Opening {
+                        word,
+                        proof: self.memory.proof(index),
+                    },
+                ));
+                result = Some(op.extract(word, offset));
+            }
+            Instruction::Store { op, imm, .. } => {
+                let (index, offset) = self.locate(a.wrapping_add(imm as u32), op.width())?;
+                let word = self.memory.get(index);
+                witness.memory = Some((
+                    index,
+                    Opening {
+                        word,
+                        proof: self.memory.proof(index),
+                    },
+                ));
+                self.memory.set(index, op.merge(word, b, offset));
+            }
+            Instruction::OpImm { op, imm, .. } => result = Some(op.apply(a, imm as u32)),
+            Instruction::Op { op, .. } => {
+                if has_division_hints(instruction) {
+                    witness.division = Some(op.division_hints(a, b));
+                }
+                result = Some(op.apply(a, b));
+            }
+            Instruction::Fence => {}
+            Instruction::Ecall | Instruction::Ebreak => next_pc = pc,
+        }
+
+        if let Some(rd) = instruction.rd() {
+            witness.rd = Some(Opening {
+                word: self.register(rd),
+                proof: self.registers.proof(rd as u32),
+            });
+            self.set_register(rd, result.expect("the instruction writes its destination"));
+        }
+        self.pc = next_pc;
+        Ok(witness)
+    }
+}
+
+/// A step of the execution
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Step {
+    pub pc: u32,
+    pub instruction: Instruction,
+    pub witness: StepWitness,
+}
+
+/// The execution of a program: the steps and the hashes of the states before and after each step
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Trace {
+    pub steps: Vec<Step>,
+    pub states: Vec<Digest>,
+    pub final_state: State,
+}
+
+impl Trace {
+    /// Executes the program from the state until it halts or for max_steps steps
+    /// The halting instructions leave the state unchanged, the trace ends before them
+    pub fn execute(
+        program: &Program,
+        mut state: State,
+        max_steps: usize,
+    ) -> Result<Self, ExecutionError> {
+        let mut steps = vec![];
+        let mut states = vec![state.hash()];
+        while steps.len() < max_steps {
+            let pc = state.pc;
+            let instruction = program.fetch(pc).ok_or(ExecutionError::InvalidPc(pc))?;
+            if instruction.is_halt() {
+                break;
+            }
+            let witness = state.step(&instruction)?;
+            steps.push(Step {
+                pc,
+                instruction,
+                witness,
+            });
+            states.push(state.hash());
+        }
+        Ok(Self {
+            steps,
+            states,
+            final_state: state,
+        })
+    }
+
+    /// The first step whose claimed post state differs from the trace
+    /// The claimed states start with the initial state, which is agreed on
+    pub fn first_faulty_step(&self, claimed_states: &[Digest]) -> Option<usize> {
+        assert_eq!(
+            claimed_states[0], self.states[0],
+            "the initial state differs"
+        );
+        (0..self.steps.len()).find(|&i| claimed_states.get(i + 1) != Some(&self.states[i + 1]))
+    }
+
+    /// The witness of the disprove script of the step, whose claimed pre state is the one of the trace
+    pub fn disprove_witness(&self, step: usize, claimed_post_state: &Digest) -> Script {
+        script! {
+            { self.steps[step].witness.push() }
+            { push_digest(&self.states[step]) }
+            { push_digest(claimed_post_state) }
+        }
+    }
+}
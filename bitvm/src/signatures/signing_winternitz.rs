@@ -64,6 +64,13 @@ pub struct WinternitzSigningInputs<'a, 'b> {
     pub signing_key: &'b WinternitzSecret,
 }
 
+/// Signing inputs for committing several messages under a single Winternitz key.
+/// The key must be created with `WinternitzSecret::new` for the total size of all messages.
+pub struct WinternitzBatchSigningInputs<'a, 'b> {
+    pub messages: &'a [&'a [u8]],
+    pub signing_key: &'b WinternitzSecret,
+}
+
 pub fn generate_winternitz_checksig_leave_hash(
     public_key: &WinternitzPublicKey,
     message_size: usize,
@@ -97,6 +104,140 @@ pub fn generate_winternitz_checksig_leave_variable(
     }
 }
 
+/// Digit keys are derived from the secret and a one-byte digit index, so a single key must not
+/// cover more than 256 digits, checksum included, or two digits would share a hash chain.
+pub const MAX_BATCH_DIGIT_COUNT: u32 = 256;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WinternitzBatchError {
+    /// The messages need more digits than a single key can sign
+    TooManyDigits(u32),
+    /// The key was not created for the total size of the messages
+    KeySizeMismatch { expected: usize, actual: usize },
+    /// The stack does not hold the total size of the messages
+    StackSizeMismatch { expected: usize, actual: usize },
+    /// The selected message is not in the batch
+    InvalidMessageIndex(usize),
+}
+
+/// Check that messages of `total_size` bytes in total can be committed under a single key.
+pub fn check_batch_size(total_size: usize) -> Result<(), WinternitzBatchError> {
+    let digit_count = Parameters::new((total_size * 2) as u32, LOG_D).total_digit_count();
+    if digit_count > MAX_BATCH_DIGIT_COUNT {
+        return Err(WinternitzBatchError::TooManyDigits(digit_count));
+    }
+    Ok(())
+}
+
+fn check_batch_key(parameters: &Parameters, total_size: usize) -> Result<(), WinternitzBatchError> {
+    check_batch_size(total_size)?;
+    let key_size = parameters.message_digit_count() as usize / 2;
+    if key_size != total_size {
+        return Err(WinternitzBatchError::KeySizeMismatch {
+            expected: key_size,
+            actual: total_size,
+        });
+    }
+    Ok(())
+}
+
+impl WinternitzSecret {
+    /// Generate a secret committing all the messages of `message_sizes` bytes at once.
+    pub fn new_batch(message_sizes: &[usize]) -> Result<Self, WinternitzBatchError> {
+        let total_size = message_sizes.iter().sum();
+        check_batch_size(total_size)?;
+        Ok(Self::new(total_size))
+    }
+}
+
+/// Verify a batched Winternitz signature with a single checksum over all messages.
+/// The messages are left on the stack in order, the first message at the bottom, each of them
+/// laid out the same way as `generate_winternitz_checksig_leave_hash` would leave it.
+pub fn generate_winternitz_batch_checksig_leave_hashes(
+    public_key: &WinternitzPublicKey,
+    message_sizes: &[usize],
+) -> Result<Script, WinternitzBatchError> {
+    let total_size = message_sizes.iter().sum();
+    check_batch_key(&public_key.parameters, total_size)?;
+    Ok(generate_winternitz_checksig_leave_hash(
+        public_key, total_size,
+    ))
+}
+
+/// Verify a batched Winternitz signature and only leave the message at `message_index`,
+/// laid out the same way as `generate_winternitz_checksig_leave_hash` would leave it.
+pub fn generate_winternitz_batch_checksig_leave_hash(
+    public_key: &WinternitzPublicKey,
+    message_sizes: &[usize],
+    message_index: usize,
+) -> Result<Script, WinternitzBatchError> {
+    let message_size = *message_sizes
+        .get(message_index)
+        .ok_or(WinternitzBatchError::InvalidMessageIndex(message_index))?;
+    let size_before: usize = message_sizes[..message_index].iter().sum();
+    let size_after: usize = message_sizes[message_index + 1..].iter().sum();
+    let checksig = generate_winternitz_batch_checksig_leave_hashes(public_key, message_sizes)?;
+    Ok(script! {
+        { checksig }
+        for _ in 0..size_after / 2 {
+            OP_2DROP
+        }
+        for _ in 0..size_after % 2 {
+            OP_DROP
+        }
+        for _ in 0..message_size {
+            OP_TOALTSTACK
+        }
+        for _ in 0..size_before / 2 {
+            OP_2DROP
+        }
+        for _ in 0..size_before % 2 {
+            OP_DROP
+        }
+        for _ in 0..message_size {
+            OP_FROMALTSTACK
+        }
+    })
+}
+
+pub fn generate_winternitz_batch_witness(
+    signing_inputs: &WinternitzBatchSigningInputs,
+) -> Result<Witness, WinternitzBatchError> {
+    let message = signing_inputs.messages.concat();
+    check_batch_key(&signing_inputs.signing_key.parameters, message.len())?;
+    Ok(WINTERNITZ_MESSAGE_VERIFIER.sign(
+        &signing_inputs.signing_key.parameters,
+        &signing_inputs.signing_key.secret_key,
+        &message,
+    ))
+}
+
+/// Split the bytes left by `generate_winternitz_batch_checksig_leave_hashes` back into messages.
+pub fn split_batch_messages(
+    stack: &[Vec<u8>],
+    message_sizes: &[usize],
+) -> Result<Vec<Vec<u8>>, WinternitzBatchError> {
+    let total_size = message_sizes.iter().sum();
+    if stack.len() != total_size {
+        return Err(WinternitzBatchError::StackSizeMismatch {
+            expected: total_size,
+            actual: stack.len(),
+        });
+    }
+    let mut offset = 0;
+    Ok(message_sizes
+        .iter()
+        .map(|size| {
+            let message = stack[offset..offset + size]
+                .iter()
+                .map(|byte| byte.first().copied().unwrap_or(0))
+                .collect();
+            offset += size;
+            message
+        })
+        .collect())
+}
+
 pub fn generate_winternitz_hash_witness(signing_inputs: &WinternitzSigningInputs) -> Witness {
     sign_hash(
         &signing_inputs.signing_key.secret_key,
@@ -112,6 +253,32 @@ pub fn generate_winternitz_witness(signing_inputs: &WinternitzSigningInputs) ->
     )
 }
 
+/// The message of a witness generated by `generate_winternitz_witness` for a message of
+/// `message_size` bytes, read from the digits given next to their signatures.
+/// The witness may be followed by other elements.
+pub fn winternitz_message_from_witness(witness: &[Vec<u8>], message_size: usize) -> Vec<u8> {
+    let digit_count = message_size * 8 / LOG_D as usize;
+    assert!(witness.len() >= 2 * digit_count, "the witness is too short for the message");
+    // the digits of the message are the first ones of the witness, least significant last
+    let digits: Vec<u8> = witness
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .take(digit_count)
+        .map(|digit| digit.first().copied().unwrap_or(0))
+        .rev()
+        .collect();
+    digits
+        .chunks(8 / LOG_D as usize)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0, |byte, (i, digit)| byte | digit << (i as u32 * LOG_D))
+        })
+        .collect()
+}
+
 pub fn winternitz_message_checksig(public_key: &WinternitzPublicKey) -> Script {
     WINTERNITZ_MESSAGE_VERIFIER.checksig_verify(&public_key.parameters, &public_key.public_key)
 }
@@ -147,6 +314,24 @@ mod tests {
     use bitcoin_script::script;
     use rand::{RngCore as _, SeedableRng as _};
 
+    #[test]
+    fn test_winternitz_message_from_witness() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        for message_size in [4, 20, 80] {
+            let secret = WinternitzSecret::new(message_size);
+            let mut message = vec![0u8; message_size];
+            rng.fill_bytes(&mut message);
+            let witness = generate_winternitz_witness(&WinternitzSigningInputs {
+                message: &message,
+                signing_key: &secret,
+            });
+            assert_eq!(
+                winternitz_message_from_witness(&witness.to_vec(), message_size),
+                message
+            );
+        }
+    }
+
     #[test]
     fn test_signing_winternitz_with_message_success() {
         let secret = WinternitzSecret::new(4);
@@ -171,6 +356,128 @@ mod tests {
         assert!(result.success);
     }
 
+    fn batch_test_messages(count: u8) -> Vec<Vec<u8>> {
+        (0..count)
+            .map(|i| {
+                (0..BLAKE3_HASH_LENGTH as u8)
+                    .map(|j| i.wrapping_mul(31).wrapping_add(j.wrapping_mul(7)))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_batch_winternitz_success() {
+        let messages = batch_test_messages(6);
+        let message_sizes = vec![BLAKE3_HASH_LENGTH; messages.len()];
+        let secret = WinternitzSecret::new_batch(&message_sizes).unwrap();
+        let public_key = WinternitzPublicKey::from(&secret);
+
+        let witness = generate_winternitz_batch_witness(&WinternitzBatchSigningInputs {
+            messages: &messages.iter().map(|m| m.as_slice()).collect::<Vec<_>>(),
+            signing_key: &secret,
+        })
+        .unwrap();
+        let script =
+            generate_winternitz_batch_checksig_leave_hashes(&public_key, &message_sizes).unwrap();
+
+        let res = execute_script_with_inputs(script.clone(), witness.to_vec());
+        let recovered =
+            split_batch_messages(&extract_witness_from_stack(res), &message_sizes).unwrap();
+        assert_eq!(recovered, messages);
+
+        let s = script! {
+            { script.clone() }
+            for message in messages.iter().rev() {
+                for byte in message.iter().rev() {
+                    { *byte }
+                    OP_EQUALVERIFY
+                }
+            }
+            OP_TRUE
+        };
+        assert!(execute_script_with_inputs(s, witness.to_vec()).success);
+
+        // compare with one checksig per message
+        let single_public_key =
+            WinternitzPublicKey::from(&WinternitzSecret::new(BLAKE3_HASH_LENGTH));
+        let single_script =
+            generate_winternitz_checksig_leave_hash(&single_public_key, BLAKE3_HASH_LENGTH);
+        assert!(script.len() < single_script.len() * messages.len());
+    }
+
+    #[test]
+    fn test_batch_winternitz_leave_single_message() {
+        let messages = batch_test_messages(3);
+        let message_sizes = vec![BLAKE3_HASH_LENGTH; messages.len()];
+        let secret = WinternitzSecret::new_batch(&message_sizes).unwrap();
+        let public_key = WinternitzPublicKey::from(&secret);
+        let witness = generate_winternitz_batch_witness(&WinternitzBatchSigningInputs {
+            messages: &messages.iter().map(|m| m.as_slice()).collect::<Vec<_>>(),
+            signing_key: &secret,
+        })
+        .unwrap();
+
+        for (message_index, message) in messages.iter().enumerate() {
+            let s = script! {
+                { generate_winternitz_batch_checksig_leave_hash(&public_key, &message_sizes, message_index).unwrap() }
+                for byte in message.iter().rev() {
+                    { *byte }
+                    OP_EQUALVERIFY
+                }
+                OP_TRUE
+            };
+            assert!(execute_script_with_inputs(s, witness.to_vec()).success);
+        }
+        assert!(matches!(
+            generate_winternitz_batch_checksig_leave_hash(&public_key, &message_sizes, 3),
+            Err(WinternitzBatchError::InvalidMessageIndex(3))
+        ));
+    }
+
+    #[test]
+    fn test_batch_winternitz_too_many_digits() {
+        let message_sizes = vec![BLAKE3_HASH_LENGTH; 7];
+        assert!(matches!(
+            WinternitzSecret::new_batch(&message_sizes),
+            Err(WinternitzBatchError::TooManyDigits(_))
+        ));
+
+        let secret = WinternitzSecret::new(BLAKE3_HASH_LENGTH * 7);
+        let public_key = WinternitzPublicKey::from(&secret);
+        assert!(matches!(
+            generate_winternitz_batch_checksig_leave_hashes(&public_key, &message_sizes),
+            Err(WinternitzBatchError::TooManyDigits(_))
+        ));
+    }
+
+    #[test]
+    fn test_batch_winternitz_wrong_message_fails() {
+        let messages: Vec<Vec<u8>> = vec![vec![1; BLAKE3_HASH_LENGTH], vec![2; BLAKE3_HASH_LENGTH]];
+        let message_sizes = vec![BLAKE3_HASH_LENGTH; messages.len()];
+        let secret = WinternitzSecret::new_batch(&message_sizes).unwrap();
+        let public_key = WinternitzPublicKey::from(&secret);
+
+        let mut witness = generate_winternitz_batch_witness(&WinternitzBatchSigningInputs {
+            messages: &messages.iter().map(|m| m.as_slice()).collect::<Vec<_>>(),
+            signing_key: &secret,
+        })
+        .unwrap()
+        .to_vec();
+        // change a message digit without updating its signature
+        let digit_index = witness.len() - 1 - 2 * 8;
+        witness[digit_index] = vec![witness[digit_index].first().copied().unwrap_or(0) ^ 1];
+
+        let s = script! {
+            { generate_winternitz_batch_checksig_leave_hashes(&public_key, &message_sizes).unwrap() }
+            for _ in 0..BLAKE3_HASH_LENGTH * messages.len() {
+                OP_DROP
+            }
+            OP_TRUE
+        };
+        assert!(!execute_script_with_inputs(s, witness).success);
+    }
+
     #[test]
     fn test_generate_winternitz_secret_length() {
         // Uses an arbitrary message size of 1
@@ -48,6 +48,9 @@ impl Parameters {
     pub fn total_digit_count(&self) -> u32 {
         self.n
     }
+    pub fn message_digit_count(&self) -> u32 {
+        self.n0
+    }
 }
 
 fn public_key_for_digit(ps: &Parameters, secret_key: &SecretKey, digit_index: u32) -> HashOut {
@@ -1,8 +1,11 @@
 pub mod u4_add;
 pub mod u4_add_stack;
+pub mod u4_alu;
 pub mod u4_logic;
 pub mod u4_logic_stack;
+pub mod u4_mul;
 pub mod u4_rot;
 pub mod u4_shift;
 pub mod u4_shift_stack;
 pub mod u4_std;
+pub mod u4_tables;
@@ -0,0 +1,412 @@
+use crate::treepp::*;
+use super::u4_add::{u4_add_with_table, u4_arrange_nibbles};
+use super::u4_logic::{u4_half_table_operation, u4_xor_with_half_and_table};
+use super::u4_mul::u4_mul_u32;
+use super::u4_rot::u4_rrot;
+use super::u4_shift::u4_2_nib_rshift_n;
+use super::u4_std::{u4_drop, u4_fromaltstack, u4_move_u32_from, u4_number_to_nibble};
+use super::u4_tables::{U4Table, U4Tables};
+
+/*
+    u32 arithmetic on numbers consisting of 8 u4's, the least significant u4 being on the top.
+    Binary operations take the numbers a and b (b on the top) and replace them with the result.
+    The operations needing tables take an offset parameter which should be equal to the number of elements between the operands and the tables.
+*/
+
+#[derive(Clone, Copy)]
+enum LogicOperation {
+    And,
+    Or,
+    Xor,
+}
+
+/// Selection of the tables of the u32 ALU, laid out by `U4Tables`
+/// The logic operations use the half AND table, the other operations the tables of the same name.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct U4Alu {
+    tables: U4Tables,
+}
+
+impl U4Alu {
+    /// Selection of the tables among the addition, multiplication, shift and half AND tables
+    pub fn new(tables: &[U4Table]) -> Self {
+        assert!(
+            tables.iter().all(|table| matches!(
+                table,
+                U4Table::Add | U4Table::Mul | U4Table::Shift | U4Table::HalfAnd | U4Table::HalfLookup
+            )),
+            "the ALU does not use the tables {:?}",
+            tables
+        );
+        Self { tables: U4Tables::new(tables) }
+    }
+
+    /// Selection of all the tables
+    pub fn all() -> Self { Self::new(&[U4Table::Add, U4Table::Mul, U4Table::Shift, U4Table::HalfAnd]) }
+
+    /// Layout of the selected tables
+    pub fn tables(&self) -> &U4Tables { &self.tables }
+
+    /// Number of elements of the selected tables
+    pub fn tables_size(&self) -> u32 { self.tables.size() }
+
+    /// Pushes the selected tables
+    pub fn push_tables(&self) -> Script { self.tables.push() }
+
+    /// Drops the selected tables
+    pub fn drop_tables(&self) -> Script { self.tables.drop() }
+
+    /// Pushes the u32 number
+    pub fn push(x: u32) -> Script { u4_number_to_nibble(x) }
+
+    /// Drops the u32 number at the top of the stack
+    pub fn drop() -> Script { u4_drop(8) }
+
+    /// a + b modulo 2^32
+    pub fn add(&self, offset: u32) -> Script {
+        script! {
+            { u4_add_with_table(8, vec![0, 8], self.tables.offset(U4Table::Add, offset + 16)) }
+            { u4_fromaltstack(8) }
+        }
+    }
+
+    /// a - b modulo 2^32, calculated as a + NOT(b) + 1
+    pub fn sub(&self, offset: u32) -> Script {
+        script! {
+            { Self::not() }
+            // the least significant u4 of NOT(b) is at most 15, so the sum of the column fits in the tables
+            OP_1ADD
+            { self.add(offset) }
+        }
+    }
+
+    /// a * b modulo 2^32
+    pub fn mul(&self, offset: u32) -> Script {
+        script! {
+            { u4_mul_u32(self.tables.offset(U4Table::Mul, offset + 16), false) }
+            { u4_fromaltstack(8) }
+        }
+    }
+
+    /// floor(a * b / 2^32), i.e. the high 32 bits of the product
+    pub fn mulhi(&self, offset: u32) -> Script {
+        script! {
+            { u4_mul_u32(self.tables.offset(U4Table::Mul, offset + 16), true) }
+            { u4_fromaltstack(8) }
+        }
+    }
+
+    fn logic(&self, offset: u32, operation: LogicOperation) -> Script {
+        assert!(self.tables.contains(U4Table::HalfAnd), "the half AND table is not selected");
+        let lookup = self.tables.offset(U4Table::HalfLookup, offset + 16);
+        let nib_operation = |lookup: u32| match operation {
+            LogicOperation::And => u4_half_table_operation(lookup),
+            LogicOperation::Xor => u4_xor_with_half_and_table(lookup),
+            // a OR b = (a + b) - (a AND b)
+            LogicOperation::Or => script! {
+                OP_2DUP
+                { u4_half_table_operation(lookup + 2) }
+                OP_SUB
+                OP_ADD
+            },
+        };
+        script! {
+            { u4_arrange_nibbles(8, vec![0, 8]) }
+            for nib in 0..8 {
+                { nib_operation(lookup - 2 * nib) }
+                OP_TOALTSTACK
+            }
+            { u4_fromaltstack(8) }
+        }
+    }
+
+    /// Bitwise a AND b
+    pub fn and(&self, offset: u32) -> Script { self.logic(offset, LogicOperation::And) }
+
+    /// Bitwise a OR b
+    pub fn or(&self, offset: u32) -> Script { self.logic(offset, LogicOperation::Or) }
+
+    /// Bitwise a XOR b
+    pub fn xor(&self, offset: u32) -> Script { self.logic(offset, LogicOperation::Xor) }
+
+    /// Bitwise NOT of the number at the top of the stack, without tables
+    pub fn not() -> Script {
+        script! {
+            for _ in 0..8 {
+                7
+                OP_ROLL
+                OP_15
+                OP_SWAP
+                OP_SUB
+            }
+        }
+    }
+
+    /// Right rotation of the number at the top of the stack by the constant n
+    pub fn rrot(&self, n: u32, offset: u32) -> Script {
+        assert!(n < 32);
+        if n == 0 {
+            return script! {};
+        }
+        script! {
+            { u4_rrot(n, 0, self.tables.offset(U4Table::Shift, offset + 8), false) }
+            { u4_drop(8) }
+            { u4_fromaltstack(8) }
+        }
+    }
+
+    /// Left rotation of the number at the top of the stack by the constant n
+    pub fn lrot(&self, n: u32, offset: u32) -> Script {
+        assert!(n < 32);
+        self.rrot((32 - n) % 32, offset)
+    }
+
+    /// Logical right shift of the number at the top of the stack by the constant n
+    pub fn rshift(&self, n: u32, offset: u32) -> Script {
+        assert!(n < 32);
+        if n == 0 {
+            return script! {};
+        }
+        script! {
+            { u4_rrot(n, 0, self.tables.offset(U4Table::Shift, offset + 8), true) }
+            { u4_drop(8) }
+            { u4_fromaltstack(8) }
+        }
+    }
+
+    /// Left shift of the number at the top of the stack by the constant n
+    /// The i'th u4 of the result is ((16 * x_j + x_(j-1)) >> (4 - n % 4)) modulo 16 with j = i - n / 4, x_j being zero for negative j
+    pub fn lshift(&self, n: u32, offset: u32) -> Script {
+        assert!(n < 32);
+        if n == 0 {
+            return script! {};
+        }
+        let (pos_shift, bit_shift) = (n / 4, n % 4);
+        let tables_offset = if bit_shift == 0 { 0 } else { self.tables.offset(U4Table::Shift, offset + 8) };
+        script! {
+            for i in 0..8 {
+                if bit_shift == 0 {
+                    if i < pos_shift {
+                        OP_0
+                    } else {
+                        { i - pos_shift }
+                        OP_PICK
+                    }
+                } else {
+                    if i > pos_shift {
+                        { i - pos_shift - 1 }
+                        OP_PICK
+                    } else {
+                        OP_0
+                    }
+                    if i >= pos_shift {
+                        { i - pos_shift + 1 }
+                        OP_PICK
+                    } else {
+                        OP_0
+                    }
+                    { u4_2_nib_rshift_n(4 - bit_shift, tables_offset) }
+                }
+                OP_TOALTSTACK
+            }
+            { u4_drop(8) }
+            { u4_fromaltstack(8) }
+        }
+    }
+
+    fn lessthan_internal(signed: bool) -> Script {
+        script! {
+            { u4_arrange_nibbles(8, vec![0, 8]) }
+            // the borrow of a - b
+            OP_0
+            for i in 0..8 {
+                if signed && i == 7 {
+                    // flipping the sign bits makes the signed order unsigned
+                    for _ in 0..2 {
+                        OP_ROT
+                        OP_8
+                        OP_ADD
+                        OP_DUP
+                        OP_16
+                        OP_GREATERTHANOREQUAL
+                        OP_IF
+                            OP_16
+                            OP_SUB
+                        OP_ENDIF
+                    }
+                    OP_ROT
+                }
+                OP_ADD
+                OP_SUB
+                OP_0
+                OP_LESSTHAN
+            }
+        }
+    }
+
+    /// a < b as unsigned numbers, without tables
+    pub fn lessthan() -> Script { Self::lessthan_internal(false) }
+
+    /// a < b as two's complement signed numbers, without tables
+    pub fn lessthan_signed() -> Script { Self::lessthan_internal(true) }
+
+    /// a > b as unsigned numbers, without tables
+    pub fn greaterthan() -> Script {
+        script! {
+            { u4_move_u32_from(8) }
+            { Self::lessthan() }
+        }
+    }
+
+    /// a <= b as unsigned numbers, without tables
+    pub fn lessthanorequal() -> Script {
+        script! {
+            { Self::greaterthan() }
+            OP_NOT
+        }
+    }
+
+    /// a >= b as unsigned numbers, without tables
+    pub fn greaterthanorequal() -> Script {
+        script! {
+            { Self::lessthan() }
+            OP_NOT
+        }
+    }
+
+    /// a == b, without tables
+    pub fn equal() -> Script {
+        script! {
+            for i in 0..8 {
+                { 8 - i }
+                OP_ROLL
+                OP_EQUAL
+                OP_TOALTSTACK
+            }
+            OP_FROMALTSTACK
+            for _ in 1..8 {
+                OP_FROMALTSTACK
+                OP_BOOLAND
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::u4::u4_std::verify_n;
+    use rand::Rng;
+
+    fn random_operands() -> Vec<(u32, u32)> {
+        let mut rng = rand::thread_rng();
+        let mut operands = vec![(0, 0), (u32::MAX, u32::MAX), (0, u32::MAX), (u32::MAX, 1), (0x8000_0000, 0x7fff_ffff)];
+        operands.extend((0..50).map(|_| (rng.gen(), rng.gen())));
+        operands
+    }
+
+    #[test]
+    fn test_binary_operations() {
+        let alu = U4Alu::all();
+        let operations: Vec<(&str, Script, fn(u32, u32) -> u32)> = vec![
+            ("add", alu.add(2), u32::wrapping_add),
+            ("sub", alu.sub(2), u32::wrapping_sub),
+            ("mul", alu.mul(2), u32::wrapping_mul),
+            ("mulhi", alu.mulhi(2), |a, b| ((a as u64 * b as u64) >> 32) as u32),
+            ("and", alu.and(2), |a, b| a & b),
+            ("or", alu.or(2), |a, b| a | b),
+            ("xor", alu.xor(2), |a, b| a ^ b),
+        ];
+        for (name, operation, expected) in operations {
+            println!("U4Alu::{}: {}", name, operation.len());
+            for (a, b) in random_operands() {
+                let script = script! {
+                    { alu.push_tables() }
+                    // unrelated elements between the tables and the operands
+                    OP_0
+                    OP_0
+                    { U4Alu::push(a) }
+                    { U4Alu::push(b) }
+                    { operation.clone() }
+                    { U4Alu::push(expected(a, b)) }
+                    { verify_n(8) }
+                    OP_2DROP
+                    { alu.drop_tables() }
+                    OP_TRUE
+                };
+                run(script);
+            }
+        }
+    }
+
+    #[test]
+    fn test_not() {
+        for (a, _) in random_operands() {
+            let script = script! {
+                { U4Alu::push(a) }
+                { U4Alu::not() }
+                { U4Alu::push(!a) }
+                { verify_n(8) }
+                OP_TRUE
+            };
+            run(script);
+        }
+    }
+
+    #[test]
+    fn test_shifts() {
+        // only the shift tables, at an offset from the operand
+        let alu = U4Alu::new(&[U4Table::Shift]);
+        let operations: Vec<(&str, fn(&U4Alu, u32, u32) -> Script, fn(u32, u32) -> u32)> = vec![
+            ("rrot", U4Alu::rrot, u32::rotate_right),
+            ("lrot", U4Alu::lrot, u32::rotate_left),
+            ("rshift", U4Alu::rshift, |x, n| x >> n),
+            ("lshift", U4Alu::lshift, |x, n| x << n),
+        ];
+        for (name, operation, expected) in operations {
+            for (x, _) in random_operands().into_iter().take(10) {
+                for n in 0..32 {
+                    let script = script! {
+                        { alu.push_tables() }
+                        OP_0
+                        { U4Alu::push(x) }
+                        { operation(&alu, n, 1) }
+                        { U4Alu::push(expected(x, n)) }
+                        { verify_n(8) }
+                        OP_DROP
+                        { alu.drop_tables() }
+                        OP_TRUE
+                    };
+                    assert!(execute_script(script).success, "{} {:x} by {}", name, x, n);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let operations: Vec<(&str, Script, fn(u32, u32) -> bool)> = vec![
+            ("lessthan", U4Alu::lessthan(), |a, b| a < b),
+            ("lessthan_signed", U4Alu::lessthan_signed(), |a, b| (a as i32) < (b as i32)),
+            ("greaterthan", U4Alu::greaterthan(), |a, b| a > b),
+            ("lessthanorequal", U4Alu::lessthanorequal(), |a, b| a <= b),
+            ("greaterthanorequal", U4Alu::greaterthanorequal(), |a, b| a >= b),
+            ("equal", U4Alu::equal(), |a, b| a == b),
+        ];
+        let mut operands = random_operands();
+        operands.extend(operands.clone().into_iter().map(|(a, b)| (b, a)));
+        operands.extend([(7, 7), (0x8000_0000, 0x8000_0000), (0x1234_5678, 0x1234_5679)]);
+        for (name, operation, expected) in operations {
+            for &(a, b) in &operands {
+                let script = script! {
+                    { U4Alu::push(a) }
+                    { U4Alu::push(b) }
+                    { operation.clone() }
+                    { expected(a, b) as u32 }
+                    OP_EQUAL
+                };
+                assert!(execute_script(script).success, "{} {:x} {:x}", name, a, b);
+            }
+        }
+    }
+}
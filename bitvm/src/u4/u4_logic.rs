@@ -193,13 +193,17 @@ pub fn u4_sort() -> Script {
 }
 
 /// Calculates the logic operation with the given half table, lookup parameter denoting how many elements are there after the table including the two u4 elements
-pub fn u4_half_table_operation(lookup: u32) -> Script {
+pub fn u4_half_table_operation(lookup: u32) -> Script { u4_half_table_operation_with_gap(lookup, 0) }
+
+/// Calculates the logic operation with a half table which is gap elements below its lookup table, lookup parameter denoting how many elements are there after the lookup table including the two u4 elements
+/// The gap allows both half tables to share a single lookup table.
+pub fn u4_half_table_operation_with_gap(lookup: u32, gap: u32) -> Script {
     script! {
         { u4_sort() }
         { lookup - 1 }
         OP_ADD
         OP_PICK
-        { lookup - 2 }
+        { lookup - 2 + gap }
         OP_ADD
         OP_ADD
         OP_PICK
@@ -235,7 +239,20 @@ pub fn u4_xor_with_half_and_table(lookup: u32) -> Script {
 /// Does bitwise operation with bases.len() elements at the top of the stack, both consisting of nibble_count u4's and at the positions of the bases vector (note that existing operations are commutative)
 /// Expects a half logic operation table and offset parameter to locate it, which should be equal to the number of elements after the table including the inputs
 /// Keeps the result at the altstack
-pub fn u4_logic_nibs(nibble_count: u32, mut bases: Vec<u32>, offset: u32, do_xor_with_half_and_table: bool) -> Script {
+pub fn u4_logic_nibs(nibble_count: u32, bases: Vec<u32>, offset: u32, do_xor_with_half_and_table: bool) -> Script {
+    u4_logic_nibs_with_gap(nibble_count, bases, offset, 0, do_xor_with_half_and_table)
+}
+
+/// Same as `u4_logic_nibs` with the half table gap elements below its lookup table, offset locating the lookup table
+/// XOR with the half AND table expects the AND table right below the lookup table.
+pub fn u4_logic_nibs_with_gap(
+    nibble_count: u32,
+    mut bases: Vec<u32>,
+    offset: u32,
+    gap: u32,
+    do_xor_with_half_and_table: bool,
+) -> Script {
+    assert!(gap == 0 || !do_xor_with_half_and_table, "the half AND table should be right below the lookup table");
     let numbers = bases.len() as u32;
     bases.sort();
     script! {
@@ -245,7 +262,7 @@ pub fn u4_logic_nibs(nibble_count: u32, mut bases: Vec<u32>, offset: u32, do_xor
                 if do_xor_with_half_and_table {
                     { u4_xor_with_half_and_table( offset - i - nib * numbers ) }
                 } else {
-                    { u4_half_table_operation( offset - i - nib * numbers ) }
+                    { u4_half_table_operation_with_gap( offset - i - nib * numbers, gap ) }
                 }
             }
             OP_TOALTSTACK
@@ -386,6 +403,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_shared_half_lookup() {
+        for x in 0..16 {
+            for y in 0..16 {
+                let script = script! {
+                    { u4_push_half_xor_table() }
+                    { u4_push_half_and_table() }
+                    { u4_push_half_lookup() }
+                    { x }
+                    { y }
+                    OP_2DUP
+                    { u4_half_table_operation_with_gap(4, 136) }
+                    { x ^ y }
+                    OP_EQUALVERIFY
+                    { u4_half_table_operation(2) }
+                    { x & y }
+                    OP_EQUALVERIFY
+                    { u4_drop_half_lookup() }
+                    { u4_drop_half_table() }
+                    { u4_drop_half_table() }
+                    OP_TRUE
+                };
+                run(script);
+            }
+        }
+    }
+
     #[test]
     fn test_xor() {
         for a in 0..16 {
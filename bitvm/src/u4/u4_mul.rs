@@ -0,0 +1,127 @@
+use crate::treepp::*;
+use super::u4_std::u4_drop;
+
+/// Pushes the u4 multiplication table, which calculates a * b with OP_PICK'ing (16 * a + b)
+pub fn u4_push_mul_table() -> Script {
+    script! {
+        for i in (0..16).rev() {
+            for j in (0..16).rev() {
+                { i * j }
+            }
+        }
+    }
+}
+
+/// Drops the u4 multiplication table
+pub fn u4_drop_mul_table() -> Script { u4_drop(16 * 16) }
+
+/// Splits the column sum at the top of the stack into the carry and the nibble, the nibble being on the top
+/// The sum of a column of a u32 product is at most 8 * 15 * 15 plus the carry, which is below 2^11
+pub fn u4_split_column() -> Script {
+    script! {
+        OP_0
+        OP_SWAP
+        for bit in (4..=10).rev() {
+            OP_DUP
+            { 1 << bit }
+            OP_GREATERTHANOREQUAL
+            OP_IF
+                { 1 << bit }
+                OP_SUB
+                OP_SWAP
+                { 1 << (bit - 4) }
+                OP_ADD
+                OP_SWAP
+            OP_ENDIF
+        }
+    }
+}
+
+/// Multiplication of the two u32 numbers at the top of the stack, consisting of 8 u4's each
+/// Calculates the low 32 bits of the product, or the high 32 bits if high is set
+/// Requires the multiplication table and tables_offset to locate it which should be equal to number of elements on top of the table including operating values
+/// Consumes the numbers and keeps the result at the altstack
+pub fn u4_mul_u32(tables_offset: u32, high: bool) -> Script {
+    let columns: u32 = if high { 16 } else { 8 };
+    script! {
+        // the carry of the columns
+        OP_0
+        for k in 0..columns {
+            for i in k.saturating_sub(7)..=k.min(7) {
+                // a_i * 16 + b_j, the carry being on top of the numbers
+                { 9 + i }
+                OP_PICK
+                for _ in 0..4 {
+                    OP_DUP
+                    OP_ADD
+                }
+                { 2 + k - i }
+                OP_PICK
+                OP_ADD
+                { tables_offset + 1 }
+                OP_ADD
+                OP_PICK
+                OP_ADD
+            }
+            { u4_split_column() }
+            if k >= columns - 8 {
+                OP_TOALTSTACK
+            } else {
+                OP_DROP
+            }
+        }
+        OP_DROP
+        { u4_drop(16) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::u4::u4_std::{u4_number_to_nibble, u4_u32_verify_from_altstack};
+    use rand::Rng;
+
+    #[test]
+    fn test_mul_table() {
+        for a in 0..16 {
+            for b in 0..16 {
+                let script = script! {
+                    { u4_push_mul_table() }
+                    { 16 * a + b }
+                    OP_PICK
+                    { a * b }
+                    OP_EQUALVERIFY
+                    { u4_drop_mul_table() }
+                    OP_TRUE
+                };
+                run(script);
+            }
+        }
+    }
+
+    #[test]
+    fn test_mul_u32() {
+        let mut rng = rand::thread_rng();
+        println!("u4_mul_u32: {}", u4_mul_u32(16, false).len());
+        println!("u4_mul_u32 high: {}", u4_mul_u32(16, true).len());
+        for _ in 0..100 {
+            let a: u32 = rng.gen();
+            let b: u32 = rng.gen();
+            let product = a as u64 * b as u64;
+            for high in [false, true] {
+                let expected = if high { (product >> 32) as u32 } else { product as u32 };
+                let script = script! {
+                    { u4_push_mul_table() }
+                    { u4_number_to_nibble(a) }
+                    { u4_number_to_nibble(b) }
+                    { u4_mul_u32(16, high) }
+                    { u4_drop_mul_table() }
+                    { u4_number_to_nibble(expected) }
+                    { u4_u32_verify_from_altstack() }
+                    OP_TRUE
+                };
+                run(script);
+            }
+        }
+    }
+}
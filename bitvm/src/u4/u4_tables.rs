@@ -0,0 +1,394 @@
+use crate::treepp::*;
+use super::u4_add::{u4_add_with_table, u4_drop_add_tables, u4_push_add_tables};
+use super::u4_logic::{
+    u4_drop_full_logic_table, u4_drop_full_lookup, u4_drop_half_lookup, u4_drop_half_table, u4_logic_nibs_with_gap,
+    u4_push_full_lookup, u4_push_full_xor_table, u4_push_half_and_table, u4_push_half_lookup, u4_push_half_xor_table,
+};
+use super::u4_mul::{u4_drop_mul_table, u4_mul_u32, u4_push_mul_table};
+use super::u4_rot::u4_rrot;
+use super::u4_shift::{u4_drop_2_nib_rshift_tables, u4_push_2_nib_rshift_tables};
+use super::u4_std::{u4_fromaltstack, u4_toaltstack};
+
+/*
+    Scheduling of the u4 lookup tables of a composite script: each part declares the tables it needs and the change of
+    the number of elements it makes on the stack, the tables are pushed once below the inputs of the composite and the
+    offsets of the parts are calculated from the layout of the tables and the number of elements on top of them.
+*/
+
+/// A u4 lookup table, the full XOR table together with its lookup table
+/// The tables are pushed in the order of the variants, the last ones on the top. The half tables share the half lookup,
+/// which is pushed on top of them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum U4Table {
+    /// Modulo and quotient tables of the additions
+    Add,
+    /// Multiplication table
+    Mul,
+    /// Full XOR table and full lookup
+    FullXor,
+    /// Left and right shift tables of the 2 nibble shifts and rotations
+    Shift,
+    /// Half XOR table
+    HalfXor,
+    /// Half AND table
+    HalfAnd,
+    /// Lookup of the half tables
+    HalfLookup,
+}
+
+impl U4Table {
+    /// Number of elements of the table
+    pub fn size(&self) -> u32 {
+        match self {
+            U4Table::Mul => 16 * 16,
+            U4Table::FullXor => 16 * 16 + 17,
+            U4Table::Shift => 16 * 5,
+            U4Table::HalfXor | U4Table::HalfAnd => 136,
+            U4Table::HalfLookup => 16,
+            U4Table::Add => 65 * 2,
+        }
+    }
+
+    pub fn push(&self) -> Script {
+        match self {
+            U4Table::Mul => u4_push_mul_table(),
+            U4Table::FullXor => script! {
+                { u4_push_full_xor_table() }
+                { u4_push_full_lookup() }
+            },
+            U4Table::Shift => u4_push_2_nib_rshift_tables(),
+            U4Table::HalfXor => u4_push_half_xor_table(),
+            U4Table::HalfAnd => u4_push_half_and_table(),
+            U4Table::HalfLookup => u4_push_half_lookup(),
+            U4Table::Add => u4_push_add_tables(),
+        }
+    }
+
+    pub fn drop(&self) -> Script {
+        match self {
+            U4Table::Mul => u4_drop_mul_table(),
+            U4Table::FullXor => script! {
+                { u4_drop_full_lookup() }
+                { u4_drop_full_logic_table() }
+            },
+            U4Table::Shift => u4_drop_2_nib_rshift_tables(),
+            U4Table::HalfXor | U4Table::HalfAnd => u4_drop_half_table(),
+            U4Table::HalfLookup => u4_drop_half_lookup(),
+            U4Table::Add => u4_drop_add_tables(),
+        }
+    }
+}
+
+/// Layout of the tables pushed once for a composite script
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct U4Tables {
+    tables: Vec<U4Table>,
+}
+
+impl U4Tables {
+    /// The layout of the tables, each of them pushed once in the order of `U4Table`
+    /// The half lookup is added to the layout of the half tables.
+    pub fn new(tables: &[U4Table]) -> Self {
+        let mut tables = tables.to_vec();
+        if tables.iter().any(|table| matches!(table, U4Table::HalfXor | U4Table::HalfAnd)) {
+            tables.push(U4Table::HalfLookup);
+        }
+        tables.sort();
+        tables.dedup();
+        Self { tables }
+    }
+
+    /// The layout of the tables of both layouts
+    pub fn union(&self, other: &U4Tables) -> Self { Self::new(&[self.tables.clone(), other.tables.clone()].concat()) }
+
+    pub fn tables(&self) -> &[U4Table] { &self.tables }
+
+    pub fn contains(&self, table: U4Table) -> bool { self.tables.contains(&table) }
+
+    /// Number of elements of the tables
+    pub fn size(&self) -> u32 { self.tables.iter().map(|table| table.size()).sum() }
+
+    pub fn push(&self) -> Script {
+        script! {
+            for table in self.tables.iter() {
+                { table.push() }
+            }
+        }
+    }
+
+    pub fn drop(&self) -> Script {
+        script! {
+            for table in self.tables.iter().rev() {
+                { table.drop() }
+            }
+        }
+    }
+
+    /// Number of elements on top of the table, given the number of elements on top of all the tables
+    pub fn offset(&self, table: U4Table, depth: u32) -> u32 {
+        assert!(self.contains(table), "the {:?} table is not in the layout", table);
+        depth + self.tables.iter().filter(|&&other| other > table).map(|other| other.size()).sum::<u32>()
+    }
+
+    /// Number of elements between the half lookup and the half table
+    pub fn half_table_gap(&self, table: U4Table) -> u32 {
+        assert!(matches!(table, U4Table::HalfXor | U4Table::HalfAnd), "{:?} is not a half table", table);
+        self.offset(table, 0) - self.offset(U4Table::HalfLookup, U4Table::HalfLookup.size())
+    }
+}
+
+struct U4Part {
+    tables: Vec<U4Table>,
+    delta: i32,
+    generate: Box<dyn Fn(&U4Tables, u32) -> Script>,
+}
+
+/// A composite script of u4 operations sharing their tables
+/// Each part is generated from the layout of the tables and the number of elements on top of them, the results of the
+/// operations are kept at the altstack as the underlying functions do.
+#[derive(Default)]
+pub struct U4Composer {
+    parts: Vec<U4Part>,
+}
+
+impl U4Composer {
+    pub fn new() -> Self { Self::default() }
+
+    /// Appends a part needing the tables, which changes the number of elements on the stack by delta
+    /// The part is generated from the layout and the number of elements on top of the tables before it.
+    pub fn with_tables(
+        mut self,
+        tables: &[U4Table],
+        delta: i32,
+        generate: impl Fn(&U4Tables, u32) -> Script + 'static,
+    ) -> Self {
+        self.parts.push(U4Part {
+            tables: tables.to_vec(),
+            delta,
+            generate: Box::new(generate),
+        });
+        self
+    }
+
+    /// Appends a script without tables, which changes the number of elements on the stack by delta
+    pub fn script(self, script: Script, delta: i32) -> Self { self.with_tables(&[], delta, move |_, _| script.clone()) }
+
+    /// Appends the parts of the other composite
+    pub fn then(mut self, mut other: U4Composer) -> Self {
+        self.parts.append(&mut other.parts);
+        self
+    }
+
+    /// Addition of the numbers of nibble_count u4's at the bases, the result being kept at the altstack
+    pub fn add(self, nibble_count: u32, bases: Vec<u32>) -> Self {
+        let delta = -((nibble_count * bases.len() as u32) as i32);
+        self.with_tables(&[U4Table::Add], delta, move |tables, depth| {
+            u4_add_with_table(nibble_count, bases.clone(), tables.offset(U4Table::Add, depth))
+        })
+    }
+
+    /// Bitwise operation of the half table on the numbers at the bases, the result being kept at the altstack
+    /// XOR is calculated with the half AND table if the table is HalfAnd and xor_with_and is set.
+    pub fn logic(self, table: U4Table, nibble_count: u32, bases: Vec<u32>, xor_with_and: bool) -> Self {
+        assert!(matches!(table, U4Table::HalfXor | U4Table::HalfAnd), "the logic operations use half tables");
+        let delta = -((nibble_count * bases.len() as u32) as i32);
+        self.with_tables(&[table], delta, move |tables, depth| {
+            u4_logic_nibs_with_gap(
+                nibble_count,
+                bases.clone(),
+                tables.offset(U4Table::HalfLookup, depth),
+                tables.half_table_gap(table),
+                xor_with_and,
+            )
+        })
+    }
+
+    /// Right rotation (or right shift if is_shift is set) of the u32 at number_pos by n bits
+    /// The number is kept and the result is pushed to the altstack.
+    pub fn rrot(self, n: u32, number_pos: u32, is_shift: bool) -> Self {
+        self.with_tables(&[U4Table::Shift], 0, move |tables, depth| {
+            u4_rrot(n, number_pos, tables.offset(U4Table::Shift, depth), is_shift)
+        })
+    }
+
+    /// Multiplication of the two u32's at the top of the stack, the low (or high) 32 bits being kept at the altstack
+    pub fn mul(self, high: bool) -> Self {
+        self.with_tables(&[U4Table::Mul], -16, move |tables, depth| {
+            u4_mul_u32(tables.offset(U4Table::Mul, depth), high)
+        })
+    }
+
+    pub fn fromaltstack(self, n: u32) -> Self { self.script(u4_fromaltstack(n), n as i32) }
+
+    pub fn toaltstack(self, n: u32) -> Self { self.script(u4_toaltstack(n), -(n as i32)) }
+
+    /// The layout of the tables needed by the parts
+    pub fn tables(&self) -> U4Tables {
+        U4Tables::new(&self.parts.iter().flat_map(|part| part.tables.clone()).collect::<Vec<_>>())
+    }
+
+    /// Change of the number of elements on the stack
+    pub fn delta(&self) -> i32 { self.parts.iter().map(|part| part.delta).sum() }
+
+    /// Generates the parts for tables already on the stack with depth elements on top of them
+    pub fn generate(&self, tables: &U4Tables, depth: u32) -> Script {
+        let mut depths = vec![];
+        let mut depth = depth as i32;
+        for part in self.parts.iter() {
+            depths.push(depth as u32);
+            depth += part.delta;
+            assert!(depth >= 0, "the part consumes elements below its inputs");
+        }
+        script! {
+            for (part, depth) in self.parts.iter().zip(depths) {
+                { (part.generate)(tables, depth) }
+            }
+        }
+    }
+
+    /// Pushes the tables below the top `inputs` elements, runs the parts and drops the tables below the outputs
+    pub fn compile(&self, inputs: u32) -> Script {
+        let tables = self.tables();
+        let outputs = inputs as i32 + self.delta();
+        assert!(outputs >= 0, "the composite consumes elements below its inputs");
+        script! {
+            { u4_toaltstack(inputs) }
+            { tables.push() }
+            { u4_fromaltstack(inputs) }
+            { self.generate(&tables, inputs) }
+            { u4_toaltstack(outputs as u32) }
+            { tables.drop() }
+            { u4_fromaltstack(outputs as u32) }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::u4::u4_add::u4_add;
+    use crate::u4::u4_std::{u4_drop, u4_number_to_nibble, verify_n};
+    use rand::Rng;
+
+    #[test]
+    fn test_layout() {
+        let tables = U4Tables::new(&[U4Table::Mul, U4Table::Add, U4Table::Mul]);
+        assert_eq!(tables.tables(), &[U4Table::Add, U4Table::Mul]);
+        assert_eq!(tables.size(), 130 + 256);
+        assert_eq!(tables.offset(U4Table::Mul, 5), 5);
+        assert_eq!(tables.offset(U4Table::Add, 5), 5 + 256);
+        assert!(!tables.contains(U4Table::Shift));
+
+        let union = tables.union(&U4Tables::new(&[U4Table::Shift]));
+        assert_eq!(union.offset(U4Table::Add, 0), 256 + 80);
+    }
+
+    #[test]
+    fn test_shared_half_lookup() {
+        let tables = U4Tables::new(&[U4Table::HalfAnd, U4Table::HalfXor]);
+        assert_eq!(tables.tables(), &[U4Table::HalfXor, U4Table::HalfAnd, U4Table::HalfLookup]);
+        assert_eq!(tables.size(), 136 * 2 + 16);
+        assert_eq!(tables.half_table_gap(U4Table::HalfAnd), 0);
+        assert_eq!(tables.half_table_gap(U4Table::HalfXor), 136);
+        assert_eq!(U4Tables::new(&[U4Table::HalfXor]).half_table_gap(U4Table::HalfXor), 0);
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let (a, b, c): (u32, u32, u32) = rng.gen();
+
+            // (a ^ b) & c with both half tables under a single lookup
+            let composer = U4Composer::new()
+                .logic(U4Table::HalfXor, 8, vec![8, 16], false)
+                .fromaltstack(8)
+                .logic(U4Table::HalfAnd, 8, vec![0, 8], false)
+                .fromaltstack(8);
+            assert_eq!(composer.tables(), tables);
+
+            let script = script! {
+                { u4_number_to_nibble(a) }
+                { u4_number_to_nibble(b) }
+                { u4_number_to_nibble(c) }
+                { composer.compile(24) }
+                { u4_number_to_nibble((a ^ b) & c) }
+                { verify_n(8) }
+                OP_TRUE
+            };
+            run(script);
+        }
+    }
+
+    #[test]
+    fn test_composer() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let (a, b, c, k): (u32, u32, u32, u32) = rng.gen();
+            let n = rng.gen_range(1..32);
+            let x = (a.wrapping_add(b) ^ c).rotate_right(n);
+            let expected = x.wrapping_mul(k);
+
+            // ((a + b) ^ c) >>> n * k with a, b and c as inputs, c being on the top
+            let composer = U4Composer::new()
+                .add(8, vec![8, 16])
+                .fromaltstack(8)
+                .logic(U4Table::HalfXor, 8, vec![0, 8], false)
+                .fromaltstack(8)
+                .rrot(n, 0, false)
+                .script(u4_drop(8), -8)
+                .fromaltstack(8)
+                .script(u4_number_to_nibble(k), 8)
+                .mul(false)
+                .fromaltstack(8);
+            assert_eq!(composer.delta(), -16);
+            assert_eq!(
+                composer.tables().tables(),
+                &[U4Table::Add, U4Table::Mul, U4Table::Shift, U4Table::HalfXor, U4Table::HalfLookup]
+            );
+
+            let script = script! {
+                { u4_number_to_nibble(a) }
+                { u4_number_to_nibble(b) }
+                { u4_number_to_nibble(c) }
+                { composer.compile(24) }
+                { u4_number_to_nibble(expected) }
+                { verify_n(8) }
+                OP_TRUE
+            };
+            run(script);
+        }
+    }
+
+    #[test]
+    fn test_shared_tables_size() {
+        // three chained additions pushing their own tables or sharing them
+        let separate = script! {
+            for _ in 0..3 {
+                { u4_toaltstack(16) }
+                { u4_push_add_tables() }
+                { u4_fromaltstack(16) }
+                { u4_add(8, vec![0, 8], 16, true) }
+                { u4_drop_add_tables() }
+                { u4_fromaltstack(8) }
+                { u4_number_to_nibble(1) }
+            }
+        };
+        let mut composer = U4Composer::new();
+        for _ in 0..3 {
+            composer = composer.add(8, vec![0, 8]).fromaltstack(8).script(u4_number_to_nibble(1), 8);
+        }
+        let shared = composer.compile(16);
+        println!("separate tables: {}, shared tables: {}", separate.len(), shared.len());
+        assert!(shared.len() < separate.len());
+
+        let script = script! {
+            { u4_number_to_nibble(5) }
+            { u4_number_to_nibble(7) }
+            { shared }
+            { u4_number_to_nibble(1) }
+            { verify_n(8) }
+            { u4_number_to_nibble(14) }
+            { verify_n(8) }
+            OP_TRUE
+        };
+        run(script);
+    }
+}
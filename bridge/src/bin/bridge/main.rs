@@ -66,9 +66,9 @@ async fn main() -> Result<(), Box<dyn Error>> {
     } else if let Some(sub_matches) = matches.subcommand_matches("broadcast") {
         let mut client_command = ClientCommand::new(global_args).await;
         let _ = client_command.handle_broadcast_command(sub_matches).await;
-    } else if matches.subcommand_matches("automatic").is_some() {
+    } else if let Some(sub_matches) = matches.subcommand_matches("automatic") {
         let mut client_command = ClientCommand::new(global_args).await;
-        let _ = client_command.handle_automatic_command().await;
+        let _ = client_command.handle_automatic_command(sub_matches).await;
     } else if matches.subcommand_matches("interactive").is_some() {
         let mut client_command = ClientCommand::new(global_args).await;
         let _ = client_command.handle_interactive_command(&command).await;
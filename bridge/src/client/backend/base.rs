@@ -0,0 +1,25 @@
+use async_trait::async_trait;
+use bitcoin::{block::Header, Address, BlockHash, OutPoint, Transaction, Txid};
+use esplora_client::{TxStatus, Utxo};
+use std::collections::HashMap;
+
+use crate::error::BackendError;
+
+/// Source chain access of the graphs and the client, so that they can run against esplora, a
+/// Bitcoin Core node or an in-memory chain
+#[async_trait]
+pub trait BitcoinBackend: Send + Sync {
+    /// The transaction if it is known to the backend, confirmed or in the mempool
+    async fn get_tx(&self, txid: &Txid) -> Result<Option<Transaction>, BackendError>;
+    /// Unconfirmed status for transactions unknown to the backend
+    async fn get_tx_status(&self, txid: &Txid) -> Result<TxStatus, BackendError>;
+    /// The transaction spending the output, confirmed or in the mempool
+    async fn get_output_spender(&self, outpoint: &OutPoint) -> Result<Option<Txid>, BackendError>;
+    async fn get_height(&self) -> Result<u32, BackendError>;
+    async fn get_block_hash(&self, block_height: u32) -> Result<BlockHash, BackendError>;
+    async fn get_block_header(&self, block_hash: &BlockHash) -> Result<Header, BackendError>;
+    async fn get_address_utxo(&self, address: Address) -> Result<Vec<Utxo>, BackendError>;
+    async fn broadcast(&self, transaction: &Transaction) -> Result<(), BackendError>;
+    /// Fee rates in sat/vB by confirmation target in blocks
+    async fn get_fee_estimates(&self) -> Result<HashMap<u16, f64>, BackendError>;
+}
@@ -0,0 +1,362 @@
+use async_trait::async_trait;
+use bitcoin::{
+    block::Header,
+    consensus::encode::{deserialize_hex, serialize_hex},
+    Address, Amount, BlockHash, OutPoint, Transaction, Txid,
+};
+use esplora_client::{TxStatus, Utxo, UtxoStatus};
+use reqwest::Client;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use super::base::BitcoinBackend;
+use crate::error::BackendError;
+
+// To use a Bitcoin Core node instead of esplora, add the following values to the .env file in the
+// base directory, with either the RPC user and password or the cookie file of the node:
+// export BRIDGE_BITCOIN_RPC_URL="..."
+// export BRIDGE_BITCOIN_RPC_USER="..."
+// export BRIDGE_BITCOIN_RPC_PASSWORD="..."
+// export BRIDGE_BITCOIN_RPC_COOKIE_FILE="..."
+//
+// The node must run with -txindex to find the confirmed transactions of the graphs. With the
+// regtest scripts, the node is reachable at http://127.0.0.1:18443 and its cookie file is
+// <data path>/esplora-bitcoin-regtest-data/bitcoin/regtest/.cookie
+
+// RPC error code of transactions and blocks not found
+const RPC_INVALID_ADDRESS_OR_KEY: i64 = -5;
+
+// Confirmation targets of the fee estimates, as returned by esplora
+const FEE_ESTIMATE_TARGETS: [u16; 8] = [1, 2, 3, 6, 12, 25, 144, 1008];
+
+pub enum BitcoinCoreAuth {
+    UserPassword(String, String),
+    // The cookie is read on every request, the node writing a new one when it restarts
+    CookieFile(PathBuf),
+}
+
+pub struct BitcoinCoreRpc {
+    client: Client,
+    url: String,
+    auth: BitcoinCoreAuth,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    result: Option<Value>,
+    error: Option<RpcErrorObject>,
+}
+
+#[derive(Deserialize)]
+struct RpcErrorObject {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct RawTransactionInfo {
+    blockhash: Option<BlockHash>,
+}
+
+#[derive(Deserialize)]
+struct SpendingPrevoutResult {
+    spendingtxid: Option<Txid>,
+}
+
+#[derive(Deserialize)]
+struct BlockInfo {
+    tx: Vec<BlockTransactionInfo>,
+}
+
+#[derive(Deserialize)]
+struct BlockTransactionInfo {
+    txid: Txid,
+    vin: Vec<BlockInputInfo>,
+}
+
+// coinbase inputs spend no output
+#[derive(Deserialize)]
+struct BlockInputInfo {
+    txid: Option<Txid>,
+    vout: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct BlockHeaderInfo {
+    height: u32,
+    time: u64,
+}
+
+#[derive(Deserialize)]
+struct ScanTxOutSetResult {
+    unspents: Vec<ScanTxOutSetUnspent>,
+}
+
+#[derive(Deserialize)]
+struct ScanTxOutSetUnspent {
+    txid: Txid,
+    vout: u32,
+    #[serde(with = "bitcoin::amount::serde::as_btc")]
+    amount: Amount,
+    height: u32,
+}
+
+#[derive(Deserialize)]
+struct EstimateSmartFeeResult {
+    // BTC/kvB
+    feerate: Option<f64>,
+}
+
+impl BitcoinCoreRpc {
+    pub fn new() -> Option<Self> {
+        dotenv::dotenv().ok();
+        let url = dotenv::var("BRIDGE_BITCOIN_RPC_URL").ok()?;
+        let user = dotenv::var("BRIDGE_BITCOIN_RPC_USER");
+        let password = dotenv::var("BRIDGE_BITCOIN_RPC_PASSWORD");
+        let cookie_file = dotenv::var("BRIDGE_BITCOIN_RPC_COOKIE_FILE");
+
+        let auth = match (user, password, cookie_file) {
+            (Ok(user), Ok(password), _) => BitcoinCoreAuth::UserPassword(user, password),
+            (_, _, Ok(cookie_file)) => BitcoinCoreAuth::CookieFile(PathBuf::from(cookie_file)),
+            _ => return None,
+        };
+
+        Some(Self::with_auth(&url, auth))
+    }
+
+    pub fn with_auth(url: &str, auth: BitcoinCoreAuth) -> Self {
+        Self {
+            client: Client::new(),
+            url: url.to_string(),
+            auth,
+        }
+    }
+
+    fn credentials(&self) -> Result<(String, String), BackendError> {
+        match &self.auth {
+            BitcoinCoreAuth::UserPassword(user, password) => Ok((user.clone(), password.clone())),
+            BitcoinCoreAuth::CookieFile(path) => {
+                let cookie = fs::read_to_string(path).map_err(|e| {
+                    BackendError::Transport(format!("Failed to read {}: {e}", path.display()))
+                })?;
+                match cookie.trim().split_once(':') {
+                    Some((user, password)) => Ok((user.to_string(), password.to_string())),
+                    None => Err(BackendError::Transport(format!(
+                        "Invalid cookie file {}",
+                        path.display()
+                    ))),
+                }
+            }
+        }
+    }
+
+    async fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<T, BackendError> {
+        let (user, password) = self.credentials()?;
+        let request = json!({
+            "jsonrpc": "1.0",
+            "id": "bridge",
+            "method": method,
+            "params": params,
+        });
+
+        let response = self
+            .client
+            .post(&self.url)
+            .basic_auth(user, Some(password))
+            .header("Content-Type", "application/json")
+            .body(request.to_string())
+            .send()
+            .await
+            .map_err(|e| BackendError::Transport(e.to_string()))?;
+        // RPC errors are returned with an error status and a JSON body, authentication errors
+        // without a body
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| BackendError::Transport(e.to_string()))?;
+        let response: RpcResponse = serde_json::from_str(&body)
+            .map_err(|_| BackendError::Transport(format!("HTTP {status}: {body}")))?;
+
+        if let Some(error) = response.error {
+            return Err(BackendError::Rpc {
+                code: error.code,
+                message: error.message,
+            });
+        }
+        serde_json::from_value(response.result.unwrap_or(Value::Null))
+            .map_err(|e| BackendError::Transport(format!("Invalid {method} result: {e}")))
+    }
+
+    async fn get_block_header_info(
+        &self,
+        block_hash: &BlockHash,
+    ) -> Result<BlockHeaderInfo, BackendError> {
+        self.call("getblockheader", json!([block_hash, true])).await
+    }
+
+    async fn confirmed_status(&self, block_hash: BlockHash) -> Result<TxStatus, BackendError> {
+        let header = self.get_block_header_info(&block_hash).await?;
+        Ok(TxStatus {
+            confirmed: true,
+            block_height: Some(header.height),
+            block_hash: Some(block_hash),
+            block_time: Some(header.time),
+        })
+    }
+}
+
+#[async_trait]
+impl BitcoinBackend for BitcoinCoreRpc {
+    async fn get_tx(&self, txid: &Txid) -> Result<Option<Transaction>, BackendError> {
+        match self
+            .call::<String>("getrawtransaction", json!([txid, false]))
+            .await
+        {
+            Ok(hex) => deserialize_hex(&hex)
+                .map(Some)
+                .map_err(|e| BackendError::Transport(format!("Invalid transaction {txid}: {e}"))),
+            Err(BackendError::Rpc {
+                code: RPC_INVALID_ADDRESS_OR_KEY,
+                ..
+            }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn get_tx_status(&self, txid: &Txid) -> Result<TxStatus, BackendError> {
+        let unconfirmed = TxStatus {
+            confirmed: false,
+            block_height: None,
+            block_hash: None,
+            block_time: None,
+        };
+
+        match self
+            .call::<RawTransactionInfo>("getrawtransaction", json!([txid, true]))
+            .await
+        {
+            Ok(RawTransactionInfo {
+                blockhash: Some(block_hash),
+            }) => self.confirmed_status(block_hash).await,
+            Ok(_) => Ok(unconfirmed),
+            Err(BackendError::Rpc {
+                code: RPC_INVALID_ADDRESS_OR_KEY,
+                ..
+            }) => Ok(unconfirmed),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Outputs of confirmed transactions only, the node having no index of the spent outputs: the
+    /// spender is looked up in the mempool, then in the blocks from the one confirming the output
+    async fn get_output_spender(&self, outpoint: &OutPoint) -> Result<Option<Txid>, BackendError> {
+        let Some(block_height) = self.get_tx_status(&outpoint.txid).await?.block_height else {
+            return Ok(None);
+        };
+        let unspent: Option<Value> = self
+            .call("gettxout", json!([outpoint.txid, outpoint.vout, true]))
+            .await?;
+        if unspent.is_some() {
+            return Ok(None);
+        }
+
+        let mempool_spenders: Vec<SpendingPrevoutResult> = self
+            .call(
+                "gettxspendingprevout",
+                json!([[{ "txid": outpoint.txid, "vout": outpoint.vout }]]),
+            )
+            .await?;
+        let mempool_spender = mempool_spenders
+            .first()
+            .and_then(|spender| spender.spendingtxid);
+        if mempool_spender.is_some() {
+            return Ok(mempool_spender);
+        }
+
+        for block_height in block_height..=self.get_height().await? {
+            let block_hash = self.get_block_hash(block_height).await?;
+            let block: BlockInfo = self.call("getblock", json!([block_hash, 2])).await?;
+            let spender = block.tx.into_iter().find(|tx| {
+                tx.vin.iter().any(|input| {
+                    input.txid == Some(outpoint.txid) && input.vout == Some(outpoint.vout)
+                })
+            });
+            if let Some(spender) = spender {
+                return Ok(Some(spender.txid));
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn get_height(&self) -> Result<u32, BackendError> {
+        self.call("getblockcount", json!([])).await
+    }
+
+    async fn get_block_hash(&self, block_height: u32) -> Result<BlockHash, BackendError> {
+        self.call("getblockhash", json!([block_height])).await
+    }
+
+    async fn get_block_header(&self, block_hash: &BlockHash) -> Result<Header, BackendError> {
+        let hex: String = self
+            .call("getblockheader", json!([block_hash, false]))
+            .await?;
+        deserialize_hex(&hex)
+            .map_err(|e| BackendError::Transport(format!("Invalid block header {block_hash}: {e}")))
+    }
+
+    /// Confirmed outputs only, the UTXO set of the node not including the mempool
+    async fn get_address_utxo(&self, address: Address) -> Result<Vec<Utxo>, BackendError> {
+        let result: ScanTxOutSetResult = self
+            .call(
+                "scantxoutset",
+                json!(["start", [format!("addr({address})")]]),
+            )
+            .await?;
+
+        let mut utxos = vec![];
+        for unspent in result.unspents {
+            let block_hash = self.get_block_hash(unspent.height).await?;
+            let header = self.get_block_header_info(&block_hash).await?;
+            utxos.push(Utxo {
+                txid: unspent.txid,
+                vout: unspent.vout,
+                status: UtxoStatus {
+                    confirmed: true,
+                    block_height: Some(header.height),
+                    block_hash: Some(block_hash),
+                    block_time: Some(header.time),
+                },
+                value: unspent.amount,
+            });
+        }
+
+        Ok(utxos)
+    }
+
+    async fn broadcast(&self, transaction: &Transaction) -> Result<(), BackendError> {
+        self.call::<Txid>("sendrawtransaction", json!([serialize_hex(transaction)]))
+            .await
+            .map(|_| ())
+    }
+
+    async fn get_fee_estimates(&self) -> Result<HashMap<u16, f64>, BackendError> {
+        let mut estimates = HashMap::new();
+        for target in FEE_ESTIMATE_TARGETS {
+            let result: EstimateSmartFeeResult =
+                self.call("estimatesmartfee", json!([target])).await?;
+            // nodes without enough data return no fee rate
+            if let Some(btc_per_kvb) = result.feerate {
+                estimates.insert(target, btc_per_kvb * 100_000.0);
+            }
+        }
+
+        Ok(estimates)
+    }
+}
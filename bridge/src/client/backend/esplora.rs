@@ -0,0 +1,65 @@
+use async_trait::async_trait;
+use bitcoin::{block::Header, Address, BlockHash, OutPoint, Transaction, Txid};
+use esplora_client::{AsyncClient, TxStatus, Utxo};
+use std::collections::HashMap;
+
+use super::base::BitcoinBackend;
+use crate::error::BackendError;
+
+#[async_trait]
+impl BitcoinBackend for AsyncClient {
+    async fn get_tx(&self, txid: &Txid) -> Result<Option<Transaction>, BackendError> {
+        AsyncClient::get_tx(self, txid)
+            .await
+            .map_err(BackendError::Esplora)
+    }
+
+    async fn get_tx_status(&self, txid: &Txid) -> Result<TxStatus, BackendError> {
+        AsyncClient::get_tx_status(self, txid)
+            .await
+            .map_err(BackendError::Esplora)
+    }
+
+    async fn get_output_spender(&self, outpoint: &OutPoint) -> Result<Option<Txid>, BackendError> {
+        AsyncClient::get_output_status(self, &outpoint.txid, outpoint.vout as u64)
+            .await
+            .map(|status| status.and_then(|status| status.txid))
+            .map_err(BackendError::Esplora)
+    }
+
+    async fn get_height(&self) -> Result<u32, BackendError> {
+        AsyncClient::get_height(self)
+            .await
+            .map_err(BackendError::Esplora)
+    }
+
+    async fn get_block_hash(&self, block_height: u32) -> Result<BlockHash, BackendError> {
+        AsyncClient::get_block_hash(self, block_height)
+            .await
+            .map_err(BackendError::Esplora)
+    }
+
+    async fn get_block_header(&self, block_hash: &BlockHash) -> Result<Header, BackendError> {
+        AsyncClient::get_header_by_hash(self, block_hash)
+            .await
+            .map_err(BackendError::Esplora)
+    }
+
+    async fn get_address_utxo(&self, address: Address) -> Result<Vec<Utxo>, BackendError> {
+        AsyncClient::get_address_utxo(self, address)
+            .await
+            .map_err(BackendError::Esplora)
+    }
+
+    async fn broadcast(&self, transaction: &Transaction) -> Result<(), BackendError> {
+        AsyncClient::broadcast(self, transaction)
+            .await
+            .map_err(BackendError::Esplora)
+    }
+
+    async fn get_fee_estimates(&self) -> Result<HashMap<u16, f64>, BackendError> {
+        AsyncClient::get_fee_estimates(self)
+            .await
+            .map_err(BackendError::Esplora)
+    }
+}
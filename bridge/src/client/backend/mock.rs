@@ -0,0 +1,402 @@
+use async_trait::async_trait;
+use bitcoin::{
+    absolute,
+    block::{self, Header},
+    hashes::{sha256d, Hash},
+    script::Builder,
+    transaction::Version,
+    Address, Amount, BlockHash, CompactTarget, OutPoint, ScriptBuf, Sequence, Transaction, TxIn,
+    TxMerkleNode, TxOut, Txid, Witness,
+};
+use esplora_client::{TxStatus, Utxo, UtxoStatus};
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard},
+};
+
+use super::base::BitcoinBackend;
+use crate::error::BackendError;
+
+const GENESIS_BLOCK_TIME: u64 = 1_700_000_000;
+const BLOCK_INTERVAL: u64 = 600;
+// Regtest difficulty, the headers not being mined
+const BLOCK_BITS: u32 = 0x207fffff;
+
+struct MockBlock {
+    header: Header,
+    txids: Vec<Txid>,
+}
+
+impl MockBlock {
+    fn hash(&self) -> BlockHash { self.header.block_hash() }
+
+    fn time(&self) -> u64 { self.header.time as u64 }
+}
+
+#[derive(Default)]
+struct MockChainState {
+    blocks: Vec<MockBlock>,
+    // Confirmed and mempool transactions
+    transactions: HashMap<Txid, Transaction>,
+    // Txid -> Height of the block confirming the transaction
+    confirmations: HashMap<Txid, u32>,
+    mempool: Vec<Txid>,
+    // Spent output -> Txid of the spending transaction
+    spent_outputs: HashMap<OutPoint, Txid>,
+    fee_estimates: HashMap<u16, f64>,
+    funding_count: u32,
+    // Timestamp of the next block
+    time: u64,
+    // Number of reorgs, so that the blocks mined again get other hashes
+    fork_count: u32,
+}
+
+/// In-memory chain for tests, accepting the transactions that spend existing outputs without
+/// validating their scripts, timelocks or fees
+///
+/// Broadcast transactions stay in the mempool until blocks are mined, ten minutes apart unless the
+/// time is advanced. Blocks can be disconnected to simulate reorgs, their transactions going back
+/// to the mempool.
+pub struct MockChain {
+    state: Mutex<MockChainState>,
+}
+
+impl Default for MockChain {
+    fn default() -> Self { Self::new() }
+}
+
+impl MockChain {
+    pub fn new() -> Self {
+        let chain = MockChain {
+            state: Mutex::new(MockChainState {
+                time: GENESIS_BLOCK_TIME,
+                ..Default::default()
+            }),
+        };
+        chain.mine_blocks(1);
+        chain
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockChainState> {
+        self.state.lock().expect("Mock chain state poisoned")
+    }
+
+    /// Adds to the mempool a transaction paying the amount to the script, returns its output
+    pub fn fund(&self, script_pubkey: ScriptBuf, amount: Amount) -> OutPoint {
+        let mut state = self.state();
+        state.funding_count += 1;
+        let funding_tx = Transaction {
+            version: Version(2),
+            lock_time: absolute::LockTime::ZERO,
+            // unique coinbase-like input, so that fundings of the same amount get other txids
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: Builder::new()
+                    .push_int(state.funding_count as i64)
+                    .into_script(),
+                sequence: Sequence::MAX,
+                witness: Witness::default(),
+            }],
+            output: vec![TxOut {
+                value: amount,
+                script_pubkey,
+            }],
+        };
+        let txid = funding_tx.compute_txid();
+        state.transactions.insert(txid, funding_tx);
+        state.mempool.push(txid);
+
+        OutPoint { txid, vout: 0 }
+    }
+
+    /// Mines blocks, the first one confirming all the transactions of the mempool
+    pub fn mine_blocks(&self, count: u32) {
+        let mut state = self.state();
+        for _ in 0..count {
+            let height = state.blocks.len() as u32;
+            let txids = std::mem::take(&mut state.mempool);
+            let mut block_data = height.to_le_bytes().to_vec();
+            block_data.extend(state.fork_count.to_le_bytes());
+            for txid in &txids {
+                block_data.extend(txid.to_byte_array());
+                state.confirmations.insert(*txid, height);
+            }
+            let header = Header {
+                version: block::Version::ONE,
+                prev_blockhash: state
+                    .blocks
+                    .last()
+                    .map_or(BlockHash::all_zeros(), MockBlock::hash),
+                merkle_root: TxMerkleNode::from_raw_hash(sha256d::Hash::hash(&block_data)),
+                time: state.time as u32,
+                bits: CompactTarget::from_consensus(BLOCK_BITS),
+                nonce: 0,
+            };
+            state.blocks.push(MockBlock { header, txids });
+            state.time += BLOCK_INTERVAL;
+        }
+    }
+
+    /// Delays the next block
+    pub fn advance_time(&self, seconds: u64) { self.state().time += seconds; }
+
+    /// Median time of the block and the 10 blocks before it, which time-based timelocks are
+    /// checked against
+    pub fn median_time_past(&self, block_height: u32) -> u64 {
+        let state = self.state();
+        let last_block = (block_height as usize).min(state.blocks.len() - 1);
+        let mut times = state.blocks[last_block.saturating_sub(10)..=last_block]
+            .iter()
+            .map(MockBlock::time)
+            .collect::<Vec<_>>();
+        times.sort();
+        times[times.len() / 2]
+    }
+
+    /// Removes the blocks above the height, their transactions going back to the mempool
+    pub fn disconnect_blocks(&self, height: u32) {
+        let mut state = self.state();
+        assert!(height > 0, "The genesis block cannot be disconnected");
+        state.fork_count += 1;
+        let mut txids = vec![];
+        while state.blocks.len() as u32 > height + 1 {
+            let block = state.blocks.pop().unwrap();
+            txids.splice(0..0, block.txids);
+        }
+        for txid in &txids {
+            state.confirmations.remove(txid);
+        }
+        txids.append(&mut state.mempool);
+        state.mempool = txids;
+    }
+
+    pub fn set_fee_estimates(&self, fee_estimates: HashMap<u16, f64>) {
+        self.state().fee_estimates = fee_estimates;
+    }
+}
+
+impl MockChainState {
+    fn tx_status(&self, txid: &Txid) -> TxStatus {
+        match self.confirmations.get(txid) {
+            Some(height) => {
+                let block = &self.blocks[*height as usize];
+                TxStatus {
+                    confirmed: true,
+                    block_height: Some(*height),
+                    block_hash: Some(block.hash()),
+                    block_time: Some(block.time()),
+                }
+            }
+            None => TxStatus {
+                confirmed: false,
+                block_height: None,
+                block_hash: None,
+                block_time: None,
+            },
+        }
+    }
+
+    fn output(&self, outpoint: &OutPoint) -> Option<&TxOut> {
+        self.transactions
+            .get(&outpoint.txid)
+            .and_then(|tx| tx.output.get(outpoint.vout as usize))
+    }
+}
+
+#[async_trait]
+impl BitcoinBackend for MockChain {
+    async fn get_tx(&self, txid: &Txid) -> Result<Option<Transaction>, BackendError> {
+        Ok(self.state().transactions.get(txid).cloned())
+    }
+
+    async fn get_tx_status(&self, txid: &Txid) -> Result<TxStatus, BackendError> {
+        Ok(self.state().tx_status(txid))
+    }
+
+    async fn get_output_spender(&self, outpoint: &OutPoint) -> Result<Option<Txid>, BackendError> {
+        Ok(self.state().spent_outputs.get(outpoint).copied())
+    }
+
+    async fn get_height(&self) -> Result<u32, BackendError> {
+        Ok(self.state().blocks.len() as u32 - 1)
+    }
+
+    async fn get_block_hash(&self, block_height: u32) -> Result<BlockHash, BackendError> {
+        match self.state().blocks.get(block_height as usize) {
+            Some(block) => Ok(block.hash()),
+            None => Err(BackendError::Transport(format!(
+                "Block {block_height} not found"
+            ))),
+        }
+    }
+
+    async fn get_block_header(&self, block_hash: &BlockHash) -> Result<Header, BackendError> {
+        match self
+            .state()
+            .blocks
+            .iter()
+            .find(|block| block.hash() == *block_hash)
+        {
+            Some(block) => Ok(block.header),
+            None => Err(BackendError::Transport(format!(
+                "Block {block_hash} not found"
+            ))),
+        }
+    }
+
+    async fn get_address_utxo(&self, address: Address) -> Result<Vec<Utxo>, BackendError> {
+        let state = self.state();
+        let script_pubkey = address.script_pubkey();
+        let mut utxos = vec![];
+        for (txid, tx) in &state.transactions {
+            for (vout, output) in tx.output.iter().enumerate() {
+                let outpoint = OutPoint {
+                    txid: *txid,
+                    vout: vout as u32,
+                };
+                if output.script_pubkey != script_pubkey
+                    || state.spent_outputs.contains_key(&outpoint)
+                {
+                    continue;
+                }
+                let status = state.tx_status(txid);
+                utxos.push(Utxo {
+                    txid: *txid,
+                    vout: vout as u32,
+                    status: UtxoStatus {
+                        confirmed: status.confirmed,
+                        block_height: status.block_height,
+                        block_hash: status.block_hash,
+                        block_time: status.block_time,
+                    },
+                    value: output.value,
+                });
+            }
+        }
+
+        Ok(utxos)
+    }
+
+    async fn broadcast(&self, transaction: &Transaction) -> Result<(), BackendError> {
+        let mut state = self.state();
+        let txid = transaction.compute_txid();
+        if state.confirmations.contains_key(&txid) {
+            return Err(BackendError::Rejected(String::from("txn-already-known")));
+        }
+        if state.transactions.contains_key(&txid) {
+            return Ok(());
+        }
+
+        let mut input_value = Amount::ZERO;
+        for input in &transaction.input {
+            let Some(output) = state.output(&input.previous_output) else {
+                return Err(BackendError::Rejected(format!(
+                    "missing-inputs {}",
+                    input.previous_output
+                )));
+            };
+            if state.spent_outputs.contains_key(&input.previous_output) {
+                return Err(BackendError::Rejected(format!(
+                    "txn-mempool-conflict {}",
+                    input.previous_output
+                )));
+            }
+            input_value += output.value;
+        }
+        let output_value: Amount = transaction.output.iter().map(|output| output.value).sum();
+        if output_value > input_value {
+            return Err(BackendError::Rejected(String::from("bad-txns-in-belowout")));
+        }
+
+        for input in &transaction.input {
+            state.spent_outputs.insert(input.previous_output, txid);
+        }
+        state.transactions.insert(txid, transaction.clone());
+        state.mempool.push(txid);
+
+        Ok(())
+    }
+
+    async fn get_fee_estimates(&self) -> Result<HashMap<u16, f64>, BackendError> {
+        Ok(self.state().fee_estimates.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{key::Secp256k1, CompressedPublicKey, Network};
+
+    use super::*;
+    use crate::graphs::monitor::ChainMonitor;
+
+    fn spend(outpoint: OutPoint, value: Amount, script_pubkey: ScriptBuf) -> Transaction {
+        Transaction {
+            version: Version(2),
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: outpoint,
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::MAX,
+                witness: Witness::default(),
+            }],
+            output: vec![TxOut {
+                value,
+                script_pubkey,
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_chain_confirms_and_reorgs_transactions() {
+        let secp = Secp256k1::new();
+        let (_, public_key) = secp.generate_keypair(&mut rand::thread_rng());
+        let address = Address::p2wpkh(&CompressedPublicKey(public_key), Network::Regtest);
+
+        let chain = MockChain::new();
+        let mut monitor = ChainMonitor::with_finality_depth(3);
+        let funding = chain.fund(address.script_pubkey(), Amount::from_sat(10_000));
+        chain.mine_blocks(1);
+        assert_eq!(chain.get_height().await.unwrap(), 1);
+        assert_eq!(
+            chain.get_address_utxo(address.clone()).await.unwrap().len(),
+            1
+        );
+
+        let tx = spend(funding, Amount::from_sat(9_000), address.script_pubkey());
+        let txid = tx.compute_txid();
+        // outputs cannot exceed inputs
+        let invalid_tx = spend(funding, Amount::from_sat(11_000), address.script_pubkey());
+        assert!(chain.broadcast(&invalid_tx).await.is_err());
+        assert!(chain.broadcast(&tx).await.is_ok());
+        // double spend
+        let conflicting_tx = spend(funding, Amount::from_sat(8_000), address.script_pubkey());
+        assert!(chain.broadcast(&conflicting_tx).await.is_err());
+        assert!(!chain.get_tx_status(&txid).await.unwrap().confirmed);
+        assert_eq!(
+            chain.get_output_spender(&funding).await.unwrap(),
+            Some(txid)
+        );
+        let change = OutPoint { txid, vout: 0 };
+        assert_eq!(chain.get_output_spender(&change).await.unwrap(), None);
+
+        chain.mine_blocks(3);
+        assert_eq!(monitor.update(&chain).await.unwrap(), None);
+        assert_eq!(
+            chain.get_tx_status(&txid).await.unwrap().block_height,
+            Some(2)
+        );
+        let utxos = chain.get_address_utxo(address).await.unwrap();
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(utxos[0].txid, txid);
+
+        // the transaction is confirmed again in another block after a reorg
+        chain.disconnect_blocks(1);
+        assert!(!chain.get_tx_status(&txid).await.unwrap().confirmed);
+        chain.mine_blocks(2);
+        assert_eq!(monitor.update(&chain).await.unwrap(), Some(2));
+        assert_eq!(
+            chain.get_tx_status(&txid).await.unwrap().block_height,
+            Some(2)
+        );
+    }
+}
@@ -0,0 +1,5 @@
+pub mod base;
+pub mod bitcoin_core;
+pub mod esplora;
+pub mod mock;
+pub mod simulated;
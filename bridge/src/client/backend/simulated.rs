@@ -0,0 +1,610 @@
+use async_trait::async_trait;
+use bitcoin::{
+    absolute::LockTime,
+    block::Header,
+    ecdsa,
+    opcodes::all::OP_CSV,
+    relative,
+    script::Instruction,
+    secp256k1::Message,
+    sighash::{Prevouts, SighashCache},
+    taproot::{self, ControlBlock, TAPROOT_ANNEX_PREFIX},
+    Address, Amount, BlockHash, OutPoint, PublicKey, Script, ScriptBuf, Transaction, TxOut, Txid,
+    XOnlyPublicKey,
+};
+use bitvm::{dry_run_p2wsh_input, dry_run_taproot_input};
+use esplora_client::{TxStatus, Utxo};
+use secp256k1::SECP256K1;
+use std::collections::HashMap;
+
+use super::{base::BitcoinBackend, mock::MockChain};
+use crate::{
+    error::BackendError,
+    transactions::fee::{generate_anchor_script, min_relay_fee_rate, tx_fee},
+};
+
+// Unit of the time-based relative timelocks, in seconds
+const SEQUENCE_LOCK_TIME_GRANULARITY: u64 = 512;
+
+/// In-memory chain validating the broadcast transactions like a node would, so that the graphs
+/// can be run end to end without network
+///
+/// The inputs must be unspent outputs of the chain or of the mempool, the transactions must pay
+/// the minimum relay fee and their absolute and relative timelocks must be met by the next block.
+/// Taproot script path and P2WSH spends are executed with the script interpreter, taproot key
+/// path and P2WPKH signatures are verified, and the relative timelock of a P2WSH witness script,
+/// if any, must be committed by the sequence of the input.
+pub struct SimulatedChain {
+    chain: MockChain,
+}
+
+impl Default for SimulatedChain {
+    fn default() -> Self { Self::new() }
+}
+
+impl SimulatedChain {
+    pub fn new() -> Self {
+        SimulatedChain {
+            chain: MockChain::new(),
+        }
+    }
+
+    /// Adds to the mempool a transaction paying the amount to the script, returns its output
+    pub fn fund(&self, script_pubkey: ScriptBuf, amount: Amount) -> OutPoint {
+        self.chain.fund(script_pubkey, amount)
+    }
+
+    pub fn mine_blocks(&self, count: u32) { self.chain.mine_blocks(count); }
+
+    pub fn advance_time(&self, seconds: u64) { self.chain.advance_time(seconds); }
+
+    pub fn disconnect_blocks(&self, height: u32) { self.chain.disconnect_blocks(height); }
+
+    pub fn set_fee_estimates(&self, fee_estimates: HashMap<u16, f64>) {
+        self.chain.set_fee_estimates(fee_estimates);
+    }
+
+    async fn validate(&self, transaction: &Transaction) -> Result<(), BackendError> {
+        let mut prevouts = vec![];
+        let mut prevout_heights = vec![];
+        for input in &transaction.input {
+            let outpoint = input.previous_output;
+            let prevout = self
+                .chain
+                .get_tx(&outpoint.txid)
+                .await?
+                .and_then(|tx| tx.output.get(outpoint.vout as usize).cloned())
+                .ok_or_else(|| BackendError::Rejected(format!("missing-inputs {outpoint}")))?;
+            prevouts.push(prevout);
+            prevout_heights.push(self.chain.get_tx_status(&outpoint.txid).await?.block_height);
+        }
+
+        let input_value: Amount = prevouts.iter().map(|prevout| prevout.value).sum();
+        let output_value: Amount = transaction.output.iter().map(|output| output.value).sum();
+        if output_value > input_value {
+            return Err(BackendError::Rejected(String::from("bad-txns-in-belowout")));
+        }
+        let fee = tx_fee(transaction, &prevouts);
+        let min_fee = min_relay_fee_rate()
+            .fee_vb(transaction.vsize() as u64)
+            .expect("Fee overflow");
+        if fee < min_fee {
+            return Err(BackendError::Rejected(format!(
+                "min relay fee not met, {} < {}",
+                fee.to_sat(),
+                min_fee.to_sat()
+            )));
+        }
+
+        let block_height = self.chain.get_height().await? + 1;
+        if !self.is_final(transaction, block_height) {
+            return Err(BackendError::Rejected(String::from("non-final")));
+        }
+        if !self.sequence_locks_met(transaction, &prevout_heights, block_height) {
+            return Err(BackendError::Rejected(String::from("non-BIP68-final")));
+        }
+
+        for input_index in 0..transaction.input.len() {
+            verify_input(transaction, input_index, &prevouts).map_err(|e| {
+                BackendError::Rejected(format!(
+                    "mandatory-script-verify-flag-failed (input {input_index}: {e})"
+                ))
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether the absolute timelock of the transaction is met in the block at the height
+    fn is_final(&self, transaction: &Transaction, block_height: u32) -> bool {
+        if !transaction.is_lock_time_enabled() {
+            return true;
+        }
+        match transaction.lock_time {
+            LockTime::Blocks(height) => height.to_consensus_u32() < block_height,
+            LockTime::Seconds(time) => {
+                (time.to_consensus_u32() as u64) < self.chain.median_time_past(block_height - 1)
+            }
+        }
+    }
+
+    /// Whether the relative timelocks of the inputs are met in the block at the height, unconfirmed
+    /// outputs being considered confirmed in that block
+    fn sequence_locks_met(
+        &self,
+        transaction: &Transaction,
+        prevout_heights: &[Option<u32>],
+        block_height: u32,
+    ) -> bool {
+        if transaction.version.0 < 2 {
+            return true;
+        }
+        transaction
+            .input
+            .iter()
+            .zip(prevout_heights)
+            .all(|(input, prevout_height)| {
+                let prevout_height = prevout_height.unwrap_or(block_height);
+                match input.sequence.to_relative_lock_time() {
+                    None => true,
+                    Some(relative::LockTime::Blocks(blocks)) => {
+                        prevout_height + blocks.value() as u32 <= block_height
+                    }
+                    Some(relative::LockTime::Time(time)) => {
+                        self.chain
+                            .median_time_past(prevout_height.saturating_sub(1))
+                            + time.value() as u64 * SEQUENCE_LOCK_TIME_GRANULARITY
+                            <= self.chain.median_time_past(block_height - 1)
+                    }
+                }
+            })
+    }
+}
+
+fn verify_input(
+    transaction: &Transaction,
+    input_index: usize,
+    prevouts: &[TxOut],
+) -> Result<(), String> {
+    let script_pubkey = &prevouts[input_index].script_pubkey;
+    if *script_pubkey == generate_anchor_script() {
+        if !transaction.input[input_index].witness.is_empty() {
+            return Err(String::from("anchor spent with a witness"));
+        }
+        return Ok(());
+    }
+
+    if script_pubkey.is_p2tr() {
+        verify_taproot_input(transaction, input_index, prevouts)
+    } else if script_pubkey.is_p2wpkh() {
+        verify_p2wpkh_input(transaction, input_index, prevouts)
+    } else if script_pubkey.is_p2wsh() {
+        verify_p2wsh_input(transaction, input_index, prevouts)
+    } else {
+        Err(format!("unsupported output script {script_pubkey}"))
+    }
+}
+
+fn verify_taproot_input(
+    transaction: &Transaction,
+    input_index: usize,
+    prevouts: &[TxOut],
+) -> Result<(), String> {
+    let output_key =
+        XOnlyPublicKey::from_slice(&prevouts[input_index].script_pubkey.as_bytes()[2..])
+            .map_err(|e| e.to_string())?;
+    let mut witness = transaction.input[input_index].witness.to_vec();
+    if witness.len() >= 2 && witness.last().unwrap().first() == Some(&TAPROOT_ANNEX_PREFIX) {
+        witness.pop();
+    }
+
+    match witness.len() {
+        0 => Err(String::from("empty witness")),
+        // key path spend
+        1 => {
+            let signature =
+                taproot::Signature::from_slice(&witness[0]).map_err(|e| e.to_string())?;
+            let sighash = SighashCache::new(transaction)
+                .taproot_key_spend_signature_hash(
+                    input_index,
+                    &Prevouts::All(prevouts),
+                    signature.sighash_type,
+                )
+                .map_err(|e| e.to_string())?;
+            SECP256K1
+                .verify_schnorr(&signature.signature, &Message::from(sighash), &output_key)
+                .map_err(|e| e.to_string())
+        }
+        // script path spend
+        length => {
+            let control_block =
+                ControlBlock::decode(&witness[length - 1]).map_err(|e| e.to_string())?;
+            let script = Script::from_bytes(&witness[length - 2]);
+            if !control_block.verify_taproot_commitment(SECP256K1, output_key, script) {
+                return Err(String::from("script not committed in the output key"));
+            }
+            let result = dry_run_taproot_input(transaction, input_index, prevouts);
+            if !result.success {
+                return Err(format!("{:?}", result.error));
+            }
+            Ok(())
+        }
+    }
+}
+
+fn verify_p2wpkh_input(
+    transaction: &Transaction,
+    input_index: usize,
+    prevouts: &[TxOut],
+) -> Result<(), String> {
+    let witness = transaction.input[input_index].witness.to_vec();
+    let [signature, public_key] = witness.as_slice() else {
+        return Err(String::from("invalid witness"));
+    };
+    let public_key = PublicKey::from_slice(public_key).map_err(|e| e.to_string())?;
+    let wpubkey_hash = public_key.wpubkey_hash().map_err(|e| e.to_string())?;
+    let script_pubkey = &prevouts[input_index].script_pubkey;
+    if ScriptBuf::new_p2wpkh(&wpubkey_hash) != *script_pubkey {
+        return Err(String::from("public key not committed in the output"));
+    }
+
+    let signature = ecdsa::Signature::from_slice(signature).map_err(|e| e.to_string())?;
+    let sighash = SighashCache::new(transaction)
+        .p2wpkh_signature_hash(
+            input_index,
+            script_pubkey,
+            prevouts[input_index].value,
+            signature.sighash_type,
+        )
+        .map_err(|e| e.to_string())?;
+    SECP256K1
+        .verify_ecdsa(
+            &Message::from(sighash),
+            &signature.signature,
+            &public_key.inner,
+        )
+        .map_err(|e| e.to_string())
+}
+
+/// Verifies the spends of witness scripts by running them with the script interpreter
+fn verify_p2wsh_input(
+    transaction: &Transaction,
+    input_index: usize,
+    prevouts: &[TxOut],
+) -> Result<(), String> {
+    let witness = &transaction.input[input_index].witness;
+    let Some(witness_script) = witness.last() else {
+        return Err(String::from("empty witness"));
+    };
+    let witness_script = ScriptBuf::from_bytes(witness_script.to_vec());
+    if ScriptBuf::new_p2wsh(&witness_script.wscript_hash()) != prevouts[input_index].script_pubkey {
+        return Err(String::from("witness script not committed in the output"));
+    }
+    verify_p2wsh_sequence(transaction, input_index, &witness_script)?;
+
+    let result = dry_run_p2wsh_input(transaction, input_index, prevouts);
+    if !result.success {
+        return Err(format!("{:?}", result.error));
+    }
+    Ok(())
+}
+
+/// Checks the sequence of the input against the OP_CHECKSEQUENCEVERIFY of the witness script
+fn verify_p2wsh_sequence(
+    transaction: &Transaction,
+    input_index: usize,
+    witness_script: &Script,
+) -> Result<(), String> {
+    let instructions = witness_script
+        .instructions()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    let Some(csv_index) = instructions
+        .iter()
+        .position(|instruction| *instruction == Instruction::Op(OP_CSV))
+    else {
+        return Ok(());
+    };
+    let required = csv_index
+        .checked_sub(1)
+        .and_then(|index| instructions[index].script_num())
+        .and_then(|value| u32::try_from(value).ok())
+        .ok_or_else(|| String::from("invalid OP_CHECKSEQUENCEVERIFY argument"))?;
+    let Ok(required) = relative::LockTime::from_consensus(required) else {
+        // the relative timelock is disabled, the opcode behaves as a no-op
+        return Ok(());
+    };
+
+    let sequence = transaction.input[input_index].sequence;
+    match sequence.to_relative_lock_time() {
+        Some(lock_time) if transaction.version.0 >= 2 && required.is_implied_by(lock_time) => {
+            Ok(())
+        }
+        _ => Err(format!(
+            "sequence {sequence} does not meet the relative timelock {required}"
+        )),
+    }
+}
+
+#[async_trait]
+impl BitcoinBackend for SimulatedChain {
+    async fn get_tx(&self, txid: &Txid) -> Result<Option<Transaction>, BackendError> {
+        self.chain.get_tx(txid).await
+    }
+
+    async fn get_tx_status(&self, txid: &Txid) -> Result<TxStatus, BackendError> {
+        self.chain.get_tx_status(txid).await
+    }
+
+    async fn get_output_spender(&self, outpoint: &OutPoint) -> Result<Option<Txid>, BackendError> {
+        self.chain.get_output_spender(outpoint).await
+    }
+
+    async fn get_height(&self) -> Result<u32, BackendError> { self.chain.get_height().await }
+
+    async fn get_block_hash(&self, block_height: u32) -> Result<BlockHash, BackendError> {
+        self.chain.get_block_hash(block_height).await
+    }
+
+    async fn get_block_header(&self, block_hash: &BlockHash) -> Result<Header, BackendError> {
+        self.chain.get_block_header(block_hash).await
+    }
+
+    async fn get_address_utxo(&self, address: Address) -> Result<Vec<Utxo>, BackendError> {
+        self.chain.get_address_utxo(address).await
+    }
+
+    async fn broadcast(&self, transaction: &Transaction) -> Result<(), BackendError> {
+        // known transactions were validated when first broadcast
+        if self
+            .chain
+            .get_tx(&transaction.compute_txid())
+            .await?
+            .is_none()
+        {
+            self.validate(transaction).await?;
+        }
+        self.chain.broadcast(transaction).await
+    }
+
+    async fn get_fee_estimates(&self) -> Result<HashMap<u16, f64>, BackendError> {
+        self.chain.get_fee_estimates().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{
+        hashes::{sha256, Hash},
+        key::Secp256k1,
+        opcodes::all::{OP_EQUAL, OP_SHA256},
+        script::Builder,
+        transaction::Version,
+        CompressedPublicKey, EcdsaSighashType, Network, PrivateKey, Sequence, TxIn, Witness,
+    };
+
+    use super::*;
+    use crate::scripts::generate_timelock_script;
+
+    fn sign_p2wpkh_spend(
+        private_key: &PrivateKey,
+        prevout: &TxOut,
+        outpoint: OutPoint,
+        sequence: Sequence,
+        value: Amount,
+    ) -> Transaction {
+        let mut tx = Transaction {
+            version: Version(2),
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: outpoint,
+                script_sig: ScriptBuf::new(),
+                sequence,
+                witness: Witness::default(),
+            }],
+            output: vec![TxOut {
+                value,
+                script_pubkey: prevout.script_pubkey.clone(),
+            }],
+        };
+        let sighash = SighashCache::new(&tx)
+            .p2wpkh_signature_hash(
+                0,
+                &prevout.script_pubkey,
+                prevout.value,
+                EcdsaSighashType::All,
+            )
+            .unwrap();
+        let signature = ecdsa::Signature {
+            signature: SECP256K1.sign_ecdsa(&Message::from(sighash), &private_key.inner),
+            sighash_type: EcdsaSighashType::All,
+        };
+        tx.input[0].witness.push_ecdsa_signature(&signature);
+        tx.input[0]
+            .witness
+            .push(private_key.public_key(SECP256K1).to_bytes());
+        tx
+    }
+
+    fn sign_p2wsh_spend(
+        private_key: &PrivateKey,
+        witness_script: &ScriptBuf,
+        prevout: &TxOut,
+        outpoint: OutPoint,
+        sequence: Sequence,
+        value: Amount,
+    ) -> Transaction {
+        let mut tx = Transaction {
+            version: Version(2),
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: outpoint,
+                script_sig: ScriptBuf::new(),
+                sequence,
+                witness: Witness::default(),
+            }],
+            output: vec![TxOut {
+                value,
+                script_pubkey: prevout.script_pubkey.clone(),
+            }],
+        };
+        let sighash = SighashCache::new(&tx)
+            .p2wsh_signature_hash(0, witness_script, prevout.value, EcdsaSighashType::All)
+            .unwrap();
+        let signature = ecdsa::Signature {
+            signature: SECP256K1.sign_ecdsa(&Message::from(sighash), &private_key.inner),
+            sighash_type: EcdsaSighashType::All,
+        };
+        tx.input[0].witness.push_ecdsa_signature(&signature);
+        tx.input[0].witness.push(witness_script.as_bytes());
+        tx
+    }
+
+    #[tokio::test]
+    async fn test_simulated_chain_validates_transactions() {
+        let secp = Secp256k1::new();
+        let (secret_key, _) = secp.generate_keypair(&mut rand::thread_rng());
+        let private_key = PrivateKey::new(secret_key, Network::Regtest);
+        let public_key = CompressedPublicKey::from_private_key(SECP256K1, &private_key).unwrap();
+        let prevout = TxOut {
+            value: Amount::from_sat(100_000),
+            script_pubkey: Address::p2wpkh(&public_key, Network::Regtest).script_pubkey(),
+        };
+
+        let chain = SimulatedChain::new();
+        let outpoint = chain.fund(prevout.script_pubkey.clone(), prevout.value);
+        chain.mine_blocks(1);
+
+        // relative timelock of 3 blocks
+        let sequence = Sequence::from_height(3);
+        let value = Amount::from_sat(99_000);
+        let tx = sign_p2wpkh_spend(&private_key, &prevout, outpoint, sequence, value);
+
+        // the fee is below the minimum relay fee
+        let low_fee_tx = sign_p2wpkh_spend(
+            &private_key,
+            &prevout,
+            outpoint,
+            sequence,
+            Amount::from_sat(99_990),
+        );
+        chain.mine_blocks(1);
+        assert!(chain.broadcast(&low_fee_tx).await.is_err());
+
+        let result = chain.broadcast(&tx).await;
+        assert!(
+            matches!(result, Err(BackendError::Rejected(reason)) if reason == "non-BIP68-final")
+        );
+        chain.mine_blocks(1);
+
+        // the signature does not commit to the modified output
+        let mut invalid_signature_tx = tx.clone();
+        invalid_signature_tx.output[0].value = Amount::from_sat(98_000);
+        let result = chain.broadcast(&invalid_signature_tx).await;
+        assert!(
+            matches!(result, Err(BackendError::Rejected(reason)) if reason.starts_with("mandatory-script-verify-flag-failed"))
+        );
+
+        assert!(chain.broadcast(&tx).await.is_ok());
+        chain.mine_blocks(1);
+        assert!(
+            chain
+                .get_tx_status(&tx.compute_txid())
+                .await
+                .unwrap()
+                .confirmed
+        );
+    }
+
+    #[tokio::test]
+    async fn test_simulated_chain_enforces_witness_script_timelock() {
+        let secp = Secp256k1::new();
+        let (secret_key, _) = secp.generate_keypair(&mut rand::thread_rng());
+        let private_key = PrivateKey::new(secret_key, Network::Regtest);
+        let num_blocks_timelock = 2;
+        let witness_script =
+            generate_timelock_script(&private_key.public_key(SECP256K1), num_blocks_timelock);
+        let prevout = TxOut {
+            value: Amount::from_sat(100_000),
+            script_pubkey: ScriptBuf::new_p2wsh(&witness_script.wscript_hash()),
+        };
+
+        let chain = SimulatedChain::new();
+        let outpoint = chain.fund(prevout.script_pubkey.clone(), prevout.value);
+        chain.mine_blocks(1);
+        chain.mine_blocks(num_blocks_timelock);
+
+        // the relative timelock of the input is met but is below the one of the witness script
+        let value = Amount::from_sat(99_000);
+        let short_sequence_tx = sign_p2wsh_spend(
+            &private_key,
+            &witness_script,
+            &prevout,
+            outpoint,
+            Sequence::from_height(num_blocks_timelock as u16 - 1),
+            value,
+        );
+        let result = chain.broadcast(&short_sequence_tx).await;
+        assert!(
+            matches!(result, Err(BackendError::Rejected(reason)) if reason.starts_with("mandatory-script-verify-flag-failed"))
+        );
+
+        let tx = sign_p2wsh_spend(
+            &private_key,
+            &witness_script,
+            &prevout,
+            outpoint,
+            Sequence::from_height(num_blocks_timelock as u16),
+            value,
+        );
+        assert!(chain.broadcast(&tx).await.is_ok());
+        chain.mine_blocks(1);
+        assert!(
+            chain
+                .get_tx_status(&tx.compute_txid())
+                .await
+                .unwrap()
+                .confirmed
+        );
+    }
+
+    #[tokio::test]
+    async fn test_simulated_chain_runs_witness_script() {
+        let preimage = [0x42; 32];
+        let witness_script = Builder::new()
+            .push_opcode(OP_SHA256)
+            .push_slice(sha256::Hash::hash(&preimage).to_byte_array())
+            .push_opcode(OP_EQUAL)
+            .into_script();
+        let prevout = TxOut {
+            value: Amount::from_sat(100_000),
+            script_pubkey: ScriptBuf::new_p2wsh(&witness_script.wscript_hash()),
+        };
+
+        let chain = SimulatedChain::new();
+        let outpoint = chain.fund(prevout.script_pubkey.clone(), prevout.value);
+        chain.mine_blocks(1);
+
+        let spend = |preimage: &[u8]| Transaction {
+            version: Version(2),
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: outpoint,
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::from_slice(&[preimage, witness_script.as_bytes()]),
+            }],
+            output: vec![TxOut {
+                value: Amount::from_sat(99_000),
+                script_pubkey: prevout.script_pubkey.clone(),
+            }],
+        };
+
+        // neither a signature nor a public key, the script has to be run
+        let result = chain.broadcast(&spend(&[0x43; 32])).await;
+        assert!(
+            matches!(result, Err(BackendError::Rejected(reason)) if reason.starts_with("mandatory-script-verify-flag-failed"))
+        );
+        assert!(chain.broadcast(&spend(&preimage)).await.is_ok());
+    }
+}
@@ -8,7 +8,7 @@ use crate::proof::get_proof;
 use crate::transactions::base::Input;
 use ark_serialize::CanonicalDeserialize;
 
-use bitcoin::PublicKey;
+use bitcoin::{Address, PublicKey};
 use bitcoin::{Network, OutPoint};
 use clap::{arg, ArgMatches, Command};
 use colored::Colorize;
@@ -124,7 +124,7 @@ impl ClientCommand {
             .unwrap();
         let outpoint = OutPoint::from_str(utxo).unwrap();
 
-        let tx = self.client.esplora.get_tx(&outpoint.txid).await.unwrap();
+        let tx = self.client.backend.get_tx(&outpoint.txid).await.unwrap();
         let tx = tx.unwrap();
         let input = Input {
             outpoint,
@@ -147,14 +147,30 @@ impl ClientCommand {
         Command::new("automatic")
             .short_flag('a')
             .about("Automatic mode: Poll for status updates and sign or broadcast transactions")
+            .arg(arg!(-r --reward_address <ADDRESS> "The address receiving the outputs of the timeouts and disproves broadcast by the verifier")
+                .required(false))
+            .arg(arg!(-f --finality_depth <DEPTH> "The number of confirmations after which graph transactions are not checked for reorgs")
+                .required(false)
+                .value_parser(clap::value_parser!(u32)))
     }
 
-    pub async fn handle_automatic_command(&mut self) -> io::Result<()> {
+    pub async fn handle_automatic_command(&mut self, sub_matches: &ArgMatches) -> io::Result<()> {
+        if let Some(address) = sub_matches.get_one::<String>("reward_address") {
+            let address = Address::from_str(address)
+                .and_then(|address| address.require_network(self.client.source_network))
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+            self.client.set_verifier_reward_address(&address);
+        }
+        if let Some(finality_depth) = sub_matches.get_one::<u32>("finality_depth") {
+            self.client.set_finality_depth(*finality_depth);
+        }
+
         loop {
             self.client.sync().await;
 
             let old_data = self.client.data().clone();
 
+            self.client.sync_graph_states().await;
             self.client.process_peg_ins().await;
             self.client.process_peg_outs().await;
 
@@ -312,8 +328,8 @@ impl ClientCommand {
                 self.handle_status_command().await?;
             } else if let Some(sub_matches) = matches.subcommand_matches("broadcast") {
                 self.handle_broadcast_command(sub_matches).await?;
-            } else if matches.subcommand_matches("automatic").is_some() {
-                self.handle_automatic_command().await?;
+            } else if let Some(sub_matches) = matches.subcommand_matches("automatic") {
+                self.handle_automatic_command(sub_matches).await?;
             } else if matches.subcommand_matches("interactive").is_some() {
                 println!("{}", "Already in interactive mode.".yellow());
             } else {
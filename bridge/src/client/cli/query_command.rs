@@ -188,10 +188,13 @@ impl QueryCommand {
             &depositor_taproot_key,
             outpoint,
         );
-        Response::new(
-            ResponseStatus::OK,
-            Some(serde_json::to_value(result).unwrap()),
-        )
+        match result {
+            Ok(result) => Response::new(
+                ResponseStatus::OK,
+                Some(serde_json::to_value(result).unwrap()),
+            ),
+            Err(err) => Response::new(ResponseStatus::NOK(err.to_string()), None),
+        }
     }
 
     pub async fn handle_depositor(&mut self, matches: &ArgMatches) -> Response {
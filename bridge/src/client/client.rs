@@ -1,15 +1,17 @@
 use bitcoin::{
-    absolute::Height, consensus::encode::serialize_hex, Address, Amount, Network, OutPoint,
-    PublicKey, ScriptBuf, Transaction, Txid, XOnlyPublicKey,
+    absolute::Height, block::Header, consensus::encode::serialize_hex, secp256k1::SecretKey,
+    Address, Amount, FeeRate, Network, OutPoint, PublicKey, ScriptBuf, Transaction, Txid,
+    XOnlyPublicKey,
 };
-use esplora_client::{AsyncClient, Builder, TxStatus, Utxo};
+use esplora_client::{Builder, TxStatus, Utxo};
 use futures::future::join_all;
 use musig2::SecNonce;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
 use crate::{
@@ -19,20 +21,27 @@ use crate::{
     connectors::{base::TaprootConnector, connector_0::Connector0, connector_z::ConnectorZ},
     constants::DestinationNetwork,
     contexts::base::generate_n_of_n_public_key,
-    error::{ClientError, Error},
+    error::{ChunkerError, ClientError, Error, TransactionError},
     graphs::{
         base::{
             broadcast_and_verify, get_tx_statuses, GraphId, PEG_OUT_FEE, REWARD_MULTIPLIER,
             REWARD_PRECISION,
         },
+        monitor::ChainMonitor,
         peg_in::{PegInDepositorStatus, PegInVerifierStatus},
-        peg_out::PegOutOperatorStatus,
+        peg_out::{CpfpParent, PegOutOperatorStatus, PegOutVerifierStatus},
     },
     proof::get_proof,
     scripts::generate_pay_to_pubkey_script_address,
+    superblock::{HeaderSource, LocalHeaderSource, SuperblockTracker},
     transactions::{
-        peg_in_confirm::PegInConfirmTransaction, peg_in_deposit::PegInDepositTransaction,
-        peg_in_refund::PegInRefundTransaction, pre_signed_musig2::PreSignedMusig2Transaction,
+        cpfp::CpfpTransaction,
+        fee::{estimate_fee_rate, fee_rate, DEFAULT_CONFIRMATION_TARGET},
+        peg_in_confirm::PegInConfirmTransaction,
+        peg_in_deposit::PegInDepositTransaction,
+        peg_in_refund::PegInRefundTransaction,
+        pre_signed_musig2::PreSignedMusig2Transaction,
+        signing_frost::FrostKeyPackage,
     },
 };
 
@@ -48,15 +57,17 @@ use super::{
         },
         graphs::{
             base::BaseGraph,
+            operator_set::OperatorSet,
             peg_in::{generate_id as peg_in_generate_id, PegInGraph},
             peg_out::{generate_id as peg_out_generate_id, PegOutGraph},
         },
         serialization::{serialize, try_deserialize},
         transactions::{
-            base::{Input, InputWithScript},
+            base::{BaseTransaction, Input, InputWithScript},
             pre_signed::PreSignedTransaction,
         },
     },
+    backend::{base::BitcoinBackend, bitcoin_core::BitcoinCoreRpc},
     chain::chain::Chain,
     data_store::data_store::DataStore,
     files::{
@@ -80,6 +91,8 @@ pub struct BitVMClientPublicData {
     pub version: u32,
     pub peg_in_graphs: Vec<PegInGraph>,
     pub peg_out_graphs: Vec<PegOutGraph>,
+    #[serde(default)]
+    pub operator_set: OperatorSet,
 }
 
 impl BitVMClientPublicData {
@@ -104,10 +117,15 @@ pub struct BitVMClientPrivateData {
     // Operator public key -> Graph ID -> Message ID -> Winternitz secret
     pub commitment_secrets:
         HashMap<PublicKey, HashMap<String, HashMap<CommitmentMessageId, WinternitzSecret>>>,
+    // Heaviest superblocks of the measurement periods of the peg-out graphs.
+    // Graph ID -> Superblock tracker
+    #[serde(default)]
+    pub superblock_trackers: HashMap<GraphId, SuperblockTracker>,
 }
 
 pub struct BitVMClient {
-    pub esplora: AsyncClient,
+    // Esplora, unless a Bitcoin Core node is configured
+    pub backend: Arc<dyn BitcoinBackend>,
     pub source_network: Network,
 
     depositor_context: Option<DepositorContext>,
@@ -126,6 +144,13 @@ pub struct BitVMClient {
     chain_adaptor: Chain,
 
     zkproof_verifying_key: Option<ZkProofVerifyingKey>,
+
+    // Output of the timeouts and disproves broadcast by the verifier, its own address if not set
+    verifier_reward_script: Option<ScriptBuf>,
+    // Peg-out graphs whose assertions were checked valid, so that the proof is not verified again
+    valid_assertions: HashSet<GraphId>,
+    // Tip of the source chain, to roll back the graph states after reorgs
+    chain_monitor: ChainMonitor,
 }
 
 impl BitVMClient {
@@ -198,6 +223,7 @@ impl BitVMClient {
             version: 1,
             peg_in_graphs: vec![],
             peg_out_graphs: vec![],
+            operator_set: OperatorSet::new(),
         };
 
         let data_store = DataStore::new().await;
@@ -207,10 +233,17 @@ impl BitVMClient {
 
         let chain_adaptor = Chain::new();
 
+        let backend: Arc<dyn BitcoinBackend> = match BitcoinCoreRpc::new() {
+            Some(bitcoin_core) => Arc::new(bitcoin_core),
+            None => Arc::new(
+                Builder::new(esplora_url.unwrap_or(ESPLORA_URL))
+                    .build_async()
+                    .expect("Could not build esplora client"),
+            ),
+        };
+
         Self {
-            esplora: Builder::new(esplora_url.unwrap_or(ESPLORA_URL))
-                .build_async()
-                .expect("Could not build esplora client"),
+            backend,
             source_network,
 
             depositor_context,
@@ -229,6 +262,10 @@ impl BitVMClient {
             chain_adaptor,
 
             zkproof_verifying_key,
+
+            verifier_reward_script: None,
+            valid_assertions: HashSet::new(),
+            chain_monitor: ChainMonitor::new(source_network),
         }
     }
 
@@ -303,6 +340,166 @@ impl BitVMClient {
         }
     }
 
+    pub fn set_verifier_reward_address(&mut self, address: &Address) {
+        self.verifier_reward_script = Some(address.script_pubkey());
+    }
+
+    pub fn set_finality_depth(&mut self, finality_depth: u32) {
+        self.chain_monitor = ChainMonitor::with_finality_depth(finality_depth);
+    }
+
+    pub fn set_backend(&mut self, backend: Arc<dyn BitcoinBackend>) { self.backend = backend; }
+
+    /// Registers the operator with the output holding the collateral it fronts withdrawals with,
+    /// the registration being published with the graphs on the next flush
+    pub fn register_operator(&mut self, collateral: Input) {
+        if self.operator_context.is_none() {
+            panic!("Operator context must be initialized");
+        }
+        self.data
+            .operator_set
+            .register(self.operator_context.as_ref().unwrap(), collateral);
+    }
+
+    /// Leaves the operator out of the new peg-ins, its existing peg-out graphs being kept
+    pub fn deregister_operator(&mut self) -> Result<(), Error> {
+        if self.operator_context.is_none() {
+            panic!("Operator context must be initialized");
+        }
+        self.data
+            .operator_set
+            .deregister(self.operator_context.as_ref().unwrap())
+    }
+
+    /// Switches to a new n-of-n verifier set for the new peg-ins
+    ///
+    /// The graphs of the previous set are flushed to its data directory, keyed by its n-of-n
+    /// public key, and are no longer processed by this client. The clients of that set keep
+    /// processing them, so a verifier leaving the set must have presigned its pending graphs
+    /// first. This client processes them again once rotated back to that set.
+    pub async fn rotate_verifier_set(&mut self, n_of_n_public_keys: &[PublicKey]) {
+        self.flush().await;
+
+        let network = self.source_network;
+        self.depositor_context = self.depositor_context.as_ref().map(|context| {
+            DepositorContext::new(
+                network,
+                &context.depositor_keypair.display_secret().to_string(),
+                n_of_n_public_keys,
+            )
+        });
+        self.operator_context = self.operator_context.as_ref().map(|context| {
+            OperatorContext::new(
+                network,
+                &context.operator_keypair.display_secret().to_string(),
+                n_of_n_public_keys,
+            )
+        });
+        self.verifier_context = self.verifier_context.as_ref().map(|context| {
+            VerifierContext::new(
+                network,
+                &context.verifier_keypair.display_secret().to_string(),
+                n_of_n_public_keys,
+            )
+        });
+        self.withdrawer_context = self.withdrawer_context.as_ref().map(|context| {
+            WithdrawerContext::new(
+                network,
+                &context.withdrawer_keypair.display_secret().to_string(),
+                n_of_n_public_keys,
+            )
+        });
+
+        let (n_of_n_public_key, _) = generate_n_of_n_public_key(n_of_n_public_keys);
+        self.switch_data_directory(&n_of_n_public_key);
+    }
+
+    /// Switches to a t-of-n verifier set for the new peg-ins, any `threshold` verifiers of the key
+    /// package being enough to presign their graphs
+    ///
+    /// The graphs of the previous set are handed over to its clients as by `rotate_verifier_set`.
+    ///
+    /// # Arguments
+    ///
+    /// * `frost_key_package` - Group key and verifying shares of the verifier set
+    /// * `frost_signing_share` - Signing share of the verifier, required if a verifier context is set
+    pub async fn rotate_frost_verifier_set(
+        &mut self,
+        frost_key_package: &FrostKeyPackage,
+        frost_signing_share: Option<&SecretKey>,
+    ) -> Result<(), Error> {
+        if let Some(verifier_context) = &self.verifier_context {
+            let frost_signing_share = frost_signing_share
+                .ok_or(Error::Client(ClientError::FrostSigningShareNotDefined))?;
+            if !frost_key_package
+                .is_signing_share_of(&verifier_context.verifier_public_key, frost_signing_share)
+            {
+                return Err(Error::Client(ClientError::InvalidFrostSigningShare));
+            }
+        }
+
+        self.flush().await;
+
+        let network = self.source_network;
+        self.depositor_context = self.depositor_context.as_ref().map(|context| {
+            DepositorContext::new_with_frost_key_package(
+                network,
+                &context.depositor_keypair.display_secret().to_string(),
+                frost_key_package,
+            )
+        });
+        self.operator_context = self.operator_context.as_ref().map(|context| {
+            OperatorContext::new_with_frost_key_package(
+                network,
+                &context.operator_keypair.display_secret().to_string(),
+                frost_key_package,
+            )
+        });
+        self.verifier_context = self.verifier_context.as_ref().zip(frost_signing_share).map(
+            |(context, frost_signing_share)| {
+                VerifierContext::new_with_frost_key_package(
+                    network,
+                    &context.verifier_keypair.display_secret().to_string(),
+                    frost_key_package,
+                    frost_signing_share,
+                )
+            },
+        );
+        self.withdrawer_context = self.withdrawer_context.as_ref().map(|context| {
+            WithdrawerContext::new_with_frost_key_package(
+                network,
+                &context.withdrawer_keypair.display_secret().to_string(),
+                frost_key_package,
+            )
+        });
+
+        self.switch_data_directory(&frost_key_package.group_public_key);
+
+        Ok(())
+    }
+
+    fn switch_data_directory(&mut self, n_of_n_public_key: &PublicKey) {
+        let (parent_path, _) = self
+            .remote_file_path
+            .rsplit_once('/')
+            .expect("Invalid remote file path");
+        self.remote_file_path = format!("{parent_path}/{n_of_n_public_key}");
+        self.local_file_path
+            .set_file_name(n_of_n_public_key.to_string());
+        println!("Using data file path: {}", self.local_file_path.display());
+
+        self.data = BitVMClientPublicData {
+            version: 1,
+            peg_in_graphs: vec![],
+            peg_out_graphs: vec![],
+            operator_set: OperatorSet::new(),
+        };
+        self.latest_processed_file_name = None;
+        self.private_data =
+            get_private_data_from_file(&get_private_data_file_path(&self.local_file_path));
+        self.valid_assertions.clear();
+    }
+
     pub fn set_chain_adaptor(&mut self, chain_adaptor: Chain) {
         self.chain_adaptor = chain_adaptor;
     }
@@ -565,7 +762,9 @@ impl BitVMClient {
         for peg_in_graph in data.peg_in_graphs.iter() {
             let graph = peg_in_graphs_by_id.get_mut(peg_in_graph.id());
             if let Some(graph) = graph {
-                graph.merge(peg_in_graph);
+                if let Err(e) = graph.merge(peg_in_graph) {
+                    eprintln!("Ignoring peg-in graph {}: {e}", peg_in_graph.id());
+                }
             } else {
                 peg_in_graphs_to_add.push(peg_in_graph);
             }
@@ -595,6 +794,9 @@ impl BitVMClient {
         for graph in peg_out_graphs_to_add.into_iter() {
             self.data.peg_out_graphs.push(graph.clone());
         }
+
+        // operator set
+        self.data.operator_set.merge(&data.operator_set);
     }
 
     // fn process(&self) {
@@ -646,7 +848,7 @@ impl BitVMClient {
             .depositor_public_key;
         for peg_in_graph in self.data.peg_in_graphs.iter() {
             if peg_in_graph.depositor_public_key.eq(depositor_public_key) {
-                let status = peg_in_graph.depositor_status(&self.esplora).await;
+                let status = peg_in_graph.depositor_status(&self.backend).await;
                 println!("Graph id: {} status: {}\n", peg_in_graph.id(), status);
             }
         }
@@ -672,7 +874,7 @@ impl BitVMClient {
                 );
             } else {
                 let peg_out_graph = peg_out_graphs_by_id.get(&peg_out_graph_id).unwrap();
-                let status = peg_out_graph.operator_status(&self.esplora).await;
+                let status = peg_out_graph.operator_status(&self.backend).await;
                 println!("Graph id: {} status: {}\n", peg_out_graph.id(), status);
             }
         }
@@ -682,7 +884,7 @@ impl BitVMClient {
     pub async fn process_peg_in_as_depositor(&mut self, peg_in_graph_id: &GraphId) {
         if self.depositor_context.is_some() {
             if let Ok(peg_in_graph) = self.get_peg_in_graph(peg_in_graph_id) {
-                let status = peg_in_graph.depositor_status(&self.esplora).await;
+                let status = peg_in_graph.depositor_status(&self.backend).await;
                 match status {
                     PegInDepositorStatus::PegInDepositWait => {
                         let _ = self.broadcast_peg_in_deposit(peg_in_graph_id).await;
@@ -709,7 +911,7 @@ impl BitVMClient {
                     .filter(|peg_out| peg_in_graph.peg_out_graphs.contains(peg_out.id()))
                     .collect::<Vec<_>>();
                 let status = peg_in_graph
-                    .verifier_status(&self.esplora, Some(context), &peg_outs_for_this_peg_in)
+                    .verifier_status(&self.backend, Some(context), &peg_outs_for_this_peg_in)
                     .await;
                 match status {
                     PegInVerifierStatus::PendingOurNonces(graph_ids) => {
@@ -721,7 +923,9 @@ impl BitVMClient {
                     PegInVerifierStatus::PendingOurSignature(graph_ids) => {
                         println!("Pushing signature for graphs {graph_ids:?}");
                         for graph_id in graph_ids {
-                            self.push_verifier_signature(&graph_id);
+                            if let Err(err) = self.push_verifier_signature(&graph_id) {
+                                println!("Error: {err}");
+                            }
                         }
                     }
                     PegInVerifierStatus::ReadyToSubmit => {
@@ -742,10 +946,15 @@ impl BitVMClient {
             if let Ok(peg_in_graph) = self.get_peg_in_graph(peg_in_graph_id) {
                 let peg_out_graph_id =
                     peg_out_generate_id(peg_in_graph, &context.operator_public_key);
-                if !peg_in_graph
-                    .peg_out_graphs
-                    .iter()
-                    .any(|x| x == &peg_out_graph_id)
+                let is_peg_in_operator = peg_in_graph.operator_public_keys.is_empty()
+                    || peg_in_graph
+                        .operator_public_keys
+                        .contains(&context.operator_public_key);
+                if is_peg_in_operator
+                    && !peg_in_graph
+                        .peg_out_graphs
+                        .iter()
+                        .any(|x| x == &peg_out_graph_id)
                 {
                     let deposit_amount =
                         peg_in_graph.peg_in_deposit_transaction.tx().output[0].value;
@@ -758,7 +967,7 @@ impl BitVMClient {
                             &context.operator_public_key,
                         );
                         let utxos = self
-                            .esplora
+                            .backend
                             .get_address_utxo(address.clone())
                             .await
                             .unwrap();
@@ -786,6 +995,39 @@ impl BitVMClient {
         }
     }
 
+    /// Syncs the state of the graphs with the chain, the progress is persisted with the graphs on
+    /// the next flush
+    ///
+    /// The states are rolled back to the first block changed by a reorg before the sync, and the
+    /// transactions confirmed deeper than the finality depth are not checked again.
+    pub async fn sync_graph_states(&mut self) {
+        match self.chain_monitor.update(&self.backend).await {
+            Ok(Some(reorg_height)) => {
+                println!("Reorg detected from block {reorg_height}, rolling back the graph states");
+                for peg_in_graph in self.data.peg_in_graphs.iter_mut() {
+                    peg_in_graph.rewind_state(reorg_height);
+                }
+                for peg_out_graph in self.data.peg_out_graphs.iter_mut() {
+                    peg_out_graph.rewind_state(reorg_height);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => println!("Failed to update the chain monitor: {e}"),
+        }
+
+        let finalized_height = self.chain_monitor.finalized_height();
+        for peg_in_graph in self.data.peg_in_graphs.iter_mut() {
+            peg_in_graph
+                .sync_state(&self.backend, finalized_height)
+                .await;
+        }
+        for peg_out_graph in self.data.peg_out_graphs.iter_mut() {
+            peg_out_graph
+                .sync_state(&self.backend, finalized_height)
+                .await;
+        }
+    }
+
     pub async fn process_peg_ins(&mut self) {
         for peg_in_graph in self.data.peg_in_graphs.clone() {
             self.process_peg_in_as_depositor(peg_in_graph.id()).await;
@@ -798,7 +1040,9 @@ impl BitVMClient {
     pub async fn process_peg_outs(&mut self) {
         let peg_out_graphs = self.data().peg_out_graphs.clone();
         for peg_out_graph in peg_out_graphs.iter() {
-            let status = peg_out_graph.operator_status(&self.esplora).await;
+            // the start time is not committed yet until the start time transaction is confirmed
+            let _ = self.sync_superblock(peg_out_graph.id()).await;
+            let status = peg_out_graph.operator_status(&self.backend).await;
             match status {
                 PegOutOperatorStatus::PegOutStartTimeAvailable => {
                     let _ = self.broadcast_start_time(peg_out_graph.id()).await;
@@ -836,6 +1080,70 @@ impl BitVMClient {
                 }
                 _ => {}
             }
+            self.process_peg_out_as_verifier(peg_out_graph.id()).await;
+        }
+    }
+
+    /// Watchtower of the verifier: broadcasts the timeouts and disproves available for the peg-out graph
+    /// The assertions of the operator are checked by running the disprove execution on its commitments, and
+    /// the disprove transaction is broadcast if a segment of the proof verification is disprovable.
+    pub async fn process_peg_out_as_verifier(&mut self, peg_out_graph_id: &GraphId) {
+        let Some(ref context) = self.verifier_context else {
+            return;
+        };
+        let reward_script = self.verifier_reward_script.clone().unwrap_or_else(|| {
+            generate_pay_to_pubkey_script_address(context.network, &context.verifier_public_key)
+                .script_pubkey()
+        });
+        let Ok(peg_out_graph) = self.get_peg_out_graph(peg_out_graph_id) else {
+            return;
+        };
+
+        let status = peg_out_graph.verifier_status(&self.backend).await;
+        let result = match status {
+            PegOutVerifierStatus::PegOutStartTimeTimeoutAvailable => {
+                self.broadcast_start_time_timeout(peg_out_graph_id, reward_script)
+                    .await
+            }
+            PegOutVerifierStatus::PegOutKickOffTimeoutAvailable => {
+                self.broadcast_kick_off_timeout(peg_out_graph_id, reward_script)
+                    .await
+            }
+            PegOutVerifierStatus::PegOutDisproveChainAvailable => {
+                let _ = self.sync_superblock(peg_out_graph_id).await;
+                let result = self
+                    .broadcast_disprove_chain(peg_out_graph_id, reward_script)
+                    .await;
+                // the chain cannot be disproven until a heavier superblock is tracked
+                if let Err(Error::Client(ClientError::HeavierSuperblockNotFound(_))) = result {
+                    return;
+                }
+                result
+            }
+            PegOutVerifierStatus::PegOutDisproveAvailable => {
+                if self.valid_assertions.contains(peg_out_graph_id) {
+                    return;
+                }
+                let result = self
+                    .broadcast_disprove(peg_out_graph_id, reward_script)
+                    .await;
+                if let Err(Error::Chunker(ChunkerError::ValidProof)) = result {
+                    println!("Peg-out graph {peg_out_graph_id}: the assertions are valid");
+                    self.valid_assertions.insert(peg_out_graph_id.clone());
+                    return;
+                }
+                result
+            }
+            PegOutVerifierStatus::PegOutChallengeAvailable => {
+                // the challenge is crowdfunded, see broadcast_challenge
+                println!("Peg-out graph {peg_out_graph_id} status: {status}");
+                return;
+            }
+            _ => return,
+        };
+        match result {
+            Ok(txid) => println!("Peg-out graph {peg_out_graph_id}: broadcast {txid}"),
+            Err(e) => println!("Peg-out graph {peg_out_graph_id}: failed to broadcast ({e})"),
         }
     }
 
@@ -857,7 +1165,7 @@ impl BitVMClient {
                 })
                 .collect::<Vec<_>>();
             let status = peg_in_graph
-                .verifier_status(&self.esplora, self.verifier_context.as_ref(), &peg_outs)
+                .verifier_status(&self.backend, self.verifier_context.as_ref(), &peg_outs)
                 .await;
             println!("Graph id: {} status: {}\n", peg_in_graph.id(), status);
         }
@@ -868,8 +1176,9 @@ impl BitVMClient {
             panic!("Depositor context must be initialized");
         }
 
-        let peg_in_graph =
+        let mut peg_in_graph =
             PegInGraph::new(self.depositor_context.as_ref().unwrap(), input, evm_address);
+        peg_in_graph.operator_public_keys = self.data.operator_set.active_operator_public_keys();
 
         let peg_in_graph_id = peg_in_generate_id(&peg_in_graph.peg_in_deposit_transaction);
 
@@ -892,7 +1201,7 @@ impl BitVMClient {
         peg_in_graph_id: &String,
     ) -> Result<Txid, Error> {
         let graph = Self::find_peg_in_or_fail(&mut self.data, peg_in_graph_id)?;
-        let tx = graph.deposit(&self.esplora).await?;
+        let tx = graph.deposit(&self.backend).await?;
         self.broadcast_tx(&tx).await
     }
 
@@ -901,7 +1210,7 @@ impl BitVMClient {
         peg_in_graph_id: &String,
     ) -> Result<Txid, Error> {
         let graph = Self::find_peg_in_or_fail(&mut self.data, peg_in_graph_id)?;
-        let tx = graph.refund(&self.esplora).await?;
+        let tx = graph.refund(&self.backend).await?;
         self.broadcast_tx(&tx).await
     }
 
@@ -910,7 +1219,7 @@ impl BitVMClient {
         peg_in_graph_id: &String,
     ) -> Result<Txid, Error> {
         let graph = Self::find_peg_in_or_fail(&mut self.data, peg_in_graph_id)?;
-        let tx = graph.confirm(&self.esplora).await?;
+        let tx = graph.confirm(&self.backend).await?;
         self.broadcast_tx(&tx).await
     }
 
@@ -931,6 +1240,13 @@ impl BitVMClient {
             .iter_mut()
             .find(|peg_in_graph| peg_in_graph.id().eq(peg_in_graph_id))
             .unwrap_or_else(|| panic!("Invalid graph id"));
+        if !peg_in_graph.operator_public_keys.is_empty()
+            && !peg_in_graph
+                .operator_public_keys
+                .contains(operator_public_key)
+        {
+            panic!("Operator is not part of the operator set of the peg-in");
+        }
 
         let peg_out_graph_id = peg_out_generate_id(peg_in_graph, operator_public_key);
         let peg_out_graph = self
@@ -952,10 +1268,12 @@ impl BitVMClient {
         self.data.peg_out_graphs.push(peg_out_graph);
         peg_in_graph.peg_out_graphs.push(peg_out_graph_id.clone());
 
-        self.private_data.commitment_secrets = HashMap::from([(
-            *operator_public_key,
-            HashMap::from([(peg_out_graph_id.to_string(), commitment_secrets)]),
-        )]);
+        // keep the secrets of the peg-out graphs of the other peg-ins
+        self.private_data
+            .commitment_secrets
+            .entry(*operator_public_key)
+            .or_default()
+            .insert(peg_out_graph_id.to_string(), commitment_secrets);
         self.save_private_data();
 
         peg_out_graph_id
@@ -966,11 +1284,29 @@ impl BitVMClient {
         peg_out_graph_id: &String,
         input: Input,
     ) -> Result<Txid, Error> {
+        if let Some(graph) = self
+            .data
+            .peg_out_graphs
+            .iter()
+            .find(|peg_out_graph| peg_out_graph.id().eq(peg_out_graph_id))
+        {
+            // the withdrawal is only fronted once, by an operator with enough collateral
+            if graph.peg_out_transaction.is_none() {
+                self.data
+                    .operator_set
+                    .check_can_front_withdrawal(
+                        self.backend.as_ref(),
+                        graph,
+                        &self.data.peg_out_graphs,
+                    )
+                    .await?;
+            }
+        }
         let graph = Self::find_peg_out_or_fail(&mut self.data, peg_out_graph_id)?;
         if self.operator_context.is_some() {
             let tx = graph
                 .peg_out(
-                    &self.esplora,
+                    &self.backend,
                     self.operator_context.as_ref().unwrap(),
                     input,
                 )
@@ -986,7 +1322,7 @@ impl BitVMClient {
         peg_out_graph_id: &String,
     ) -> Result<Txid, Error> {
         let graph = Self::find_peg_out_or_fail(&mut self.data, peg_out_graph_id)?;
-        let tx = graph.peg_out_confirm(&self.esplora).await?;
+        let tx = graph.peg_out_confirm(&self.backend).await?;
         self.broadcast_tx(&tx).await
     }
 
@@ -996,7 +1332,7 @@ impl BitVMClient {
         if self.operator_context.is_some() {
             let tx = graph
                 .kick_off_1(
-                    &self.esplora,
+                    &self.backend,
                     self.operator_context.as_ref().unwrap(),
                     &self.private_data.commitment_secrets
                         [&self.operator_context.as_ref().unwrap().operator_public_key]
@@ -1018,7 +1354,7 @@ impl BitVMClient {
         if self.operator_context.is_some() {
             let tx = graph
                 .start_time(
-                    &self.esplora,
+                    &self.backend,
                     self.operator_context.as_ref().unwrap(),
                     &self.private_data.commitment_secrets
                         [&self.operator_context.as_ref().unwrap().operator_public_key]
@@ -1038,16 +1374,100 @@ impl BitVMClient {
     ) -> Result<Txid, Error> {
         let graph = Self::find_peg_out_or_fail(&mut self.data, peg_out_graph_id)?;
         let tx = graph
-            .start_time_timeout(&self.esplora, output_script_pubkey)
+            .start_time_timeout(&self.backend, output_script_pubkey)
             .await?;
         self.broadcast_tx(&tx).await
     }
 
+    /// Scans the headers of the source for the heaviest superblock of the measurement period of the
+    /// peg-out graph, starting at `start_height` with the committed `start_time` the first time the
+    /// graph is tracked
+    pub fn track_superblock(
+        &mut self,
+        peg_out_graph_id: &GraphId,
+        source: &impl HeaderSource,
+        start_time: u32,
+        start_height: u32,
+    ) -> Option<Header> {
+        let tracker = self
+            .private_data
+            .superblock_trackers
+            .entry(peg_out_graph_id.clone())
+            .or_insert_with(|| SuperblockTracker::new(start_time, start_height));
+        tracker.scan(source);
+        let superblock = tracker.superblock;
+        self.save_private_data();
+
+        superblock
+    }
+
+    /// Scans the blocks of the backend confirmed since the last scan for the heaviest superblock
+    /// of the peg-out graph, the measurement period starting at the start time committed on chain.
+    /// The scan starts over if the tracked superblock is no longer on the main chain.
+    pub async fn sync_superblock(
+        &mut self,
+        peg_out_graph_id: &GraphId,
+    ) -> Result<Option<Header>, Error> {
+        let tracked = self
+            .private_data
+            .superblock_trackers
+            .get(peg_out_graph_id)
+            .and_then(|tracker| tracker.superblock.zip(tracker.superblock_height));
+        if let Some((superblock, superblock_height)) = tracked {
+            let block_hash = self
+                .backend
+                .get_block_hash(superblock_height)
+                .await
+                .map_err(Error::Backend)?;
+            if block_hash != superblock.block_hash() {
+                self.private_data
+                    .superblock_trackers
+                    .remove(peg_out_graph_id);
+                self.save_private_data();
+            }
+        }
+
+        let tracker = self.private_data.superblock_trackers.get(peg_out_graph_id);
+        if let Some(tracker) = tracker.filter(|tracker| tracker.complete) {
+            return Ok(tracker.superblock);
+        }
+        let next_height = tracker.map(|tracker| tracker.next_height);
+
+        let graph = self.get_peg_out_graph(peg_out_graph_id)?;
+        let (start_time, start_height) = graph.committed_start_time(&self.backend).await?;
+        let base_height = next_height.unwrap_or(start_height);
+        let mut source = LocalHeaderSource::new(base_height);
+        let height = self.backend.get_height().await.map_err(Error::Backend)?;
+        for block_height in base_height..=height {
+            let block_hash = self
+                .backend
+                .get_block_hash(block_height)
+                .await
+                .map_err(Error::Backend)?;
+            let header = self
+                .backend
+                .get_block_header(&block_hash)
+                .await
+                .map_err(Error::Backend)?;
+            source.push(header);
+        }
+
+        Ok(self.track_superblock(peg_out_graph_id, &source, start_time, start_height))
+    }
+
     pub async fn broadcast_kick_off_2(&mut self, peg_out_graph_id: &String) -> Result<Txid, Error> {
         let graph = Self::find_peg_out_or_fail(&mut self.data, peg_out_graph_id)?;
+        let superblock = self
+            .private_data
+            .superblock_trackers
+            .get(peg_out_graph_id)
+            .and_then(|tracker| tracker.superblock)
+            .ok_or(Error::Client(ClientError::SuperblockNotTracked(
+                peg_out_graph_id.clone(),
+            )))?;
         let tx = graph
             .kick_off_2(
-                &self.esplora,
+                &self.backend,
                 self.operator_context.as_ref().unwrap(),
                 &self.private_data.commitment_secrets
                     [&self.operator_context.as_ref().unwrap().operator_public_key]
@@ -1055,6 +1475,7 @@ impl BitVMClient {
                 &self.private_data.commitment_secrets
                     [&self.operator_context.as_ref().unwrap().operator_public_key]
                     [peg_out_graph_id][&CommitmentMessageId::SuperblockHash],
+                &superblock,
             )
             .await?;
         self.broadcast_tx(&tx).await
@@ -1067,7 +1488,7 @@ impl BitVMClient {
     ) -> Result<Txid, Error> {
         let graph = Self::find_peg_out_or_fail(&mut self.data, peg_out_graph_id)?;
         let tx = graph
-            .kick_off_timeout(&self.esplora, output_script_pubkey)
+            .kick_off_timeout(&self.backend, output_script_pubkey)
             .await?;
         self.broadcast_tx(&tx).await
     }
@@ -1083,7 +1504,7 @@ impl BitVMClient {
         if self.depositor_context.is_some() {
             let tx = graph
                 .challenge(
-                    &self.esplora,
+                    &self.backend,
                     crowdfundng_inputs,
                     &self.depositor_context.as_ref().unwrap().depositor_keypair,
                     output_script_pubkey,
@@ -1093,7 +1514,7 @@ impl BitVMClient {
         } else if self.operator_context.is_some() {
             let tx = graph
                 .challenge(
-                    &self.esplora,
+                    &self.backend,
                     crowdfundng_inputs,
                     &self.operator_context.as_ref().unwrap().operator_keypair,
                     output_script_pubkey,
@@ -1103,7 +1524,7 @@ impl BitVMClient {
         } else if self.verifier_context.is_some() {
             let tx = graph
                 .challenge(
-                    &self.esplora,
+                    &self.backend,
                     crowdfundng_inputs,
                     &self.verifier_context.as_ref().unwrap().verifier_keypair,
                     output_script_pubkey,
@@ -1113,7 +1534,7 @@ impl BitVMClient {
         } else if self.withdrawer_context.is_some() {
             let tx = graph
                 .challenge(
-                    &self.esplora,
+                    &self.backend,
                     crowdfundng_inputs,
                     &self.withdrawer_context.as_ref().unwrap().withdrawer_keypair,
                     output_script_pubkey,
@@ -1130,7 +1551,7 @@ impl BitVMClient {
         peg_out_graph_id: &String,
     ) -> Result<Txid, Error> {
         let graph = Self::find_peg_out_or_fail(&mut self.data, peg_out_graph_id)?;
-        let tx = graph.assert_initial(&self.esplora).await?;
+        let tx = graph.assert_initial(&self.backend).await?;
         self.broadcast_tx(&tx).await
     }
 
@@ -1142,7 +1563,7 @@ impl BitVMClient {
         let graph = Self::find_peg_out_or_fail(&mut self.data, peg_out_graph_id)?;
         let tx = graph
             .assert_commit_1(
-                &self.esplora,
+                &self.backend,
                 &self.private_data.commitment_secrets
                     [&self.operator_context.as_ref().unwrap().operator_public_key]
                     [peg_out_graph_id],
@@ -1160,7 +1581,7 @@ impl BitVMClient {
         let graph = Self::find_peg_out_or_fail(&mut self.data, peg_out_graph_id)?;
         let tx = graph
             .assert_commit_2(
-                &self.esplora,
+                &self.backend,
                 &self.private_data.commitment_secrets
                     [&self.operator_context.as_ref().unwrap().operator_public_key]
                     [peg_out_graph_id],
@@ -1175,7 +1596,7 @@ impl BitVMClient {
         peg_out_graph_id: &String,
     ) -> Result<Txid, Error> {
         let graph = Self::find_peg_out_or_fail(&mut self.data, peg_out_graph_id)?;
-        let tx = graph.assert_final(&self.esplora).await?;
+        let tx = graph.assert_final(&self.backend).await?;
         self.broadcast_tx(&tx).await
     }
 
@@ -1187,7 +1608,7 @@ impl BitVMClient {
         let graph = Self::find_peg_out_or_fail(&mut self.data, peg_out_graph_id)?;
         let tx = graph
             .disprove(
-                &self.esplora,
+                &self.backend,
                 output_script_pubkey,
                 self.zkproof_verifying_key
                     .as_ref()
@@ -1203,28 +1624,91 @@ impl BitVMClient {
         output_script_pubkey: ScriptBuf,
     ) -> Result<Txid, Error> {
         let graph = Self::find_peg_out_or_fail(&mut self.data, peg_out_graph_id)?;
+        let committed_superblock = graph.committed_superblock(&self.backend).await?;
+        let superblock = self
+            .private_data
+            .superblock_trackers
+            .get(peg_out_graph_id)
+            .and_then(|tracker| tracker.heavier_than(&committed_superblock))
+            .ok_or(Error::Client(ClientError::HeavierSuperblockNotFound(
+                peg_out_graph_id.clone(),
+            )))?;
         let tx = graph
-            .disprove_chain(&self.esplora, output_script_pubkey)
+            .disprove_chain(&self.backend, output_script_pubkey, &superblock)
             .await?;
         self.broadcast_tx(&tx).await
     }
 
     pub async fn broadcast_take_1(&mut self, peg_out_graph_id: &String) -> Result<Txid, Error> {
         let graph = Self::find_peg_out_or_fail(&mut self.data, peg_out_graph_id)?;
-        let tx = graph.take_1(&self.esplora).await?;
+        let tx = graph.take_1(&self.backend).await?;
         self.broadcast_tx(&tx).await
     }
 
     pub async fn broadcast_take_2(&mut self, peg_out_graph_id: &String) -> Result<Txid, Error> {
         let graph = Self::find_peg_out_or_fail(&mut self.data, peg_out_graph_id)?;
         let tx = graph
-            .take_2(&self.esplora, self.operator_context.as_ref().unwrap())
+            .take_2(&self.backend, self.operator_context.as_ref().unwrap())
             .await?;
         self.broadcast_tx(&tx).await
     }
 
+    /// Bumps the fee of the unconfirmed transaction of the peg-out graph with a CPFP transaction,
+    /// funded by the operator for the kick-off and assert transactions and by the verifier for the
+    /// disprove transactions, at the estimated fee rate if none is given
+    pub async fn broadcast_cpfp(
+        &mut self,
+        peg_out_graph_id: &String,
+        parent: CpfpParent,
+        funding_input: Input,
+        target_fee_rate: Option<FeeRate>,
+    ) -> Result<Txid, Error> {
+        let (public_key, keypair) = match parent {
+            CpfpParent::DisproveChain | CpfpParent::Disprove => {
+                let context = self
+                    .verifier_context
+                    .as_ref()
+                    .ok_or(Error::Client(ClientError::VerifierContextNotDefined))?;
+                (context.verifier_public_key, context.verifier_keypair)
+            }
+            _ => {
+                let context = self
+                    .operator_context
+                    .as_ref()
+                    .ok_or(Error::Client(ClientError::OperatorContextNotDefined))?;
+                (context.operator_public_key, context.operator_keypair)
+            }
+        };
+        let target_fee_rate = match target_fee_rate {
+            Some(target_fee_rate) => target_fee_rate,
+            None => estimate_fee_rate(&self.backend, DEFAULT_CONFIRMATION_TARGET)
+                .await
+                .map_err(Error::Backend)?,
+        };
+
+        let graph = Self::find_peg_out_or_fail(&mut self.data, peg_out_graph_id)?;
+        let (parent_tx, parent_fee, parent_vout) = graph.cpfp_parent(&self.backend, parent).await?;
+        if fee_rate(parent_fee, parent_tx.weight()) >= target_fee_rate {
+            return Err(Error::Transaction(TransactionError::FeeRateAlreadyMet(
+                parent_tx.compute_txid(),
+            )));
+        }
+
+        let tx = CpfpTransaction::new(
+            self.source_network,
+            &public_key,
+            &keypair,
+            &parent_tx,
+            parent_fee,
+            parent_vout,
+            funding_input,
+            target_fee_rate,
+        )?;
+        self.broadcast_tx(&tx.finalize()).await
+    }
+
     pub async fn get_initial_utxo(&self, address: Address, amount: Amount) -> Option<Utxo> {
-        let utxos = self.esplora.get_address_utxo(address).await.unwrap();
+        let utxos = self.backend.get_address_utxo(address).await.unwrap();
 
         let possible_utxos = utxos
             .into_iter()
@@ -1238,7 +1722,7 @@ impl BitVMClient {
     }
 
     pub async fn get_initial_utxos(&self, address: Address, amount: Amount) -> Option<Vec<Utxo>> {
-        let utxos: Vec<Utxo> = self.esplora.get_address_utxo(address).await.unwrap();
+        let utxos: Vec<Utxo> = self.backend.get_address_utxo(address).await.unwrap();
         let possible_utxos = utxos
             .into_iter()
             .filter(|utxo| utxo.value == amount)
@@ -1259,7 +1743,7 @@ impl BitVMClient {
     }
 
     pub async fn get_depositor_utxos(&self) -> Vec<Utxo> {
-        self.esplora
+        self.backend
             .get_address_utxo(self.get_depositor_address())
             .await
             .unwrap()
@@ -1276,6 +1760,16 @@ impl BitVMClient {
         self.save_private_data();
     }
 
+    fn get_peg_out_graph(&self, peg_out_graph_id: &String) -> Result<&PegOutGraph, Error> {
+        self.data
+            .peg_out_graphs
+            .iter()
+            .find(|peg_out_graph| peg_out_graph.id().eq(peg_out_graph_id))
+            .ok_or(Error::Client(ClientError::PegOutGraphNotFound(
+                peg_out_graph_id.clone(),
+            )))
+    }
+
     fn get_peg_in_graph(&self, peg_in_graph_id: &String) -> Result<&PegInGraph, Error> {
         self.data
             .peg_in_graphs
@@ -1334,7 +1828,7 @@ impl BitVMClient {
 
     async fn broadcast_tx(&self, tx: &Transaction) -> Result<Txid, Error> {
         let transaction_id = tx.compute_txid();
-        let status_message = broadcast_and_verify(&self.esplora, tx).await?;
+        let status_message = broadcast_and_verify(&self.backend, tx).await?;
         // TODO: expose this or have it print out here?
         println!("{} ({:?})", status_message, transaction_id);
         Ok(tx.compute_txid())
@@ -1345,29 +1839,20 @@ impl BitVMClient {
         graph_id: &str,
         secret_nonces: HashMap<Txid, HashMap<usize, SecNonce>>,
     ) {
-        self.private_data
+        // the nonces are pushed per input, the secret nonces of the inputs pushed before staying
+        let graph_secret_nonces = self
+            .private_data
             .secret_nonces
             .entry(self.verifier_context.as_ref().unwrap().verifier_public_key)
+            .or_default()
+            .entry(graph_id.to_string())
             .or_default();
-
-        if !self.private_data.secret_nonces
-            [&self.verifier_context.as_ref().unwrap().verifier_public_key]
-            .contains_key(graph_id)
-        {
-            self.private_data
-                .secret_nonces
-                .get_mut(&self.verifier_context.as_ref().unwrap().verifier_public_key)
-                .unwrap()
-                .insert(graph_id.to_string(), HashMap::new());
+        for (txid, tx_secret_nonces) in secret_nonces {
+            graph_secret_nonces
+                .entry(txid)
+                .or_default()
+                .extend(tx_secret_nonces);
         }
-
-        self.private_data
-            .secret_nonces
-            .get_mut(&self.verifier_context.as_ref().unwrap().verifier_public_key)
-            .unwrap()
-            .get_mut(graph_id)
-            .unwrap()
-            .extend(secret_nonces);
     }
 
     pub fn generate_connector_z_taproot_address(
@@ -1396,7 +1881,7 @@ impl BitVMClient {
         amount: Amount,
         depositor_taproot_key: &XOnlyPublicKey,
         outpoint: OutPoint,
-    ) -> String {
+    ) -> Result<String, Error> {
         let connector_z = ConnectorZ::new(
             source_network,
             recipient_address,
@@ -1432,8 +1917,8 @@ impl BitVMClient {
             self.verifier_context.as_ref().unwrap(),
             &connector_z,
             &secret_nonces_0,
-        );
-        serialize_hex(&(peg_in_confirm_tx.tx_mut()))
+        )?;
+        Ok(serialize_hex(&(peg_in_confirm_tx.tx_mut())))
     }
 
     pub fn generate_presign_pegin_deposit_tx(
@@ -1492,7 +1977,7 @@ impl BitVMClient {
         serialize_hex(&(peg_in_refund_tx.tx_mut()))
     }
 
-    pub fn push_verifier_signature(&mut self, graph_id: &GraphId) {
+    pub fn push_verifier_signature(&mut self, graph_id: &GraphId) -> Result<(), Error> {
         let verifier = self
             .verifier_context
             .as_ref()
@@ -1501,9 +1986,22 @@ impl BitVMClient {
         let graph = self.data.graph_mut(graph_id);
         graph.verifier_sign(
             verifier,
-            &self.private_data.secret_nonces
-                [&self.verifier_context.as_ref().unwrap().verifier_public_key][graph_id],
-        );
+            &self
+                .private_data
+                .secret_nonces
+                .get(&verifier.verifier_public_key)
+                .and_then(|graph_secret_nonces| graph_secret_nonces.get(graph_id))
+                .cloned()
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Evicts a verifier whose nonces or partial signatures were found invalid from the graph, for
+    /// it to sign again with fresh nonces, the graph being blocked until then
+    pub fn evict_verifier(&mut self, graph_id: &GraphId, verifier_public_key: &PublicKey) {
+        self.data
+            .graph_mut(graph_id)
+            .evict_verifier(verifier_public_key);
     }
 
     // pub async fn execute_possible_txs(
@@ -1598,9 +2096,9 @@ impl ClientCliQuery for BitVMClient {
     async fn get_unused_peg_in_graphs(&self) -> Vec<Value> {
         join_all(self.data.peg_in_graphs.iter().filter_map(|peg_in| {
             Some(async move {
-                match peg_in.depositor_status(&self.esplora).await {
+                match peg_in.depositor_status(&self.backend).await {
                     PegInDepositorStatus::PegInConfirmComplete => match self.data.peg_out_graphs.iter().find(|peg_out| peg_out.peg_in_graph_id == *peg_in.id()) {
-                        Some(peg_out) => match peg_out.operator_status(&self.esplora).await {
+                        Some(peg_out) => match peg_out.operator_status(&self.backend).await {
                             PegOutOperatorStatus::PegOutWait => Some(json!({
                                 "graph_id": peg_in.id(),
                                 "amount": peg_in.peg_in_confirm_transaction.prev_outs()[0].value.to_sat(),
@@ -1637,8 +2135,8 @@ impl ClientCliQuery for BitVMClient {
                         graph.peg_in_confirm_transaction.tx().compute_txid(),
                         graph.peg_in_refund_transaction.tx().compute_txid(),
                     ];
-                    let tx_statuses_results = get_tx_statuses(&self.esplora, &tx_ids).await;
-                    let blockchain_height = self.esplora.get_height().await;
+                    let tx_statuses_results = get_tx_statuses(&self.backend, &tx_ids).await;
+                    let blockchain_height = self.backend.get_height().await;
                     let status = graph.interpret_depositor_status(
                         &tx_statuses_results[0],
                         &tx_statuses_results[1],
@@ -1711,7 +2209,7 @@ impl ClientCliQuery for BitVMClient {
                     let (tx_json_value, tx_status_result) = match &graph.peg_out_transaction {
                         Some(tx) => {
                             let txid = tx.tx().compute_txid();
-                            let tx_status_result = self.esplora.get_tx_status(&txid).await;
+                            let tx_status_result = self.backend.get_tx_status(&txid).await;
                             let tx_status = tx_status_result.as_ref().unwrap_or(&TxStatus {
                                 confirmed: false,
                                 block_height: None,
@@ -1837,7 +2335,7 @@ impl ClientCliQuery for BitVMClient {
 
         self.data.peg_in_graphs.push(peg_in_graph.clone());
 
-        match peg_in_graph.broadcast_deposit(&self.esplora).await {
+        match peg_in_graph.broadcast_deposit(&self.backend).await {
             Ok(_) => Ok(json!({"graph_id": peg_in_graph_id})),
             Err(e) => Err(e),
         }
@@ -1853,7 +2351,7 @@ impl ClientCliQuery for BitVMClient {
             return Err("Peg in graph not found".into());
         };
 
-        match peg_in_graph.broadcast_deposit(&self.esplora).await {
+        match peg_in_graph.broadcast_deposit(&self.backend).await {
             Ok(_) => Ok(json!({"graph_id": peg_in_graph_id})),
             Err(e) => Err(e),
         }
@@ -52,6 +52,7 @@ pub fn get_private_data_from_file(path: &Path) -> BitVMClientPrivateData {
             BitVMClientPrivateData {
                 secret_nonces: HashMap::new(),
                 commitment_secrets: HashMap::new(),
+                superblock_trackers: HashMap::new(),
             }
         }
     }
@@ -24,6 +24,8 @@ pub enum CommitmentMessageId {
     SuperblockHash,
     // name of intermediate value and length of message
     Groth16IntermediateValues((String, usize)),
+    // names and lengths of intermediate values committed under a single key
+    Groth16IntermediateValuesBatch(Vec<(String, usize)>),
 }
 
 const VAL_SEPARATOR: char = '|';
@@ -37,6 +39,16 @@ impl From<CommitmentMessageId> for String {
                     VAL_SEPARATOR, variable_name, VAL_SEPARATOR, size
                 )
             }
+            CommitmentMessageId::Groth16IntermediateValuesBatch(variables) => {
+                let mut s = String::from("Groth16IntermediateValuesBatch");
+                for (variable_name, size) in variables {
+                    s.push_str(&format!(
+                        "{}{}{}{}",
+                        VAL_SEPARATOR, variable_name, VAL_SEPARATOR, size
+                    ));
+                }
+                s
+            }
             _ => id.to_string(),
         }
     }
@@ -63,6 +75,29 @@ impl TryFrom<String> for CommitmentMessageId {
                     variable_name,
                     size,
                 )));
+            } else if s.starts_with(&format!("Groth16IntermediateValuesBatch{}", VAL_SEPARATOR)) {
+                let parts: Vec<_> = s.split(VAL_SEPARATOR).skip(1).collect();
+                if parts.is_empty() || parts.len() % 2 != 0 {
+                    return Err(format!(
+                        "Invalid Groth16IntermediateValuesBatch format: {}",
+                        s
+                    ));
+                }
+                let variables = parts
+                    .chunks(2)
+                    .map(|variable| {
+                        variable[1]
+                            .parse::<usize>()
+                            .map(|size| (variable[0].to_string(), size))
+                            .map_err(|e| {
+                                format!("Invalid size in Groth16IntermediateValuesBatch: {}", e)
+                            })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                return Ok(CommitmentMessageId::Groth16IntermediateValuesBatch(
+                    variables,
+                ));
             }
         }
 
@@ -97,13 +132,24 @@ impl CommitmentMessageId {
         ]);
 
         // maybe variable cache is more efficient
-        let all_variables = BridgeAssigner::default().all_intermediate_variables();
+        let (all_variables, batches) =
+            BridgeAssigner::default().all_intermediate_variables_with_batches();
 
-        // split variable to different connectors
+        // the hashed results of a segment are committed in batches, the others variable by variable
         for (v, size) in all_variables {
+            if batches.iter().flatten().all(|(name, _)| *name != v) {
+                commitment_map.insert(
+                    CommitmentMessageId::Groth16IntermediateValues((v, size)),
+                    WinternitzSecret::new(size),
+                );
+            }
+        }
+        for batch in batches {
+            let message_sizes: Vec<usize> = batch.iter().map(|(_, size)| *size).collect();
             commitment_map.insert(
-                CommitmentMessageId::Groth16IntermediateValues((v, size)),
-                WinternitzSecret::new(size),
+                CommitmentMessageId::Groth16IntermediateValuesBatch(batch),
+                WinternitzSecret::new_batch(&message_sizes)
+                    .expect("intermediate variable batches fit in a single key"),
             );
         }
 
@@ -138,6 +184,13 @@ mod tests {
                 )),
                 "test",
             ),
+            (
+                CommitmentMessageId::Groth16IntermediateValuesBatch(vec![
+                    ("F_10_mul_c_1p0c".to_string(), 20),
+                    ("F_18_mul_ca0_a1 * b0_b1".to_string(), 16),
+                ]),
+                "test",
+            ),
         ]);
 
         let json = serialize(&messages);
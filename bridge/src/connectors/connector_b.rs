@@ -18,7 +18,9 @@ use serde::{Deserialize, Serialize};
 use crate::{
     commitments::CommitmentMessageId,
     constants::START_TIME_MESSAGE_LENGTH,
-    superblock::{extract_superblock_ts_from_header, SUPERBLOCK_MESSAGE_LENGTH},
+    superblock::{
+        extract_superblock_ts_from_header, SUPERBLOCK_MEASUREMENT_PERIOD, SUPERBLOCK_MESSAGE_LENGTH,
+    },
     utils::{sb_hash_from_bytes, sb_hash_from_nibbles, H256},
 };
 
@@ -70,7 +72,6 @@ impl ConnectorB {
     }
 
     fn generate_taproot_leaf_2_script(&self) -> ScriptBuf {
-        const TWO_WEEKS_IN_SECONDS: u32 = 60 * 60 * 24 * 14;
         let superblock_hash_public_key =
             &self.commitment_public_keys[&CommitmentMessageId::SuperblockHash];
         let start_time_public_key = &self.commitment_public_keys[&CommitmentMessageId::StartTime];
@@ -103,7 +104,7 @@ impl ConnectorB {
             OP_GREATERTHAN OP_VERIFY        // Stack: SB' SB'.time start_time | Altstack: SB.hash
 
             // SB'.time < start_time + 2 weeks
-            { TWO_WEEKS_IN_SECONDS } OP_ADD // Stack: SB' SB'.time (start_time + 2 weeks) | Altstack: SB.hash
+            { SUPERBLOCK_MEASUREMENT_PERIOD } OP_ADD // Stack: SB' SB'.time (start_time + 2 weeks) | Altstack: SB.hash
             OP_LESSTHAN OP_VERIFY           // Stack: SB' | Altstack: SB.hash
 
             // Calculate SB' hash
@@ -278,7 +279,7 @@ mod tests {
             OP_GREATERTHAN OP_VERIFY        // Stack: SB' SB'.time start_time | Altstack: SB.hash
 
             // SB'.time < start_time + 2 weeks
-            { TWO_WEEKS_IN_SECONDS } OP_ADD // Stack: SB' SB'.time (start_time + 2 weeks) | Altstack: SB.hash
+            { SUPERBLOCK_MEASUREMENT_PERIOD } OP_ADD // Stack: SB' SB'.time (start_time + 2 weeks) | Altstack: SB.hash
             OP_LESSTHAN OP_VERIFY           // Stack: SB' | Altstack: SB.hash
 
             // Calculate SB' hash
@@ -0,0 +1,193 @@
+use bitcoin::{
+    taproot::{TaprootBuilder, TaprootSpendInfo},
+    Address, Network, ScriptBuf, TxIn, XOnlyPublicKey,
+};
+use bitvm::{
+    bisection::{
+        protocol::BisectionConfig,
+        scripts::{commit_leaves, response_leaves, BisectionPublicKeys},
+        TraceHash,
+    },
+    treepp::Script,
+};
+use secp256k1::SECP256K1;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    super::{scripts::UNSPENDABLE_TAPROOT_PUBLIC_KEY, transactions::base::Input},
+    base::*,
+};
+
+/// Output of a move of a bisection game, the last leaf being the timeout of the party to move
+#[derive(Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct ConnectorBisection {
+    pub network: Network,
+    pub num_blocks_timelock: u32,
+    pub lock_scripts_bytes: Vec<Vec<u8>>, // using primitive type for binary serialization, convert to ScriptBuf when using it
+}
+
+impl ConnectorBisection {
+    /// The output spent by the commitment of the operator for the round
+    pub fn new_for_commit(
+        network: Network,
+        config: &BisectionConfig,
+        public_keys: &BisectionPublicKeys,
+        round: u32,
+        initial_state: &TraceHash,
+        operator_taproot_public_key: &XOnlyPublicKey,
+        challenger_taproot_public_key: &XOnlyPublicKey,
+    ) -> Self {
+        Self::from_leaves(
+            network,
+            config,
+            commit_leaves(
+                config,
+                public_keys,
+                round,
+                initial_state,
+                operator_taproot_public_key,
+                challenger_taproot_public_key,
+            ),
+        )
+    }
+
+    /// The output of the commitment of the operator for the round, spent by the challenger
+    /// The step scripts are used after the last round only, the script at index `i` disproving
+    /// step `i`.
+    pub fn new_for_response(
+        network: Network,
+        config: &BisectionConfig,
+        public_keys: &BisectionPublicKeys,
+        round: u32,
+        step_scripts: &[Script],
+        operator_taproot_public_key: &XOnlyPublicKey,
+        challenger_taproot_public_key: &XOnlyPublicKey,
+    ) -> Self {
+        Self::from_leaves(
+            network,
+            config,
+            response_leaves(
+                config,
+                public_keys,
+                round,
+                step_scripts,
+                operator_taproot_public_key,
+                challenger_taproot_public_key,
+            ),
+        )
+    }
+
+    fn from_leaves(network: Network, config: &BisectionConfig, leaves: Vec<Script>) -> Self {
+        ConnectorBisection {
+            network,
+            num_blocks_timelock: config.timeout,
+            lock_scripts_bytes: leaves
+                .into_iter()
+                .map(|leaf| leaf.compile().to_bytes())
+                .collect(),
+        }
+    }
+
+    pub fn timeout_leaf_index(&self) -> u32 { self.lock_scripts_bytes.len() as u32 - 1 }
+}
+
+impl TaprootConnector for ConnectorBisection {
+    fn generate_taproot_leaf_script(&self, leaf_index: u32) -> ScriptBuf {
+        let index = leaf_index as usize;
+        if index >= self.lock_scripts_bytes.len() {
+            panic!("Invalid leaf index.")
+        }
+        ScriptBuf::from_bytes(self.lock_scripts_bytes[index].clone())
+    }
+
+    fn generate_taproot_leaf_tx_in(&self, leaf_index: u32, input: &Input) -> TxIn {
+        if leaf_index as usize >= self.lock_scripts_bytes.len() {
+            panic!("Invalid leaf index.")
+        }
+        if leaf_index == self.timeout_leaf_index() {
+            generate_timelock_tx_in(input, self.num_blocks_timelock)
+        } else {
+            generate_default_tx_in(input)
+        }
+    }
+
+    fn generate_taproot_spend_info(&self) -> TaprootSpendInfo {
+        let script_weights = self
+            .lock_scripts_bytes
+            .iter()
+            .map(|b| (1, ScriptBuf::from_bytes(b.clone())));
+
+        TaprootBuilder::with_huffman_tree(script_weights)
+            .expect("Unable to add bisection leaves")
+            .finalize(SECP256K1, *UNSPENDABLE_TAPROOT_PUBLIC_KEY)
+            .expect("Unable to finalize bisection connector taproot")
+    }
+
+    fn generate_taproot_address(&self) -> Address {
+        Address::p2tr_tweaked(
+            self.generate_taproot_spend_info().output_key(),
+            self.network,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{Amount, Network, OutPoint, Sequence};
+    use bitvm::{
+        bisection::scripts::{
+            generate_challenger_secrets, generate_operator_secrets, generate_step_secret,
+        },
+        treepp::script,
+    };
+
+    use super::*;
+    use crate::constants::NUM_BLOCKS_PER_DAY;
+
+    #[test]
+    fn test_bisection_connector_leaves() {
+        let config = BisectionConfig::new(9, 3, NUM_BLOCKS_PER_DAY);
+        let public_keys = BisectionPublicKeys::new(
+            &generate_operator_secrets(&config),
+            &generate_step_secret(),
+            &generate_challenger_secrets(&config),
+        );
+        let key = *UNSPENDABLE_TAPROOT_PUBLIC_KEY;
+
+        let commit = ConnectorBisection::new_for_commit(
+            Network::Regtest,
+            &config,
+            &public_keys,
+            1,
+            &[0; 20],
+            &key,
+            &key,
+        );
+        assert_eq!(commit.timeout_leaf_index(), 1);
+
+        let step_scripts = vec![script! { OP_TRUE }; 2];
+        let response = ConnectorBisection::new_for_response(
+            Network::Regtest,
+            &config,
+            &public_keys,
+            config.rounds(),
+            &step_scripts,
+            &key,
+            &key,
+        );
+        // the disprove leaves, the equivocation leaves and the timeout
+        assert_eq!(response.timeout_leaf_index(), 2 + config.arity);
+
+        let input = Input {
+            outpoint: OutPoint::null(),
+            amount: Amount::ZERO,
+        };
+        let tx_in = response.generate_taproot_leaf_tx_in(response.timeout_leaf_index(), &input);
+        assert_eq!(tx_in.sequence, Sequence(NUM_BLOCKS_PER_DAY));
+        assert_eq!(
+            response.generate_taproot_leaf_tx_in(0, &input).sequence,
+            Sequence::MAX
+        );
+        response.generate_taproot_address();
+    }
+}
@@ -30,7 +30,7 @@ use serde::{
 
 use bitvm::{
     chunker::{
-        assigner::BridgeAssigner,
+        assigner::{BatchError, BridgeAssigner},
         chunk_groth16_verifier::groth16_verify_to_segments,
         common::RawWitness,
         disprove_execution::{disprove_exec, RawProof},
@@ -198,21 +198,8 @@ impl ConnectorC {
         commit_2_witness: Vec<RawWitness>,
         vk: &ZkProofVerifyingKey,
     ) -> Result<(usize, RawWitness), Error> {
-        let pks = self
-            .commitment_public_keys
-            .clone()
-            .into_iter()
-            .map(|(k, v)| {
-                (
-                    match k {
-                        CommitmentMessageId::Groth16IntermediateValues((name, _)) => name,
-                        _ => String::new(),
-                    },
-                    v,
-                )
-            })
-            .collect();
-        let mut assigner = BridgeAssigner::new_watcher(pks);
+        let mut assigner = new_watcher_assigner(&self.commitment_public_keys)
+            .map_err(|err| Error::Chunker(ChunkerError::InvalidBatch(err)))?;
         // merge commit1 and commit2
         disprove_exec(
             &mut assigner,
@@ -274,25 +261,35 @@ impl TaprootConnector for ConnectorC {
     }
 }
 
+/// The public key of a batch is shared by all of its variables.
+fn new_watcher_assigner(
+    commits_public_keys: &BTreeMap<CommitmentMessageId, WinternitzPublicKey>,
+) -> Result<BridgeAssigner, BatchError> {
+    let mut batches = vec![];
+    let mut pks = BTreeMap::new();
+    for (message, pk) in commits_public_keys {
+        match message {
+            CommitmentMessageId::Groth16IntermediateValues((name, _)) => {
+                pks.insert(name.clone(), pk.clone());
+            }
+            CommitmentMessageId::Groth16IntermediateValuesBatch(batch) => {
+                for (name, _) in batch.iter() {
+                    pks.insert(name.clone(), pk.clone());
+                }
+                batches.push(batch.clone());
+            }
+            _ => (),
+        }
+    }
+    BridgeAssigner::new_watcher(pks).with_batches(batches)
+}
+
 pub fn generate_assert_leaves(
     commits_public_keys: &BTreeMap<CommitmentMessageId, WinternitzPublicKey>,
 ) -> Vec<Vec<u8>> {
     println!("Generating new lock scripts...");
-    // hash map to btree map
-    let pks = commits_public_keys
-        .clone()
-        .into_iter()
-        .map(|(k, v)| {
-            (
-                match k {
-                    CommitmentMessageId::Groth16IntermediateValues((name, _)) => name,
-                    _ => String::new(),
-                },
-                v,
-            )
-        })
-        .collect();
-    let mut bridge_assigner = BridgeAssigner::new_watcher(pks);
+    let mut bridge_assigner =
+        new_watcher_assigner(commits_public_keys).expect("Invalid batch of intermediate values");
     let default_proof = RawProof::default(); // mock a default proof to generate scripts
 
     let segments = groth16_verify_to_segments(
@@ -6,7 +6,8 @@ use bitcoin::{
 };
 use bitcoin_script::script;
 use bitvm::signatures::signing_winternitz::{
-    winternitz_message_checksig_verify, WinternitzPublicKey,
+    generate_winternitz_batch_checksig_leave_hashes, winternitz_message_checksig_verify,
+    WinternitzPublicKey,
 };
 use secp256k1::SECP256K1;
 use serde::{Deserialize, Serialize};
@@ -52,6 +53,21 @@ impl TaprootConnector for ConnectorE {
                         .compile(),
                     );
                 }
+                CommitmentMessageId::Groth16IntermediateValuesBatch(variables) => {
+                    let message_sizes: Vec<usize> =
+                        variables.iter().map(|(_, size)| *size).collect();
+                    script = script.push_script(
+                        script! {
+                            {generate_winternitz_batch_checksig_leave_hashes(pk, &message_sizes)
+                                .expect("Invalid batch of intermediate values")}
+                            for _ in 0..message_sizes.iter().sum::<usize>() {
+                                OP_DROP
+                            }
+                            OP_TRUE
+                        }
+                        .compile(),
+                    );
+                }
                 _ => {
                     panic!("connector e only reveal intermediate value of groth16")
                 }
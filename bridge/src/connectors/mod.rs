@@ -8,6 +8,7 @@ pub mod connector_5;
 pub mod connector_6;
 pub mod connector_a;
 pub mod connector_b;
+pub mod connector_bisection;
 pub mod connector_c;
 pub mod connector_d;
 pub mod connector_e;
@@ -10,6 +10,13 @@ pub const NUM_BLOCKS_PER_WEEK: u32 = NUM_BLOCKS_PER_DAY * 7;
 pub const NUM_BLOCKS_PER_2_WEEKS: u32 = NUM_BLOCKS_PER_WEEK * 2;
 pub const NUM_BLOCKS_PER_4_WEEKS: u32 = NUM_BLOCKS_PER_WEEK * 4;
 
+// Confirmations after which a transaction is not expected to be reorged out
+pub const FINALITY_DEPTH_MAINNET: u32 = 6;
+// the test networks reset their difficulty and see deeper reorgs than mainnet
+pub const FINALITY_DEPTH_TESTNET: u32 = 12;
+// reorgs are only simulated, a few blocks deep
+pub const FINALITY_DEPTH_REGTEST: u32 = 3;
+
 pub const N_SEQUENCE_FOR_LOCK_TIME: u32 = 0xFFFFFFFE; // The nSequence field must be set to less than 0xffffffff, usually 0xffffffff-1 to avoid confilcts with relative timelocks.
 
 // /// Commitment message parameters. Hardcoded for 4-bit digits.
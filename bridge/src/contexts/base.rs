@@ -5,11 +5,15 @@ use bitcoin::{
 use musig2::{secp::Point, KeyAggContext};
 use secp256k1::SECP256K1;
 
+use crate::transactions::signing_frost::FrostKeyPackage;
+
 pub trait BaseContext {
     fn network(&self) -> Network;
     fn n_of_n_public_keys(&self) -> &Vec<PublicKey>;
     fn n_of_n_public_key(&self) -> &PublicKey;
     fn n_of_n_taproot_public_key(&self) -> &XOnlyPublicKey;
+    // Key package of the verifier set when it signs with a t-of-n FROST group key
+    fn frost_key_package(&self) -> Option<&FrostKeyPackage>;
 }
 
 pub fn generate_keys_from_secret(network: Network, secret: &str) -> (Keypair, PublicKey) {
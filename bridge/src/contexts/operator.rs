@@ -1,6 +1,7 @@
 use bitcoin::{key::Keypair, Network, PublicKey, XOnlyPublicKey};
 
 use super::base::{generate_keys_from_secret, generate_n_of_n_public_key, BaseContext};
+use crate::transactions::signing_frost::FrostKeyPackage;
 
 pub struct OperatorContext {
     pub network: Network,
@@ -12,6 +13,8 @@ pub struct OperatorContext {
     pub n_of_n_public_keys: Vec<PublicKey>,
     pub n_of_n_public_key: PublicKey,
     pub n_of_n_taproot_public_key: XOnlyPublicKey,
+
+    pub frost_key_package: Option<FrostKeyPackage>,
 }
 
 impl BaseContext for OperatorContext {
@@ -19,6 +22,7 @@ impl BaseContext for OperatorContext {
     fn n_of_n_public_keys(&self) -> &Vec<PublicKey> { &self.n_of_n_public_keys }
     fn n_of_n_public_key(&self) -> &PublicKey { &self.n_of_n_public_key }
     fn n_of_n_taproot_public_key(&self) -> &XOnlyPublicKey { &self.n_of_n_taproot_public_key }
+    fn frost_key_package(&self) -> Option<&FrostKeyPackage> { self.frost_key_package.as_ref() }
 }
 
 impl OperatorContext {
@@ -37,6 +41,26 @@ impl OperatorContext {
             n_of_n_public_keys: n_of_n_public_keys.to_owned(),
             n_of_n_public_key,
             n_of_n_taproot_public_key,
+
+            frost_key_package: None,
         }
     }
+
+    /// Context for a verifier set signing with a t-of-n group key instead of the n-of-n one
+    pub fn new_with_frost_key_package(
+        network: Network,
+        operator_secret: &str,
+        frost_key_package: &FrostKeyPackage,
+    ) -> Self {
+        let mut context = Self::new(
+            network,
+            operator_secret,
+            &frost_key_package.verifier_public_keys(),
+        );
+        context.n_of_n_public_key = frost_key_package.group_public_key;
+        context.n_of_n_taproot_public_key = frost_key_package.group_taproot_public_key();
+        context.frost_key_package = Some(frost_key_package.clone());
+
+        context
+    }
 }
@@ -1,6 +1,7 @@
-use bitcoin::{key::Keypair, Network, PublicKey, XOnlyPublicKey};
+use bitcoin::{key::Keypair, secp256k1::SecretKey, Network, PublicKey, XOnlyPublicKey};
 
 use super::base::{generate_keys_from_secret, generate_n_of_n_public_key, BaseContext};
+use crate::transactions::signing_frost::FrostKeyPackage;
 
 pub struct VerifierContext {
     pub network: Network,
@@ -11,6 +12,9 @@ pub struct VerifierContext {
     pub n_of_n_public_keys: Vec<PublicKey>,
     pub n_of_n_public_key: PublicKey,
     pub n_of_n_taproot_public_key: XOnlyPublicKey,
+
+    pub frost_key_package: Option<FrostKeyPackage>,
+    pub frost_signing_share: Option<SecretKey>,
 }
 
 impl BaseContext for VerifierContext {
@@ -18,6 +22,7 @@ impl BaseContext for VerifierContext {
     fn n_of_n_public_keys(&self) -> &Vec<PublicKey> { &self.n_of_n_public_keys }
     fn n_of_n_public_key(&self) -> &PublicKey { &self.n_of_n_public_key }
     fn n_of_n_taproot_public_key(&self) -> &XOnlyPublicKey { &self.n_of_n_taproot_public_key }
+    fn frost_key_package(&self) -> Option<&FrostKeyPackage> { self.frost_key_package.as_ref() }
 }
 
 impl VerifierContext {
@@ -35,6 +40,29 @@ impl VerifierContext {
             n_of_n_public_keys: n_of_n_public_keys.to_owned(),
             n_of_n_public_key,
             n_of_n_taproot_public_key,
+
+            frost_key_package: None,
+            frost_signing_share: None,
         }
     }
+
+    /// Context of a verifier signing with its share of the t-of-n group key of the verifier set
+    pub fn new_with_frost_key_package(
+        network: Network,
+        verifier_secret: &str,
+        frost_key_package: &FrostKeyPackage,
+        frost_signing_share: &SecretKey,
+    ) -> Self {
+        let mut context = Self::new(
+            network,
+            verifier_secret,
+            &frost_key_package.verifier_public_keys(),
+        );
+        context.n_of_n_public_key = frost_key_package.group_public_key;
+        context.n_of_n_taproot_public_key = frost_key_package.group_taproot_public_key();
+        context.frost_key_package = Some(frost_key_package.clone());
+        context.frost_signing_share = Some(*frost_signing_share);
+
+        context
+    }
 }
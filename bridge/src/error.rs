@@ -1,7 +1,8 @@
 use super::commitments::CommitmentMessageId;
 use super::graphs::base::GraphId;
 use super::transactions::{base::BaseTransaction, pre_signed::PreSignedTransaction};
-use bitcoin::Txid;
+use bitcoin::{Amount, OutPoint, PublicKey, Txid};
+use bitvm::chunker::assigner::BatchError;
 use std::fmt;
 use strum::Display;
 
@@ -9,9 +10,14 @@ use strum::Display;
 pub enum ClientError {
     NoUserContextDefined,
     OperatorContextNotDefined,
+    VerifierContextNotDefined,
     ZkProofVerifyingKeyNotDefined,
     PegInGraphNotFound(GraphId),
     PegOutGraphNotFound(GraphId),
+    HeavierSuperblockNotFound(GraphId),
+    SuperblockNotTracked(GraphId),
+    FrostSigningShareNotDefined,
+    InvalidFrostSigningShare,
 }
 
 #[derive(Debug)]
@@ -36,7 +42,11 @@ pub enum GraphError {
     PrecedingTxNotCreated(&'static str),
     PrecedingTxNotConfirmed(Vec<NamedTx>),
     PrecedingTxTimelockNotMet(NamedTx),
+    InvalidStateTransition(String, String),
     WitnessNotGenerated(CommitmentMessageId),
+    FrostKeyPackageMismatch(GraphId),
+    // Transactions of the graph the verifier failed to pre-sign, the others being signed
+    PreSigningFailed(Vec<(Txid, Error)>),
 }
 
 #[derive(Debug, Display)]
@@ -47,6 +57,10 @@ pub enum ConnectorError {
 #[derive(Debug)]
 pub enum TransactionError {
     AlreadyMined(Txid),
+    FeeRateAlreadyMet(Txid),
+    AnchorOutputNotFound(Txid),
+    OutputNotSpendable(OutPoint),
+    InsufficientFunding { available: Amount, required: Amount },
 }
 
 #[derive(Debug)]
@@ -54,18 +68,80 @@ pub enum L2Error {
     PegOutNotInitiated,
 }
 
+#[derive(Debug)]
+pub enum OperatorError {
+    NotRegistered(PublicKey),
+    Deregistered(PublicKey),
+    WithdrawalAlreadyFronted(GraphId),
+    InsufficientCollateral { available: Amount, required: Amount },
+    CollateralUnavailable(OutPoint),
+}
+
+#[derive(Debug)]
+pub enum SignatureError {
+    MissingNonces {
+        input_index: usize,
+        verifiers: Vec<PublicKey>,
+    },
+    InvalidNonces {
+        input_index: usize,
+        verifiers: Vec<PublicKey>,
+    },
+    PartialSigningFailed {
+        input_index: usize,
+        verifier: PublicKey,
+    },
+    MissingPartialSignatures {
+        input_index: usize,
+        verifiers: Vec<PublicKey>,
+    },
+    // Verifiers whose partial signatures do not verify against their keys and nonces
+    InvalidPartialSignatures {
+        input_index: usize,
+        verifiers: Vec<PublicKey>,
+    },
+    InvalidAggregatedSignature {
+        input_index: usize,
+    },
+    KeyAggregationFailed,
+    MissingFrostSigningShare(PublicKey),
+    FrostKeyGenerationFailed(frost_secp256k1_tr::Error),
+}
+
 #[derive(Debug)]
 pub enum ChunkerError {
     ValidProof,
+    InvalidBatch(BatchError),
 }
 
 #[derive(Debug)]
-pub enum Error {
+pub enum BackendError {
     Esplora(esplora_client::Error),
+    Rpc { code: i64, message: String },
+    Transport(String),
+    Rejected(String),
+}
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackendError::Esplora(e) => write!(f, "{e}"),
+            BackendError::Rpc { code, message } => write!(f, "RPC error {code}: {message}"),
+            BackendError::Transport(message) => write!(f, "Transport error: {message}"),
+            BackendError::Rejected(message) => write!(f, "Transaction rejected: {message}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Backend(BackendError),
     Client(ClientError),
     Graph(GraphError),
     Transaction(TransactionError),
     L2(L2Error),
+    Operator(OperatorError),
+    Signature(SignatureError),
     Chunker(ChunkerError),
     Other(&'static str),
 }
@@ -2,20 +2,24 @@ use std::collections::HashMap;
 
 use bitcoin::{
     policy::{DEFAULT_MIN_RELAY_TX_FEE, DUST_RELAY_TX_FEE},
-    Network, Transaction, Txid,
+    Network, PublicKey, Transaction, Txid,
 };
-use esplora_client::{AsyncClient, TxStatus};
+use esplora_client::TxStatus;
 use futures::future::join_all;
 use musig2::SecNonce;
 
 use crate::{
+    client::backend::base::BitcoinBackend,
     contexts::verifier::VerifierContext,
-    error::{Error, TransactionError},
-    transactions::base::{
-        MIN_RELAY_FEE_ASSERT_COMMIT1, MIN_RELAY_FEE_ASSERT_COMMIT2, MIN_RELAY_FEE_ASSERT_FINAL,
-        MIN_RELAY_FEE_ASSERT_INITIAL, MIN_RELAY_FEE_DISPROVE, MIN_RELAY_FEE_KICK_OFF_1,
-        MIN_RELAY_FEE_KICK_OFF_2, MIN_RELAY_FEE_PEG_IN_CONFIRM, MIN_RELAY_FEE_PEG_IN_DEPOSIT,
-        MIN_RELAY_FEE_PEG_IN_REFUND, MIN_RELAY_FEE_PEG_OUT_CONFIRM, MIN_RELAY_FEE_START_TIME,
+    error::{BackendError, Error, TransactionError},
+    transactions::{
+        base::{
+            MIN_RELAY_FEE_ASSERT_COMMIT1, MIN_RELAY_FEE_ASSERT_COMMIT2, MIN_RELAY_FEE_ASSERT_FINAL,
+            MIN_RELAY_FEE_ASSERT_INITIAL, MIN_RELAY_FEE_DISPROVE, MIN_RELAY_FEE_KICK_OFF_1,
+            MIN_RELAY_FEE_KICK_OFF_2, MIN_RELAY_FEE_PEG_IN_CONFIRM, MIN_RELAY_FEE_PEG_IN_DEPOSIT,
+            MIN_RELAY_FEE_PEG_IN_REFUND, MIN_RELAY_FEE_PEG_OUT_CONFIRM, MIN_RELAY_FEE_START_TIME,
+        },
+        fee::ANCHOR_AMOUNT,
     },
 };
 
@@ -48,7 +52,10 @@ pub const PEG_OUT_FEE: u64 = MIN_RELAY_FEE_PEG_OUT_CONFIRM // depth 0
     + MIN_RELAY_FEE_START_TIME // include START_TIME tx, spent in kickoff 1
     + MIN_RELAY_FEE_KICK_OFF_2 // depth 2
     + MIN_RELAY_FEE_ASSERT_SET // depth 3
-    + MIN_RELAY_FEE_DISPROVE; // depth 4
+    + MIN_RELAY_FEE_DISPROVE // depth 4
+    + PEG_OUT_ANCHOR_AMOUNT;
+// anchors of kick off 1, kick off 2, assert initial and assert final for CPFP
+pub const PEG_OUT_ANCHOR_AMOUNT: u64 = ANCHOR_AMOUNT * 4;
 pub const PEG_IN_FEE: u64 =
     MIN_RELAY_FEE_PEG_IN_DEPOSIT + max(MIN_RELAY_FEE_PEG_IN_CONFIRM, MIN_RELAY_FEE_PEG_IN_REFUND);
 
@@ -85,33 +92,36 @@ pub trait BaseGraph {
         &mut self,
         verifier_context: &VerifierContext,
         secret_nonces: &HashMap<Txid, HashMap<usize, SecNonce>>,
-    );
+    ) -> Result<(), Error>;
+    /// Evicts the nonces and partial signatures of a verifier identified as invalid, for it to
+    /// sign again with fresh nonces, and blocks the graph until all the signers signed it again
+    fn evict_verifier(&mut self, verifier_public_key: &PublicKey);
 }
 
 pub const fn max(a: u64, b: u64) -> u64 { [a, b][(a < b) as usize] }
 
-pub async fn get_block_height(client: &AsyncClient) -> Result<u32, Error> {
+pub async fn get_block_height(client: &dyn BitcoinBackend) -> Result<u32, Error> {
     match client.get_height().await {
         Ok(height) => Ok(height),
-        Err(e) => Err(Error::Esplora(e)),
+        Err(e) => Err(Error::Backend(e)),
     }
 }
 
-pub async fn verify_if_not_mined(client: &AsyncClient, txid: Txid) -> Result<(), Error> {
+pub async fn verify_if_not_mined(client: &dyn BitcoinBackend, txid: Txid) -> Result<(), Error> {
     match is_confirmed(client, txid).await {
         Ok(false) => Ok(()),
         Ok(true) => Err(Error::Transaction(TransactionError::AlreadyMined(txid))),
-        Err(e) => Err(Error::Esplora(e)),
+        Err(e) => Err(Error::Backend(e)),
     }
 }
 
-pub async fn is_confirmed(client: &AsyncClient, txid: Txid) -> Result<bool, esplora_client::Error> {
+pub async fn is_confirmed(client: &dyn BitcoinBackend, txid: Txid) -> Result<bool, BackendError> {
     let tx_status = client.get_tx_status(&txid).await;
     tx_status.map(|x| x.confirmed)
 }
 
 pub async fn broadcast_and_verify(
-    client: &AsyncClient,
+    client: &dyn BitcoinBackend,
     transaction: &Transaction,
 ) -> Result<&'static str, Error> {
     let txid = transaction.compute_txid();
@@ -124,13 +134,13 @@ pub async fn broadcast_and_verify(
 
     match (tx_result, is_confirmed(client, txid).await) {
         (Ok(_), _) | (Err(_), Ok(true)) => Ok("Tx mined successfully."),
-        (Err(e), _) => Err(Error::Esplora(e)),
+        (Err(e), _) => Err(Error::Backend(e)),
     }
 }
 
 pub async fn get_tx_statuses(
-    client: &AsyncClient,
+    client: &dyn BitcoinBackend,
     txids: &[Txid],
-) -> Vec<Result<TxStatus, esplora_client::Error>> {
+) -> Vec<Result<TxStatus, BackendError>> {
     join_all(txids.iter().map(|txid| client.get_tx_status(txid))).await
 }
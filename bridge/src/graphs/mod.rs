@@ -1,3 +1,6 @@
 pub mod base;
+pub mod monitor;
+pub mod operator_set;
 pub mod peg_in;
 pub mod peg_out;
+pub mod state;
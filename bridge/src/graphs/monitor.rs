@@ -0,0 +1,130 @@
+use bitcoin::{BlockHash, Network};
+use std::collections::BTreeMap;
+
+use crate::{
+    client::backend::base::BitcoinBackend,
+    constants::{FINALITY_DEPTH_MAINNET, FINALITY_DEPTH_REGTEST, FINALITY_DEPTH_TESTNET},
+    error::Error,
+};
+
+/// Confirmations after which the transactions of the network are final
+pub fn finality_depth(network: Network) -> u32 {
+    match network {
+        Network::Bitcoin => FINALITY_DEPTH_MAINNET,
+        Network::Regtest => FINALITY_DEPTH_REGTEST,
+        _ => FINALITY_DEPTH_TESTNET, // Testnet, Signet
+    }
+}
+
+/// Tracks the tip of the source chain to find the confirmation depth of the graph transactions and
+/// the reorgs that invalidate their confirmations
+///
+/// The hashes of the blocks that are not final yet are kept, a reorg is detected when one of them
+/// changes and reported with the height of the first block that changed. Reorgs deeper than the
+/// finality depth are not detected, transactions confirmed at that depth being final.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChainMonitor {
+    finality_depth: u32,
+    blocks: BTreeMap<u32, BlockHash>,
+}
+
+impl ChainMonitor {
+    pub fn new(network: Network) -> Self { Self::with_finality_depth(finality_depth(network)) }
+
+    pub fn with_finality_depth(finality_depth: u32) -> Self {
+        assert!(finality_depth > 0, "The finality depth must be at least 1");
+        ChainMonitor {
+            finality_depth,
+            blocks: BTreeMap::new(),
+        }
+    }
+
+    pub fn finality_depth(&self) -> u32 { self.finality_depth }
+
+    pub fn tip_height(&self) -> Option<u32> { self.blocks.keys().next_back().copied() }
+
+    /// Number of confirmations of a transaction confirmed at the block height, including its block
+    pub fn confirmations(&self, block_height: u32) -> u32 {
+        self.tip_height().map_or(0, |tip_height| {
+            (tip_height + 1).saturating_sub(block_height)
+        })
+    }
+
+    pub fn is_final(&self, block_height: u32) -> bool {
+        self.confirmations(block_height) >= self.finality_depth
+    }
+
+    /// Highest block height whose transactions are final, `None` before the first update or while
+    /// the chain is shorter than the finality depth
+    pub fn finalized_height(&self) -> Option<u32> {
+        self.tip_height()
+            .and_then(|tip_height| (tip_height + 1).checked_sub(self.finality_depth))
+    }
+
+    /// Fetches the blocks that are not final yet, returns the height of the first block changed by
+    /// a reorg since the last update
+    pub async fn update(&mut self, client: &dyn BitcoinBackend) -> Result<Option<u32>, Error> {
+        let tip_height = client.get_height().await.map_err(Error::Backend)?;
+        let mut blocks = BTreeMap::new();
+        for height in (tip_height + 1).saturating_sub(self.finality_depth)..=tip_height {
+            let hash = client
+                .get_block_hash(height)
+                .await
+                .map_err(Error::Backend)?;
+            blocks.insert(height, hash);
+        }
+        Ok(self.apply_blocks(blocks))
+    }
+
+    /// Replaces the tracked blocks with the latest blocks of the chain, returns the height of the
+    /// first tracked block that is missing or has another hash
+    pub fn apply_blocks(&mut self, blocks: BTreeMap<u32, BlockHash>) -> Option<u32> {
+        let lowest_height = blocks.keys().next().copied();
+        let reorg_height = self
+            .blocks
+            .iter()
+            .filter(|(height, _)| lowest_height.is_some_and(|lowest| **height >= lowest))
+            .find(|(height, hash)| blocks.get(height) != Some(hash))
+            .map(|(height, _)| *height);
+        self.blocks = blocks;
+        reorg_height
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::hashes::Hash;
+
+    use super::*;
+
+    fn blocks(heights: std::ops::RangeInclusive<u32>, fork: u8) -> BTreeMap<u32, BlockHash> {
+        heights
+            .map(|height| {
+                let mut bytes = [fork; 32];
+                bytes[..4].copy_from_slice(&height.to_le_bytes());
+                (height, BlockHash::from_byte_array(bytes))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_chain_monitor_detects_reorg() {
+        let mut monitor = ChainMonitor::with_finality_depth(3);
+        assert_eq!(monitor.finalized_height(), None);
+        assert_eq!(monitor.apply_blocks(blocks(98..=100, 0)), None);
+        assert_eq!(monitor.finalized_height(), Some(98));
+        assert_eq!(monitor.confirmations(100), 1);
+        assert!(monitor.is_final(98));
+        assert!(!monitor.is_final(99));
+
+        // the chain is extended
+        assert_eq!(monitor.apply_blocks(blocks(99..=101, 0)), None);
+        // blocks 100 and 101 are replaced by a fork
+        let mut fork = blocks(100..=101, 1);
+        fork.extend(blocks(99..=99, 0));
+        assert_eq!(monitor.apply_blocks(fork), Some(100));
+        // the fork is replaced by a shorter chain
+        assert_eq!(monitor.apply_blocks(blocks(98..=100, 0)), Some(100));
+        assert_eq!(monitor.tip_height(), Some(100));
+    }
+}
@@ -0,0 +1,419 @@
+use bitcoin::{
+    consensus::encode::serialize,
+    hashes::{sha256, Hash},
+    Amount, OutPoint, PublicKey, ScriptBuf, XOnlyPublicKey,
+};
+use musig2::secp256k1::{schnorr::Signature, Message};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    client::backend::base::BitcoinBackend,
+    contexts::operator::OperatorContext,
+    error::{Error, OperatorError},
+    scripts::generate_pay_to_pubkey_script,
+    transactions::base::Input,
+};
+
+use super::peg_out::PegOutGraph;
+
+#[derive(Serialize, Deserialize, Eq, PartialEq, Clone, Debug)]
+pub struct OperatorRegistration {
+    pub operator_public_key: PublicKey,
+    pub operator_taproot_public_key: XOnlyPublicKey,
+    // Unspent output of the operator holding the funds it fronts the withdrawals with, until
+    // take 1 or take 2 reimburses them
+    pub collateral_outpoint: OutPoint,
+    pub collateral: Amount,
+    pub active: bool,
+    // Incremented on every update, so that merging keeps the latest registration
+    pub revision: u32,
+    // Signature of the operator over the registration, so that only the operator can update it
+    pub signature: Signature,
+}
+
+impl OperatorRegistration {
+    fn new(context: &OperatorContext, collateral: Input, active: bool, revision: u32) -> Self {
+        let message = registration_message(
+            &context.operator_public_key,
+            &collateral.outpoint,
+            collateral.amount,
+            active,
+            revision,
+        );
+
+        OperatorRegistration {
+            operator_public_key: context.operator_public_key,
+            operator_taproot_public_key: context.operator_taproot_public_key,
+            collateral_outpoint: collateral.outpoint,
+            collateral: collateral.amount,
+            active,
+            revision,
+            signature: context.operator_keypair.sign_schnorr(message),
+        }
+    }
+
+    /// Whether the registration is signed by its operator
+    pub fn verify_signature(&self) -> bool {
+        self.operator_taproot_public_key == XOnlyPublicKey::from(self.operator_public_key)
+            && self
+                .signature
+                .verify(
+                    &registration_message(
+                        &self.operator_public_key,
+                        &self.collateral_outpoint,
+                        self.collateral,
+                        self.active,
+                        self.revision,
+                    ),
+                    &self.operator_taproot_public_key,
+                )
+                .is_ok()
+    }
+}
+
+fn registration_message(
+    operator_public_key: &PublicKey,
+    collateral_outpoint: &OutPoint,
+    collateral: Amount,
+    active: bool,
+    revision: u32,
+) -> Message {
+    let mut data = operator_public_key.to_bytes();
+    data.extend(serialize(collateral_outpoint));
+    data.extend(collateral.to_sat().to_le_bytes());
+    data.push(active as u8);
+    data.extend(revision.to_le_bytes());
+
+    Message::from_digest(sha256::Hash::hash(&data).to_byte_array())
+}
+
+/// Operators that create peg-out graphs for the new peg-ins and front their withdrawals
+///
+/// Each registered operator gets its own peg-out graph against the peg-in confirm output, and the
+/// operator designated by the withdrawal event may front it if it is active, no other operator
+/// fronted a withdrawal of the same peg-in and its collateral not locked by withdrawals awaiting
+/// reimbursement covers the amount. The collateral must be an unspent output paying the operator,
+/// and the registrations are signed by their operators. Deregistered operators keep their
+/// existing graphs but are left out of the new peg-ins.
+#[derive(Serialize, Deserialize, Eq, PartialEq, Clone, Debug, Default)]
+pub struct OperatorSet {
+    operators: Vec<OperatorRegistration>,
+}
+
+impl OperatorSet {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn operators(&self) -> &[OperatorRegistration] { &self.operators }
+
+    pub fn operator(&self, operator_public_key: &PublicKey) -> Option<&OperatorRegistration> {
+        self.operators
+            .iter()
+            .find(|x| x.operator_public_key == *operator_public_key)
+    }
+
+    /// Operators expected to create a peg-out graph for a new peg-in, in registration order
+    pub fn active_operator_public_keys(&self) -> Vec<PublicKey> {
+        self.operators
+            .iter()
+            .filter(|x| x.active)
+            .map(|x| x.operator_public_key)
+            .collect()
+    }
+
+    /// Registers the operator with its collateral output, or reactivates it with the new one
+    pub fn register(&mut self, context: &OperatorContext, collateral: Input) {
+        match self
+            .operators
+            .iter_mut()
+            .find(|x| x.operator_public_key == context.operator_public_key)
+        {
+            Some(registration) => {
+                *registration =
+                    OperatorRegistration::new(context, collateral, true, registration.revision + 1);
+            }
+            None => self
+                .operators
+                .push(OperatorRegistration::new(context, collateral, true, 0)),
+        }
+    }
+
+    pub fn deregister(&mut self, context: &OperatorContext) -> Result<(), Error> {
+        let registration = self
+            .operators
+            .iter_mut()
+            .find(|x| x.operator_public_key == context.operator_public_key)
+            .ok_or(Error::Operator(OperatorError::NotRegistered(
+                context.operator_public_key,
+            )))?;
+        *registration = OperatorRegistration::new(
+            context,
+            Input {
+                outpoint: registration.collateral_outpoint,
+                amount: registration.collateral,
+            },
+            false,
+            registration.revision + 1,
+        );
+
+        Ok(())
+    }
+
+    /// Amount of the withdrawals fronted by the operator and not reimbursed yet
+    pub fn locked_collateral(
+        &self,
+        operator_public_key: &PublicKey,
+        peg_out_graphs: &[PegOutGraph],
+    ) -> Amount {
+        peg_out_graphs
+            .iter()
+            .filter(|x| {
+                x.operator_public_key() == operator_public_key
+                    && x.is_peg_out_fronted()
+                    && !x.state().state().is_reimbursed()
+            })
+            .filter_map(|x| x.peg_out_chain_event.as_ref())
+            .map(|event| event.amount)
+            .sum()
+    }
+
+    pub fn available_collateral(
+        &self,
+        operator_public_key: &PublicKey,
+        peg_out_graphs: &[PegOutGraph],
+    ) -> Amount {
+        self.operator(operator_public_key)
+            .map_or(Amount::ZERO, |registration| {
+                registration
+                    .collateral
+                    .checked_sub(self.locked_collateral(operator_public_key, peg_out_graphs))
+                    .unwrap_or(Amount::ZERO)
+            })
+    }
+
+    /// Checks that the collateral of the operator is a confirmed and unspent output paying it at
+    /// least the registered amount
+    pub async fn verify_collateral(
+        &self,
+        client: &dyn BitcoinBackend,
+        operator_public_key: &PublicKey,
+    ) -> Result<(), Error> {
+        let registration = self.operator(operator_public_key).ok_or(Error::Operator(
+            OperatorError::NotRegistered(*operator_public_key),
+        ))?;
+        let outpoint = registration.collateral_outpoint;
+        let collateral_unavailable =
+            Error::Operator(OperatorError::CollateralUnavailable(outpoint));
+
+        let script_pubkey = ScriptBuf::new_p2wsh(
+            &generate_pay_to_pubkey_script(&registration.operator_public_key).wscript_hash(),
+        );
+        let output = client
+            .get_tx(&outpoint.txid)
+            .await
+            .map_err(Error::Backend)?
+            .and_then(|tx| tx.output.get(outpoint.vout as usize).cloned());
+        if !output.is_some_and(|output| {
+            output.script_pubkey == script_pubkey && output.value >= registration.collateral
+        }) {
+            return Err(collateral_unavailable);
+        }
+
+        let status = client
+            .get_tx_status(&outpoint.txid)
+            .await
+            .map_err(Error::Backend)?;
+        let spender = client
+            .get_output_spender(&outpoint)
+            .await
+            .map_err(Error::Backend)?;
+        if !status.confirmed || spender.is_some() {
+            return Err(collateral_unavailable);
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the operator of the peg-out graph may front its withdrawal
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - Backend holding the collateral outputs of the operators
+    /// * `peg_out_graph` - Peg-out graph with the withdrawal event of the operator
+    /// * `peg_out_graphs` - All the peg-out graphs, to find the withdrawals already fronted
+    pub async fn check_can_front_withdrawal(
+        &self,
+        client: &dyn BitcoinBackend,
+        peg_out_graph: &PegOutGraph,
+        peg_out_graphs: &[PegOutGraph],
+    ) -> Result<(), Error> {
+        let operator_public_key = peg_out_graph.operator_public_key();
+
+        // the peg-in confirm output reimburses a single operator
+        if let Some(fronted_peg_out_graph) = peg_out_graphs.iter().find(|x| {
+            x.peg_in_graph_id == peg_out_graph.peg_in_graph_id
+                && x.operator_public_key() != operator_public_key
+                && x.is_peg_out_fronted()
+        }) {
+            return Err(Error::Operator(OperatorError::WithdrawalAlreadyFronted(
+                fronted_peg_out_graph.id().clone(),
+            )));
+        }
+
+        // without registered operators, the operators of the peg-out graphs front the
+        // withdrawals with their own funds
+        if self.operators.is_empty() {
+            return Ok(());
+        }
+        let registration = self.operator(operator_public_key).ok_or(Error::Operator(
+            OperatorError::NotRegistered(*operator_public_key),
+        ))?;
+        if !registration.active {
+            return Err(Error::Operator(OperatorError::Deregistered(
+                *operator_public_key,
+            )));
+        }
+        self.verify_collateral(client, operator_public_key).await?;
+
+        let Some(event) = peg_out_graph.peg_out_chain_event.as_ref() else {
+            return Ok(());
+        };
+        let available = self.available_collateral(operator_public_key, peg_out_graphs);
+        if available < event.amount {
+            return Err(Error::Operator(OperatorError::InsufficientCollateral {
+                available,
+                required: event.amount,
+            }));
+        }
+
+        Ok(())
+    }
+
+    /// Merges the registrations signed by their operators, keeping the latest revisions
+    pub fn merge(&mut self, source_operator_set: &OperatorSet) {
+        for source_registration in source_operator_set.operators.iter() {
+            if !source_registration.verify_signature() {
+                eprintln!(
+                    "Ignoring the registration of operator {} with an invalid signature",
+                    source_registration.operator_public_key
+                );
+                continue;
+            }
+            match self
+                .operators
+                .iter_mut()
+                .find(|x| x.operator_public_key == source_registration.operator_public_key)
+            {
+                Some(registration) => {
+                    if source_registration.revision > registration.revision {
+                        *registration = source_registration.clone();
+                    }
+                }
+                None => self.operators.push(source_registration.clone()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bitcoin::{Network, Txid};
+
+    use super::*;
+    use crate::{
+        contexts::base::generate_keys_from_secret,
+        graphs::base::{DEPOSITOR_SECRET, OPERATOR_SECRET, VERIFIER_0_SECRET, VERIFIER_1_SECRET},
+    };
+
+    fn operator_context(operator_secret: &str) -> OperatorContext {
+        let n_of_n_public_keys = [VERIFIER_0_SECRET, VERIFIER_1_SECRET]
+            .map(|secret| generate_keys_from_secret(Network::Regtest, secret).1);
+        OperatorContext::new(Network::Regtest, operator_secret, &n_of_n_public_keys)
+    }
+
+    fn collateral(vout: u32, sats: u64) -> Input {
+        Input {
+            outpoint: OutPoint {
+                txid: Txid::from_str(
+                    "4e254eab8a41f14f56491813a7100cebe305d84edf09488001d9dd3d180a4900",
+                )
+                .unwrap(),
+                vout,
+            },
+            amount: Amount::from_sat(sats),
+        }
+    }
+
+    #[test]
+    fn test_operator_set_registration_and_merge() {
+        let operator_0 = operator_context(OPERATOR_SECRET);
+        let operator_1 = operator_context(DEPOSITOR_SECRET);
+
+        let mut operator_set = OperatorSet::new();
+        operator_set.register(&operator_0, collateral(0, 1_000_000));
+        operator_set.register(&operator_1, collateral(1, 2_000_000));
+        assert_eq!(
+            operator_set.active_operator_public_keys(),
+            vec![
+                operator_0.operator_public_key,
+                operator_1.operator_public_key
+            ]
+        );
+        assert_eq!(
+            operator_set.available_collateral(&operator_1.operator_public_key, &[]),
+            Amount::from_sat(2_000_000)
+        );
+
+        let mut remote_operator_set = operator_set.clone();
+        remote_operator_set.deregister(&operator_0).unwrap();
+        operator_set.merge(&remote_operator_set);
+        assert_eq!(
+            operator_set.active_operator_public_keys(),
+            vec![operator_1.operator_public_key]
+        );
+
+        // reactivating the operator supersedes the deregistration
+        operator_set.register(&operator_0, collateral(2, 500_000));
+        operator_set.merge(&remote_operator_set);
+        let registration = operator_set
+            .operator(&operator_0.operator_public_key)
+            .unwrap();
+        assert!(registration.active);
+        assert_eq!(registration.collateral, Amount::from_sat(500_000));
+    }
+
+    #[test]
+    fn test_operator_set_rejects_unsigned_registrations() {
+        let operator_0 = operator_context(OPERATOR_SECRET);
+        let operator_1 = operator_context(DEPOSITOR_SECRET);
+
+        let mut operator_set = OperatorSet::new();
+        operator_set.register(&operator_0, collateral(0, 1_000_000));
+        assert!(matches!(
+            operator_set.deregister(&operator_1),
+            Err(Error::Operator(OperatorError::NotRegistered(_)))
+        ));
+
+        // another party raises the collateral and the revision of the operator
+        let mut remote_operator_set = operator_set.clone();
+        remote_operator_set.operators[0].collateral = Amount::from_sat(5_000_000);
+        remote_operator_set.operators[0].revision += 1;
+        operator_set.merge(&remote_operator_set);
+        assert_eq!(
+            operator_set.available_collateral(&operator_0.operator_public_key, &[]),
+            Amount::from_sat(1_000_000)
+        );
+
+        // or registers an operator on its behalf
+        let mut forged_registration =
+            OperatorRegistration::new(&operator_0, collateral(1, 1_000_000), true, 0);
+        forged_registration.operator_public_key = operator_1.operator_public_key;
+        forged_registration.operator_taproot_public_key = operator_1.operator_taproot_public_key;
+        remote_operator_set.operators.push(forged_registration);
+        operator_set.merge(&remote_operator_set);
+        assert!(operator_set
+            .operator(&operator_1.operator_public_key)
+            .is_none());
+    }
+}
@@ -2,9 +2,9 @@ use bitcoin::{
     hex::{Case::Upper, DisplayHex},
     Network, OutPoint, PublicKey, Transaction, Txid, XOnlyPublicKey,
 };
-use esplora_client::{AsyncClient, TxStatus};
+use esplora_client::TxStatus;
 use itertools::Itertools;
-use musig2::SecNonce;
+use musig2::{PubNonce, SecNonce};
 use num_traits::ToPrimitive;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
@@ -14,11 +14,12 @@ use std::{
 };
 
 use crate::{
-    client::sdk::{
-        query::GraphCliQuery, query_contexts::depositor_signatures::DepositorSignatures,
+    client::{
+        backend::base::BitcoinBackend,
+        sdk::{query::GraphCliQuery, query_contexts::depositor_signatures::DepositorSignatures},
     },
-    error::{Error, GraphError, NamedTx},
-    transactions::pre_signed_musig2::PreSignedMusig2Transaction,
+    error::{BackendError, Error, GraphError, NamedTx},
+    transactions::{pre_signed_musig2::PreSignedMusig2Transaction, signing_frost::FrostKeyPackage},
 };
 
 use super::{
@@ -33,11 +34,9 @@ use super::{
             pre_signed::PreSignedTransaction,
         },
     },
-    base::{
-        get_tx_statuses, verify_if_not_mined, BaseGraph, GraphId, GRAPH_VERSION,
-        NUM_REQUIRED_OPERATORS,
-    },
+    base::{verify_if_not_mined, BaseGraph, GraphId, GRAPH_VERSION, NUM_REQUIRED_OPERATORS},
     peg_out::{PegOutGraph, PegOutId},
+    state::{PegInState, PegInStateMachine, PegInTimelocks, PegInTransactionType},
 };
 
 pub enum PegInDepositorStatus {
@@ -155,6 +154,9 @@ pub struct PegInGraph {
     n_of_n_public_key: PublicKey,
     n_of_n_public_keys: Vec<PublicKey>,
     n_of_n_taproot_public_key: XOnlyPublicKey,
+    // Set when the verifier set signs with a t-of-n FROST group key instead of the n-of-n one
+    #[serde(default)]
+    frost_key_package: Option<FrostKeyPackage>,
 
     pub depositor_public_key: PublicKey,
     depositor_taproot_public_key: XOnlyPublicKey,
@@ -164,6 +166,21 @@ pub struct PegInGraph {
     connector_z: ConnectorZ,
 
     pub peg_out_graphs: Vec<PegOutId>,
+    // Operators expected to create a peg-out graph, taken from the operator set when the peg-in
+    // is created. Any NUM_REQUIRED_OPERATORS operators are enough when empty.
+    #[serde(default)]
+    pub operator_public_keys: Vec<PublicKey>,
+
+    #[serde(default)]
+    state: PegInStateMachine,
+
+    // Set when a verifier is evicted for its nonces or partial signatures, the graph being blocked
+    // until all the signers signed it again. The evicted nonces are evicted again from the copies
+    // merged in.
+    #[serde(default)]
+    blocked: bool,
+    #[serde(default)]
+    evicted_nonces: Vec<PubNonce>,
 }
 
 impl BaseGraph for PegInGraph {
@@ -175,12 +192,15 @@ impl BaseGraph for PegInGraph {
         &mut self,
         verifier_context: &VerifierContext,
         secret_nonces: &HashMap<Txid, HashMap<usize, SecNonce>>,
-    ) {
-        self.peg_in_confirm_transaction.pre_sign(
-            verifier_context,
-            &self.connector_z,
-            &secret_nonces[&self.peg_in_confirm_transaction.tx().compute_txid()],
-        );
+    ) -> Result<(), Error> {
+        let txid = self.peg_in_confirm_transaction.tx().compute_txid();
+        self.peg_in_confirm_transaction
+            .pre_sign(
+                verifier_context,
+                &self.connector_z,
+                &secret_nonces.get(&txid).cloned().unwrap_or_default(),
+            )
+            .map_err(|e| Error::Graph(GraphError::PreSigningFailed(vec![(txid, e)])))
     }
 
     fn push_verifier_nonces(
@@ -194,6 +214,14 @@ impl BaseGraph for PegInGraph {
         )]
         .into()
     }
+
+    fn evict_verifier(&mut self, verifier_public_key: &PublicKey) {
+        let evicted_nonces = self
+            .peg_in_confirm_transaction
+            .evict_verifier(verifier_public_key);
+        self.evicted_nonces.extend(evicted_nonces);
+        self.blocked = true;
+    }
 }
 
 impl PegInGraph {
@@ -233,12 +261,17 @@ impl PegInGraph {
             n_of_n_public_key: context.n_of_n_public_key,
             n_of_n_public_keys: context.n_of_n_public_keys.clone(),
             n_of_n_taproot_public_key: context.n_of_n_taproot_public_key,
+            frost_key_package: context.frost_key_package.clone(),
             depositor_public_key: context.depositor_public_key,
             depositor_taproot_public_key: context.depositor_taproot_public_key,
             depositor_evm_address: evm_address.to_string(),
             connector_0: connectors.connector_0,
             connector_z: connectors.connector_z,
             peg_out_graphs: Vec::new(),
+            operator_public_keys: Vec::new(),
+            state: PegInStateMachine::default(),
+            blocked: false,
+            evicted_nonces: vec![],
         }
     }
 
@@ -320,12 +353,17 @@ impl PegInGraph {
             n_of_n_public_key: *n_of_n_public_key,
             n_of_n_public_keys: n_of_n_public_keys.to_owned(),
             n_of_n_taproot_public_key: *n_of_n_taproot_public_key,
+            frost_key_package: None,
             depositor_public_key: *depositor_public_key,
             depositor_taproot_public_key: *depositor_taproot_public_key,
             depositor_evm_address: depositor_evm_address.to_string(),
             connector_0: connectors.connector_0,
             connector_z: connectors.connector_z,
             peg_out_graphs: Vec::new(),
+            operator_public_keys: Vec::new(),
+            state: PegInStateMachine::default(),
+            blocked: false,
+            evicted_nonces: vec![],
         }
     }
 
@@ -345,13 +383,62 @@ impl PegInGraph {
         )
     }
 
+    pub fn frost_key_package(&self) -> Option<&FrostKeyPackage> { self.frost_key_package.as_ref() }
+
     pub fn peg_in_confirm_transaction_ref(&self) -> &PegInConfirmTransaction {
         &self.peg_in_confirm_transaction
     }
 
+    /// Whether a verifier was evicted from the graph and not all the signers signed it again
+    pub fn is_blocked(&self) -> bool {
+        self.blocked
+            && !self
+                .peg_in_confirm_transaction
+                .has_signing_signatures(&self.n_of_n_public_keys, self.frost_key_package.as_ref())
+    }
+
+    pub fn state(&self) -> &PegInStateMachine { &self.state }
+
+    pub fn timelocks(&self) -> PegInTimelocks {
+        PegInTimelocks {
+            refund: self.connector_z.num_blocks_timelock_0,
+        }
+    }
+
+    /// Applies the transactions confirmed since the last sync, after rolling back the ones no
+    /// longer confirmed at the same height
+    pub async fn sync_state(&mut self, client: &dyn BitcoinBackend, finalized_height: Option<u32>) {
+        self.state = self.synced_state(client, finalized_height).await;
+    }
+
+    /// Rolls back the transactions confirmed at or above the block height, after a reorg
+    pub fn rewind_state(&mut self, block_height: u32) { self.state.rewind(block_height); }
+
+    async fn synced_state(
+        &self,
+        client: &dyn BitcoinBackend,
+        finalized_height: Option<u32>,
+    ) -> PegInStateMachine {
+        let mut state = self.state.clone();
+        state
+            .sync(client, finalized_height, |transaction| {
+                Some(self.txid(transaction))
+            })
+            .await;
+        state
+    }
+
+    fn txid(&self, transaction: PegInTransactionType) -> Txid {
+        match transaction {
+            PegInTransactionType::Deposit => self.peg_in_deposit_transaction.tx().compute_txid(),
+            PegInTransactionType::Confirm => self.peg_in_confirm_transaction.tx().compute_txid(),
+            PegInTransactionType::Refund => self.peg_in_refund_transaction.tx().compute_txid(),
+        }
+    }
+
     pub async fn verifier_status(
         &self,
-        client: &AsyncClient,
+        client: &dyn BitcoinBackend,
         verifier: Option<&VerifierContext>,
         peg_outs: &[&PegOutGraph],
     ) -> PegInVerifierStatus {
@@ -362,19 +449,18 @@ impl PegInGraph {
             panic!("Invalid peg outs supplied as argument");
         }
 
-        let (peg_in_deposit_status, peg_in_confirm_status, _) =
-            Self::get_peg_in_statuses(self, client).await;
+        let state = self.synced_state(client, None).await.state();
 
-        if !peg_in_deposit_status.is_ok_and(|status| status.confirmed) {
+        if state == PegInState::Created {
             // peg-in deposit not confirmed yet, wait
             return PegInVerifierStatus::AwaitingDeposit;
         }
 
-        if peg_outs.len() < NUM_REQUIRED_OPERATORS {
+        if !self.has_required_peg_outs(peg_outs) {
             return PegInVerifierStatus::AwaitingPegOutCreation;
         }
 
-        if peg_in_confirm_status.is_ok_and(|status| status.confirmed) {
+        if state == PegInState::Confirmed {
             // peg in complete
             return PegInVerifierStatus::Complete;
         }
@@ -385,10 +471,11 @@ impl PegInGraph {
                 .filter(|x| !x.has_all_nonces_of(verifier_context))
                 .map(|x| x.id().clone())
                 .collect::<Vec<_>>();
-            if !self
-                .peg_in_confirm_transaction
-                .has_nonce_of(verifier_context)
-            {
+            if self.peg_in_confirm_transaction.needs_nonces_from(
+                verifier_context.verifier_public_key,
+                &self.n_of_n_public_keys,
+                self.frost_key_package.as_ref(),
+            ) {
                 peg_outs_without_nonces.push(self.id().clone());
             }
             if !peg_outs_without_nonces.is_empty() {
@@ -396,10 +483,12 @@ impl PegInGraph {
             }
         }
 
-        let has_all_pegin_nonces = self.peg_in_confirm_transaction.has_all_nonces();
-        let has_all_pegout_nonces = peg_outs
-            .iter()
-            .any(|peg_out| peg_out.has_all_nonces(&self.n_of_n_public_keys));
+        let has_all_pegin_nonces = self
+            .peg_in_confirm_transaction
+            .has_signing_nonces(&self.n_of_n_public_keys, self.frost_key_package.as_ref());
+        let has_all_pegout_nonces = peg_outs.iter().all(|peg_out| {
+            peg_out.has_all_nonces(&self.n_of_n_public_keys, self.frost_key_package.as_ref())
+        });
         if !has_all_pegin_nonces || !has_all_pegout_nonces {
             return PegInVerifierStatus::AwaitingNonces;
         }
@@ -410,10 +499,11 @@ impl PegInGraph {
                 .filter(|x| !x.has_all_signatures_of(verifier_context))
                 .map(|x| x.id().clone())
                 .collect::<Vec<_>>();
-            if !self
-                .peg_in_confirm_transaction
-                .has_signatures_for(verifier_context.verifier_public_key)
-            {
+            if self.peg_in_confirm_transaction.needs_signatures_from(
+                verifier_context.verifier_public_key,
+                &self.n_of_n_public_keys,
+                self.frost_key_package.as_ref(),
+            ) {
                 peg_outs_without_signatures.push(self.id().clone());
             }
             if !peg_outs_without_signatures.is_empty() {
@@ -421,10 +511,12 @@ impl PegInGraph {
             }
         }
 
-        let has_all_pegin_signatures = self.peg_in_confirm_transaction.has_all_signatures();
-        let has_all_pegout_signatures = peg_outs
-            .iter()
-            .any(|peg_out| peg_out.has_all_signatures(&self.n_of_n_public_keys));
+        let has_all_pegin_signatures = self
+            .peg_in_confirm_transaction
+            .has_signing_signatures(&self.n_of_n_public_keys, self.frost_key_package.as_ref());
+        let has_all_pegout_signatures = peg_outs.iter().all(|peg_out| {
+            peg_out.has_all_signatures(&self.n_of_n_public_keys, self.frost_key_package.as_ref())
+        });
         if !has_all_pegin_signatures || !has_all_pegout_signatures {
             return PegInVerifierStatus::AwaitingSignatures;
         }
@@ -433,33 +525,46 @@ impl PegInGraph {
         PegInVerifierStatus::ReadyToSubmit
     }
 
-    pub async fn operator_status(&self, client: &AsyncClient) -> PegInOperatorStatus {
-        let (peg_in_deposit_status, peg_in_confirm_status, _) =
-            Self::get_peg_in_statuses(self, client).await;
-
-        if peg_in_deposit_status.is_ok_and(|status| status.confirmed) {
-            if peg_in_confirm_status.is_ok_and(|status| status.confirmed) {
-                // peg in complete
-                PegInOperatorStatus::PegInComplete
-            } else if self.peg_in_confirm_transaction.has_all_signatures() {
-                // should execute peg-in confirm
-                PegInOperatorStatus::PegInConfirmAvailable
-            } else {
-                // peg-in confirm not yet presigned, wait
-                PegInOperatorStatus::PegInWait
+    /// Whether every operator of the peg-in created its peg-out graph, the peg-in confirm output
+    /// being only presigned once all the operators can be reimbursed from it
+    pub fn has_required_peg_outs(&self, peg_outs: &[&PegOutGraph]) -> bool {
+        if self.operator_public_keys.is_empty() {
+            return peg_outs.len() >= NUM_REQUIRED_OPERATORS;
+        }
+        self.operator_public_keys.iter().all(|operator_public_key| {
+            peg_outs
+                .iter()
+                .any(|peg_out| peg_out.operator_public_key() == operator_public_key)
+        })
+    }
+
+    pub async fn operator_status(&self, client: &dyn BitcoinBackend) -> PegInOperatorStatus {
+        match self.synced_state(client, None).await.state() {
+            PegInState::Deposited { .. } => {
+                if self.peg_in_confirm_transaction.has_signing_signatures(
+                    &self.n_of_n_public_keys,
+                    self.frost_key_package.as_ref(),
+                ) {
+                    // should execute peg-in confirm
+                    PegInOperatorStatus::PegInConfirmAvailable
+                } else {
+                    // peg-in confirm not yet presigned, wait
+                    PegInOperatorStatus::PegInWait
+                }
             }
-        } else {
-            // peg-in deposit not confirmed yet, wait
-            PegInOperatorStatus::PegInWait
+            // peg in complete
+            PegInState::Confirmed => PegInOperatorStatus::PegInComplete,
+            // peg-in deposit not confirmed yet or peg-in refunded, wait
+            PegInState::Created | PegInState::Refunded => PegInOperatorStatus::PegInWait,
         }
     }
 
     pub fn interpret_depositor_status(
         &self,
-        peg_in_deposit_status: &Result<TxStatus, esplora_client::Error>,
-        peg_in_confirm_status: &Result<TxStatus, esplora_client::Error>,
-        peg_in_refund_status: &Result<TxStatus, esplora_client::Error>,
-        blockchain_height: Result<u32, esplora_client::Error>,
+        peg_in_deposit_status: &Result<TxStatus, BackendError>,
+        peg_in_confirm_status: &Result<TxStatus, BackendError>,
+        peg_in_refund_status: &Result<TxStatus, BackendError>,
+        blockchain_height: Result<u32, BackendError>,
     ) -> PegInDepositorStatus {
         if peg_in_deposit_status
             .as_ref()
@@ -501,38 +606,32 @@ impl PegInGraph {
         }
     }
 
-    pub async fn depositor_status(&self, client: &AsyncClient) -> PegInDepositorStatus {
-        let tx_statuses = get_tx_statuses(
-            client,
-            &[
-                self.peg_in_deposit_transaction.tx().compute_txid(),
-                self.peg_in_confirm_transaction.tx().compute_txid(),
-                self.peg_in_refund_transaction.tx().compute_txid(),
-            ],
-        )
-        .await;
-        let (peg_in_deposit_status, peg_in_confirm_status, peg_in_refund_status) =
-            match &tx_statuses[..] {
-                [stat1, stat2, stat3, ..] => (stat1, stat2, stat3),
-                // make sure vectors size are the same or will panic
-                _ => unreachable!(),
-            };
-
-        self.interpret_depositor_status(
-            peg_in_deposit_status,
-            peg_in_confirm_status,
-            peg_in_refund_status,
-            client.get_height().await,
-        )
+    pub async fn depositor_status(&self, client: &dyn BitcoinBackend) -> PegInDepositorStatus {
+        let state = self.synced_state(client, None).await;
+        match state.state() {
+            PegInState::Created => PegInDepositorStatus::PegInDepositWait,
+            PegInState::Deposited { .. } => {
+                let timelocks = self.timelocks();
+                if client.get_height().await.is_ok_and(|height| {
+                    state.is_available(PegInTransactionType::Refund, height, &timelocks)
+                }) {
+                    PegInDepositorStatus::PegInRefundAvailable
+                } else {
+                    PegInDepositorStatus::PegInConfirmWait
+                }
+            }
+            PegInState::Confirmed => PegInDepositorStatus::PegInConfirmComplete,
+            PegInState::Refunded => PegInDepositorStatus::PegInRefundComplete,
+        }
     }
 
-    pub async fn deposit(&mut self, client: &AsyncClient) -> Result<Transaction, Error> {
+    pub async fn deposit(&mut self, client: &dyn BitcoinBackend) -> Result<Transaction, Error> {
         let txid = self.peg_in_deposit_transaction.tx().compute_txid();
         verify_if_not_mined(client, txid).await?;
         Ok(self.peg_in_deposit_transaction.finalize())
     }
 
-    pub async fn confirm(&mut self, client: &AsyncClient) -> Result<Transaction, Error> {
+    pub async fn confirm(&mut self, client: &dyn BitcoinBackend) -> Result<Transaction, Error> {
         let txid = self.peg_in_confirm_transaction.tx().compute_txid();
         verify_if_not_mined(client, txid).await?;
 
@@ -546,11 +645,11 @@ impl PegInGraph {
                     NamedTx::for_tx(&self.peg_in_deposit_transaction, status.confirmed),
                 ]))),
             },
-            Err(e) => Err(Error::Esplora(e)),
+            Err(e) => Err(Error::Backend(e)),
         }
     }
 
-    pub async fn refund(&mut self, client: &AsyncClient) -> Result<Transaction, Error> {
+    pub async fn refund(&mut self, client: &dyn BitcoinBackend) -> Result<Transaction, Error> {
         let txid = self.peg_in_refund_transaction.tx().compute_txid();
         verify_if_not_mined(client, txid).await?;
 
@@ -564,37 +663,10 @@ impl PegInGraph {
                     NamedTx::for_tx(&self.peg_in_deposit_transaction, status.confirmed),
                 ]))),
             },
-            Err(e) => Err(Error::Esplora(e)),
+            Err(e) => Err(Error::Backend(e)),
         }
     }
 
-    async fn get_peg_in_statuses(
-        &self,
-        client: &AsyncClient,
-    ) -> (
-        Result<TxStatus, esplora_client::Error>,
-        Result<TxStatus, esplora_client::Error>,
-        Result<TxStatus, esplora_client::Error>,
-    ) {
-        let peg_in_deposit_status = client
-            .get_tx_status(&self.peg_in_deposit_transaction.tx().compute_txid())
-            .await;
-
-        let peg_in_confirm_status = client
-            .get_tx_status(&self.peg_in_confirm_transaction.tx().compute_txid())
-            .await;
-
-        let peg_in_refund_status = client
-            .get_tx_status(&self.peg_in_refund_transaction.tx().compute_txid())
-            .await;
-
-        (
-            peg_in_deposit_status,
-            peg_in_confirm_status,
-            peg_in_refund_status,
-        )
-    }
-
     pub fn validate(&self) -> bool {
         let mut ret_val = true;
         let peg_in_graph = self.new_for_validation();
@@ -621,10 +693,30 @@ impl PegInGraph {
             ret_val = false;
         }
 
+        // the FROST group key must be the key the connectors commit to
+        if let Some(frost_key_package) = &self.frost_key_package {
+            let mut verifier_public_keys = self.n_of_n_public_keys.clone();
+            verifier_public_keys.sort();
+            if frost_key_package.group_public_key != self.n_of_n_public_key
+                || frost_key_package.verifier_public_keys() != verifier_public_keys
+            {
+                ret_val = false;
+            }
+        }
+
         ret_val
     }
 
-    pub fn merge(&mut self, source_peg_in_graph: &PegInGraph) {
+    /// Merges the nonces, signatures and peg-out graphs of the source graph, which must be of the
+    /// same verifier set
+    pub fn merge(&mut self, source_peg_in_graph: &PegInGraph) -> Result<(), Error> {
+        // the FROST verifying shares, unlike the group key, are not committed to by the graph id
+        if self.frost_key_package != source_peg_in_graph.frost_key_package {
+            return Err(Error::Graph(GraphError::FrostKeyPackageMismatch(
+                self.id.clone(),
+            )));
+        }
+
         self.peg_in_confirm_transaction
             .merge(&source_peg_in_graph.peg_in_confirm_transaction);
 
@@ -632,11 +724,28 @@ impl PegInGraph {
             .extend(source_peg_in_graph.peg_out_graphs.clone());
         self.peg_out_graphs.sort();
         self.peg_out_graphs.dedup();
+
+        if self.operator_public_keys.is_empty() {
+            self.operator_public_keys
+                .clone_from(&source_peg_in_graph.operator_public_keys);
+        }
+
+        // nonces evicted from either copy must not come back with the other one
+        self.blocked |= source_peg_in_graph.blocked;
+        for nonce in &source_peg_in_graph.evicted_nonces {
+            if !self.evicted_nonces.contains(nonce) {
+                self.evicted_nonces.push(nonce.clone());
+            }
+        }
+        self.peg_in_confirm_transaction
+            .evict_nonces(&self.evicted_nonces);
+
+        Ok(())
     }
 }
 
 impl GraphCliQuery for PegInGraph {
-    async fn broadcast_deposit(&self, client: &AsyncClient) -> Result<(), String> {
+    async fn broadcast_deposit(&self, client: &dyn BitcoinBackend) -> Result<(), String> {
         let txid = self.peg_in_deposit_transaction.tx().compute_txid();
         let tx_status = client.get_tx_status(&txid).await;
         match tx_status {
@@ -738,12 +847,17 @@ fn create_graph_without_signing(
         n_of_n_public_key: *n_of_n_public_key,
         n_of_n_public_keys: n_of_n_public_keys.to_owned(),
         n_of_n_taproot_public_key: *n_of_n_taproot_public_key,
+        frost_key_package: None,
         depositor_public_key: *depositor_public_key,
         depositor_taproot_public_key: *depositor_taproot_public_key,
         depositor_evm_address: depositor_evm_address.to_string(),
         connector_0: connectors.connector_0,
         connector_z: connectors.connector_z,
         peg_out_graphs: Vec::new(),
+        operator_public_keys: Vec::new(),
+        state: PegInStateMachine::default(),
+        blocked: false,
+        evicted_nonces: vec![],
     }
 }
 
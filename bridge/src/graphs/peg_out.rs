@@ -1,11 +1,13 @@
 use bitcoin::{
+    block::Header,
+    consensus::encode::deserialize,
     hashes::Hash,
     hex::{Case::Upper, DisplayHex},
     key::Keypair,
-    Amount, Network, OutPoint, PublicKey, ScriptBuf, Transaction, Txid, XOnlyPublicKey,
+    Amount, Network, OutPoint, PublicKey, ScriptBuf, Transaction, TxOut, Txid, XOnlyPublicKey,
 };
-use esplora_client::{AsyncClient, TxStatus};
-use musig2::SecNonce;
+use esplora_client::TxStatus;
+use musig2::{PubNonce, SecNonce};
 use num_traits::ToPrimitive;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
@@ -15,16 +17,18 @@ use std::{
 };
 
 use crate::{
+    client::backend::base::BitcoinBackend,
     commitments::CommitmentMessageId,
     common::ZkProofVerifyingKey,
     connectors::{
         connector_c::get_commit_from_assert_commit_tx, connector_d::ConnectorD,
         connector_e::ConnectorE, connector_f_1::ConnectorF1, connector_f_2::ConnectorF2,
     },
-    error::{Error, GraphError, L2Error, NamedTx},
+    constants::START_TIME_MESSAGE_LENGTH,
+    error::{BackendError, Error, GraphError, L2Error, NamedTx, TransactionError},
     superblock::{
-        find_superblock, get_start_time_block_number, get_superblock_hash_message,
-        get_superblock_message,
+        get_start_time_block_number, get_superblock_hash_message, get_superblock_message,
+        SUPERBLOCK_MESSAGE_LENGTH,
     },
     transactions::{
         assert_transactions::{
@@ -40,12 +44,13 @@ use crate::{
         },
         peg_in_confirm::PEG_IN_CONFIRM_TX_NAME,
         pre_signed_musig2::PreSignedMusig2Transaction,
+        signing_frost::FrostKeyPackage,
     },
 };
 
 use bitvm::chunker::disprove_execution::RawProof;
 use bitvm::signatures::signing_winternitz::{
-    WinternitzPublicKey, WinternitzSecret, WinternitzSigningInputs,
+    winternitz_message_from_witness, WinternitzPublicKey, WinternitzSecret, WinternitzSigningInputs,
 };
 
 use super::{
@@ -66,6 +71,7 @@ use super::{
             challenge::ChallengeTransaction,
             disprove::DisproveTransaction,
             disprove_chain::DisproveChainTransaction,
+            fee::{find_anchor_output, tx_fee},
             kick_off_1::KickOff1Transaction,
             kick_off_2::KickOff2Transaction,
             kick_off_timeout::KickOffTimeoutTransaction,
@@ -80,10 +86,23 @@ use super::{
     },
     base::{verify_if_not_mined, BaseGraph, GraphId, CROWDFUNDING_AMOUNT, GRAPH_VERSION},
     peg_in::PegInGraph,
+    state::{PegOutState, PegOutStateMachine, PegOutTimelocks, PegOutTransactionType},
 };
 
 pub type PegOutId = GraphId;
 
+/// Transactions of the peg-out graph whose fee can be bumped with a CPFP transaction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CpfpParent {
+    KickOff1,
+    KickOff2,
+    AssertInitial,
+    AssertFinal,
+    // the reward output of the verifier is spent instead of an anchor
+    DisproveChain,
+    Disprove,
+}
+
 pub enum PegOutWithdrawerStatus {
     PegOutNotStarted, // peg-out transaction not created yet
     PegOutWait,       // peg-out not confirmed yet, wait
@@ -254,7 +273,8 @@ pub struct PegOutGraph {
     network: Network,
     id: String,
 
-    // state: State,
+    #[serde(default)]
+    state: PegOutStateMachine,
     // n_of_n_pre_signing_state: PreSigningState,
     n_of_n_presigned: bool,
     n_of_n_public_key: PublicKey,
@@ -304,6 +324,14 @@ pub struct PegOutGraph {
 
     pub peg_out_chain_event: Option<PegOutEvent>,
     pub peg_out_transaction: Option<PegOutTransaction>,
+
+    // Set when a verifier is evicted for its nonces or partial signatures, the graph being blocked
+    // until all the signers signed it again. The evicted nonces are evicted again from the copies
+    // merged in.
+    #[serde(default)]
+    blocked: bool,
+    #[serde(default)]
+    evicted_nonces: Vec<PubNonce>,
 }
 
 impl BaseGraph for PegOutGraph {
@@ -315,52 +343,71 @@ impl BaseGraph for PegOutGraph {
         &mut self,
         verifier_context: &VerifierContext,
         secret_nonces: &HashMap<Txid, HashMap<usize, SecNonce>>,
-    ) {
-        self.assert_initial_transaction.pre_sign(
-            verifier_context,
-            &self.connector_b,
-            &secret_nonces[&self.assert_initial_transaction.tx().compute_txid()],
-        );
-        self.assert_final_transaction.pre_sign(
-            verifier_context,
-            &self.connector_d,
-            &secret_nonces[&self.assert_final_transaction.tx().compute_txid()],
-        );
-        self.disprove_chain_transaction.pre_sign(
-            verifier_context,
-            &self.connector_b,
-            &secret_nonces[&self.disprove_chain_transaction.tx().compute_txid()],
-        );
-        self.disprove_transaction.pre_sign(
-            verifier_context,
-            &self.connector_5,
-            &secret_nonces[&self.disprove_transaction.tx().compute_txid()],
-        );
-        self.kick_off_timeout_transaction.pre_sign(
-            verifier_context,
-            &self.connector_1,
-            &secret_nonces[&self.kick_off_timeout_transaction.tx().compute_txid()],
-        );
-        self.start_time_timeout_transaction.pre_sign(
-            verifier_context,
-            &self.connector_1,
-            &self.connector_2,
-            &secret_nonces[&self.start_time_timeout_transaction.tx().compute_txid()],
-        );
-        self.take_1_transaction.pre_sign(
-            verifier_context,
-            &self.connector_0,
-            &self.connector_b,
-            &secret_nonces[&self.take_1_transaction.tx().compute_txid()],
-        );
-        self.take_2_transaction.pre_sign(
-            verifier_context,
-            &self.connector_0,
-            &self.connector_5,
-            &secret_nonces[&self.take_2_transaction.tx().compute_txid()],
-        );
+    ) -> Result<(), Error> {
+        let no_secret_nonces = HashMap::new();
+        let tx_secret_nonces = |txid: Txid| secret_nonces.get(&txid).unwrap_or(&no_secret_nonces);
+
+        // every transaction is signed, a failing one not holding the others back
+        let results = [
+            self.assert_initial_transaction.pre_sign(
+                verifier_context,
+                &self.connector_b,
+                tx_secret_nonces(self.assert_initial_transaction.tx().compute_txid()),
+            ),
+            self.assert_final_transaction.pre_sign(
+                verifier_context,
+                &self.connector_d,
+                tx_secret_nonces(self.assert_final_transaction.tx().compute_txid()),
+            ),
+            self.disprove_chain_transaction.pre_sign(
+                verifier_context,
+                &self.connector_b,
+                tx_secret_nonces(self.disprove_chain_transaction.tx().compute_txid()),
+            ),
+            self.disprove_transaction.pre_sign(
+                verifier_context,
+                &self.connector_5,
+                tx_secret_nonces(self.disprove_transaction.tx().compute_txid()),
+            ),
+            self.kick_off_timeout_transaction.pre_sign(
+                verifier_context,
+                &self.connector_1,
+                tx_secret_nonces(self.kick_off_timeout_transaction.tx().compute_txid()),
+            ),
+            self.start_time_timeout_transaction.pre_sign(
+                verifier_context,
+                &self.connector_1,
+                &self.connector_2,
+                tx_secret_nonces(self.start_time_timeout_transaction.tx().compute_txid()),
+            ),
+            self.take_1_transaction.pre_sign(
+                verifier_context,
+                &self.connector_0,
+                &self.connector_b,
+                tx_secret_nonces(self.take_1_transaction.tx().compute_txid()),
+            ),
+            self.take_2_transaction.pre_sign(
+                verifier_context,
+                &self.connector_0,
+                &self.connector_5,
+                tx_secret_nonces(self.take_2_transaction.tx().compute_txid()),
+            ),
+        ];
+
+        // the results are in the order of all_presigned_txs
+        let errors: Vec<(Txid, Error)> = self
+            .all_presigned_txs()
+            .map(|tx_wrapper| tx_wrapper.tx().compute_txid())
+            .zip(results)
+            .filter_map(|(txid, result)| result.err().map(|e| (txid, e)))
+            .collect();
+        if !errors.is_empty() {
+            return Err(Error::Graph(GraphError::PreSigningFailed(errors)));
+        }
 
         self.n_of_n_presigned = true; // TODO: set to true after collecting all n of n signatures
+
+        Ok(())
     }
 
     fn push_verifier_nonces(
@@ -376,6 +423,16 @@ impl BaseGraph for PegOutGraph {
             })
             .collect()
     }
+
+    fn evict_verifier(&mut self, verifier_public_key: &PublicKey) {
+        let evicted_nonces: Vec<PubNonce> = self
+            .all_presigned_txs_mut()
+            .flat_map(|tx_wrapper| tx_wrapper.evict_verifier(verifier_public_key))
+            .collect();
+        self.evicted_nonces.extend(evicted_nonces);
+        self.blocked = true;
+        self.n_of_n_presigned = false;
+    }
 }
 
 impl PegOutGraph {
@@ -750,6 +807,7 @@ impl PegOutGraph {
             version: GRAPH_VERSION.to_string(),
             network: context.network,
             id: generate_id(peg_in_graph, &context.operator_public_key),
+            state: PegOutStateMachine::default(),
             n_of_n_presigned: false,
             n_of_n_public_key: context.n_of_n_public_key,
             n_of_n_taproot_public_key: context.n_of_n_taproot_public_key,
@@ -789,6 +847,8 @@ impl PegOutGraph {
             operator_taproot_public_key: context.operator_taproot_public_key,
             peg_out_chain_event: None,
             peg_out_transaction: None,
+            blocked: false,
+            evicted_nonces: vec![],
         }
     }
 
@@ -1122,6 +1182,7 @@ impl PegOutGraph {
             version: GRAPH_VERSION.to_string(),
             network: self.network,
             id: self.id.clone(),
+            state: PegOutStateMachine::default(),
             n_of_n_presigned: false,
             n_of_n_public_key: self.n_of_n_public_key,
             n_of_n_taproot_public_key: self.n_of_n_taproot_public_key,
@@ -1161,278 +1222,247 @@ impl PegOutGraph {
             operator_taproot_public_key: self.operator_taproot_public_key,
             peg_out_chain_event: None,
             peg_out_transaction: None,
+            blocked: false,
+            evicted_nonces: vec![],
         }
     }
 
-    pub async fn verifier_status(&self, client: &AsyncClient) -> PegOutVerifierStatus {
-        if self.n_of_n_presigned {
-            let (
-                _,
-                _,
-                _,
-                assert_final_status,
-                challenge_status,
-                disprove_chain_status,
-                disprove_status,
-                _,
-                kick_off_1_status,
-                kick_off_2_status,
-                kick_off_timeout_status,
-                _,
-                start_time_timeout_status,
-                start_time_status,
-                take_1_status,
-                take_2_status,
-            ) = Self::get_peg_out_statuses(self, client).await;
-            let blockchain_height = client.get_height().await;
-
-            if kick_off_2_status
-                .as_ref()
-                .is_ok_and(|status| status.confirmed)
-            {
-                if take_1_status.as_ref().is_ok_and(|status| status.confirmed)
-                    || take_2_status.as_ref().is_ok_and(|status| status.confirmed)
-                {
-                    PegOutVerifierStatus::PegOutComplete
-                } else if disprove_status
-                    .as_ref()
-                    .is_ok_and(|status| status.confirmed)
-                    || disprove_chain_status
-                        .as_ref()
-                        .is_ok_and(|status| status.confirmed)
+    pub fn state(&self) -> &PegOutStateMachine { &self.state }
+
+    pub fn timelocks(&self) -> PegOutTimelocks {
+        PegOutTimelocks {
+            start_time_timeout: self.connector_1.num_blocks_timelock_leaf_2,
+            kick_off_timeout: self.connector_1.num_blocks_timelock_leaf_1,
+            kick_off_2: self.connector_1.num_blocks_timelock_leaf_0,
+            take_1: self.connector_3.num_blocks_timelock,
+            assert_initial: self.connector_b.num_blocks_timelock_1,
+            take_2: self.connector_4.num_blocks_timelock,
+        }
+    }
+
+    /// Applies the transactions confirmed since the last sync, after rolling back the ones no
+    /// longer confirmed at the same height
+    pub async fn sync_state(&mut self, client: &dyn BitcoinBackend, finalized_height: Option<u32>) {
+        self.state = self.synced_state(client, finalized_height).await;
+    }
+
+    /// Rolls back the transactions confirmed at or above the block height, after a reorg
+    pub fn rewind_state(&mut self, block_height: u32) { self.state.rewind(block_height); }
+
+    async fn synced_state(
+        &self,
+        client: &dyn BitcoinBackend,
+        finalized_height: Option<u32>,
+    ) -> PegOutStateMachine {
+        let spenders = self.connector_spenders(client).await;
+        let mut state = self.state.clone();
+        state
+            .sync(client, finalized_height, |transaction| {
+                match spenders
+                    .iter()
+                    .find(|(spender_transaction, _)| *spender_transaction == transaction)
                 {
-                    return PegOutVerifierStatus::PegOutFailed; // TODO: can be also `PegOutVerifierStatus::PegOutComplete`
-                } else if assert_final_status
+                    Some((_, spender)) => *spender,
+                    None => self.txid(transaction),
+                }
+            })
+            .await;
+        state
+    }
+
+    /// The challenge, disprove chain and disprove transactions found on chain, by the spending of
+    /// the outputs of connectors A, B and 5
+    ///
+    /// Their txids differ from the pre-signed ones, the verifiers adding the crowdfunding inputs of
+    /// the challenge and the reward outputs of the disproves. The outputs are also spent by the
+    /// pre-signed take and assert transactions, whose txids are known.
+    async fn connector_spenders(
+        &self,
+        client: &dyn BitcoinBackend,
+    ) -> Vec<(PegOutTransactionType, Option<Txid>)> {
+        let connector_spends = [
+            (
+                PegOutTransactionType::Challenge,
+                self.challenge_transaction.tx(),
+                vec![PegOutTransactionType::Take1],
+            ),
+            (
+                PegOutTransactionType::DisproveChain,
+                self.disprove_chain_transaction.tx(),
+                vec![
+                    PegOutTransactionType::AssertInitial,
+                    PegOutTransactionType::Take1,
+                ],
+            ),
+            (
+                PegOutTransactionType::Disprove,
+                self.disprove_transaction.tx(),
+                vec![PegOutTransactionType::Take2],
+            ),
+        ];
+
+        let mut spenders = vec![];
+        for (transaction, tx, other_transactions) in connector_spends {
+            let spender = client
+                .get_output_spender(&tx.input[0].previous_output)
+                .await
+                .ok()
+                .flatten()
+                .filter(|spender| {
+                    other_transactions
+                        .iter()
+                        .all(|other| self.txid(*other) != Some(*spender))
+                });
+            spenders.push((transaction, spender));
+        }
+        spenders
+    }
+
+    // the peg-out txid is unknown until the operator creates it
+    fn txid(&self, transaction: PegOutTransactionType) -> Option<Txid> {
+        let txid = match transaction {
+            PegOutTransactionType::PegOut => {
+                return self
+                    .peg_out_transaction
                     .as_ref()
-                    .is_ok_and(|status| status.confirmed)
-                {
-                    return PegOutVerifierStatus::PegOutDisproveAvailable;
+                    .map(|peg_out| peg_out.tx().compute_txid())
+            }
+            PegOutTransactionType::PegOutConfirm => self.peg_out_confirm_transaction.tx(),
+            PegOutTransactionType::KickOff1 => self.kick_off_1_transaction.tx(),
+            PegOutTransactionType::Challenge => self.challenge_transaction.tx(),
+            PegOutTransactionType::StartTime => self.start_time_transaction.tx(),
+            PegOutTransactionType::StartTimeTimeout => self.start_time_timeout_transaction.tx(),
+            PegOutTransactionType::KickOffTimeout => self.kick_off_timeout_transaction.tx(),
+            PegOutTransactionType::KickOff2 => self.kick_off_2_transaction.tx(),
+            PegOutTransactionType::Take1 => self.take_1_transaction.tx(),
+            PegOutTransactionType::DisproveChain => self.disprove_chain_transaction.tx(),
+            PegOutTransactionType::AssertInitial => self.assert_initial_transaction.tx(),
+            PegOutTransactionType::AssertCommit1 => self.assert_commit_1_transaction.tx(),
+            PegOutTransactionType::AssertCommit2 => self.assert_commit_2_transaction.tx(),
+            PegOutTransactionType::AssertFinal => self.assert_final_transaction.tx(),
+            PegOutTransactionType::Take2 => self.take_2_transaction.tx(),
+            PegOutTransactionType::Disprove => self.disprove_transaction.tx(),
+        }
+        .compute_txid();
+        Some(txid)
+    }
+
+    pub async fn verifier_status(&self, client: &dyn BitcoinBackend) -> PegOutVerifierStatus {
+        if !self.n_of_n_presigned {
+            return PegOutVerifierStatus::PegOutPresign;
+        }
+
+        let state = self.synced_state(client, None).await;
+        let blockchain_height = client.get_height().await;
+        let timelocks = self.timelocks();
+        let is_available = |transaction| {
+            blockchain_height
+                .as_ref()
+                .is_ok_and(|height| state.is_available(transaction, *height, &timelocks))
+        };
+
+        match state.state() {
+            PegOutState::Created | PegOutState::PegOutPaid | PegOutState::PegOutConfirmed => {
+                PegOutVerifierStatus::PegOutWait
+            }
+            PegOutState::KickedOff1 {
+                start_time_committed: false,
+                ..
+            } => {
+                if is_available(PegOutTransactionType::StartTimeTimeout) {
+                    PegOutVerifierStatus::PegOutStartTimeTimeoutAvailable
                 } else {
-                    return PegOutVerifierStatus::PegOutDisproveChainAvailable;
+                    PegOutVerifierStatus::PegOutWait
                 }
-            } else if kick_off_1_status
-                .as_ref()
-                .is_ok_and(|status| status.confirmed)
-            {
-                if start_time_timeout_status
-                    .as_ref()
-                    .is_ok_and(|status| status.confirmed)
-                    || kick_off_timeout_status
-                        .as_ref()
-                        .is_ok_and(|status| status.confirmed)
-                {
-                    return PegOutVerifierStatus::PegOutFailed; // TODO: can be also `PegOutVerifierStatus::PegOutComplete`
-                } else if start_time_status
-                    .as_ref()
-                    .is_ok_and(|status| !status.confirmed)
-                {
-                    if kick_off_1_status
-                        .as_ref()
-                        .unwrap()
-                        .block_height
-                        .is_some_and(|block_height| {
-                            blockchain_height.is_ok_and(|blockchain_height| {
-                                block_height + self.connector_1.num_blocks_timelock_leaf_2
-                                    <= blockchain_height
-                            })
-                        })
-                    {
-                        return PegOutVerifierStatus::PegOutStartTimeTimeoutAvailable;
-                    } else {
-                        return PegOutVerifierStatus::PegOutWait;
-                    }
-                } else if kick_off_1_status
-                    .as_ref()
-                    .unwrap()
-                    .block_height
-                    .is_some_and(|block_height| {
-                        blockchain_height.is_ok_and(|blockchain_height| {
-                            block_height + self.connector_1.num_blocks_timelock_leaf_1
-                                <= blockchain_height
-                        })
-                    })
-                {
-                    return PegOutVerifierStatus::PegOutKickOffTimeoutAvailable;
-                } else if challenge_status
-                    .as_ref()
-                    .is_ok_and(|status| !status.confirmed)
-                {
-                    return PegOutVerifierStatus::PegOutChallengeAvailable;
+            }
+            PegOutState::KickedOff1 { challenged, .. } => {
+                if is_available(PegOutTransactionType::KickOffTimeout) {
+                    PegOutVerifierStatus::PegOutKickOffTimeoutAvailable
+                } else if !challenged {
+                    PegOutVerifierStatus::PegOutChallengeAvailable
                 } else {
-                    return PegOutVerifierStatus::PegOutWait;
+                    PegOutVerifierStatus::PegOutWait
                 }
-            } else {
-                return PegOutVerifierStatus::PegOutWait;
             }
-        } else {
-            PegOutVerifierStatus::PegOutPresign
+            PegOutState::KickedOff2 { .. } => PegOutVerifierStatus::PegOutDisproveChainAvailable,
+            // the chain can no longer be disproven, wait for the assertions
+            PegOutState::AssertInitialized { .. } => PegOutVerifierStatus::PegOutWait,
+            PegOutState::Asserted { .. } => PegOutVerifierStatus::PegOutDisproveAvailable,
+            PegOutState::Taken1 | PegOutState::Taken2 => PegOutVerifierStatus::PegOutComplete,
+            PegOutState::StartTimeTimedOut
+            | PegOutState::KickOffTimedOut
+            | PegOutState::ChainDisproven
+            | PegOutState::Disproven => PegOutVerifierStatus::PegOutFailed,
         }
     }
 
-    pub async fn operator_status(&self, client: &AsyncClient) -> PegOutOperatorStatus {
-        if self.n_of_n_presigned && self.is_peg_out_initiated() {
-            let (
-                assert_initial_status,
-                assert_commit_1_status,
-                assert_commit_2_status,
-                assert_final_status,
-                challenge_status,
-                disprove_chain_status,
-                disprove_status,
-                peg_out_confirm_status,
-                kick_off_1_status,
-                kick_off_2_status,
-                kick_off_timeout_status,
-                peg_out_status,
-                start_time_timeout_status,
-                start_time_status,
-                take_1_status,
-                take_2_status,
-            ) = Self::get_peg_out_statuses(self, client).await;
-            let blockchain_height = client.get_height().await;
-
-            if peg_out_status.is_some_and(|status| status.unwrap().confirmed) {
-                if kick_off_2_status
-                    .as_ref()
-                    .is_ok_and(|status| status.confirmed)
-                {
-                    if take_1_status.as_ref().is_ok_and(|status| status.confirmed)
-                        || take_2_status.as_ref().is_ok_and(|status| status.confirmed)
-                    {
-                        return PegOutOperatorStatus::PegOutComplete;
-                    } else if disprove_chain_status
-                        .as_ref()
-                        .is_ok_and(|status| status.confirmed)
-                        || disprove_status
-                            .as_ref()
-                            .is_ok_and(|status| status.confirmed)
-                    {
-                        return PegOutOperatorStatus::PegOutFailed; // TODO: can be also `PegOutOperatorStatus::PegOutComplete`
-                    } else if challenge_status.is_ok_and(|status| status.confirmed) {
-                        if assert_final_status
-                            .as_ref()
-                            .is_ok_and(|status| status.confirmed)
-                        {
-                            if assert_final_status
-                                .as_ref()
-                                .unwrap()
-                                .block_height
-                                .is_some_and(|block_height| {
-                                    blockchain_height.is_ok_and(|blockchain_height| {
-                                        block_height + self.connector_4.num_blocks_timelock
-                                            <= blockchain_height
-                                    })
-                                })
-                            {
-                                return PegOutOperatorStatus::PegOutTake2Available;
-                            } else {
-                                return PegOutOperatorStatus::PegOutWait;
-                            }
-                        } else if kick_off_2_status
-                            .as_ref()
-                            .unwrap()
-                            .block_height
-                            .is_some_and(|block_height| {
-                                blockchain_height.is_ok_and(|blockchain_height| {
-                                    block_height + self.connector_b.num_blocks_timelock_1
-                                        <= blockchain_height
-                                })
-                            })
-                        {
-                            if assert_initial_status
-                                .as_ref()
-                                .is_ok_and(|status| status.confirmed)
-                            {
-                                if assert_commit_1_status
-                                    .as_ref()
-                                    .is_ok_and(|status| status.confirmed)
-                                    && assert_commit_2_status
-                                        .as_ref()
-                                        .is_ok_and(|status| status.confirmed)
-                                {
-                                    return PegOutOperatorStatus::PegOutAssertFinalAvailable;
-                                } else if assert_commit_1_status
-                                    .as_ref()
-                                    .is_ok_and(|status| status.confirmed)
-                                {
-                                    return PegOutOperatorStatus::PegOutAssertCommit2Available;
-                                } else {
-                                    return PegOutOperatorStatus::PegOutAssertCommit1Available;
-                                }
-                            } else {
-                                return PegOutOperatorStatus::PegOutAssertInitialAvailable;
-                            }
-                        } else {
-                            return PegOutOperatorStatus::PegOutWait;
-                        }
-                    } else if kick_off_2_status
-                        .as_ref()
-                        .unwrap()
-                        .block_height
-                        .is_some_and(|block_height| {
-                            blockchain_height.is_ok_and(|blockchain_height| {
-                                block_height + self.connector_3.num_blocks_timelock
-                                    <= blockchain_height
-                            })
-                        })
-                    {
-                        return PegOutOperatorStatus::PegOutTake1Available;
-                    } else {
-                        return PegOutOperatorStatus::PegOutWait;
-                    }
-                } else if kick_off_1_status
-                    .as_ref()
-                    .is_ok_and(|status| status.confirmed)
-                {
-                    if start_time_timeout_status
-                        .as_ref()
-                        .is_ok_and(|status| status.confirmed)
-                        || kick_off_timeout_status
-                            .as_ref()
-                            .is_ok_and(|status| status.confirmed)
-                    {
-                        return PegOutOperatorStatus::PegOutFailed; // TODO: can be also `PegOutOperatorStatus::PegOutComplete`
-                    } else if start_time_status
-                        .as_ref()
-                        .is_ok_and(|status| status.confirmed)
-                    {
-                        if kick_off_1_status
-                            .as_ref()
-                            .unwrap()
-                            .block_height
-                            .is_some_and(|block_height| {
-                                blockchain_height.is_ok_and(|blockchain_height| {
-                                    block_height + self.connector_1.num_blocks_timelock_leaf_0
-                                        <= blockchain_height
-                                })
-                            })
-                        {
-                            return PegOutOperatorStatus::PegOutKickOff2Available;
-                        } else {
-                            return PegOutOperatorStatus::PegOutWait;
-                        }
-                    } else {
-                        return PegOutOperatorStatus::PegOutStartTimeAvailable;
-                    }
-                } else if peg_out_confirm_status
-                    .as_ref()
-                    .is_ok_and(|status| status.confirmed)
-                {
-                    return PegOutOperatorStatus::PegOutKickOff1Available;
-                } else {
-                    return PegOutOperatorStatus::PegOutPegOutConfirmAvailable;
-                }
+    pub async fn operator_status(&self, client: &dyn BitcoinBackend) -> PegOutOperatorStatus {
+        if !self.n_of_n_presigned || !self.is_peg_out_initiated() {
+            return PegOutOperatorStatus::PegOutWait;
+        }
+
+        let state = self.synced_state(client, None).await;
+        let blockchain_height = client.get_height().await;
+        let timelocks = self.timelocks();
+        let is_available = |transaction| {
+            blockchain_height
+                .as_ref()
+                .is_ok_and(|height| state.is_available(transaction, *height, &timelocks))
+        };
+        let available_or_wait = |transaction, status| {
+            if is_available(transaction) {
+                status
             } else {
-                return PegOutOperatorStatus::PegOutStartPegOut;
+                PegOutOperatorStatus::PegOutWait
             }
-        }
+        };
 
-        PegOutOperatorStatus::PegOutWait
+        match state.state() {
+            PegOutState::Created => PegOutOperatorStatus::PegOutStartPegOut,
+            PegOutState::PegOutPaid => PegOutOperatorStatus::PegOutPegOutConfirmAvailable,
+            PegOutState::PegOutConfirmed => PegOutOperatorStatus::PegOutKickOff1Available,
+            PegOutState::KickedOff1 {
+                start_time_committed: false,
+                ..
+            } => PegOutOperatorStatus::PegOutStartTimeAvailable,
+            PegOutState::KickedOff1 { .. } => available_or_wait(
+                PegOutTransactionType::KickOff2,
+                PegOutOperatorStatus::PegOutKickOff2Available,
+            ),
+            PegOutState::KickedOff2 {
+                challenged: true, ..
+            } => available_or_wait(
+                PegOutTransactionType::AssertInitial,
+                PegOutOperatorStatus::PegOutAssertInitialAvailable,
+            ),
+            PegOutState::KickedOff2 { .. } => available_or_wait(
+                PegOutTransactionType::Take1,
+                PegOutOperatorStatus::PegOutTake1Available,
+            ),
+            PegOutState::AssertInitialized {
+                commit_1: false, ..
+            } => PegOutOperatorStatus::PegOutAssertCommit1Available,
+            PegOutState::AssertInitialized {
+                commit_2: false, ..
+            } => PegOutOperatorStatus::PegOutAssertCommit2Available,
+            PegOutState::AssertInitialized { .. } => {
+                PegOutOperatorStatus::PegOutAssertFinalAvailable
+            }
+            PegOutState::Asserted { .. } => available_or_wait(
+                PegOutTransactionType::Take2,
+                PegOutOperatorStatus::PegOutTake2Available,
+            ),
+            PegOutState::Taken1 | PegOutState::Taken2 => PegOutOperatorStatus::PegOutComplete,
+            PegOutState::StartTimeTimedOut
+            | PegOutState::KickOffTimedOut
+            | PegOutState::ChainDisproven
+            | PegOutState::Disproven => PegOutOperatorStatus::PegOutFailed,
+        }
     }
 
     pub fn interpret_withdrawer_status(
         &self,
-        peg_out_status: Option<&Result<TxStatus, esplora_client::Error>>,
+        peg_out_status: Option<&Result<TxStatus, BackendError>>,
     ) -> PegOutWithdrawerStatus {
         if let Some(peg_out_status) = peg_out_status {
             if peg_out_status.as_ref().is_ok_and(|status| status.confirmed) {
@@ -1445,7 +1475,7 @@ impl PegOutGraph {
         }
     }
 
-    pub async fn withdrawer_status(&self, client: &AsyncClient) -> PegOutWithdrawerStatus {
+    pub async fn withdrawer_status(&self, client: &dyn BitcoinBackend) -> PegOutWithdrawerStatus {
         let peg_out_status = match self.peg_out_transaction {
             Some(_) => {
                 let peg_out_txid = self
@@ -1464,7 +1494,7 @@ impl PegOutGraph {
 
     pub async fn peg_out(
         &mut self,
-        client: &AsyncClient,
+        client: &dyn BitcoinBackend,
         context: &OperatorContext,
         input: Input,
     ) -> Result<Transaction, Error> {
@@ -1489,7 +1519,10 @@ impl PegOutGraph {
         Ok(self.peg_out_transaction.as_mut().unwrap().finalize())
     }
 
-    pub async fn peg_out_confirm(&mut self, client: &AsyncClient) -> Result<Transaction, Error> {
+    pub async fn peg_out_confirm(
+        &mut self,
+        client: &dyn BitcoinBackend,
+    ) -> Result<Transaction, Error> {
         verify_if_not_mined(client, self.peg_out_confirm_transaction.tx().compute_txid()).await?;
 
         if self.peg_out_transaction.as_ref().is_some() {
@@ -1511,7 +1544,7 @@ impl PegOutGraph {
                         ),
                     ]))),
                 },
-                Err(e) => Err(Error::Esplora(e)),
+                Err(e) => Err(Error::Backend(e)),
             }
         } else {
             Err(Error::Graph(GraphError::PrecedingTxNotCreated("peg-out")))
@@ -1520,7 +1553,7 @@ impl PegOutGraph {
 
     pub async fn kick_off_1(
         &mut self,
-        client: &AsyncClient,
+        client: &dyn BitcoinBackend,
         context: &OperatorContext,
         source_network_txid_commitment_secret: &WinternitzSecret,
         destination_network_txid_commitment_secret: &WinternitzSecret,
@@ -1566,13 +1599,13 @@ impl PegOutGraph {
                     NamedTx::for_tx(&self.peg_out_confirm_transaction, status.confirmed),
                 ]))),
             },
-            Err(e) => Err(Error::Esplora(e)),
+            Err(e) => Err(Error::Backend(e)),
         }
     }
 
     pub async fn challenge(
         &mut self,
-        client: &AsyncClient,
+        client: &dyn BitcoinBackend,
         crowdfundng_inputs: &Vec<InputWithScript<'_>>,
         keypair: &Keypair,
         output_script_pubkey: ScriptBuf,
@@ -1596,13 +1629,13 @@ impl PegOutGraph {
                     NamedTx::for_tx(&self.kick_off_1_transaction, status.confirmed),
                 ]))),
             },
-            Err(e) => Err(Error::Esplora(e)),
+            Err(e) => Err(Error::Backend(e)),
         }
     }
 
     pub async fn start_time(
         &mut self,
-        client: &AsyncClient,
+        client: &dyn BitcoinBackend,
         context: &OperatorContext,
         start_time_commitment_secret: &WinternitzSecret,
     ) -> Result<Transaction, Error> {
@@ -1626,13 +1659,13 @@ impl PegOutGraph {
                     NamedTx::for_tx(&self.kick_off_1_transaction, status.confirmed),
                 ]))),
             },
-            Err(e) => Err(Error::Esplora(e)),
+            Err(e) => Err(Error::Backend(e)),
         }
     }
 
     pub async fn start_time_timeout(
         &mut self,
-        client: &AsyncClient,
+        client: &dyn BitcoinBackend,
         output_script_pubkey: ScriptBuf,
     ) -> Result<Transaction, Error> {
         verify_if_not_mined(
@@ -1666,16 +1699,17 @@ impl PegOutGraph {
                     NamedTx::for_tx(&self.kick_off_1_transaction, status.confirmed),
                 ]))),
             },
-            Err(e) => Err(Error::Esplora(e)),
+            Err(e) => Err(Error::Backend(e)),
         }
     }
 
     pub async fn kick_off_2(
         &mut self,
-        client: &AsyncClient,
+        client: &dyn BitcoinBackend,
         context: &OperatorContext,
         superblock_commitment_secret: &WinternitzSecret,
         superblock_hash_commitment_secret: &WinternitzSecret,
+        superblock_header: &Header,
     ) -> Result<Transaction, Error> {
         verify_if_not_mined(client, self.kick_off_2_transaction.tx().compute_txid()).await?;
 
@@ -1692,16 +1726,15 @@ impl PegOutGraph {
                             block_height + self.connector_1.num_blocks_timelock_leaf_0 <= height
                         }) =>
                     {
-                        let superblock_header = find_superblock();
                         self.kick_off_2_transaction.sign(
                             context,
                             &self.connector_1,
                             &WinternitzSigningInputs {
-                                message: &get_superblock_message(&superblock_header),
+                                message: &get_superblock_message(superblock_header),
                                 signing_key: superblock_commitment_secret,
                             },
                             &WinternitzSigningInputs {
-                                message: &get_superblock_hash_message(&superblock_header),
+                                message: &get_superblock_hash_message(superblock_header),
                                 signing_key: superblock_hash_commitment_secret,
                             },
                         );
@@ -1715,13 +1748,13 @@ impl PegOutGraph {
                     NamedTx::for_tx(&self.kick_off_1_transaction, status.confirmed),
                 ]))),
             },
-            Err(e) => Err(Error::Esplora(e)),
+            Err(e) => Err(Error::Backend(e)),
         }
     }
 
     pub async fn kick_off_timeout(
         &mut self,
-        client: &AsyncClient,
+        client: &dyn BitcoinBackend,
         output_script_pubkey: ScriptBuf,
     ) -> Result<Transaction, Error> {
         verify_if_not_mined(
@@ -1755,11 +1788,14 @@ impl PegOutGraph {
                     NamedTx::for_tx(&self.kick_off_1_transaction, status.confirmed),
                 ]))),
             },
-            Err(e) => Err(Error::Esplora(e)),
+            Err(e) => Err(Error::Backend(e)),
         }
     }
 
-    pub async fn assert_initial(&mut self, client: &AsyncClient) -> Result<Transaction, Error> {
+    pub async fn assert_initial(
+        &mut self,
+        client: &dyn BitcoinBackend,
+    ) -> Result<Transaction, Error> {
         verify_if_not_mined(client, self.assert_initial_transaction.tx().compute_txid()).await?;
 
         let kick_off_2_txid = self.kick_off_2_transaction.tx().compute_txid();
@@ -1785,13 +1821,13 @@ impl PegOutGraph {
                     NamedTx::for_tx(&self.kick_off_2_transaction, status.confirmed),
                 ]))),
             },
-            Err(e) => Err(Error::Esplora(e)),
+            Err(e) => Err(Error::Backend(e)),
         }
     }
 
     pub async fn assert_commit_1(
         &mut self,
-        client: &AsyncClient,
+        client: &dyn BitcoinBackend,
         commitment_secrets: &HashMap<CommitmentMessageId, WinternitzSecret>,
         proof: &RawProof,
     ) -> Result<Transaction, Error> {
@@ -1804,7 +1840,7 @@ impl PegOutGraph {
             Ok(status) => match status.confirmed {
                 true => {
                     let (witness_for_commit1, _) =
-                        sign_assert_tx_with_groth16_proof(commitment_secrets, proof);
+                        sign_assert_tx_with_groth16_proof(commitment_secrets, proof)?;
                     self.assert_commit_1_transaction
                         .sign(&self.connector_e_1, witness_for_commit1.clone());
                     Ok(self.assert_commit_1_transaction.finalize())
@@ -1813,13 +1849,13 @@ impl PegOutGraph {
                     NamedTx::for_tx(&self.assert_initial_transaction, status.confirmed),
                 ]))),
             },
-            Err(e) => Err(Error::Esplora(e)),
+            Err(e) => Err(Error::Backend(e)),
         }
     }
 
     pub async fn assert_commit_2(
         &mut self,
-        client: &AsyncClient,
+        client: &dyn BitcoinBackend,
         commitment_secrets: &HashMap<CommitmentMessageId, WinternitzSecret>,
         proof: &RawProof,
     ) -> Result<Transaction, Error> {
@@ -1832,7 +1868,7 @@ impl PegOutGraph {
             Ok(status) => match status.confirmed {
                 true => {
                     let (_, witness_for_commit2) =
-                        sign_assert_tx_with_groth16_proof(commitment_secrets, proof);
+                        sign_assert_tx_with_groth16_proof(commitment_secrets, proof)?;
                     self.assert_commit_2_transaction
                         .sign(&self.connector_e_2, witness_for_commit2.clone());
                     Ok(self.assert_commit_2_transaction.finalize())
@@ -1841,11 +1877,14 @@ impl PegOutGraph {
                     NamedTx::for_tx(&self.assert_initial_transaction, status.confirmed),
                 ]))),
             },
-            Err(e) => Err(Error::Esplora(e)),
+            Err(e) => Err(Error::Backend(e)),
         }
     }
 
-    pub async fn assert_final(&mut self, client: &AsyncClient) -> Result<Transaction, Error> {
+    pub async fn assert_final(
+        &mut self,
+        client: &dyn BitcoinBackend,
+    ) -> Result<Transaction, Error> {
         verify_if_not_mined(client, self.assert_final_transaction.tx().compute_txid()).await?;
 
         let assert_initial_txid = self.assert_initial_transaction.tx().compute_txid();
@@ -1858,13 +1897,13 @@ impl PegOutGraph {
                     NamedTx::for_tx(&self.assert_initial_transaction, status.confirmed),
                 ]))),
             },
-            Err(e) => Err(Error::Esplora(e)),
+            Err(e) => Err(Error::Backend(e)),
         }
     }
 
     pub async fn disprove(
         &mut self,
-        client: &AsyncClient,
+        client: &dyn BitcoinBackend,
         output_script_pubkey: ScriptBuf,
         verifying_key: &ZkProofVerifyingKey,
     ) -> Result<Transaction, Error> {
@@ -1900,14 +1939,97 @@ impl PegOutGraph {
                     NamedTx::for_tx(&self.assert_final_transaction, status.confirmed),
                 ]))),
             },
-            Err(e) => Err(Error::Esplora(e)),
+            Err(e) => Err(Error::Backend(e)),
+        }
+    }
+
+    /// The broadcast but unconfirmed transaction to bump, with its fee and the output to be spent by
+    /// the CPFP transaction
+    pub async fn cpfp_parent(
+        &self,
+        client: &dyn BitcoinBackend,
+        parent: CpfpParent,
+    ) -> Result<(Transaction, Amount, usize), Error> {
+        let (name, txid, prev_outs, vout) = match parent {
+            CpfpParent::KickOff1 => cpfp_parent_with_anchor(&self.kick_off_1_transaction)?,
+            CpfpParent::KickOff2 => cpfp_parent_with_anchor(&self.kick_off_2_transaction)?,
+            CpfpParent::AssertInitial => cpfp_parent_with_anchor(&self.assert_initial_transaction)?,
+            CpfpParent::AssertFinal => cpfp_parent_with_anchor(&self.assert_final_transaction)?,
+            CpfpParent::DisproveChain => cpfp_parent_with_reward(&self.disprove_chain_transaction),
+            CpfpParent::Disprove => cpfp_parent_with_reward(&self.disprove_transaction),
+        };
+
+        match client.get_tx(&txid).await {
+            Ok(Some(tx)) => {
+                verify_if_not_mined(client, txid).await?;
+                let fee = tx_fee(&tx, &prev_outs);
+                Ok((tx, fee, vout))
+            }
+            Ok(None) => Err(Error::Graph(GraphError::PrecedingTxNotCreated(name))),
+            Err(e) => Err(Error::Backend(e)),
+        }
+    }
+
+    /// The superblock committed by the operator in the kick-off 2 transaction on chain
+    pub async fn committed_superblock(&self, client: &dyn BitcoinBackend) -> Result<Header, Error> {
+        let kick_off_2_txid = self.kick_off_2_transaction.tx().compute_txid();
+        match client.get_tx(&kick_off_2_txid).await {
+            Ok(Some(tx)) => {
+                // the witness starts with the Schnorr signature of the operator
+                let witness = tx.input[0].witness.to_vec();
+                let message =
+                    winternitz_message_from_witness(&witness[1..], SUPERBLOCK_MESSAGE_LENGTH);
+                deserialize(&message).map_err(|_| Error::Other("Invalid superblock commitment"))
+            }
+            Ok(None) => Err(Error::Graph(GraphError::PrecedingTxNotConfirmed(vec![
+                NamedTx::for_tx(&self.kick_off_2_transaction, false),
+            ]))),
+            Err(e) => Err(Error::Backend(e)),
+        }
+    }
+
+    /// The start time committed by the operator in the start time transaction, and the height of
+    /// the block confirming it
+    pub async fn committed_start_time(
+        &self,
+        client: &dyn BitcoinBackend,
+    ) -> Result<(u32, u32), Error> {
+        let start_time_txid = self.start_time_transaction.tx().compute_txid();
+        let status = client
+            .get_tx_status(&start_time_txid)
+            .await
+            .map_err(Error::Backend)?;
+        let (true, Some(block_height)) = (status.confirmed, status.block_height) else {
+            return Err(Error::Graph(GraphError::PrecedingTxNotConfirmed(vec![
+                NamedTx::for_tx(&self.start_time_transaction, false),
+            ])));
+        };
+        match client.get_tx(&start_time_txid).await {
+            Ok(Some(tx)) => {
+                // the witness starts with the Schnorr signature of the operator
+                let witness = tx.input[0].witness.to_vec();
+                let start_time = witness
+                    .get(1..)
+                    .map(|witness| {
+                        winternitz_message_from_witness(witness, START_TIME_MESSAGE_LENGTH)
+                    })
+                    .and_then(|message| message.try_into().ok())
+                    .map(u32::from_le_bytes)
+                    .ok_or(Error::Other("Invalid start time commitment"))?;
+                Ok((start_time, block_height))
+            }
+            Ok(None) => Err(Error::Graph(GraphError::PrecedingTxNotConfirmed(vec![
+                NamedTx::for_tx(&self.start_time_transaction, false),
+            ]))),
+            Err(e) => Err(Error::Backend(e)),
         }
     }
 
     pub async fn disprove_chain(
         &mut self,
-        client: &AsyncClient,
+        client: &dyn BitcoinBackend,
         output_script_pubkey: ScriptBuf,
+        disprove_sb: &Header,
     ) -> Result<Transaction, Error> {
         verify_if_not_mined(client, self.disprove_chain_transaction.tx().compute_txid()).await?;
 
@@ -1920,11 +2042,10 @@ impl PegOutGraph {
                     self.disprove_chain_transaction
                         .add_output(output_script_pubkey);
 
-                    // TODO: This must be a heavier superblock than the one the Operator committed in the KickOff2 tx.
-                    let disprove_sb = find_superblock();
-
+                    // The superblock must be heavier than the one the Operator committed in the KickOff2 tx,
+                    // see SuperblockTracker::heavier_than
                     self.disprove_chain_transaction.sign(
-                        &disprove_sb,
+                        disprove_sb,
                         self.start_time_transaction
                             .start_time_witness
                             .as_ref()
@@ -1945,11 +2066,11 @@ impl PegOutGraph {
                     NamedTx::for_tx(&self.kick_off_2_transaction, status.confirmed),
                 ]))),
             },
-            Err(e) => Err(Error::Esplora(e)),
+            Err(e) => Err(Error::Backend(e)),
         }
     }
 
-    pub async fn take_1(&mut self, client: &AsyncClient) -> Result<Transaction, Error> {
+    pub async fn take_1(&mut self, client: &dyn BitcoinBackend) -> Result<Transaction, Error> {
         verify_if_not_mined(client, self.take_1_transaction.tx().compute_txid()).await?;
         verify_if_not_mined(client, self.challenge_transaction.tx().compute_txid()).await?;
         verify_if_not_mined(client, self.assert_initial_transaction.tx().compute_txid()).await?;
@@ -1991,13 +2112,13 @@ impl PegOutGraph {
                     ]))),
                 }
             }
-            (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => Err(Error::Esplora(e)),
+            (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => Err(Error::Backend(e)),
         }
     }
 
     pub async fn take_2(
         &mut self,
-        client: &AsyncClient,
+        client: &dyn BitcoinBackend,
         context: &OperatorContext,
     ) -> Result<Transaction, Error> {
         verify_if_not_mined(client, self.take_2_transaction.tx().compute_txid()).await?;
@@ -2035,12 +2156,20 @@ impl PegOutGraph {
                     NamedTx::for_tx(&self.assert_final_transaction, assert_stat.confirmed),
                 ]))),
             },
-            (Err(e), _) | (_, Err(e)) => Err(Error::Esplora(e)),
+            (Err(e), _) | (_, Err(e)) => Err(Error::Backend(e)),
         }
     }
 
     pub fn is_peg_out_initiated(&self) -> bool { self.peg_out_chain_event.is_some() }
 
+    pub fn operator_public_key(&self) -> &PublicKey { &self.operator_public_key }
+
+    /// Whether the operator paid the withdrawal of the peg-in, the amount staying locked in its
+    /// collateral until take 1 or take 2 reimburses it
+    pub fn is_peg_out_fronted(&self) -> bool {
+        self.peg_out_transaction.is_some() || self.state.state() != PegOutState::Created
+    }
+
     pub fn min_crowdfunding_amount(&self) -> u64 {
         self.challenge_transaction.min_crowdfunding_amount()
     }
@@ -2073,123 +2202,6 @@ impl PegOutGraph {
         }
     }
 
-    async fn get_peg_out_statuses(
-        &self,
-        client: &AsyncClient,
-    ) -> (
-        Result<TxStatus, esplora_client::Error>,
-        Result<TxStatus, esplora_client::Error>,
-        Result<TxStatus, esplora_client::Error>,
-        Result<TxStatus, esplora_client::Error>,
-        Result<TxStatus, esplora_client::Error>,
-        Result<TxStatus, esplora_client::Error>,
-        Result<TxStatus, esplora_client::Error>,
-        Result<TxStatus, esplora_client::Error>,
-        Result<TxStatus, esplora_client::Error>,
-        Result<TxStatus, esplora_client::Error>,
-        Result<TxStatus, esplora_client::Error>,
-        Option<Result<TxStatus, esplora_client::Error>>,
-        Result<TxStatus, esplora_client::Error>,
-        Result<TxStatus, esplora_client::Error>,
-        Result<TxStatus, esplora_client::Error>,
-        Result<TxStatus, esplora_client::Error>,
-    ) {
-        let assert_initial_status = client
-            .get_tx_status(&self.assert_initial_transaction.tx().compute_txid())
-            .await;
-
-        let assert_commit_1_status = client
-            .get_tx_status(&self.assert_commit_1_transaction.tx().compute_txid())
-            .await;
-
-        let assert_commit_2_status = client
-            .get_tx_status(&self.assert_commit_2_transaction.tx().compute_txid())
-            .await;
-
-        let assert_final_status = client
-            .get_tx_status(&self.assert_final_transaction.tx().compute_txid())
-            .await;
-
-        let challenge_status = client
-            .get_tx_status(&self.challenge_transaction.tx().compute_txid())
-            .await;
-
-        let disprove_chain_status = client
-            .get_tx_status(&self.disprove_chain_transaction.tx().compute_txid())
-            .await;
-
-        let disprove_status = client
-            .get_tx_status(&self.disprove_transaction.tx().compute_txid())
-            .await;
-
-        let peg_out_confirm_status = client
-            .get_tx_status(&self.peg_out_confirm_transaction.tx().compute_txid())
-            .await;
-
-        let kick_off_1_status = client
-            .get_tx_status(&self.kick_off_1_transaction.tx().compute_txid())
-            .await;
-
-        let kick_off_2_status = client
-            .get_tx_status(&self.kick_off_2_transaction.tx().compute_txid())
-            .await;
-
-        let kick_off_timeout_status = client
-            .get_tx_status(&self.kick_off_timeout_transaction.tx().compute_txid())
-            .await;
-
-        let mut peg_out_status: Option<Result<TxStatus, esplora_client::Error>> = None;
-        if self.peg_out_transaction.is_some() {
-            peg_out_status = Some(
-                client
-                    .get_tx_status(
-                        &self
-                            .peg_out_transaction
-                            .as_ref()
-                            .unwrap()
-                            .tx()
-                            .compute_txid(),
-                    )
-                    .await,
-            );
-        }
-
-        let start_time_timeout_status = client
-            .get_tx_status(&self.start_time_timeout_transaction.tx().compute_txid())
-            .await;
-
-        let start_time_status = client
-            .get_tx_status(&self.start_time_transaction.tx().compute_txid())
-            .await;
-
-        let take_1_status = client
-            .get_tx_status(&self.take_1_transaction.tx().compute_txid())
-            .await;
-
-        let take_2_status = client
-            .get_tx_status(&self.take_2_transaction.tx().compute_txid())
-            .await;
-
-        (
-            assert_initial_status,
-            assert_commit_1_status,
-            assert_commit_2_status,
-            assert_final_status,
-            challenge_status,
-            disprove_chain_status,
-            disprove_status,
-            peg_out_confirm_status,
-            kick_off_1_status,
-            kick_off_2_status,
-            kick_off_timeout_status,
-            peg_out_status,
-            start_time_timeout_status,
-            start_time_status,
-            take_1_status,
-            take_2_status,
-        )
-    }
-
     pub fn validate(&self) -> bool {
         let mut ret_val = true;
         let peg_out_graph = self.new_for_validation();
@@ -2351,6 +2363,26 @@ impl PegOutGraph {
 
         self.take_2_transaction
             .merge(&source_peg_out_graph.take_2_transaction);
+
+        // the other operators learn that the withdrawal was fronted
+        if self.peg_out_chain_event.is_none() {
+            self.peg_out_chain_event = source_peg_out_graph.peg_out_chain_event.clone();
+        }
+        if self.peg_out_transaction.is_none() {
+            self.peg_out_transaction = source_peg_out_graph.peg_out_transaction.clone();
+        }
+
+        // nonces evicted from either copy must not come back with the other one
+        self.blocked |= source_peg_out_graph.blocked;
+        for nonce in &source_peg_out_graph.evicted_nonces {
+            if !self.evicted_nonces.contains(nonce) {
+                self.evicted_nonces.push(nonce.clone());
+            }
+        }
+        let evicted_nonces = self.evicted_nonces.clone();
+        for tx_wrapper in self.all_presigned_txs_mut() {
+            tx_wrapper.evict_nonces(&evicted_nonces);
+        }
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -2489,21 +2521,69 @@ impl PegOutGraph {
     }
 
     pub fn has_all_nonces_of(&self, context: &VerifierContext) -> bool {
-        self.all_presigned_txs()
-            .all(|x| x.has_nonces_for(context.verifier_public_key))
+        self.all_presigned_txs().all(|x| {
+            !x.needs_nonces_from(
+                context.verifier_public_key,
+                &context.n_of_n_public_keys,
+                context.frost_key_package.as_ref(),
+            )
+        })
     }
-    pub fn has_all_nonces(&self, verifier_pubkeys: &[PublicKey]) -> bool {
+    pub fn has_all_nonces(
+        &self,
+        verifier_pubkeys: &[PublicKey],
+        frost_key_package: Option<&FrostKeyPackage>,
+    ) -> bool {
         self.all_presigned_txs()
-            .all(|x| x.has_all_nonces(verifier_pubkeys))
+            .all(|x| x.has_signing_nonces(verifier_pubkeys, frost_key_package))
     }
     pub fn has_all_signatures_of(&self, context: &VerifierContext) -> bool {
-        self.all_presigned_txs()
-            .all(|x| x.has_signatures_for(context.verifier_public_key))
+        self.all_presigned_txs().all(|x| {
+            !x.needs_signatures_from(
+                context.verifier_public_key,
+                &context.n_of_n_public_keys,
+                context.frost_key_package.as_ref(),
+            )
+        })
     }
-    pub fn has_all_signatures(&self, verifier_pubkeys: &[PublicKey]) -> bool {
+    pub fn has_all_signatures(
+        &self,
+        verifier_pubkeys: &[PublicKey],
+        frost_key_package: Option<&FrostKeyPackage>,
+    ) -> bool {
         self.all_presigned_txs()
-            .all(|x| x.has_all_signatures(verifier_pubkeys))
+            .all(|x| x.has_signing_signatures(verifier_pubkeys, frost_key_package))
     }
+    /// Whether a verifier was evicted from the graph and not all the signers signed it again
+    pub fn is_blocked(
+        &self,
+        verifier_pubkeys: &[PublicKey],
+        frost_key_package: Option<&FrostKeyPackage>,
+    ) -> bool {
+        self.blocked && !self.has_all_signatures(verifier_pubkeys, frost_key_package)
+    }
+}
+
+fn cpfp_parent_with_anchor(
+    tx: &(impl BaseTransaction + PreSignedTransaction),
+) -> Result<(&'static str, Txid, Vec<TxOut>, usize), Error> {
+    let txid = tx.tx().compute_txid();
+    let vout = find_anchor_output(tx.tx()).ok_or(Error::Transaction(
+        TransactionError::AnchorOutputNotFound(txid),
+    ))?;
+    Ok((tx.name(), txid, tx.prev_outs().clone(), vout))
+}
+
+// the reward output is the one added by the verifier after the pre-signed ones
+fn cpfp_parent_with_reward(
+    tx: &(impl BaseTransaction + PreSignedTransaction),
+) -> (&'static str, Txid, Vec<TxOut>, usize) {
+    (
+        tx.name(),
+        tx.tx().compute_txid(),
+        tx.prev_outs().clone(),
+        tx.tx().output.len() - 1,
+    )
 }
 
 pub fn generate_id(peg_in_graph: &PegInGraph, operator_public_key: &PublicKey) -> String {
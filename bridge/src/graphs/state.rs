@@ -0,0 +1,759 @@
+use bitcoin::Txid;
+use esplora_client::TxStatus;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use strum::Display;
+
+use crate::{
+    client::backend::base::BitcoinBackend,
+    error::{Error, GraphError},
+};
+
+/// State of a graph, moved forward by the confirmation of its transactions
+pub trait GraphState: Copy + Default + Display {
+    type Transaction: Copy + Display + PartialEq;
+    type Timelocks;
+
+    /// Transactions that can be confirmed next, possibly once their timelock expires
+    fn next_transactions(&self) -> Vec<Self::Transaction>;
+
+    /// State after the transaction is confirmed at the block height, `None` if the transaction
+    /// cannot follow this state
+    fn transition(&self, transaction: Self::Transaction, block_height: u32) -> Option<Self>;
+
+    /// Block height from which the next transaction can be confirmed, `None` if not timelocked
+    fn timelock_expiry(
+        &self,
+        transaction: Self::Transaction,
+        timelocks: &Self::Timelocks,
+    ) -> Option<u32>;
+
+    fn is_final(&self) -> bool { self.next_transactions().is_empty() }
+}
+
+/// Confirmation of a graph transaction that moved the graph to its next state
+#[derive(Serialize, Deserialize, Eq, PartialEq, Clone, Debug)]
+pub struct StateEvent<T> {
+    pub transaction: T,
+    pub txid: Txid,
+    pub block_height: u32,
+}
+
+/// The state of a graph with the log of the events that led to it
+///
+/// The state is always the replay of the event log from the initial state, which makes it possible
+/// to roll back the events invalidated by a reorg and to sync again from the chain.
+#[derive(Serialize, Deserialize, Eq, PartialEq, Clone, Debug, Default)]
+#[serde(bound(
+    serialize = "S: Serialize, S::Transaction: Serialize",
+    deserialize = "S: Deserialize<'de>, S::Transaction: Deserialize<'de>"
+))]
+pub struct StateMachine<S: GraphState> {
+    state: S,
+    events: Vec<StateEvent<S::Transaction>>,
+}
+
+impl<S: GraphState> StateMachine<S> {
+    pub fn state(&self) -> S { self.state }
+
+    pub fn events(&self) -> &Vec<StateEvent<S::Transaction>> { &self.events }
+
+    pub fn apply(
+        &mut self,
+        transaction: S::Transaction,
+        txid: Txid,
+        block_height: u32,
+    ) -> Result<(), Error> {
+        match self.state.transition(transaction, block_height) {
+            Some(state) => {
+                self.state = state;
+                self.events.push(StateEvent {
+                    transaction,
+                    txid,
+                    block_height,
+                });
+                Ok(())
+            }
+            None => Err(Error::Graph(GraphError::InvalidStateTransition(
+                self.state.to_string(),
+                transaction.to_string(),
+            ))),
+        }
+    }
+
+    /// Drops the events confirmed at or above the block height and replays the remaining ones
+    pub fn rewind(&mut self, block_height: u32) {
+        let events = std::mem::take(&mut self.events);
+        self.state = S::default();
+        for event in events
+            .into_iter()
+            .filter(|event| event.block_height < block_height)
+        {
+            self.apply(event.transaction, event.txid, event.block_height)
+                .expect("Replayed events must be valid transitions");
+        }
+    }
+
+    /// Next transactions with the block height from which they can be confirmed
+    pub fn deadlines(&self, timelocks: &S::Timelocks) -> Vec<(S::Transaction, Option<u32>)> {
+        self.state
+            .next_transactions()
+            .into_iter()
+            .map(|transaction| {
+                (
+                    transaction,
+                    self.state.timelock_expiry(transaction, timelocks),
+                )
+            })
+            .collect()
+    }
+
+    /// The state is final and none of its events can be reorged out
+    pub fn is_settled(&self, finalized_height: Option<u32>) -> bool {
+        self.state.is_final()
+            && finalized_height.is_some_and(|finalized_height| {
+                self.events
+                    .iter()
+                    .all(|event| event.block_height <= finalized_height)
+            })
+    }
+
+    pub fn is_available(
+        &self,
+        transaction: S::Transaction,
+        block_height: u32,
+        timelocks: &S::Timelocks,
+    ) -> bool {
+        self.state.next_transactions().contains(&transaction)
+            && self
+                .state
+                .timelock_expiry(transaction, timelocks)
+                .is_none_or(|expiry| expiry <= block_height)
+    }
+
+    /// Rolls back the events whose transaction is no longer confirmed at the same height, then
+    /// applies the next transactions confirmed on chain until none is found
+    ///
+    /// Events confirmed at or below the finalized height are not checked again. Transactions
+    /// without a txid yet are skipped. Backend errors are treated as no change, so that an
+    /// unavailable backend neither rolls back nor moves forward the state.
+    pub async fn sync(
+        &mut self,
+        client: &dyn BitcoinBackend,
+        finalized_height: Option<u32>,
+        txid_of: impl Fn(S::Transaction) -> Option<Txid>,
+    ) {
+        if self.is_settled(finalized_height) {
+            return;
+        }
+
+        let unfinalized_events = self.events.clone().into_iter().filter(|event| {
+            finalized_height.is_none_or(|finalized_height| event.block_height > finalized_height)
+        });
+        for event in unfinalized_events {
+            if client
+                .get_tx_status(&event.txid)
+                .await
+                .is_ok_and(|status| status.block_height != Some(event.block_height))
+            {
+                self.rewind(event.block_height);
+                break;
+            }
+        }
+
+        'sync: loop {
+            for transaction in self.state.next_transactions() {
+                let Some(txid) = txid_of(transaction) else {
+                    continue;
+                };
+                if let Ok(TxStatus {
+                    confirmed: true,
+                    block_height: Some(block_height),
+                    ..
+                }) = client.get_tx_status(&txid).await
+                {
+                    self.apply(transaction, txid, block_height)
+                        .expect("Next transactions must be valid transitions");
+                    continue 'sync;
+                }
+            }
+            break;
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Eq, PartialEq, Clone, Copy, Debug, Display)]
+pub enum PegInTransactionType {
+    Deposit,
+    Confirm,
+    Refund,
+}
+
+#[derive(Serialize, Deserialize, Eq, PartialEq, Clone, Copy, Debug, Default)]
+pub enum PegInState {
+    #[default]
+    Created,
+    Deposited {
+        block_height: u32,
+    },
+    Confirmed,
+    Refunded,
+}
+
+pub struct PegInTimelocks {
+    pub refund: u32,
+}
+
+impl Display for PegInState {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            PegInState::Created => write!(f, "Peg-in deposit not confirmed"),
+            PegInState::Deposited { block_height } => {
+                write!(f, "Peg-in deposit confirmed at block {block_height}")
+            }
+            PegInState::Confirmed => write!(f, "Peg-in confirmed"),
+            PegInState::Refunded => write!(f, "Peg-in refunded"),
+        }
+    }
+}
+
+impl GraphState for PegInState {
+    type Transaction = PegInTransactionType;
+    type Timelocks = PegInTimelocks;
+
+    fn next_transactions(&self) -> Vec<PegInTransactionType> {
+        match self {
+            PegInState::Created => vec![PegInTransactionType::Deposit],
+            PegInState::Deposited { .. } => {
+                vec![PegInTransactionType::Confirm, PegInTransactionType::Refund]
+            }
+            PegInState::Confirmed | PegInState::Refunded => vec![],
+        }
+    }
+
+    fn transition(&self, transaction: PegInTransactionType, block_height: u32) -> Option<Self> {
+        match (self, transaction) {
+            (PegInState::Created, PegInTransactionType::Deposit) => {
+                Some(PegInState::Deposited { block_height })
+            }
+            (PegInState::Deposited { .. }, PegInTransactionType::Confirm) => {
+                Some(PegInState::Confirmed)
+            }
+            (PegInState::Deposited { .. }, PegInTransactionType::Refund) => {
+                Some(PegInState::Refunded)
+            }
+            _ => None,
+        }
+    }
+
+    fn timelock_expiry(
+        &self,
+        transaction: PegInTransactionType,
+        timelocks: &PegInTimelocks,
+    ) -> Option<u32> {
+        match (self, transaction) {
+            (PegInState::Deposited { block_height }, PegInTransactionType::Refund) => {
+                Some(block_height + timelocks.refund)
+            }
+            _ => None,
+        }
+    }
+}
+
+pub type PegInStateMachine = StateMachine<PegInState>;
+
+#[derive(Serialize, Deserialize, Eq, PartialEq, Clone, Copy, Debug, Display)]
+pub enum PegOutTransactionType {
+    PegOut,
+    PegOutConfirm,
+    KickOff1,
+    Challenge,
+    StartTime,
+    StartTimeTimeout,
+    KickOffTimeout,
+    KickOff2,
+    Take1,
+    DisproveChain,
+    AssertInitial,
+    AssertCommit1,
+    AssertCommit2,
+    AssertFinal,
+    Take2,
+    Disprove,
+}
+
+/// The peg-out paid to the withdrawer is not spent by the peg-out confirm, whose txid is the only
+/// one known to the verifiers, so the graph can be confirmed without the peg-out.
+///
+/// The challenge spends the connector A output of kick off 1, it can be confirmed until take 1
+/// spends that output and is tracked by the states in between.
+#[derive(Serialize, Deserialize, Eq, PartialEq, Clone, Copy, Debug, Default)]
+pub enum PegOutState {
+    #[default]
+    Created,
+    PegOutPaid,
+    PegOutConfirmed,
+    KickedOff1 {
+        block_height: u32,
+        start_time_committed: bool,
+        challenged: bool,
+    },
+    KickedOff2 {
+        block_height: u32,
+        challenged: bool,
+    },
+    AssertInitialized {
+        challenged: bool,
+        commit_1: bool,
+        commit_2: bool,
+    },
+    Asserted {
+        block_height: u32,
+        challenged: bool,
+    },
+    StartTimeTimedOut,
+    KickOffTimedOut,
+    Taken1,
+    ChainDisproven,
+    Taken2,
+    Disproven,
+}
+
+/// Relative timelocks in blocks of the peg-out transactions
+pub struct PegOutTimelocks {
+    pub start_time_timeout: u32,
+    pub kick_off_timeout: u32,
+    pub kick_off_2: u32,
+    pub take_1: u32,
+    pub assert_initial: u32,
+    pub take_2: u32,
+}
+
+impl PegOutState {
+    pub fn is_challenged(&self) -> bool {
+        matches!(
+            self,
+            PegOutState::KickedOff1 {
+                challenged: true,
+                ..
+            } | PegOutState::KickedOff2 {
+                challenged: true,
+                ..
+            } | PegOutState::AssertInitialized {
+                challenged: true,
+                ..
+            } | PegOutState::Asserted {
+                challenged: true,
+                ..
+            }
+        )
+    }
+
+    /// The operator is reimbursed with take 1 or take 2
+    pub fn is_reimbursed(&self) -> bool {
+        matches!(self, PegOutState::Taken1 | PegOutState::Taken2)
+    }
+
+    fn challenge(self) -> Self {
+        match self {
+            PegOutState::KickedOff1 {
+                block_height,
+                start_time_committed,
+                ..
+            } => PegOutState::KickedOff1 {
+                block_height,
+                start_time_committed,
+                challenged: true,
+            },
+            PegOutState::KickedOff2 { block_height, .. } => PegOutState::KickedOff2 {
+                block_height,
+                challenged: true,
+            },
+            PegOutState::AssertInitialized {
+                commit_1, commit_2, ..
+            } => PegOutState::AssertInitialized {
+                challenged: true,
+                commit_1,
+                commit_2,
+            },
+            PegOutState::Asserted { block_height, .. } => PegOutState::Asserted {
+                block_height,
+                challenged: true,
+            },
+            state => state,
+        }
+    }
+}
+
+impl Display for PegOutState {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let challenged = if self.is_challenged() {
+            ", challenged"
+        } else {
+            ""
+        };
+        match self {
+            PegOutState::Created => write!(f, "Peg-out not paid"),
+            PegOutState::PegOutPaid => write!(f, "Peg-out paid"),
+            PegOutState::PegOutConfirmed => write!(f, "Peg-out confirmed"),
+            PegOutState::KickedOff1 {
+                block_height,
+                start_time_committed,
+                ..
+            } => {
+                write!(f, "Kick off 1 confirmed at block {block_height}")?;
+                if *start_time_committed {
+                    write!(f, ", start time committed")?;
+                }
+                write!(f, "{challenged}")
+            }
+            PegOutState::KickedOff2 { block_height, .. } => {
+                write!(
+                    f,
+                    "Kick off 2 confirmed at block {block_height}{challenged}"
+                )
+            }
+            PegOutState::AssertInitialized {
+                commit_1, commit_2, ..
+            } => write!(
+                f,
+                "Assert initial confirmed, assert commit 1 {}, assert commit 2 {}{challenged}",
+                if *commit_1 { "confirmed" } else { "pending" },
+                if *commit_2 { "confirmed" } else { "pending" },
+            ),
+            PegOutState::Asserted { block_height, .. } => {
+                write!(
+                    f,
+                    "Assert final confirmed at block {block_height}{challenged}"
+                )
+            }
+            PegOutState::StartTimeTimedOut => write!(f, "Start time timed out"),
+            PegOutState::KickOffTimedOut => write!(f, "Kick off timed out"),
+            PegOutState::Taken1 => write!(f, "Operator reimbursed with take 1"),
+            PegOutState::ChainDisproven => write!(f, "Chain disproven"),
+            PegOutState::Taken2 => write!(f, "Operator reimbursed with take 2"),
+            PegOutState::Disproven => write!(f, "Assertions disproven"),
+        }
+    }
+}
+
+impl GraphState for PegOutState {
+    type Transaction = PegOutTransactionType;
+    type Timelocks = PegOutTimelocks;
+
+    fn next_transactions(&self) -> Vec<PegOutTransactionType> {
+        let mut transactions = match self {
+            PegOutState::Created => {
+                vec![
+                    PegOutTransactionType::PegOut,
+                    PegOutTransactionType::PegOutConfirm,
+                ]
+            }
+            PegOutState::PegOutPaid => vec![PegOutTransactionType::PegOutConfirm],
+            PegOutState::PegOutConfirmed => vec![PegOutTransactionType::KickOff1],
+            PegOutState::KickedOff1 {
+                start_time_committed,
+                ..
+            } => {
+                let mut transactions = vec![];
+                if !start_time_committed {
+                    transactions.push(PegOutTransactionType::StartTime);
+                    transactions.push(PegOutTransactionType::StartTimeTimeout);
+                }
+                transactions.push(PegOutTransactionType::KickOffTimeout);
+                transactions.push(PegOutTransactionType::KickOff2);
+                transactions
+            }
+            PegOutState::KickedOff2 { challenged, .. } => {
+                let mut transactions = vec![
+                    PegOutTransactionType::DisproveChain,
+                    PegOutTransactionType::AssertInitial,
+                ];
+                if !challenged {
+                    transactions.push(PegOutTransactionType::Take1);
+                }
+                transactions
+            }
+            PegOutState::AssertInitialized {
+                commit_1, commit_2, ..
+            } => match (commit_1, commit_2) {
+                (true, true) => vec![PegOutTransactionType::AssertFinal],
+                (true, false) => vec![PegOutTransactionType::AssertCommit2],
+                (false, true) => vec![PegOutTransactionType::AssertCommit1],
+                (false, false) => vec![
+                    PegOutTransactionType::AssertCommit1,
+                    PegOutTransactionType::AssertCommit2,
+                ],
+            },
+            PegOutState::Asserted { .. } => {
+                vec![
+                    PegOutTransactionType::Disprove,
+                    PegOutTransactionType::Take2,
+                ]
+            }
+            PegOutState::StartTimeTimedOut
+            | PegOutState::KickOffTimedOut
+            | PegOutState::Taken1
+            | PegOutState::ChainDisproven
+            | PegOutState::Taken2
+            | PegOutState::Disproven => vec![],
+        };
+        let can_be_challenged = matches!(
+            self,
+            PegOutState::KickedOff1 { .. }
+                | PegOutState::KickedOff2 { .. }
+                | PegOutState::AssertInitialized { .. }
+                | PegOutState::Asserted { .. }
+        );
+        if can_be_challenged && !self.is_challenged() {
+            transactions.push(PegOutTransactionType::Challenge);
+        }
+        transactions
+    }
+
+    fn transition(&self, transaction: PegOutTransactionType, block_height: u32) -> Option<Self> {
+        if !self.next_transactions().contains(&transaction) {
+            return None;
+        }
+        let state = match (*self, transaction) {
+            (state, PegOutTransactionType::Challenge) => state.challenge(),
+            (_, PegOutTransactionType::PegOut) => PegOutState::PegOutPaid,
+            (_, PegOutTransactionType::PegOutConfirm) => PegOutState::PegOutConfirmed,
+            (_, PegOutTransactionType::KickOff1) => PegOutState::KickedOff1 {
+                block_height,
+                start_time_committed: false,
+                challenged: false,
+            },
+            (
+                PegOutState::KickedOff1 {
+                    block_height,
+                    challenged,
+                    ..
+                },
+                PegOutTransactionType::StartTime,
+            ) => PegOutState::KickedOff1 {
+                block_height,
+                start_time_committed: true,
+                challenged,
+            },
+            (_, PegOutTransactionType::StartTimeTimeout) => PegOutState::StartTimeTimedOut,
+            (_, PegOutTransactionType::KickOffTimeout) => PegOutState::KickOffTimedOut,
+            (state, PegOutTransactionType::KickOff2) => PegOutState::KickedOff2 {
+                block_height,
+                challenged: state.is_challenged(),
+            },
+            (_, PegOutTransactionType::Take1) => PegOutState::Taken1,
+            (_, PegOutTransactionType::DisproveChain) => PegOutState::ChainDisproven,
+            (state, PegOutTransactionType::AssertInitial) => PegOutState::AssertInitialized {
+                challenged: state.is_challenged(),
+                commit_1: false,
+                commit_2: false,
+            },
+            (
+                PegOutState::AssertInitialized {
+                    challenged,
+                    commit_2,
+                    ..
+                },
+                PegOutTransactionType::AssertCommit1,
+            ) => PegOutState::AssertInitialized {
+                challenged,
+                commit_1: true,
+                commit_2,
+            },
+            (
+                PegOutState::AssertInitialized {
+                    challenged,
+                    commit_1,
+                    ..
+                },
+                PegOutTransactionType::AssertCommit2,
+            ) => PegOutState::AssertInitialized {
+                challenged,
+                commit_1,
+                commit_2: true,
+            },
+            (state, PegOutTransactionType::AssertFinal) => PegOutState::Asserted {
+                block_height,
+                challenged: state.is_challenged(),
+            },
+            (_, PegOutTransactionType::Take2) => PegOutState::Taken2,
+            (_, PegOutTransactionType::Disprove) => PegOutState::Disproven,
+            _ => return None,
+        };
+        Some(state)
+    }
+
+    fn timelock_expiry(
+        &self,
+        transaction: PegOutTransactionType,
+        timelocks: &PegOutTimelocks,
+    ) -> Option<u32> {
+        match (self, transaction) {
+            (
+                PegOutState::KickedOff1 { block_height, .. },
+                PegOutTransactionType::StartTimeTimeout,
+            ) => Some(block_height + timelocks.start_time_timeout),
+            (
+                PegOutState::KickedOff1 { block_height, .. },
+                PegOutTransactionType::KickOffTimeout,
+            ) => Some(block_height + timelocks.kick_off_timeout),
+            (PegOutState::KickedOff1 { block_height, .. }, PegOutTransactionType::KickOff2) => {
+                Some(block_height + timelocks.kick_off_2)
+            }
+            (PegOutState::KickedOff2 { block_height, .. }, PegOutTransactionType::Take1) => {
+                Some(block_height + timelocks.take_1)
+            }
+            (
+                PegOutState::KickedOff2 { block_height, .. },
+                PegOutTransactionType::AssertInitial,
+            ) => Some(block_height + timelocks.assert_initial),
+            (PegOutState::Asserted { block_height, .. }, PegOutTransactionType::Take2) => {
+                Some(block_height + timelocks.take_2)
+            }
+            _ => None,
+        }
+    }
+}
+
+pub type PegOutStateMachine = StateMachine<PegOutState>;
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::hashes::Hash;
+
+    use super::*;
+
+    const TIMELOCKS: PegOutTimelocks = PegOutTimelocks {
+        start_time_timeout: 1,
+        kick_off_timeout: 20,
+        kick_off_2: 10,
+        take_1: 5,
+        assert_initial: 3,
+        take_2: 4,
+    };
+
+    fn txid(n: u8) -> Txid {
+        Txid::from_byte_array([n; 32])
+    }
+
+    fn apply_all(machine: &mut PegOutStateMachine, transactions: &[(PegOutTransactionType, u32)]) {
+        for (i, (transaction, block_height)) in transactions.iter().enumerate() {
+            machine
+                .apply(*transaction, txid(i as u8), *block_height)
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_peg_out_state_machine_challenged_path() {
+        let mut machine = PegOutStateMachine::default();
+        apply_all(
+            &mut machine,
+            &[
+                (PegOutTransactionType::PegOut, 100),
+                (PegOutTransactionType::PegOutConfirm, 101),
+                (PegOutTransactionType::KickOff1, 102),
+                (PegOutTransactionType::StartTime, 103),
+                (PegOutTransactionType::Challenge, 104),
+                (PegOutTransactionType::KickOff2, 112),
+            ],
+        );
+        assert_eq!(
+            machine.state(),
+            PegOutState::KickedOff2 {
+                block_height: 112,
+                challenged: true
+            }
+        );
+        // take 1 spends the output of kick off 1 spent by the challenge
+        assert!(!machine.is_available(PegOutTransactionType::Take1, 200, &TIMELOCKS));
+        assert!(!machine.is_available(PegOutTransactionType::AssertInitial, 114, &TIMELOCKS));
+        assert!(machine.is_available(PegOutTransactionType::AssertInitial, 115, &TIMELOCKS));
+        assert!(machine.is_available(PegOutTransactionType::DisproveChain, 112, &TIMELOCKS));
+
+        apply_all(
+            &mut machine,
+            &[
+                (PegOutTransactionType::AssertInitial, 115),
+                (PegOutTransactionType::AssertCommit2, 116),
+                (PegOutTransactionType::AssertCommit1, 116),
+                (PegOutTransactionType::AssertFinal, 117),
+            ],
+        );
+        assert_eq!(
+            machine.deadlines(&TIMELOCKS),
+            vec![
+                (PegOutTransactionType::Disprove, None),
+                (PegOutTransactionType::Take2, Some(121))
+            ]
+        );
+        apply_all(&mut machine, &[(PegOutTransactionType::Take2, 121)]);
+        assert!(machine.state().is_final());
+        assert!(machine.state().is_reimbursed());
+        assert_eq!(machine.events().len(), 11);
+    }
+
+    #[test]
+    fn test_peg_out_state_machine_invalid_transitions() {
+        let mut machine = PegOutStateMachine::default();
+        assert!(machine
+            .apply(PegOutTransactionType::KickOff1, txid(0), 100)
+            .is_err());
+        apply_all(
+            &mut machine,
+            &[
+                (PegOutTransactionType::PegOut, 100),
+                (PegOutTransactionType::PegOutConfirm, 101),
+                (PegOutTransactionType::KickOff1, 102),
+                (PegOutTransactionType::StartTime, 103),
+            ],
+        );
+        // the start time output is spent by the start time transaction
+        assert!(machine
+            .apply(PegOutTransactionType::StartTimeTimeout, txid(0), 104)
+            .is_err());
+        assert!(machine
+            .apply(PegOutTransactionType::AssertFinal, txid(0), 104)
+            .is_err());
+        apply_all(
+            &mut machine,
+            &[(PegOutTransactionType::KickOffTimeout, 122)],
+        );
+        assert_eq!(machine.state(), PegOutState::KickOffTimedOut);
+        assert!(machine.state().next_transactions().is_empty());
+        assert!(!machine.state().is_reimbursed());
+        // final once its last transaction cannot be reorged out
+        assert!(!machine.is_settled(None));
+        assert!(!machine.is_settled(Some(121)));
+        assert!(machine.is_settled(Some(122)));
+    }
+
+    #[test]
+    fn test_state_machine_rewind() {
+        let mut machine = PegOutStateMachine::default();
+        apply_all(
+            &mut machine,
+            &[
+                (PegOutTransactionType::PegOut, 100),
+                (PegOutTransactionType::PegOutConfirm, 101),
+                (PegOutTransactionType::KickOff1, 102),
+                (PegOutTransactionType::Challenge, 102),
+                (PegOutTransactionType::StartTime, 103),
+            ],
+        );
+        machine.rewind(102);
+        assert_eq!(machine.state(), PegOutState::PegOutConfirmed);
+        assert_eq!(machine.events().len(), 2);
+
+        let mut peg_in_machine = PegInStateMachine::default();
+        peg_in_machine
+            .apply(PegInTransactionType::Deposit, txid(0), 100)
+            .unwrap();
+        let timelocks = PegInTimelocks { refund: 10 };
+        assert!(!peg_in_machine.is_available(PegInTransactionType::Refund, 109, &timelocks));
+        assert!(peg_in_machine.is_available(PegInTransactionType::Refund, 110, &timelocks));
+        peg_in_machine.rewind(100);
+        assert_eq!(peg_in_machine.state(), PegInState::Created);
+    }
+}
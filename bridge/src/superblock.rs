@@ -1,4 +1,4 @@
-use std::{mem::size_of, str::FromStr};
+use std::{cmp::Ordering, mem::size_of, str::FromStr};
 
 use bitcoin::{
     block::{Header, Version},
@@ -7,9 +7,13 @@ use bitcoin::{
     BlockHash, CompactTarget, Network, TxMerkleNode,
 };
 use bitcoin_script::{script, Script};
+use serde::{Deserialize, Serialize};
 
 use bitvm::pseudo::NMUL;
 
+/// Length in seconds of the superblock measurement period following the start time
+pub const SUPERBLOCK_MEASUREMENT_PERIOD: u32 = 60 * 60 * 24 * 14;
+
 /*
   TODO: Implement selecting a block that marks the start of a superblock measurement period
   that lasts for the period ∆C (e.g. 2000 blocks), during which the operator must observe
@@ -23,7 +27,7 @@ pub fn get_start_time_block_number(network: Network) -> u32 {
     }
 }
 
-// TODO: Replace with a real superblock
+/// A mainnet header, standing in for a superblock in the tests
 pub fn find_superblock() -> Header {
     Header {
         version: Version::from_consensus(0x200d2000),
@@ -57,3 +61,177 @@ pub fn extract_superblock_ts_from_header() -> Script {
         for _ in 1..4 {  { NMUL(1 << 8) } OP_ADD }
     }
 }
+
+/// Compares the weights of the headers, the header with the lower hash being the heavier
+pub fn compare_superblock_weight(a: &Header, b: &Header) -> Ordering {
+    // hashes are compared as little endian numbers, the same way as the disprove chain script
+    let mut a_hash = a.block_hash().to_byte_array();
+    let mut b_hash = b.block_hash().to_byte_array();
+    a_hash.reverse();
+    b_hash.reverse();
+    b_hash.cmp(&a_hash)
+}
+
+pub fn is_heavier_superblock(a: &Header, b: &Header) -> bool {
+    compare_superblock_weight(a, b) == Ordering::Greater
+}
+
+/// Source of the headers of the main chain, e.g. a node
+pub trait HeaderSource {
+    /// The header at the height, none after the tip of the chain
+    fn header_at(&self, height: u32) -> Option<Header>;
+}
+
+/// Headers of the main chain kept in memory, standing in for a node
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LocalHeaderSource {
+    /// Height of the first header
+    pub base_height: u32,
+    pub headers: Vec<Header>,
+}
+
+impl LocalHeaderSource {
+    pub fn new(base_height: u32) -> Self {
+        LocalHeaderSource {
+            base_height,
+            headers: vec![],
+        }
+    }
+
+    pub fn push(&mut self, header: Header) { self.headers.push(header); }
+}
+
+impl HeaderSource for LocalHeaderSource {
+    fn header_at(&self, height: u32) -> Option<Header> {
+        height
+            .checked_sub(self.base_height)
+            .and_then(|index| self.headers.get(index as usize))
+            .copied()
+    }
+}
+
+/// Tracks the heaviest superblock of the measurement period following the committed start time
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SuperblockTracker {
+    pub start_time: u32,
+    /// Height of the next header to scan
+    pub next_height: u32,
+    /// The heaviest header of the measurement period found so far
+    pub superblock: Option<Header>,
+    /// Height of the superblock, to check that it is still on the main chain
+    pub superblock_height: Option<u32>,
+    /// Whether a header after the measurement period was scanned, so that the superblock is final
+    pub complete: bool,
+}
+
+impl SuperblockTracker {
+    pub fn new(start_time: u32, start_height: u32) -> Self {
+        SuperblockTracker {
+            start_time,
+            next_height: start_height,
+            superblock: None,
+            superblock_height: None,
+            complete: false,
+        }
+    }
+
+    fn end_time(&self) -> u32 { self.start_time.saturating_add(SUPERBLOCK_MEASUREMENT_PERIOD) }
+
+    /// Whether the header is in the measurement period, as checked by the disprove chain script
+    pub fn is_in_period(&self, header: &Header) -> bool {
+        header.time > self.start_time && header.time < self.end_time()
+    }
+
+    /// Scans the headers added to the source since the last scan
+    /// Returns whether a heavier superblock was found.
+    pub fn scan(&mut self, source: &impl HeaderSource) -> bool {
+        let mut updated = false;
+        while let Some(header) = source.header_at(self.next_height) {
+            if self.is_in_period(&header)
+                && self
+                    .superblock
+                    .is_none_or(|superblock| is_heavier_superblock(&header, &superblock))
+            {
+                self.superblock = Some(header);
+                self.superblock_height = Some(self.next_height);
+                updated = true;
+            }
+            if header.time >= self.end_time() {
+                self.complete = true;
+            }
+            self.next_height += 1;
+        }
+        updated
+    }
+
+    /// The tracked superblock if it is strictly heavier than the committed one
+    pub fn heavier_than(&self, committed: &Header) -> Option<Header> {
+        self.superblock
+            .filter(|superblock| is_heavier_superblock(superblock, committed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(time: u32, nonce: u32) -> Header {
+        Header {
+            time,
+            nonce,
+            ..find_superblock()
+        }
+    }
+
+    #[test]
+    fn test_superblock_tracker_finds_heaviest_header() {
+        let start_time = 1_700_000_000;
+        let mut source = LocalHeaderSource::new(100);
+        // a header before the period, whose weight should be ignored
+        source.push(header(start_time, 0));
+        for i in 1..20 {
+            source.push(header(start_time + i * 600, i));
+        }
+
+        let mut tracker = SuperblockTracker::new(start_time, 100);
+        assert!(tracker.scan(&source));
+        assert!(!tracker.complete);
+        let heaviest = source.headers[1..]
+            .iter()
+            .copied()
+            .max_by(compare_superblock_weight)
+            .unwrap();
+        assert_eq!(tracker.superblock, Some(heaviest));
+        let heaviest_index = source.headers.iter().position(|h| *h == heaviest).unwrap();
+        assert_eq!(tracker.superblock_height, Some(100 + heaviest_index as u32));
+        assert_eq!(tracker.next_height, 120);
+
+        // nothing new to scan
+        assert!(!tracker.scan(&source));
+
+        source.push(header(start_time + SUPERBLOCK_MEASUREMENT_PERIOD, 0));
+        tracker.scan(&source);
+        assert!(tracker.complete);
+        assert_eq!(tracker.superblock, Some(heaviest));
+
+        assert_eq!(tracker.heavier_than(&heaviest), None);
+        let lighter = source.headers[1..]
+            .iter()
+            .copied()
+            .find(|header| *header != heaviest)
+            .unwrap();
+        assert_eq!(tracker.heavier_than(&lighter), Some(heaviest));
+    }
+
+    #[test]
+    fn test_superblock_tracker_persistence() {
+        let mut tracker = SuperblockTracker::new(0, 0);
+        let mut source = LocalHeaderSource::new(0);
+        source.push(header(1, 0));
+        tracker.scan(&source);
+
+        let json = serde_json::to_string(&tracker).unwrap();
+        let loaded: SuperblockTracker = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, tracker);
+    }
+}
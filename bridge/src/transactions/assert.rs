@@ -12,6 +12,7 @@ use super::{
             connector_c::ConnectorC,
         },
         contexts::{base::BaseContext, verifier::VerifierContext},
+        error::Error,
         graphs::base::DUST_AMOUNT,
     },
     base::*,
@@ -30,6 +31,8 @@ pub struct AssertTransaction {
     musig2_nonces: HashMap<usize, HashMap<PublicKey, PubNonce>>,
     musig2_nonce_signatures: HashMap<usize, HashMap<PublicKey, Signature>>,
     musig2_signatures: HashMap<usize, HashMap<PublicKey, PartialSignature>>,
+    #[serde(default)]
+    frost_signing_sets: HashMap<usize, Vec<PublicKey>>,
 }
 
 impl PreSignedTransaction for AssertTransaction {
@@ -63,6 +66,10 @@ impl PreSignedMusig2Transaction for AssertTransaction {
     ) -> &mut HashMap<usize, HashMap<PublicKey, PartialSignature>> {
         &mut self.musig2_signatures
     }
+    fn frost_signing_sets(&self) -> &HashMap<usize, Vec<PublicKey>> { &self.frost_signing_sets }
+    fn frost_signing_sets_mut(&mut self) -> &mut HashMap<usize, Vec<PublicKey>> {
+        &mut self.frost_signing_sets
+    }
     fn verifier_inputs(&self) -> Vec<usize> { vec![0] }
 }
 
@@ -119,6 +126,7 @@ impl AssertTransaction {
             musig2_nonces: HashMap::new(),
             musig2_nonce_signatures: HashMap::new(),
             musig2_signatures: HashMap::new(),
+            frost_signing_sets: HashMap::new(),
         }
     }
 
@@ -126,8 +134,8 @@ impl AssertTransaction {
         &mut self,
         context: &VerifierContext,
         connector_b: &ConnectorB,
-        secret_nonce: &SecNonce,
-    ) {
+        secret_nonce: Option<&SecNonce>,
+    ) -> Result<(), Error> {
         let input_index = 0;
         pre_sign_musig2_taproot_input(
             self,
@@ -135,15 +143,20 @@ impl AssertTransaction {
             input_index,
             TapSighashType::All,
             secret_nonce,
-        );
+        )?;
 
-        // TODO: Consider verifying the final signature against the n-of-n public key and the tx.
-        if self.musig2_signatures[&input_index].len() == context.n_of_n_public_keys.len() {
-            self.finalize_input_0(context, connector_b);
+        if self.is_input_signed(input_index, context) {
+            self.finalize_input_0(context, connector_b)?;
         }
+
+        Ok(())
     }
 
-    fn finalize_input_0(&mut self, context: &dyn BaseContext, connector_b: &ConnectorB) {
+    fn finalize_input_0(
+        &mut self,
+        context: &dyn BaseContext,
+        connector_b: &ConnectorB,
+    ) -> Result<(), Error> {
         let input_index = 0;
         finalize_musig2_taproot_input(
             self,
@@ -151,7 +164,7 @@ impl AssertTransaction {
             input_index,
             TapSighashType::All,
             connector_b.generate_taproot_spend_info(),
-        );
+        )
     }
 
     pub fn pre_sign(
@@ -159,9 +172,9 @@ impl AssertTransaction {
         context: &VerifierContext,
         connector_b: &ConnectorB,
         secret_nonces: &HashMap<usize, SecNonce>,
-    ) {
+    ) -> Result<(), Error> {
         let input_index = 0;
-        self.sign_input_0(context, connector_b, &secret_nonces[&input_index]);
+        self.sign_input_0(context, connector_b, secret_nonces.get(&input_index))
     }
 
     pub fn merge(&mut self, assert: &AssertTransaction) {
@@ -15,9 +15,11 @@ use super::{
                 base::*, connector_4::Connector4, connector_5::Connector5, connector_c::ConnectorC,
             },
             contexts::{base::BaseContext, verifier::VerifierContext},
+            error::Error,
             graphs::base::DUST_AMOUNT,
         },
         base::*,
+        fee::generate_anchor_output,
         pre_signed::*,
         pre_signed_musig2::*,
     },
@@ -35,6 +37,8 @@ pub struct AssertFinalTransaction {
     musig2_nonces: HashMap<usize, HashMap<PublicKey, PubNonce>>,
     musig2_nonce_signatures: HashMap<usize, HashMap<PublicKey, Signature>>,
     musig2_signatures: HashMap<usize, HashMap<PublicKey, PartialSignature>>,
+    #[serde(default)]
+    frost_signing_sets: HashMap<usize, Vec<PublicKey>>,
 }
 
 impl PreSignedTransaction for AssertFinalTransaction {
@@ -68,6 +72,10 @@ impl PreSignedMusig2Transaction for AssertFinalTransaction {
     ) -> &mut HashMap<usize, HashMap<PublicKey, PartialSignature>> {
         &mut self.musig2_signatures
     }
+    fn frost_signing_sets(&self) -> &HashMap<usize, Vec<PublicKey>> { &self.frost_signing_sets }
+    fn frost_signing_sets_mut(&mut self) -> &mut HashMap<usize, Vec<PublicKey>> {
+        &mut self.frost_signing_sets
+    }
     fn verifier_inputs(&self) -> Vec<usize> { vec![0] }
 }
 
@@ -131,9 +139,12 @@ impl AssertFinalTransaction {
             script_pubkey: connector_4.generate_address().script_pubkey(),
         };
 
+        // anchor for CPFP
+        let _output_3 = generate_anchor_output();
+
         // goes to take_2 tx or disprove tx
         let _output_1 = TxOut {
-            value: total_output_amount - Amount::from_sat(DUST_AMOUNT) * 2,
+            value: total_output_amount - Amount::from_sat(DUST_AMOUNT) * 2 - _output_3.value,
             script_pubkey: connector_5.generate_taproot_address().script_pubkey(),
         };
 
@@ -148,7 +159,7 @@ impl AssertFinalTransaction {
                 version: bitcoin::transaction::Version(2),
                 lock_time: absolute::LockTime::ZERO,
                 input: vec![_input_0, _input_1, _input_2],
-                output: vec![_output_0, _output_1, _output_2],
+                output: vec![_output_0, _output_1, _output_2, _output_3],
             },
             prev_outs: vec![
                 TxOut {
@@ -178,6 +189,7 @@ impl AssertFinalTransaction {
             musig2_nonces: HashMap::new(),
             musig2_nonce_signatures: HashMap::new(),
             musig2_signatures: HashMap::new(),
+            frost_signing_sets: HashMap::new(),
         }
     }
 
@@ -185,8 +197,8 @@ impl AssertFinalTransaction {
         &mut self,
         context: &VerifierContext,
         connector_d: &ConnectorD,
-        secret_nonce: &SecNonce,
-    ) {
+        secret_nonce: Option<&SecNonce>,
+    ) -> Result<(), Error> {
         let input_index = 0;
         pre_sign_musig2_taproot_input(
             self,
@@ -194,12 +206,13 @@ impl AssertFinalTransaction {
             input_index,
             TapSighashType::All,
             secret_nonce,
-        );
+        )?;
 
-        // TODO: Consider verifying the final signature against the n-of-n public key and the tx.
-        if self.musig2_signatures[&input_index].len() == context.n_of_n_public_keys.len() {
-            self.finalize_input_0(context, connector_d);
+        if self.is_input_signed(input_index, context) {
+            self.finalize_input_0(context, connector_d)?;
         }
+
+        Ok(())
     }
 
     fn sign_commit_inputs(&mut self, context: &OperatorContext) {
@@ -214,7 +227,11 @@ impl AssertFinalTransaction {
         }
     }
 
-    fn finalize_input_0(&mut self, context: &dyn BaseContext, connector_d: &ConnectorD) {
+    fn finalize_input_0(
+        &mut self,
+        context: &dyn BaseContext,
+        connector_d: &ConnectorD,
+    ) -> Result<(), Error> {
         let input_index = 0;
         finalize_musig2_taproot_input(
             self,
@@ -222,7 +239,7 @@ impl AssertFinalTransaction {
             input_index,
             TapSighashType::All,
             connector_d.generate_taproot_spend_info(),
-        );
+        )
     }
 
     pub fn pre_sign(
@@ -230,9 +247,9 @@ impl AssertFinalTransaction {
         context: &VerifierContext,
         connector_d: &ConnectorD,
         secret_nonces: &HashMap<usize, SecNonce>,
-    ) {
+    ) -> Result<(), Error> {
         let input_index = 0;
-        self.sign_input_0(context, connector_d, &secret_nonces[&input_index]);
+        self.sign_input_0(context, connector_d, secret_nonces.get(&input_index))
     }
 
     pub fn merge(&mut self, assert: &AssertFinalTransaction) {
@@ -10,9 +10,11 @@ use super::{
         super::{
             connectors::{base::*, connector_b::ConnectorB, connector_d::ConnectorD},
             contexts::{base::BaseContext, verifier::VerifierContext},
+            error::Error,
             graphs::base::DUST_AMOUNT,
         },
         base::*,
+        fee::generate_anchor_output,
         pre_signed::*,
         pre_signed_musig2::*,
     },
@@ -30,6 +32,8 @@ pub struct AssertInitialTransaction {
     musig2_nonces: HashMap<usize, HashMap<PublicKey, PubNonce>>,
     musig2_nonce_signatures: HashMap<usize, HashMap<PublicKey, Signature>>,
     musig2_signatures: HashMap<usize, HashMap<PublicKey, PartialSignature>>,
+    #[serde(default)]
+    frost_signing_sets: HashMap<usize, Vec<PublicKey>>,
 }
 
 impl PreSignedTransaction for AssertInitialTransaction {
@@ -63,6 +67,10 @@ impl PreSignedMusig2Transaction for AssertInitialTransaction {
     ) -> &mut HashMap<usize, HashMap<PublicKey, PartialSignature>> {
         &mut self.musig2_signatures
     }
+    fn frost_signing_sets(&self) -> &HashMap<usize, Vec<PublicKey>> { &self.frost_signing_sets }
+    fn frost_signing_sets_mut(&mut self) -> &mut HashMap<usize, Vec<PublicKey>> {
+        &mut self.frost_signing_sets
+    }
     fn verifier_inputs(&self) -> Vec<usize> { vec![0] }
 }
 
@@ -103,9 +111,13 @@ impl AssertInitialTransaction {
             MIN_RELAY_FEE_ASSERT_COMMIT2
                 + assert_commit2_connectors_e.connectors_num() as u64 * DUST_AMOUNT,
         );
+        let anchor_output = generate_anchor_output();
         // goes to assert_final
         let _output_0 = TxOut {
-            value: total_output_amount - assert_commit1_expense - assert_commit2_expense,
+            value: total_output_amount
+                - assert_commit1_expense
+                - assert_commit2_expense
+                - anchor_output.value,
             script_pubkey: connector_d.generate_taproot_address().script_pubkey(),
         };
 
@@ -143,6 +155,9 @@ impl AssertInitialTransaction {
             });
         }
 
+        // anchor for CPFP, after the outputs of the assert_x txs
+        output.push(anchor_output);
+
         AssertInitialTransaction {
             tx: Transaction {
                 version: bitcoin::transaction::Version(2),
@@ -158,6 +173,7 @@ impl AssertInitialTransaction {
             musig2_nonces: HashMap::new(),
             musig2_nonce_signatures: HashMap::new(),
             musig2_signatures: HashMap::new(),
+            frost_signing_sets: HashMap::new(),
         }
     }
 
@@ -165,8 +181,8 @@ impl AssertInitialTransaction {
         &mut self,
         context: &VerifierContext,
         connector_b: &ConnectorB,
-        secret_nonce: &SecNonce,
-    ) {
+        secret_nonce: Option<&SecNonce>,
+    ) -> Result<(), Error> {
         let input_index = 0;
         pre_sign_musig2_taproot_input(
             self,
@@ -174,15 +190,20 @@ impl AssertInitialTransaction {
             input_index,
             TapSighashType::All,
             secret_nonce,
-        );
+        )?;
 
-        // TODO: Consider verifying the final signature against the n-of-n public key and the tx.
-        if self.musig2_signatures[&input_index].len() == context.n_of_n_public_keys.len() {
-            self.finalize_input_0(context, connector_b);
+        if self.is_input_signed(input_index, context) {
+            self.finalize_input_0(context, connector_b)?;
         }
+
+        Ok(())
     }
 
-    fn finalize_input_0(&mut self, context: &dyn BaseContext, connector_b: &ConnectorB) {
+    fn finalize_input_0(
+        &mut self,
+        context: &dyn BaseContext,
+        connector_b: &ConnectorB,
+    ) -> Result<(), Error> {
         let input_index = 0;
         finalize_musig2_taproot_input(
             self,
@@ -190,7 +211,7 @@ impl AssertInitialTransaction {
             input_index,
             TapSighashType::All,
             connector_b.generate_taproot_spend_info(),
-        );
+        )
     }
 
     pub fn pre_sign(
@@ -198,9 +219,9 @@ impl AssertInitialTransaction {
         context: &VerifierContext,
         connector_b: &ConnectorB,
         secret_nonces: &HashMap<usize, SecNonce>,
-    ) {
+    ) -> Result<(), Error> {
         let input_index = 0;
-        self.sign_input_0(context, connector_b, &secret_nonces[&input_index]);
+        self.sign_input_0(context, connector_b, secret_nonces.get(&input_index))
     }
 
     pub fn merge(&mut self, assert: &AssertInitialTransaction) {
@@ -4,6 +4,7 @@ use std::collections::{BTreeMap, HashMap};
 use crate::{
     commitments::CommitmentMessageId,
     connectors::{connector_e::ConnectorE, connector_f_1::ConnectorF1, connector_f_2::ConnectorF2},
+    error::{ChunkerError, Error},
 };
 
 use bitvm::{
@@ -61,26 +62,35 @@ impl AssertCommit2ConnectorsE {
 pub fn sign_assert_tx_with_groth16_proof(
     commitment_secrets: &HashMap<CommitmentMessageId, WinternitzSecret>,
     proof: &RawProof,
-) -> (Vec<RawWitness>, Vec<RawWitness>) {
+) -> Result<(Vec<RawWitness>, Vec<RawWitness>), Error> {
     let (commit1_publickeys, commit2_publickeys) =
         groth16_commitment_secrets_to_public_keys(commitment_secrets);
 
-    // hash map to btree map
-    let commitment_secrets: BTreeMap<String, WinternitzSecret> = commitment_secrets
+    // hash map to btree map, the secret of a batch is shared by all of its variables
+    let mut batches = vec![];
+    let mut variable_secrets: BTreeMap<String, WinternitzSecret> = BTreeMap::new();
+    for (message, secret) in commitment_secrets
         .clone()
         .into_iter()
-        .map(|(k, v)| {
-            (
-                match k {
-                    CommitmentMessageId::Groth16IntermediateValues((name, _)) => name,
-                    _ => String::new(),
-                },
-                v,
-            )
-        })
-        .collect();
-
-    let mut bridge_assigner = BridgeAssigner::new_operator(commitment_secrets);
+        .collect::<BTreeMap<_, _>>()
+    {
+        match message {
+            CommitmentMessageId::Groth16IntermediateValues((name, _)) => {
+                variable_secrets.insert(name, secret);
+            }
+            CommitmentMessageId::Groth16IntermediateValuesBatch(batch) => {
+                for (name, _) in batch.iter() {
+                    variable_secrets.insert(name.clone(), secret.clone());
+                }
+                batches.push(batch);
+            }
+            _ => (),
+        }
+    }
+
+    let mut bridge_assigner = BridgeAssigner::new_operator(variable_secrets)
+        .with_batches(batches)
+        .map_err(|err| Error::Chunker(ChunkerError::InvalidBatch(err)))?;
 
     let segments =
         groth16_verify_to_segments(&mut bridge_assigner, &proof.public, &proof.proof, &proof.vk);
@@ -98,27 +108,39 @@ pub fn sign_assert_tx_with_groth16_proof(
     let mut commit1_witness = vec![];
     let mut commit2_witness = vec![];
 
-    for pks in commit1_publickeys {
+    let connector_witness = |pks: BTreeMap<CommitmentMessageId, WinternitzPublicKey>| -> Result<
+        Vec<RawWitness>,
+        Error,
+    > {
         let mut witness = vec![];
         for (message, _) in pks {
-            if let CommitmentMessageId::Groth16IntermediateValues((name, _)) = message {
-                witness.append(&mut bridge_assigner.get_witness(elements.get(&name).unwrap()));
+            match message {
+                CommitmentMessageId::Groth16IntermediateValues((name, _)) => {
+                    witness.append(&mut bridge_assigner.get_witness(elements.get(&name).unwrap()));
+                }
+                CommitmentMessageId::Groth16IntermediateValuesBatch(batch) => {
+                    let names: Vec<String> = batch.into_iter().map(|(name, _)| name).collect();
+                    witness.append(
+                        &mut bridge_assigner
+                            .get_batch_witness(&names, &elements)
+                            .map_err(|err| Error::Chunker(ChunkerError::InvalidBatch(err)))?,
+                    );
+                }
+                _ => (),
             }
         }
-        commit1_witness.push(witness);
+        Ok(witness)
+    };
+
+    for pks in commit1_publickeys {
+        commit1_witness.push(connector_witness(pks)?);
     }
 
     for pks in commit2_publickeys {
-        let mut witness = vec![];
-        for (message, _) in pks {
-            if let CommitmentMessageId::Groth16IntermediateValues((name, _)) = message {
-                witness.append(&mut bridge_assigner.get_witness(elements.get(&name).unwrap()));
-            }
-        }
-        commit2_witness.push(witness);
+        commit2_witness.push(connector_witness(pks)?);
     }
 
-    (commit1_witness, commit2_witness)
+    Ok((commit1_witness, commit2_witness))
 }
 
 pub fn groth16_commitment_secrets_to_public_keys(
@@ -137,7 +159,11 @@ pub fn groth16_commitment_secrets_to_public_keys(
     let mut connector_e2_commitment_public_keys = vec![];
 
     for (message_id, secret) in commitment_secrets.iter() {
-        if let CommitmentMessageId::Groth16IntermediateValues((_, _)) = message_id {
+        if matches!(
+            message_id,
+            CommitmentMessageId::Groth16IntermediateValues(_)
+                | CommitmentMessageId::Groth16IntermediateValuesBatch(_)
+        ) {
             let pushing_keys =
                 if connector_e1_commitment_public_keys.len() < connectors_e_of_transaction {
                     &mut connector_e1_commitment_public_keys
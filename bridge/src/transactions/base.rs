@@ -8,6 +8,10 @@ use std::collections::HashMap;
 
 // TODO: set to larger value to be compatible with future tx modifications
 pub const RELAY_FEE_BUFFER_MULTIPLIER: f32 = 1.0;
+// Fees of the pre-signed transactions, for their vsize at the minimum relay fee rate. They are
+// fixed when the graphs are funded and signed, long before the transactions are broadcast, so
+// they only need to relay: the fee rate at broadcast is met by a CPFP child spending the anchor
+// or the reward output, see `CpfpTransaction`.
 pub const MIN_RELAY_FEE_KICK_OFF_1: u64 = relay_fee(6231);
 pub const MIN_RELAY_FEE_START_TIME: u64 = relay_fee(407);
 pub const MIN_RELAY_FEE_START_TIME_TIMEOUT: u64 = relay_fee(264);
@@ -102,6 +106,14 @@ pub fn merge_musig2_nonces_and_signatures(
 
     let signatures = destination_transaction.musig2_signatures_mut();
     merge_hash_maps(signatures, source_transaction.musig2_signatures().clone());
+
+    // a recorded FROST signing set is never replaced, the partial signatures being bound to it
+    let signing_sets = destination_transaction.frost_signing_sets_mut();
+    for (input_index, signing_set) in source_transaction.frost_signing_sets() {
+        signing_sets
+            .entry(*input_index)
+            .or_insert_with(|| signing_set.clone());
+    }
 }
 
 // merge the nonce/signature hashmaps. We can't just do a.extend(b) since that would just overwrite the inner
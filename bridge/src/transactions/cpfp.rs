@@ -0,0 +1,214 @@
+use bitcoin::{
+    absolute, consensus, key::Keypair, Amount, EcdsaSighashType, FeeRate, Network, OutPoint,
+    PublicKey, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Weight, Witness,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    super::{
+        error::{Error, TransactionError},
+        graphs::base::DUST_AMOUNT,
+        scripts::*,
+    },
+    base::*,
+    fee::{cpfp_fee, generate_anchor_script},
+    pre_signed::*,
+};
+
+// upper bound of the length of an ECDSA signature with its sighash type
+const MAX_ECDSA_SIGNATURE_LENGTH: usize = 73;
+
+#[derive(Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct CpfpTransaction {
+    #[serde(with = "consensus::serde::With::<consensus::serde::Hex>")]
+    tx: Transaction,
+    #[serde(with = "consensus::serde::With::<consensus::serde::Hex>")]
+    prev_outs: Vec<TxOut>,
+    prev_scripts: Vec<ScriptBuf>,
+}
+
+impl PreSignedTransaction for CpfpTransaction {
+    fn tx(&self) -> &Transaction { &self.tx }
+
+    fn tx_mut(&mut self) -> &mut Transaction { &mut self.tx }
+
+    fn prev_outs(&self) -> &Vec<TxOut> { &self.prev_outs }
+
+    fn prev_scripts(&self) -> &Vec<ScriptBuf> { &self.prev_scripts }
+}
+
+impl CpfpTransaction {
+    /// Child of the finalized parent transaction paying for the package at the fee rate
+    ///
+    /// Input 0 spends the output of the parent at `parent_vout`, either its anchor or an output
+    /// paying to the public key, and input 1 is the funding input of the public key, the change
+    /// going back to the public key.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        network: Network,
+        public_key: &PublicKey,
+        keypair: &Keypair,
+        parent: &Transaction,
+        parent_fee: Amount,
+        parent_vout: usize,
+        funding_input: Input,
+        fee_rate: FeeRate,
+    ) -> Result<Self, Error> {
+        let parent_outpoint = OutPoint {
+            txid: parent.compute_txid(),
+            vout: parent_vout as u32,
+        };
+        let output_not_spendable =
+            Error::Transaction(TransactionError::OutputNotSpendable(parent_outpoint));
+        let Some(parent_output) = parent.output.get(parent_vout).cloned() else {
+            return Err(output_not_spendable);
+        };
+        let pay_to_pubkey_script_pubkey =
+            generate_pay_to_pubkey_script_address(network, public_key).script_pubkey();
+        let input_0_script = if parent_output.script_pubkey == generate_anchor_script() {
+            ScriptBuf::new()
+        } else if parent_output.script_pubkey == pay_to_pubkey_script_pubkey {
+            generate_pay_to_pubkey_script(public_key)
+        } else {
+            return Err(output_not_spendable);
+        };
+
+        let _input_0 = TxIn {
+            previous_output: parent_outpoint,
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::MAX,
+            witness: Witness::default(),
+        };
+
+        let _input_1 = TxIn {
+            previous_output: funding_input.outpoint,
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::MAX,
+            witness: Witness::default(),
+        };
+
+        let _output_0 = TxOut {
+            value: Amount::ZERO,
+            script_pubkey: pay_to_pubkey_script_pubkey.clone(),
+        };
+
+        let mut this = CpfpTransaction {
+            tx: Transaction {
+                version: bitcoin::transaction::Version(2),
+                lock_time: absolute::LockTime::ZERO,
+                input: vec![_input_0, _input_1],
+                output: vec![_output_0],
+            },
+            prev_outs: vec![
+                parent_output,
+                TxOut {
+                    value: funding_input.amount,
+                    script_pubkey: pay_to_pubkey_script_pubkey,
+                },
+            ],
+            prev_scripts: vec![input_0_script, generate_pay_to_pubkey_script(public_key)],
+        };
+
+        let fee = cpfp_fee(parent.weight(), parent_fee, this.max_weight(), fee_rate);
+        let input_amount = this.prev_outs[0].value + this.prev_outs[1].value;
+        let required_amount = fee + Amount::from_sat(DUST_AMOUNT);
+        if input_amount < required_amount {
+            return Err(Error::Transaction(TransactionError::InsufficientFunding {
+                available: input_amount,
+                required: required_amount,
+            }));
+        }
+        this.tx.output[0].value = input_amount - fee;
+
+        this.sign_inputs(keypair);
+
+        Ok(this)
+    }
+
+    // weight of the transaction with the longest signatures
+    fn max_weight(&self) -> Weight {
+        let mut tx = self.tx.clone();
+        for (input, script) in tx.input.iter_mut().zip(self.prev_scripts.iter()) {
+            if !script.is_empty() {
+                input.witness.push([0; MAX_ECDSA_SIGNATURE_LENGTH]);
+                input.witness.push(script);
+            }
+        }
+        tx.weight()
+    }
+
+    // the anchor is spent with an empty witness
+    fn sign_inputs(&mut self, keypair: &Keypair) {
+        let signed_inputs: Vec<usize> = self
+            .prev_scripts
+            .iter()
+            .enumerate()
+            .filter(|(_, script)| !script.is_empty())
+            .map(|(input_index, _)| input_index)
+            .collect();
+        for input_index in signed_inputs {
+            pre_sign_p2wsh_input(self, input_index, EcdsaSighashType::All, &vec![keypair]);
+        }
+    }
+}
+
+impl BaseTransaction for CpfpTransaction {
+    fn finalize(&self) -> Transaction { self.tx.clone() }
+    fn name(&self) -> &'static str { "Cpfp" }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{hashes::Hash, Txid};
+
+    use super::*;
+    use crate::{
+        contexts::base::generate_keys_from_secret,
+        graphs::base::OPERATOR_SECRET,
+        transactions::fee::{generate_anchor_output, tx_fee},
+    };
+
+    #[test]
+    fn test_cpfp_pays_for_package() {
+        let network = Network::Regtest;
+        let (keypair, public_key) = generate_keys_from_secret(network, OPERATOR_SECRET);
+        let parent = Transaction {
+            version: bitcoin::transaction::Version(2),
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![
+                TxOut {
+                    value: Amount::from_sat(10_000),
+                    script_pubkey: ScriptBuf::new(),
+                },
+                generate_anchor_output(),
+            ],
+        };
+        let parent_fee = Amount::from_sat(100);
+        let fee_rate = FeeRate::from_sat_per_vb_unchecked(20);
+        let funding_input = Input {
+            outpoint: OutPoint {
+                txid: Txid::all_zeros(),
+                vout: 0,
+            },
+            amount: Amount::from_sat(100_000),
+        };
+
+        let cpfp = CpfpTransaction::new(
+            network,
+            &public_key,
+            &keypair,
+            &parent,
+            parent_fee,
+            1,
+            funding_input,
+            fee_rate,
+        )
+        .unwrap();
+        let tx = cpfp.finalize();
+        let package_fee = parent_fee + tx_fee(&tx, cpfp.prev_outs());
+        assert!(package_fee >= fee_rate.fee_wu(parent.weight() + tx.weight()).unwrap());
+        assert!(tx.input[0].witness.is_empty());
+        assert_eq!(tx.input[1].witness.len(), 2);
+    }
+}
@@ -11,6 +11,7 @@ use super::{
     super::{
         connectors::{base::*, connector_5::Connector5, connector_c::ConnectorC},
         contexts::{base::BaseContext, operator::OperatorContext, verifier::VerifierContext},
+        error::Error,
         scripts::*,
     },
     base::*,
@@ -31,6 +32,8 @@ pub struct DisproveTransaction {
     musig2_nonces: HashMap<usize, HashMap<PublicKey, PubNonce>>,
     musig2_nonce_signatures: HashMap<usize, HashMap<PublicKey, Signature>>,
     musig2_signatures: HashMap<usize, HashMap<PublicKey, PartialSignature>>,
+    #[serde(default)]
+    frost_signing_sets: HashMap<usize, Vec<PublicKey>>,
 }
 
 impl PreSignedTransaction for DisproveTransaction {
@@ -64,6 +67,10 @@ impl PreSignedMusig2Transaction for DisproveTransaction {
     ) -> &mut HashMap<usize, HashMap<PublicKey, PartialSignature>> {
         &mut self.musig2_signatures
     }
+    fn frost_signing_sets(&self) -> &HashMap<usize, Vec<PublicKey>> { &self.frost_signing_sets }
+    fn frost_signing_sets_mut(&mut self) -> &mut HashMap<usize, Vec<PublicKey>> {
+        &mut self.frost_signing_sets
+    }
     fn verifier_inputs(&self) -> Vec<usize> { vec![0] }
 }
 
@@ -140,6 +147,7 @@ impl DisproveTransaction {
             musig2_nonces: HashMap::new(),
             musig2_nonce_signatures: HashMap::new(),
             musig2_signatures: HashMap::new(),
+            frost_signing_sets: HashMap::new(),
         }
     }
 
@@ -147,8 +155,8 @@ impl DisproveTransaction {
         &mut self,
         context: &VerifierContext,
         connector_5: &Connector5,
-        secret_nonce: &SecNonce,
-    ) {
+        secret_nonce: Option<&SecNonce>,
+    ) -> Result<(), Error> {
         let input_index = 0;
         pre_sign_musig2_taproot_input(
             self,
@@ -156,15 +164,20 @@ impl DisproveTransaction {
             input_index,
             TapSighashType::Single,
             secret_nonce,
-        );
+        )?;
 
-        // TODO: Consider verifying the final signature against the n-of-n public key and the tx.
-        if self.musig2_signatures[&input_index].len() == context.n_of_n_public_keys.len() {
-            self.finalize_input_0(context, connector_5);
+        if self.is_input_signed(input_index, context) {
+            self.finalize_input_0(context, connector_5)?;
         }
+
+        Ok(())
     }
 
-    fn finalize_input_0(&mut self, context: &dyn BaseContext, connector_5: &Connector5) {
+    fn finalize_input_0(
+        &mut self,
+        context: &dyn BaseContext,
+        connector_5: &Connector5,
+    ) -> Result<(), Error> {
         let input_index = 0;
         finalize_musig2_taproot_input(
             self,
@@ -172,7 +185,7 @@ impl DisproveTransaction {
             input_index,
             TapSighashType::Single,
             connector_5.generate_taproot_spend_info(),
-        );
+        )
     }
 
     pub fn pre_sign(
@@ -180,9 +193,9 @@ impl DisproveTransaction {
         context: &VerifierContext,
         connector_5: &Connector5,
         secret_nonces: &HashMap<usize, SecNonce>,
-    ) {
+    ) -> Result<(), Error> {
         let input_index = 0;
-        self.sign_input_0(context, connector_5, &secret_nonces[&input_index]);
+        self.sign_input_0(context, connector_5, secret_nonces.get(&input_index))
     }
 
     pub fn add_input_output(
@@ -12,6 +12,7 @@ use super::{
     super::{
         connectors::connector_b::ConnectorB,
         contexts::{base::BaseContext, operator::OperatorContext, verifier::VerifierContext},
+        error::Error,
         scripts::*,
     },
     base::*,
@@ -32,6 +33,8 @@ pub struct DisproveChainTransaction {
     musig2_nonces: HashMap<usize, HashMap<PublicKey, PubNonce>>,
     musig2_nonce_signatures: HashMap<usize, HashMap<PublicKey, Signature>>,
     musig2_signatures: HashMap<usize, HashMap<PublicKey, PartialSignature>>,
+    #[serde(default)]
+    frost_signing_sets: HashMap<usize, Vec<PublicKey>>,
 }
 
 impl PreSignedTransaction for DisproveChainTransaction {
@@ -65,6 +68,10 @@ impl PreSignedMusig2Transaction for DisproveChainTransaction {
     ) -> &mut HashMap<usize, HashMap<PublicKey, PartialSignature>> {
         &mut self.musig2_signatures
     }
+    fn frost_signing_sets(&self) -> &HashMap<usize, Vec<PublicKey>> { &self.frost_signing_sets }
+    fn frost_signing_sets_mut(&mut self) -> &mut HashMap<usize, Vec<PublicKey>> {
+        &mut self.frost_signing_sets
+    }
     fn verifier_inputs(&self) -> Vec<usize> { vec![0] }
 }
 
@@ -107,6 +114,7 @@ impl DisproveChainTransaction {
             musig2_nonces: HashMap::new(),
             musig2_nonce_signatures: HashMap::new(),
             musig2_signatures: HashMap::new(),
+            frost_signing_sets: HashMap::new(),
         }
     }
 
@@ -114,8 +122,8 @@ impl DisproveChainTransaction {
         &mut self,
         context: &VerifierContext,
         connector_b: &ConnectorB,
-        secret_nonce: &SecNonce,
-    ) {
+        secret_nonce: Option<&SecNonce>,
+    ) -> Result<(), Error> {
         let input_index = 0;
         pre_sign_musig2_taproot_input(
             self,
@@ -123,15 +131,20 @@ impl DisproveChainTransaction {
             input_index,
             TapSighashType::Single,
             secret_nonce,
-        );
+        )?;
 
-        // TODO: Consider verifying the final signature against the n-of-n public key and the tx.
-        if self.musig2_signatures[&input_index].len() == context.n_of_n_public_keys.len() {
-            self.finalize_input_0(context, connector_b);
+        if self.is_input_signed(input_index, context) {
+            self.finalize_input_0(context, connector_b)?;
         }
+
+        Ok(())
     }
 
-    fn finalize_input_0(&mut self, context: &dyn BaseContext, connector_b: &ConnectorB) {
+    fn finalize_input_0(
+        &mut self,
+        context: &dyn BaseContext,
+        connector_b: &ConnectorB,
+    ) -> Result<(), Error> {
         let input_index = 0;
         finalize_musig2_taproot_input(
             self,
@@ -139,7 +152,7 @@ impl DisproveChainTransaction {
             input_index,
             TapSighashType::Single,
             connector_b.generate_taproot_spend_info(),
-        );
+        )
     }
 
     pub fn pre_sign(
@@ -147,12 +160,14 @@ impl DisproveChainTransaction {
         context: &VerifierContext,
         connector_b: &ConnectorB,
         secret_nonces: &HashMap<usize, SecNonce>,
-    ) {
+    ) -> Result<(), Error> {
         let input_index = 0;
-        self.sign_input_0(context, connector_b, &secret_nonces[&input_index]);
+        self.sign_input_0(context, connector_b, secret_nonces.get(&input_index))?;
         // TODO: We probably shouldn't finalize the witness when pre-signing (sign_input_0 calls finalize_input_0,
         // which adds a control block to the witness). Please double-check that the control block should be only added
         // after the tx is signed (see `sign()`) and ready to be broadcast.
+
+        Ok(())
     }
 
     pub fn sign(
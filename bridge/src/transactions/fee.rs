@@ -0,0 +1,148 @@
+use bitcoin::{Amount, FeeRate, ScriptBuf, Transaction, TxOut, Weight};
+use std::collections::HashMap;
+
+use super::super::{
+    client::backend::base::BitcoinBackend, error::BackendError, graphs::base::MIN_RELAY_FEE_RATE,
+};
+
+// pay-to-anchor output, spendable by anyone with an empty witness
+// 240 = (13 + 67) * 3, the dust limit of the output at the dust relay fee rate
+pub const ANCHOR_AMOUNT: u64 = 240;
+const ANCHOR_SCRIPT: [u8; 4] = [0x51, 0x02, 0x4e, 0x73];
+
+// confirmation target in blocks of the estimated fee rates
+pub const DEFAULT_CONFIRMATION_TARGET: u16 = 6;
+
+pub fn generate_anchor_script() -> ScriptBuf { ScriptBuf::from_bytes(ANCHOR_SCRIPT.to_vec()) }
+
+/// Output reserved on pre-signed transactions so that their fee can be bumped with a child
+pub fn generate_anchor_output() -> TxOut {
+    TxOut {
+        value: Amount::from_sat(ANCHOR_AMOUNT),
+        script_pubkey: generate_anchor_script(),
+    }
+}
+
+pub fn find_anchor_output(tx: &Transaction) -> Option<usize> {
+    let anchor_script = generate_anchor_script();
+    tx.output
+        .iter()
+        .position(|output| output.script_pubkey == anchor_script)
+}
+
+pub fn min_relay_fee_rate() -> FeeRate { FeeRate::from_sat_per_vb_unchecked(MIN_RELAY_FEE_RATE) }
+
+/// The fee paid by the transaction spending the previous outputs
+pub fn tx_fee(tx: &Transaction, prev_outs: &[TxOut]) -> Amount {
+    let input_amount: Amount = prev_outs.iter().map(|output| output.value).sum();
+    let output_amount: Amount = tx.output.iter().map(|output| output.value).sum();
+    input_amount - output_amount
+}
+
+pub fn fee_rate(fee: Amount, weight: Weight) -> FeeRate {
+    FeeRate::from_sat_per_kwu(fee.to_sat() * 1000 / weight.to_wu())
+}
+
+/// The fee of the child for the package of the parent and the child to pay the fee rate
+pub fn cpfp_fee(
+    parent_weight: Weight,
+    parent_fee: Amount,
+    child_weight: Weight,
+    fee_rate: FeeRate,
+) -> Amount {
+    let package_fee = fee_rate
+        .fee_wu(parent_weight + child_weight)
+        .expect("Fee overflow");
+    let child_fee = fee_rate.fee_wu(child_weight).expect("Fee overflow");
+    // the child pays at least for itself
+    child_fee.max(package_fee.checked_sub(parent_fee).unwrap_or(Amount::ZERO))
+}
+
+/// The fee rate of the estimate with the largest confirmation target within the one requested,
+/// estimates being in sat/vB as returned by the backend
+pub fn select_fee_rate(estimates: &HashMap<u16, f64>, confirmation_target: u16) -> FeeRate {
+    estimates
+        .iter()
+        .filter(|(target, _)| **target <= confirmation_target)
+        .max_by_key(|(target, _)| **target)
+        .map(|(_, sat_per_vb)| FeeRate::from_sat_per_kwu((sat_per_vb * 250.0).ceil() as u64))
+        .map(|fee_rate| fee_rate.max(min_relay_fee_rate()))
+        .unwrap_or_else(min_relay_fee_rate)
+}
+
+/// Estimates the fee rate to confirm within the target, the minimum relay fee rate if the backend
+/// has no estimate
+pub async fn estimate_fee_rate(
+    client: &dyn BitcoinBackend,
+    confirmation_target: u16,
+) -> Result<FeeRate, BackendError> {
+    let estimates = client.get_fee_estimates().await?;
+    Ok(select_fee_rate(&estimates, confirmation_target))
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{absolute, transaction::Version};
+
+    use super::*;
+
+    #[test]
+    fn test_select_fee_rate() {
+        let estimates = HashMap::from([(1, 20.5), (3, 10.0), (6, 4.5), (144, 0.5)]);
+        assert_eq!(
+            select_fee_rate(&estimates, 6),
+            FeeRate::from_sat_per_kwu(1125)
+        );
+        assert_eq!(
+            select_fee_rate(&estimates, 5),
+            FeeRate::from_sat_per_vb_unchecked(10)
+        );
+        // below the minimum relay fee rate
+        assert_eq!(select_fee_rate(&estimates, 1000), min_relay_fee_rate());
+        assert_eq!(select_fee_rate(&HashMap::new(), 6), min_relay_fee_rate());
+    }
+
+    #[test]
+    fn test_cpfp_fee() {
+        let fee_rate = FeeRate::from_sat_per_vb_unchecked(10);
+        let parent_weight = Weight::from_vb_unchecked(1000);
+        let child_weight = Weight::from_vb_unchecked(200);
+
+        // the child pays what the parent is missing for the package
+        assert_eq!(
+            cpfp_fee(
+                parent_weight,
+                Amount::from_sat(1000),
+                child_weight,
+                fee_rate
+            ),
+            Amount::from_sat(11000)
+        );
+        // the parent already pays for the package
+        assert_eq!(
+            cpfp_fee(
+                parent_weight,
+                Amount::from_sat(50000),
+                child_weight,
+                fee_rate
+            ),
+            Amount::from_sat(2000)
+        );
+    }
+
+    #[test]
+    fn test_find_anchor_output() {
+        let mut tx = Transaction {
+            version: Version(2),
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![],
+            output: vec![TxOut {
+                value: Amount::from_sat(1000),
+                script_pubkey: ScriptBuf::new(),
+            }],
+        };
+        assert_eq!(find_anchor_output(&tx), None);
+        tx.output.push(generate_anchor_output());
+        assert_eq!(find_anchor_output(&tx), Some(1));
+    }
+}
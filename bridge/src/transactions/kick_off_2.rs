@@ -13,6 +13,7 @@ use super::{
         graphs::base::DUST_AMOUNT,
     },
     base::*,
+    fee::generate_anchor_output,
     pre_signed::*,
     signing::{generate_taproot_leaf_schnorr_signature, populate_taproot_input_witness},
 };
@@ -77,8 +78,10 @@ impl KickOff2Transaction {
             script_pubkey: connector_3.generate_address().script_pubkey(),
         };
 
+        let _output_2 = generate_anchor_output();
+
         let _output_1 = TxOut {
-            value: total_output_amount - _output_0.value,
+            value: total_output_amount - _output_0.value - _output_2.value,
             script_pubkey: connector_b.generate_taproot_address().script_pubkey(),
         };
 
@@ -87,7 +90,7 @@ impl KickOff2Transaction {
                 version: bitcoin::transaction::Version(2),
                 lock_time: absolute::LockTime::ZERO,
                 input: vec![_input_0],
-                output: vec![_output_0, _output_1],
+                output: vec![_output_0, _output_1, _output_2],
             },
             prev_outs: vec![TxOut {
                 value: input_0.amount,
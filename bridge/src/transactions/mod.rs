@@ -2,8 +2,10 @@ pub mod assert;
 pub mod assert_transactions;
 pub mod base;
 pub mod challenge;
+pub mod cpfp;
 pub mod disprove;
 pub mod disprove_chain;
+pub mod fee;
 pub mod kick_off_1;
 pub mod kick_off_2;
 pub mod kick_off_timeout;
@@ -15,6 +17,7 @@ pub mod peg_out_confirm;
 pub mod pre_signed;
 pub mod pre_signed_musig2;
 pub mod signing;
+pub mod signing_frost;
 pub mod signing_musig2;
 pub mod start_time;
 pub mod start_time_timeout;
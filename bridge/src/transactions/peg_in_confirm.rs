@@ -9,6 +9,7 @@ use super::{
     super::{
         connectors::{base::*, connector_0::Connector0, connector_z::ConnectorZ},
         contexts::{base::BaseContext, depositor::DepositorContext, verifier::VerifierContext},
+        error::Error,
     },
     base::*,
     pre_signed::*,
@@ -31,6 +32,8 @@ pub struct PegInConfirmTransaction {
     musig2_nonces: HashMap<usize, HashMap<PublicKey, PubNonce>>,
     musig2_nonce_signatures: HashMap<usize, HashMap<PublicKey, Signature>>,
     musig2_signatures: HashMap<usize, HashMap<PublicKey, PartialSignature>>,
+    #[serde(default)]
+    frost_signing_sets: HashMap<usize, Vec<PublicKey>>,
 }
 
 impl PreSignedTransaction for PegInConfirmTransaction {
@@ -64,6 +67,10 @@ impl PreSignedMusig2Transaction for PegInConfirmTransaction {
     ) -> &mut HashMap<usize, HashMap<PublicKey, PartialSignature>> {
         &mut self.musig2_signatures
     }
+    fn frost_signing_sets(&self) -> &HashMap<usize, Vec<PublicKey>> { &self.frost_signing_sets }
+    fn frost_signing_sets_mut(&mut self) -> &mut HashMap<usize, Vec<PublicKey>> {
+        &mut self.frost_signing_sets
+    }
     fn verifier_inputs(&self) -> Vec<usize> { vec![0] }
 }
 
@@ -137,6 +144,7 @@ impl PegInConfirmTransaction {
             musig2_nonces: HashMap::new(),
             musig2_nonce_signatures: HashMap::new(),
             musig2_signatures: HashMap::new(),
+            frost_signing_sets: HashMap::new(),
         }
     }
 
@@ -168,8 +176,8 @@ impl PegInConfirmTransaction {
         &mut self,
         context: &VerifierContext,
         connector_z: &ConnectorZ,
-        secret_nonce: &SecNonce,
-    ) {
+        secret_nonce: Option<&SecNonce>,
+    ) -> Result<(), Error> {
         let input_index = 0;
         pre_sign_musig2_taproot_input(
             self,
@@ -177,15 +185,20 @@ impl PegInConfirmTransaction {
             input_index,
             TapSighashType::All,
             secret_nonce,
-        );
+        )?;
 
-        // TODO: Consider verifying the final signature against the n-of-n public key and the tx.
-        if self.musig2_signatures[&input_index].len() == context.n_of_n_public_keys.len() {
-            self.finalize_input_0(context, connector_z);
+        if self.is_input_signed(input_index, context) {
+            self.finalize_input_0(context, connector_z)?;
         }
+
+        Ok(())
     }
 
-    fn finalize_input_0(&mut self, context: &dyn BaseContext, connector_z: &ConnectorZ) {
+    fn finalize_input_0(
+        &mut self,
+        context: &dyn BaseContext,
+        connector_z: &ConnectorZ,
+    ) -> Result<(), Error> {
         let input_index = 0;
         finalize_musig2_taproot_input(
             self,
@@ -193,7 +206,7 @@ impl PegInConfirmTransaction {
             input_index,
             TapSighashType::All,
             connector_z.generate_taproot_spend_info(),
-        );
+        )
     }
 
     pub fn pre_sign(
@@ -201,9 +214,9 @@ impl PegInConfirmTransaction {
         context: &VerifierContext,
         connector_z: &ConnectorZ,
         secret_nonces: &HashMap<usize, SecNonce>,
-    ) {
+    ) -> Result<(), Error> {
         let input_index = 0;
-        self.push_verifier_signature_input_0(context, connector_z, &secret_nonces[&input_index]);
+        self.push_verifier_signature_input_0(context, connector_z, secret_nonces.get(&input_index))
     }
 
     pub fn merge(&mut self, peg_in_confirm: &PegInConfirmTransaction) {
@@ -1,22 +1,32 @@
 use bitcoin::{
     hashes::{sha256, Hash},
-    taproot::TaprootSpendInfo,
-    PublicKey, TapSighashType, XOnlyPublicKey,
+    sighash::{Prevouts, SighashCache},
+    taproot::{LeafVersion, TaprootSpendInfo},
+    PublicKey, TapLeafHash, TapSighashType, XOnlyPublicKey,
 };
 use musig2::{
     secp::MaybeScalar,
     secp256k1::{schnorr::Signature, Message},
     BinaryEncoding, PartialSignature, PubNonce, SecNonce,
 };
+use secp256k1::SECP256K1;
 use std::collections::HashMap;
 
 use super::{
-    super::contexts::{base::BaseContext, verifier::VerifierContext},
+    super::{
+        contexts::{base::BaseContext, verifier::VerifierContext},
+        error::{Error, SignatureError},
+    },
     pre_signed::PreSignedTransaction,
     signing::push_taproot_leaf_script_and_control_block_to_witness,
+    signing_frost::{
+        generate_frost_nonce, generate_taproot_frost_aggregated_signature,
+        generate_taproot_frost_partial_signature, verify_taproot_frost_partial_signature,
+        FrostKeyPackage,
+    },
     signing_musig2::{
         generate_aggregated_nonce, generate_nonce, generate_taproot_aggregated_signature,
-        generate_taproot_partial_signature,
+        generate_taproot_partial_signature, verify_taproot_partial_signature,
     },
 };
 
@@ -30,6 +40,8 @@ pub trait PreSignedMusig2Transaction: PreSignedTransaction {
     fn musig2_signatures_mut(
         &mut self,
     ) -> &mut HashMap<usize, HashMap<PublicKey, PartialSignature>>;
+    fn frost_signing_sets(&self) -> &HashMap<usize, Vec<PublicKey>>;
+    fn frost_signing_sets_mut(&mut self) -> &mut HashMap<usize, Vec<PublicKey>>;
     fn verifier_inputs(&self) -> Vec<usize>;
     fn has_nonces_for(&self, verifier_pubkey: PublicKey) -> bool {
         self.has_all_nonces(&[verifier_pubkey])
@@ -53,21 +65,140 @@ pub trait PreSignedMusig2Transaction: PreSignedTransaction {
             })
         })
     }
+    /// Verifiers signing the input, all of them for the n-of-n key or the FROST signing set for a
+    /// t-of-n one: the recorded set, or the one formed by the pushed nonces until it is recorded,
+    /// which is empty until enough verifiers pushed their nonces
+    fn signers(
+        &self,
+        input_index: usize,
+        verifier_pubkeys: &[PublicKey],
+        frost_key_package: Option<&FrostKeyPackage>,
+    ) -> Vec<PublicKey> {
+        match frost_key_package {
+            Some(frost_key_package) => match self.frost_signing_sets().get(&input_index) {
+                Some(signing_set) => signing_set.clone(),
+                None => nonce_signing_set(self, frost_key_package, input_index),
+            },
+            None => verifier_pubkeys.to_vec(),
+        }
+    }
+    fn has_signing_nonces(
+        &self,
+        verifier_pubkeys: &[PublicKey],
+        frost_key_package: Option<&FrostKeyPackage>,
+    ) -> bool {
+        match frost_key_package {
+            Some(_) => self.verifier_inputs().into_iter().all(|input_index| {
+                !self
+                    .signers(input_index, verifier_pubkeys, frost_key_package)
+                    .is_empty()
+            }),
+            None => self.has_all_nonces(verifier_pubkeys),
+        }
+    }
+    fn has_signing_signatures(
+        &self,
+        verifier_pubkeys: &[PublicKey],
+        frost_key_package: Option<&FrostKeyPackage>,
+    ) -> bool {
+        self.verifier_inputs().into_iter().all(|input_index| {
+            let signers = self.signers(input_index, verifier_pubkeys, frost_key_package);
+            !signers.is_empty()
+                && signers
+                    .iter()
+                    .all(|pubkey| self.has_input_signature_of(input_index, pubkey))
+        })
+    }
+    /// Whether the verifier still has to push nonces, a t-of-n verifier set not needing them once
+    /// the signing set is complete
+    fn needs_nonces_from(
+        &self,
+        verifier_pubkey: PublicKey,
+        verifier_pubkeys: &[PublicKey],
+        frost_key_package: Option<&FrostKeyPackage>,
+    ) -> bool {
+        !self.has_nonces_for(verifier_pubkey)
+            && !self.has_signing_nonces(verifier_pubkeys, frost_key_package)
+    }
+    fn needs_signatures_from(
+        &self,
+        verifier_pubkey: PublicKey,
+        verifier_pubkeys: &[PublicKey],
+        frost_key_package: Option<&FrostKeyPackage>,
+    ) -> bool {
+        self.verifier_inputs().into_iter().any(|input_index| {
+            self.signers(input_index, verifier_pubkeys, frost_key_package)
+                .contains(&verifier_pubkey)
+                && !self.has_input_signature_of(input_index, &verifier_pubkey)
+        })
+    }
+    fn has_input_signature_of(&self, input_index: usize, verifier_pubkey: &PublicKey) -> bool {
+        self.musig2_signatures()
+            .get(&input_index)
+            .is_some_and(|signatures| signatures.contains_key(verifier_pubkey))
+    }
+    /// Whether all the signers of the input signed it, the verifier being one of them, so that the
+    /// input can be finalized
+    fn is_input_signed(&self, input_index: usize, context: &VerifierContext) -> bool {
+        let signers = self.signers(
+            input_index,
+            &context.n_of_n_public_keys,
+            context.frost_key_package.as_ref(),
+        );
+        signers.contains(&context.verifier_public_key)
+            && signers
+                .iter()
+                .all(|pubkey| self.has_input_signature_of(input_index, pubkey))
+    }
     fn push_nonces(&mut self, context: &VerifierContext) -> HashMap<usize, SecNonce> {
         self.verifier_inputs()
             .iter()
-            .map(|input_index| (*input_index, self.push_nonce(context, *input_index)))
+            .filter_map(|input_index| {
+                self.push_nonce(context, *input_index)
+                    .map(|secret_nonce| (*input_index, secret_nonce))
+            })
             .collect()
     }
 
-    fn push_nonce(&mut self, context: &VerifierContext, input_index: usize) -> SecNonce {
+    /// Pushes a nonce of the verifier for the input, returning its secret nonce, or none when the
+    /// verifier already pushed one, which other verifiers may have signed with, or once the FROST
+    /// signing set of the input is complete, a new nonce not being part of it, or when the FROST
+    /// nonce of the verifier can't be generated
+    fn push_nonce(&mut self, context: &VerifierContext, input_index: usize) -> Option<SecNonce> {
+        let has_nonce = self
+            .musig2_nonces()
+            .get(&input_index)
+            .is_some_and(|nonces| nonces.contains_key(&context.verifier_public_key));
+        if has_nonce {
+            return None;
+        }
+        if context.frost_key_package.is_some()
+            && !self
+                .signers(
+                    input_index,
+                    &context.n_of_n_public_keys,
+                    context.frost_key_package.as_ref(),
+                )
+                .is_empty()
+        {
+            return None;
+        }
+
+        // FROST nonces are committed with the signing share of the verifier
+        let secret_nonce = match (
+            context.frost_key_package.as_ref(),
+            context.frost_signing_share.as_ref(),
+        ) {
+            (Some(_), Some(signing_share)) => generate_frost_nonce(signing_share).ok()?,
+            _ => generate_nonce(),
+        };
+
         // Push nonce
         let musig2_nonces = self.musig2_nonces_mut();
         if musig2_nonces.get(&input_index).is_none() {
             musig2_nonces.insert(input_index, HashMap::new());
         }
 
-        let secret_nonce = generate_nonce();
         musig2_nonces
             .get_mut(&input_index)
             .unwrap()
@@ -88,10 +219,91 @@ pub trait PreSignedMusig2Transaction: PreSignedTransaction {
             .unwrap()
             .insert(context.verifier_public_key, nonce_signature);
 
-        secret_nonce
+        // The signing set is recorded as soon as the nonces reach the threshold, nonces merged
+        // from other verifiers later on not changing it
+        if let Some(frost_key_package) = context.frost_key_package.as_ref() {
+            let signing_set = nonce_signing_set(self, frost_key_package, input_index);
+            if !signing_set.is_empty() {
+                self.frost_signing_sets_mut()
+                    .insert(input_index, signing_set);
+            }
+        }
+
+        Some(secret_nonce)
+    }
+
+    /// Evicts the nonces of the verifier, returning the evicted nonces, so that it signs again
+    /// with fresh ones, see evict_nonces
+    fn evict_verifier(&mut self, verifier_pubkey: &PublicKey) -> Vec<PubNonce> {
+        let nonces: Vec<PubNonce> = self
+            .musig2_nonces()
+            .values()
+            .filter_map(|nonces| nonces.get(verifier_pubkey))
+            .cloned()
+            .collect();
+        self.evict_nonces(&nonces)
+    }
+
+    /// Evicts the given nonces from the inputs holding them, returning the evicted nonces. The
+    /// partial signatures and the recorded FROST signing set of such an input are dropped, and so
+    /// are the nonces of the verifiers that signed it, a nonce never being signed with twice.
+    fn evict_nonces(&mut self, evicted_nonces: &[PubNonce]) -> Vec<PubNonce> {
+        let mut nonces = Vec::new();
+        for input_index in self.verifier_inputs() {
+            let evicted_verifiers: Vec<PublicKey> = match self.musig2_nonces().get(&input_index) {
+                Some(input_nonces)
+                    if input_nonces
+                        .values()
+                        .any(|nonce| evicted_nonces.contains(nonce)) =>
+                {
+                    input_nonces
+                        .iter()
+                        .filter(|(pubkey, nonce)| {
+                            evicted_nonces.contains(nonce)
+                                || self.has_input_signature_of(input_index, pubkey)
+                        })
+                        .map(|(pubkey, _)| *pubkey)
+                        .collect()
+                }
+                _ => continue,
+            };
+
+            for pubkey in &evicted_verifiers {
+                if let Some(nonce) = self
+                    .musig2_nonces_mut()
+                    .get_mut(&input_index)
+                    .and_then(|input_nonces| input_nonces.remove(pubkey))
+                {
+                    nonces.push(nonce);
+                }
+                if let Some(nonce_signatures) =
+                    self.musig2_nonce_signatures_mut().get_mut(&input_index)
+                {
+                    nonce_signatures.remove(pubkey);
+                }
+            }
+            self.musig2_signatures_mut().remove(&input_index);
+            self.frost_signing_sets_mut().remove(&input_index);
+        }
+
+        nonces
     }
 }
 
+/// FROST signing set formed by all the nonces pushed for the input
+fn nonce_signing_set<T: PreSignedMusig2Transaction + ?Sized>(
+    tx: &T,
+    frost_key_package: &FrostKeyPackage,
+    input_index: usize,
+) -> Vec<PublicKey> {
+    frost_key_package.signing_set(
+        tx.musig2_nonces()
+            .get(&input_index)
+            .into_iter()
+            .flat_map(|nonces| nonces.keys()),
+    )
+}
+
 pub fn get_nonce_message(nonce: &PubNonce) -> Message {
     let nonce_hash = sha256::Hash::hash(nonce.to_bytes().as_slice());
     Message::from_digest_slice(nonce_hash.as_ref()).expect("Failed to create nonce message")
@@ -116,25 +328,68 @@ pub fn pre_sign_musig2_taproot_input<T: PreSignedTransaction + PreSignedMusig2Tr
     context: &VerifierContext,
     input_index: usize,
     sighash_type: TapSighashType,
-    secret_nonce: &SecNonce,
-) {
-    // TODO validate nonces first
+    secret_nonce: Option<&SecNonce>,
+) -> Result<(), Error> {
+    let signers = tx.signers(
+        input_index,
+        &context.n_of_n_public_keys,
+        context.frost_key_package.as_ref(),
+    );
+    if !signers.contains(&context.verifier_public_key)
+        || tx.has_input_signature_of(input_index, &context.verifier_public_key)
+    {
+        // the FROST threshold is reached without this verifier, or not reached yet, or the
+        // verifier already signed the input with its nonce
+        return Ok(());
+    }
+    if context.frost_key_package.is_some() {
+        // the signing set is recorded before signing, so that nonces merged afterwards don't make
+        // this verifier sign for a different set than the other signers
+        tx.frost_signing_sets_mut()
+            .insert(input_index, signers.clone());
+    }
+    validate_musig2_nonces(tx, input_index, &signers)?;
+    let nonces = signer_nonces(tx, input_index, &signers);
+    // a secret nonce whose public nonce was evicted is never signed with again
+    let secret_nonce = secret_nonce
+        .filter(|secret_nonce| nonces[&context.verifier_public_key] == secret_nonce.public_nonce())
+        .ok_or(Error::Signature(SignatureError::MissingNonces {
+            input_index,
+            verifiers: vec![context.verifier_public_key],
+        }))?;
 
     let prev_outs = &tx.prev_outs().clone();
     let script = &tx.prev_scripts()[input_index].clone();
-    let musig2_nonces = &tx.musig2_nonces()[&input_index].values().cloned().collect();
 
-    let partial_signature = generate_taproot_partial_signature(
-        context,
-        tx.tx_mut(),
-        secret_nonce,
-        &generate_aggregated_nonce(musig2_nonces),
-        input_index,
-        prev_outs,
-        script,
-        sighash_type,
-    )
-    .unwrap(); // TODO: Add error handling.
+    let partial_signature = match context.frost_key_package.as_ref() {
+        Some(frost_key_package) => generate_taproot_frost_partial_signature(
+            frost_key_package,
+            &context.verifier_public_key,
+            context
+                .frost_signing_share
+                .as_ref()
+                .ok_or(Error::Signature(SignatureError::MissingFrostSigningShare(
+                    context.verifier_public_key,
+                )))?,
+            tx.tx(),
+            secret_nonce,
+            &nonces,
+            input_index,
+            prev_outs,
+            script,
+            sighash_type,
+        )?,
+        None => generate_taproot_partial_signature(
+            context,
+            tx.tx(),
+            secret_nonce,
+            &generate_aggregated_nonce(&nonces.values().cloned().collect()),
+            input_index,
+            prev_outs,
+            script,
+            sighash_type,
+        )?,
+    };
 
     let musig2_signatures = tx.musig2_signatures_mut();
     if musig2_signatures.get(&input_index).is_none() {
@@ -144,6 +399,145 @@ pub fn pre_sign_musig2_taproot_input<T: PreSignedTransaction + PreSignedMusig2Tr
         .get_mut(&input_index)
         .unwrap()
         .insert(context.verifier_public_key, partial_signature);
+
+    Ok(())
+}
+
+fn signer_nonces<T: PreSignedMusig2Transaction>(
+    tx: &T,
+    input_index: usize,
+    signers: &[PublicKey],
+) -> HashMap<PublicKey, PubNonce> {
+    signers
+        .iter()
+        .map(|signer| (*signer, tx.musig2_nonces()[&input_index][signer].clone()))
+        .collect()
+}
+
+/// Checks that the signers of the input pushed their nonces, signed by their keys
+pub fn validate_musig2_nonces<T: PreSignedMusig2Transaction>(
+    tx: &T,
+    input_index: usize,
+    signers: &[PublicKey],
+) -> Result<(), Error> {
+    let nonces = tx.musig2_nonces().get(&input_index);
+    let missing_verifiers: Vec<PublicKey> = signers
+        .iter()
+        .filter(|&signer| !nonces.is_some_and(|nonces| nonces.contains_key(signer)))
+        .cloned()
+        .collect();
+    if !missing_verifiers.is_empty() {
+        return Err(Error::Signature(SignatureError::MissingNonces {
+            input_index,
+            verifiers: missing_verifiers,
+        }));
+    }
+
+    let nonce_signatures = tx.musig2_nonce_signatures().get(&input_index);
+    let invalid_verifiers: Vec<PublicKey> = signers
+        .iter()
+        .filter(|&signer| {
+            !nonce_signatures
+                .and_then(|nonce_signatures| nonce_signatures.get(signer))
+                .is_some_and(|nonce_signature| {
+                    verify_public_nonce(
+                        nonce_signature,
+                        &tx.musig2_nonces()[&input_index][signer],
+                        &XOnlyPublicKey::from(*signer),
+                    )
+                })
+        })
+        .cloned()
+        .collect();
+    if !invalid_verifiers.is_empty() {
+        return Err(Error::Signature(SignatureError::InvalidNonces {
+            input_index,
+            verifiers: invalid_verifiers,
+        }));
+    }
+
+    Ok(())
+}
+
+/// Checks that all the signers of the input pushed partial signatures valid for their keys and
+/// nonces, identifying the verifiers that did not
+pub fn validate_musig2_partial_signatures<T: PreSignedTransaction + PreSignedMusig2Transaction>(
+    tx: &T,
+    context: &dyn BaseContext,
+    input_index: usize,
+    sighash_type: TapSighashType,
+) -> Result<(), Error> {
+    let signers = tx.signers(
+        input_index,
+        context.n_of_n_public_keys(),
+        context.frost_key_package(),
+    );
+    if signers.is_empty() {
+        // not enough FROST nonces to form a signing set
+        return validate_musig2_nonces(tx, input_index, context.n_of_n_public_keys());
+    }
+    validate_musig2_nonces(tx, input_index, &signers)?;
+
+    let missing_verifiers: Vec<PublicKey> = signers
+        .iter()
+        .filter(|&signer| !tx.has_input_signature_of(input_index, signer))
+        .cloned()
+        .collect();
+    if !missing_verifiers.is_empty() {
+        return Err(Error::Signature(SignatureError::MissingPartialSignatures {
+            input_index,
+            verifiers: missing_verifiers,
+        }));
+    }
+
+    let prev_outs = tx.prev_outs();
+    let script = &tx.prev_scripts()[input_index];
+    let nonces = signer_nonces(tx, input_index, &signers);
+    let aggregated_nonce = generate_aggregated_nonce(&nonces.values().cloned().collect());
+    let mut invalid_verifiers: Vec<PublicKey> = Vec::new();
+    for signer in &signers {
+        let partial_signature = tx.musig2_signatures()[&input_index][signer];
+        let is_valid = match context.frost_key_package() {
+            Some(frost_key_package) => verify_taproot_frost_partial_signature(
+                frost_key_package,
+                signer,
+                partial_signature,
+                tx.tx(),
+                &nonces,
+                input_index,
+                prev_outs,
+                script,
+                sighash_type,
+            ),
+            None => match verify_taproot_partial_signature(
+                context,
+                tx.tx(),
+                partial_signature,
+                signer,
+                &nonces[signer],
+                &aggregated_nonce,
+                input_index,
+                prev_outs,
+                script,
+                sighash_type,
+            ) {
+                Ok(()) => true,
+                Err(Error::Signature(SignatureError::InvalidPartialSignatures { .. })) => false,
+                Err(e) => return Err(e),
+            },
+        };
+        if !is_valid {
+            invalid_verifiers.push(*signer);
+        }
+    }
+    if !invalid_verifiers.is_empty() {
+        return Err(Error::Signature(SignatureError::InvalidPartialSignatures {
+            input_index,
+            verifiers: invalid_verifiers,
+        }));
+    }
+
+    Ok(())
 }
 
 pub fn finalize_musig2_taproot_input<T: PreSignedTransaction + PreSignedMusig2Transaction>(
@@ -152,36 +546,73 @@ pub fn finalize_musig2_taproot_input<T: PreSignedTransaction + PreSignedMusig2Tr
     input_index: usize,
     sighash_type: TapSighashType,
     taproot_spend_info: TaprootSpendInfo,
-) {
-    // TODO: Verify we have partial signatures from all verifiers.
-    // TODO: Verify each signature against the signers public key.
-    // See example here: https://github.com/conduition/musig2/blob/c39bfce58098d337a3ec38b54d93def8306d9953/src/signing.rs#L358C1-L366C65
+) -> Result<(), Error> {
+    validate_musig2_partial_signatures(tx, context, input_index, sighash_type)?;
 
     let prev_outs = &tx.prev_outs().clone();
     let script = &tx.prev_scripts()[input_index].clone();
-    let musig2_nonces: &Vec<PubNonce> =
-        &tx.musig2_nonces()[&input_index].values().cloned().collect();
-    let musig2_signatures: Vec<MaybeScalar> = tx.musig2_signatures()[&input_index]
-        .values()
-        .map(|&partial_signature| PartialSignature::from(partial_signature))
-        .collect();
-    let tx_mut = tx.tx_mut();
+    let signers = tx.signers(
+        input_index,
+        context.n_of_n_public_keys(),
+        context.frost_key_package(),
+    );
+    let nonces = signer_nonces(tx, input_index, &signers);
+    let invalid_aggregated_signature =
+        || Error::Signature(SignatureError::InvalidAggregatedSignature { input_index });
 
     // Aggregate signature
-    let signature = generate_taproot_aggregated_signature(
-        context,
-        tx_mut,
-        &generate_aggregated_nonce(musig2_nonces),
-        input_index,
-        prev_outs,
-        script,
-        sighash_type,
-        musig2_signatures, // TODO: Is there a more elegant way of doing this?
-    )
-    .unwrap(); // TODO: Add error handling.
+    let signature: Signature = match context.frost_key_package() {
+        Some(frost_key_package) => generate_taproot_frost_aggregated_signature(
+            frost_key_package,
+            tx.tx(),
+            &nonces,
+            &tx.musig2_signatures()[&input_index],
+            input_index,
+            prev_outs,
+            script,
+            sighash_type,
+        )?,
+        None => {
+            let musig2_signatures: Vec<MaybeScalar> = signers
+                .iter()
+                .map(|signer| tx.musig2_signatures()[&input_index][signer])
+                .collect();
+
+            generate_taproot_aggregated_signature(
+                context,
+                tx.tx(),
+                &generate_aggregated_nonce(&nonces.values().cloned().collect()),
+                input_index,
+                prev_outs,
+                script,
+                sighash_type,
+                musig2_signatures,
+            )?
+            .into()
+        }
+    };
+
+    // The aggregated signature must be valid for the n-of-n key the connector scripts commit to
+    let sighash = SighashCache::new(tx.tx())
+        .taproot_script_spend_signature_hash(
+            input_index,
+            &Prevouts::All(prev_outs),
+            TapLeafHash::from_script(script, LeafVersion::TapScript),
+            sighash_type,
+        )
+        .expect("Failed to construct sighash");
+    SECP256K1
+        .verify_schnorr(
+            &signature,
+            &Message::from_digest(sighash.to_byte_array()),
+            context.n_of_n_taproot_public_key(),
+        )
+        .map_err(|_| invalid_aggregated_signature())?;
+
+    let tx_mut = tx.tx_mut();
 
     let final_signature = bitcoin::taproot::Signature {
-        signature: signature.into(),
+        signature,
         sighash_type,
     };
 
@@ -197,4 +628,6 @@ pub fn finalize_musig2_taproot_input<T: PreSignedTransaction + PreSignedMusig2Tr
         &taproot_spend_info,
         script,
     );
+
+    Ok(())
 }
@@ -0,0 +1,653 @@
+#![allow(clippy::too_many_arguments)]
+use bitcoin::{
+    hashes::Hash,
+    secp256k1::{schnorr::Signature, Message, SecretKey},
+    sighash::{Prevouts, SighashCache},
+    taproot::LeafVersion,
+    PublicKey, Script, TapLeafHash, TapSighashType, Transaction, TxOut, XOnlyPublicKey,
+};
+use frost_secp256k1_tr::{
+    self as frost,
+    keys::{
+        dkg::{self, round1, round2},
+        KeyPackage, PublicKeyPackage, SigningShare, Tweak, VerifyingShare,
+    },
+    round1::{Nonce, NonceCommitment, SigningCommitments, SigningNonces},
+    round2::SignatureShare,
+    Error as FrostError, Identifier, SigningPackage, VerifyingKey,
+};
+use musig2::{BinaryEncoding, PartialSignature, PubNonce, SecNonce};
+use secp256k1::SECP256K1;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use super::super::error::{Error, SignatureError};
+
+// FROST threshold signing over secp256k1 with the taproot ciphersuite of the FROST reference
+// implementation, producing BIP340 signatures for the group key so that the verifier scripts are
+// unchanged. The ciphersuite signs for the key tweaked as a BIP86 output key, which is the group
+// key of the verifier set. The nonces and partial signatures of the signers are carried by the
+// MuSig2 types of the pre-signed transactions, a public nonce holding the hiding and binding nonce
+// commitments of a signer.
+//
+// The key shares are generated by the verifiers with the distributed key generation of the FROST
+// reference implementation, the participant index of a verifier being its position among the
+// sorted verifier public keys.
+
+/// Public keys of a t-of-n verifier set, shared by all the participants
+#[derive(Serialize, Deserialize, Eq, PartialEq, Clone, Debug)]
+pub struct FrostKeyPackage {
+    pub threshold: usize,
+    // Key generated by the verifiers, whose tweaked key is the group key
+    pub verifying_key: PublicKey,
+    pub group_public_key: PublicKey,
+    // Verifier public key -> Public key of its signing share
+    pub verifying_shares: BTreeMap<PublicKey, PublicKey>,
+}
+
+impl FrostKeyPackage {
+    pub fn verifier_public_keys(&self) -> Vec<PublicKey> {
+        self.verifying_shares.keys().cloned().collect()
+    }
+
+    pub fn group_taproot_public_key(&self) -> XOnlyPublicKey {
+        XOnlyPublicKey::from(self.group_public_key)
+    }
+
+    /// Signers of an input, the `threshold` verifiers with the lowest participant indices among
+    /// the ones that pushed nonces, or none until enough nonces are pushed
+    pub fn signing_set<'a>(
+        &self,
+        nonce_public_keys: impl Iterator<Item = &'a PublicKey>,
+    ) -> Vec<PublicKey> {
+        let mut signers = nonce_public_keys
+            .filter(|public_key| self.verifying_shares.contains_key(public_key))
+            .cloned()
+            .collect::<Vec<_>>();
+        if signers.len() < self.threshold {
+            return vec![];
+        }
+        signers.sort();
+        signers.truncate(self.threshold);
+        signers
+    }
+
+    /// Whether the signing share is the one of the verifier in the verifier set
+    pub fn is_signing_share_of(
+        &self,
+        verifier_public_key: &PublicKey,
+        signing_share: &SecretKey,
+    ) -> bool {
+        self.verifying_shares.get(verifier_public_key)
+            == Some(&PublicKey::new(signing_share.public_key(SECP256K1)))
+    }
+
+    fn frost_verifying_key(&self) -> Option<VerifyingKey> {
+        VerifyingKey::deserialize(&self.verifying_key.to_bytes()).ok()
+    }
+
+    fn frost_verifying_share(&self, verifier_public_key: &PublicKey) -> Option<VerifyingShare> {
+        self.verifying_shares
+            .get(verifier_public_key)
+            .and_then(|verifying_share| {
+                VerifyingShare::deserialize(&verifying_share.to_bytes()).ok()
+            })
+    }
+}
+
+/// First round of the key generation of a t-of-n verifier set, run by each verifier
+///
+/// The package is broadcast to the other verifiers, the secret package is kept for the second
+/// round.
+pub fn generate_frost_dkg_round_1(
+    verifier_public_keys: &[PublicKey],
+    verifier_public_key: &PublicKey,
+    threshold: usize,
+) -> Result<(round1::SecretPackage, round1::Package), Error> {
+    let identifiers = frost_identifiers(verifier_public_keys)?;
+    let identifier = identifiers
+        .get(verifier_public_key)
+        .ok_or(FrostError::UnknownIdentifier)
+        .map_err(frost_key_generation_failed)?;
+    let max_signers = u16::try_from(identifiers.len())
+        .map_err(|_| frost_key_generation_failed(FrostError::InvalidMaxSigners))?;
+    let min_signers = u16::try_from(threshold)
+        .map_err(|_| frost_key_generation_failed(FrostError::InvalidMinSigners))?;
+
+    dkg::part1(*identifier, max_signers, min_signers, rand::thread_rng())
+        .map_err(frost_key_generation_failed)
+}
+
+/// Second round of the key generation, run by each verifier with the first round packages of the
+/// other verifiers
+///
+/// Each package is sent privately to the verifier it is keyed by, the secret package is kept for
+/// the last round.
+pub fn generate_frost_dkg_round_2(
+    verifier_public_keys: &[PublicKey],
+    secret_package: round1::SecretPackage,
+    round_1_packages: &BTreeMap<PublicKey, round1::Package>,
+) -> Result<(round2::SecretPackage, BTreeMap<PublicKey, round2::Package>), Error> {
+    let identifiers = frost_identifiers(verifier_public_keys)?;
+    let (secret_package, packages) = dkg::part2(
+        secret_package,
+        &by_identifier(&identifiers, round_1_packages)?,
+    )
+    .map_err(frost_key_generation_failed)?;
+
+    let packages = identifiers
+        .iter()
+        .filter_map(|(public_key, identifier)| {
+            packages
+                .get(identifier)
+                .map(|package| (*public_key, package.clone()))
+        })
+        .collect();
+
+    Ok((secret_package, packages))
+}
+
+/// Last round of the key generation, run by each verifier with the first round packages of the
+/// other verifiers and the second round packages they sent to it, returning the key package of
+/// the verifier set and the signing share of the verifier
+pub fn generate_frost_key_package(
+    verifier_public_keys: &[PublicKey],
+    secret_package: &round2::SecretPackage,
+    round_1_packages: &BTreeMap<PublicKey, round1::Package>,
+    round_2_packages: &BTreeMap<PublicKey, round2::Package>,
+) -> Result<(FrostKeyPackage, SecretKey), Error> {
+    let identifiers = frost_identifiers(verifier_public_keys)?;
+    let (key_package, public_key_package) = dkg::part3(
+        secret_package,
+        &by_identifier(&identifiers, round_1_packages)?,
+        &by_identifier(&identifiers, round_2_packages)?,
+    )
+    .map_err(frost_key_generation_failed)?;
+
+    let verifying_shares = identifiers
+        .iter()
+        .map(|(public_key, identifier)| {
+            let verifying_share = public_key_package
+                .verifying_shares()
+                .get(identifier)
+                .ok_or(FrostError::UnknownIdentifier)
+                .map_err(frost_key_generation_failed)?;
+            Ok((
+                *public_key,
+                from_frost_element(verifying_share.serialize())?,
+            ))
+        })
+        .collect::<Result<BTreeMap<_, _>, Error>>()?;
+    let group_public_key = public_key_package.clone().tweak(None::<&[u8]>);
+    let frost_key_package = FrostKeyPackage {
+        threshold: *key_package.min_signers() as usize,
+        verifying_key: from_frost_element(public_key_package.verifying_key().serialize())?,
+        group_public_key: from_frost_element(group_public_key.verifying_key().serialize())?,
+        verifying_shares,
+    };
+    let signing_share = SecretKey::from_slice(&key_package.signing_share().serialize())
+        .map_err(|_| frost_key_generation_failed(FrostError::MalformedSigningKey))?;
+
+    Ok((frost_key_package, signing_share))
+}
+
+fn frost_identifiers(
+    verifier_public_keys: &[PublicKey],
+) -> Result<BTreeMap<PublicKey, Identifier>, Error> {
+    let mut verifier_public_keys = verifier_public_keys.to_vec();
+    verifier_public_keys.sort();
+    verifier_public_keys.dedup();
+
+    verifier_public_keys
+        .into_iter()
+        .enumerate()
+        .map(|(position, public_key)| {
+            let identifier = u16::try_from(position + 1)
+                .map_err(|_| FrostError::InvalidMaxSigners)
+                .and_then(Identifier::try_from)
+                .map_err(frost_key_generation_failed)?;
+            Ok((public_key, identifier))
+        })
+        .collect()
+}
+
+fn by_identifier<T: Clone>(
+    identifiers: &BTreeMap<PublicKey, Identifier>,
+    packages: &BTreeMap<PublicKey, T>,
+) -> Result<BTreeMap<Identifier, T>, Error> {
+    packages
+        .iter()
+        .map(|(public_key, package)| {
+            let identifier = identifiers
+                .get(public_key)
+                .ok_or(FrostError::UnknownIdentifier)
+                .map_err(frost_key_generation_failed)?;
+            Ok((*identifier, package.clone()))
+        })
+        .collect()
+}
+
+fn from_frost_element(serialized: Result<Vec<u8>, FrostError>) -> Result<PublicKey, Error> {
+    serialized
+        .and_then(|bytes| {
+            PublicKey::from_slice(&bytes).map_err(|_| FrostError::MalformedVerifyingKey)
+        })
+        .map_err(frost_key_generation_failed)
+}
+
+fn frost_key_generation_failed(error: FrostError) -> Error {
+    Error::Signature(SignatureError::FrostKeyGenerationFailed(error))
+}
+
+/// Nonces of a verifier for the first FROST signing round, committed with its signing share
+///
+/// They are carried by a MuSig2 secret nonce, the hiding nonce followed by the binding nonce, so
+/// that the public nonce pushed to the transactions holds their commitments.
+pub fn generate_frost_nonce(signing_share: &SecretKey) -> Result<SecNonce, Error> {
+    let signing_share = SigningShare::deserialize(&signing_share.secret_bytes())
+        .map_err(frost_key_generation_failed)?;
+    let (signing_nonces, _) = frost::round1::commit(&signing_share, &mut rand::thread_rng());
+    let mut secret_nonce_bytes = signing_nonces.hiding().serialize();
+    secret_nonce_bytes.extend(signing_nonces.binding().serialize());
+    SecNonce::from_bytes(&secret_nonce_bytes)
+        .map_err(|_| frost_key_generation_failed(FrostError::SerializationError))
+}
+
+pub fn generate_taproot_frost_partial_signature(
+    key_package: &FrostKeyPackage,
+    verifier_public_key: &PublicKey,
+    signing_share: &SecretKey,
+    tx: &Transaction,
+    secret_nonce: &SecNonce,
+    nonces: &HashMap<PublicKey, PubNonce>,
+    input_index: usize,
+    prevouts: &[TxOut],
+    script: &Script,
+    sighash_type: TapSighashType,
+) -> Result<PartialSignature, Error> {
+    let partial_signing_failed = || {
+        Error::Signature(SignatureError::PartialSigningFailed {
+            input_index,
+            verifier: *verifier_public_key,
+        })
+    };
+    let message = taproot_script_spend_message(tx, input_index, prevouts, script, sighash_type);
+    let session = SigningSession::new(key_package, nonces, &message)
+        .filter(|session| session.signers.contains(verifier_public_key))
+        .ok_or_else(partial_signing_failed)?;
+
+    let signing_key_package = session
+        .key_package(key_package, verifier_public_key, signing_share)
+        .ok_or_else(partial_signing_failed)?;
+    let signing_nonces = to_frost_nonces(secret_nonce).ok_or_else(partial_signing_failed)?;
+    let signature_share = frost::round2::sign(
+        &session.signing_package,
+        &signing_nonces,
+        &signing_key_package,
+    )
+    .map_err(|_| partial_signing_failed())?;
+
+    from_frost_signature_share(&signature_share).ok_or_else(partial_signing_failed)
+}
+
+pub fn verify_taproot_frost_partial_signature(
+    key_package: &FrostKeyPackage,
+    verifier_public_key: &PublicKey,
+    partial_signature: PartialSignature,
+    tx: &Transaction,
+    nonces: &HashMap<PublicKey, PubNonce>,
+    input_index: usize,
+    prevouts: &[TxOut],
+    script: &Script,
+    sighash_type: TapSighashType,
+) -> bool {
+    let message = taproot_script_spend_message(tx, input_index, prevouts, script, sighash_type);
+    let Some(session) = SigningSession::new(key_package, nonces, &message) else {
+        return false;
+    };
+    if !session.signers.contains(verifier_public_key) {
+        return false;
+    }
+    let (Some(verifying_share), Some(verifying_key), Some(signature_share)) = (
+        key_package.frost_verifying_share(verifier_public_key),
+        key_package.frost_verifying_key(),
+        to_frost_signature_share(&partial_signature),
+    ) else {
+        return false;
+    };
+
+    frost::verify_signature_share(
+        session.identifiers[verifier_public_key],
+        &verifying_share,
+        &signature_share,
+        &session.signing_package,
+        &verifying_key,
+    )
+    .is_ok()
+}
+
+pub fn generate_taproot_frost_aggregated_signature(
+    key_package: &FrostKeyPackage,
+    tx: &Transaction,
+    nonces: &HashMap<PublicKey, PubNonce>,
+    partial_signatures: &HashMap<PublicKey, PartialSignature>,
+    input_index: usize,
+    prevouts: &[TxOut],
+    script: &Script,
+    sighash_type: TapSighashType,
+) -> Result<Signature, Error> {
+    let invalid_aggregated_signature =
+        || Error::Signature(SignatureError::InvalidAggregatedSignature { input_index });
+    let message = taproot_script_spend_message(tx, input_index, prevouts, script, sighash_type);
+    let session = SigningSession::new(key_package, nonces, &message)
+        .ok_or_else(invalid_aggregated_signature)?;
+
+    let missing_verifiers: Vec<PublicKey> = session
+        .signers
+        .iter()
+        .filter(|signer| !partial_signatures.contains_key(signer))
+        .cloned()
+        .collect();
+    if !missing_verifiers.is_empty() {
+        return Err(Error::Signature(SignatureError::MissingPartialSignatures {
+            input_index,
+            verifiers: missing_verifiers,
+        }));
+    }
+
+    let signature_shares = session
+        .signers
+        .iter()
+        .map(|signer| {
+            let signature_share = to_frost_signature_share(&partial_signatures[signer])?;
+            Some((session.identifiers[signer], signature_share))
+        })
+        .collect::<Option<BTreeMap<_, _>>>()
+        .ok_or_else(invalid_aggregated_signature)?;
+    let public_key_package = session
+        .public_key_package(key_package)
+        .ok_or_else(invalid_aggregated_signature)?;
+    let signature = frost::aggregate(
+        &session.signing_package,
+        &signature_shares,
+        &public_key_package,
+    )
+    .and_then(|signature| signature.serialize())
+    .map_err(|_| invalid_aggregated_signature())?;
+
+    let signature =
+        Signature::from_slice(&signature).map_err(|_| invalid_aggregated_signature())?;
+    SECP256K1
+        .verify_schnorr(
+            &signature,
+            &Message::from_digest(message),
+            &key_package.group_taproot_public_key(),
+        )
+        .map_err(|_| invalid_aggregated_signature())?;
+
+    Ok(signature)
+}
+
+struct SigningSession {
+    signers: Vec<PublicKey>,
+    identifiers: BTreeMap<PublicKey, Identifier>,
+    signing_package: SigningPackage,
+}
+
+impl SigningSession {
+    // None when the nonces are not enough for the threshold
+    fn new(
+        key_package: &FrostKeyPackage,
+        nonces: &HashMap<PublicKey, PubNonce>,
+        message: &[u8; 32],
+    ) -> Option<Self> {
+        let signers = key_package.signing_set(nonces.keys());
+        if signers.is_empty() {
+            return None;
+        }
+        let identifiers = frost_identifiers(&key_package.verifier_public_keys()).ok()?;
+
+        let signing_commitments = signers
+            .iter()
+            .map(|signer| Some((identifiers[signer], to_frost_commitments(&nonces[signer])?)))
+            .collect::<Option<BTreeMap<_, _>>>()?;
+
+        Some(SigningSession {
+            signers,
+            identifiers,
+            signing_package: SigningPackage::new(signing_commitments, message),
+        })
+    }
+
+    fn key_package(
+        &self,
+        key_package: &FrostKeyPackage,
+        signer: &PublicKey,
+        signing_share: &SecretKey,
+    ) -> Option<KeyPackage> {
+        Some(KeyPackage::new(
+            self.identifiers[signer],
+            SigningShare::deserialize(&signing_share.secret_bytes()).ok()?,
+            key_package.frost_verifying_share(signer)?,
+            key_package.frost_verifying_key()?,
+            u16::try_from(key_package.threshold).ok()?,
+        ))
+    }
+
+    fn public_key_package(&self, key_package: &FrostKeyPackage) -> Option<PublicKeyPackage> {
+        let verifying_shares = self
+            .identifiers
+            .iter()
+            .map(|(verifier, identifier)| {
+                Some((*identifier, key_package.frost_verifying_share(verifier)?))
+            })
+            .collect::<Option<BTreeMap<_, _>>>()?;
+        Some(PublicKeyPackage::new(
+            verifying_shares,
+            key_package.frost_verifying_key()?,
+        ))
+    }
+}
+
+fn taproot_script_spend_message(
+    tx: &Transaction,
+    input_index: usize,
+    prevouts: &[TxOut],
+    script: &Script,
+    sighash_type: TapSighashType,
+) -> [u8; 32] {
+    let leaf_hash = TapLeafHash::from_script(script, LeafVersion::TapScript);
+    SighashCache::new(tx)
+        .taproot_script_spend_signature_hash(
+            input_index,
+            &Prevouts::All(prevouts),
+            leaf_hash,
+            sighash_type,
+        )
+        .expect("Failed to construct sighash")
+        .to_byte_array()
+}
+
+// The hiding and binding nonces of a secret nonce generated by generate_frost_nonce
+fn to_frost_nonces(secret_nonce: &SecNonce) -> Option<SigningNonces> {
+    let secret_nonce_bytes = secret_nonce.to_bytes();
+    Some(SigningNonces::from_nonces(
+        Nonce::deserialize(&secret_nonce_bytes[..32]).ok()?,
+        Nonce::deserialize(&secret_nonce_bytes[32..]).ok()?,
+    ))
+}
+
+// The hiding and binding nonce commitments of a public nonce
+fn to_frost_commitments(nonce: &PubNonce) -> Option<SigningCommitments> {
+    let nonce_bytes = nonce.to_bytes();
+    Some(SigningCommitments::new(
+        NonceCommitment::deserialize(&nonce_bytes[..33]).ok()?,
+        NonceCommitment::deserialize(&nonce_bytes[33..]).ok()?,
+    ))
+}
+
+fn to_frost_signature_share(partial_signature: &PartialSignature) -> Option<SignatureShare> {
+    SignatureShare::deserialize(&partial_signature.serialize()).ok()
+}
+
+fn from_frost_signature_share(signature_share: &SignatureShare) -> Option<PartialSignature> {
+    PartialSignature::from_slice(&signature_share.serialize()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{
+        absolute::LockTime, transaction::Version, Amount, OutPoint, ScriptBuf, Sequence, TxIn,
+        Witness,
+    };
+
+    use super::*;
+    use crate::contexts::base::generate_keys_from_secret;
+
+    const VERIFIER_SECRETS: [&str; 3] = [
+        "ee0817eac0c13aa8ee2dd3256304041f09f0499d1089b56495310ae8093583e2",
+        "fc294c70faf210d4d0807ea7a3dba8f7e41700d90c119e1ae82a0687d89d297f",
+        "9bf1b2d5e1a09c19e9b7c79d0e7ab4d13f5c1e1c6c0a1a8e4e6bb0d1c9f0a2b3",
+    ];
+
+    // runs the key generation rounds of all the verifiers
+    fn generate_frost_key_shares(
+        verifier_public_keys: &[PublicKey],
+        threshold: usize,
+    ) -> (FrostKeyPackage, HashMap<PublicKey, SecretKey>) {
+        let (round_1_secret_packages, round_1_packages): (BTreeMap<_, _>, BTreeMap<_, _>) =
+            verifier_public_keys
+                .iter()
+                .map(|public_key| {
+                    let (secret_package, package) =
+                        generate_frost_dkg_round_1(verifier_public_keys, public_key, threshold)
+                            .unwrap();
+                    ((*public_key, secret_package), (*public_key, package))
+                })
+                .unzip();
+        let received_round_1_packages = |public_key: &PublicKey| {
+            round_1_packages
+                .iter()
+                .filter(|(sender, _)| *sender != public_key)
+                .map(|(sender, package)| (*sender, package.clone()))
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        let round_2: BTreeMap<PublicKey, _> = round_1_secret_packages
+            .into_iter()
+            .map(|(public_key, secret_package)| {
+                let round_2 = generate_frost_dkg_round_2(
+                    verifier_public_keys,
+                    secret_package,
+                    &received_round_1_packages(&public_key),
+                )
+                .unwrap();
+                (public_key, round_2)
+            })
+            .collect();
+
+        let mut key_packages = vec![];
+        let mut signing_shares = HashMap::new();
+        for (public_key, (secret_package, _)) in round_2.iter() {
+            let received_round_2_packages = round_2
+                .iter()
+                .filter(|(sender, _)| *sender != public_key)
+                .map(|(sender, (_, packages))| (*sender, packages[public_key].clone()))
+                .collect();
+            let (key_package, signing_share) = generate_frost_key_package(
+                verifier_public_keys,
+                secret_package,
+                &received_round_1_packages(public_key),
+                &received_round_2_packages,
+            )
+            .unwrap();
+            assert!(key_package.is_signing_share_of(public_key, &signing_share));
+            key_packages.push(key_package);
+            signing_shares.insert(*public_key, signing_share);
+        }
+        assert!(key_packages.windows(2).all(|pair| pair[0] == pair[1]));
+
+        (key_packages.remove(0), signing_shares)
+    }
+
+    #[test]
+    fn test_frost_threshold_signature() {
+        let verifier_public_keys = VERIFIER_SECRETS
+            .map(|secret| generate_keys_from_secret(bitcoin::Network::Regtest, secret).1);
+        let (key_package, signing_shares) = generate_frost_key_shares(&verifier_public_keys, 2);
+        let script = ScriptBuf::builder()
+            .push_x_only_key(&key_package.group_taproot_public_key())
+            .push_opcode(bitcoin::opcodes::all::OP_CHECKSIG)
+            .into_script();
+        let prevouts = vec![TxOut {
+            value: Amount::from_sat(100_000),
+            script_pubkey: ScriptBuf::new(),
+        }];
+        let tx = Transaction {
+            version: Version(2),
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::MAX,
+                witness: Witness::default(),
+            }],
+            output: vec![],
+        };
+
+        // the first verifier is offline, the two others sign
+        let secret_nonces: HashMap<PublicKey, SecNonce> = verifier_public_keys[1..]
+            .iter()
+            .map(|public_key| {
+                let secret_nonce = generate_frost_nonce(&signing_shares[public_key]).unwrap();
+                (*public_key, secret_nonce)
+            })
+            .collect();
+        let nonces: HashMap<PublicKey, PubNonce> = secret_nonces
+            .iter()
+            .map(|(public_key, secret_nonce)| (*public_key, secret_nonce.public_nonce()))
+            .collect();
+        let partial_signatures: HashMap<PublicKey, PartialSignature> = secret_nonces
+            .iter()
+            .map(|(public_key, secret_nonce)| {
+                let partial_signature = generate_taproot_frost_partial_signature(
+                    &key_package,
+                    public_key,
+                    &signing_shares[public_key],
+                    &tx,
+                    secret_nonce,
+                    &nonces,
+                    0,
+                    &prevouts,
+                    &script,
+                    TapSighashType::All,
+                )
+                .unwrap();
+                assert!(verify_taproot_frost_partial_signature(
+                    &key_package,
+                    public_key,
+                    partial_signature,
+                    &tx,
+                    &nonces,
+                    0,
+                    &prevouts,
+                    &script,
+                    TapSighashType::All,
+                ));
+                (*public_key, partial_signature)
+            })
+            .collect();
+
+        let signature = generate_taproot_frost_aggregated_signature(
+            &key_package,
+            &tx,
+            &nonces,
+            &partial_signatures,
+            0,
+            &prevouts,
+            &script,
+            TapSighashType::All,
+        );
+        assert!(signature.is_ok());
+    }
+}
@@ -2,16 +2,19 @@
 use bitcoin::{
     sighash::{Prevouts, SighashCache},
     taproot::LeafVersion,
-    Script, TapLeafHash, TapSighashType, Transaction, TxOut,
+    PublicKey, Script, TapLeafHash, TapSighashType, Transaction, TxOut,
 };
 use musig2::{
     aggregate_partial_signatures,
-    errors::{SigningError, VerifyError},
     secp::{MaybeScalar, Point},
-    sign_partial, AggNonce, KeyAggContext, LiftedSignature, PartialSignature, PubNonce, SecNonce,
+    sign_partial, verify_partial, AggNonce, KeyAggContext, LiftedSignature, PartialSignature,
+    PubNonce, SecNonce,
 };
 
-use super::super::contexts::{base::BaseContext, verifier::VerifierContext};
+use super::super::{
+    contexts::{base::BaseContext, verifier::VerifierContext},
+    error::{Error, SignatureError},
+};
 
 pub fn generate_nonce() -> SecNonce { SecNonce::build(&mut rand::rngs::OsRng).build() }
 
@@ -26,14 +29,8 @@ pub fn generate_taproot_partial_signature(
     prevouts: &[TxOut],
     script: &Script,
     sighash_type: TapSighashType,
-) -> Result<MaybeScalar, SigningError> {
-    let pubkeys: Vec<Point> = Vec::from_iter(
-        context
-            .n_of_n_public_keys
-            .iter()
-            .map(|&public_key| public_key.inner.into()),
-    ); // TODO: The tests will reveal whether this conversion works as expected.
-    let key_agg_ctx = KeyAggContext::new(pubkeys).unwrap();
+) -> Result<MaybeScalar, Error> {
+    let key_agg_ctx = key_agg_context(&context.n_of_n_public_keys)?;
 
     let leaf_hash = TapLeafHash::from_script(script, LeafVersion::TapScript);
     let sighash = SighashCache::new(tx)
@@ -52,6 +49,55 @@ pub fn generate_taproot_partial_signature(
         aggregated_nonce,
         sighash,
     )
+    .map_err(|_| {
+        Error::Signature(SignatureError::PartialSigningFailed {
+            input_index,
+            verifier: context.verifier_public_key,
+        })
+    })
+}
+
+/// Verifies the partial signature of a verifier against its public key and nonce, so that a
+/// verifier sending an invalid one can be identified before the aggregation fails
+pub fn verify_taproot_partial_signature(
+    context: &dyn BaseContext,
+    tx: &Transaction,
+    partial_signature: PartialSignature,
+    verifier_public_key: &PublicKey,
+    public_nonce: &PubNonce,
+    aggregated_nonce: &AggNonce,
+    input_index: usize,
+    prevouts: &[TxOut],
+    script: &Script,
+    sighash_type: TapSighashType,
+) -> Result<(), Error> {
+    let key_agg_ctx = key_agg_context(context.n_of_n_public_keys())?;
+
+    let leaf_hash = TapLeafHash::from_script(script, LeafVersion::TapScript);
+    let sighash = SighashCache::new(tx)
+        .taproot_script_spend_signature_hash(
+            input_index,
+            &Prevouts::All(prevouts), // TODO: add Prevouts::One
+            leaf_hash,
+            sighash_type,
+        )
+        .expect("Failed to construct sighash");
+
+    let verifier_point: Point = verifier_public_key.inner.into();
+    verify_partial(
+        &key_agg_ctx,
+        partial_signature,
+        aggregated_nonce,
+        verifier_point,
+        public_nonce,
+        sighash,
+    )
+    .map_err(|_| {
+        Error::Signature(SignatureError::InvalidPartialSignatures {
+            input_index,
+            verifiers: vec![*verifier_public_key],
+        })
+    })
 }
 
 pub fn generate_taproot_aggregated_signature(
@@ -63,14 +109,8 @@ pub fn generate_taproot_aggregated_signature(
     script: &Script,
     sighash_type: TapSighashType,
     partial_signatures: Vec<PartialSignature>,
-) -> Result<LiftedSignature, VerifyError> {
-    let pubkeys: Vec<Point> = Vec::from_iter(
-        context
-            .n_of_n_public_keys()
-            .iter()
-            .map(|&public_key| public_key.inner.into()),
-    );
-    let key_agg_ctx = KeyAggContext::new(pubkeys).unwrap();
+) -> Result<LiftedSignature, Error> {
+    let key_agg_ctx = key_agg_context(context.n_of_n_public_keys())?;
 
     let leaf_hash = TapLeafHash::from_script(script, LeafVersion::TapScript);
     let sighash_cache = SighashCache::new(tx)
@@ -88,6 +128,16 @@ pub fn generate_taproot_aggregated_signature(
         partial_signatures,
         sighash_cache,
     )
+    .map_err(|_| Error::Signature(SignatureError::InvalidAggregatedSignature { input_index }))
+}
+
+fn key_agg_context(n_of_n_public_keys: &[PublicKey]) -> Result<KeyAggContext, Error> {
+    let pubkeys: Vec<Point> = Vec::from_iter(
+        n_of_n_public_keys
+            .iter()
+            .map(|&public_key| public_key.inner.into()),
+    ); // TODO: The tests will reveal whether this conversion works as expected.
+    KeyAggContext::new(pubkeys).map_err(|_| Error::Signature(SignatureError::KeyAggregationFailed))
 }
 
 // TODO: This is currently unused and can be removed. If the conversion at the start of the above functions is incorrect, try this approach.
@@ -9,6 +9,7 @@ use super::{
     super::{
         connectors::{base::*, connector_1::Connector1, connector_2::Connector2},
         contexts::{base::BaseContext, operator::OperatorContext, verifier::VerifierContext},
+        error::Error,
         scripts::*,
     },
     base::*,
@@ -28,6 +29,8 @@ pub struct StartTimeTimeoutTransaction {
     musig2_nonces: HashMap<usize, HashMap<PublicKey, PubNonce>>,
     musig2_nonce_signatures: HashMap<usize, HashMap<PublicKey, Signature>>,
     musig2_signatures: HashMap<usize, HashMap<PublicKey, PartialSignature>>,
+    #[serde(default)]
+    frost_signing_sets: HashMap<usize, Vec<PublicKey>>,
 }
 
 impl PreSignedTransaction for StartTimeTimeoutTransaction {
@@ -61,6 +64,10 @@ impl PreSignedMusig2Transaction for StartTimeTimeoutTransaction {
     ) -> &mut HashMap<usize, HashMap<PublicKey, PartialSignature>> {
         &mut self.musig2_signatures
     }
+    fn frost_signing_sets(&self) -> &HashMap<usize, Vec<PublicKey>> { &self.frost_signing_sets }
+    fn frost_signing_sets_mut(&mut self) -> &mut HashMap<usize, Vec<PublicKey>> {
+        &mut self.frost_signing_sets
+    }
     fn verifier_inputs(&self) -> Vec<usize> { vec![0, 1] }
 }
 
@@ -128,6 +135,7 @@ impl StartTimeTimeoutTransaction {
             musig2_nonces: HashMap::new(),
             musig2_nonce_signatures: HashMap::new(),
             musig2_signatures: HashMap::new(),
+            frost_signing_sets: HashMap::new(),
         }
     }
 
@@ -135,8 +143,8 @@ impl StartTimeTimeoutTransaction {
         &mut self,
         context: &VerifierContext,
         connector_2: &Connector2,
-        secret_nonce: &SecNonce,
-    ) {
+        secret_nonce: Option<&SecNonce>,
+    ) -> Result<(), Error> {
         let input_index = 0;
         pre_sign_musig2_taproot_input(
             self,
@@ -144,15 +152,20 @@ impl StartTimeTimeoutTransaction {
             input_index,
             TapSighashType::Single,
             secret_nonce,
-        );
+        )?;
 
-        // TODO: Consider verifying the final signature against the n-of-n public key and the tx.
-        if self.musig2_signatures[&input_index].len() == context.n_of_n_public_keys.len() {
-            self.finalize_input_0(context, connector_2);
+        if self.is_input_signed(input_index, context) {
+            self.finalize_input_0(context, connector_2)?;
         }
+
+        Ok(())
     }
 
-    fn finalize_input_0(&mut self, context: &dyn BaseContext, connector_2: &Connector2) {
+    fn finalize_input_0(
+        &mut self,
+        context: &dyn BaseContext,
+        connector_2: &Connector2,
+    ) -> Result<(), Error> {
         let input_index = 0;
         finalize_musig2_taproot_input(
             self,
@@ -160,15 +173,15 @@ impl StartTimeTimeoutTransaction {
             input_index,
             TapSighashType::Single,
             connector_2.generate_taproot_spend_info(),
-        );
+        )
     }
 
     fn sign_input_1(
         &mut self,
         context: &VerifierContext,
         connector_1: &Connector1,
-        secret_nonce: &SecNonce,
-    ) {
+        secret_nonce: Option<&SecNonce>,
+    ) -> Result<(), Error> {
         let input_index = 1;
         pre_sign_musig2_taproot_input(
             self,
@@ -176,15 +189,20 @@ impl StartTimeTimeoutTransaction {
             input_index,
             TapSighashType::None,
             secret_nonce,
-        );
+        )?;
 
-        // TODO: Consider verifying the final signature against the n-of-n public key and the tx.
-        if self.musig2_signatures[&input_index].len() == context.n_of_n_public_keys.len() {
-            self.finalize_input_1(context, connector_1);
+        if self.is_input_signed(input_index, context) {
+            self.finalize_input_1(context, connector_1)?;
         }
+
+        Ok(())
     }
 
-    fn finalize_input_1(&mut self, context: &dyn BaseContext, connector_1: &Connector1) {
+    fn finalize_input_1(
+        &mut self,
+        context: &dyn BaseContext,
+        connector_1: &Connector1,
+    ) -> Result<(), Error> {
         let input_index = 1;
         finalize_musig2_taproot_input(
             self,
@@ -192,7 +210,7 @@ impl StartTimeTimeoutTransaction {
             input_index,
             TapSighashType::None,
             connector_1.generate_taproot_spend_info(),
-        );
+        )
     }
 
     pub fn pre_sign(
@@ -201,12 +219,14 @@ impl StartTimeTimeoutTransaction {
         connector_1: &Connector1,
         connector_2: &Connector2,
         secret_nonces: &HashMap<usize, SecNonce>,
-    ) {
+    ) -> Result<(), Error> {
         let input_index = 0;
-        self.sign_input_0(context, connector_2, &secret_nonces[&input_index]);
+        self.sign_input_0(context, connector_2, secret_nonces.get(&input_index))?;
 
         let input_index = 1;
-        self.sign_input_1(context, connector_1, &secret_nonces[&input_index]);
+        self.sign_input_1(context, connector_1, secret_nonces.get(&input_index))?;
+
+        Ok(())
     }
 
     pub fn add_output(&mut self, output_script_pubkey: ScriptBuf) {
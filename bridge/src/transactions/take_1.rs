@@ -13,6 +13,7 @@ use super::{
             connector_b::ConnectorB,
         },
         contexts::{base::BaseContext, operator::OperatorContext, verifier::VerifierContext},
+        error::Error,
         scripts::*,
     },
     base::*,
@@ -31,6 +32,8 @@ pub struct Take1Transaction {
     musig2_nonces: HashMap<usize, HashMap<PublicKey, PubNonce>>,
     musig2_nonce_signatures: HashMap<usize, HashMap<PublicKey, Signature>>,
     musig2_signatures: HashMap<usize, HashMap<PublicKey, PartialSignature>>,
+    #[serde(default)]
+    frost_signing_sets: HashMap<usize, Vec<PublicKey>>,
 }
 
 impl PreSignedTransaction for Take1Transaction {
@@ -64,6 +67,10 @@ impl PreSignedMusig2Transaction for Take1Transaction {
     ) -> &mut HashMap<usize, HashMap<PublicKey, PartialSignature>> {
         &mut self.musig2_signatures
     }
+    fn frost_signing_sets(&self) -> &HashMap<usize, Vec<PublicKey>> { &self.frost_signing_sets }
+    fn frost_signing_sets_mut(&mut self) -> &mut HashMap<usize, Vec<PublicKey>> {
+        &mut self.frost_signing_sets
+    }
     fn verifier_inputs(&self) -> Vec<usize> { vec![0, 3] }
 }
 
@@ -166,6 +173,7 @@ impl Take1Transaction {
             musig2_nonces: HashMap::new(),
             musig2_nonce_signatures: HashMap::new(),
             musig2_signatures: HashMap::new(),
+            frost_signing_sets: HashMap::new(),
         }
     }
 
@@ -173,8 +181,8 @@ impl Take1Transaction {
         &mut self,
         context: &VerifierContext,
         connector_0: &Connector0,
-        secret_nonce: &SecNonce,
-    ) {
+        secret_nonce: Option<&SecNonce>,
+    ) -> Result<(), Error> {
         let input_index = 0;
         pre_sign_musig2_taproot_input(
             self,
@@ -182,15 +190,20 @@ impl Take1Transaction {
             input_index,
             TapSighashType::All,
             secret_nonce,
-        );
+        )?;
 
-        // TODO: Consider verifying the final signature against the n-of-n public key and the tx.
-        if self.musig2_signatures[&input_index].len() == context.n_of_n_public_keys.len() {
-            self.finalize_input_0(context, connector_0);
+        if self.is_input_signed(input_index, context) {
+            self.finalize_input_0(context, connector_0)?;
         }
+
+        Ok(())
     }
 
-    fn finalize_input_0(&mut self, context: &dyn BaseContext, connector_0: &Connector0) {
+    fn finalize_input_0(
+        &mut self,
+        context: &dyn BaseContext,
+        connector_0: &Connector0,
+    ) -> Result<(), Error> {
         let input_index = 0;
         finalize_musig2_taproot_input(
             self,
@@ -198,7 +211,7 @@ impl Take1Transaction {
             input_index,
             TapSighashType::All,
             connector_0.generate_taproot_spend_info(),
-        );
+        )
     }
 
     fn sign_input_1(&mut self, context: &OperatorContext, connector_a: &ConnectorA) {
@@ -226,8 +239,8 @@ impl Take1Transaction {
         &mut self,
         context: &VerifierContext,
         connector_b: &ConnectorB,
-        secret_nonce: &SecNonce,
-    ) {
+        secret_nonce: Option<&SecNonce>,
+    ) -> Result<(), Error> {
         let input_index = 3;
         pre_sign_musig2_taproot_input(
             self,
@@ -235,15 +248,20 @@ impl Take1Transaction {
             input_index,
             TapSighashType::All,
             secret_nonce,
-        );
+        )?;
 
-        // TODO: Consider verifying the final signature against the n-of-n public key and the tx.
-        if self.musig2_signatures[&input_index].len() == context.n_of_n_public_keys.len() {
-            self.finalize_input_3(context, connector_b);
+        if self.is_input_signed(input_index, context) {
+            self.finalize_input_3(context, connector_b)?;
         }
+
+        Ok(())
     }
 
-    fn finalize_input_3(&mut self, context: &dyn BaseContext, connector_b: &ConnectorB) {
+    fn finalize_input_3(
+        &mut self,
+        context: &dyn BaseContext,
+        connector_b: &ConnectorB,
+    ) -> Result<(), Error> {
         let input_index = 3;
         finalize_musig2_taproot_input(
             self,
@@ -251,7 +269,7 @@ impl Take1Transaction {
             input_index,
             TapSighashType::All,
             connector_b.generate_taproot_spend_info(),
-        );
+        )
     }
 
     pub fn pre_sign(
@@ -260,12 +278,14 @@ impl Take1Transaction {
         connector_0: &Connector0,
         connector_b: &ConnectorB,
         secret_nonces: &HashMap<usize, SecNonce>,
-    ) {
+    ) -> Result<(), Error> {
         let input_index = 0;
-        self.sign_input_0(context, connector_0, &secret_nonces[&input_index]);
+        self.sign_input_0(context, connector_0, secret_nonces.get(&input_index))?;
 
         let input_index = 3;
-        self.sign_input_3(context, connector_b, &secret_nonces[&input_index]);
+        self.sign_input_3(context, connector_b, secret_nonces.get(&input_index))?;
+
+        Ok(())
     }
 
     pub fn merge(&mut self, take_1: &Take1Transaction) {
@@ -46,16 +46,20 @@ async fn test_assert_tx_success() {
     let secret_nonces_0 = assert_tx.push_nonces(&config.verifier_0_context);
     let secret_nonces_1 = assert_tx.push_nonces(&config.verifier_1_context);
 
-    assert_tx.pre_sign(
-        &config.verifier_0_context,
-        &config.connector_b,
-        &secret_nonces_0,
-    );
-    assert_tx.pre_sign(
-        &config.verifier_1_context,
-        &config.connector_b,
-        &secret_nonces_1,
-    );
+    assert_tx
+        .pre_sign(
+            &config.verifier_0_context,
+            &config.connector_b,
+            &secret_nonces_0,
+        )
+        .unwrap();
+    assert_tx
+        .pre_sign(
+            &config.verifier_1_context,
+            &config.connector_b,
+            &secret_nonces_1,
+        )
+        .unwrap();
 
     println!(
         "tx output before finalize: {:?}",
@@ -78,7 +82,7 @@ async fn test_assert_tx_success() {
     );
     check_tx_output_sum(ONE_HUNDRED, &tx);
     wait_for_timelock_expiry(config.network, Some("kick off 2 connector b")).await;
-    let result = config.client_0.esplora.broadcast(&tx).await;
+    let result = config.client_0.backend.broadcast(&tx).await;
     println!("Txid: {:?}", tx.compute_txid());
     println!("Assert tx result: {:?}\n", result);
     assert!(result.is_ok());
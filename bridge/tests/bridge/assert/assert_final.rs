@@ -84,16 +84,20 @@ async fn test_assert_final_tx_success() {
     let secret_nonces_0 = assert_final_tx.push_nonces(&config.verifier_0_context);
     let secret_nonces_1 = assert_final_tx.push_nonces(&config.verifier_1_context);
 
-    assert_final_tx.pre_sign(
-        &config.verifier_0_context,
-        &config.connector_d,
-        &secret_nonces_0,
-    );
-    assert_final_tx.pre_sign(
-        &config.verifier_1_context,
-        &config.connector_d,
-        &secret_nonces_1,
-    );
+    assert_final_tx
+        .pre_sign(
+            &config.verifier_0_context,
+            &config.connector_d,
+            &secret_nonces_0,
+        )
+        .unwrap();
+    assert_final_tx
+        .pre_sign(
+            &config.verifier_1_context,
+            &config.connector_d,
+            &secret_nonces_1,
+        )
+        .unwrap();
 
     let tx = assert_final_tx.finalize();
     check_tx_output_sum(
@@ -101,7 +105,7 @@ async fn test_assert_final_tx_success() {
             - MIN_RELAY_FEE_ASSERT_FINAL,
         &tx,
     );
-    let result = config.client_0.esplora.broadcast(&tx).await;
+    let result = config.client_0.backend.broadcast(&tx).await;
     println!("Txid: {:?}", tx.compute_txid());
     println!("Assert final tx result: {:?}\n", result);
     println!("Transaction hex: \n{}", serialize_hex(&tx));
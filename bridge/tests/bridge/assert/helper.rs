@@ -1,5 +1,6 @@
 use bitcoin::{Amount, Network, Transaction, Txid};
 use bridge::{
+    client::backend::base::BitcoinBackend,
     connectors::{base::TaprootConnector, connector_b::ConnectorB, connector_d::ConnectorD},
     contexts::verifier::VerifierContext,
     transactions::{
@@ -11,7 +12,6 @@ use bridge::{
         pre_signed_musig2::PreSignedMusig2Transaction,
     },
 };
-use esplora_client::AsyncClient;
 
 use crate::bridge::{
     faucet::Faucet,
@@ -38,7 +38,7 @@ pub async fn fund_create_and_mine_assert_initial_tx(
     .await;
 
     let (tx, _) = create_and_mine_assert_initial_tx(
-        &config.client_0.esplora,
+        &config.client_0.backend,
         config.network,
         &config.verifier_0_context,
         &config.verifier_1_context,
@@ -57,7 +57,7 @@ pub async fn fund_create_and_mine_assert_initial_tx(
 }
 
 pub async fn create_and_mine_assert_initial_tx(
-    esplora: &AsyncClient,
+    backend: &dyn BitcoinBackend,
     network: Network,
     verifier_0_context: &VerifierContext,
     verifier_1_context: &VerifierContext,
@@ -78,14 +78,18 @@ pub async fn create_and_mine_assert_initial_tx(
     let secret_nonces_0 = assert_initial_tx.push_nonces(verifier_0_context);
     let secret_nonces_1 = assert_initial_tx.push_nonces(verifier_1_context);
 
-    assert_initial_tx.pre_sign(verifier_0_context, connector_b, &secret_nonces_0);
-    assert_initial_tx.pre_sign(verifier_1_context, connector_b, &secret_nonces_1);
+    assert_initial_tx
+        .pre_sign(verifier_0_context, connector_b, &secret_nonces_0)
+        .unwrap();
+    assert_initial_tx
+        .pre_sign(verifier_1_context, connector_b, &secret_nonces_1)
+        .unwrap();
 
     let tx = assert_initial_tx.finalize();
     let tx_id = tx.compute_txid();
     println!("Txid: {:?}", tx_id);
     wait_for_timelock_expiry(network, Some("kick off 2 connector b")).await;
-    let result = esplora.broadcast(&tx).await;
+    let result = backend.broadcast(&tx).await;
     println!("Assert initial tx result: {:?}\n", result);
     assert!(result.is_ok());
 
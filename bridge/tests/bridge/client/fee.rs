@@ -15,6 +15,7 @@ use bridge::{
         generate_p2pkh_address, generate_pay_to_pubkey_script,
         generate_pay_to_pubkey_script_address,
     },
+    superblock::find_superblock,
     transactions::{
         base::{
             Input, InputWithScript, MIN_RELAY_FEE_ASSERT_INITIAL, MIN_RELAY_FEE_CHALLENGE,
@@ -24,6 +25,7 @@ use bridge::{
             MIN_RELAY_FEE_START_TIME, MIN_RELAY_FEE_START_TIME_TIMEOUT, MIN_RELAY_FEE_TAKE_1,
             MIN_RELAY_FEE_TAKE_2,
         },
+        fee::ANCHOR_AMOUNT,
         pre_signed::PreSignedTransaction,
     },
 };
@@ -61,15 +63,15 @@ async fn test_peg_in_fees() {
         .create_peg_in_graph(peg_in_input, &config.depositor_evm_address)
         .await;
 
-    let esplora_client = config.client_0.esplora.clone();
+    let backend = config.client_0.backend.clone();
 
     let peg_in_graph = get_peg_in_graph_mut(&mut config.client_0, peg_in_graph_id.clone());
-    let peg_in_deposit_tx = peg_in_graph.deposit(&esplora_client).await.unwrap();
+    let peg_in_deposit_tx = peg_in_graph.deposit(&backend).await.unwrap();
     check_tx_output_sum(
         INITIAL_AMOUNT + max(MIN_RELAY_FEE_PEG_IN_CONFIRM, MIN_RELAY_FEE_PEG_IN_REFUND),
         &peg_in_deposit_tx,
     );
-    let deposit_result = esplora_client.broadcast(&peg_in_deposit_tx).await;
+    let deposit_result = backend.broadcast(&peg_in_deposit_tx).await;
     println!("Deposit result: {deposit_result:?}");
     assert!(deposit_result.is_ok());
     config
@@ -86,14 +88,14 @@ async fn test_peg_in_fees() {
 
     let peg_in_graph = get_peg_in_graph_mut(&mut config.client_0, peg_in_graph_id.clone());
     wait_for_timelock_expiry(config.network, Some("peg-in deposit connector z")).await;
-    let peg_in_confirm_tx = peg_in_graph.confirm(&esplora_client).await.unwrap();
+    let peg_in_confirm_tx = peg_in_graph.confirm(&backend).await.unwrap();
     check_tx_output_sum(
         INITIAL_AMOUNT + max(MIN_RELAY_FEE_PEG_IN_CONFIRM, MIN_RELAY_FEE_PEG_IN_REFUND)
             - MIN_RELAY_FEE_PEG_IN_CONFIRM,
         &peg_in_confirm_tx,
     );
 
-    let peg_in_refund_tx = peg_in_graph.refund(&esplora_client).await.unwrap();
+    let peg_in_refund_tx = peg_in_graph.refund(&backend).await.unwrap();
     check_tx_output_sum(
         INITIAL_AMOUNT + max(MIN_RELAY_FEE_PEG_IN_CONFIRM, MIN_RELAY_FEE_PEG_IN_REFUND)
             - MIN_RELAY_FEE_PEG_IN_REFUND,
@@ -160,7 +162,7 @@ async fn test_peg_out_fees() {
         config.commitment_secrets.clone(),
     );
 
-    let esplora_client = config.client_0.esplora.clone();
+    let backend = config.client_0.backend.clone();
     config
         .client_0
         .broadcast_peg_in_deposit(&peg_in_graph_id)
@@ -238,7 +240,7 @@ async fn test_peg_out_fees() {
     let peg_out_graph = get_peg_out_graph_mut(&mut config.client_0, peg_out_graph_id.clone());
     let peg_out_tx = peg_out_graph
         .peg_out(
-            &esplora_client,
+            &backend,
             &config.operator_context,
             Input {
                 outpoint: peg_out_outpoint,
@@ -248,20 +250,17 @@ async fn test_peg_out_fees() {
         .await
         .unwrap();
     check_tx_output_sum(ONE_HUNDRED, &peg_out_tx);
-    let peg_out_result = esplora_client.broadcast(&peg_out_tx).await;
+    let peg_out_result = backend.broadcast(&peg_out_tx).await;
     wait_for_confirmation(config.network).await;
     println!("peg out tx result: {:?}\n", peg_out_result);
     assert!(peg_out_result.is_ok());
 
-    let peg_out_confirm_tx = peg_out_graph
-        .peg_out_confirm(&esplora_client)
-        .await
-        .unwrap();
+    let peg_out_confirm_tx = peg_out_graph.peg_out_confirm(&backend).await.unwrap();
     check_tx_output_sum(
         reward_amount + PEG_OUT_FEE - MIN_RELAY_FEE_PEG_OUT_CONFIRM,
         &peg_out_confirm_tx,
     );
-    let peg_out_confirm_result = esplora_client.broadcast(&peg_out_confirm_tx).await;
+    let peg_out_confirm_result = backend.broadcast(&peg_out_confirm_tx).await;
     wait_for_confirmation(config.network).await;
     println!("peg out confirm tx result: {:?}\n", peg_out_confirm_result);
     assert!(peg_out_confirm_result.is_ok());
@@ -273,7 +272,7 @@ async fn test_peg_out_fees() {
     let peg_out_graph = get_peg_out_graph_mut(&mut config.client_0, peg_out_graph_id.clone());
     let kick_off_1_tx = peg_out_graph
         .kick_off_1(
-            &esplora_client,
+            &backend,
             &config.operator_context,
             &secrets_map[&CommitmentMessageId::PegOutTxIdSourceNetwork],
             &secrets_map[&CommitmentMessageId::PegOutTxIdDestinationNetwork],
@@ -284,7 +283,7 @@ async fn test_peg_out_fees() {
         reward_amount + PEG_OUT_FEE - MIN_RELAY_FEE_PEG_OUT_CONFIRM - MIN_RELAY_FEE_KICK_OFF_1,
         &kick_off_1_tx,
     );
-    let kick_off_1_result = esplora_client.broadcast(&kick_off_1_tx).await;
+    let kick_off_1_result = backend.broadcast(&kick_off_1_tx).await;
     wait_for_confirmation(config.network).await;
     println!(
         "kick off 1 tx result: {:?}, {:?}\n",
@@ -295,7 +294,7 @@ async fn test_peg_out_fees() {
 
     let start_time_tx = peg_out_graph
         .start_time(
-            &esplora_client,
+            &backend,
             &config.operator_context,
             &secrets_map[&CommitmentMessageId::StartTime],
         )
@@ -306,7 +305,7 @@ async fn test_peg_out_fees() {
     wait_for_timelock_expiry(config.network, Some("kick off 1 connector 1")).await;
     let start_time_timeout_tx = peg_out_graph
         .start_time_timeout(
-            &esplora_client,
+            &backend,
             generate_pay_to_pubkey_script(&config.depositor_context.depositor_public_key),
         )
         .await
@@ -316,7 +315,8 @@ async fn test_peg_out_fees() {
             - MIN_RELAY_FEE_PEG_OUT_CONFIRM
             - MIN_RELAY_FEE_KICK_OFF_1
             - MIN_RELAY_FEE_START_TIME_TIMEOUT
-            - DUST_AMOUNT,
+            - DUST_AMOUNT
+            - ANCHOR_AMOUNT,
         &start_time_timeout_tx,
     );
 
@@ -349,7 +349,7 @@ async fn test_peg_out_fees() {
     let peg_out_graph = get_peg_out_graph_mut(&mut config.client_0, peg_out_graph_id.clone());
     let challenge_tx = peg_out_graph
         .challenge(
-            &esplora_client,
+            &backend,
             &challenge_crowdfunding_inputs,
             &config.depositor_context.depositor_keypair,
             depositor_pubkey_script.clone(),
@@ -367,7 +367,7 @@ async fn test_peg_out_fees() {
         &config.withdrawer_context.withdrawer_public_key,
     );
     let kick_off_timeout_tx = peg_out_graph
-        .kick_off_timeout(&esplora_client, reward_address.script_pubkey())
+        .kick_off_timeout(&backend, reward_address.script_pubkey())
         .await
         .unwrap();
     check_tx_output_sum(
@@ -376,16 +376,18 @@ async fn test_peg_out_fees() {
             - MIN_RELAY_FEE_KICK_OFF_1
             - MIN_RELAY_FEE_KICK_OFF_TIMEOUT
             - DUST_AMOUNT * 2
-            - MIN_RELAY_FEE_START_TIME,
+            - MIN_RELAY_FEE_START_TIME
+            - ANCHOR_AMOUNT,
         &kick_off_timeout_tx,
     );
 
     let kick_off_2_tx = peg_out_graph
         .kick_off_2(
-            &esplora_client,
+            &backend,
             &config.operator_context,
             &config.commitment_secrets[&CommitmentMessageId::Superblock],
             &config.commitment_secrets[&CommitmentMessageId::SuperblockHash],
+            &find_superblock(),
         )
         .await
         .unwrap();
@@ -395,10 +397,11 @@ async fn test_peg_out_fees() {
             - MIN_RELAY_FEE_KICK_OFF_1
             - MIN_RELAY_FEE_KICK_OFF_2
             - DUST_AMOUNT * 2
-            - MIN_RELAY_FEE_START_TIME,
+            - MIN_RELAY_FEE_START_TIME
+            - ANCHOR_AMOUNT,
         &kick_off_2_tx,
     );
-    let kick_off_2_result = esplora_client.broadcast(&kick_off_2_tx).await;
+    let kick_off_2_result = backend.broadcast(&kick_off_2_tx).await;
     println!(
         "kick off 2 tx result: {:?}, {:?}\n",
         kick_off_2_result,
@@ -407,38 +410,41 @@ async fn test_peg_out_fees() {
     wait_for_confirmation(config.network).await;
     wait_for_timelock_expiry(config.network, Some("kick off 2 connector 3")).await;
 
-    let take_1_tx = peg_out_graph.take_1(&esplora_client).await.unwrap();
-    // minus 1 dust from kick off 1 connector 2
+    let take_1_tx = peg_out_graph.take_1(&backend).await.unwrap();
+    // minus 1 dust from kick off 1 connector 2, plus the anchors of assert initial and assert final
     check_tx_output_sum(
         INITIAL_AMOUNT + reward_amount + MIN_RELAY_FEE_ASSERT_SET - MIN_RELAY_FEE_TAKE_1
             + MIN_RELAY_FEE_DISPROVE
-            - DUST_AMOUNT,
+            - DUST_AMOUNT
+            + ANCHOR_AMOUNT * 2,
         &take_1_tx,
     );
 
     let verifier_pubkey_script =
         generate_pay_to_pubkey_script(&config.verifier_0_context.verifier_public_key);
     let disprove_chain_tx = peg_out_graph
-        .disprove_chain(&esplora_client, verifier_pubkey_script.clone())
+        .disprove_chain(&backend, verifier_pubkey_script.clone(), &find_superblock())
         .await
         .unwrap();
     // minus 2 dust from kick off 1, 1 dust from kick off 2
     check_tx_output_sum(
         reward_amount + MIN_RELAY_FEE_ASSERT_SET - MIN_RELAY_FEE_DISPROVE_CHAIN
             + MIN_RELAY_FEE_DISPROVE
-            - DUST_AMOUNT * 3,
+            - DUST_AMOUNT * 3
+            + ANCHOR_AMOUNT * 2,
         &disprove_chain_tx,
     );
 
-    let assert_initial_tx = peg_out_graph.assert_initial(&esplora_client).await.unwrap();
+    let assert_initial_tx = peg_out_graph.assert_initial(&backend).await.unwrap();
     // minus 2 dust from kick off 1, 1 dust from kick off 2
     check_tx_output_sum(
         reward_amount + MIN_RELAY_FEE_ASSERT_SET - MIN_RELAY_FEE_ASSERT_INITIAL
             + MIN_RELAY_FEE_DISPROVE
-            - DUST_AMOUNT * 3,
+            - DUST_AMOUNT * 3
+            + ANCHOR_AMOUNT * 2,
         &assert_initial_tx,
     );
-    let assert_initial_result = esplora_client.broadcast(&assert_initial_tx).await;
+    let assert_initial_result = backend.broadcast(&assert_initial_tx).await;
     println!(
         "assert initial tx result: {:?}, {:?}\n",
         assert_initial_result,
@@ -447,12 +453,12 @@ async fn test_peg_out_fees() {
     wait_for_confirmation(config.network).await;
 
     let assert_commit1_tx = peg_out_graph
-        .assert_commit_1(&esplora_client, &config.commitment_secrets, &get_proof())
+        .assert_commit_1(&backend, &config.commitment_secrets, &get_proof())
         .await
         .unwrap();
     // checked in assert_commit_1 single tx test
     // check_tx_output_sum(assert_commit1_dust_amount, &assert_commit1_tx);
-    let assert_commit1_result = esplora_client.broadcast(&assert_commit1_tx).await;
+    let assert_commit1_result = backend.broadcast(&assert_commit1_tx).await;
     println!(
         "assert commit 1 tx result: {:?}, {:?}\n",
         assert_commit1_result,
@@ -461,12 +467,12 @@ async fn test_peg_out_fees() {
     wait_for_confirmation(config.network).await;
 
     let assert_commit2_tx = peg_out_graph
-        .assert_commit_2(&esplora_client, &config.commitment_secrets, &get_proof())
+        .assert_commit_2(&backend, &config.commitment_secrets, &get_proof())
         .await
         .unwrap();
     // checked in assert_commit_2 single tx test
     // check_tx_output_sum(assert_commit2_dust_amount, &assert_commit2_tx);
-    let assert_commit2_result = esplora_client.broadcast(&assert_commit2_tx).await;
+    let assert_commit2_result = backend.broadcast(&assert_commit2_tx).await;
     println!(
         "assert commit 2 tx result: {:?}, {:?}\n",
         assert_commit2_result,
@@ -474,13 +480,13 @@ async fn test_peg_out_fees() {
     );
     wait_for_confirmation(config.network).await;
 
-    let assert_final_tx = peg_out_graph.assert_final(&esplora_client).await.unwrap();
+    let assert_final_tx = peg_out_graph.assert_final(&backend).await.unwrap();
     // minus 2 dust from kick off 1, 1 dust from kick off 2
     check_tx_output_sum(
-        reward_amount + MIN_RELAY_FEE_DISPROVE - DUST_AMOUNT * 3,
+        reward_amount + MIN_RELAY_FEE_DISPROVE - DUST_AMOUNT * 3 + ANCHOR_AMOUNT,
         &assert_final_tx,
     );
-    let assert_final_result = esplora_client.broadcast(&assert_final_tx).await;
+    let assert_final_result = backend.broadcast(&assert_final_tx).await;
     println!(
         "assert final tx result: {:?}, {:?}\n",
         assert_final_result,
@@ -490,7 +496,7 @@ async fn test_peg_out_fees() {
     wait_for_timelock_expiry(config.network, Some("assert final connector 4")).await;
 
     let take_2_tx = peg_out_graph
-        .take_2(&esplora_client, &config.operator_context)
+        .take_2(&backend, &config.operator_context)
         .await
         .unwrap();
     // minus 2 dust from kick off 1, 1 dust from kick off 2
@@ -503,11 +509,7 @@ async fn test_peg_out_fees() {
 
     let zk_verifying_key = config.invalid_proof.vk;
     let disprove_tx = peg_out_graph
-        .disprove(
-            &esplora_client,
-            verifier_pubkey_script.clone(),
-            &zk_verifying_key,
-        )
+        .disprove(&backend, verifier_pubkey_script.clone(), &zk_verifying_key)
         .await
         .unwrap();
     // minus 2 dust from kick off 1, 1 dust from kick off 2
@@ -3,7 +3,9 @@ use std::str::FromStr;
 use bitcoin::{Amount, OutPoint, Txid};
 use bridge::{
     client::client::{BitVMClient, BitVMClientPublicData},
-    graphs::{base::PEG_OUT_FEE, peg_in::PegInGraph, peg_out::PegOutGraph},
+    graphs::{
+        base::PEG_OUT_FEE, operator_set::OperatorSet, peg_in::PegInGraph, peg_out::PegOutGraph,
+    },
     transactions::base::Input,
 };
 
@@ -19,6 +21,7 @@ async fn test_merge_add_new_graph() {
         version: data.version + 1,
         peg_in_graphs: vec![new_peg_in_graph.clone()],
         peg_out_graphs: vec![new_peg_out_graph.clone()],
+        operator_set: OperatorSet::new(),
     };
 
     assert_eq!(data.peg_in_graphs.len(), 1);
@@ -3,5 +3,7 @@ pub mod merge;
 pub mod musig2_keys;
 pub mod musig2_peg_in;
 pub mod musig2_peg_out;
+pub mod operator_set;
+pub mod rotation;
 pub mod sync;
 pub mod validate;
@@ -1,4 +1,4 @@
-use bitcoin::{Address, Amount, OutPoint};
+use bitcoin::{block::Header, Address, Amount, OutPoint};
 use bridge::{
     client::{
         chain::chain::{Chain, PegOutEvent},
@@ -12,6 +12,9 @@ use bridge::{
         generate_p2pkh_address, generate_pay_to_pubkey_script,
         generate_pay_to_pubkey_script_address,
     },
+    superblock::{
+        find_superblock, get_start_time_block_number, is_heavier_superblock, LocalHeaderSource,
+    },
     transactions::{
         base::{Input, InputWithScript},
         pre_signed::PreSignedTransaction,
@@ -265,6 +268,19 @@ async fn test_musig2_peg_out_disprove_chain_with_challenge() {
     )
     .await;
 
+    // the operator commits the lighter superblock, the heavier one is found afterwards
+    let start_time =
+        get_start_time_block_number(depositor_operator_verifier_0_client.source_network);
+    let (light_superblock, heavy_superblock) = superblocks_in_period(start_time);
+    let mut header_source = LocalHeaderSource::new(0);
+    header_source.push(light_superblock);
+    depositor_operator_verifier_0_client.track_superblock(
+        &peg_out_graph_id,
+        &header_source,
+        start_time,
+        0,
+    );
+
     let with_kick_off_2_tx = true;
     let with_challenge_tx = true;
     let with_assert_tx = false;
@@ -278,6 +294,14 @@ async fn test_musig2_peg_out_disprove_chain_with_challenge() {
     )
     .await;
 
+    header_source.push(heavy_superblock);
+    depositor_operator_verifier_0_client.track_superblock(
+        &peg_out_graph_id,
+        &header_source,
+        start_time,
+        0,
+    );
+
     depositor_operator_verifier_0_client.sync().await;
     depositor_operator_verifier_0_client
         .broadcast_disprove_chain(
@@ -471,12 +495,16 @@ async fn create_peg_out_graph() -> (
 
     println!("Verifier 0 pre-sign peg-out");
     depositor_operator_verifier_0_client.sync().await;
-    depositor_operator_verifier_0_client.push_verifier_signature(&peg_out_graph_id);
+    depositor_operator_verifier_0_client
+        .push_verifier_signature(&peg_out_graph_id)
+        .unwrap();
     depositor_operator_verifier_0_client.flush().await;
 
     println!("Verifier 1 pre-sign peg-out");
     verifier_1_client.sync().await;
-    verifier_1_client.push_verifier_signature(&peg_out_graph_id);
+    verifier_1_client
+        .push_verifier_signature(&peg_out_graph_id)
+        .unwrap();
     verifier_1_client.flush().await;
 
     (
@@ -521,11 +549,11 @@ async fn create_peg_in_graph(
     client_1.flush().await;
 
     client_0.sync().await;
-    client_0.push_verifier_signature(&graph_id);
+    client_0.push_verifier_signature(&graph_id).unwrap();
     client_0.flush().await;
 
     client_1.sync().await;
-    client_1.push_verifier_signature(&graph_id);
+    client_1.push_verifier_signature(&graph_id).unwrap();
     client_1.flush().await;
 
     wait_for_confirmation_with_message(client_0.source_network, Some("peg-in deposit tx")).await;
@@ -624,3 +652,17 @@ async fn simulate_peg_out_from_l2(
 
     wait_for_confirmation_with_message(client.source_network, Some("peg-out confirm tx")).await;
 }
+
+fn superblocks_in_period(start_time: u32) -> (Header, Header) {
+    let superblock = |nonce| Header {
+        time: start_time + 1,
+        nonce,
+        ..find_superblock()
+    };
+    let (a, b) = (superblock(0), superblock(1));
+    if is_heavier_superblock(&a, &b) {
+        (b, a)
+    } else {
+        (a, b)
+    }
+}
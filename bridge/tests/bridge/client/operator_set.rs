@@ -0,0 +1,173 @@
+use std::str::FromStr;
+
+use bitcoin::{Amount, OutPoint, PubkeyHash, PublicKey, Txid};
+
+use bridge::{
+    client::{backend::mock::MockChain, chain::chain::PegOutEvent},
+    contexts::operator::OperatorContext,
+    error::{Error, OperatorError},
+    graphs::{
+        base::DEPOSITOR_SECRET, operator_set::OperatorSet, peg_in::PegInGraph, peg_out::PegOutGraph,
+    },
+    scripts::generate_pay_to_pubkey_script_address,
+    transactions::{base::Input, peg_out::PegOutTransaction},
+};
+
+use crate::bridge::setup::{setup_test, SetupConfig, INITIAL_AMOUNT};
+
+fn stub_input(vout: u32, amount: Amount) -> Input {
+    Input {
+        outpoint: OutPoint {
+            txid: Txid::from_str(
+                "4e254eab8a41f14f56491813a7100cebe305d84edf09488001d9dd3d180a4900",
+            )
+            .unwrap(),
+            vout,
+        },
+        amount,
+    }
+}
+
+fn peg_out_event(operator_public_key: &PublicKey, amount: Amount) -> PegOutEvent {
+    PegOutEvent {
+        withdrawer_chain_address: String::new(),
+        withdrawer_destination_address: String::new(),
+        withdrawer_public_key_hash: PubkeyHash::from_str(
+            "0e6719ac074b0e3cac76d057643506faa1c266b3",
+        )
+        .unwrap(),
+        source_outpoint: stub_input(0, amount).outpoint,
+        amount,
+        operator_public_key: *operator_public_key,
+        timestamp: 0,
+        tx_hash: vec![],
+    }
+}
+
+fn fund_collateral(chain: &MockChain, config: &SetupConfig, context: &OperatorContext) -> Input {
+    let amount = Amount::from_sat(INITIAL_AMOUNT * 3 / 2);
+    let address =
+        generate_pay_to_pubkey_script_address(config.network, &context.operator_public_key);
+    let outpoint = chain.fund(address.script_pubkey(), amount);
+    chain.mine_blocks(1);
+
+    Input { outpoint, amount }
+}
+
+#[tokio::test]
+async fn test_operator_set_fronting_rules() {
+    let config = setup_test().await;
+    let chain = MockChain::new();
+    let operator_0 = &config.operator_context;
+    let operator_1 = OperatorContext::new(
+        config.network,
+        DEPOSITOR_SECRET,
+        &[
+            config.verifier_0_context.verifier_public_key,
+            config.verifier_1_context.verifier_public_key,
+        ],
+    );
+    let withdrawal_amount = Amount::from_sat(INITIAL_AMOUNT);
+
+    let peg_in_graph_0 = PegInGraph::new(
+        &config.depositor_context,
+        stub_input(0, withdrawal_amount),
+        &config.depositor_evm_address,
+    );
+    let peg_in_graph_1 = PegInGraph::new(
+        &config.depositor_context,
+        stub_input(1, withdrawal_amount),
+        &config.depositor_evm_address,
+    );
+    let mut peg_out_graphs: Vec<PegOutGraph> = [
+        (operator_0, &peg_in_graph_0),
+        (&operator_1, &peg_in_graph_0),
+        (operator_0, &peg_in_graph_1),
+    ]
+    .into_iter()
+    .map(|(context, peg_in_graph)| {
+        let mut peg_out_graph = PegOutGraph::new(
+            context,
+            peg_in_graph,
+            stub_input(2, withdrawal_amount),
+            &config.commitment_secrets,
+        );
+        peg_out_graph.peg_out_chain_event = Some(peg_out_event(
+            &context.operator_public_key,
+            withdrawal_amount,
+        ));
+        peg_out_graph
+    })
+    .collect();
+
+    // operators front the withdrawals with their own funds until one registers
+    let mut operator_set = OperatorSet::new();
+    let result = operator_set
+        .check_can_front_withdrawal(&chain, &peg_out_graphs[0], &peg_out_graphs)
+        .await;
+    assert!(result.is_ok());
+
+    // the collateral must be an output of the chain
+    operator_set.register(operator_0, stub_input(3, withdrawal_amount * 2));
+    let result = operator_set
+        .check_can_front_withdrawal(&chain, &peg_out_graphs[0], &peg_out_graphs)
+        .await;
+    assert!(matches!(
+        result,
+        Err(Error::Operator(OperatorError::CollateralUnavailable(_)))
+    ));
+
+    operator_set.register(operator_0, fund_collateral(&chain, &config, operator_0));
+    let result = operator_set
+        .check_can_front_withdrawal(&chain, &peg_out_graphs[0], &peg_out_graphs)
+        .await;
+    assert!(result.is_ok());
+
+    let result = operator_set
+        .check_can_front_withdrawal(&chain, &peg_out_graphs[1], &peg_out_graphs)
+        .await;
+    assert!(matches!(
+        result,
+        Err(Error::Operator(OperatorError::NotRegistered(_)))
+    ));
+    operator_set.register(&operator_1, fund_collateral(&chain, &config, &operator_1));
+
+    // operator 0 fronts the withdrawal of peg-in 0
+    peg_out_graphs[0].peg_out_transaction = Some(PegOutTransaction::new(
+        operator_0,
+        peg_out_graphs[0].peg_out_chain_event.as_ref().unwrap(),
+        stub_input(4, withdrawal_amount),
+    ));
+    assert_eq!(
+        operator_set.locked_collateral(&operator_0.operator_public_key, &peg_out_graphs),
+        withdrawal_amount
+    );
+
+    // the peg-in confirm output only reimburses operator 0
+    let result = operator_set
+        .check_can_front_withdrawal(&chain, &peg_out_graphs[1], &peg_out_graphs)
+        .await;
+    assert!(matches!(
+        result,
+        Err(Error::Operator(OperatorError::WithdrawalAlreadyFronted(id))) if id == *peg_out_graphs[0].id()
+    ));
+
+    // the collateral left by the withdrawal of peg-in 0 does not cover the one of peg-in 1
+    let result = operator_set
+        .check_can_front_withdrawal(&chain, &peg_out_graphs[2], &peg_out_graphs)
+        .await;
+    assert!(matches!(
+        result,
+        Err(Error::Operator(OperatorError::InsufficientCollateral { available, required }))
+            if available == withdrawal_amount / 2 && required == withdrawal_amount
+    ));
+
+    operator_set.deregister(operator_0).unwrap();
+    let result = operator_set
+        .check_can_front_withdrawal(&chain, &peg_out_graphs[0], &peg_out_graphs)
+        .await;
+    assert!(matches!(
+        result,
+        Err(Error::Operator(OperatorError::Deregistered(_)))
+    ));
+}
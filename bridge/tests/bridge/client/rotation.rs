@@ -0,0 +1,143 @@
+use std::str::FromStr;
+
+use bitcoin::{Amount, OutPoint, Txid};
+
+use bridge::{
+    contexts::{base::generate_keys_from_secret, depositor::DepositorContext},
+    error::{ClientError, Error, GraphError},
+    graphs::{
+        base::{
+            BaseGraph, DEPOSITOR_SECRET, VERIFIER_0_SECRET, VERIFIER_1_SECRET, WITHDRAWER_SECRET,
+        },
+        peg_in::PegInGraph,
+    },
+    transactions::{base::Input, pre_signed_musig2::PreSignedMusig2Transaction},
+};
+
+use crate::bridge::{
+    helper::{find_peg_in_graph, generate_frost_key_shares},
+    setup::{setup_test, INITIAL_AMOUNT},
+};
+
+fn stub_input(vout: u32) -> Input {
+    Input {
+        outpoint: OutPoint {
+            txid: Txid::from_str(
+                "4e254eab8a41f14f56491813a7100cebe305d84edf09488001d9dd3d180a4900",
+            )
+            .unwrap(),
+            vout,
+        },
+        amount: Amount::from_sat(INITIAL_AMOUNT),
+    }
+}
+
+#[tokio::test]
+async fn test_frost_verifier_set_rotation_handover() {
+    let mut config = setup_test().await;
+    let network = config.network;
+    let verifier_0_public_key = config.verifier_0_context.verifier_public_key;
+    let verifier_1_public_key = config.verifier_1_context.verifier_public_key;
+
+    // a peg-in of the n-of-n verifier set, known to both of its verifiers
+    let peg_in_graph_id = config
+        .client_0
+        .create_peg_in_graph(stub_input(0), &config.depositor_evm_address)
+        .await;
+    config.client_1.merge_data(config.client_0.data().clone());
+
+    // verifier 0 moves to a 2-of-3 verifier set, with its own signing share only
+    let verifier_public_keys = [VERIFIER_0_SECRET, VERIFIER_1_SECRET, WITHDRAWER_SECRET]
+        .map(|secret| generate_keys_from_secret(network, secret).1);
+    let (frost_key_package, signing_shares) = generate_frost_key_shares(&verifier_public_keys, 2);
+    let result = config
+        .client_0
+        .rotate_frost_verifier_set(&frost_key_package, None)
+        .await;
+    assert!(matches!(
+        result,
+        Err(Error::Client(ClientError::FrostSigningShareNotDefined))
+    ));
+    let result = config
+        .client_0
+        .rotate_frost_verifier_set(
+            &frost_key_package,
+            Some(&signing_shares[&verifier_1_public_key]),
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(Error::Client(ClientError::InvalidFrostSigningShare))
+    ));
+    assert!(find_peg_in_graph(&config.client_0, &peg_in_graph_id).is_some());
+
+    config
+        .client_0
+        .rotate_frost_verifier_set(
+            &frost_key_package,
+            Some(&signing_shares[&verifier_0_public_key]),
+        )
+        .await
+        .unwrap();
+
+    // the new peg-ins are locked to the group key, the graphs of the previous set being left to it
+    assert!(find_peg_in_graph(&config.client_0, &peg_in_graph_id).is_none());
+    let frost_peg_in_graph_id = config
+        .client_0
+        .create_peg_in_graph(stub_input(1), &config.depositor_evm_address)
+        .await;
+    let frost_peg_in_graph = find_peg_in_graph(&config.client_0, &frost_peg_in_graph_id).unwrap();
+    assert_eq!(
+        frost_peg_in_graph.frost_key_package(),
+        Some(&frost_key_package)
+    );
+
+    // the previous set keeps processing its graphs
+    config.client_1.push_verifier_nonces(&peg_in_graph_id);
+    let peg_in_graph = find_peg_in_graph(&config.client_1, &peg_in_graph_id).unwrap();
+    assert!(peg_in_graph
+        .peg_in_confirm_transaction_ref()
+        .has_nonces_for(verifier_1_public_key));
+    assert!(peg_in_graph.frost_key_package().is_none());
+}
+
+#[tokio::test]
+async fn test_peg_in_graph_merge_rejects_other_frost_key_package() {
+    let config = setup_test().await;
+    let network = config.network;
+    let verifier_public_keys = [VERIFIER_0_SECRET, VERIFIER_1_SECRET, WITHDRAWER_SECRET]
+        .map(|secret| generate_keys_from_secret(network, secret).1);
+    let (frost_key_package, _) = generate_frost_key_shares(&verifier_public_keys, 2);
+    let (other_frost_key_package, _) = generate_frost_key_shares(&verifier_public_keys, 2);
+
+    // the verifying shares of another key generation under the same group key, which the graph
+    // id does not commit to
+    let mut forged_frost_key_package = frost_key_package.clone();
+    forged_frost_key_package.verifying_shares = other_frost_key_package.verifying_shares;
+
+    let depositor_context =
+        DepositorContext::new_with_frost_key_package(network, DEPOSITOR_SECRET, &frost_key_package);
+    let forged_depositor_context = DepositorContext::new_with_frost_key_package(
+        network,
+        DEPOSITOR_SECRET,
+        &forged_frost_key_package,
+    );
+    let mut peg_in_graph = PegInGraph::new(
+        &depositor_context,
+        stub_input(0),
+        &config.depositor_evm_address,
+    );
+    let forged_peg_in_graph = PegInGraph::new(
+        &forged_depositor_context,
+        stub_input(0),
+        &config.depositor_evm_address,
+    );
+    assert_eq!(peg_in_graph.id(), forged_peg_in_graph.id());
+
+    let result = peg_in_graph.merge(&forged_peg_in_graph);
+    assert!(matches!(
+        result,
+        Err(Error::Graph(GraphError::FrostKeyPackageMismatch(id))) if id == *peg_in_graph.id()
+    ));
+    assert_eq!(peg_in_graph.frost_key_package(), Some(&frost_key_package));
+}
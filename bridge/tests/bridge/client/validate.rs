@@ -4,7 +4,9 @@ use bitcoin::{Amount, OutPoint, Txid};
 
 use bridge::{
     client::client::{BitVMClient, BitVMClientPublicData},
-    graphs::{base::PEG_OUT_FEE, peg_in::PegInGraph, peg_out::PegOutGraph},
+    graphs::{
+        base::PEG_OUT_FEE, operator_set::OperatorSet, peg_in::PegInGraph, peg_out::PegOutGraph,
+    },
     scripts::generate_burn_script,
     transactions::{base::Input, pre_signed::PreSignedTransaction},
 };
@@ -133,6 +135,7 @@ async fn setup_and_create_graphs() -> (BitVMClientPublicData, OutPoint) {
         version: 1,
         peg_in_graphs: vec![peg_in_graph_0, peg_in_graph_1],
         peg_out_graphs: vec![peg_out_graph],
+        operator_set: OperatorSet::new(),
     };
 
     (data, peg_in_outpoint)
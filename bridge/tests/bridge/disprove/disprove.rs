@@ -56,16 +56,20 @@ async fn test_disprove_tx_success() {
     let secret_nonces_0 = disprove_tx.push_nonces(&config.verifier_0_context);
     let secret_nonces_1 = disprove_tx.push_nonces(&config.verifier_1_context);
 
-    disprove_tx.pre_sign(
-        &config.verifier_0_context,
-        &config.connector_5,
-        &secret_nonces_0,
-    );
-    disprove_tx.pre_sign(
-        &config.verifier_1_context,
-        &config.connector_5,
-        &secret_nonces_1,
-    );
+    disprove_tx
+        .pre_sign(
+            &config.verifier_0_context,
+            &config.connector_5,
+            &secret_nonces_0,
+        )
+        .unwrap();
+    disprove_tx
+        .pre_sign(
+            &config.verifier_1_context,
+            &config.connector_5,
+            &secret_nonces_1,
+        )
+        .unwrap();
 
     let reward_address = generate_pay_to_pubkey_script_address(
         config.withdrawer_context.network,
@@ -76,7 +80,7 @@ async fn test_disprove_tx_success() {
 
     let tx = disprove_tx.finalize();
     check_tx_output_sum(INITIAL_AMOUNT, &tx);
-    let result = config.client_0.esplora.broadcast(&tx).await;
+    let result = config.client_0.backend.broadcast(&tx).await;
     println!("Txid: {:?}", tx.compute_txid());
     println!("Disprove tx result: {:?}\n", result);
     assert!(result.is_ok());
@@ -121,16 +125,20 @@ async fn test_disprove_tx_with_verifier_added_to_output_success() {
     let secret_nonces_0 = disprove_tx.push_nonces(&config.verifier_0_context);
     let secret_nonces_1 = disprove_tx.push_nonces(&config.verifier_1_context);
 
-    disprove_tx.pre_sign(
-        &config.verifier_0_context,
-        &config.connector_5,
-        &secret_nonces_0,
-    );
-    disprove_tx.pre_sign(
-        &config.verifier_1_context,
-        &config.connector_5,
-        &secret_nonces_1,
-    );
+    disprove_tx
+        .pre_sign(
+            &config.verifier_0_context,
+            &config.connector_5,
+            &secret_nonces_0,
+        )
+        .unwrap();
+    disprove_tx
+        .pre_sign(
+            &config.verifier_1_context,
+            &config.connector_5,
+            &secret_nonces_1,
+        )
+        .unwrap();
 
     let reward_address = generate_pay_to_pubkey_script_address(
         config.withdrawer_context.network,
@@ -162,7 +170,7 @@ async fn test_disprove_tx_with_verifier_added_to_output_success() {
     tx.output.push(verifier_output);
     check_tx_output_sum(INITIAL_AMOUNT, &tx);
 
-    let result = config.client_0.esplora.broadcast(&tx).await;
+    let result = config.client_0.backend.broadcast(&tx).await;
     println!("Txid: {:?}", tx.compute_txid());
     println!("Disprove tx result: {:?}\n", result);
     assert!(result.is_ok());
@@ -47,16 +47,20 @@ async fn test_disprove_chain_tx_success() {
     let secret_nonces_0 = disprove_chain_tx.push_nonces(&config.verifier_0_context);
     let secret_nonces_1 = disprove_chain_tx.push_nonces(&config.verifier_1_context);
 
-    disprove_chain_tx.pre_sign(
-        &config.verifier_0_context,
-        &config.connector_b,
-        &secret_nonces_0,
-    );
-    disprove_chain_tx.pre_sign(
-        &config.verifier_1_context,
-        &config.connector_b,
-        &secret_nonces_1,
-    );
+    disprove_chain_tx
+        .pre_sign(
+            &config.verifier_0_context,
+            &config.connector_b,
+            &secret_nonces_0,
+        )
+        .unwrap();
+    disprove_chain_tx
+        .pre_sign(
+            &config.verifier_1_context,
+            &config.connector_b,
+            &secret_nonces_1,
+        )
+        .unwrap();
 
     let reward_address = generate_pay_to_pubkey_script_address(
         config.withdrawer_context.network,
@@ -82,7 +86,7 @@ async fn test_disprove_chain_tx_success() {
     let tx = disprove_chain_tx.finalize();
     check_tx_output_sum(INITIAL_AMOUNT, &tx);
 
-    let result = config.client_0.esplora.broadcast(&tx).await;
+    let result = config.client_0.backend.broadcast(&tx).await;
     println!("Txid: {:?}", tx.compute_txid());
     println!("Disprove Chain tx result: {:?}\n", result);
     assert!(result.is_ok());
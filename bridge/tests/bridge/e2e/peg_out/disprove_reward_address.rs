@@ -0,0 +1,67 @@
+use bitcoin::{secp256k1::SecretKey, PublicKey};
+use bridge::scripts::generate_pay_to_pubkey_script_address;
+
+use colored::Colorize;
+use serial_test::serial;
+
+use crate::bridge::helper::wait_for_confirmation_with_message;
+
+use super::utils::{broadcast_txs_for_disprove_scenario, create_peg_out_graph};
+
+#[tokio::test]
+#[serial(client)]
+async fn test_e2e_disprove_to_reward_address() {
+    let (
+        mut verifier_0_operator_depositor,
+        mut verifier_1,
+        peg_out_graph_id,
+        _,
+        peg_out_input,
+        _,
+        invalid_proof,
+    ) = create_peg_out_graph().await;
+
+    broadcast_txs_for_disprove_scenario(
+        &mut verifier_0_operator_depositor,
+        &mut verifier_1,
+        &peg_out_graph_id,
+        peg_out_input,
+        &invalid_proof,
+    )
+    .await;
+
+    // a fresh address, distinct from the verifier's own one used by default
+    let secret_key = SecretKey::from_slice(&rand::random::<[u8; 32]>()).unwrap();
+    let reward_address = generate_pay_to_pubkey_script_address(
+        verifier_1.source_network,
+        &PublicKey::new(secret_key.public_key(secp256k1::SECP256K1)),
+    );
+    verifier_1.set_verifier_reward_address(&reward_address);
+
+    verifier_1
+        .process_peg_out_as_verifier(&peg_out_graph_id)
+        .await;
+    wait_for_confirmation_with_message(verifier_1.source_network, Some("disprove tx")).await;
+
+    let reward_utxos = verifier_1
+        .backend
+        .get_address_utxo(reward_address)
+        .await
+        .unwrap();
+    assert_eq!(
+        reward_utxos.len(),
+        1,
+        "{}",
+        "The disprove reward should be sent to the given address"
+            .bold()
+            .red()
+    );
+    assert!(reward_utxos[0].value.to_sat() > 0);
+
+    println!(
+        "{}",
+        "Successfully sent the disprove reward to the given address"
+            .bold()
+            .green()
+    );
+}
@@ -1,3 +1,4 @@
 pub mod disprove_reject;
+pub mod disprove_reward_address;
 pub mod disprove_success;
 pub mod utils;
@@ -1,4 +1,10 @@
-use std::{borrow::Cow, collections::BTreeMap, path::Path, str::FromStr, time::Duration};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    path::Path,
+    str::FromStr,
+    time::Duration,
+};
 
 use ark_bn254::g1::G1Affine;
 use ark_bn254::Bn254;
@@ -16,7 +22,7 @@ use bitcoin::{
     hex::{Case::Lower, DisplayHex},
     Address, Amount, BlockHash, CompactTarget, Network, OutPoint, Transaction, TxMerkleNode,
 };
-use bitcoin::{PubkeyHash, PublicKey, Txid};
+use bitcoin::{secp256k1::SecretKey, PubkeyHash, PublicKey, Txid};
 
 use bridge::client::chain::chain::PegOutEvent;
 use bridge::{
@@ -26,6 +32,10 @@ use bridge::{
         peg_in::PegInGraph,
         peg_out::PegOutGraph,
     },
+    transactions::signing_frost::{
+        generate_frost_dkg_round_1, generate_frost_dkg_round_2, generate_frost_key_package,
+        FrostKeyPackage,
+    },
     utils::{num_blocks_per_network, read_cache, write_cache},
 };
 
@@ -283,9 +293,12 @@ pub fn random_hex<'a>(size: usize) -> Cow<'a, str> {
 }
 
 const TEST_CACHE_DIRECTORY_NAME: &str = "test_cache";
-const INTERMEDIATE_VARIABLES_FILE_NAME: &str = "intermediates.bin";
+const INTERMEDIATE_VARIABLES_FILE_NAME: &str = "intermediates_with_batches.bin";
 
-pub fn get_intermediate_variables_cached() -> BTreeMap<String, usize> {
+pub type IntermediateVariableBatches = Vec<Vec<(String, usize)>>;
+
+pub fn get_intermediate_variables_with_batches_cached(
+) -> (BTreeMap<String, usize>, IntermediateVariableBatches) {
     let intermediate_variables_cache_path =
         Path::new(TEST_CACHE_DIRECTORY_NAME).join(INTERMEDIATE_VARIABLES_FILE_NAME);
     let intermediate_variables = if intermediate_variables_cache_path.exists() {
@@ -303,7 +316,8 @@ pub fn get_intermediate_variables_cached() -> BTreeMap<String, usize> {
 
     intermediate_variables.unwrap_or_else(|| {
         println!("Generating new intermediate variables...");
-        let intermediate_variables = BridgeAssigner::default().all_intermediate_variables();
+        let intermediate_variables =
+            BridgeAssigner::default().all_intermediate_variables_with_batches();
         write_cache(&intermediate_variables_cache_path, &intermediate_variables).unwrap();
         intermediate_variables
     })
@@ -344,6 +358,65 @@ pub fn print_tx_broadcasted(tx_name: &str, txid: Txid) {
     println!("Broadcasted {} with txid: {txid}", tx_name.bold().green(),);
 }
 
+// Runs the key generation rounds of all the verifiers, returning the key package of the verifier
+// set and the signing shares of the verifiers
+pub fn generate_frost_key_shares(
+    verifier_public_keys: &[PublicKey],
+    threshold: usize,
+) -> (FrostKeyPackage, HashMap<PublicKey, SecretKey>) {
+    let (round_1_secret_packages, round_1_packages): (BTreeMap<_, _>, BTreeMap<_, _>) =
+        verifier_public_keys
+            .iter()
+            .map(|public_key| {
+                let (secret_package, package) =
+                    generate_frost_dkg_round_1(verifier_public_keys, public_key, threshold)
+                        .unwrap();
+                ((*public_key, secret_package), (*public_key, package))
+            })
+            .unzip();
+    let received_round_1_packages = |public_key: &PublicKey| {
+        round_1_packages
+            .iter()
+            .filter(|(sender, _)| *sender != public_key)
+            .map(|(sender, package)| (*sender, package.clone()))
+            .collect::<BTreeMap<_, _>>()
+    };
+
+    let round_2: BTreeMap<PublicKey, _> = round_1_secret_packages
+        .into_iter()
+        .map(|(public_key, secret_package)| {
+            let round_2 = generate_frost_dkg_round_2(
+                verifier_public_keys,
+                secret_package,
+                &received_round_1_packages(&public_key),
+            )
+            .unwrap();
+            (public_key, round_2)
+        })
+        .collect();
+
+    let mut key_package = None;
+    let mut signing_shares = HashMap::new();
+    for (public_key, (secret_package, _)) in round_2.iter() {
+        let received_round_2_packages = round_2
+            .iter()
+            .filter(|(sender, _)| *sender != public_key)
+            .map(|(sender, (_, packages))| (*sender, packages[public_key].clone()))
+            .collect();
+        let (verifier_key_package, signing_share) = generate_frost_key_package(
+            verifier_public_keys,
+            secret_package,
+            &received_round_1_packages(public_key),
+            &received_round_2_packages,
+        )
+        .unwrap();
+        key_package = Some(verifier_key_package);
+        signing_shares.insert(*public_key, signing_share);
+    }
+
+    (key_package.unwrap(), signing_shares)
+}
+
 // TODO: Consider importing `gen_correct_proof` fn from bitvm/src/chunker/disprove_execution.rs
 // It requires refactoring bitvm crate
 // Copied from bitvm/src/chunker/disprove_execution.rs
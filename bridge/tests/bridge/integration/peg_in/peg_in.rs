@@ -45,7 +45,7 @@ async fn test_peg_in_success() {
     let deposit_txid = peg_in_deposit_tx.compute_txid();
 
     // mine peg-in deposit
-    let deposit_result = config.client_0.esplora.broadcast(&peg_in_deposit_tx).await;
+    let deposit_result = config.client_0.backend.broadcast(&peg_in_deposit_tx).await;
     println!("Peg-in Deposit tx result: {:?}\n", deposit_result);
     assert!(deposit_result.is_ok());
     println!("Deposit Txid: {:?}", deposit_txid);
@@ -70,22 +70,26 @@ async fn test_peg_in_success() {
     let secret_nonces_0 = peg_in_confirm.push_nonces(&config.verifier_0_context);
     let secret_nonces_1 = peg_in_confirm.push_nonces(&config.verifier_1_context);
 
-    peg_in_confirm.pre_sign(
-        &config.verifier_0_context,
-        &config.connector_z,
-        &secret_nonces_0,
-    );
-    peg_in_confirm.pre_sign(
-        &config.verifier_1_context,
-        &config.connector_z,
-        &secret_nonces_1,
-    );
+    peg_in_confirm
+        .pre_sign(
+            &config.verifier_0_context,
+            &config.connector_z,
+            &secret_nonces_0,
+        )
+        .unwrap();
+    peg_in_confirm
+        .pre_sign(
+            &config.verifier_1_context,
+            &config.connector_z,
+            &secret_nonces_1,
+        )
+        .unwrap();
 
     let peg_in_confirm_tx = peg_in_confirm.finalize();
     let confirm_txid = peg_in_confirm_tx.compute_txid();
 
     // mine peg-in confirm
-    let confirm_result = config.client_0.esplora.broadcast(&peg_in_confirm_tx).await;
+    let confirm_result = config.client_0.backend.broadcast(&peg_in_confirm_tx).await;
     println!("Peg-in Confirm tx result: {:?}\n", confirm_result);
     assert!(confirm_result.is_ok());
     println!("Confirm Txid: {:?}", confirm_txid);
@@ -98,7 +102,7 @@ async fn test_peg_in_success() {
     let multi_sig_address = connector_0.generate_taproot_address();
     let multi_sig_utxos = config
         .client_0
-        .esplora
+        .backend
         .get_address_utxo(multi_sig_address.clone())
         .await
         .unwrap();
@@ -137,7 +141,7 @@ async fn test_peg_in_time_lock_not_surpassed() {
     let deposit_txid = peg_in_deposit_tx.compute_txid();
 
     // mine peg-in deposit
-    let deposit_result = config.client_0.esplora.broadcast(&peg_in_deposit_tx).await;
+    let deposit_result = config.client_0.backend.broadcast(&peg_in_deposit_tx).await;
     println!("Peg-in Deposit tx result: {:?}\n", deposit_result);
     assert!(deposit_result.is_ok());
 
@@ -156,7 +160,7 @@ async fn test_peg_in_time_lock_not_surpassed() {
     let peg_in_refund_tx = peg_in_refund.finalize();
 
     // mine peg-in refund
-    let refund_result = config.client_0.esplora.broadcast(&peg_in_refund_tx).await;
+    let refund_result = config.client_0.backend.broadcast(&peg_in_refund_tx).await;
     println!("Peg-in Refund tx result: {:?}\n", refund_result);
     assert!(refund_result.is_err());
     let error = refund_result.unwrap_err();
@@ -187,7 +191,7 @@ async fn test_peg_in_time_lock_surpassed() {
     let deposit_txid = peg_in_deposit_tx.compute_txid();
 
     // mine peg-in deposit
-    let deposit_result = config.client_0.esplora.broadcast(&peg_in_deposit_tx).await;
+    let deposit_result = config.client_0.backend.broadcast(&peg_in_deposit_tx).await;
     println!("Peg-in Deposit tx result: {:?}\n", deposit_result);
     assert!(deposit_result.is_ok());
 
@@ -208,7 +212,7 @@ async fn test_peg_in_time_lock_surpassed() {
 
     // mine peg-in refund
     wait_for_timelock_expiry(config.network, Some("peg-in deposit connector_z")).await;
-    let refund_result = config.client_0.esplora.broadcast(&peg_in_refund_tx).await;
+    let refund_result = config.client_0.backend.broadcast(&peg_in_refund_tx).await;
     println!("Peg-in Refund tx result: {:?}\n", refund_result);
     assert!(refund_result.is_ok());
 
@@ -219,7 +223,7 @@ async fn test_peg_in_time_lock_surpassed() {
     );
     let depositor_utxos = config
         .client_0
-        .esplora
+        .backend
         .get_address_utxo(depositor_address.clone())
         .await
         .unwrap();
@@ -308,7 +312,7 @@ async fn test_peg_in_graph_automatic_verifier() {
     .await;
     let client_0 = &mut config.client_0;
     let client_1 = &mut config.client_1;
-    let esplora = client_0.esplora.clone();
+    let backend = client_0.backend.clone();
     let context = Some(&config.verifier_0_context);
 
     // create the actual graph & check that status changes to PegInWait
@@ -317,7 +321,7 @@ async fn test_peg_in_graph_automatic_verifier() {
         .await;
     assert_eq!(
         graph(client_0)
-            .verifier_status(&esplora, context, &[])
+            .verifier_status(&backend, context, &[])
             .await,
         PegInVerifierStatus::AwaitingDeposit
     );
@@ -329,7 +333,7 @@ async fn test_peg_in_graph_automatic_verifier() {
     loop {
         if !matches!(
             graph(client_0)
-                .verifier_status(&esplora, context, &[])
+                .verifier_status(&backend, context, &[])
                 .await,
             PegInVerifierStatus::AwaitingDeposit
         ) {
@@ -341,7 +345,7 @@ async fn test_peg_in_graph_automatic_verifier() {
 
     assert_eq!(
         graph(client_0)
-            .verifier_status(&esplora, context, &[])
+            .verifier_status(&backend, context, &[])
             .await,
         PegInVerifierStatus::AwaitingPegOutCreation
     );
@@ -378,7 +382,7 @@ async fn test_peg_in_graph_automatic_verifier() {
     assert_eq!(
         graph(client_0)
             .verifier_status(
-                &esplora,
+                &backend,
                 context,
                 &pegouts_of(client_0).iter().collect::<Vec<_>>()
             )
@@ -397,7 +401,7 @@ async fn test_peg_in_graph_automatic_verifier() {
     assert_eq!(
         graph(client_0)
             .verifier_status(
-                &esplora,
+                &backend,
                 context,
                 &pegouts_of(client_0).iter().collect::<Vec<_>>()
             )
@@ -413,7 +417,7 @@ async fn test_peg_in_graph_automatic_verifier() {
     assert!(matches!(
         graph(client_0)
             .verifier_status(
-                &esplora,
+                &backend,
                 context,
                 &pegouts_of(client_0).iter().collect::<Vec<_>>()
             )
@@ -429,7 +433,7 @@ async fn test_peg_in_graph_automatic_verifier() {
     assert_eq!(
         graph(client_0)
             .verifier_status(
-                &esplora,
+                &backend,
                 context,
                 &pegouts_of(client_0).iter().collect::<Vec<_>>()
             )
@@ -445,7 +449,7 @@ async fn test_peg_in_graph_automatic_verifier() {
     assert_eq!(
         graph(client_0)
             .verifier_status(
-                &esplora,
+                &backend,
                 context,
                 &pegouts_of(client_0).iter().collect::<Vec<_>>()
             )
@@ -460,7 +464,7 @@ async fn test_peg_in_graph_automatic_verifier() {
     loop {
         if graph(client_0)
             .verifier_status(
-                &esplora,
+                &backend,
                 context,
                 &pegouts_of(client_0).iter().collect::<Vec<_>>(),
             )
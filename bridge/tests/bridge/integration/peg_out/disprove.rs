@@ -14,6 +14,7 @@ use bridge::{
             MIN_RELAY_FEE_KICK_OFF_2,
         },
         disprove::DisproveTransaction,
+        fee::ANCHOR_AMOUNT,
         pre_signed::PreSignedTransaction,
         pre_signed_musig2::PreSignedMusig2Transaction,
     },
@@ -48,7 +49,8 @@ async fn test_disprove_success() {
             + MIN_RELAY_FEE_ASSERT_COMMIT2
             + MIN_RELAY_FEE_ASSERT_FINAL
             + DUST_AMOUNT // connector 4 to take 2
-            + MIN_RELAY_FEE_DISPROVE,
+            + MIN_RELAY_FEE_DISPROVE
+            + ANCHOR_AMOUNT * 3, // kick off 2, assert initial and assert final anchors
     );
     let kick_off_2_funding_utxo_address = config.connector_1.generate_taproot_address();
     funding_inputs.push((&kick_off_2_funding_utxo_address, kick_off_2_input_amount));
@@ -83,7 +85,7 @@ async fn test_disprove_success() {
     };
 
     let (assert_initial_tx, assert_initial_txid) = create_and_mine_assert_initial_tx(
-        &config.client_0.esplora,
+        &config.client_0.backend,
         config.network,
         &config.verifier_0_context,
         &config.verifier_1_context,
@@ -126,7 +128,7 @@ async fn test_disprove_success() {
         assert_commit_1.tx().input.len(),
         assert_commit_1.tx().output.len()
     );
-    let assert_commit_1_result = config.client_0.esplora.broadcast(&assert_commit_1_tx).await;
+    let assert_commit_1_result = config.client_0.backend.broadcast(&assert_commit_1_tx).await;
     assert!(
         assert_commit_1_result.is_ok(),
         "error: {:?}",
@@ -161,7 +163,7 @@ async fn test_disprove_success() {
         assert_commit_2.tx().input.len(),
         assert_commit_2.tx().output.len()
     );
-    let assert_commit_2_result = config.client_0.esplora.broadcast(&assert_commit_2_tx).await;
+    let assert_commit_2_result = config.client_0.backend.broadcast(&assert_commit_2_tx).await;
     assert!(
         assert_commit_2_result.is_ok(),
         "error: {:?}",
@@ -208,20 +210,24 @@ async fn test_disprove_success() {
     let secret_nonces_0 = assert_final.push_nonces(&config.verifier_0_context);
     let secret_nonces_1 = assert_final.push_nonces(&config.verifier_1_context);
 
-    assert_final.pre_sign(
-        &config.verifier_0_context,
-        &config.connector_d,
-        &secret_nonces_0,
-    );
-    assert_final.pre_sign(
-        &config.verifier_1_context,
-        &config.connector_d,
-        &secret_nonces_1,
-    );
+    assert_final
+        .pre_sign(
+            &config.verifier_0_context,
+            &config.connector_d,
+            &secret_nonces_0,
+        )
+        .unwrap();
+    assert_final
+        .pre_sign(
+            &config.verifier_1_context,
+            &config.connector_d,
+            &secret_nonces_1,
+        )
+        .unwrap();
 
     let assert_final_tx = assert_final.finalize();
     let assert_final_txid = assert_final_tx.compute_txid();
-    let assert_final_result = config.client_0.esplora.broadcast(&assert_final_tx).await;
+    let assert_final_result = config.client_0.backend.broadcast(&assert_final_tx).await;
     assert!(
         assert_final_result.is_ok(),
         "error: {:?}",
@@ -274,16 +280,20 @@ async fn test_disprove_success() {
     let secret_nonces_0 = disprove.push_nonces(&config.verifier_0_context);
     let secret_nonces_1 = disprove.push_nonces(&config.verifier_1_context);
 
-    disprove.pre_sign(
-        &config.verifier_0_context,
-        &config.connector_5,
-        &secret_nonces_0,
-    );
-    disprove.pre_sign(
-        &config.verifier_1_context,
-        &config.connector_5,
-        &secret_nonces_1,
-    );
+    disprove
+        .pre_sign(
+            &config.verifier_0_context,
+            &config.connector_5,
+            &secret_nonces_0,
+        )
+        .unwrap();
+    disprove
+        .pre_sign(
+            &config.verifier_1_context,
+            &config.connector_5,
+            &secret_nonces_1,
+        )
+        .unwrap();
 
     let reward_address = generate_pay_to_pubkey_script_address(
         config.withdrawer_context.network,
@@ -303,14 +313,14 @@ async fn test_disprove_success() {
     // mine disprove
     check_tx_output_sum(INITIAL_AMOUNT, &disprove_tx);
     wait_for_timelock_expiry(config.network, Some("Assert connector 4")).await;
-    let disprove_result = config.client_0.esplora.broadcast(&disprove_tx).await;
+    let disprove_result = config.client_0.backend.broadcast(&disprove_tx).await;
     println!("Disprove tx result: {disprove_result:?}");
     assert!(disprove_result.is_ok());
 
     // reward balance
     let reward_utxos = config
         .client_0
-        .esplora
+        .backend
         .get_address_utxo(reward_address)
         .await
         .unwrap();
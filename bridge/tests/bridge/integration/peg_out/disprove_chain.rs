@@ -6,6 +6,7 @@ use bridge::{
     transactions::{
         base::{BaseTransaction, Input, MIN_RELAY_FEE_DISPROVE_CHAIN, MIN_RELAY_FEE_KICK_OFF_2},
         disprove_chain::DisproveChainTransaction,
+        fee::ANCHOR_AMOUNT,
         pre_signed_musig2::PreSignedMusig2Transaction,
     },
 };
@@ -25,7 +26,11 @@ async fn test_disprove_chain_success() {
     // verify funding inputs
     let mut funding_inputs: Vec<(&Address, Amount)> = vec![];
     let kick_off_2_input_amount = Amount::from_sat(
-        INITIAL_AMOUNT + MIN_RELAY_FEE_KICK_OFF_2 + DUST_AMOUNT + MIN_RELAY_FEE_DISPROVE_CHAIN,
+        INITIAL_AMOUNT
+            + MIN_RELAY_FEE_KICK_OFF_2
+            + DUST_AMOUNT
+            + MIN_RELAY_FEE_DISPROVE_CHAIN
+            + ANCHOR_AMOUNT, // kick off 2 anchor
     );
     let kick_off_2_funding_utxo_address = config.connector_1.generate_taproot_address();
     funding_inputs.push((&kick_off_2_funding_utxo_address, kick_off_2_input_amount));
@@ -68,16 +73,20 @@ async fn test_disprove_chain_success() {
     let secret_nonces_0 = disprove_chain.push_nonces(&config.verifier_0_context);
     let secret_nonces_1 = disprove_chain.push_nonces(&config.verifier_1_context);
 
-    disprove_chain.pre_sign(
-        &config.verifier_0_context,
-        &config.connector_b,
-        &secret_nonces_0,
-    );
-    disprove_chain.pre_sign(
-        &config.verifier_1_context,
-        &config.connector_b,
-        &secret_nonces_1,
-    );
+    disprove_chain
+        .pre_sign(
+            &config.verifier_0_context,
+            &config.connector_b,
+            &secret_nonces_0,
+        )
+        .unwrap();
+    disprove_chain
+        .pre_sign(
+            &config.verifier_1_context,
+            &config.connector_b,
+            &secret_nonces_1,
+        )
+        .unwrap();
 
     let reward_address = generate_pay_to_pubkey_script_address(
         config.withdrawer_context.network,
@@ -91,14 +100,14 @@ async fn test_disprove_chain_success() {
     check_tx_output_sum(INITIAL_AMOUNT, &disprove_chain_tx);
     // mine disprove chain
     wait_for_timelock_expiry(config.network, Some("kick off 2 connector 3")).await;
-    let disprove_chain_result = config.client_0.esplora.broadcast(&disprove_chain_tx).await;
+    let disprove_chain_result = config.client_0.backend.broadcast(&disprove_chain_tx).await;
     println!("disprove chain result: {:?}", disprove_chain_result);
     assert!(disprove_chain_result.is_ok());
 
     // reward balance
     let reward_utxos = config
         .client_0
-        .esplora
+        .backend
         .get_address_utxo(reward_address)
         .await
         .unwrap();
@@ -8,6 +8,7 @@ use bridge::{
             BaseTransaction, Input, MIN_RELAY_FEE_KICK_OFF_1, MIN_RELAY_FEE_KICK_OFF_TIMEOUT,
             MIN_RELAY_FEE_START_TIME,
         },
+        fee::ANCHOR_AMOUNT,
         kick_off_timeout::KickOffTimeoutTransaction,
         pre_signed_musig2::PreSignedMusig2Transaction,
     },
@@ -30,7 +31,8 @@ async fn test_kick_off_timeout_success() {
             + MIN_RELAY_FEE_KICK_OFF_1
             + MIN_RELAY_FEE_START_TIME // kick off 1 carries relay fee for start time
             + DUST_AMOUNT * 2
-            + MIN_RELAY_FEE_KICK_OFF_TIMEOUT,
+            + MIN_RELAY_FEE_KICK_OFF_TIMEOUT
+            + ANCHOR_AMOUNT, // kick off 1 anchor
     );
     let kick_off_1_funding_utxo_address = config.connector_6.generate_taproot_address();
     faucet
@@ -77,22 +79,26 @@ async fn test_kick_off_timeout_success() {
         &config.verifier_0_context.n_of_n_taproot_public_key,
         &config.connector_1.commitment_public_keys, // Verifiers get this via remote storage.
     );
-    kick_off_timeout.pre_sign(
-        &config.verifier_0_context,
-        &verifier_0_connector_1,
-        &secret_nonces_0,
-    );
+    kick_off_timeout
+        .pre_sign(
+            &config.verifier_0_context,
+            &verifier_0_connector_1,
+            &secret_nonces_0,
+        )
+        .unwrap();
     let verifier_1_connector_1 = Connector1::new(
         config.verifier_0_context.network,
         &config.operator_context.operator_taproot_public_key,
         &config.verifier_0_context.n_of_n_taproot_public_key,
         &config.connector_1.commitment_public_keys,
     );
-    kick_off_timeout.pre_sign(
-        &config.verifier_1_context,
-        &verifier_1_connector_1,
-        &secret_nonces_1,
-    );
+    kick_off_timeout
+        .pre_sign(
+            &config.verifier_1_context,
+            &verifier_1_connector_1,
+            &secret_nonces_1,
+        )
+        .unwrap();
 
     let reward_address = generate_pay_to_pubkey_script_address(
         config.withdrawer_context.network,
@@ -108,7 +114,7 @@ async fn test_kick_off_timeout_success() {
     wait_for_timelock_expiry(config.network, Some("kick off 1 connector 1")).await;
     let kick_off_timeout_result = config
         .client_0
-        .esplora
+        .backend
         .broadcast(&kick_off_timeout_tx)
         .await;
     println!("Kick-off timeout result: {kick_off_timeout_result:?}");
@@ -117,7 +123,7 @@ async fn test_kick_off_timeout_success() {
     // reward balance
     let reward_utxos = config
         .client_0
-        .esplora
+        .backend
         .get_address_utxo(reward_address)
         .await
         .unwrap();
@@ -78,7 +78,7 @@ async fn test_start_time_success() {
     check_tx_output_sum(DUST_AMOUNT, &start_time_tx);
     // mine start time timeout
     wait_for_timelock_expiry(config.network, Some("kick off 1 connector 1")).await;
-    let start_time_result = config.client_0.esplora.broadcast(&start_time_tx).await;
+    let start_time_result = config.client_0.backend.broadcast(&start_time_tx).await;
     println!("Start time tx result: {:?}\n", start_time_result);
     assert!(start_time_result.is_ok());
 }
@@ -8,6 +8,7 @@ use bridge::{
             BaseTransaction, Input, MIN_RELAY_FEE_KICK_OFF_1, MIN_RELAY_FEE_START_TIME,
             MIN_RELAY_FEE_START_TIME_TIMEOUT,
         },
+        fee::ANCHOR_AMOUNT,
         pre_signed_musig2::PreSignedMusig2Transaction,
         start_time_timeout::StartTimeTimeoutTransaction,
     },
@@ -31,7 +32,8 @@ async fn test_start_time_timeout_success() {
         INITIAL_AMOUNT
             + MIN_RELAY_FEE_KICK_OFF_1
             + MIN_RELAY_FEE_START_TIME // kick off 1 carries relay fee for start time, which also covers start time timeout
-            + DUST_AMOUNT * 2,
+            + DUST_AMOUNT * 2
+            + ANCHOR_AMOUNT, // kick off 1 anchor
     );
     let kick_off_1_funding_utxo_address = config.connector_6.generate_taproot_address();
     funding_inputs.push((&kick_off_1_funding_utxo_address, kick_off_1_input_amount));
@@ -84,18 +86,22 @@ async fn test_start_time_timeout_success() {
     let secret_nonces_0 = start_time_timeout.push_nonces(&config.verifier_0_context);
     let secret_nonces_1 = start_time_timeout.push_nonces(&config.verifier_1_context);
 
-    start_time_timeout.pre_sign(
-        &config.verifier_0_context,
-        &config.connector_1,
-        &config.connector_2,
-        &secret_nonces_0,
-    );
-    start_time_timeout.pre_sign(
-        &config.verifier_1_context,
-        &config.connector_1,
-        &config.connector_2,
-        &secret_nonces_1,
-    );
+    start_time_timeout
+        .pre_sign(
+            &config.verifier_0_context,
+            &config.connector_1,
+            &config.connector_2,
+            &secret_nonces_0,
+        )
+        .unwrap();
+    start_time_timeout
+        .pre_sign(
+            &config.verifier_1_context,
+            &config.connector_1,
+            &config.connector_2,
+            &secret_nonces_1,
+        )
+        .unwrap();
 
     let reward_address = generate_pay_to_pubkey_script_address(
         config.withdrawer_context.network,
@@ -115,7 +121,7 @@ async fn test_start_time_timeout_success() {
     wait_for_timelock_expiry(config.network, Some("kick off 1 connector 1")).await;
     let start_time_timeout_result = config
         .client_0
-        .esplora
+        .backend
         .broadcast(&start_time_timeout_tx)
         .await;
     println!("Start time timeout result: {:?}", start_time_timeout_result);
@@ -124,7 +130,7 @@ async fn test_start_time_timeout_success() {
     // reward balance
     let reward_utxos = config
         .client_0
-        .esplora
+        .backend
         .get_address_utxo(reward_address)
         .await
         .unwrap();
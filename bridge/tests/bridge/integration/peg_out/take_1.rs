@@ -109,7 +109,7 @@ async fn test_take_1_success() {
 
     // mine kick-off 2
     wait_for_timelock_expiry(config.network, Some("kick off 1 connector 1")).await;
-    let kick_off_2_result = config.client_0.esplora.broadcast(&kick_off_2_tx).await;
+    let kick_off_2_result = config.client_0.backend.broadcast(&kick_off_2_tx).await;
     println!("Kick-off 2 result: {:?}\n", kick_off_2_result);
     assert!(kick_off_2_result.is_ok());
 
@@ -162,18 +162,22 @@ async fn test_take_1_success() {
     let secret_nonces_0 = take_1.push_nonces(&config.verifier_0_context);
     let secret_nonces_1 = take_1.push_nonces(&config.verifier_1_context);
 
-    take_1.pre_sign(
-        &config.verifier_0_context,
-        &config.connector_0,
-        &config.connector_b,
-        &secret_nonces_0,
-    );
-    take_1.pre_sign(
-        &config.verifier_1_context,
-        &config.connector_0,
-        &config.connector_b,
-        &secret_nonces_1,
-    );
+    take_1
+        .pre_sign(
+            &config.verifier_0_context,
+            &config.connector_0,
+            &config.connector_b,
+            &secret_nonces_0,
+        )
+        .unwrap();
+    take_1
+        .pre_sign(
+            &config.verifier_1_context,
+            &config.connector_0,
+            &config.connector_b,
+            &secret_nonces_1,
+        )
+        .unwrap();
 
     let take_1_tx = take_1.finalize();
     let take_1_txid = take_1_tx.compute_txid();
@@ -182,7 +186,7 @@ async fn test_take_1_success() {
     check_tx_output_sum(ONE_HUNDRED + reward_amount + DUST_AMOUNT, &take_1_tx);
     // mine take 1
     wait_for_timelock_expiry(config.network, Some("kick off 2 connector 3")).await;
-    let take_1_result = config.client_0.esplora.broadcast(&take_1_tx).await;
+    let take_1_result = config.client_0.backend.broadcast(&take_1_tx).await;
     println!("TAKE 1 result: {:?}\n", take_1_result);
     assert!(take_1_result.is_ok());
 
@@ -193,7 +197,7 @@ async fn test_take_1_success() {
     );
     let operator_utxos = config
         .client_0
-        .esplora
+        .backend
         .get_address_utxo(operator_address.clone())
         .await
         .unwrap();
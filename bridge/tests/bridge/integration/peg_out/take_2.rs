@@ -124,18 +124,22 @@ async fn test_take_2_success() {
     let secret_nonces_0 = take_2.push_nonces(&config.verifier_0_context);
     let secret_nonces_1 = take_2.push_nonces(&config.verifier_1_context);
 
-    take_2.pre_sign(
-        &config.verifier_0_context,
-        &config.connector_0,
-        &config.connector_5,
-        &secret_nonces_0,
-    );
-    take_2.pre_sign(
-        &config.verifier_1_context,
-        &config.connector_0,
-        &config.connector_5,
-        &secret_nonces_1,
-    );
+    take_2
+        .pre_sign(
+            &config.verifier_0_context,
+            &config.connector_0,
+            &config.connector_5,
+            &secret_nonces_0,
+        )
+        .unwrap();
+    take_2
+        .pre_sign(
+            &config.verifier_1_context,
+            &config.connector_0,
+            &config.connector_5,
+            &secret_nonces_1,
+        )
+        .unwrap();
 
     take_2.sign(&config.operator_context, &config.connector_c);
 
@@ -145,7 +149,7 @@ async fn test_take_2_success() {
     // mine take 2
     check_tx_output_sum(INITIAL_AMOUNT + reward_amount + DUST_AMOUNT * 2, &take_2_tx);
     wait_for_timelock_expiry(config.network, Some("assert connector 4")).await;
-    let take_2_result = config.client_0.esplora.broadcast(&take_2_tx).await;
+    let take_2_result = config.client_0.backend.broadcast(&take_2_tx).await;
     println!("Take 2 result: {:?}\n", take_2_result);
     assert!(take_2_result.is_ok());
 
@@ -156,7 +160,7 @@ async fn test_take_2_success() {
     );
     let operator_utxos = config
         .client_0
-        .esplora
+        .backend
         .get_address_utxo(operator_address.clone())
         .await
         .unwrap();
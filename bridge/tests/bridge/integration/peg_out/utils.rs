@@ -71,7 +71,7 @@ pub async fn create_and_mine_kick_off_1_tx(
     let kick_off_1_txid = kick_off_1_tx.compute_txid();
 
     // mine kick-off 1 tx
-    let kick_off_1_result = client.esplora.broadcast(&kick_off_1_tx).await;
+    let kick_off_1_result = client.backend.broadcast(&kick_off_1_tx).await;
     println!("Kick-off 1 result: {kick_off_1_result:?}");
     assert!(kick_off_1_result.is_ok());
 
@@ -117,7 +117,7 @@ pub async fn create_and_mine_kick_off_2_tx(
 
     // mine kick-off 2 tx
     wait_for_timelock_expiry(operator_context.network, Some("kick off 1 connector 1")).await;
-    let kick_off_2_result = client.esplora.broadcast(&kick_off_2_tx).await;
+    let kick_off_2_result = client.backend.broadcast(&kick_off_2_tx).await;
     println!("Kick off 2 tx result: {kick_off_2_result:?}");
     assert!(kick_off_2_result.is_ok());
 
@@ -153,15 +153,19 @@ pub async fn create_and_mine_assert_tx(
     let secret_nonces_0 = assert.push_nonces(verifier_0_context);
     let secret_nonces_1 = assert.push_nonces(verifier_1_context);
 
-    assert.pre_sign(verifier_0_context, connector_b, &secret_nonces_0);
-    assert.pre_sign(verifier_1_context, connector_b, &secret_nonces_1);
+    assert
+        .pre_sign(verifier_0_context, connector_b, &secret_nonces_0)
+        .unwrap();
+    assert
+        .pre_sign(verifier_1_context, connector_b, &secret_nonces_1)
+        .unwrap();
 
     let assert_tx = assert.finalize();
     let assert_txid = assert_tx.compute_txid();
 
     // mine assert tx
     wait_for_timelock_expiry(verifier_0_context.network, Some("kick off 2 connector b")).await;
-    let assert_result = client.esplora.broadcast(&assert_tx).await;
+    let assert_result = client.backend.broadcast(&assert_tx).await;
     assert!(assert_result.is_ok());
 
     (assert_tx, assert_txid)
@@ -190,14 +194,18 @@ pub async fn create_and_mine_peg_in_confirm_tx(
     let secret_nonces_0 = peg_in_confirm.push_nonces(verifier_0_context);
     let secret_nonces_1 = peg_in_confirm.push_nonces(verifier_1_context);
 
-    peg_in_confirm.pre_sign(verifier_0_context, connector_z, &secret_nonces_0);
-    peg_in_confirm.pre_sign(verifier_1_context, connector_z, &secret_nonces_1);
+    peg_in_confirm
+        .pre_sign(verifier_0_context, connector_z, &secret_nonces_0)
+        .unwrap();
+    peg_in_confirm
+        .pre_sign(verifier_1_context, connector_z, &secret_nonces_1)
+        .unwrap();
 
     let peg_in_confirm_tx = peg_in_confirm.finalize();
     let peg_in_confirm_txid = peg_in_confirm_tx.compute_txid();
 
     // mine peg-in confirm
-    let confirm_result = client.esplora.broadcast(&peg_in_confirm_tx).await;
+    let confirm_result = client.backend.broadcast(&peg_in_confirm_tx).await;
     assert!(confirm_result.is_ok());
 
     (peg_in_confirm_tx, peg_in_confirm_txid)
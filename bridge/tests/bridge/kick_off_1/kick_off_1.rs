@@ -66,7 +66,7 @@ async fn test_kick_off_1_tx_success() {
 
     let tx = kick_off_1_tx.finalize();
     check_tx_output_sum(INITIAL_AMOUNT + subsequent_tx_fee, &tx);
-    let result = config.client_0.esplora.broadcast(&tx).await;
+    let result = config.client_0.backend.broadcast(&tx).await;
     println!("Txid: {:?}", tx.compute_txid());
     println!("Kick Off 1 tx result: {:?}\n", result);
     assert!(result.is_ok());
@@ -3,6 +3,7 @@ use bitcoin::Amount;
 use bridge::{
     commitments::CommitmentMessageId,
     connectors::base::TaprootConnector,
+    error::BackendError,
     graphs::base::DUST_AMOUNT,
     superblock::{get_superblock_hash_message, get_superblock_message},
     transactions::{
@@ -68,7 +69,7 @@ async fn test_kick_off_2_tx_success() {
     let tx = kick_off_2_tx.finalize();
     check_tx_output_sum(reward_amount + DUST_AMOUNT, &tx);
     wait_for_timelock_expiry(config.network, Some("kick off 2 connector 3")).await;
-    let result: Result<(), esplora_client::Error> = config.client_0.esplora.broadcast(&tx).await;
+    let result: Result<(), BackendError> = config.client_0.backend.broadcast(&tx).await;
     println!("Txid: {:?}", tx.compute_txid());
     println!("Kick Off 2 tx result: {:?}\n", result);
     assert!(result.is_ok());
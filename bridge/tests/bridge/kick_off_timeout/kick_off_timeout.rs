@@ -49,16 +49,20 @@ async fn test_kick_off_timeout_tx_success() {
     let secret_nonces_0 = kick_off_timeout_tx.push_nonces(&config.verifier_0_context);
     let secret_nonces_1 = kick_off_timeout_tx.push_nonces(&config.verifier_1_context);
 
-    kick_off_timeout_tx.pre_sign(
-        &config.verifier_0_context,
-        &config.connector_1,
-        &secret_nonces_0,
-    );
-    kick_off_timeout_tx.pre_sign(
-        &config.verifier_1_context,
-        &config.connector_1,
-        &secret_nonces_1,
-    );
+    kick_off_timeout_tx
+        .pre_sign(
+            &config.verifier_0_context,
+            &config.connector_1,
+            &secret_nonces_0,
+        )
+        .unwrap();
+    kick_off_timeout_tx
+        .pre_sign(
+            &config.verifier_1_context,
+            &config.connector_1,
+            &secret_nonces_1,
+        )
+        .unwrap();
 
     let reward_address = generate_pay_to_pubkey_script_address(
         config.withdrawer_context.network,
@@ -69,7 +73,7 @@ async fn test_kick_off_timeout_tx_success() {
     let tx = kick_off_timeout_tx.finalize();
     check_tx_output_sum(reward_amount, &tx);
     wait_for_timelock_expiry(config.network, Some("kick off 1 connector 1")).await;
-    let result = config.client_0.esplora.broadcast(&tx).await;
+    let result = config.client_0.backend.broadcast(&tx).await;
     println!("Txid: {:?}", tx.compute_txid());
     println!("Kick off timeout result: {:?}\n", result);
     assert!(result.is_ok());
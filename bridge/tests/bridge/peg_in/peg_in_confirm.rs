@@ -1,7 +1,9 @@
-use bitcoin::{consensus::encode::serialize_hex, Amount};
+use bitcoin::{consensus::encode::serialize_hex, hashes::Hash, Amount, OutPoint, Txid};
+use musig2::secp::{MaybeScalar, Scalar};
 
 use bridge::{
     connectors::base::TaprootConnector,
+    error::{Error, SignatureError},
     transactions::{
         base::{BaseTransaction, Input, MIN_RELAY_FEE_PEG_IN_CONFIRM},
         peg_in_confirm::PegInConfirmTransaction,
@@ -43,22 +45,86 @@ async fn test_peg_in_confirm_tx_success() {
     let secret_nonces_0 = peg_in_confirm_tx.push_nonces(&config.verifier_0_context);
     let secret_nonces_1 = peg_in_confirm_tx.push_nonces(&config.verifier_1_context);
 
-    peg_in_confirm_tx.pre_sign(
+    peg_in_confirm_tx
+        .pre_sign(
+            &config.verifier_0_context,
+            &config.connector_z,
+            &secret_nonces_0,
+        )
+        .unwrap();
+    peg_in_confirm_tx
+        .pre_sign(
+            &config.verifier_1_context,
+            &config.connector_z,
+            &secret_nonces_1,
+        )
+        .unwrap();
+
+    let tx = peg_in_confirm_tx.finalize();
+    check_tx_output_sum(INITIAL_AMOUNT, &tx);
+    let result = config.client_0.backend.broadcast(&tx).await;
+    println!("Txid: {:?}", tx.compute_txid());
+    println!("Peg in confirm tx result: {:?}\n", result);
+    println!("Transaction hex: \n{}", serialize_hex(&tx));
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn test_peg_in_confirm_tx_invalid_partial_signature() {
+    let config = setup_test().await;
+    let outpoint = OutPoint {
+        txid: Txid::all_zeros(),
+        vout: 0,
+    };
+    let amount = Amount::from_sat(INITIAL_AMOUNT + MIN_RELAY_FEE_PEG_IN_CONFIRM);
+
+    let mut peg_in_confirm_tx = PegInConfirmTransaction::new(
+        &config.depositor_context,
+        &config.connector_0,
+        &config.connector_z,
+        Input { outpoint, amount },
+    );
+
+    let secret_nonces_0 = peg_in_confirm_tx.push_nonces(&config.verifier_0_context);
+    let result = peg_in_confirm_tx.pre_sign(
         &config.verifier_0_context,
         &config.connector_z,
         &secret_nonces_0,
     );
-    peg_in_confirm_tx.pre_sign(
+    assert!(matches!(
+        result,
+        Err(Error::Signature(SignatureError::MissingNonces { verifiers, .. }))
+            if verifiers == vec![config.verifier_1_context.verifier_public_key]
+    ));
+
+    let secret_nonces_1 = peg_in_confirm_tx.push_nonces(&config.verifier_1_context);
+    peg_in_confirm_tx
+        .pre_sign(
+            &config.verifier_0_context,
+            &config.connector_z,
+            &secret_nonces_0,
+        )
+        .unwrap();
+
+    // the aggregation identifies the verifier that pushed an invalid partial signature
+    peg_in_confirm_tx
+        .musig2_signatures_mut()
+        .get_mut(&0)
+        .unwrap()
+        .insert(
+            config.verifier_0_context.verifier_public_key,
+            MaybeScalar::Valid(Scalar::one()),
+        );
+    let result = peg_in_confirm_tx.pre_sign(
         &config.verifier_1_context,
         &config.connector_z,
         &secret_nonces_1,
     );
-
-    let tx = peg_in_confirm_tx.finalize();
-    check_tx_output_sum(INITIAL_AMOUNT, &tx);
-    let result = config.client_0.esplora.broadcast(&tx).await;
-    println!("Txid: {:?}", tx.compute_txid());
-    println!("Peg in confirm tx result: {:?}\n", result);
-    println!("Transaction hex: \n{}", serialize_hex(&tx));
-    assert!(result.is_ok());
+    assert!(matches!(
+        result,
+        Err(Error::Signature(SignatureError::InvalidPartialSignatures { verifiers, .. }))
+            if verifiers == vec![config.verifier_0_context.verifier_public_key]
+    ));
+    // only the depositor signature is in the witness
+    assert_eq!(peg_in_confirm_tx.finalize().input[0].witness.len(), 1);
 }
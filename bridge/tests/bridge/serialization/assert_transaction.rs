@@ -43,16 +43,20 @@ async fn test_assert_tx_serialization() {
     let secret_nonces_0 = assert_tx.push_nonces(&config.verifier_0_context);
     let secret_nonces_1 = assert_tx.push_nonces(&config.verifier_1_context);
 
-    assert_tx.pre_sign(
-        &config.verifier_0_context,
-        &config.connector_b,
-        &secret_nonces_0,
-    );
-    assert_tx.pre_sign(
-        &config.verifier_1_context,
-        &config.connector_b,
-        &secret_nonces_1,
-    );
+    assert_tx
+        .pre_sign(
+            &config.verifier_0_context,
+            &config.connector_b,
+            &secret_nonces_0,
+        )
+        .unwrap();
+    assert_tx
+        .pre_sign(
+            &config.verifier_1_context,
+            &config.connector_b,
+            &secret_nonces_1,
+        )
+        .unwrap();
 
     let json = serialize(&assert_tx);
     assert!(!json.is_empty());
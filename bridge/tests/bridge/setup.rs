@@ -3,7 +3,8 @@ use std::collections::HashMap;
 use bitcoin::{Network, PublicKey};
 
 use super::helper::{
-    get_esplora_url, get_intermediate_variables_cached, get_valid_proof, invalidate_proof,
+    get_esplora_url, get_intermediate_variables_with_batches_cached, get_valid_proof,
+    invalidate_proof,
 };
 use bridge::{
     client::client::BitVMClient,
@@ -377,12 +378,20 @@ fn get_test_commitment_secrets() -> HashMap<CommitmentMessageId, WinternitzSecre
         ),
     ]);
 
-    let all_variables = get_intermediate_variables_cached();
-    // split variable to different connectors
+    let (all_variables, batches) = get_intermediate_variables_with_batches_cached();
     for (v, size) in all_variables {
+        if batches.iter().flatten().all(|(name, _)| *name != v) {
+            commitment_map.insert(
+                CommitmentMessageId::Groth16IntermediateValues((v, size)),
+                generate_test_winternitz_secret(5, size),
+            );
+        }
+    }
+    for batch in batches {
+        let total_size = batch.iter().map(|(_, size)| size).sum();
         commitment_map.insert(
-            CommitmentMessageId::Groth16IntermediateValues((v, size)),
-            generate_test_winternitz_secret(5, size),
+            CommitmentMessageId::Groth16IntermediateValuesBatch(batch),
+            generate_test_winternitz_secret(5, total_size),
         );
     }
     commitment_map
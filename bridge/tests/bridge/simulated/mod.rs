@@ -0,0 +1,3 @@
+pub mod peg_in;
+pub mod peg_out;
+pub mod utils;
@@ -0,0 +1,386 @@
+use bitcoin::{Amount, OutPoint};
+
+use crate::bridge::{
+    helper::generate_frost_key_shares,
+    setup::{SetupConfig, INITIAL_AMOUNT},
+    simulated::utils::setup_simulated_test,
+};
+use bridge::{
+    client::backend::simulated::SimulatedChain,
+    connectors::{connector_0::Connector0, connector_z::ConnectorZ},
+    contexts::{
+        base::generate_keys_from_secret, depositor::DepositorContext, verifier::VerifierContext,
+    },
+    error::{BackendError, Error, GraphError, SignatureError},
+    graphs::{
+        base::{
+            BaseGraph, DEPOSITOR_EVM_ADDRESS, DEPOSITOR_SECRET, VERIFIER_0_SECRET,
+            VERIFIER_1_SECRET, WITHDRAWER_SECRET,
+        },
+        peg_in::PegInGraph,
+    },
+    scripts::generate_pay_to_pubkey_script_address,
+    transactions::{
+        base::{
+            BaseTransaction, Input, MIN_RELAY_FEE_PEG_IN_CONFIRM, MIN_RELAY_FEE_PEG_IN_DEPOSIT,
+            MIN_RELAY_FEE_PEG_IN_REFUND,
+        },
+        peg_in_confirm::PegInConfirmTransaction,
+        peg_in_deposit::PegInDepositTransaction,
+        peg_in_refund::PegInRefundTransaction,
+        pre_signed_musig2::PreSignedMusig2Transaction,
+    },
+};
+
+fn fund_depositor(config: &SetupConfig, chain: &SimulatedChain, sats: u64) -> Input {
+    let depositor_address = generate_pay_to_pubkey_script_address(
+        config.depositor_context.network,
+        &config.depositor_context.depositor_public_key,
+    );
+    let amount = Amount::from_sat(sats);
+    let outpoint = chain.fund(depositor_address.script_pubkey(), amount);
+    chain.mine_blocks(1);
+
+    Input { outpoint, amount }
+}
+
+#[tokio::test]
+async fn test_simulated_peg_in_success() {
+    let (config, chain) = setup_simulated_test().await;
+    let deposit_input = fund_depositor(
+        &config,
+        &chain,
+        INITIAL_AMOUNT + MIN_RELAY_FEE_PEG_IN_DEPOSIT + MIN_RELAY_FEE_PEG_IN_CONFIRM,
+    );
+
+    let peg_in_deposit = PegInDepositTransaction::new(
+        &config.depositor_context,
+        &config.connector_z,
+        deposit_input,
+    );
+    let peg_in_deposit_tx = peg_in_deposit.finalize();
+    let deposit_txid = peg_in_deposit_tx.compute_txid();
+    let deposit_result = config.client_0.backend.broadcast(&peg_in_deposit_tx).await;
+    assert!(deposit_result.is_ok());
+    chain.mine_blocks(1);
+
+    let confirm_input = Input {
+        outpoint: OutPoint {
+            txid: deposit_txid,
+            vout: 0,
+        },
+        amount: peg_in_deposit_tx.output[0].value,
+    };
+    let mut peg_in_confirm = PegInConfirmTransaction::new(
+        &config.depositor_context,
+        &config.connector_0,
+        &config.connector_z,
+        confirm_input,
+    );
+    let secret_nonces_0 = peg_in_confirm.push_nonces(&config.verifier_0_context);
+    let secret_nonces_1 = peg_in_confirm.push_nonces(&config.verifier_1_context);
+    peg_in_confirm
+        .pre_sign(
+            &config.verifier_0_context,
+            &config.connector_z,
+            &secret_nonces_0,
+        )
+        .unwrap();
+    peg_in_confirm
+        .pre_sign(
+            &config.verifier_1_context,
+            &config.connector_z,
+            &secret_nonces_1,
+        )
+        .unwrap();
+    let peg_in_confirm_tx = peg_in_confirm.finalize();
+    let confirm_txid = peg_in_confirm_tx.compute_txid();
+
+    // the confirm tx spends the script path of connector z, executed by the simulated chain
+    let confirm_result = config.client_0.backend.broadcast(&peg_in_confirm_tx).await;
+    assert!(confirm_result.is_ok());
+    chain.mine_blocks(1);
+
+    let confirm_status = config
+        .client_0
+        .backend
+        .get_tx_status(&confirm_txid)
+        .await
+        .unwrap();
+    assert!(confirm_status.confirmed);
+    assert_eq!(
+        peg_in_confirm_tx.output[0].value,
+        Amount::from_sat(INITIAL_AMOUNT),
+    );
+}
+
+#[tokio::test]
+async fn test_simulated_peg_in_refund_time_lock() {
+    let (config, chain) = setup_simulated_test().await;
+    let deposit_input = fund_depositor(
+        &config,
+        &chain,
+        INITIAL_AMOUNT + MIN_RELAY_FEE_PEG_IN_DEPOSIT + MIN_RELAY_FEE_PEG_IN_REFUND,
+    );
+
+    let peg_in_deposit = PegInDepositTransaction::new(
+        &config.depositor_context,
+        &config.connector_z,
+        deposit_input,
+    );
+    let peg_in_deposit_tx = peg_in_deposit.finalize();
+    let deposit_txid = peg_in_deposit_tx.compute_txid();
+    let deposit_result = config.client_0.backend.broadcast(&peg_in_deposit_tx).await;
+    assert!(deposit_result.is_ok());
+    chain.mine_blocks(1);
+
+    let refund_input = Input {
+        outpoint: OutPoint {
+            txid: deposit_txid,
+            vout: 0,
+        },
+        amount: peg_in_deposit_tx.output[0].value,
+    };
+    let peg_in_refund =
+        PegInRefundTransaction::new(&config.depositor_context, &config.connector_z, refund_input);
+    let peg_in_refund_tx = peg_in_refund.finalize();
+
+    // the relative timelock of connector z starts with the deposit block
+    let refund_result = config.client_0.backend.broadcast(&peg_in_refund_tx).await;
+    assert!(
+        matches!(refund_result, Err(BackendError::Rejected(reason)) if reason == "non-BIP68-final")
+    );
+
+    chain.mine_blocks(config.connector_z.num_blocks_timelock_0 - 1);
+    let refund_result = config.client_0.backend.broadcast(&peg_in_refund_tx).await;
+    assert!(refund_result.is_ok());
+    chain.mine_blocks(1);
+
+    let refund_status = config
+        .client_0
+        .backend
+        .get_tx_status(&peg_in_refund_tx.compute_txid())
+        .await
+        .unwrap();
+    assert!(refund_status.confirmed);
+}
+
+#[tokio::test]
+async fn test_simulated_peg_in_frost_threshold() {
+    let (config, chain) = setup_simulated_test().await;
+    let network = config.depositor_context.network;
+
+    // 2-of-3 verifier set, the verifier with the lowest key being offline
+    let verifier_secrets = [VERIFIER_0_SECRET, VERIFIER_1_SECRET, WITHDRAWER_SECRET];
+    let verifier_public_keys =
+        verifier_secrets.map(|secret| generate_keys_from_secret(network, secret).1);
+    let (frost_key_package, signing_shares) = generate_frost_key_shares(&verifier_public_keys, 2);
+    let depositor_context =
+        DepositorContext::new_with_frost_key_package(network, DEPOSITOR_SECRET, &frost_key_package);
+    let mut verifier_contexts = verifier_secrets
+        .iter()
+        .zip(verifier_public_keys.iter())
+        .map(|(secret, public_key)| {
+            VerifierContext::new_with_frost_key_package(
+                network,
+                secret,
+                &frost_key_package,
+                &signing_shares[public_key],
+            )
+        })
+        .collect::<Vec<_>>();
+    verifier_contexts.sort_by_key(|context| context.verifier_public_key);
+    let offline_verifier_context = verifier_contexts.remove(0);
+    let online_verifier_contexts = verifier_contexts;
+    let connector_0 = Connector0::new(network, &depositor_context.n_of_n_taproot_public_key);
+    let connector_z = ConnectorZ::new(
+        network,
+        DEPOSITOR_EVM_ADDRESS,
+        &depositor_context.depositor_taproot_public_key,
+        &depositor_context.n_of_n_taproot_public_key,
+    );
+
+    let deposit_input = fund_depositor(
+        &config,
+        &chain,
+        INITIAL_AMOUNT + MIN_RELAY_FEE_PEG_IN_DEPOSIT + MIN_RELAY_FEE_PEG_IN_CONFIRM,
+    );
+    let peg_in_deposit =
+        PegInDepositTransaction::new(&depositor_context, &connector_z, deposit_input);
+    let peg_in_deposit_tx = peg_in_deposit.finalize();
+    let deposit_result = config.client_0.backend.broadcast(&peg_in_deposit_tx).await;
+    assert!(deposit_result.is_ok());
+    chain.mine_blocks(1);
+
+    let confirm_input = Input {
+        outpoint: OutPoint {
+            txid: peg_in_deposit_tx.compute_txid(),
+            vout: 0,
+        },
+        amount: peg_in_deposit_tx.output[0].value,
+    };
+    let mut peg_in_confirm = PegInConfirmTransaction::new(
+        &depositor_context,
+        &connector_0,
+        &connector_z,
+        confirm_input,
+    );
+    let mut late_peg_in_confirm = peg_in_confirm.clone();
+    let secret_nonces = online_verifier_contexts
+        .iter()
+        .map(|context| peg_in_confirm.push_nonces(context))
+        .collect::<Vec<_>>();
+    assert!(!peg_in_confirm.needs_nonces_from(
+        offline_verifier_context.verifier_public_key,
+        &verifier_public_keys,
+        Some(&frost_key_package),
+    ));
+
+    // the signing set is recorded with the nonces reaching the threshold, the offline verifier
+    // coming back online not joining it
+    let mut recorded_peg_in_confirm = peg_in_confirm.clone();
+    assert!(recorded_peg_in_confirm
+        .push_nonces(&offline_verifier_context)
+        .is_empty());
+
+    // a nonce pushed concurrently by the offline verifier and merged afterwards would form another
+    // signing set, the online verifiers still sign for the recorded one
+    late_peg_in_confirm.push_nonces(&offline_verifier_context);
+    let mut changed_peg_in_confirm = peg_in_confirm.clone();
+    changed_peg_in_confirm.merge(&late_peg_in_confirm);
+    changed_peg_in_confirm
+        .pre_sign(
+            &online_verifier_contexts[0],
+            &connector_z,
+            &secret_nonces[0],
+        )
+        .unwrap();
+    assert_eq!(
+        changed_peg_in_confirm.signers(0, &verifier_public_keys, Some(&frost_key_package)),
+        peg_in_confirm.signers(0, &verifier_public_keys, Some(&frost_key_package))
+    );
+    for (context, secret_nonces) in online_verifier_contexts.iter().zip(secret_nonces.iter()) {
+        peg_in_confirm
+            .pre_sign(context, &connector_z, secret_nonces)
+            .unwrap();
+    }
+    assert!(peg_in_confirm.has_signing_signatures(&verifier_public_keys, Some(&frost_key_package)));
+
+    // the group signature of the threshold signers spends the n-of-n leaf of connector z
+    let peg_in_confirm_tx = peg_in_confirm.finalize();
+    let confirm_result = config.client_0.backend.broadcast(&peg_in_confirm_tx).await;
+    assert!(confirm_result.is_ok());
+    chain.mine_blocks(1);
+
+    let confirm_status = config
+        .client_0
+        .backend
+        .get_tx_status(&peg_in_confirm_tx.compute_txid())
+        .await
+        .unwrap();
+    assert!(confirm_status.confirmed);
+}
+
+#[tokio::test]
+async fn test_simulated_peg_in_frost_invalid_signing_share() {
+    let (config, chain) = setup_simulated_test().await;
+    let network = config.depositor_context.network;
+
+    // 2-of-3 verifier set, verifier 0 signing with the share of another verifier
+    let verifier_public_keys = [VERIFIER_0_SECRET, VERIFIER_1_SECRET, WITHDRAWER_SECRET]
+        .map(|secret| generate_keys_from_secret(network, secret).1);
+    let (frost_key_package, signing_shares) = generate_frost_key_shares(&verifier_public_keys, 2);
+    let depositor_context =
+        DepositorContext::new_with_frost_key_package(network, DEPOSITOR_SECRET, &frost_key_package);
+    let verifier_0_context = VerifierContext::new_with_frost_key_package(
+        network,
+        VERIFIER_0_SECRET,
+        &frost_key_package,
+        &signing_shares[&verifier_public_keys[0]],
+    );
+    let faulty_verifier_0_context = VerifierContext::new_with_frost_key_package(
+        network,
+        VERIFIER_0_SECRET,
+        &frost_key_package,
+        &signing_shares[&verifier_public_keys[2]],
+    );
+    let verifier_1_context = VerifierContext::new_with_frost_key_package(
+        network,
+        VERIFIER_1_SECRET,
+        &frost_key_package,
+        &signing_shares[&verifier_public_keys[1]],
+    );
+
+    let deposit_input = fund_depositor(
+        &config,
+        &chain,
+        INITIAL_AMOUNT + MIN_RELAY_FEE_PEG_IN_DEPOSIT + MIN_RELAY_FEE_PEG_IN_CONFIRM,
+    );
+    let mut peg_in_graph =
+        PegInGraph::new(&depositor_context, deposit_input, DEPOSITOR_EVM_ADDRESS);
+    let peg_in_deposit_tx = peg_in_graph.peg_in_deposit_transaction.finalize();
+    let deposit_result = config.client_0.backend.broadcast(&peg_in_deposit_tx).await;
+    assert!(deposit_result.is_ok());
+    chain.mine_blocks(1);
+
+    // the aggregation identifies the partial signature of the invalid share
+    let secret_nonces_0 = peg_in_graph.push_verifier_nonces(&faulty_verifier_0_context);
+    let secret_nonces_1 = peg_in_graph.push_verifier_nonces(&verifier_1_context);
+    peg_in_graph
+        .verifier_sign(&faulty_verifier_0_context, &secret_nonces_0)
+        .unwrap();
+    let sign_result = peg_in_graph.verifier_sign(&verifier_1_context, &secret_nonces_1);
+    let Err(Error::Graph(GraphError::PreSigningFailed(errors))) = sign_result else {
+        panic!("Pre-signing with an invalid FROST signing share succeeded");
+    };
+    assert!(matches!(
+        errors.as_slice(),
+        [(_, Error::Signature(SignatureError::InvalidPartialSignatures { verifiers, .. }))]
+            if *verifiers == vec![verifier_0_context.verifier_public_key]
+    ));
+
+    // evicting the verifier blocks the graph, the nonces of both signers being spent, and keeps
+    // its nonce out of the copies merged in
+    let stale_peg_in_graph = peg_in_graph.clone();
+    peg_in_graph.evict_verifier(&verifier_0_context.verifier_public_key);
+    peg_in_graph.merge(&stale_peg_in_graph).unwrap();
+    assert!(peg_in_graph.is_blocked());
+    let peg_in_confirm = peg_in_graph.peg_in_confirm_transaction_ref();
+    assert!(!peg_in_confirm.has_nonces_for(verifier_0_context.verifier_public_key));
+    assert!(!peg_in_confirm.has_nonces_for(verifier_1_context.verifier_public_key));
+    assert!(!peg_in_confirm.has_signatures_for(verifier_1_context.verifier_public_key));
+
+    // the signers sign again with fresh nonces, the evicted ones not being signed with
+    let stale_secret_nonces_1 = secret_nonces_1;
+    let secret_nonces_0 = peg_in_graph.push_verifier_nonces(&verifier_0_context);
+    let secret_nonces_1 = peg_in_graph.push_verifier_nonces(&verifier_1_context);
+    let sign_result = peg_in_graph.verifier_sign(&verifier_1_context, &stale_secret_nonces_1);
+    assert!(matches!(
+        sign_result,
+        Err(Error::Graph(GraphError::PreSigningFailed(errors)))
+            if matches!(
+                errors.as_slice(),
+                [(_, Error::Signature(SignatureError::MissingNonces { .. }))]
+            )
+    ));
+    peg_in_graph
+        .verifier_sign(&verifier_0_context, &secret_nonces_0)
+        .unwrap();
+    peg_in_graph
+        .verifier_sign(&verifier_1_context, &secret_nonces_1)
+        .unwrap();
+    assert!(!peg_in_graph.is_blocked());
+
+    let peg_in_confirm_tx = peg_in_graph.peg_in_confirm_transaction_ref().finalize();
+    let confirm_result = config.client_0.backend.broadcast(&peg_in_confirm_tx).await;
+    assert!(confirm_result.is_ok());
+    chain.mine_blocks(1);
+
+    let confirm_status = config
+        .client_0
+        .backend
+        .get_tx_status(&peg_in_confirm_tx.compute_txid())
+        .await
+        .unwrap();
+    assert!(confirm_status.confirmed);
+}
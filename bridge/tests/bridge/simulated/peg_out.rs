@@ -0,0 +1,296 @@
+use bitcoin::{Amount, OutPoint, Transaction};
+
+use crate::bridge::{
+    helper::{check_tx_output_sum, get_reward_amount, get_superblock_header},
+    setup::{SetupConfig, INITIAL_AMOUNT, ONE_HUNDRED},
+    simulated::utils::setup_simulated_test,
+};
+use bitvm::signatures::signing_winternitz::WinternitzSigningInputs;
+use bridge::{
+    client::backend::simulated::SimulatedChain,
+    commitments::CommitmentMessageId,
+    connectors::base::TaprootConnector,
+    error::BackendError,
+    graphs::base::{DUST_AMOUNT, PEG_OUT_FEE},
+    scripts::{generate_pay_to_pubkey_script, generate_pay_to_pubkey_script_address},
+    superblock::{get_superblock_hash_message, get_superblock_message},
+    transactions::{
+        base::{
+            BaseTransaction, Input, InputWithScript, MIN_RELAY_FEE_CHALLENGE,
+            MIN_RELAY_FEE_PEG_IN_CONFIRM,
+        },
+        challenge::ChallengeTransaction,
+        kick_off_1::KickOff1Transaction,
+        kick_off_2::KickOff2Transaction,
+        peg_in_confirm::PegInConfirmTransaction,
+        pre_signed_musig2::PreSignedMusig2Transaction,
+        take_1::Take1Transaction,
+    },
+};
+
+fn output_input(tx: &Transaction, vout: u32) -> Input {
+    Input {
+        outpoint: OutPoint {
+            txid: tx.compute_txid(),
+            vout,
+        },
+        amount: tx.output[vout as usize].value,
+    }
+}
+
+async fn confirm_peg_in(config: &SetupConfig, chain: &SimulatedChain) -> Transaction {
+    let amount = Amount::from_sat(ONE_HUNDRED + MIN_RELAY_FEE_PEG_IN_CONFIRM);
+    let outpoint = chain.fund(
+        config
+            .connector_z
+            .generate_taproot_address()
+            .script_pubkey(),
+        amount,
+    );
+    chain.mine_blocks(1);
+
+    let mut peg_in_confirm = PegInConfirmTransaction::new(
+        &config.depositor_context,
+        &config.connector_0,
+        &config.connector_z,
+        Input { outpoint, amount },
+    );
+    let secret_nonces_0 = peg_in_confirm.push_nonces(&config.verifier_0_context);
+    let secret_nonces_1 = peg_in_confirm.push_nonces(&config.verifier_1_context);
+    peg_in_confirm
+        .pre_sign(
+            &config.verifier_0_context,
+            &config.connector_z,
+            &secret_nonces_0,
+        )
+        .unwrap();
+    peg_in_confirm
+        .pre_sign(
+            &config.verifier_1_context,
+            &config.connector_z,
+            &secret_nonces_1,
+        )
+        .unwrap();
+    let peg_in_confirm_tx = peg_in_confirm.finalize();
+
+    let confirm_result = config.client_0.backend.broadcast(&peg_in_confirm_tx).await;
+    assert!(confirm_result.is_ok());
+    chain.mine_blocks(1);
+
+    peg_in_confirm_tx
+}
+
+async fn kick_off(config: &SetupConfig, chain: &SimulatedChain) -> Transaction {
+    let amount = Amount::from_sat(get_reward_amount(ONE_HUNDRED) + PEG_OUT_FEE);
+    let outpoint = chain.fund(
+        config
+            .connector_6
+            .generate_taproot_address()
+            .script_pubkey(),
+        amount,
+    );
+    chain.mine_blocks(1);
+
+    let mut kick_off_1 = KickOff1Transaction::new(
+        &config.operator_context,
+        &config.connector_1,
+        &config.connector_2,
+        &config.connector_6,
+        Input { outpoint, amount },
+    );
+    let ethereum_txid = "8b274fbb76c72f66c467c976c61d5ac212620e036818b5986a33f7b557cb2de8";
+    let bitcoin_txid = "8b4cce4a1a9522392c095df6416533d89e1e6ac7bdf8ab3c1685426b321ed182";
+    kick_off_1.sign(
+        &config.operator_context,
+        &config.connector_6,
+        &WinternitzSigningInputs {
+            message: bitcoin_txid.as_bytes(),
+            signing_key: &config.commitment_secrets[&CommitmentMessageId::PegOutTxIdSourceNetwork],
+        },
+        &WinternitzSigningInputs {
+            message: ethereum_txid.as_bytes(),
+            signing_key: &config.commitment_secrets
+                [&CommitmentMessageId::PegOutTxIdDestinationNetwork],
+        },
+    );
+    let kick_off_1_tx = kick_off_1.finalize();
+
+    let kick_off_1_result = config.client_0.backend.broadcast(&kick_off_1_tx).await;
+    assert!(kick_off_1_result.is_ok());
+    chain.mine_blocks(1);
+
+    kick_off_1_tx
+}
+
+fn kick_off_2(config: &SetupConfig, kick_off_1_tx: &Transaction) -> Transaction {
+    let vout = 1; // connector 1
+    let mut kick_off_2 = KickOff2Transaction::new(
+        &config.operator_context,
+        &config.connector_1,
+        &config.connector_b,
+        output_input(kick_off_1_tx, vout),
+    );
+    let superblock_header = get_superblock_header();
+    kick_off_2.sign(
+        &config.operator_context,
+        &config.connector_1,
+        &WinternitzSigningInputs {
+            message: &get_superblock_message(&superblock_header),
+            signing_key: &config.commitment_secrets[&CommitmentMessageId::Superblock],
+        },
+        &WinternitzSigningInputs {
+            message: &get_superblock_hash_message(&superblock_header),
+            signing_key: &config.commitment_secrets[&CommitmentMessageId::SuperblockHash],
+        },
+    );
+
+    kick_off_2.finalize()
+}
+
+fn take_1(
+    config: &SetupConfig,
+    peg_in_confirm_tx: &Transaction,
+    kick_off_1_tx: &Transaction,
+    kick_off_2_tx: &Transaction,
+) -> Transaction {
+    let mut take_1 = Take1Transaction::new(
+        &config.operator_context,
+        &config.connector_0,
+        &config.connector_3,
+        &config.connector_a,
+        &config.connector_b,
+        output_input(peg_in_confirm_tx, 0), // connector 0
+        output_input(kick_off_1_tx, 0),     // connector a
+        output_input(kick_off_2_tx, 0),     // connector 3
+        output_input(kick_off_2_tx, 1),     // connector b
+    );
+    let secret_nonces_0 = take_1.push_nonces(&config.verifier_0_context);
+    let secret_nonces_1 = take_1.push_nonces(&config.verifier_1_context);
+    take_1
+        .pre_sign(
+            &config.verifier_0_context,
+            &config.connector_0,
+            &config.connector_b,
+            &secret_nonces_0,
+        )
+        .unwrap();
+    take_1
+        .pre_sign(
+            &config.verifier_1_context,
+            &config.connector_0,
+            &config.connector_b,
+            &secret_nonces_1,
+        )
+        .unwrap();
+
+    take_1.finalize()
+}
+
+#[tokio::test]
+async fn test_simulated_peg_out_take_1_time_locks() {
+    let (config, chain) = setup_simulated_test().await;
+    let peg_in_confirm_tx = confirm_peg_in(&config, &chain).await;
+    let kick_off_1_tx = kick_off(&config, &chain).await;
+
+    // the relative timelock of connector 1 starts with the kick-off 1 block
+    let kick_off_2_tx = kick_off_2(&config, &kick_off_1_tx);
+    let kick_off_2_result = config.client_0.backend.broadcast(&kick_off_2_tx).await;
+    assert!(
+        matches!(kick_off_2_result, Err(BackendError::Rejected(reason)) if reason == "non-BIP68-final")
+    );
+    chain.mine_blocks(config.connector_1.num_blocks_timelock_leaf_0 - 1);
+    let kick_off_2_result = config.client_0.backend.broadcast(&kick_off_2_tx).await;
+    assert!(kick_off_2_result.is_ok());
+    chain.mine_blocks(1);
+
+    // the operator takes the peg-in once the relative timelock of connector 3 is met
+    let take_1_tx = take_1(&config, &peg_in_confirm_tx, &kick_off_1_tx, &kick_off_2_tx);
+    let take_1_result = config.client_0.backend.broadcast(&take_1_tx).await;
+    assert!(
+        matches!(take_1_result, Err(BackendError::Rejected(reason)) if reason == "non-BIP68-final")
+    );
+    chain.mine_blocks(config.connector_3.num_blocks_timelock - 1);
+    let take_1_result = config.client_0.backend.broadcast(&take_1_tx).await;
+    assert!(take_1_result.is_ok());
+    chain.mine_blocks(1);
+
+    // addtional dust is from kick off 1 connector a
+    check_tx_output_sum(
+        ONE_HUNDRED + get_reward_amount(ONE_HUNDRED) + DUST_AMOUNT,
+        &take_1_tx,
+    );
+    let operator_address = generate_pay_to_pubkey_script_address(
+        config.operator_context.network,
+        &config.operator_context.operator_public_key,
+    );
+    let operator_utxos = config
+        .client_0
+        .backend
+        .get_address_utxo(operator_address)
+        .await
+        .unwrap();
+    assert!(operator_utxos
+        .iter()
+        .any(|utxo| utxo.txid == take_1_tx.compute_txid() && utxo.status.confirmed));
+}
+
+#[tokio::test]
+async fn test_simulated_peg_out_challenge() {
+    let (config, chain) = setup_simulated_test().await;
+    let peg_in_confirm_tx = confirm_peg_in(&config, &chain).await;
+    let kick_off_1_tx = kick_off(&config, &chain).await;
+
+    // a depositor crowdfunds the challenge of the kick-off
+    let crowdfunding_amount = Amount::from_sat(INITIAL_AMOUNT + MIN_RELAY_FEE_CHALLENGE);
+    let crowdfunding_address = generate_pay_to_pubkey_script_address(
+        config.depositor_context.network,
+        &config.depositor_context.depositor_public_key,
+    );
+    let crowdfunding_outpoint =
+        chain.fund(crowdfunding_address.script_pubkey(), crowdfunding_amount);
+    chain.mine_blocks(1);
+
+    let vout = 0; // connector a
+    let mut challenge = ChallengeTransaction::new(
+        &config.operator_context,
+        &config.connector_a,
+        output_input(&kick_off_1_tx, vout),
+        crowdfunding_amount,
+    );
+    challenge.add_inputs_and_output(
+        &vec![InputWithScript {
+            outpoint: crowdfunding_outpoint,
+            amount: crowdfunding_amount,
+            script: &generate_pay_to_pubkey_script(&config.depositor_context.depositor_public_key),
+        }],
+        &config.depositor_context.depositor_keypair,
+        generate_pay_to_pubkey_script(&config.depositor_context.depositor_public_key),
+    );
+    let challenge_tx = challenge.finalize();
+    check_tx_output_sum(INITIAL_AMOUNT + DUST_AMOUNT, &challenge_tx);
+
+    let challenge_result = config.client_0.backend.broadcast(&challenge_tx).await;
+    assert!(challenge_result.is_ok());
+    chain.mine_blocks(1);
+
+    let connector_a_spender = config
+        .client_0
+        .backend
+        .get_output_spender(&output_input(&kick_off_1_tx, vout).outpoint)
+        .await
+        .unwrap();
+    assert_eq!(connector_a_spender, Some(challenge_tx.compute_txid()));
+
+    // the challenged operator can no longer take the peg-in through connector a
+    chain.mine_blocks(config.connector_1.num_blocks_timelock_leaf_0);
+    let kick_off_2_tx = kick_off_2(&config, &kick_off_1_tx);
+    let kick_off_2_result = config.client_0.backend.broadcast(&kick_off_2_tx).await;
+    assert!(kick_off_2_result.is_ok());
+    chain.mine_blocks(config.connector_3.num_blocks_timelock);
+
+    let take_1_tx = take_1(&config, &peg_in_confirm_tx, &kick_off_1_tx, &kick_off_2_tx);
+    let take_1_result = config.client_0.backend.broadcast(&take_1_tx).await;
+    assert!(
+        matches!(take_1_result, Err(BackendError::Rejected(reason)) if reason.starts_with("txn-mempool-conflict"))
+    );
+}
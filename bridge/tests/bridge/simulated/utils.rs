@@ -0,0 +1,14 @@
+use std::sync::Arc;
+
+use bridge::client::backend::simulated::SimulatedChain;
+
+use crate::bridge::setup::{setup_test, SetupConfig};
+
+pub async fn setup_simulated_test() -> (SetupConfig, Arc<SimulatedChain>) {
+    let mut config = setup_test().await;
+    let chain = Arc::new(SimulatedChain::new());
+    config.client_0.set_backend(chain.clone());
+    config.client_1.set_backend(chain.clone());
+
+    (config, chain)
+}
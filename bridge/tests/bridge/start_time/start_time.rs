@@ -53,7 +53,7 @@ async fn test_start_time_tx_success() {
     check_tx_output_sum(DUST_AMOUNT, &tx);
     // TODO: revisit here after superblock time lock is implemented
     // wait_timelock_expiry(config.network, Some("start time absolute lock time")).await;
-    let result = config.client_0.esplora.broadcast(&tx).await;
+    let result = config.client_0.backend.broadcast(&tx).await;
     println!("Txid: {:?}", tx.compute_txid());
     println!("Start time tx result: {:?}\n", result);
     assert!(result.is_ok());
@@ -66,18 +66,22 @@ async fn test_start_time_timeout_tx_success() {
     let secret_nonces_0 = start_time_timeout_tx.push_nonces(&config.verifier_0_context);
     let secret_nonces_1 = start_time_timeout_tx.push_nonces(&config.verifier_1_context);
 
-    start_time_timeout_tx.pre_sign(
-        &config.verifier_0_context,
-        &config.connector_1,
-        &config.connector_2,
-        &secret_nonces_0,
-    );
-    start_time_timeout_tx.pre_sign(
-        &config.verifier_1_context,
-        &config.connector_1,
-        &config.connector_2,
-        &secret_nonces_1,
-    );
+    start_time_timeout_tx
+        .pre_sign(
+            &config.verifier_0_context,
+            &config.connector_1,
+            &config.connector_2,
+            &secret_nonces_0,
+        )
+        .unwrap();
+    start_time_timeout_tx
+        .pre_sign(
+            &config.verifier_1_context,
+            &config.connector_1,
+            &config.connector_2,
+            &secret_nonces_1,
+        )
+        .unwrap();
     let reward_address = generate_pay_to_pubkey_script_address(
         config.withdrawer_context.network,
         &config.withdrawer_context.withdrawer_public_key,
@@ -87,7 +91,7 @@ async fn test_start_time_timeout_tx_success() {
     let tx = start_time_timeout_tx.finalize();
     check_tx_output_sum(reward_amount + DUST_AMOUNT, &tx);
     wait_for_timelock_expiry(config.network, Some("kick off 1 connector 1")).await;
-    let result = config.client_0.esplora.broadcast(&tx).await;
+    let result = config.client_0.backend.broadcast(&tx).await;
     println!("Txid: {:?}", tx.compute_txid());
     println!("Start time timeout tx result: {:?}\n", result);
     assert!(result.is_ok());
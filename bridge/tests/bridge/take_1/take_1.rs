@@ -79,23 +79,27 @@ async fn test_take_1_tx_success() {
     let secret_nonces_0 = take_1_tx.push_nonces(&config.verifier_0_context);
     let secret_nonces_1 = take_1_tx.push_nonces(&config.verifier_1_context);
 
-    take_1_tx.pre_sign(
-        &config.verifier_0_context,
-        &config.connector_0,
-        &config.connector_b,
-        &secret_nonces_0,
-    );
-    take_1_tx.pre_sign(
-        &config.verifier_1_context,
-        &config.connector_0,
-        &config.connector_b,
-        &secret_nonces_1,
-    );
+    take_1_tx
+        .pre_sign(
+            &config.verifier_0_context,
+            &config.connector_0,
+            &config.connector_b,
+            &secret_nonces_0,
+        )
+        .unwrap();
+    take_1_tx
+        .pre_sign(
+            &config.verifier_1_context,
+            &config.connector_0,
+            &config.connector_b,
+            &secret_nonces_1,
+        )
+        .unwrap();
 
     let tx = take_1_tx.finalize();
     check_tx_output_sum(ONE_HUNDRED + reward_amount + DUST_AMOUNT * 2, &tx);
     wait_for_timelock_expiry(config.network, Some("kick off 2 connector 3")).await;
-    let result = config.client_0.esplora.broadcast(&tx).await;
+    let result = config.client_0.backend.broadcast(&tx).await;
     println!("Txid: {:?}", tx.compute_txid());
     println!("Take 1 tx result: {:?}\n", result);
     assert!(result.is_ok());
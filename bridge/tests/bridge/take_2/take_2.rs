@@ -82,25 +82,29 @@ async fn test_take_2_tx_success() {
     let secret_nonces_0 = take_2_tx.push_nonces(&config.verifier_0_context);
     let secret_nonces_1 = take_2_tx.push_nonces(&config.verifier_1_context);
 
-    take_2_tx.pre_sign(
-        &config.verifier_0_context,
-        &config.connector_0,
-        &config.connector_5,
-        &secret_nonces_0,
-    );
-    take_2_tx.pre_sign(
-        &config.verifier_1_context,
-        &config.connector_0,
-        &config.connector_5,
-        &secret_nonces_1,
-    );
+    take_2_tx
+        .pre_sign(
+            &config.verifier_0_context,
+            &config.connector_0,
+            &config.connector_5,
+            &secret_nonces_0,
+        )
+        .unwrap();
+    take_2_tx
+        .pre_sign(
+            &config.verifier_1_context,
+            &config.connector_0,
+            &config.connector_5,
+            &secret_nonces_1,
+        )
+        .unwrap();
 
     take_2_tx.sign(&config.operator_context, &config.connector_c);
 
     let tx = take_2_tx.finalize();
     check_tx_output_sum(ONE_HUNDRED + reward_amount + DUST_AMOUNT * 2, &tx);
     wait_for_timelock_expiry(config.network, Some("assert connector 4")).await;
-    let result = config.client_0.esplora.broadcast(&tx).await;
+    let result = config.client_0.backend.broadcast(&tx).await;
     println!("Txid: {:?}", tx.compute_txid());
     println!("Take 2 tx result: {:?}\n", result);
     assert!(result.is_ok());
@@ -7,10 +7,12 @@ if [ -n "$data_path" ]; then
   if [ -n "$pid" ]; then
     echo "esplora client already running"
   else
-    docker run -d -p 50001:50001 -p 8094:80 \
+    docker run -d -p 50001:50001 -p 8094:80 -p 18443:18443 \
         --volume "$data_path/esplora-bitcoin-regtest-data:/data" \
         --rm -i -t blockstream/esplora \
         bash -c "sed -i '182i echo \"acceptnonstdtxn=1\" >> /data/.bitcoin.conf' /srv/explorer/run.sh && \
+                sed -i '182i echo \"regtest.rpcbind=0.0.0.0\" >> /data/.bitcoin.conf' /srv/explorer/run.sh && \
+                sed -i '182i echo \"rpcallowip=0.0.0.0/0\" >> /data/.bitcoin.conf' /srv/explorer/run.sh && \
                 sed -i '/http {/a \    client_max_body_size 100M;' /etc/nginx/nginx.conf && \
                 /srv/explorer/run.sh bitcoin-regtest explorer"
 